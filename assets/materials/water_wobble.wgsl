@@ -0,0 +1,11 @@
+// Layer material: gentle horizontal wobble, stronger further down the layer,
+// with a slow cool tint. See sme_render::material for the available inputs.
+
+fn material(in: MaterialInput) -> vec4<f32> {
+    let phase = engine.time * 2.0 + in.world_position.y * 0.08;
+    let texel = 1.0 / vec2<f32>(textureDimensions(t_diffuse));
+    let offset = vec2<f32>(sin(phase) * 1.5 * texel.x, 0.0);
+    let tex_color = sample_diffuse(in.tex_coords + offset);
+    let tint = vec3<f32>(0.85, 0.95, 1.1);
+    return vec4<f32>(tex_color.rgb * tint, tex_color.a) * in.color;
+}
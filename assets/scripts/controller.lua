@@ -1,10 +1,37 @@
 -- controller.lua — Default movement controller for Saturday Morning Engine
 -- Lua provides intents (desired motion), Rust resolves physics/collision.
 
+delivery_accepted = false
+hud_visible = true
+souls_collected = 0
+
+local MAX_RUN_SPEED = 180 -- CharacterController max_speed
+local DASH_TRAIL = 8
+local was_dashing = false
+
 function on_init()
     -- Called on script load/reload. Use for one-time setup.
 end
 
+-- Dialog callback (on_select in assets/dialog/courier_intro.json)
+function on_delivery_accepted(dialog_id, node_id)
+    delivery_accepted = true
+end
+
+-- Item pickup hook (on_pickup in assets/items/grim_items.json)
+function on_soul_coin(actor_id, item_id, count, pickup_id)
+    souls_collected = engine.inventory.count(actor_id, item_id)
+    engine.ui.set_text("souls_label", "SOULS " .. souls_collected)
+end
+
+-- UI button callback (on_click in assets/ui/hud.json)
+function on_hud_toggle(widget_id)
+    hud_visible = not hud_visible
+    for _, id in ipairs({ "status_panel", "courier_icon", "speed_bar", "delivery_bar", "souls_label" }) do
+        engine.ui.set_visible(id, hud_visible)
+    end
+end
+
 function on_update(dt)
     local move_x = 0
     if engine.input.is_held("left") or engine.input.is_held("a") then
@@ -18,5 +45,24 @@ function on_update(dt)
         or engine.input.is_just_pressed("w")
         or engine.input.is_just_pressed("up")
 
-    engine.actor.set_intent(move_x, jump)
+    local crouch = engine.input.is_held("s")
+
+    engine.actor.set_intent(move_x, jump, crouch)
+    if engine.input.is_just_pressed("shift") then
+        engine.actor.dash()
+    end
+
+    -- Afterimages while dashing
+    if engine.actor.dashing ~= was_dashing then
+        was_dashing = engine.actor.dashing
+        engine.sprite.set_trail("player", was_dashing and DASH_TRAIL or 0)
+    end
+
+    -- Talk: pauses the simulation until the conversation ends
+    if engine.input.is_just_pressed("down") and engine.actor.grounded then
+        engine.dialog.start("courier_intro")
+    end
+
+    engine.ui.set_value("speed_bar", math.abs(engine.actor.velocity_x) / MAX_RUN_SPEED)
+    engine.ui.set_value("delivery_bar", delivery_accepted and 1 or 0)
 end
@@ -0,0 +1,67 @@
+// Post-process pass: one fullscreen triangle that copies the scene to the
+// surface through the color grading LUT. See `sme_render::post_process` for
+// the LUT layout.
+
+struct GradeUniform {
+    lut_size: f32,
+    // 1.0 when the scene texture is sRGB, so samples come back linear and
+    // have to be re-encoded before the lookup.
+    srgb_surface: f32,
+    _padding: vec2<f32>,
+};
+
+@group(0) @binding(0) var t_scene: texture_2d<f32>;
+@group(0) @binding(1) var s_scene: sampler;
+@group(0) @binding(2) var t_lut: texture_2d<f32>;
+@group(0) @binding(3) var s_lut: sampler;
+@group(0) @binding(4) var<uniform> grade: GradeUniform;
+
+struct VertexOutput {
+    @builtin(position) clip_position: vec4<f32>,
+    @location(0) uv: vec2<f32>,
+};
+
+@vertex
+fn vs_main(@builtin(vertex_index) index: u32) -> VertexOutput {
+    let uv = vec2<f32>(f32((index << 1u) & 2u), f32(index & 2u));
+    var out: VertexOutput;
+    out.clip_position = vec4<f32>(uv.x * 2.0 - 1.0, 1.0 - uv.y * 2.0, 0.0, 1.0);
+    out.uv = uv;
+    return out;
+}
+
+fn linear_to_srgb(c: vec3<f32>) -> vec3<f32> {
+    let low = c * 12.92;
+    let high = 1.055 * pow(c, vec3<f32>(1.0 / 2.4)) - 0.055;
+    return select(high, low, c <= vec3<f32>(0.0031308));
+}
+
+fn srgb_to_linear(c: vec3<f32>) -> vec3<f32> {
+    let low = c / 12.92;
+    let high = pow((c + 0.055) / 1.055, vec3<f32>(2.4));
+    return select(high, low, c <= vec3<f32>(0.04045));
+}
+
+fn grade_color(c: vec3<f32>) -> vec3<f32> {
+    let n = grade.lut_size;
+    let color = clamp(c, vec3<f32>(0.0), vec3<f32>(1.0));
+    let blue = color.b * (n - 1.0);
+    let slice0 = floor(blue);
+    let slice1 = min(slice0 + 1.0, n - 1.0);
+    // Texel centers, so filtering never bleeds into the neighboring slice.
+    let texel = color.rg * (n - 1.0) + 0.5;
+    let uv0 = vec2<f32>((slice0 * n + texel.x) / (n * n), texel.y / n);
+    let uv1 = vec2<f32>((slice1 * n + texel.x) / (n * n), texel.y / n);
+    let a = textureSampleLevel(t_lut, s_lut, uv0, 0.0).rgb;
+    let b = textureSampleLevel(t_lut, s_lut, uv1, 0.0).rgb;
+    return mix(a, b, blue - slice0);
+}
+
+@fragment
+fn fs_main(in: VertexOutput) -> @location(0) vec4<f32> {
+    let scene = textureSample(t_scene, s_scene, in.uv);
+    if grade.srgb_surface > 0.5 {
+        return vec4<f32>(srgb_to_linear(grade_color(linear_to_srgb(scene.rgb))), scene.a);
+    }
+    return vec4<f32>(grade_color(scene.rgb), scene.a);
+}
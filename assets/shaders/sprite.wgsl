@@ -0,0 +1,79 @@
+// Sprite shader: bindings, vertex stage and the plain fragment stage.
+// Effect and material shaders are appended to this file, so keep
+// `VertexOutput` and the bind groups stable when editing.
+
+struct CameraUniform {
+    view_proj: mat4x4<f32>,
+    // Day/night tint; white for the screen-space camera.
+    ambient: vec4<f32>,
+};
+@group(0) @binding(0) var<uniform> camera: CameraUniform;
+
+@group(1) @binding(0) var t_diffuse: texture_2d<f32>;
+@group(1) @binding(1) var s_diffuse: sampler;
+// Normal map or emissive mask from a parallel atlas, sampled with
+// `s_diffuse`. Sprites without one (and everything on Tier 0) get a 1x1
+// transparent black texture, so alpha 0 means "no secondary".
+@group(1) @binding(2) var t_secondary: texture_2d<f32>;
+
+// Whether sprite textures hold premultiplied alpha (`AlphaMode`). Set per
+// pipeline, whose blend state then expects premultiplied output.
+override premultiplied_alpha: bool = false;
+
+struct VertexInput {
+    @location(0) position: vec2<f32>,
+    @location(1) tex_coords: vec2<f32>,
+    @location(2) color: vec4<f32>,
+    @location(3) effect: vec2<f32>,
+    @location(4) tex_coords2: vec2<f32>,
+};
+
+struct VertexOutput {
+    @builtin(position) clip_position: vec4<f32>,
+    @location(0) tex_coords: vec2<f32>,
+    @location(1) color: vec4<f32>,
+    @location(2) effect: vec2<f32>,
+    @location(3) world_position: vec2<f32>,
+    @location(4) tex_coords2: vec2<f32>,
+};
+
+@vertex
+fn vs_main(in: VertexInput) -> VertexOutput {
+    var out: VertexOutput;
+    out.clip_position = camera.view_proj * vec4<f32>(in.position, 0.0, 1.0);
+    out.tex_coords = in.tex_coords;
+    out.color = vec4<f32>(in.color.rgb * camera.ambient.rgb, in.color.a);
+    out.effect = in.effect;
+    out.world_position = in.position;
+    out.tex_coords2 = in.tex_coords2;
+    return out;
+}
+
+fn sample_secondary(in: VertexOutput) -> vec4<f32> {
+    return textureSample(t_secondary, s_diffuse, in.tex_coords2);
+}
+
+// The sprite texel at `uv` in straight alpha, however the texture stores it.
+// Premultiplied texels are filtered before the divide, so transparent texels
+// never bleed their color into anti-aliased edges.
+fn sample_diffuse(uv: vec2<f32>) -> vec4<f32> {
+    let texel = textureSample(t_diffuse, s_diffuse, uv);
+    if premultiplied_alpha && texel.a > 0.0 {
+        return vec4<f32>(texel.rgb / texel.a, texel.a);
+    }
+    return texel;
+}
+
+// A straight-alpha fragment color in the form the blend state expects.
+fn blend_output(color: vec4<f32>) -> vec4<f32> {
+    if premultiplied_alpha {
+        return vec4<f32>(color.rgb * color.a, color.a);
+    }
+    return color;
+}
+
+@fragment
+fn fs_main(in: VertexOutput) -> @location(0) vec4<f32> {
+    let tex_color = sample_diffuse(in.tex_coords);
+    return blend_output(tex_color * in.color);
+}
@@ -0,0 +1,39 @@
+// Fragment stage for `SpriteEffect`s, appended to sprite.wgsl. Effect ids
+// match `SpriteEffect::vertex_data`: 1 flash, 2 dissolve, 3 outline.
+
+// Stable per-texel noise for dissolve.
+fn texel_noise(texel: vec2<f32>) -> f32 {
+    return fract(sin(dot(floor(texel), vec2<f32>(12.9898, 78.233))) * 43758.5453);
+}
+
+@fragment
+fn fs_effect(in: VertexOutput) -> @location(0) vec4<f32> {
+    let tex_color = sample_diffuse(in.tex_coords);
+    let size = vec2<f32>(textureDimensions(t_diffuse));
+    let kind = i32(in.effect.x + 0.5);
+    let param = in.effect.y;
+    var color = tex_color * in.color;
+    if kind == 1 {
+        color = vec4<f32>(mix(color.rgb, vec3<f32>(1.0), param), color.a);
+    } else if kind == 2 {
+        if texel_noise(in.tex_coords * size) < param {
+            discard;
+        }
+    } else if kind == 3 && tex_color.a < 0.5 {
+        let step = vec2<f32>(param) / size;
+        let neighbors = max(
+            max(
+                textureSampleLevel(t_diffuse, s_diffuse, in.tex_coords + vec2<f32>(step.x, 0.0), 0.0).a,
+                textureSampleLevel(t_diffuse, s_diffuse, in.tex_coords - vec2<f32>(step.x, 0.0), 0.0).a,
+            ),
+            max(
+                textureSampleLevel(t_diffuse, s_diffuse, in.tex_coords + vec2<f32>(0.0, step.y), 0.0).a,
+                textureSampleLevel(t_diffuse, s_diffuse, in.tex_coords - vec2<f32>(0.0, step.y), 0.0).a,
+            ),
+        );
+        if neighbors >= 0.5 {
+            color = in.color;
+        }
+    }
+    return blend_output(color);
+}
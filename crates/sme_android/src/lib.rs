@@ -0,0 +1,17 @@
+//! Android shell for the Saturday Morning Engine.
+//!
+//! NativeActivity loads this library and calls `android_main`; everything
+//! after logging setup lives in `sme_engine::run_android`. Content ships as
+//! `android_assets/content.smepack` (build it with `sme_pack`), which the APK
+//! packs into its `assets/` folder.
+
+#[cfg(target_os = "android")]
+#[no_mangle]
+fn android_main(app: sme_platform::mobile::AndroidApp) {
+    android_logger::init_once(
+        android_logger::Config::default()
+            .with_max_level(log::LevelFilter::Info)
+            .with_tag("sme_game"),
+    );
+    sme_engine::run_android(app);
+}
@@ -0,0 +1,70 @@
+//! The atlas and animation file being previewed.
+
+use sme_atlas::metadata::{AtlasMetadata, AtlasSprite};
+use sme_core::animation::{load_animation_file, AnimationClip, AnimationFile};
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+
+pub struct PreviewAssets {
+    pub atlas_path: PathBuf,
+    pub animation_path: PathBuf,
+    pub atlas: AtlasMetadata,
+    pub animation: AnimationFile,
+    sprites: HashMap<String, AtlasSprite>,
+    /// Clip names in display order.
+    pub clip_names: Vec<String>,
+    /// Frame sprite ids the atlas does not contain; those frames draw nothing.
+    pub missing_sprites: Vec<String>,
+}
+
+impl PreviewAssets {
+    pub fn load(atlas_path: &Path, animation_path: &Path) -> Result<Self, String> {
+        let raw = sme_core::assets::read_to_string(atlas_path)
+            .map_err(|e| format!("Failed to read atlas {}: {e}", atlas_path.display()))?;
+        let atlas: AtlasMetadata = serde_json::from_str(&raw)
+            .map_err(|e| format!("Failed to parse atlas {}: {e}", atlas_path.display()))?;
+        let animation = load_animation_file(animation_path)?;
+
+        let sprites: HashMap<String, AtlasSprite> = atlas
+            .sprites
+            .iter()
+            .map(|sprite| (sprite.sprite_id.clone(), sprite.clone()))
+            .collect();
+        let mut clip_names: Vec<String> = animation.animations.keys().cloned().collect();
+        clip_names.sort();
+        let mut missing_sprites: Vec<String> = animation
+            .animations
+            .values()
+            .flat_map(|clip| clip.frames.iter())
+            .filter(|frame| !sprites.contains_key(frame.sprite_id.as_str()))
+            .map(|frame| frame.sprite_id.to_string())
+            .collect();
+        missing_sprites.sort();
+        missing_sprites.dedup();
+
+        Ok(Self {
+            atlas_path: atlas_path.to_path_buf(),
+            animation_path: animation_path.to_path_buf(),
+            atlas,
+            animation,
+            sprites,
+            clip_names,
+            missing_sprites,
+        })
+    }
+
+    pub fn clip(&self, name: &str) -> Option<&AnimationClip> {
+        self.animation.animations.get(name)
+    }
+
+    pub fn sprite(&self, sprite_id: &str) -> Option<&AtlasSprite> {
+        self.sprites.get(sprite_id)
+    }
+
+    /// Display name for a frame's sprite, falling back to its id.
+    pub fn sprite_name<'a>(&'a self, sprite_id: &'a str) -> &'a str {
+        self.sprite(sprite_id)
+            .map(|sprite| sprite.name.as_str())
+            .unwrap_or(sprite_id)
+    }
+}
@@ -0,0 +1,529 @@
+//! Animation preview: plays the clips of one animation file against one
+//! atlas, without a scene. For checking timing, not gameplay.
+//!
+//! Usage: `cargo run -p sme_anim_preview -- <atlas.json> <animation.json> [clip]`
+//!
+//! Controls: SPACE play/pause · LEFT/RIGHT step a frame · R reloads both
+//! files · ESC quits. Scrubbing, speed, onion skin and zoom are in the panel.
+
+mod assets;
+mod panel;
+mod playback;
+
+use std::path::PathBuf;
+use std::sync::Arc;
+use std::time::Instant;
+
+use winit::application::ApplicationHandler;
+use winit::event::{ElementState, WindowEvent};
+use winit::event_loop::{ActiveEventLoop, ControlFlow, EventLoop};
+use winit::keyboard::{KeyCode, PhysicalKey};
+use winit::window::{Window, WindowId};
+
+use assets::PreviewAssets;
+use panel::{PreviewPanel, ViewSettings};
+use playback::{OnionSide, Playback};
+use sme_platform::window::PlatformConfig;
+use sme_render::{Camera2D, GpuContext, SpriteEffect, SpritePipeline, SpriteVertex, Texture};
+
+const CLEAR_COLOR: wgpu::Color = wgpu::Color {
+    r: 0.16,
+    g: 0.17,
+    b: 0.2,
+    a: 1.0,
+};
+/// Onion skins: earlier frames tinted red, later ones blue, fading with
+/// distance from the current frame.
+const ONION_BEFORE_TINT: [f32; 3] = [1.0, 0.45, 0.45];
+const ONION_AFTER_TINT: [f32; 3] = [0.45, 0.7, 1.0];
+const ONION_ALPHA: f32 = 0.45;
+const PIVOT_COLOR: [f32; 4] = [1.0, 0.85, 0.2, 1.0];
+/// Pivot crosshair arm length, in screen pixels.
+const PIVOT_ARM_PX: f32 = 6.0;
+
+struct PreviewApp {
+    window: Arc<Window>,
+    gpu: GpuContext,
+    camera: Camera2D,
+    sprite_pipeline: SpritePipeline,
+    panel: PreviewPanel,
+    assets: PreviewAssets,
+    playback: Playback,
+    view: ViewSettings,
+    /// Last reload error, shown in the panel until a reload succeeds.
+    status: Option<String>,
+    last_frame: Instant,
+
+    atlas_bind_group: wgpu::BindGroup,
+    white_bind_group: wgpu::BindGroup,
+    camera_buffer: wgpu::Buffer,
+    camera_bind_group: wgpu::BindGroup,
+    vertex_buffer: wgpu::Buffer,
+    index_buffer: wgpu::Buffer,
+    vertex_capacity: usize,
+    index_capacity: usize,
+    /// Indices drawn with the atlas; the rest use the white texture.
+    sprite_index_count: u32,
+    index_count: u32,
+}
+
+impl PreviewApp {
+    fn new(window: Arc<Window>, assets: PreviewAssets, playback: Playback) -> Self {
+        let gpu = GpuContext::new(window.clone());
+        let sprite_pipeline = SpritePipeline::new(&gpu.device, gpu.surface_format);
+        let panel = PreviewPanel::new(&gpu.device, gpu.surface_format, &window);
+
+        let atlas_texture = load_atlas_texture(&gpu, &assets)
+            .unwrap_or_else(|err| panic!("Atlas texture failed to load: {err}"));
+        let atlas_bind_group =
+            sprite_pipeline.create_texture_bind_group(&gpu.device, &atlas_texture, None);
+        let white = Texture::from_rgba8(
+            &gpu.device,
+            &gpu.queue,
+            &[255, 255, 255, 255],
+            1,
+            1,
+            "white",
+        );
+        let white_bind_group = sprite_pipeline.create_texture_bind_group(&gpu.device, &white, None);
+
+        let camera = Camera2D::new(gpu.size.0, gpu.size.1);
+        let camera_buffer = {
+            use wgpu::util::DeviceExt;
+            gpu.device
+                .create_buffer_init(&wgpu::util::BufferInitDescriptor {
+                    label: Some("Camera Uniform Buffer"),
+                    contents: bytemuck::cast_slice(&[camera.build_uniform()]),
+                    usage: wgpu::BufferUsages::UNIFORM | wgpu::BufferUsages::COPY_DST,
+                })
+        };
+        let camera_bind_group =
+            sprite_pipeline.create_camera_bind_group(&gpu.device, &camera_buffer);
+        let vertex_buffer = create_vertex_buffer(&gpu.device, 64);
+        let index_buffer = create_index_buffer(&gpu.device, 96);
+
+        Self {
+            window,
+            gpu,
+            camera,
+            sprite_pipeline,
+            panel,
+            assets,
+            playback,
+            view: ViewSettings::default(),
+            status: None,
+            last_frame: Instant::now(),
+            atlas_bind_group,
+            white_bind_group,
+            camera_buffer,
+            camera_bind_group,
+            vertex_buffer,
+            index_buffer,
+            vertex_capacity: 64,
+            index_capacity: 96,
+            sprite_index_count: 0,
+            index_count: 0,
+        }
+    }
+
+    /// Re-read both files. On failure the previous versions stay loaded.
+    fn reload(&mut self) {
+        let result = PreviewAssets::load(&self.assets.atlas_path, &self.assets.animation_path)
+            .and_then(|assets| {
+                let texture = load_atlas_texture(&self.gpu, &assets)?;
+                Ok((assets, texture))
+            });
+        match result {
+            Ok((assets, texture)) => {
+                self.atlas_bind_group = self.sprite_pipeline.create_texture_bind_group(
+                    &self.gpu.device,
+                    &texture,
+                    None,
+                );
+                if assets.clip(&self.playback.clip_name).is_none() {
+                    if let Some(first) = assets.clip_names.first() {
+                        self.playback.select_clip(first);
+                    }
+                }
+                self.assets = assets;
+                self.status = None;
+                log::info!("Reloaded {}", self.assets.animation_path.display());
+            }
+            Err(err) => {
+                log::error!("Reload failed: {err}");
+                self.status = Some(format!("Reload failed: {err}"));
+            }
+        }
+    }
+
+    fn handle_key(&mut self, event_loop: &ActiveEventLoop, key_code: KeyCode) {
+        if key_code == KeyCode::Escape {
+            event_loop.exit();
+            return;
+        }
+        if key_code == KeyCode::KeyR {
+            self.reload();
+            return;
+        }
+        let Some(clip) = self.assets.clip(&self.playback.clip_name) else {
+            return;
+        };
+        match key_code {
+            KeyCode::Space => self.playback.playing = !self.playback.playing,
+            KeyCode::ArrowLeft => self.playback.step(-1, clip),
+            KeyCode::ArrowRight => self.playback.step(1, clip),
+            _ => {}
+        }
+    }
+
+    fn rebuild_mesh(&mut self) {
+        let mut vertices: Vec<SpriteVertex> = Vec::new();
+        let mut indices: Vec<u32> = Vec::new();
+
+        if let Some(clip) = self.assets.clip(&self.playback.clip_name) {
+            let mut draw_frame = |frame_index: usize, color: [f32; 4]| {
+                let Some(sprite) = clip
+                    .frames
+                    .get(frame_index)
+                    .and_then(|frame| self.assets.sprite(frame.sprite_id.as_str()))
+                else {
+                    return;
+                };
+                let w = sprite.rect_px.w as f32;
+                let h = sprite.rect_px.h as f32;
+                let left = -w * sprite.pivot.x;
+                let bottom = -h * sprite.pivot.y;
+                let uv = sprite.uv;
+                push_quad(
+                    &mut vertices,
+                    &mut indices,
+                    [left, bottom, left + w, bottom + h],
+                    [
+                        [uv.u0, uv.v1],
+                        [uv.u1, uv.v1],
+                        [uv.u1, uv.v0],
+                        [uv.u0, uv.v0],
+                    ],
+                    color,
+                );
+            };
+
+            for onion in self.playback.onion_frames(clip) {
+                let [r, g, b] = match onion.side {
+                    OnionSide::Before => ONION_BEFORE_TINT,
+                    OnionSide::After => ONION_AFTER_TINT,
+                };
+                let alpha = ONION_ALPHA / onion.distance as f32;
+                draw_frame(onion.frame_index, [r, g, b, alpha]);
+            }
+            draw_frame(self.playback.frame_index, [1.0, 1.0, 1.0, 1.0]);
+        }
+        self.sprite_index_count = indices.len() as u32;
+
+        if self.view.show_pivot {
+            let arm = PIVOT_ARM_PX / self.view.zoom;
+            let half = 0.5 / self.view.zoom;
+            let uv = [[0.0, 1.0], [1.0, 1.0], [1.0, 0.0], [0.0, 0.0]];
+            push_quad(
+                &mut vertices,
+                &mut indices,
+                [-arm, -half, arm, half],
+                uv,
+                PIVOT_COLOR,
+            );
+            push_quad(
+                &mut vertices,
+                &mut indices,
+                [-half, -arm, half, arm],
+                uv,
+                PIVOT_COLOR,
+            );
+        }
+
+        if vertices.len() > self.vertex_capacity {
+            self.vertex_capacity = vertices.len().next_power_of_two();
+            self.vertex_buffer = create_vertex_buffer(&self.gpu.device, self.vertex_capacity);
+        }
+        if indices.len() > self.index_capacity {
+            self.index_capacity = indices.len().next_power_of_two();
+            self.index_buffer = create_index_buffer(&self.gpu.device, self.index_capacity);
+        }
+        self.index_count = indices.len() as u32;
+        if !vertices.is_empty() {
+            self.gpu
+                .queue
+                .write_buffer(&self.vertex_buffer, 0, bytemuck::cast_slice(&vertices));
+            self.gpu
+                .queue
+                .write_buffer(&self.index_buffer, 0, bytemuck::cast_slice(&indices));
+        }
+    }
+
+    fn redraw(&mut self) {
+        if self.gpu.size.0 == 0 || self.gpu.size.1 == 0 {
+            return;
+        }
+
+        let now = Instant::now();
+        let dt_us = now.duration_since(self.last_frame).as_micros() as u64;
+        self.last_frame = now;
+        if let Some(clip) = self.assets.clip(&self.playback.clip_name) {
+            self.playback.advance(dt_us, clip);
+        }
+
+        let (egui_primitives, egui_textures_delta, panel_output) = self.panel.prepare(
+            &self.window,
+            &self.assets,
+            &mut self.playback,
+            &mut self.view,
+            self.status.as_deref(),
+        );
+        if panel_output.reload_requested {
+            self.reload();
+        }
+
+        // Put the world origin (the sprite pivot) in the middle of the area
+        // the panel leaves free.
+        let (stage_x, stage_y) = panel_output.stage_center_px;
+        self.camera.zoom = self.view.zoom;
+        self.camera.position.x = (self.gpu.size.0 as f32 * 0.5 - stage_x) / self.camera.zoom;
+        self.camera.position.y = (stage_y - self.gpu.size.1 as f32 * 0.5) / self.camera.zoom;
+        self.rebuild_mesh();
+        self.gpu.queue.write_buffer(
+            &self.camera_buffer,
+            0,
+            bytemuck::cast_slice(&[self.camera.build_uniform()]),
+        );
+
+        let Some((output, view)) = self.gpu.begin_frame() else {
+            return;
+        };
+        let screen_descriptor = egui_wgpu::ScreenDescriptor {
+            size_in_pixels: [self.gpu.size.0, self.gpu.size.1],
+            pixels_per_point: self.window.scale_factor() as f32,
+        };
+
+        let mut encoder = self
+            .gpu
+            .device
+            .create_command_encoder(&wgpu::CommandEncoderDescriptor {
+                label: Some("Anim Preview Encoder"),
+            });
+
+        {
+            let mut render_pass = encoder.begin_render_pass(&wgpu::RenderPassDescriptor {
+                label: Some("Preview Render Pass"),
+                color_attachments: &[Some(wgpu::RenderPassColorAttachment {
+                    view: &view,
+                    resolve_target: None,
+                    ops: wgpu::Operations {
+                        load: wgpu::LoadOp::Clear(CLEAR_COLOR),
+                        store: wgpu::StoreOp::Store,
+                    },
+                })],
+                depth_stencil_attachment: None,
+                ..Default::default()
+            });
+            render_pass.set_pipeline(&self.sprite_pipeline.render_pipeline);
+            render_pass.set_bind_group(0, &self.camera_bind_group, &[]);
+            render_pass.set_vertex_buffer(0, self.vertex_buffer.slice(..));
+            render_pass.set_index_buffer(self.index_buffer.slice(..), wgpu::IndexFormat::Uint32);
+            if self.sprite_index_count > 0 {
+                render_pass.set_bind_group(1, &self.atlas_bind_group, &[]);
+                render_pass.draw_indexed(0..self.sprite_index_count, 0, 0..1);
+            }
+            if self.index_count > self.sprite_index_count {
+                render_pass.set_bind_group(1, &self.white_bind_group, &[]);
+                render_pass.draw_indexed(self.sprite_index_count..self.index_count, 0, 0..1);
+            }
+        }
+
+        self.panel.upload(
+            &self.gpu.device,
+            &self.gpu.queue,
+            &mut encoder,
+            &egui_primitives,
+            &egui_textures_delta,
+            &screen_descriptor,
+        );
+
+        {
+            let mut egui_pass = encoder
+                .begin_render_pass(&wgpu::RenderPassDescriptor {
+                    label: Some("Panel Render Pass"),
+                    color_attachments: &[Some(wgpu::RenderPassColorAttachment {
+                        view: &view,
+                        resolve_target: None,
+                        ops: wgpu::Operations {
+                            load: wgpu::LoadOp::Load,
+                            store: wgpu::StoreOp::Store,
+                        },
+                    })],
+                    depth_stencil_attachment: None,
+                    ..Default::default()
+                })
+                .forget_lifetime();
+            self.panel
+                .paint(&mut egui_pass, &egui_primitives, &screen_descriptor);
+        }
+
+        self.panel.cleanup(&egui_textures_delta);
+        self.gpu.queue.submit(std::iter::once(encoder.finish()));
+        output.present();
+    }
+}
+
+/// Decode the atlas PNG up front so a bad file is an error, not a panic
+/// inside `Texture::from_bytes`.
+fn load_atlas_texture(gpu: &GpuContext, assets: &PreviewAssets) -> Result<Texture, String> {
+    let path = PathBuf::from(&assets.atlas.texture.path);
+    let bytes = sme_core::assets::read_bytes(&path)
+        .map_err(|e| format!("Failed to read atlas texture {}: {e}", path.display()))?;
+    let image = image::load_from_memory(&bytes)
+        .map_err(|e| format!("Failed to decode atlas texture {}: {e}", path.display()))?
+        .to_rgba8();
+    Ok(Texture::from_rgba8(
+        &gpu.device,
+        &gpu.queue,
+        image.as_raw(),
+        image.width(),
+        image.height(),
+        &assets.atlas.atlas_id,
+    ))
+}
+
+/// Append one quad; `rect` is `[left, bottom, right, top]` and `uvs` run
+/// bottom-left, bottom-right, top-right, top-left.
+fn push_quad(
+    vertices: &mut Vec<SpriteVertex>,
+    indices: &mut Vec<u32>,
+    rect: [f32; 4],
+    uvs: [[f32; 2]; 4],
+    color: [f32; 4],
+) {
+    let [left, bottom, right, top] = rect;
+    let base = vertices.len() as u32;
+    let corners = [[left, bottom], [right, bottom], [right, top], [left, top]];
+    for (position, tex_coords) in corners.into_iter().zip(uvs) {
+        vertices.push(SpriteVertex {
+            position,
+            tex_coords,
+            color,
+            effect: SpriteEffect::None.vertex_data(),
+            tex_coords2: [0.0, 0.0],
+        });
+    }
+    indices.extend_from_slice(&[base, base + 1, base + 2, base, base + 2, base + 3]);
+}
+
+struct App {
+    config: PlatformConfig,
+    /// Loaded before the window opens so bad paths fail fast on the CLI.
+    pending: Option<(PreviewAssets, Playback)>,
+    state: Option<PreviewApp>,
+}
+
+impl ApplicationHandler for App {
+    fn resumed(&mut self, event_loop: &ActiveEventLoop) {
+        let Some((assets, playback)) = self.pending.take() else {
+            return;
+        };
+        let window = sme_platform::window::create_window(event_loop, &self.config);
+        self.state = Some(PreviewApp::new(window, assets, playback));
+    }
+
+    fn about_to_wait(&mut self, _event_loop: &ActiveEventLoop) {
+        if let Some(state) = &self.state {
+            state.window.request_redraw();
+        }
+    }
+
+    fn window_event(&mut self, event_loop: &ActiveEventLoop, _id: WindowId, event: WindowEvent) {
+        let Some(state) = self.state.as_mut() else {
+            return;
+        };
+        let egui_consumed = state.panel.handle_window_event(&state.window, &event);
+
+        match event {
+            WindowEvent::CloseRequested => event_loop.exit(),
+            WindowEvent::Resized(size) if size.width > 0 && size.height > 0 => {
+                state.gpu.resize(size.width, size.height);
+                state.camera.viewport = (size.width, size.height);
+            }
+            WindowEvent::KeyboardInput { event, .. }
+                if !egui_consumed && event.state == ElementState::Pressed && !event.repeat =>
+            {
+                if let PhysicalKey::Code(key_code) = event.physical_key {
+                    state.handle_key(event_loop, key_code);
+                }
+            }
+            WindowEvent::RedrawRequested => state.redraw(),
+            _ => {}
+        }
+    }
+}
+
+fn create_vertex_buffer(device: &wgpu::Device, vertex_capacity: usize) -> wgpu::Buffer {
+    device.create_buffer(&wgpu::BufferDescriptor {
+        label: Some("Preview Vertex Buffer"),
+        size: (vertex_capacity * std::mem::size_of::<SpriteVertex>()).max(1) as u64,
+        usage: wgpu::BufferUsages::VERTEX | wgpu::BufferUsages::COPY_DST,
+        mapped_at_creation: false,
+    })
+}
+
+fn create_index_buffer(device: &wgpu::Device, index_capacity: usize) -> wgpu::Buffer {
+    device.create_buffer(&wgpu::BufferDescriptor {
+        label: Some("Preview Index Buffer"),
+        size: (index_capacity * std::mem::size_of::<u32>()).max(1) as u64,
+        usage: wgpu::BufferUsages::INDEX | wgpu::BufferUsages::COPY_DST,
+        mapped_at_creation: false,
+    })
+}
+
+fn usage() -> String {
+    "Usage: cargo run -p sme_anim_preview -- <atlas.json> <animation.json> [clip]\nExample: cargo run -p sme_anim_preview -- assets/generated/m4_sample_atlas.json assets/animations/hero.json walk".to_string()
+}
+
+fn main() -> Result<(), String> {
+    env_logger::Builder::from_env(env_logger::Env::default().default_filter_or("info")).init();
+
+    let args: Vec<String> = std::env::args().skip(1).collect();
+    if args.len() < 2 || args.len() > 3 {
+        return Err(usage());
+    }
+    let assets = PreviewAssets::load(&PathBuf::from(&args[0]), &PathBuf::from(&args[1]))?;
+    let clip_name = match args.get(2) {
+        Some(name) if assets.clip(name).is_some() => name.clone(),
+        Some(name) => {
+            return Err(format!(
+                "Clip '{name}' not found in {}; available: {}",
+                args[1],
+                assets.clip_names.join(", ")
+            ))
+        }
+        None => assets
+            .clip_names
+            .first()
+            .cloned()
+            .ok_or_else(|| format!("Animation file {} has no clips", args[1]))?,
+    };
+    for sprite_id in &assets.missing_sprites {
+        log::warn!("Sprite id '{sprite_id}' is not in the atlas; its frames draw nothing");
+    }
+
+    let event_loop = EventLoop::new().map_err(|e| format!("Failed to create event loop: {e}"))?;
+    event_loop.set_control_flow(ControlFlow::Poll);
+    let mut app = App {
+        config: PlatformConfig {
+            title: format!("SME Animation Preview — {}", args[1]),
+            width: 1280,
+            height: 720,
+        },
+        pending: Some((assets, Playback::new(&clip_name))),
+        state: None,
+    };
+    event_loop
+        .run_app(&mut app)
+        .map_err(|e| format!("Event loop error: {e}"))
+}
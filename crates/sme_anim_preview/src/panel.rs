@@ -0,0 +1,256 @@
+//! Playback controls, drawn with egui in the same prepare/upload/paint
+//! phases as the engine's DebugOverlay.
+
+use winit::window::Window;
+
+use crate::assets::PreviewAssets;
+use crate::playback::{Playback, MAX_ONION_FRAMES};
+
+const PANEL_WIDTH: f32 = 280.0;
+
+/// How the stage is drawn; edited from the panel.
+pub struct ViewSettings {
+    pub zoom: f32,
+    pub show_pivot: bool,
+}
+
+impl Default for ViewSettings {
+    fn default() -> Self {
+        Self {
+            zoom: 4.0,
+            show_pivot: true,
+        }
+    }
+}
+
+#[derive(Default)]
+pub struct PanelOutput {
+    pub reload_requested: bool,
+    /// Center of the area left of the panel, in physical pixels.
+    pub stage_center_px: (f32, f32),
+}
+
+pub struct PreviewPanel {
+    egui_ctx: egui::Context,
+    egui_winit_state: egui_winit::State,
+    egui_renderer: egui_wgpu::Renderer,
+}
+
+impl PreviewPanel {
+    pub fn new(
+        device: &wgpu::Device,
+        surface_format: wgpu::TextureFormat,
+        window: &Window,
+    ) -> Self {
+        let egui_ctx = egui::Context::default();
+        let egui_winit_state = egui_winit::State::new(
+            egui_ctx.clone(),
+            egui_ctx.viewport_id(),
+            window,
+            None,
+            None,
+            None,
+        );
+        let egui_renderer = egui_wgpu::Renderer::new(device, surface_format, None, 1, false);
+        Self {
+            egui_ctx,
+            egui_winit_state,
+            egui_renderer,
+        }
+    }
+
+    pub fn handle_window_event(
+        &mut self,
+        window: &Window,
+        event: &winit::event::WindowEvent,
+    ) -> bool {
+        self.egui_winit_state
+            .on_window_event(window, event)
+            .consumed
+    }
+
+    pub fn prepare(
+        &mut self,
+        window: &Window,
+        assets: &PreviewAssets,
+        playback: &mut Playback,
+        view: &mut ViewSettings,
+        status: Option<&str>,
+    ) -> (
+        Vec<egui::ClippedPrimitive>,
+        egui::TexturesDelta,
+        PanelOutput,
+    ) {
+        let raw_input = self.egui_winit_state.take_egui_input(window);
+        let mut output = PanelOutput::default();
+        let full_output = self.egui_ctx.run(raw_input, |ctx| {
+            egui::SidePanel::left("anim_preview_controls")
+                .exact_width(PANEL_WIDTH)
+                .resizable(false)
+                .show(ctx, |ui| {
+                    draw_controls(ui, assets, playback, view, status, &mut output);
+                });
+            let stage = ctx.available_rect();
+            let scale = ctx.pixels_per_point();
+            output.stage_center_px = (stage.center().x * scale, stage.center().y * scale);
+        });
+
+        self.egui_winit_state
+            .handle_platform_output(window, full_output.platform_output);
+        let primitives = self
+            .egui_ctx
+            .tessellate(full_output.shapes, full_output.pixels_per_point);
+        (primitives, full_output.textures_delta, output)
+    }
+
+    pub fn upload(
+        &mut self,
+        device: &wgpu::Device,
+        queue: &wgpu::Queue,
+        encoder: &mut wgpu::CommandEncoder,
+        primitives: &[egui::ClippedPrimitive],
+        textures_delta: &egui::TexturesDelta,
+        screen_descriptor: &egui_wgpu::ScreenDescriptor,
+    ) {
+        for (id, image_delta) in &textures_delta.set {
+            self.egui_renderer
+                .update_texture(device, queue, *id, image_delta);
+        }
+        self.egui_renderer
+            .update_buffers(device, queue, encoder, primitives, screen_descriptor);
+    }
+
+    pub fn paint(
+        &self,
+        render_pass: &mut wgpu::RenderPass<'static>,
+        primitives: &[egui::ClippedPrimitive],
+        screen_descriptor: &egui_wgpu::ScreenDescriptor,
+    ) {
+        self.egui_renderer
+            .render(render_pass, primitives, screen_descriptor);
+    }
+
+    pub fn cleanup(&mut self, textures_delta: &egui::TexturesDelta) {
+        for id in &textures_delta.free {
+            self.egui_renderer.free_texture(id);
+        }
+    }
+}
+
+fn draw_controls(
+    ui: &mut egui::Ui,
+    assets: &PreviewAssets,
+    playback: &mut Playback,
+    view: &mut ViewSettings,
+    status: Option<&str>,
+    output: &mut PanelOutput,
+) {
+    ui.heading("Animation Preview");
+    ui.label(format!("Atlas: {}", assets.atlas_path.display()));
+    ui.label(format!("Animation: {}", assets.animation_path.display()));
+    if ui.button("Reload files (R)").clicked() {
+        output.reload_requested = true;
+    }
+    if let Some(status) = status {
+        ui.colored_label(egui::Color32::from_rgb(230, 90, 80), status);
+    }
+    if !assets.missing_sprites.is_empty() {
+        ui.colored_label(
+            egui::Color32::from_rgb(230, 180, 60),
+            format!(
+                "{} sprite id(s) not in atlas: {}",
+                assets.missing_sprites.len(),
+                assets.missing_sprites.join(", ")
+            ),
+        );
+    }
+    ui.separator();
+
+    let mut selected = playback.clip_name.clone();
+    egui::ComboBox::from_label("Clip")
+        .selected_text(&selected)
+        .show_ui(ui, |ui| {
+            for name in &assets.clip_names {
+                ui.selectable_value(&mut selected, name.clone(), name);
+            }
+        });
+    if selected != playback.clip_name {
+        playback.select_clip(&selected);
+    }
+    let Some(clip) = assets.clip(&playback.clip_name) else {
+        ui.label("No clip selected.");
+        return;
+    };
+
+    ui.horizontal(|ui| {
+        if ui
+            .button("|<")
+            .on_hover_text("Previous frame (Left)")
+            .clicked()
+        {
+            playback.step(-1, clip);
+        }
+        let play_label = if playback.playing { "Pause" } else { "Play" };
+        if ui.button(play_label).on_hover_text("Space").clicked() {
+            playback.playing = !playback.playing;
+        }
+        if ui
+            .button(">|")
+            .on_hover_text("Next frame (Right)")
+            .clicked()
+        {
+            playback.step(1, clip);
+        }
+        ui.label(if clip.looping { "looping" } else { "once" });
+    });
+
+    let total_ms = clip.total_duration_us() as f32 / 1000.0;
+    let mut time_ms = playback.time_us(clip) as f32 / 1000.0;
+    let scrub = ui.add(
+        egui::Slider::new(&mut time_ms, 0.0..=total_ms)
+            .text("ms")
+            .fixed_decimals(0),
+    );
+    if scrub.changed() {
+        playback.scrub_to((time_ms * 1000.0) as u64, clip);
+    }
+    ui.add(
+        egui::Slider::new(&mut playback.speed, 0.1..=4.0)
+            .text("speed")
+            .logarithmic(true),
+    );
+    ui.separator();
+
+    ui.label("Onion skin");
+    ui.add(egui::Slider::new(&mut playback.onion_before, 0..=MAX_ONION_FRAMES).text("before"));
+    ui.add(egui::Slider::new(&mut playback.onion_after, 0..=MAX_ONION_FRAMES).text("after"));
+    ui.add(egui::Slider::new(&mut view.zoom, 1.0..=16.0).text("zoom"));
+    ui.checkbox(&mut view.show_pivot, "Show pivot");
+    ui.separator();
+
+    ui.label(format!(
+        "Frame {} / {}",
+        playback.frame_index + 1,
+        clip.frames.len()
+    ));
+    let mut jump = None;
+    egui::ScrollArea::vertical().show(ui, |ui| {
+        for (i, frame) in clip.frames.iter().enumerate() {
+            let text = format!(
+                "{:>2}  {}  {} ms",
+                i + 1,
+                assets.sprite_name(frame.sprite_id.as_str()),
+                frame.duration_us / 1000
+            );
+            if ui
+                .selectable_label(i == playback.frame_index, text)
+                .clicked()
+            {
+                jump = Some(i);
+            }
+        }
+    });
+    if let Some(i) = jump {
+        playback.jump_to(i, clip);
+    }
+}
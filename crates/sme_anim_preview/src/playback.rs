@@ -0,0 +1,252 @@
+//! Playback state for the previewed clip: play/pause, frame stepping,
+//! scrubbing, and which neighbouring frames to onion-skin.
+//!
+//! Timing uses integer microseconds like `sme_core::animation`, so what the
+//! preview shows matches what the engine's fixed-step ticking produces. The
+//! only float is the preview-only playback `speed`.
+
+use sme_core::animation::AnimationClip;
+
+/// Neighbouring frames drawn per side, at most.
+pub const MAX_ONION_FRAMES: usize = 3;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum OnionSide {
+    Before,
+    After,
+}
+
+/// A ghost frame to draw behind the current one.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct OnionFrame {
+    pub frame_index: usize,
+    pub side: OnionSide,
+    /// 1 for the nearest neighbour, growing outward.
+    pub distance: usize,
+}
+
+#[derive(Debug, Clone)]
+pub struct Playback {
+    pub clip_name: String,
+    pub frame_index: usize,
+    /// Time spent in the current frame.
+    pub elapsed_us: u64,
+    pub playing: bool,
+    /// Playback rate multiplier (1.0 = real time).
+    pub speed: f32,
+    pub onion_before: usize,
+    pub onion_after: usize,
+}
+
+impl Playback {
+    pub fn new(clip_name: &str) -> Self {
+        Self {
+            clip_name: clip_name.to_string(),
+            frame_index: 0,
+            elapsed_us: 0,
+            playing: true,
+            speed: 1.0,
+            onion_before: 0,
+            onion_after: 0,
+        }
+    }
+
+    /// Switch clips, restarting from the first frame. Other settings stay.
+    pub fn select_clip(&mut self, clip_name: &str) {
+        self.clip_name = clip_name.to_string();
+        self.frame_index = 0;
+        self.elapsed_us = 0;
+        self.playing = true;
+    }
+
+    /// Advance by `dt_us` of wall time (scaled by `speed`) while playing.
+    /// A non-looping clip pauses on its last frame.
+    pub fn advance(&mut self, dt_us: u64, clip: &AnimationClip) {
+        if !self.playing || clip.total_duration_us() == 0 {
+            return;
+        }
+        self.clamp_to(clip);
+        self.elapsed_us += (dt_us as f64 * self.speed.max(0.0) as f64) as u64;
+        loop {
+            let duration = clip.frames[self.frame_index].duration_us;
+            if self.elapsed_us < duration {
+                break;
+            }
+            self.elapsed_us -= duration;
+            self.frame_index += 1;
+            if self.frame_index == clip.frames.len() {
+                if clip.looping {
+                    self.frame_index = 0;
+                } else {
+                    self.frame_index = clip.frames.len() - 1;
+                    self.elapsed_us = 0;
+                    self.playing = false;
+                    break;
+                }
+            }
+        }
+    }
+
+    /// Pause and move `delta` frames, wrapping around the clip.
+    pub fn step(&mut self, delta: isize, clip: &AnimationClip) {
+        self.playing = false;
+        self.elapsed_us = 0;
+        let len = clip.frames.len() as isize;
+        if len == 0 {
+            return;
+        }
+        self.frame_index = (self.frame_index as isize + delta).rem_euclid(len) as usize;
+    }
+
+    /// Pause on a specific frame.
+    pub fn jump_to(&mut self, frame_index: usize, clip: &AnimationClip) {
+        self.playing = false;
+        self.elapsed_us = 0;
+        self.frame_index = frame_index.min(clip.frames.len().saturating_sub(1));
+    }
+
+    /// Position within the clip, from its start.
+    pub fn time_us(&self, clip: &AnimationClip) -> u64 {
+        let before: u64 = clip
+            .frames
+            .iter()
+            .take(self.frame_index)
+            .map(|frame| frame.duration_us)
+            .sum();
+        before + self.elapsed_us
+    }
+
+    /// Pause at `time_us` from the clip start (clamped to its length).
+    pub fn scrub_to(&mut self, time_us: u64, clip: &AnimationClip) {
+        self.playing = false;
+        let mut remaining = time_us;
+        for (i, frame) in clip.frames.iter().enumerate() {
+            if remaining < frame.duration_us || i + 1 == clip.frames.len() {
+                self.frame_index = i;
+                self.elapsed_us = remaining.min(frame.duration_us.saturating_sub(1));
+                return;
+            }
+            remaining -= frame.duration_us;
+        }
+        self.frame_index = 0;
+        self.elapsed_us = 0;
+    }
+
+    /// Ghost frames around the current one, farthest first so nearer ghosts
+    /// draw on top. Looping clips wrap; others stop at their ends.
+    pub fn onion_frames(&self, clip: &AnimationClip) -> Vec<OnionFrame> {
+        let len = clip.frames.len();
+        let mut frames = Vec::new();
+        if len < 2 {
+            return frames;
+        }
+        let reach = |count: usize| count.min(MAX_ONION_FRAMES).min(len - 1);
+        for (side, count) in [
+            (OnionSide::Before, reach(self.onion_before)),
+            (OnionSide::After, reach(self.onion_after)),
+        ] {
+            for distance in (1..=count).rev() {
+                let index = match side {
+                    OnionSide::Before => self.frame_index as isize - distance as isize,
+                    OnionSide::After => (self.frame_index + distance) as isize,
+                };
+                let index = if clip.looping {
+                    index.rem_euclid(len as isize)
+                } else if (0..len as isize).contains(&index) {
+                    index
+                } else {
+                    continue;
+                };
+                frames.push(OnionFrame {
+                    frame_index: index as usize,
+                    side,
+                    distance,
+                });
+            }
+        }
+        frames
+    }
+
+    fn clamp_to(&mut self, clip: &AnimationClip) {
+        if self.frame_index >= clip.frames.len() {
+            self.frame_index = 0;
+            self.elapsed_us = 0;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use sme_core::animation::AnimationFrame;
+
+    fn clip(durations_ms: &[u64], looping: bool) -> AnimationClip {
+        AnimationClip {
+            frames: durations_ms
+                .iter()
+                .enumerate()
+                .map(|(i, ms)| AnimationFrame {
+                    sprite_id: sme_core::interned::Id::new(&format!("f{i}")),
+                    duration_us: ms * 1000,
+                })
+                .collect(),
+            looping,
+        }
+    }
+
+    #[test]
+    fn advance_loops_or_stops_at_the_end() {
+        let looping = clip(&[100, 100, 100], true);
+        let mut playback = Playback::new("walk");
+        playback.advance(250_000, &looping);
+        assert_eq!((playback.frame_index, playback.elapsed_us), (2, 50_000));
+        playback.advance(100_000, &looping);
+        assert_eq!((playback.frame_index, playback.elapsed_us), (0, 50_000));
+
+        let once = clip(&[100, 100], false);
+        let mut playback = Playback::new("die");
+        playback.speed = 2.0;
+        playback.advance(150_000, &once);
+        assert_eq!(playback.frame_index, 1);
+        assert!(!playback.playing);
+    }
+
+    #[test]
+    fn step_and_scrub_pause_on_the_right_frame() {
+        let walk = clip(&[100, 50, 100], true);
+        let mut playback = Playback::new("walk");
+        playback.step(-1, &walk);
+        assert_eq!(playback.frame_index, 2);
+        assert!(!playback.playing);
+
+        playback.scrub_to(120_000, &walk);
+        assert_eq!((playback.frame_index, playback.elapsed_us), (1, 20_000));
+        assert_eq!(playback.time_us(&walk), 120_000);
+
+        // Past the end clamps into the last frame.
+        playback.scrub_to(10_000_000, &walk);
+        assert_eq!(playback.frame_index, 2);
+    }
+
+    #[test]
+    fn onion_frames_wrap_only_for_looping_clips() {
+        let walk = clip(&[100; 4], true);
+        let mut playback = Playback::new("walk");
+        playback.onion_before = 2;
+        playback.onion_after = 1;
+        let indices: Vec<usize> = playback
+            .onion_frames(&walk)
+            .iter()
+            .map(|f| f.frame_index)
+            .collect();
+        assert_eq!(indices, [2, 3, 1]);
+
+        let once = clip(&[100; 4], false);
+        let indices: Vec<usize> = playback
+            .onion_frames(&once)
+            .iter()
+            .map(|f| f.frame_index)
+            .collect();
+        assert_eq!(indices, [1]);
+    }
+}
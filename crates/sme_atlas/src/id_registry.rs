@@ -0,0 +1,335 @@
+//! Persistent sprite ID registry (`<atlas>.ids.json`).
+//!
+//! Sprite IDs must survive repacks, renames, and moves, because scenes refer
+//! to sprites by ID. Each entry remembers the source path and a hash of the
+//! decoded pixels; a sprite keeps its ID when either still matches.
+
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use std::collections::HashSet;
+use std::fs;
+use std::path::{Path, PathBuf};
+use uuid::Uuid;
+
+use crate::metadata::AtlasMetadata;
+
+#[derive(Debug, Clone, Serialize, Deserialize, Default)]
+pub struct IdRegistryFile {
+    pub entries: Vec<IdRegistryEntry>,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct IdRegistryEntry {
+    pub sprite_id: String,
+    pub source_hash: String,
+    pub last_known_path: String,
+    /// Hash of the encoded source file plus its sidecar meta as of the last
+    /// pack; lets incremental repacks skip decoding unchanged sprites.
+    #[serde(default, skip_serializing_if = "String::is_empty")]
+    pub input_fingerprint: String,
+}
+
+/// SHA-256 of decoded RGBA8 pixels, as lowercase hex.
+pub fn hash_rgba8_bytes(bytes: &[u8]) -> String {
+    let mut hasher = Sha256::new();
+    hasher.update(bytes);
+    let digest = hasher.finalize();
+    format!("{digest:x}")
+}
+
+/// Registry file stored alongside the atlas metadata JSON.
+pub fn id_registry_path_for(atlas_json_output: &Path) -> PathBuf {
+    atlas_json_output.with_extension("ids.json")
+}
+
+/// Load a registry, treating a missing file as empty.
+pub fn load_id_registry(path: &Path) -> Result<IdRegistryFile, String> {
+    if !path.exists() {
+        return Ok(IdRegistryFile::default());
+    }
+
+    let raw = fs::read_to_string(path)
+        .map_err(|e| format!("Failed to read id registry '{}': {e}", path.display()))?;
+    serde_json::from_str::<IdRegistryFile>(&raw)
+        .map_err(|e| format!("Failed to parse id registry '{}': {e}", path.display()))
+}
+
+/// Adopt the IDs of an atlas packed before registries existed.
+pub fn seed_registry_from_existing_metadata(
+    atlas_json_output: &Path,
+    id_registry: &mut IdRegistryFile,
+) -> Result<(), String> {
+    if !atlas_json_output.exists() {
+        return Ok(());
+    }
+
+    let raw = fs::read_to_string(atlas_json_output).map_err(|e| {
+        format!(
+            "Failed to read existing atlas metadata '{}': {e}",
+            atlas_json_output.display()
+        )
+    })?;
+    let metadata = serde_json::from_str::<AtlasMetadata>(&raw).map_err(|e| {
+        format!(
+            "Failed to parse existing atlas metadata '{}': {e}",
+            atlas_json_output.display()
+        )
+    })?;
+    for sprite in metadata.sprites {
+        if id_registry
+            .entries
+            .iter()
+            .any(|entry| entry.sprite_id == sprite.sprite_id)
+        {
+            continue;
+        }
+        id_registry.entries.push(IdRegistryEntry {
+            sprite_id: sprite.sprite_id,
+            source_hash: String::new(),
+            last_known_path: sprite.source_path,
+            input_fingerprint: String::new(),
+        });
+    }
+    Ok(())
+}
+
+/// Look up the ID for a source by path, then by content hash; assign a new
+/// UUID if neither matches. `live_paths` holds every source of the current
+/// pack: an entry still at one of them belongs to that source rather than
+/// being a renamed copy, so identical images keep distinct IDs.
+pub fn resolve_or_assign_sprite_id(
+    id_registry: &mut IdRegistryFile,
+    source_path: &str,
+    source_hash: &str,
+    live_paths: &HashSet<&str>,
+) -> String {
+    if let Some(entry) = id_registry
+        .entries
+        .iter_mut()
+        .find(|entry| entry.last_known_path == source_path)
+    {
+        entry.source_hash = source_hash.to_string();
+        return entry.sprite_id.clone();
+    }
+
+    let mut hash_matches = id_registry.entries.iter_mut().filter(|entry| {
+        entry.source_hash == source_hash && !live_paths.contains(entry.last_known_path.as_str())
+    });
+    if let Some(entry) = hash_matches.next() {
+        // Reuse IDs across rename/move when content stays identical.
+        entry.last_known_path = source_path.to_string();
+        return entry.sprite_id.clone();
+    }
+
+    let sprite_id = Uuid::new_v4().to_string();
+    id_registry.entries.push(IdRegistryEntry {
+        sprite_id: sprite_id.clone(),
+        source_hash: source_hash.to_string(),
+        last_known_path: source_path.to_string(),
+        input_fingerprint: String::new(),
+    });
+    sprite_id
+}
+
+/// Whether `source_path` was last packed from exactly these inputs.
+pub fn input_fingerprint_matches(
+    id_registry: &IdRegistryFile,
+    source_path: &str,
+    fingerprint: &str,
+) -> bool {
+    id_registry
+        .entries
+        .iter()
+        .any(|entry| entry.last_known_path == source_path && entry.input_fingerprint == fingerprint)
+}
+
+pub fn record_input_fingerprint(
+    id_registry: &mut IdRegistryFile,
+    source_path: &str,
+    fingerprint: &str,
+) {
+    if let Some(entry) = id_registry
+        .entries
+        .iter_mut()
+        .find(|entry| entry.last_known_path == source_path)
+    {
+        entry.input_fingerprint = fingerprint.to_string();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_temp_path;
+
+    #[test]
+    fn test_hash_rgba8_bytes_deterministic() {
+        let bytes_a = vec![0u8, 1, 2, 3, 4, 5, 6, 7];
+        let bytes_b = vec![10u8, 20, 30, 40, 50, 60, 70, 80];
+
+        let hash_a1 = hash_rgba8_bytes(&bytes_a);
+        let hash_a2 = hash_rgba8_bytes(&bytes_a);
+        let hash_b = hash_rgba8_bytes(&bytes_b);
+
+        // Same input produces same hash
+        assert_eq!(hash_a1, hash_a2);
+
+        // Different input produces different hash
+        assert_ne!(hash_a1, hash_b);
+
+        // Valid hex string: lowercase, 64 chars for SHA-256
+        assert_eq!(hash_a1.len(), 64);
+        assert!(hash_a1.chars().all(|c| c.is_ascii_hexdigit()));
+        assert_eq!(hash_a1, hash_a1.to_lowercase());
+    }
+
+    #[test]
+    fn test_id_registry_path_for() {
+        let result = id_registry_path_for(Path::new("atlas.json"));
+        assert_eq!(result, PathBuf::from("atlas.ids.json"));
+    }
+
+    #[test]
+    fn test_load_id_registry_missing_file() {
+        let path = test_temp_path("nonexistent_registry");
+        // Ensure it doesn't exist
+        let _ = fs::remove_file(&path);
+
+        let result = load_id_registry(&path);
+        assert!(result.is_ok());
+        let registry = result.unwrap();
+        assert!(registry.entries.is_empty());
+    }
+
+    #[test]
+    fn test_load_id_registry_valid_file() {
+        let path = test_temp_path("valid_registry");
+        let registry = IdRegistryFile {
+            entries: vec![
+                IdRegistryEntry {
+                    sprite_id: "id-aaa".to_string(),
+                    source_hash: "hash-aaa".to_string(),
+                    last_known_path: "sprites/hero.png".to_string(),
+                    input_fingerprint: String::new(),
+                },
+                IdRegistryEntry {
+                    sprite_id: "id-bbb".to_string(),
+                    source_hash: "hash-bbb".to_string(),
+                    last_known_path: "sprites/enemy.png".to_string(),
+                    input_fingerprint: String::new(),
+                },
+            ],
+        };
+        let json = serde_json::to_string_pretty(&registry).expect("serialize");
+        fs::write(&path, json).expect("write");
+
+        let loaded = load_id_registry(&path).expect("load");
+        assert_eq!(loaded.entries.len(), 2);
+        assert_eq!(loaded.entries[0].sprite_id, "id-aaa");
+        assert_eq!(loaded.entries[0].source_hash, "hash-aaa");
+        assert_eq!(loaded.entries[0].last_known_path, "sprites/hero.png");
+        assert_eq!(loaded.entries[1].sprite_id, "id-bbb");
+        assert_eq!(loaded.entries[1].source_hash, "hash-bbb");
+        assert_eq!(loaded.entries[1].last_known_path, "sprites/enemy.png");
+
+        // Cleanup
+        let _ = fs::remove_file(&path);
+    }
+
+    #[test]
+    fn test_resolve_sprite_id_by_path() {
+        let mut registry = IdRegistryFile {
+            entries: vec![IdRegistryEntry {
+                sprite_id: "existing-id-123".to_string(),
+                source_hash: "old_hash".to_string(),
+                last_known_path: "a.png".to_string(),
+                input_fingerprint: String::new(),
+            }],
+        };
+
+        let result =
+            resolve_or_assign_sprite_id(&mut registry, "a.png", "some_hash", &HashSet::new());
+        assert_eq!(result, "existing-id-123");
+    }
+
+    #[test]
+    fn test_resolve_sprite_id_by_hash() {
+        let mut registry = IdRegistryFile {
+            entries: vec![IdRegistryEntry {
+                sprite_id: "hash-matched-id".to_string(),
+                source_hash: "abc123".to_string(),
+                last_known_path: "old.png".to_string(),
+                input_fingerprint: String::new(),
+            }],
+        };
+
+        let result =
+            resolve_or_assign_sprite_id(&mut registry, "renamed.png", "abc123", &HashSet::new());
+        assert_eq!(result, "hash-matched-id");
+
+        // Verify last_known_path was updated
+        assert_eq!(registry.entries[0].last_known_path, "renamed.png");
+    }
+
+    #[test]
+    fn test_resolve_sprite_id_keeps_identical_live_images_apart() {
+        let mut registry = IdRegistryFile {
+            entries: vec![IdRegistryEntry {
+                sprite_id: "frame-1-id".to_string(),
+                source_hash: "same".to_string(),
+                last_known_path: "frame_1.png".to_string(),
+                input_fingerprint: String::new(),
+            }],
+        };
+        let live = HashSet::from(["frame_1.png", "frame_2.png"]);
+
+        let result = resolve_or_assign_sprite_id(&mut registry, "frame_2.png", "same", &live);
+        assert_ne!(result, "frame-1-id");
+        assert_eq!(registry.entries[0].last_known_path, "frame_1.png");
+    }
+
+    #[test]
+    fn test_resolve_sprite_id_new_assignment() {
+        let mut registry = IdRegistryFile { entries: vec![] };
+
+        let result = resolve_or_assign_sprite_id(
+            &mut registry,
+            "brand_new.png",
+            "new_hash_xyz",
+            &HashSet::new(),
+        );
+
+        // Should return a non-empty string (UUID)
+        assert!(!result.is_empty());
+        // UUID format: 8-4-4-4-12 hex chars with dashes = 36 chars
+        assert_eq!(result.len(), 36);
+        assert!(result.contains('-'));
+
+        // Should have added an entry
+        assert_eq!(registry.entries.len(), 1);
+        assert_eq!(registry.entries[0].sprite_id, result);
+        assert_eq!(registry.entries[0].source_hash, "new_hash_xyz");
+        assert_eq!(registry.entries[0].last_known_path, "brand_new.png");
+    }
+
+    #[test]
+    fn test_resolve_sprite_id_updates_hash_on_path_match() {
+        let mut registry = IdRegistryFile {
+            entries: vec![IdRegistryEntry {
+                sprite_id: "stable-id-456".to_string(),
+                source_hash: "old_hash".to_string(),
+                last_known_path: "a.png".to_string(),
+                input_fingerprint: String::new(),
+            }],
+        };
+
+        let result =
+            resolve_or_assign_sprite_id(&mut registry, "a.png", "new_hash", &HashSet::new());
+
+        // Should return the same sprite_id
+        assert_eq!(result, "stable-id-456");
+
+        // source_hash should be updated
+        assert_eq!(registry.entries[0].source_hash, "new_hash");
+    }
+}
@@ -0,0 +1,195 @@
+//! Finding source PNGs to pack.
+//!
+//! By default only the top level of the input directory is scanned, as the
+//! packer always did. With `recursive` set, subdirectories are walked too and
+//! sprite names are namespaced by their path below the input directory:
+//! `characters/hero.png` becomes `characters/hero`. Include and exclude globs
+//! are matched against that same relative path (`*` stays within one
+//! directory, `**` crosses directories).
+
+use glob::{MatchOptions, Pattern};
+use std::fs;
+use std::path::{Path, PathBuf};
+
+const GLOB_OPTIONS: MatchOptions = MatchOptions {
+    case_sensitive: true,
+    require_literal_separator: true,
+    require_literal_leading_dot: false,
+};
+
+/// One source image and the sprite name it packs under.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PackInput {
+    pub source_path: PathBuf,
+    pub name: String,
+}
+
+impl PackInput {
+    /// Input named after the file stem, e.g. `hero.png` -> `hero`.
+    pub fn from_path(source_path: impl Into<PathBuf>) -> Self {
+        let source_path = source_path.into();
+        let name = source_path
+            .file_stem()
+            .and_then(|s| s.to_str())
+            .unwrap_or("sprite")
+            .to_string();
+        Self { source_path, name }
+    }
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct InputFilter {
+    /// Walk subdirectories and namespace names by relative path.
+    pub recursive: bool,
+    /// Keep only files matching at least one of these; empty keeps all.
+    pub include: Vec<String>,
+    /// Drop files matching any of these.
+    pub exclude: Vec<String>,
+}
+
+/// The `.png` files directly inside `dir`, sorted for a stable layout.
+pub fn collect_png_inputs(dir: &Path) -> Result<Vec<PackInput>, String> {
+    collect_inputs(dir, &InputFilter::default())
+}
+
+/// The `.png` files under `dir` that pass `filter`, sorted by relative path.
+pub fn collect_inputs(dir: &Path, filter: &InputFilter) -> Result<Vec<PackInput>, String> {
+    let include = compile_patterns(&filter.include)?;
+    let exclude = compile_patterns(&filter.exclude)?;
+
+    let mut files = Vec::new();
+    walk_pngs(dir, filter.recursive, &mut files)?;
+
+    let mut inputs: Vec<(String, PackInput)> = Vec::new();
+    for source_path in files {
+        let relative = source_path
+            .strip_prefix(dir)
+            .unwrap_or(&source_path)
+            .to_string_lossy()
+            .replace('\\', "/");
+        if !include.is_empty()
+            && !include
+                .iter()
+                .any(|p| p.matches_with(&relative, GLOB_OPTIONS))
+        {
+            continue;
+        }
+        if exclude
+            .iter()
+            .any(|p| p.matches_with(&relative, GLOB_OPTIONS))
+        {
+            continue;
+        }
+        let name = relative
+            .strip_suffix(".png")
+            .unwrap_or(&relative)
+            .to_string();
+        inputs.push((relative, PackInput { source_path, name }));
+    }
+    inputs.sort_by(|a, b| a.0.cmp(&b.0));
+
+    if inputs.is_empty() {
+        return Err(format!(
+            "No .png files found in input directory '{}'",
+            dir.display()
+        ));
+    }
+    Ok(inputs.into_iter().map(|(_, input)| input).collect())
+}
+
+fn compile_patterns(patterns: &[String]) -> Result<Vec<Pattern>, String> {
+    patterns
+        .iter()
+        .map(|pattern| {
+            Pattern::new(pattern).map_err(|e| format!("Invalid glob pattern '{pattern}': {e}"))
+        })
+        .collect()
+}
+
+fn walk_pngs(dir: &Path, recursive: bool, out: &mut Vec<PathBuf>) -> Result<(), String> {
+    let entries = fs::read_dir(dir)
+        .map_err(|e| format!("Failed to read input dir '{}': {e}", dir.display()))?;
+    for entry in entries.filter_map(|entry| entry.ok()) {
+        let path = entry.path();
+        if path.is_dir() {
+            if recursive {
+                walk_pngs(&path, recursive, out)?;
+            }
+        } else if path.extension().and_then(|e| e.to_str()) == Some("png") {
+            out.push(path);
+        }
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_temp_path;
+
+    fn touch(root: &Path, relative: &str) {
+        let path = root.join(relative);
+        fs::create_dir_all(path.parent().expect("parent")).expect("create dir");
+        fs::write(path, b"").expect("write");
+    }
+
+    fn names(inputs: &[PackInput]) -> Vec<&str> {
+        inputs.iter().map(|input| input.name.as_str()).collect()
+    }
+
+    #[test]
+    fn test_collect_inputs_recurses_filters_and_namespaces() {
+        let root = test_temp_path("inputs_tree");
+        touch(&root, "logo.png");
+        touch(&root, "notes.txt");
+        touch(&root, "characters/hero.png");
+        touch(&root, "characters/hero_wip.png");
+        touch(&root, "characters/npc/baker.png");
+        touch(&root, "tiles/grass.png");
+
+        let top_only = collect_png_inputs(&root).expect("top level");
+        assert_eq!(names(&top_only), ["logo"]);
+
+        let mut filter = InputFilter {
+            recursive: true,
+            ..InputFilter::default()
+        };
+        let all = collect_inputs(&root, &filter).expect("recursive");
+        assert_eq!(
+            names(&all),
+            [
+                "characters/hero",
+                "characters/hero_wip",
+                "characters/npc/baker",
+                "logo",
+                "tiles/grass"
+            ]
+        );
+
+        filter.include = vec!["characters/**/*.png".to_string()];
+        filter.exclude = vec!["**/*_wip.png".to_string()];
+        let filtered = collect_inputs(&root, &filter).expect("filtered");
+        assert_eq!(
+            names(&filtered),
+            ["characters/hero", "characters/npc/baker"]
+        );
+
+        // `*` does not cross directories.
+        filter.include = vec!["*.png".to_string()];
+        filter.exclude.clear();
+        let shallow = collect_inputs(&root, &filter).expect("shallow glob");
+        assert_eq!(names(&shallow), ["logo"]);
+
+        let _ = fs::remove_dir_all(&root);
+    }
+
+    #[test]
+    fn test_collect_inputs_rejects_bad_pattern() {
+        let filter = InputFilter {
+            include: vec!["[".to_string()],
+            ..InputFilter::default()
+        };
+        let err = collect_inputs(Path::new("."), &filter).expect_err("bad glob");
+        assert!(err.contains("Invalid glob pattern"), "{err}");
+    }
+}
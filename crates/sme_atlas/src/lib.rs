@@ -0,0 +1,1106 @@
+//! Sprite atlas packing as a library.
+//!
+//! `sme_atlas_packer` is a thin CLI over this crate; tools that need an atlas
+//! (the editor, build scripts) call it in-process instead of shelling out:
+//!
+//! ```no_run
+//! use sme_atlas::{collect_png_inputs, pack, PackOptions};
+//! use std::path::Path;
+//!
+//! let inputs = collect_png_inputs(Path::new("assets/textures"))?;
+//! let options = PackOptions::new("assets/generated/atlas.png", "assets/generated/atlas.json");
+//! let result = pack(&inputs, &options)?;
+//! result.write()?;
+//! # Ok::<(), String>(())
+//! ```
+//!
+//! `pack` does everything in memory: it applies each source's sidecar meta
+//! (pivot, nine-slice, trim; see `sidecar`), shelf-packs the sources into one
+//! texture, resolves stable sprite IDs against the registry stored next to
+//! the metadata output, and builds the metadata. Nothing touches disk until
+//! `PackResult::write`, which promotes the PNG, metadata, and registry
+//! together (see `output`).
+//!
+//! Repacks are incremental by default: the registry remembers a fingerprint of
+//! each input, so unchanged sprites are neither decoded nor re-placed, and a
+//! repack with no changes writes nothing.
+//!
+//! Sources with identical pixels (exported animations often repeat frames)
+//! are packed once: each keeps its own sprite entry and ID, and they all
+//! point at the same rect.
+//!
+//! With `PackOptions::half_res` the atlas is also written at half resolution
+//! (`<stem>@1x.png` beside the full-size "@2x" texture) for low fidelity
+//! tiers. Sprites keep one set of UVs and their design-unit sizes; only the
+//! texture the engine samples changes.
+//!
+//! Sheets that are already laid out on a grid need no packing at all;
+//! `slice_sheet` describes their cells in place (see `slice`). Illustrations
+//! too large for any atlas go the other way: `split_tiles` cuts them into
+//! tiles the engine streams in around the camera (see `tiles`).
+//!
+//! `usage_report` and `annotate` show how well an atlas is used (see
+//! `report`).
+
+pub mod id_registry;
+pub mod inputs;
+pub mod metadata;
+pub mod output;
+pub mod report;
+pub mod sidecar;
+pub mod slice;
+pub mod tiles;
+
+use image::RgbaImage;
+use std::collections::{HashMap, HashSet};
+use std::fs;
+use std::path::{Path, PathBuf};
+
+pub use inputs::{collect_inputs, collect_png_inputs, InputFilter, PackInput};
+pub use report::{annotate, usage_report, write_usage_report, UsageReport};
+pub use slice::{slice_sheet, SliceGrid, SliceResult};
+pub use tiles::{split_tiles, TileResult};
+
+use id_registry::{
+    hash_rgba8_bytes, id_registry_path_for, input_fingerprint_matches, load_id_registry,
+    record_input_fingerprint, resolve_or_assign_sprite_id, seed_registry_from_existing_metadata,
+    IdRegistryFile,
+};
+use metadata::{
+    AtlasMetadata, AtlasNineSlice, AtlasPivot, AtlasRectPx, AtlasSprite, AtlasTexture,
+    AtlasTextureFile, AtlasTrim, AtlasUvRect, ATLAS_FORMAT_VERSION,
+};
+use output::{normalize_path_for_json, promote_outputs_transactional, temporary_output_path};
+use sidecar::{SidecarResolver, SpriteMeta};
+
+pub const DEFAULT_ATLAS_SIZE: u32 = 512;
+pub const DEFAULT_PADDING: u32 = 1;
+
+#[derive(Debug, Clone)]
+pub struct PackOptions {
+    /// Where the atlas texture goes; also recorded in the metadata.
+    pub atlas_png_output: PathBuf,
+    /// Where the metadata goes. Its file stem is the `atlas_id`, and the ID
+    /// registry lives next to it.
+    pub atlas_json_output: PathBuf,
+    /// Width and height of the square atlas texture.
+    pub atlas_size: u32,
+    /// Transparent pixels kept between sprites.
+    pub padding: u32,
+    /// Reuse unchanged sprites from the previous pack into the same outputs
+    /// instead of decoding and re-encoding everything.
+    pub incremental: bool,
+    /// Also write a half-resolution copy of the atlas (see
+    /// [`half_res_path_for`]). Needs an even `atlas_size`.
+    pub half_res: bool,
+    /// Store color multiplied by alpha, so filtering never bleeds the color
+    /// of transparent pixels into sprite edges.
+    pub premultiply_alpha: bool,
+    /// Store sprites taller than they are wide turned 90° clockwise, so
+    /// shelves stay short. Nine-slice sprites are never turned.
+    pub allow_rotation: bool,
+}
+
+impl PackOptions {
+    pub fn new(
+        atlas_png_output: impl Into<PathBuf>,
+        atlas_json_output: impl Into<PathBuf>,
+    ) -> Self {
+        Self {
+            atlas_png_output: atlas_png_output.into(),
+            atlas_json_output: atlas_json_output.into(),
+            atlas_size: DEFAULT_ATLAS_SIZE,
+            padding: DEFAULT_PADDING,
+            incremental: true,
+            half_res: false,
+            premultiply_alpha: false,
+            allow_rotation: false,
+        }
+    }
+}
+
+/// Where the half-resolution copy of `atlas_png` goes: `atlas@1x.png` next
+/// to `atlas.png`.
+pub fn half_res_path_for(atlas_png: &Path) -> PathBuf {
+    let stem = atlas_png
+        .file_stem()
+        .and_then(|s| s.to_str())
+        .unwrap_or("atlas");
+    let file_name = match atlas_png.extension().and_then(|s| s.to_str()) {
+        Some(ext) => format!("{stem}@1x.{ext}"),
+        None => format!("{stem}@1x"),
+    };
+    atlas_png.with_file_name(file_name)
+}
+
+/// A packed atlas, not yet written to disk.
+#[derive(Debug, Clone)]
+pub struct PackResult {
+    /// The packed texture, or `None` when every input matched the previous
+    /// pack and the atlas already on disk is current.
+    pub image: Option<RgbaImage>,
+    pub metadata: AtlasMetadata,
+    /// Registry updated with any newly assigned IDs.
+    pub id_registry: IdRegistryFile,
+    /// Sprites carried over from the previous pack without being decoded.
+    pub reused: usize,
+    /// Sprites whose pixels were already in the atlas under another source.
+    pub duplicates: usize,
+    atlas_png_output: PathBuf,
+    atlas_json_output: PathBuf,
+}
+
+/// A source decoded, checked against its sidecar meta, and trimmed.
+struct PreparedSprite {
+    image: RgbaImage,
+    source_hash: String,
+    pivot: AtlasPivot,
+    nine_slice: Option<AtlasNineSlice>,
+    trim: Option<AtlasTrim>,
+    rotated: bool,
+}
+
+/// One input on its way into the atlas: either carried over from the
+/// previous pack (`prior`) or freshly decoded (`prepared`).
+struct Slot {
+    source_path: String,
+    fingerprint: String,
+    meta: SpriteMeta,
+    prior: Option<AtlasSprite>,
+    prepared: Option<PreparedSprite>,
+}
+
+impl Slot {
+    fn size(&self) -> (u32, u32) {
+        match (&self.prepared, &self.prior) {
+            (Some(prepared), _) => prepared.image.dimensions(),
+            (None, Some(prior)) => (prior.rect_px.w, prior.rect_px.h),
+            (None, None) => unreachable!("slot has neither a prior sprite nor an image"),
+        }
+    }
+
+    /// Identifies the pixels this slot puts in the atlas: decoded images by
+    /// content, sprites carried over from the previous pack by the rect they
+    /// occupied, which their copies shared.
+    fn pixel_key(&self) -> String {
+        match (&self.prepared, &self.prior) {
+            (Some(prepared), _) => {
+                let (w, h) = prepared.image.dimensions();
+                let hash = hash_rgba8_bytes(prepared.image.as_raw());
+                format!("{w}x{h}:{}:{hash}", prepared.rotated)
+            }
+            (None, Some(prior)) => {
+                let rect = prior.rect_px;
+                format!("rect:{},{},{}x{}", rect.x, rect.y, rect.w, rect.h)
+            }
+            (None, None) => unreachable!("slot has neither a prior sprite nor an image"),
+        }
+    }
+}
+
+/// Pack `inputs`, in order, into a single atlas.
+///
+/// With `options.incremental`, inputs whose fingerprint (file bytes plus
+/// effective sidecar meta) matches the ID registry are not decoded again.
+/// When the resulting layout puts every unchanged sprite where the previous
+/// pack did, the previous atlas PNG is patched in place of a full rebuild, and
+/// when nothing changed at all no image is produced.
+pub fn pack(inputs: &[PackInput], options: &PackOptions) -> Result<PackResult, String> {
+    let atlas_size = options.atlas_size;
+    let padding = options.padding;
+    if atlas_size == 0 {
+        return Err("atlas_size must be > 0".to_string());
+    }
+    if options.half_res && !atlas_size.is_multiple_of(2) {
+        return Err(format!(
+            "atlas_size must be even for a half-res atlas, got {atlas_size}"
+        ));
+    }
+    if inputs.is_empty() {
+        return Err("No input sprites to pack".to_string());
+    }
+
+    let mut id_registry = load_id_registry(&id_registry_path_for(&options.atlas_json_output))?;
+    if id_registry.entries.is_empty() {
+        seed_registry_from_existing_metadata(&options.atlas_json_output, &mut id_registry)?;
+    }
+    let previous = if options.incremental {
+        load_previous_pack(options)
+    } else {
+        None
+    };
+
+    let mut sidecars = SidecarResolver::new();
+    let mut slots = Vec::with_capacity(inputs.len());
+    for (index, input) in inputs.iter().enumerate() {
+        let meta = sidecars.resolve(&input.source_path)?;
+        let fingerprint = input_fingerprint(&input.source_path, &meta)?;
+        let source_path = normalize_path_for_json(&input.source_path);
+        let prior = previous
+            .as_ref()
+            .and_then(|metadata| metadata.sprites.get(index))
+            .filter(|sprite| sprite.source_path == source_path && sprite.name == input.name)
+            .filter(|sprite| sprite.rotated == turns_prior_sprite(sprite, options))
+            .filter(|_| input_fingerprint_matches(&id_registry, &source_path, &fingerprint))
+            .cloned();
+        let prepared = match prior {
+            Some(_) => None,
+            None => Some(prepare_sprite(&input.source_path, &meta, options)?),
+        };
+        slots.push(Slot {
+            source_path,
+            fingerprint,
+            meta,
+            prior,
+            prepared,
+        });
+    }
+
+    // Identical images are laid out once; copies share the first one's spot.
+    let mut first_with_key = HashMap::new();
+    let copy_of: Vec<Option<usize>> = slots
+        .iter()
+        .enumerate()
+        .map(|(index, slot)| {
+            let first = *first_with_key.entry(slot.pixel_key()).or_insert(index);
+            (first != index).then_some(first)
+        })
+        .collect();
+    let items: Vec<(&Path, (u32, u32))> = slots
+        .iter()
+        .zip(inputs)
+        .zip(&copy_of)
+        .filter(|(_, copy)| copy.is_none())
+        .map(|((slot, input), _)| (input.source_path.as_path(), slot.size()))
+        .collect();
+    let mut unique_positions = shelf_layout(&items, atlas_size, padding)?.into_iter();
+    let mut positions: Vec<(u32, u32)> = Vec::with_capacity(slots.len());
+    for copy in &copy_of {
+        let position = match copy {
+            Some(first) => positions[*first],
+            None => unique_positions
+                .next()
+                .expect("one position per unique image"),
+        };
+        positions.push(position);
+    }
+    let duplicates = copy_of.iter().filter(|copy| copy.is_some()).count();
+    let layout_unchanged = previous
+        .as_ref()
+        .is_some_and(|metadata| metadata.sprites.len() == slots.len())
+        && slots.iter().zip(&positions).all(|(slot, &(x, y))| {
+            slot.prior
+                .as_ref()
+                .is_none_or(|prior| (prior.rect_px.x, prior.rect_px.y) == (x, y))
+        });
+
+    let mut reused = 0;
+    let image = if layout_unchanged {
+        reused = slots.iter().filter(|slot| slot.prior.is_some()).count();
+        if reused == slots.len() {
+            None
+        } else {
+            let mut atlas = image::open(&options.atlas_png_output)
+                .map_err(|e| {
+                    format!(
+                        "Failed to open previous atlas '{}': {e}",
+                        options.atlas_png_output.display()
+                    )
+                })?
+                .to_rgba8();
+            // A changed sprite may have shrunk: clear what it covered before,
+            // gutter included, unless a carried-over copy still draws there.
+            let kept: HashSet<AtlasRectPx> = slots
+                .iter()
+                .filter_map(|slot| slot.prior.as_ref().map(|prior| prior.rect_px))
+                .collect();
+            let previous_sprites = previous.as_ref().map_or(&[][..], |m| &m.sprites[..]);
+            for (slot, old) in slots.iter().zip(previous_sprites) {
+                if slot.prepared.is_some() && !kept.contains(&old.rect_px) {
+                    clear_rect(&mut atlas, old.rect_px, padding);
+                }
+            }
+            for (slot, &(x, y)) in slots.iter().zip(&positions) {
+                if let Some(prepared) = &slot.prepared {
+                    image::imageops::replace(&mut atlas, &prepared.image, x as i64, y as i64);
+                }
+            }
+            Some(atlas)
+        }
+    } else {
+        let mut atlas = RgbaImage::new(atlas_size, atlas_size);
+        for ((slot, input), &(x, y)) in slots.iter_mut().zip(inputs).zip(&positions) {
+            if slot.prepared.is_none() {
+                slot.prior = None;
+                slot.prepared = Some(prepare_sprite(&input.source_path, &slot.meta, options)?);
+            }
+            let prepared = slot.prepared.as_ref().expect("prepared above");
+            image::imageops::replace(&mut atlas, &prepared.image, x as i64, y as i64);
+        }
+        Some(atlas)
+    };
+
+    let live_paths: HashSet<&str> = slots.iter().map(|slot| slot.source_path.as_str()).collect();
+    let mut assigned = Vec::with_capacity(slots.len());
+    for slot in &slots {
+        if let Some(prepared) = &slot.prepared {
+            let sprite_id = resolve_or_assign_sprite_id(
+                &mut id_registry,
+                &slot.source_path,
+                &prepared.source_hash,
+                &live_paths,
+            );
+            record_input_fingerprint(&mut id_registry, &slot.source_path, &slot.fingerprint);
+            assigned.push(Some(sprite_id));
+        } else {
+            assigned.push(None);
+        }
+    }
+    let mut sprites = Vec::with_capacity(slots.len());
+    for (((slot, input), &(x, y)), sprite_id) in
+        slots.into_iter().zip(inputs).zip(&positions).zip(assigned)
+    {
+        let Some(prepared) = slot.prepared else {
+            sprites.push(slot.prior.expect("unprepared slots carry a prior sprite"));
+            continue;
+        };
+        let (w, h) = prepared.image.dimensions();
+        sprites.push(AtlasSprite {
+            sprite_id: sprite_id.expect("prepared slots are assigned an id"),
+            name: input.name.clone(),
+            source_path: slot.source_path,
+            rect_px: AtlasRectPx { x, y, w, h },
+            uv: AtlasUvRect {
+                u0: x as f32 / atlas_size as f32,
+                v0: y as f32 / atlas_size as f32,
+                u1: (x + w) as f32 / atlas_size as f32,
+                v1: (y + h) as f32 / atlas_size as f32,
+            },
+            pivot: prepared.pivot,
+            nine_slice: prepared.nine_slice,
+            trim: prepared.trim,
+            rotated: prepared.rotated,
+        });
+    }
+
+    let atlas_id = options
+        .atlas_json_output
+        .file_stem()
+        .and_then(|s| s.to_str())
+        .unwrap_or("atlas")
+        .to_string();
+    let metadata = AtlasMetadata {
+        version: ATLAS_FORMAT_VERSION.to_string(),
+        atlas_id,
+        texture: AtlasTexture {
+            path: normalize_path_for_json(&options.atlas_png_output),
+            width: atlas_size,
+            height: atlas_size,
+            half_res: half_res_texture(options),
+            premultiplied_alpha: options.premultiply_alpha,
+        },
+        sprites,
+    };
+
+    Ok(PackResult {
+        image,
+        metadata,
+        id_registry,
+        reused,
+        duplicates,
+        atlas_png_output: options.atlas_png_output.clone(),
+        atlas_json_output: options.atlas_json_output.clone(),
+    })
+}
+
+/// Make `rect` and `padding` pixels around it transparent.
+fn clear_rect(atlas: &mut RgbaImage, rect: AtlasRectPx, padding: u32) {
+    let x0 = rect.x.saturating_sub(padding);
+    let y0 = rect.y.saturating_sub(padding);
+    let x1 = (rect.x + rect.w + padding).min(atlas.width());
+    let y1 = (rect.y + rect.h + padding).min(atlas.height());
+    for y in y0..y1 {
+        for x in x0..x1 {
+            atlas.put_pixel(x, y, image::Rgba([0, 0, 0, 0]));
+        }
+    }
+}
+
+/// Metadata of the previous pack into the same outputs, if it can be built
+/// upon: both outputs exist and the texture path, size, half-res copy, and
+/// alpha convention are unchanged.
+fn load_previous_pack(options: &PackOptions) -> Option<AtlasMetadata> {
+    if !options.atlas_png_output.exists() {
+        return None;
+    }
+    let raw = fs::read_to_string(&options.atlas_json_output).ok()?;
+    let metadata: AtlasMetadata = serde_json::from_str(&raw).ok()?;
+    let texture = &metadata.texture;
+    (texture.path == normalize_path_for_json(&options.atlas_png_output)
+        && texture.width == options.atlas_size
+        && texture.height == options.atlas_size
+        && texture.half_res == half_res_texture(options)
+        && texture.premultiplied_alpha == options.premultiply_alpha)
+        .then_some(metadata)
+}
+
+/// The half-res texture entry `options` asks for, if any.
+fn half_res_texture(options: &PackOptions) -> Option<AtlasTextureFile> {
+    options.half_res.then(|| AtlasTextureFile {
+        path: normalize_path_for_json(&half_res_path_for(&options.atlas_png_output)),
+        width: options.atlas_size / 2,
+        height: options.atlas_size / 2,
+    })
+}
+
+/// Halve `image` by averaging each 2x2 block. Straight colour is weighted by
+/// alpha so transparent padding doesn't darken sprite edges; premultiplied
+/// colour already is. With at least one pixel of padding no block spans two
+/// sprites.
+fn downscale_half(image: &RgbaImage, premultiplied: bool) -> RgbaImage {
+    let (w, h) = image.dimensions();
+    RgbaImage::from_fn(w / 2, h / 2, |x, y| {
+        let mut rgb = [0u32; 3];
+        let mut alpha = 0u32;
+        for (dx, dy) in [(0, 0), (1, 0), (0, 1), (1, 1)] {
+            let [r, g, b, a] = image.get_pixel(x * 2 + dx, y * 2 + dy).0;
+            let a = a as u32;
+            let weight = if premultiplied { 1 } else { a };
+            rgb[0] += r as u32 * weight;
+            rgb[1] += g as u32 * weight;
+            rgb[2] += b as u32 * weight;
+            alpha += a;
+        }
+        if alpha == 0 {
+            return image::Rgba([0, 0, 0, 0]);
+        }
+        let total = if premultiplied { 4 } else { alpha };
+        let channel = |sum: u32| ((sum + total / 2) / total) as u8;
+        image::Rgba([
+            channel(rgb[0]),
+            channel(rgb[1]),
+            channel(rgb[2]),
+            ((alpha + 2) / 4) as u8,
+        ])
+    })
+}
+
+/// Multiply colour by alpha. sRGB values are decoded first, so the product
+/// is the one the GPU's sRGB sampling expects.
+fn premultiply_srgb(image: &mut RgbaImage) {
+    let to_linear = |c: f32| {
+        if c <= 0.04045 {
+            c / 12.92
+        } else {
+            ((c + 0.055) / 1.055).powf(2.4)
+        }
+    };
+    let to_srgb = |c: f32| {
+        if c <= 0.003_130_8 {
+            c * 12.92
+        } else {
+            1.055 * c.powf(1.0 / 2.4) - 0.055
+        }
+    };
+    for pixel in image.pixels_mut() {
+        let alpha = pixel.0[3] as f32 / 255.0;
+        if alpha == 1.0 {
+            continue;
+        }
+        for channel in &mut pixel.0[..3] {
+            let linear = to_linear(*channel as f32 / 255.0) * alpha;
+            *channel = (to_srgb(linear) * 255.0).round() as u8;
+        }
+    }
+}
+
+/// Hash of everything that shapes a sprite's output: the encoded source file
+/// and its effective sidecar meta. Cheap compared to decoding the PNG.
+fn input_fingerprint(source_path: &Path, meta: &SpriteMeta) -> Result<String, String> {
+    let mut bytes = fs::read(source_path)
+        .map_err(|e| format!("Failed to read '{}': {e}", source_path.display()))?;
+    let meta_json =
+        serde_json::to_vec(meta).map_err(|e| format!("Failed to serialize sprite meta: {e}"))?;
+    bytes.extend_from_slice(&meta_json);
+    Ok(hash_rgba8_bytes(&bytes))
+}
+
+fn prepare_sprite(
+    source_path: &Path,
+    meta: &SpriteMeta,
+    options: &PackOptions,
+) -> Result<PreparedSprite, String> {
+    let source = image::open(source_path)
+        .map_err(|e| format!("Failed to open '{}': {e}", source_path.display()))?
+        .to_rgba8();
+    let (source_w, source_h) = source.dimensions();
+    let source_hash = hash_rgba8_bytes(source.as_raw());
+
+    if let Some(nine_slice) = meta.nine_slice {
+        if nine_slice.left + nine_slice.right > source_w
+            || nine_slice.top + nine_slice.bottom > source_h
+        {
+            return Err(format!(
+                "Sprite '{}': nine_slice borders exceed the {}x{} source",
+                source_path.display(),
+                source_w,
+                source_h
+            ));
+        }
+        if meta.trim == Some(true) {
+            return Err(format!(
+                "Sprite '{}': nine_slice sprites cannot be trimmed",
+                source_path.display()
+            ));
+        }
+    }
+    let trim_bounds = if meta.trim == Some(true) {
+        opaque_bounds(&source).filter(|&(_, _, w, h)| (w, h) != (source_w, source_h))
+    } else {
+        None
+    };
+    let mut pivot = meta.pivot.unwrap_or(AtlasPivot { x: 0.5, y: 0.5 });
+    let (image, trim) = match trim_bounds {
+        Some((bx, by, bw, bh)) => {
+            let trim = AtlasTrim {
+                x: bx,
+                y: by,
+                source_w,
+                source_h,
+            };
+            pivot = trimmed_pivot(pivot, &trim, bw, bh);
+            let cropped = image::imageops::crop_imm(&source, bx, by, bw, bh).to_image();
+            (cropped, Some(trim))
+        }
+        None => (source, None),
+    };
+    let mut image = image;
+    if options.premultiply_alpha {
+        premultiply_srgb(&mut image);
+    }
+    let rotated = turns_sprite(options, image.dimensions(), meta.nine_slice.is_some());
+    if rotated {
+        image = image::imageops::rotate90(&image);
+    }
+    Ok(PreparedSprite {
+        image,
+        source_hash,
+        pivot,
+        nine_slice: meta.nine_slice,
+        trim,
+        rotated,
+    })
+}
+
+/// Whether a sprite of upright size `(w, h)` is stored turned.
+fn turns_sprite(options: &PackOptions, (w, h): (u32, u32), nine_slice: bool) -> bool {
+    options.allow_rotation && !nine_slice && h > w
+}
+
+/// Whether `options` would turn a sprite from the previous pack, which can
+/// only be reused if it was stored the same way.
+fn turns_prior_sprite(sprite: &AtlasSprite, options: &PackOptions) -> bool {
+    let (w, h) = (sprite.rect_px.w, sprite.rect_px.h);
+    let upright = if sprite.rotated { (h, w) } else { (w, h) };
+    turns_sprite(options, upright, sprite.nine_slice.is_some())
+}
+
+/// Shelf packing: left to right, wrapping to a new row below the tallest
+/// sprite of the current one. Returns each sprite's top-left corner.
+fn shelf_layout(
+    items: &[(&Path, (u32, u32))],
+    atlas_size: u32,
+    padding: u32,
+) -> Result<Vec<(u32, u32)>, String> {
+    let mut positions = Vec::with_capacity(items.len());
+    let mut x = 0u32;
+    let mut y = 0u32;
+    let mut row_height = 0u32;
+    for &(source_path, (w, h)) in items {
+        if w + padding * 2 > atlas_size || h + padding * 2 > atlas_size {
+            return Err(format!(
+                "Sprite '{}' ({}x{}) does not fit in atlas {}x{}",
+                source_path.display(),
+                w,
+                h,
+                atlas_size,
+                atlas_size
+            ));
+        }
+
+        if x + w + padding > atlas_size {
+            x = 0;
+            y += row_height;
+            row_height = 0;
+        }
+        if y + h + padding > atlas_size {
+            return Err(format!(
+                "Atlas overflow while packing '{}'. Increase atlas_size.",
+                source_path.display()
+            ));
+        }
+        positions.push((x, y));
+
+        x += w + padding;
+        row_height = row_height.max(h + padding);
+    }
+    Ok(positions)
+}
+
+/// Smallest `(x, y, w, h)` covering every pixel with non-zero alpha, or
+/// `None` for a fully transparent image.
+fn opaque_bounds(image: &RgbaImage) -> Option<(u32, u32, u32, u32)> {
+    let mut bounds: Option<(u32, u32, u32, u32)> = None;
+    for (px, py, pixel) in image.enumerate_pixels() {
+        if pixel.0[3] == 0 {
+            continue;
+        }
+        bounds = Some(match bounds {
+            None => (px, py, px, py),
+            Some((x0, y0, x1, y1)) => (x0.min(px), y0.min(py), x1.max(px), y1.max(py)),
+        });
+    }
+    bounds.map(|(x0, y0, x1, y1)| (x0, y0, x1 - x0 + 1, y1 - y0 + 1))
+}
+
+/// Re-express a pivot given against the full source as a fraction of the
+/// trimmed `w`x`h` region, so the sprite lands in the same place on screen.
+/// Pivots are measured from the bottom-left; trim offsets from the top-left.
+fn trimmed_pivot(pivot: AtlasPivot, trim: &AtlasTrim, w: u32, h: u32) -> AtlasPivot {
+    let pivot_px_x = pivot.x * trim.source_w as f32;
+    let pivot_px_y = pivot.y * trim.source_h as f32;
+    let trimmed_bottom = (trim.source_h - trim.y - h) as f32;
+    AtlasPivot {
+        x: (pivot_px_x - trim.x as f32) / w as f32,
+        y: (pivot_px_y - trimmed_bottom) / h as f32,
+    }
+}
+
+impl PackResult {
+    /// Write the atlas PNG (and its half-res copy, if requested), metadata
+    /// JSON, and ID registry, all or nothing.
+    /// Does nothing when the outputs on disk are already current.
+    pub fn write(&self) -> Result<(), String> {
+        let Some(image) = &self.image else {
+            return Ok(());
+        };
+        let atlas_png_output = &self.atlas_png_output;
+        let atlas_json_output = &self.atlas_json_output;
+        if let Some(parent) = atlas_png_output.parent() {
+            fs::create_dir_all(parent).map_err(|e| {
+                format!(
+                    "Failed to create atlas output dir '{}': {e}",
+                    parent.display()
+                )
+            })?;
+        }
+        if let Some(parent) = atlas_json_output.parent() {
+            fs::create_dir_all(parent).map_err(|e| {
+                format!(
+                    "Failed to create atlas metadata output dir '{}': {e}",
+                    parent.display()
+                )
+            })?;
+        }
+
+        let png_tmp = temporary_output_path(atlas_png_output);
+        image
+            .save_with_format(&png_tmp, image::ImageFormat::Png)
+            .map_err(|e| format!("Failed to write '{}': {e}", png_tmp.display()))?;
+
+        let json = serde_json::to_string_pretty(&self.metadata)
+            .map_err(|e| format!("Failed to serialize atlas metadata: {e}"))?;
+        let json_tmp = temporary_output_path(atlas_json_output);
+        fs::write(&json_tmp, json)
+            .map_err(|e| format!("Failed to write '{}': {e}", json_tmp.display()))?;
+        let id_registry_path = id_registry_path_for(atlas_json_output);
+        let id_registry_json = serde_json::to_string_pretty(&self.id_registry).map_err(|e| {
+            format!(
+                "Failed to serialize id registry '{}': {e}",
+                id_registry_path.display()
+            )
+        })?;
+        let id_registry_tmp = temporary_output_path(&id_registry_path);
+        fs::write(&id_registry_tmp, id_registry_json)
+            .map_err(|e| format!("Failed to write '{}': {e}", id_registry_tmp.display()))?;
+
+        let mut outputs = vec![
+            (png_tmp, atlas_png_output.clone()),
+            (json_tmp, atlas_json_output.clone()),
+            (id_registry_tmp, id_registry_path),
+        ];
+        if self.metadata.texture.half_res.is_some() {
+            let half_res_output = half_res_path_for(atlas_png_output);
+            let half_res_tmp = temporary_output_path(&half_res_output);
+            downscale_half(image, self.metadata.texture.premultiplied_alpha)
+                .save_with_format(&half_res_tmp, image::ImageFormat::Png)
+                .map_err(|e| format!("Failed to write '{}': {e}", half_res_tmp.display()))?;
+            outputs.push((half_res_tmp, half_res_output));
+        }
+        let outputs: Vec<(&Path, &Path)> = outputs
+            .iter()
+            .map(|(tmp, output)| (tmp.as_path(), output.as_path()))
+            .collect();
+        promote_outputs_transactional(&outputs)
+    }
+
+    pub fn atlas_png_output(&self) -> &Path {
+        &self.atlas_png_output
+    }
+
+    pub fn atlas_json_output(&self) -> &Path {
+        &self.atlas_json_output
+    }
+}
+
+#[cfg(test)]
+pub(crate) fn test_temp_path(hint: &str) -> PathBuf {
+    use std::time::{SystemTime, UNIX_EPOCH};
+    let nanos = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .expect("time")
+        .as_nanos();
+    std::env::temp_dir().join(format!("sme_atlas_test_{}_{}.tmp", hint, nanos))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn write_sprite(dir: &Path, name: &str, w: u32, h: u32, rgba: [u8; 4]) -> PathBuf {
+        let path = dir.join(name);
+        RgbaImage::from_pixel(w, h, image::Rgba(rgba))
+            .save(&path)
+            .expect("write sprite");
+        path
+    }
+
+    #[test]
+    fn test_pack_lays_out_rows_and_keeps_ids_across_repacks() {
+        let dir = test_temp_path("pack_dir");
+        fs::create_dir_all(&dir).expect("create dir");
+        write_sprite(&dir, "a.png", 10, 8, [255, 0, 0, 255]);
+        write_sprite(&dir, "b.png", 10, 6, [0, 255, 0, 255]);
+        write_sprite(&dir, "c.png", 4, 4, [0, 0, 255, 255]);
+
+        let inputs = collect_png_inputs(&dir).expect("inputs");
+        let mut options = PackOptions::new(dir.join("out/atlas.png"), dir.join("out/atlas.json"));
+        options.atlas_size = 24;
+        let result = pack(&inputs, &options).expect("pack");
+
+        let rects: Vec<AtlasRectPx> = result
+            .metadata
+            .sprites
+            .iter()
+            .map(|sprite| sprite.rect_px)
+            .collect();
+        assert_eq!(
+            rects[0],
+            AtlasRectPx {
+                x: 0,
+                y: 0,
+                w: 10,
+                h: 8
+            }
+        );
+        assert_eq!(
+            rects[1],
+            AtlasRectPx {
+                x: 11,
+                y: 0,
+                w: 10,
+                h: 6
+            }
+        );
+        // Third sprite doesn't fit the first row and wraps below its tallest entry.
+        assert_eq!(
+            rects[2],
+            AtlasRectPx {
+                x: 0,
+                y: 9,
+                w: 4,
+                h: 4
+            }
+        );
+        assert_eq!(result.metadata.atlas_id, "atlas");
+        let image = result.image.as_ref().expect("fresh pack has an image");
+        assert_eq!(image.get_pixel(11, 0).0, [0, 255, 0, 255]);
+
+        result.write().expect("write");
+        assert!(options.atlas_png_output.exists());
+        assert!(id_registry_path_for(&options.atlas_json_output).exists());
+
+        let repacked = pack(&inputs, &options).expect("repack");
+        let ids = |r: &PackResult| -> Vec<String> {
+            r.metadata
+                .sprites
+                .iter()
+                .map(|sprite| sprite.sprite_id.clone())
+                .collect()
+        };
+        assert_eq!(ids(&result), ids(&repacked));
+
+        let _ = fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn test_incremental_repack_reuses_unchanged_sprites() {
+        let dir = test_temp_path("pack_incremental");
+        fs::create_dir_all(&dir).expect("create dir");
+        write_sprite(&dir, "a.png", 8, 8, [255, 0, 0, 255]);
+        write_sprite(&dir, "b.png", 8, 8, [0, 255, 0, 255]);
+
+        let inputs = collect_png_inputs(&dir).expect("inputs");
+        let mut options = PackOptions::new(dir.join("atlas.png"), dir.join("atlas.json"));
+        options.atlas_size = 32;
+        pack(&inputs, &options)
+            .expect("pack")
+            .write()
+            .expect("write");
+
+        // Nothing changed: no image, nothing to write.
+        let noop = pack(&inputs, &options).expect("no-op repack");
+        assert!(noop.image.is_none());
+        assert_eq!(noop.reused, 2);
+
+        // Same size, new pixels: the previous atlas is patched in place.
+        write_sprite(&dir, "b.png", 8, 8, [0, 0, 255, 255]);
+        let patched = pack(&inputs, &options).expect("patch repack");
+        assert_eq!(patched.reused, 1);
+        let image = patched.image.as_ref().expect("patched image");
+        assert_eq!(image.get_pixel(0, 0).0, [255, 0, 0, 255]);
+        assert_eq!(image.get_pixel(9, 0).0, [0, 0, 255, 255]);
+        patched.write().expect("write patched");
+
+        // A shrunk sprite leaves nothing of its old pixels behind.
+        write_sprite(&dir, "b.png", 8, 3, [0, 255, 0, 255]);
+        let shrunk = pack(&inputs, &options).expect("shrink repack");
+        assert_eq!(shrunk.reused, 1);
+        let image = shrunk.image.as_ref().expect("patched image");
+        assert_eq!(image.get_pixel(9, 2).0, [0, 255, 0, 255]);
+        for y in 3..9 {
+            assert_eq!(image.get_pixel(9, y).0, [0, 0, 0, 0], "row {y}");
+        }
+        assert_eq!(image.get_pixel(0, 7).0, [255, 0, 0, 255]);
+        shrunk.write().expect("write shrunk");
+
+        // A size change moves sprites, so everything is packed again.
+        write_sprite(&dir, "a.png", 12, 8, [255, 0, 0, 255]);
+        let full = pack(&inputs, &options).expect("full repack");
+        assert_eq!(full.reused, 0);
+        assert_eq!(full.metadata.sprites[1].rect_px.x, 13);
+        assert_eq!(
+            full.metadata.sprites[1].sprite_id,
+            patched.metadata.sprites[1].sprite_id
+        );
+
+        options.incremental = false;
+        full.write().expect("write full");
+        assert_eq!(pack(&inputs, &options).expect("forced").reused, 0);
+
+        let _ = fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn test_half_res_atlas_is_written_beside_the_full_one() {
+        let dir = test_temp_path("pack_half_res");
+        fs::create_dir_all(&dir).expect("create dir");
+        write_sprite(&dir, "a.png", 4, 4, [200, 100, 0, 255]);
+        // Odd width: its last column shares a block with the padding.
+        write_sprite(&dir, "b.png", 3, 2, [0, 0, 255, 255]);
+
+        let inputs = collect_png_inputs(&dir).expect("inputs");
+        let mut options = PackOptions::new(dir.join("atlas.png"), dir.join("atlas.json"));
+        options.atlas_size = 16;
+        options.half_res = true;
+        let result = pack(&inputs, &options).expect("pack");
+        let half_res = result.metadata.texture.half_res.clone().expect("half res");
+        assert_eq!((half_res.width, half_res.height), (8, 8));
+        assert!(half_res.path.ends_with("atlas@1x.png"), "{}", half_res.path);
+        result.write().expect("write");
+
+        let small = image::open(half_res_path_for(&options.atlas_png_output))
+            .expect("half res png")
+            .to_rgba8();
+        assert_eq!(small.dimensions(), (8, 8));
+        assert_eq!(small.get_pixel(0, 0).0, [200, 100, 0, 255]);
+        // b sits at x 5..8: block x 6..8 is fully inside, block 4..6 half.
+        assert_eq!(small.get_pixel(3, 0).0, [0, 0, 255, 255]);
+        assert_eq!(small.get_pixel(2, 0).0, [0, 0, 255, 128]);
+        assert_eq!(small.get_pixel(7, 7).0, [0, 0, 0, 0]);
+
+        // Unchanged inputs and options: nothing to write.
+        assert!(pack(&inputs, &options).expect("repack").image.is_none());
+        // Dropping the half-res copy invalidates the previous pack.
+        options.half_res = false;
+        assert_eq!(pack(&inputs, &options).expect("repack").reused, 0);
+
+        options.half_res = true;
+        options.atlas_size = 15;
+        let err = pack(&inputs, &options).expect_err("odd size");
+        assert!(err.contains("even"), "{err}");
+
+        let _ = fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn test_premultiplied_atlas_scales_color_by_alpha() {
+        let dir = test_temp_path("pack_premultiplied");
+        fs::create_dir_all(&dir).expect("create dir");
+        write_sprite(&dir, "glow.png", 2, 2, [255, 0, 128, 128]);
+
+        let inputs = collect_png_inputs(&dir).expect("inputs");
+        let mut options = PackOptions::new(dir.join("atlas.png"), dir.join("atlas.json"));
+        options.atlas_size = 8;
+        options.premultiply_alpha = true;
+        let result = pack(&inputs, &options).expect("pack");
+        assert!(result.metadata.texture.premultiplied_alpha);
+        let image = result.image.as_ref().expect("image");
+        // Linear light: sRGB 255 at half alpha encodes back to 188.
+        assert_eq!(image.get_pixel(1, 1).0, [188, 0, 93, 128]);
+        result.write().expect("write");
+
+        // Switching conventions repacks every sprite.
+        options.premultiply_alpha = false;
+        let result = pack(&inputs, &options).expect("repack");
+        assert_eq!(result.reused, 0);
+        assert!(!result.metadata.texture.premultiplied_alpha);
+        let json = serde_json::to_string(&result.metadata).expect("json");
+        assert!(!json.contains("premultiplied_alpha"), "{json}");
+
+        let _ = fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn test_rotation_lays_tall_sprites_on_their_side() {
+        let dir = test_temp_path("pack_rotated");
+        fs::create_dir_all(&dir).expect("create dir");
+        write_sprite(&dir, "a_wide.png", 8, 2, [0, 255, 0, 255]);
+        // Tall sprites, red along the top row.
+        for (name, blue) in [("b_tall.png", 255), ("c_tall.png", 200)] {
+            let mut tall = RgbaImage::from_pixel(2, 8, image::Rgba([0, 0, blue, 255]));
+            tall.put_pixel(0, 0, image::Rgba([255, 0, 0, 255]));
+            tall.put_pixel(1, 0, image::Rgba([255, 0, 0, 255]));
+            tall.save(dir.join(name)).expect("write sprite");
+        }
+
+        let inputs = collect_png_inputs(&dir).expect("inputs");
+        let mut options = PackOptions::new(dir.join("atlas.png"), dir.join("atlas.json"));
+        options.atlas_size = 12;
+        let err = pack(&inputs, &options).expect_err("upright shelves overflow");
+        assert!(err.contains("Atlas overflow"), "{err}");
+
+        options.allow_rotation = true;
+        let result = pack(&inputs, &options).expect("pack");
+        let sprites = &result.metadata.sprites;
+        assert!(!sprites[0].rotated);
+        assert!(sprites[1].rotated);
+        assert_eq!(
+            sprites[1].rect_px,
+            AtlasRectPx {
+                x: 0,
+                y: 3,
+                w: 8,
+                h: 2
+            }
+        );
+        // Turned clockwise: the top row ends up in the rightmost column.
+        let image = result.image.as_ref().expect("image");
+        assert_eq!(image.get_pixel(7, 3).0, [255, 0, 0, 255]);
+        assert_eq!(image.get_pixel(0, 3).0, [0, 0, 255, 255]);
+        result.write().expect("write");
+
+        // Reusing the previous pack keeps the flags it was packed with.
+        let result = pack(&inputs, &options).expect("repack");
+        assert_eq!(result.reused, 3);
+        assert!(result.image.is_none());
+        assert!(result.metadata.sprites[2].rotated);
+
+        let _ = fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn test_identical_images_share_one_rect() {
+        let dir = test_temp_path("pack_dedup");
+        fs::create_dir_all(&dir).expect("create dir");
+        write_sprite(&dir, "walk_1.png", 4, 4, [10, 20, 30, 255]);
+        write_sprite(&dir, "walk_2.png", 4, 4, [40, 50, 60, 255]);
+        write_sprite(&dir, "walk_3.png", 4, 4, [10, 20, 30, 255]);
+
+        let inputs = collect_png_inputs(&dir).expect("inputs");
+        let mut options = PackOptions::new(dir.join("atlas.png"), dir.join("atlas.json"));
+        options.atlas_size = 16;
+        let result = pack(&inputs, &options).expect("pack");
+        assert_eq!(result.duplicates, 1);
+        let sprites = &result.metadata.sprites;
+        assert_eq!(sprites[2].rect_px, sprites[0].rect_px);
+        assert_ne!(sprites[1].rect_px, sprites[0].rect_px);
+        // Separate entries, separate IDs.
+        assert_ne!(sprites[2].sprite_id, sprites[0].sprite_id);
+        let ids: Vec<String> = sprites.iter().map(|s| s.sprite_id.clone()).collect();
+        result.write().expect("write");
+
+        // Changing one copy splits it off and keeps every ID.
+        write_sprite(&dir, "walk_3.png", 4, 4, [70, 80, 90, 255]);
+        let result = pack(&inputs, &options).expect("repack");
+        assert_eq!(result.duplicates, 0);
+        let sprites = &result.metadata.sprites;
+        assert_ne!(sprites[2].rect_px, sprites[0].rect_px);
+        let repacked: Vec<String> = sprites.iter().map(|s| s.sprite_id.clone()).collect();
+        assert_eq!(repacked, ids);
+
+        let _ = fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn test_pack_applies_sidecar_trim_and_pivot() {
+        let dir = test_temp_path("pack_sidecar");
+        fs::create_dir_all(&dir).expect("create dir");
+        // 8x8 source whose opaque pixels occupy x 1..=4, y 4..=7 (bottom rows).
+        let mut source = RgbaImage::new(8, 8);
+        for y in 4..8 {
+            for x in 1..5 {
+                source.put_pixel(x, y, image::Rgba([9, 9, 9, 255]));
+            }
+        }
+        let path = dir.join("feet.png");
+        source.save(&path).expect("write sprite");
+        fs::write(
+            dir.join("feet.meta.json"),
+            r#"{ "pivot": { "x": 0.5, "y": 0.0 }, "trim": true }"#,
+        )
+        .expect("write sidecar");
+
+        let options = PackOptions::new(dir.join("atlas.png"), dir.join("atlas.json"));
+        let result = pack(&[PackInput::from_path(path)], &options).expect("pack");
+        let sprite = &result.metadata.sprites[0];
+        assert_eq!((sprite.rect_px.w, sprite.rect_px.h), (4, 4));
+        assert_eq!(
+            sprite.trim,
+            Some(AtlasTrim {
+                x: 1,
+                y: 4,
+                source_w: 8,
+                source_h: 8
+            })
+        );
+        // Source pivot (4, 0) px from bottom-left is (3, 0) inside the trimmed rect.
+        assert_eq!(sprite.pivot, AtlasPivot { x: 0.75, y: 0.0 });
+
+        let _ = fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn test_pack_rejects_oversized_sprite() {
+        let dir = test_temp_path("pack_oversized");
+        fs::create_dir_all(&dir).expect("create dir");
+        let big = write_sprite(&dir, "big.png", 20, 20, [1, 2, 3, 255]);
+
+        let mut options = PackOptions::new(dir.join("atlas.png"), dir.join("atlas.json"));
+        options.atlas_size = 16;
+        let err = pack(&[PackInput::from_path(big)], &options).expect_err("should not fit");
+        assert!(err.contains("does not fit"), "{err}");
+
+        let _ = fs::remove_dir_all(&dir);
+    }
+}
@@ -0,0 +1,101 @@
+//! Atlas metadata JSON as written next to the packed texture and read by
+//! the engine (`docs/planning/asset_formats_v0.1.md`, section 3).
+
+use serde::{Deserialize, Serialize};
+
+/// Format version written into `AtlasMetadata::version`.
+pub const ATLAS_FORMAT_VERSION: &str = "0.1";
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct AtlasMetadata {
+    pub version: String,
+    pub atlas_id: String,
+    pub texture: AtlasTexture,
+    pub sprites: Vec<AtlasSprite>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct AtlasTexture {
+    pub path: String,
+    pub width: u32,
+    pub height: u32,
+    /// The same atlas at half resolution (the "@1x" set to `path`'s "@2x"),
+    /// which the engine loads on low fidelity tiers. Sprite UVs apply to
+    /// both; `rect_px` is in `path`'s pixels.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub half_res: Option<AtlasTextureFile>,
+    /// Color is stored multiplied by alpha (`PackOptions::premultiply_alpha`).
+    #[serde(default, skip_serializing_if = "is_false")]
+    pub premultiplied_alpha: bool,
+}
+
+fn is_false(value: &bool) -> bool {
+    !value
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct AtlasTextureFile {
+    pub path: String,
+    pub width: u32,
+    pub height: u32,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct AtlasSprite {
+    pub sprite_id: String,
+    pub name: String,
+    pub source_path: String,
+    pub rect_px: AtlasRectPx,
+    pub uv: AtlasUvRect,
+    pub pivot: AtlasPivot,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub nine_slice: Option<AtlasNineSlice>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub trim: Option<AtlasTrim>,
+    /// Stored turned 90° clockwise (`PackOptions::allow_rotation`).
+    /// `rect_px` and `uv` cover the turned pixels; `pivot` and `trim` stay
+    /// in the upright image's space.
+    #[serde(default, skip_serializing_if = "is_false")]
+    pub rotated: bool,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub struct AtlasRectPx {
+    pub x: u32,
+    pub y: u32,
+    pub w: u32,
+    pub h: u32,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct AtlasUvRect {
+    pub u0: f32,
+    pub v0: f32,
+    pub u1: f32,
+    pub v1: f32,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct AtlasPivot {
+    pub x: f32,
+    pub y: f32,
+}
+
+/// Nine-slice borders in pixels, measured inward from each edge.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct AtlasNineSlice {
+    pub left: u32,
+    pub right: u32,
+    pub top: u32,
+    pub bottom: u32,
+}
+
+/// Where a trimmed sprite's `rect_px` sat inside its original source image.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct AtlasTrim {
+    /// Offset of the kept region from the source's top-left corner.
+    pub x: u32,
+    pub y: u32,
+    pub source_w: u32,
+    pub source_h: u32,
+}
@@ -0,0 +1,186 @@
+//! Transactional output writes.
+//!
+//! Every output is first written to `<name>.tmp`, then all of them are
+//! promoted together. If any promotion fails, already-promoted files are
+//! rolled back to their previous contents so the atlas PNG, metadata, and ID
+//! registry never disagree on disk.
+
+use std::collections::HashMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+/// Path as written into JSON: forward slashes on every platform.
+pub fn normalize_path_for_json(path: &Path) -> String {
+    path.to_string_lossy().replace('\\', "/")
+}
+
+pub fn temporary_output_path(path: &Path) -> PathBuf {
+    let file_name = path
+        .file_name()
+        .and_then(|name| name.to_str())
+        .unwrap_or("output");
+    path.with_file_name(format!("{file_name}.tmp"))
+}
+
+pub fn promote_temporary_file(temp_path: &Path, final_path: &Path) -> Result<(), String> {
+    if final_path.exists() {
+        fs::remove_file(final_path).map_err(|e| {
+            format!(
+                "Failed to replace existing output '{}': {e}",
+                final_path.display()
+            )
+        })?;
+    }
+    fs::rename(temp_path, final_path).map_err(|e| {
+        format!(
+            "Failed to move temporary output '{}' -> '{}': {e}",
+            temp_path.display(),
+            final_path.display()
+        )
+    })
+}
+
+/// Promote `(temp, final)` pairs all-or-nothing.
+pub fn promote_outputs_transactional(pairs: &[(&Path, &Path)]) -> Result<(), String> {
+    let mut backups: HashMap<PathBuf, PathBuf> = HashMap::new();
+    let mut promoted: Vec<PathBuf> = Vec::new();
+
+    for (_, final_path) in pairs {
+        if final_path.exists() {
+            let backup_path = final_path.with_extension("bak.tmp");
+            fs::rename(final_path, &backup_path).map_err(|e| {
+                format!(
+                    "Failed to stage backup '{}' -> '{}': {e}",
+                    final_path.display(),
+                    backup_path.display()
+                )
+            })?;
+            backups.insert((*final_path).to_path_buf(), backup_path);
+        }
+    }
+
+    for (temp_path, final_path) in pairs {
+        match promote_temporary_file(temp_path, final_path) {
+            Ok(()) => promoted.push((*final_path).to_path_buf()),
+            Err(err) => {
+                for promoted_path in promoted.iter().rev() {
+                    let _ = fs::remove_file(promoted_path);
+                    if let Some(backup_path) = backups.get(promoted_path) {
+                        let _ = fs::rename(backup_path, promoted_path);
+                    }
+                }
+                for (final_path, backup_path) in backups {
+                    if !final_path.exists() {
+                        let _ = fs::rename(backup_path, final_path);
+                    }
+                }
+                return Err(err);
+            }
+        }
+    }
+
+    for (_, backup_path) in backups {
+        let _ = fs::remove_file(backup_path);
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_temp_path;
+
+    #[test]
+    fn test_normalize_path_replaces_backslashes() {
+        let result = normalize_path_for_json(Path::new("foo\\bar\\baz.png"));
+        assert_eq!(result, "foo/bar/baz.png");
+    }
+
+    #[test]
+    fn test_normalize_path_preserves_forward_slashes() {
+        let result = normalize_path_for_json(Path::new("foo/bar/baz.png"));
+        assert_eq!(result, "foo/bar/baz.png");
+    }
+
+    #[test]
+    fn test_temporary_output_path() {
+        let result = temporary_output_path(Path::new("output/atlas.png"));
+        assert_eq!(result, PathBuf::from("output/atlas.png.tmp"));
+    }
+
+    #[test]
+    fn test_promote_temporary_file_success() {
+        let temp = test_temp_path("promote_temp");
+        let final_path = test_temp_path("promote_final");
+
+        // Ensure final doesn't exist
+        let _ = fs::remove_file(&final_path);
+
+        fs::write(&temp, "temporary content").expect("write temp");
+
+        let result = promote_temporary_file(&temp, &final_path);
+        assert!(result.is_ok());
+
+        // Final should have the content
+        let content = fs::read_to_string(&final_path).expect("read final");
+        assert_eq!(content, "temporary content");
+
+        // Temp should be gone
+        assert!(!temp.exists());
+
+        // Cleanup
+        let _ = fs::remove_file(&final_path);
+    }
+
+    #[test]
+    fn test_promote_temporary_file_overwrites_existing() {
+        let temp = test_temp_path("overwrite_temp");
+        let final_path = test_temp_path("overwrite_final");
+
+        fs::write(&final_path, "old content").expect("write final");
+        fs::write(&temp, "new content").expect("write temp");
+
+        let result = promote_temporary_file(&temp, &final_path);
+        assert!(result.is_ok());
+
+        let content = fs::read_to_string(&final_path).expect("read final");
+        assert_eq!(content, "new content");
+
+        assert!(!temp.exists());
+
+        // Cleanup
+        let _ = fs::remove_file(&final_path);
+    }
+
+    #[test]
+    fn test_promote_outputs_transactional_success() {
+        let temp1 = test_temp_path("txn_temp1");
+        let final1 = test_temp_path("txn_final1");
+        let temp2 = test_temp_path("txn_temp2");
+        let final2 = test_temp_path("txn_final2");
+
+        // Ensure finals don't exist
+        let _ = fs::remove_file(&final1);
+        let _ = fs::remove_file(&final2);
+
+        fs::write(&temp1, "content one").expect("write temp1");
+        fs::write(&temp2, "content two").expect("write temp2");
+
+        let pairs: Vec<(&Path, &Path)> = vec![(&temp1, &final1), (&temp2, &final2)];
+        let result = promote_outputs_transactional(&pairs);
+        assert!(result.is_ok());
+
+        let content1 = fs::read_to_string(&final1).expect("read final1");
+        let content2 = fs::read_to_string(&final2).expect("read final2");
+        assert_eq!(content1, "content one");
+        assert_eq!(content2, "content two");
+
+        assert!(!temp1.exists());
+        assert!(!temp2.exists());
+
+        // Cleanup
+        let _ = fs::remove_file(&final1);
+        let _ = fs::remove_file(&final2);
+    }
+}
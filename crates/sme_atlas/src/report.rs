@@ -0,0 +1,413 @@
+//! Atlas usage report and annotated debug image.
+//!
+//! Artists deciding whether to split an atlas or change its size need to see
+//! how full it is and where the room is. `usage_report` measures a packed
+//! atlas from its metadata: the fraction of pixels sprites cover, the rest
+//! as waste, and the largest empty rectangles (found greedily, largest
+//! first, so they never overlap). `annotate` draws the same over the atlas:
+//! free rects tinted red, every sprite outlined and labelled with its index
+//! in `UsageReport::sprites`.
+//!
+//! Both work from metadata alone, so they apply to sliced sheets as well.
+
+use image::{Rgba, RgbaImage};
+use serde::Serialize;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use crate::metadata::{AtlasMetadata, AtlasRectPx};
+
+/// How many free rects the report lists.
+pub const MAX_FREE_RECTS: usize = 5;
+
+#[derive(Debug, Clone, Serialize)]
+pub struct UsageReport {
+    pub atlas_id: String,
+    pub width: u32,
+    pub height: u32,
+    pub sprite_count: usize,
+    /// Pixels covered by at least one sprite; duplicates sharing a rect
+    /// count once.
+    pub used_px: u64,
+    /// `used_px` over the atlas area.
+    pub fill_ratio: f64,
+    /// Pixels no sprite covers, padding included.
+    pub wasted_px: u64,
+    /// Largest first, non-overlapping.
+    pub largest_free_rects: Vec<AtlasRectPx>,
+    /// Labels in the debug image, by index.
+    pub sprites: Vec<ReportSprite>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct ReportSprite {
+    pub name: String,
+    pub sprite_id: String,
+    pub rect_px: AtlasRectPx,
+}
+
+/// Where the report for `atlas_json` goes: `<stem>.report.json` and
+/// `<stem>.debug.png` next to it.
+pub fn report_paths_for(atlas_json: &Path) -> (PathBuf, PathBuf) {
+    let stem = atlas_json
+        .file_stem()
+        .and_then(|s| s.to_str())
+        .unwrap_or("atlas");
+    (
+        atlas_json.with_file_name(format!("{stem}.report.json")),
+        atlas_json.with_file_name(format!("{stem}.debug.png")),
+    )
+}
+
+pub fn usage_report(metadata: &AtlasMetadata) -> UsageReport {
+    let (width, height) = (metadata.texture.width, metadata.texture.height);
+    let mut occupied = Occupancy::new(width, height);
+    for sprite in &metadata.sprites {
+        occupied.fill(&sprite.rect_px);
+    }
+    let used_px = occupied.count();
+    let area = u64::from(width) * u64::from(height);
+
+    let mut largest_free_rects = Vec::new();
+    while largest_free_rects.len() < MAX_FREE_RECTS {
+        let Some(rect) = occupied.largest_empty_rect() else {
+            break;
+        };
+        occupied.fill(&rect);
+        largest_free_rects.push(rect);
+    }
+
+    UsageReport {
+        atlas_id: metadata.atlas_id.clone(),
+        width,
+        height,
+        sprite_count: metadata.sprites.len(),
+        used_px,
+        fill_ratio: if area == 0 {
+            0.0
+        } else {
+            used_px as f64 / area as f64
+        },
+        wasted_px: area - used_px,
+        largest_free_rects,
+        sprites: metadata
+            .sprites
+            .iter()
+            .map(|sprite| ReportSprite {
+                name: sprite.name.clone(),
+                sprite_id: sprite.sprite_id.clone(),
+                rect_px: sprite.rect_px,
+            })
+            .collect(),
+    }
+}
+
+impl UsageReport {
+    /// A few lines for the terminal.
+    pub fn summary(&self) -> String {
+        let mut lines = vec![format!(
+            "Atlas '{}' {}x{}: {} sprites, {:.1}% filled, {} px wasted",
+            self.atlas_id,
+            self.width,
+            self.height,
+            self.sprite_count,
+            self.fill_ratio * 100.0,
+            self.wasted_px
+        )];
+        for rect in &self.largest_free_rects {
+            lines.push(format!(
+                "  free {}x{} at ({}, {})",
+                rect.w, rect.h, rect.x, rect.y
+            ));
+        }
+        lines.join("\n")
+    }
+}
+
+/// Report on the atlas at `atlas_png` described by `metadata`, writing the
+/// report JSON and debug PNG to `report_paths_for(atlas_json)`.
+pub fn write_usage_report(
+    metadata: &AtlasMetadata,
+    atlas_png: &Path,
+    atlas_json: &Path,
+) -> Result<UsageReport, String> {
+    let atlas = image::open(atlas_png)
+        .map_err(|e| format!("Failed to open atlas '{}': {e}", atlas_png.display()))?
+        .to_rgba8();
+    let report = usage_report(metadata);
+    let (json_path, png_path) = report_paths_for(atlas_json);
+    let json = serde_json::to_string_pretty(&report)
+        .map_err(|e| format!("Failed to serialize usage report: {e}"))?;
+    fs::write(&json_path, json)
+        .map_err(|e| format!("Failed to write '{}': {e}", json_path.display()))?;
+    annotate(&atlas, &report)
+        .save_with_format(&png_path, image::ImageFormat::Png)
+        .map_err(|e| format!("Failed to write '{}': {e}", png_path.display()))?;
+    Ok(report)
+}
+
+const FREE_TINT: Rgba<u8> = Rgba([255, 40, 40, 90]);
+const LABEL_BACKGROUND: Rgba<u8> = Rgba([0, 0, 0, 255]);
+const LABEL_TEXT: Rgba<u8> = Rgba([255, 255, 255, 255]);
+/// Outline colours, cycled by sprite index so neighbours differ.
+const OUTLINES: [Rgba<u8>; 4] = [
+    Rgba([0, 220, 255, 255]),
+    Rgba([255, 220, 0, 255]),
+    Rgba([120, 255, 80, 255]),
+    Rgba([255, 120, 220, 255]),
+];
+
+/// `atlas` over a checkerboard, with `report`'s free rects and labelled
+/// sprite outlines drawn on top.
+pub fn annotate(atlas: &RgbaImage, report: &UsageReport) -> RgbaImage {
+    let mut out = RgbaImage::from_fn(atlas.width(), atlas.height(), |x, y| {
+        let checker = if (x / 8 + y / 8) % 2 == 0 { 60 } else { 90 };
+        blend(
+            Rgba([checker, checker, checker, 255]),
+            *atlas.get_pixel(x, y),
+        )
+    });
+    for rect in &report.largest_free_rects {
+        for y in rect.y..rect.y + rect.h {
+            for x in rect.x..rect.x + rect.w {
+                let pixel = out.get_pixel_mut(x, y);
+                *pixel = blend(*pixel, FREE_TINT);
+            }
+        }
+    }
+    // Labels scale up with the atlas so they stay readable zoomed out.
+    let scale = 1 + report.width.max(report.height) / 1024;
+    for (index, sprite) in report.sprites.iter().enumerate() {
+        outline(&mut out, &sprite.rect_px, OUTLINES[index % OUTLINES.len()]);
+        draw_label(
+            &mut out,
+            sprite.rect_px.x + 1,
+            sprite.rect_px.y + 1,
+            index,
+            scale,
+        );
+    }
+    out
+}
+
+/// `top` composited over opaque `bottom`.
+fn blend(bottom: Rgba<u8>, top: Rgba<u8>) -> Rgba<u8> {
+    let alpha = u32::from(top.0[3]);
+    let mix = |b: u8, t: u8| ((u32::from(t) * alpha + u32::from(b) * (255 - alpha)) / 255) as u8;
+    Rgba([
+        mix(bottom.0[0], top.0[0]),
+        mix(bottom.0[1], top.0[1]),
+        mix(bottom.0[2], top.0[2]),
+        255,
+    ])
+}
+
+fn outline(image: &mut RgbaImage, rect: &AtlasRectPx, color: Rgba<u8>) {
+    let (right, bottom) = (rect.x + rect.w - 1, rect.y + rect.h - 1);
+    for x in rect.x..=right {
+        put_clipped(image, x, rect.y, color);
+        put_clipped(image, x, bottom, color);
+    }
+    for y in rect.y..=bottom {
+        put_clipped(image, rect.x, y, color);
+        put_clipped(image, right, y, color);
+    }
+}
+
+fn put_clipped(image: &mut RgbaImage, x: u32, y: u32, color: Rgba<u8>) {
+    if x < image.width() && y < image.height() {
+        image.put_pixel(x, y, color);
+    }
+}
+
+/// 3x5 digits, one row per byte, leftmost pixel in bit 2.
+const DIGITS: [[u8; 5]; 10] = [
+    [0b111, 0b101, 0b101, 0b101, 0b111],
+    [0b010, 0b110, 0b010, 0b010, 0b111],
+    [0b111, 0b001, 0b111, 0b100, 0b111],
+    [0b111, 0b001, 0b111, 0b001, 0b111],
+    [0b101, 0b101, 0b111, 0b001, 0b001],
+    [0b111, 0b100, 0b111, 0b001, 0b111],
+    [0b111, 0b100, 0b111, 0b101, 0b111],
+    [0b111, 0b001, 0b001, 0b001, 0b001],
+    [0b111, 0b101, 0b111, 0b101, 0b111],
+    [0b111, 0b101, 0b111, 0b001, 0b111],
+];
+
+/// `number` in white on a black box whose top-left is `(x, y)`, each font
+/// pixel drawn `scale` pixels square.
+fn draw_label(image: &mut RgbaImage, x: u32, y: u32, number: usize, scale: u32) {
+    let text = number.to_string();
+    let box_w = (text.len() as u32 * 4 + 1) * scale;
+    let box_h = 7 * scale;
+    for dy in 0..box_h {
+        for dx in 0..box_w {
+            put_clipped(image, x + dx, y + dy, LABEL_BACKGROUND);
+        }
+    }
+    for (i, digit) in text.bytes().enumerate() {
+        let glyph = DIGITS[usize::from(digit - b'0')];
+        let left = x + (i as u32 * 4 + 1) * scale;
+        for (row, bits) in glyph.iter().enumerate() {
+            for column in 0..3 {
+                if bits & (0b100 >> column) == 0 {
+                    continue;
+                }
+                for sy in 0..scale {
+                    for sx in 0..scale {
+                        put_clipped(
+                            image,
+                            left + column * scale + sx,
+                            y + (row as u32 + 1) * scale + sy,
+                            LABEL_TEXT,
+                        );
+                    }
+                }
+            }
+        }
+    }
+}
+
+/// Which atlas pixels are taken.
+struct Occupancy {
+    width: u32,
+    height: u32,
+    taken: Vec<bool>,
+}
+
+impl Occupancy {
+    fn new(width: u32, height: u32) -> Self {
+        Self {
+            width,
+            height,
+            taken: vec![false; width as usize * height as usize],
+        }
+    }
+
+    fn fill(&mut self, rect: &AtlasRectPx) {
+        let right = (rect.x + rect.w).min(self.width);
+        let bottom = (rect.y + rect.h).min(self.height);
+        for y in rect.y.min(bottom)..bottom {
+            let row = y as usize * self.width as usize;
+            self.taken[row + rect.x.min(right) as usize..row + right as usize].fill(true);
+        }
+    }
+
+    fn count(&self) -> u64 {
+        self.taken.iter().filter(|&&taken| taken).count() as u64
+    }
+
+    /// Largest empty rectangle by area, or `None` when nothing is free.
+    /// Row by row, each column's run of free pixels ending at that row forms
+    /// a histogram whose largest rectangle is found with a stack.
+    fn largest_empty_rect(&self) -> Option<AtlasRectPx> {
+        let width = self.width as usize;
+        let mut heights = vec![0u32; width];
+        let mut best: Option<(u64, AtlasRectPx)> = None;
+        let mut stack: Vec<usize> = Vec::with_capacity(width);
+        for y in 0..self.height {
+            let row = y as usize * width;
+            for (x, height) in heights.iter_mut().enumerate() {
+                *height = if self.taken[row + x] { 0 } else { *height + 1 };
+            }
+            stack.clear();
+            for x in 0..=width {
+                let current = heights.get(x).copied().unwrap_or(0);
+                while let Some(&top) = stack.last() {
+                    if heights[top] < current {
+                        break;
+                    }
+                    stack.pop();
+                    let h = heights[top];
+                    let left = stack.last().map_or(0, |&left| left + 1);
+                    let w = (x - left) as u32;
+                    let area = u64::from(w) * u64::from(h);
+                    if area > 0 && best.is_none_or(|(best_area, _)| area > best_area) {
+                        let rect = AtlasRectPx {
+                            x: left as u32,
+                            y: y + 1 - h,
+                            w,
+                            h,
+                        };
+                        best = Some((area, rect));
+                    }
+                }
+                stack.push(x);
+            }
+        }
+        best.map(|(_, rect)| rect)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::metadata::{AtlasPivot, AtlasSprite, AtlasTexture, AtlasUvRect};
+
+    fn sprite(name: &str, rect_px: AtlasRectPx) -> AtlasSprite {
+        AtlasSprite {
+            sprite_id: format!("id-{name}"),
+            name: name.to_string(),
+            source_path: format!("{name}.png"),
+            rect_px,
+            uv: AtlasUvRect {
+                u0: 0.0,
+                v0: 0.0,
+                u1: 1.0,
+                v1: 1.0,
+            },
+            pivot: AtlasPivot { x: 0.5, y: 0.5 },
+            nine_slice: None,
+            trim: None,
+            rotated: false,
+        }
+    }
+
+    #[test]
+    fn report_measures_fill_and_finds_free_space_largest_first() {
+        let rect = |x, y, w, h| AtlasRectPx { x, y, w, h };
+        let metadata = AtlasMetadata {
+            version: "0.1".to_string(),
+            atlas_id: "chars".to_string(),
+            texture: AtlasTexture {
+                path: "chars.png".to_string(),
+                width: 16,
+                height: 16,
+                half_res: None,
+                premultiplied_alpha: false,
+            },
+            // The left half, plus a duplicate sharing the first rect.
+            sprites: vec![
+                sprite("a", rect(0, 0, 8, 12)),
+                sprite("b", rect(0, 12, 8, 4)),
+                sprite("a_copy", rect(0, 0, 8, 12)),
+                sprite("c", rect(8, 0, 4, 4)),
+            ],
+        };
+        let report = usage_report(&metadata);
+        assert_eq!(report.used_px, 8 * 16 + 16);
+        assert_eq!(report.wasted_px, 256 - 144);
+        assert!((report.fill_ratio - 144.0 / 256.0).abs() < 1e-9);
+        assert_eq!(
+            report.largest_free_rects,
+            vec![rect(8, 4, 8, 12), rect(12, 0, 4, 4)]
+        );
+        assert!(
+            report.summary().contains("56.2% filled"),
+            "{}",
+            report.summary()
+        );
+
+        let atlas = RgbaImage::from_pixel(16, 16, Rgba([0, 0, 0, 0]));
+        let annotated = annotate(&atlas, &report);
+        assert_eq!(annotated.dimensions(), (16, 16));
+        // Sprite 1's outline, then its label box and the "1" glyph.
+        assert_eq!(*annotated.get_pixel(0, 12), OUTLINES[1]);
+        assert_eq!(*annotated.get_pixel(1, 13), LABEL_BACKGROUND);
+        assert_eq!(*annotated.get_pixel(3, 14), LABEL_TEXT);
+        assert_eq!(*annotated.get_pixel(2, 14), LABEL_BACKGROUND);
+        // Free space is tinted red.
+        let free = annotated.get_pixel(12, 10).0;
+        assert!(free[0] > free[1] && free[0] > free[2], "{free:?}");
+    }
+}
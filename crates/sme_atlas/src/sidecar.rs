@@ -0,0 +1,199 @@
+//! Per-sprite metadata sidecars.
+//!
+//! A source `hero.png` may sit next to `hero.meta.json`, and a directory may
+//! hold `_defaults.meta.json` that applies to every sprite in it. Both share
+//! one shape, every field optional; a sprite's own sidecar wins field by field
+//! over the directory defaults:
+//!
+//! ```json
+//! {
+//!   "pivot": { "x": 0.5, "y": 0.0 },
+//!   "nine_slice": { "left": 4, "right": 4, "top": 4, "bottom": 4 },
+//!   "trim": true
+//! }
+//! ```
+//!
+//! `pivot` is a fraction of the untrimmed source size measured from its
+//! bottom-left corner (the engine's y-up convention); it defaults to the
+//! center. It may also name a preset such as `"bottom-center"`, resolved to
+//! numbers when the sidecar is read (see [`PIVOT_PRESETS`]). `nine_slice` borders are in source pixels. `trim` crops fully
+//! transparent rows and columns before packing.
+
+use serde::{Deserialize, Deserializer, Serialize};
+use std::collections::HashMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use crate::metadata::{AtlasNineSlice, AtlasPivot};
+
+/// Directory-level defaults file, read from each source's directory.
+pub const DEFAULTS_FILE_NAME: &str = "_defaults.meta.json";
+
+/// Named pivots accepted in place of `{ "x", "y" }`.
+pub const PIVOT_PRESETS: [(&str, AtlasPivot); 9] = [
+    ("top-left", AtlasPivot { x: 0.0, y: 1.0 }),
+    ("top-center", AtlasPivot { x: 0.5, y: 1.0 }),
+    ("top-right", AtlasPivot { x: 1.0, y: 1.0 }),
+    ("center-left", AtlasPivot { x: 0.0, y: 0.5 }),
+    ("center", AtlasPivot { x: 0.5, y: 0.5 }),
+    ("center-right", AtlasPivot { x: 1.0, y: 0.5 }),
+    ("bottom-left", AtlasPivot { x: 0.0, y: 0.0 }),
+    ("bottom-center", AtlasPivot { x: 0.5, y: 0.0 }),
+    ("bottom-right", AtlasPivot { x: 1.0, y: 0.0 }),
+];
+
+pub fn pivot_preset(name: &str) -> Option<AtlasPivot> {
+    PIVOT_PRESETS
+        .iter()
+        .find(|(preset, _)| *preset == name)
+        .map(|&(_, pivot)| pivot)
+}
+
+#[derive(Deserialize)]
+#[serde(untagged)]
+enum PivotSpec {
+    Named(String),
+    Point(AtlasPivot),
+}
+
+fn deserialize_pivot<'de, D>(deserializer: D) -> Result<Option<AtlasPivot>, D::Error>
+where
+    D: Deserializer<'de>,
+{
+    match Option::<PivotSpec>::deserialize(deserializer)? {
+        None => Ok(None),
+        Some(PivotSpec::Point(pivot)) => Ok(Some(pivot)),
+        Some(PivotSpec::Named(name)) => pivot_preset(&name).map(Some).ok_or_else(|| {
+            let names: Vec<&str> = PIVOT_PRESETS.iter().map(|(preset, _)| *preset).collect();
+            serde::de::Error::custom(format!(
+                "unknown pivot preset '{name}', expected one of: {}",
+                names.join(", ")
+            ))
+        }),
+    }
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct SpriteMeta {
+    #[serde(default, deserialize_with = "deserialize_pivot")]
+    pub pivot: Option<AtlasPivot>,
+    #[serde(default)]
+    pub nine_slice: Option<AtlasNineSlice>,
+    #[serde(default)]
+    pub trim: Option<bool>,
+}
+
+impl SpriteMeta {
+    /// Fields set here, falling back to `defaults` for the rest.
+    pub fn merged_over(&self, defaults: &SpriteMeta) -> SpriteMeta {
+        SpriteMeta {
+            pivot: self.pivot.or(defaults.pivot),
+            nine_slice: self.nine_slice.or(defaults.nine_slice),
+            trim: self.trim.or(defaults.trim),
+        }
+    }
+}
+
+/// `sprites/hero.png` -> `sprites/hero.meta.json`.
+pub fn sidecar_path_for(source_path: &Path) -> PathBuf {
+    source_path.with_extension("meta.json")
+}
+
+/// Read a sidecar or defaults file; a missing file is `None`.
+pub fn load_sprite_meta(path: &Path) -> Result<Option<SpriteMeta>, String> {
+    if !path.exists() {
+        return Ok(None);
+    }
+    let raw = fs::read_to_string(path)
+        .map_err(|e| format!("Failed to read sprite meta '{}': {e}", path.display()))?;
+    let meta: SpriteMeta = serde_json::from_str(&raw)
+        .map_err(|e| format!("Failed to parse sprite meta '{}': {e}", path.display()))?;
+    if let Some(pivot) = meta.pivot {
+        if !pivot.x.is_finite() || !pivot.y.is_finite() {
+            return Err(format!(
+                "Sprite meta validation failed: '{}' pivot must be finite",
+                path.display()
+            ));
+        }
+    }
+    Ok(Some(meta))
+}
+
+/// Resolves the effective meta for each source, reading every directory's
+/// defaults file once.
+#[derive(Debug, Default)]
+pub struct SidecarResolver {
+    defaults_by_dir: HashMap<PathBuf, SpriteMeta>,
+}
+
+impl SidecarResolver {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn resolve(&mut self, source_path: &Path) -> Result<SpriteMeta, String> {
+        let dir = source_path.parent().unwrap_or(Path::new("")).to_path_buf();
+        if !self.defaults_by_dir.contains_key(&dir) {
+            let defaults = load_sprite_meta(&dir.join(DEFAULTS_FILE_NAME))?.unwrap_or_default();
+            self.defaults_by_dir.insert(dir.clone(), defaults);
+        }
+        let defaults = &self.defaults_by_dir[&dir];
+        let own = load_sprite_meta(&sidecar_path_for(source_path))?.unwrap_or_default();
+        Ok(own.merged_over(defaults))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_temp_path;
+
+    #[test]
+    fn test_sidecar_overrides_directory_defaults_per_field() {
+        let dir = test_temp_path("sidecar_dir");
+        fs::create_dir_all(&dir).expect("create dir");
+        fs::write(
+            dir.join(DEFAULTS_FILE_NAME),
+            r#"{ "pivot": { "x": 0.5, "y": 0.0 }, "trim": true }"#,
+        )
+        .expect("write defaults");
+        fs::write(dir.join("hero.meta.json"), r#"{ "trim": false }"#).expect("write sidecar");
+
+        let mut resolver = SidecarResolver::new();
+        let hero = resolver.resolve(&dir.join("hero.png")).expect("hero");
+        assert_eq!(hero.pivot, Some(AtlasPivot { x: 0.5, y: 0.0 }));
+        assert_eq!(hero.trim, Some(false));
+
+        let other = resolver.resolve(&dir.join("other.png")).expect("other");
+        assert_eq!(other.trim, Some(true));
+        assert_eq!(other.nine_slice, None);
+
+        let _ = fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn test_sidecar_pivot_accepts_preset_names() {
+        let path = test_temp_path("sidecar_named_pivot");
+        fs::write(&path, r#"{ "pivot": "bottom-center" }"#).expect("write");
+        let meta = load_sprite_meta(&path).expect("load").expect("present");
+        assert_eq!(meta.pivot, Some(AtlasPivot { x: 0.5, y: 0.0 }));
+
+        fs::write(&path, r#"{ "pivot": "bottom-middle" }"#).expect("write");
+        let err = load_sprite_meta(&path).expect_err("unknown preset");
+        assert!(
+            err.contains("unknown pivot preset 'bottom-middle'"),
+            "{err}"
+        );
+        let _ = fs::remove_file(&path);
+    }
+
+    #[test]
+    fn test_sidecar_rejects_unknown_fields() {
+        let path = test_temp_path("sidecar_typo");
+        fs::write(&path, r#"{ "pivott": { "x": 0.0, "y": 0.0 } }"#).expect("write");
+        let err = load_sprite_meta(&path).expect_err("typo should fail");
+        assert!(err.contains("unknown field"), "{err}");
+        let _ = fs::remove_file(&path);
+    }
+}
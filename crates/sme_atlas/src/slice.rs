@@ -0,0 +1,336 @@
+//! Slicing an existing sprite sheet into atlas metadata, without repacking.
+//!
+//! Legacy sheets are already atlases in all but metadata: cells on a regular
+//! grid. `slice_sheet` walks that grid and emits one sprite per non-empty cell,
+//! with `texture.path` pointing at the sheet itself, so the engine loads the
+//! sheet as-is.
+//!
+//! Cells are numbered row-major from the top-left, counting empty cells, so a
+//! cell keeps its name (`<sheet stem>_<index>`) and its registry key
+//! (`<sheet path>#<index>`) when neighbouring cells are filled in or cleared.
+//! The sheet's own sidecar (`hero.png` -> `hero.meta.json`) supplies the
+//! pivot and nine-slice borders for every cell; trimming is not supported,
+//! since cells are never moved.
+
+use image::RgbaImage;
+use std::collections::HashSet;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use crate::id_registry::{
+    hash_rgba8_bytes, id_registry_path_for, load_id_registry, resolve_or_assign_sprite_id,
+    seed_registry_from_existing_metadata, IdRegistryFile,
+};
+use crate::metadata::{
+    AtlasMetadata, AtlasPivot, AtlasRectPx, AtlasSprite, AtlasTexture, AtlasUvRect,
+    ATLAS_FORMAT_VERSION,
+};
+use crate::output::{
+    normalize_path_for_json, promote_outputs_transactional, temporary_output_path,
+};
+use crate::sidecar::{load_sprite_meta, sidecar_path_for};
+
+/// Cell layout of a sprite sheet, in pixels.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct SliceGrid {
+    pub cell_w: u32,
+    pub cell_h: u32,
+    /// Offset of the first cell from the sheet's top-left corner.
+    pub margin: u32,
+    /// Gap between neighbouring cells.
+    pub spacing: u32,
+}
+
+impl SliceGrid {
+    pub fn new(cell_w: u32, cell_h: u32) -> Self {
+        Self {
+            cell_w,
+            cell_h,
+            margin: 0,
+            spacing: 0,
+        }
+    }
+
+    /// Columns and rows of whole cells that fit in a `width`x`height` sheet.
+    pub fn cell_counts(&self, width: u32, height: u32) -> (u32, u32) {
+        let fit = |extent: u32, cell: u32| {
+            (extent.saturating_sub(self.margin) + self.spacing) / (cell + self.spacing)
+        };
+        (fit(width, self.cell_w), fit(height, self.cell_h))
+    }
+}
+
+/// Sheet metadata, not yet written to disk.
+#[derive(Debug, Clone)]
+pub struct SliceResult {
+    pub metadata: AtlasMetadata,
+    /// Registry updated with any newly assigned IDs.
+    pub id_registry: IdRegistryFile,
+    atlas_json_output: PathBuf,
+}
+
+/// Slice `sheet_path` along `grid` into metadata for `atlas_json_output`.
+/// Fully transparent cells are skipped.
+pub fn slice_sheet(
+    sheet_path: &Path,
+    grid: SliceGrid,
+    atlas_json_output: &Path,
+) -> Result<SliceResult, String> {
+    if grid.cell_w == 0 || grid.cell_h == 0 {
+        return Err("Slice grid cell width and height must be > 0".to_string());
+    }
+    let sheet = image::open(sheet_path)
+        .map_err(|e| format!("Failed to open '{}': {e}", sheet_path.display()))?
+        .to_rgba8();
+    let (sheet_w, sheet_h) = sheet.dimensions();
+    let (columns, rows) = grid.cell_counts(sheet_w, sheet_h);
+    if columns == 0 || rows == 0 {
+        return Err(format!(
+            "Sheet '{}' ({}x{}) holds no whole {}x{} cells with margin {}",
+            sheet_path.display(),
+            sheet_w,
+            sheet_h,
+            grid.cell_w,
+            grid.cell_h,
+            grid.margin
+        ));
+    }
+
+    let meta = load_sprite_meta(&sidecar_path_for(sheet_path))?.unwrap_or_default();
+    if meta.trim == Some(true) {
+        return Err(format!(
+            "Sheet '{}': sliced sheets cannot be trimmed",
+            sheet_path.display()
+        ));
+    }
+    if let Some(nine_slice) = meta.nine_slice {
+        if nine_slice.left + nine_slice.right > grid.cell_w
+            || nine_slice.top + nine_slice.bottom > grid.cell_h
+        {
+            return Err(format!(
+                "Sheet '{}': nine_slice borders exceed the {}x{} cell",
+                sheet_path.display(),
+                grid.cell_w,
+                grid.cell_h
+            ));
+        }
+    }
+    let pivot = meta.pivot.unwrap_or(AtlasPivot { x: 0.5, y: 0.5 });
+
+    let mut id_registry = load_id_registry(&id_registry_path_for(atlas_json_output))?;
+    if id_registry.entries.is_empty() {
+        seed_registry_from_existing_metadata(atlas_json_output, &mut id_registry)?;
+    }
+
+    let sheet_json_path = normalize_path_for_json(sheet_path);
+    let stem = sheet_path
+        .file_stem()
+        .and_then(|s| s.to_str())
+        .unwrap_or("sheet");
+    let mut cells = Vec::new();
+    for row in 0..rows {
+        for column in 0..columns {
+            let index = row * columns + column;
+            let x = grid.margin + column * (grid.cell_w + grid.spacing);
+            let y = grid.margin + row * (grid.cell_h + grid.spacing);
+            let cell = image::imageops::crop_imm(&sheet, x, y, grid.cell_w, grid.cell_h).to_image();
+            if is_fully_transparent(&cell) {
+                continue;
+            }
+            let source_path = format!("{sheet_json_path}#{index}");
+            cells.push((index, x, y, source_path, hash_rgba8_bytes(cell.as_raw())));
+        }
+    }
+    let live_paths: HashSet<&str> = cells.iter().map(|cell| cell.3.as_str()).collect();
+    let mut sprites = Vec::new();
+    for (index, x, y, source_path, source_hash) in &cells {
+        let (x, y) = (*x, *y);
+        let sprite_id =
+            resolve_or_assign_sprite_id(&mut id_registry, source_path, source_hash, &live_paths);
+        let (w, h) = (grid.cell_w, grid.cell_h);
+        sprites.push(AtlasSprite {
+            sprite_id,
+            name: format!("{stem}_{index}"),
+            source_path: source_path.clone(),
+            rect_px: AtlasRectPx { x, y, w, h },
+            uv: AtlasUvRect {
+                u0: x as f32 / sheet_w as f32,
+                v0: y as f32 / sheet_h as f32,
+                u1: (x + w) as f32 / sheet_w as f32,
+                v1: (y + h) as f32 / sheet_h as f32,
+            },
+            pivot,
+            nine_slice: meta.nine_slice,
+            trim: None,
+            rotated: false,
+        });
+    }
+    if sprites.is_empty() {
+        return Err(format!(
+            "Sheet '{}' has no non-transparent cells",
+            sheet_path.display()
+        ));
+    }
+
+    let atlas_id = atlas_json_output
+        .file_stem()
+        .and_then(|s| s.to_str())
+        .unwrap_or("atlas")
+        .to_string();
+    Ok(SliceResult {
+        metadata: AtlasMetadata {
+            version: ATLAS_FORMAT_VERSION.to_string(),
+            atlas_id,
+            texture: AtlasTexture {
+                path: sheet_json_path,
+                width: sheet_w,
+                height: sheet_h,
+                half_res: None,
+                premultiplied_alpha: false,
+            },
+            sprites,
+        },
+        id_registry,
+        atlas_json_output: atlas_json_output.to_path_buf(),
+    })
+}
+
+fn is_fully_transparent(image: &RgbaImage) -> bool {
+    image.pixels().all(|pixel| pixel.0[3] == 0)
+}
+
+impl SliceResult {
+    /// Write the metadata JSON and ID registry, all or nothing. The sheet
+    /// itself is left untouched.
+    pub fn write(&self) -> Result<(), String> {
+        let atlas_json_output = &self.atlas_json_output;
+        if let Some(parent) = atlas_json_output.parent() {
+            fs::create_dir_all(parent).map_err(|e| {
+                format!(
+                    "Failed to create atlas metadata output dir '{}': {e}",
+                    parent.display()
+                )
+            })?;
+        }
+
+        let json = serde_json::to_string_pretty(&self.metadata)
+            .map_err(|e| format!("Failed to serialize atlas metadata: {e}"))?;
+        let json_tmp = temporary_output_path(atlas_json_output);
+        fs::write(&json_tmp, json)
+            .map_err(|e| format!("Failed to write '{}': {e}", json_tmp.display()))?;
+        let id_registry_path = id_registry_path_for(atlas_json_output);
+        let id_registry_json = serde_json::to_string_pretty(&self.id_registry).map_err(|e| {
+            format!(
+                "Failed to serialize id registry '{}': {e}",
+                id_registry_path.display()
+            )
+        })?;
+        let id_registry_tmp = temporary_output_path(&id_registry_path);
+        fs::write(&id_registry_tmp, id_registry_json)
+            .map_err(|e| format!("Failed to write '{}': {e}", id_registry_tmp.display()))?;
+
+        promote_outputs_transactional(&[
+            (&json_tmp, atlas_json_output),
+            (&id_registry_tmp, &id_registry_path),
+        ])
+    }
+
+    pub fn atlas_json_output(&self) -> &Path {
+        &self.atlas_json_output
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_temp_path;
+
+    /// 2x2 grid of 4x4 cells with margin 1 and spacing 2; the bottom-right
+    /// cell is left transparent.
+    fn write_sheet(dir: &Path) -> PathBuf {
+        let mut sheet = RgbaImage::new(1 + 4 + 2 + 4 + 1, 1 + 4 + 2 + 4);
+        for (i, (cx, cy)) in [(1, 1), (7, 1), (1, 7)].into_iter().enumerate() {
+            for y in cy..cy + 4 {
+                for x in cx..cx + 4 {
+                    sheet.put_pixel(x, y, image::Rgba([i as u8 * 60, 0, 0, 255]));
+                }
+            }
+        }
+        let path = dir.join("legacy.png");
+        sheet.save(&path).expect("write sheet");
+        path
+    }
+
+    #[test]
+    fn test_slice_sheet_maps_cells_and_skips_empty_ones() {
+        let dir = test_temp_path("slice_sheet");
+        fs::create_dir_all(&dir).expect("create dir");
+        let sheet = write_sheet(&dir);
+        fs::write(
+            dir.join("legacy.meta.json"),
+            r#"{ "pivot": { "x": 0.5, "y": 0.0 } }"#,
+        )
+        .expect("write sidecar");
+        let json = dir.join("out/legacy_atlas.json");
+
+        let grid = SliceGrid {
+            margin: 1,
+            spacing: 2,
+            ..SliceGrid::new(4, 4)
+        };
+        assert_eq!(grid.cell_counts(12, 11), (2, 2));
+        let result = slice_sheet(&sheet, grid, &json).expect("slice");
+        let sprites = &result.metadata.sprites;
+        assert_eq!(sprites.len(), 3);
+        assert_eq!(
+            sprites.iter().map(|s| s.name.as_str()).collect::<Vec<_>>(),
+            ["legacy_0", "legacy_1", "legacy_2"]
+        );
+        assert_eq!(
+            sprites[1].rect_px,
+            AtlasRectPx {
+                x: 7,
+                y: 1,
+                w: 4,
+                h: 4
+            }
+        );
+        assert_eq!(
+            sprites[2].rect_px,
+            AtlasRectPx {
+                x: 1,
+                y: 7,
+                w: 4,
+                h: 4
+            }
+        );
+        assert!(sprites[1].source_path.ends_with("legacy.png#1"));
+        assert_eq!(sprites[0].pivot, AtlasPivot { x: 0.5, y: 0.0 });
+        assert_eq!(result.metadata.texture.width, 12);
+        assert_eq!(
+            result.metadata.texture.path,
+            normalize_path_for_json(&sheet)
+        );
+        result.write().expect("write");
+        assert!(!dir.join("out/legacy_atlas.png").exists());
+
+        // IDs survive a re-slice.
+        let again = slice_sheet(&sheet, grid, &json).expect("slice again");
+        for (a, b) in sprites.iter().zip(&again.metadata.sprites) {
+            assert_eq!(a.sprite_id, b.sprite_id);
+        }
+
+        let _ = fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn test_slice_sheet_rejects_grid_larger_than_sheet() {
+        let dir = test_temp_path("slice_too_big");
+        fs::create_dir_all(&dir).expect("create dir");
+        let sheet = write_sheet(&dir);
+        let err = slice_sheet(&sheet, SliceGrid::new(64, 64), &dir.join("a.json"))
+            .expect_err("grid too large");
+        assert!(err.contains("no whole 64x64 cells"), "{err}");
+        let _ = fs::remove_dir_all(&dir);
+    }
+}
@@ -0,0 +1,209 @@
+//! Splitting an illustration too large for any atlas into streamable tiles.
+//!
+//! Painted backgrounds can run to many thousands of pixels on a side; no
+//! atlas holds them, and low-memory devices can't keep them resident whole.
+//! `split_tiles` cuts such an image into `tile_size` squares (edge tiles are
+//! smaller) written next to a tileset JSON, which the engine reads to load
+//! only the tiles near the camera. Fully transparent tiles are left out.
+//!
+//! Tiles are named `<tileset stem>_<column>_<row>.png`, numbered from the
+//! top-left, so re-splitting a retouched source rewrites the same files.
+
+use image::RgbaImage;
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use crate::output::{
+    normalize_path_for_json, promote_outputs_transactional, temporary_output_path,
+};
+
+pub const TILESET_FORMAT_VERSION: &str = "0.1";
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TileSet {
+    pub version: String,
+    pub tileset_id: String,
+    /// Size of the whole source image, in pixels.
+    pub width: u32,
+    pub height: u32,
+    pub tile_size: u32,
+    pub tiles: Vec<TileEntry>,
+}
+
+/// One tile: `tile_size` pixels square, except along the right and bottom
+/// edges of the source.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct TileEntry {
+    pub column: u32,
+    pub row: u32,
+    pub path: String,
+    pub width: u32,
+    pub height: u32,
+}
+
+/// A split illustration, not yet written to disk.
+#[derive(Debug, Clone)]
+pub struct TileResult {
+    pub tileset: TileSet,
+    images: Vec<(PathBuf, RgbaImage)>,
+    tileset_json_output: PathBuf,
+}
+
+/// Cut `source_path` into `tile_size` tiles described by
+/// `tileset_json_output`.
+pub fn split_tiles(
+    source_path: &Path,
+    tile_size: u32,
+    tileset_json_output: &Path,
+) -> Result<TileResult, String> {
+    if tile_size == 0 {
+        return Err("tile_size must be > 0".to_string());
+    }
+    let source = image::open(source_path)
+        .map_err(|e| format!("Failed to open '{}': {e}", source_path.display()))?
+        .to_rgba8();
+    let (width, height) = source.dimensions();
+    let stem = tileset_json_output
+        .file_stem()
+        .and_then(|s| s.to_str())
+        .unwrap_or("tiles")
+        .to_string();
+
+    let mut tiles = Vec::new();
+    let mut images = Vec::new();
+    for row in 0..height.div_ceil(tile_size) {
+        for column in 0..width.div_ceil(tile_size) {
+            let (x, y) = (column * tile_size, row * tile_size);
+            let w = tile_size.min(width - x);
+            let h = tile_size.min(height - y);
+            let tile = image::imageops::crop_imm(&source, x, y, w, h).to_image();
+            if tile.pixels().all(|pixel| pixel.0[3] == 0) {
+                continue;
+            }
+            let path = tileset_json_output.with_file_name(format!("{stem}_{column}_{row}.png"));
+            tiles.push(TileEntry {
+                column,
+                row,
+                path: normalize_path_for_json(&path),
+                width: w,
+                height: h,
+            });
+            images.push((path, tile));
+        }
+    }
+    if tiles.is_empty() {
+        return Err(format!(
+            "Image '{}' is fully transparent",
+            source_path.display()
+        ));
+    }
+
+    Ok(TileResult {
+        tileset: TileSet {
+            version: TILESET_FORMAT_VERSION.to_string(),
+            tileset_id: stem,
+            width,
+            height,
+            tile_size,
+            tiles,
+        },
+        images,
+        tileset_json_output: tileset_json_output.to_path_buf(),
+    })
+}
+
+impl TileResult {
+    /// Write every tile PNG and the tileset JSON, all or nothing.
+    pub fn write(&self) -> Result<(), String> {
+        let tileset_json_output = &self.tileset_json_output;
+        if let Some(parent) = tileset_json_output.parent() {
+            fs::create_dir_all(parent).map_err(|e| {
+                format!(
+                    "Failed to create tileset output dir '{}': {e}",
+                    parent.display()
+                )
+            })?;
+        }
+
+        let mut outputs = Vec::with_capacity(self.images.len() + 1);
+        for (path, image) in &self.images {
+            let tmp = temporary_output_path(path);
+            image
+                .save_with_format(&tmp, image::ImageFormat::Png)
+                .map_err(|e| format!("Failed to write '{}': {e}", tmp.display()))?;
+            outputs.push((tmp, path.clone()));
+        }
+        let json = serde_json::to_string_pretty(&self.tileset)
+            .map_err(|e| format!("Failed to serialize tileset: {e}"))?;
+        let json_tmp = temporary_output_path(tileset_json_output);
+        fs::write(&json_tmp, json)
+            .map_err(|e| format!("Failed to write '{}': {e}", json_tmp.display()))?;
+        outputs.push((json_tmp, tileset_json_output.clone()));
+
+        let outputs: Vec<(&Path, &Path)> = outputs
+            .iter()
+            .map(|(tmp, output)| (tmp.as_path(), output.as_path()))
+            .collect();
+        promote_outputs_transactional(&outputs)
+    }
+
+    pub fn tileset_json_output(&self) -> &Path {
+        &self.tileset_json_output
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_temp_path;
+
+    #[test]
+    fn test_split_tiles_covers_the_image_and_skips_empty_tiles() {
+        let dir = test_temp_path("split_tiles");
+        fs::create_dir_all(&dir).expect("create dir");
+        // 10x7 source, opaque except the bottom-right tile.
+        let mut source = RgbaImage::from_pixel(10, 7, image::Rgba([10, 20, 30, 255]));
+        for y in 4..7 {
+            for x in 8..10 {
+                source.put_pixel(x, y, image::Rgba([0, 0, 0, 0]));
+            }
+        }
+        source.put_pixel(5, 6, image::Rgba([1, 2, 3, 255]));
+        let source_path = dir.join("cliff.png");
+        source.save(&source_path).expect("write source");
+
+        let json = dir.join("out/cliff_bg.json");
+        let result = split_tiles(&source_path, 4, &json).expect("split");
+        let tileset = &result.tileset;
+        assert_eq!((tileset.width, tileset.height), (10, 7));
+        assert_eq!(tileset.tileset_id, "cliff_bg");
+        // 3x2 grid minus the transparent corner.
+        assert_eq!(tileset.tiles.len(), 5);
+        let edge = &tileset.tiles[2];
+        assert_eq!(
+            (edge.column, edge.row, edge.width, edge.height),
+            (2, 0, 2, 4)
+        );
+        let bottom = &tileset.tiles[4];
+        assert_eq!((bottom.column, bottom.row), (1, 1));
+        assert!(
+            bottom.path.ends_with("out/cliff_bg_1_1.png"),
+            "{}",
+            bottom.path
+        );
+
+        result.write().expect("write");
+        let tile = image::open(dir.join("out/cliff_bg_1_1.png"))
+            .expect("tile png")
+            .to_rgba8();
+        assert_eq!(tile.dimensions(), (4, 3));
+        assert_eq!(tile.get_pixel(1, 2).0, [1, 2, 3, 255]);
+        assert!(!dir.join("out/cliff_bg_2_1.png").exists());
+
+        let err = split_tiles(&source_path, 0, &json).expect_err("zero tile size");
+        assert!(err.contains("tile_size"), "{err}");
+
+        let _ = fs::remove_dir_all(&dir);
+    }
+}
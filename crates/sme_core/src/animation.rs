@@ -6,17 +6,29 @@
 //! drift across platforms.
 //!
 //! The JSON format stores `duration_ms` for human readability; on load this
-//! is converted to `duration_us` for internal use.
+//! is converted to `duration_us` for internal use. Sprite ids, animation ids
+//! and clip names are interned (`crate::interned::Id`) so ticking and looking
+//! up clips never hashes a string.
 
 use serde::Deserialize;
 use std::collections::HashMap;
-use std::fs;
 use std::path::Path;
 
+use crate::assets;
+use crate::interned::Id;
+use crate::migrate::SchemaMigrations;
+
+/// Animation schema versions and the upgrade path to the current one.
+pub const ANIMATION_SCHEMA: SchemaMigrations = SchemaMigrations {
+    kind: "Animation",
+    current: "0.1",
+    steps: &[],
+};
+
 /// A single frame in an animation clip.
 #[derive(Debug, Clone)]
 pub struct AnimationFrame {
-    pub sprite_id: String,
+    pub sprite_id: Id,
     pub duration_us: u64,
 }
 
@@ -45,8 +57,8 @@ pub struct AnimationFile {
 /// Runtime state for one active animation instance.
 #[derive(Debug, Clone)]
 pub struct AnimationState {
-    pub source_id: String,
-    pub clip_name: String,
+    pub source_id: Id,
+    pub clip_name: Id,
     pub frame_index: usize,
     pub elapsed_us: u64,
     pub finished: bool,
@@ -55,8 +67,8 @@ pub struct AnimationState {
 impl AnimationState {
     pub fn new(source_id: &str, clip_name: &str) -> Self {
         Self {
-            source_id: source_id.to_string(),
-            clip_name: clip_name.to_string(),
+            source_id: Id::new(source_id),
+            clip_name: Id::new(clip_name),
             frame_index: 0,
             elapsed_us: 0,
             finished: false,
@@ -65,14 +77,14 @@ impl AnimationState {
 
     /// Advance the animation by `dt_us` microseconds. Returns the current frame's
     /// `sprite_id`. Uses integer arithmetic only for determinism.
-    pub fn tick<'a>(&mut self, dt_us: u64, clip: &'a AnimationClip) -> &'a str {
+    pub fn tick(&mut self, dt_us: u64, clip: &AnimationClip) -> Id {
         if clip.frames.is_empty() || self.finished {
             return if let Some(frame) = clip.frames.get(self.frame_index) {
-                &frame.sprite_id
+                frame.sprite_id
             } else if let Some(frame) = clip.frames.last() {
-                &frame.sprite_id
+                frame.sprite_id
             } else {
-                ""
+                Id::new("")
             };
         }
 
@@ -99,7 +111,7 @@ impl AnimationState {
             }
         }
 
-        &clip.frames[self.frame_index].sprite_id
+        clip.frames[self.frame_index].sprite_id
     }
 }
 
@@ -127,9 +139,12 @@ struct AnimationFrameJson {
 
 /// Load an animation definition file from disk.
 pub fn load_animation_file(path: &Path) -> Result<AnimationFile, String> {
-    let raw = fs::read_to_string(path)
+    let raw = assets::read_to_string(path)
         .map_err(|e| format!("Failed to read animation file {}: {e}", path.display()))?;
-    let json: AnimationFileJson = serde_json::from_str(&raw)
+    let mut value: serde_json::Value = serde_json::from_str(&raw)
+        .map_err(|e| format!("Failed to parse animation file {}: {e}", path.display()))?;
+    ANIMATION_SCHEMA.migrate_with_warning(&mut value, &path.display().to_string())?;
+    let json: AnimationFileJson = serde_json::from_value(value)
         .map_err(|e| format!("Failed to parse animation file {}: {e}", path.display()))?;
     validate_animation_json(&json)?;
 
@@ -139,7 +154,7 @@ pub fn load_animation_file(path: &Path) -> Result<AnimationFile, String> {
             .frames
             .into_iter()
             .map(|f| AnimationFrame {
-                sprite_id: f.sprite_id,
+                sprite_id: Id::new(&f.sprite_id),
                 duration_us: f.duration_ms * 1000,
             })
             .collect();
@@ -160,12 +175,6 @@ pub fn load_animation_file(path: &Path) -> Result<AnimationFile, String> {
 }
 
 fn validate_animation_json(json: &AnimationFileJson) -> Result<(), String> {
-    if json.version != "0.1" {
-        return Err(format!(
-            "Animation validation failed: unsupported version '{}'",
-            json.version
-        ));
-    }
     if json.animation_id.is_empty() {
         return Err("Animation validation failed: animation_id is empty".to_string());
     }
@@ -197,6 +206,7 @@ fn validate_animation_json(json: &AnimationFileJson) -> Result<(), String> {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use std::fs;
     use std::time::{SystemTime, UNIX_EPOCH};
 
     fn temp_file_path(name_hint: &str) -> std::path::PathBuf {
@@ -218,7 +228,7 @@ mod tests {
                 .iter()
                 .enumerate()
                 .map(|(i, &d)| AnimationFrame {
-                    sprite_id: format!("sprite_{}", i),
+                    sprite_id: Id::new(&format!("sprite_{}", i)),
                     duration_us: d * 1000,
                 })
                 .collect(),
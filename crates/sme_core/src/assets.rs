@@ -0,0 +1,194 @@
+//! Asset read path shared by every content loader.
+//!
+//! Loaders call [`read_to_string`] / [`read_bytes`] instead of `std::fs`
+//! directly. In dev nothing is mounted and reads go to disk. Shipping builds
+//! mount a `.smepack` once at startup via [`mount_pack`]; from then on paths
+//! found in the pack are served from it, and anything the pack does not
+//! contain still falls back to disk.
+//!
+//! Asset paths are keys like `assets/scenes/m4_scene.json`. On disk they are
+//! resolved against the asset root set once with [`set_root`] (see
+//! [`resolve`]), or against the working directory if no root is set; pack
+//! lookups always use the key itself.
+
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::OnceLock;
+use std::time::SystemTime;
+
+use serde::Deserialize;
+
+use crate::pack::{normalize_pack_path, PackArchive};
+
+/// Environment variable naming the asset root.
+pub const ASSET_ROOT_ENV: &str = "SME_ASSET_ROOT";
+
+/// Engine config file looked up next to the executable; its `asset_root`
+/// key names the asset root, relative to the file.
+pub const ENGINE_CONFIG_FILE: &str = "engine.toml";
+
+static MOUNTED_PACK: OnceLock<PackArchive> = OnceLock::new();
+static ASSET_ROOT: OnceLock<PathBuf> = OnceLock::new();
+
+/// Mount `pack` for the rest of the process. Only one pack can be mounted.
+pub fn mount_pack(pack: PackArchive) -> Result<(), String> {
+    MOUNTED_PACK
+        .set(pack)
+        .map_err(|_| "A content pack is already mounted".to_string())
+}
+
+pub fn mounted_pack() -> Option<&'static PackArchive> {
+    MOUNTED_PACK.get()
+}
+
+/// Resolve relative asset paths against `root` for the rest of the
+/// process. Only one root can be set.
+pub fn set_root(root: PathBuf) -> Result<(), String> {
+    if !root.is_dir() {
+        return Err(format!("Asset root {} is not a directory", root.display()));
+    }
+    ASSET_ROOT
+        .set(root)
+        .map_err(|_| "An asset root is already set".to_string())
+}
+
+pub fn root() -> Option<&'static Path> {
+    ASSET_ROOT.get().map(PathBuf::as_path)
+}
+
+/// Where `path` lives on disk: relative paths are joined onto the asset root,
+/// absolute paths and paths with no root set are returned as they are.
+pub fn resolve(path: &Path) -> PathBuf {
+    resolve_in(root(), path)
+}
+
+/// The `asset_root` named by an engine config file, relative to the file's
+/// directory. `None` if the file does not exist or has no `asset_root`.
+pub fn root_from_config_file(path: &Path) -> Result<Option<PathBuf>, String> {
+    #[derive(Deserialize)]
+    struct EngineConfigFile {
+        asset_root: Option<PathBuf>,
+    }
+
+    let raw = match fs::read_to_string(path) {
+        Ok(raw) => raw,
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(None),
+        Err(e) => return Err(format!("Failed to read {}: {e}", path.display())),
+    };
+    let file: EngineConfigFile =
+        toml::from_str(&raw).map_err(|e| format!("Failed to parse {}: {e}", path.display()))?;
+    let dir = path.parent().unwrap_or(Path::new(""));
+    Ok(file.asset_root.map(|root| dir.join(root)))
+}
+
+pub fn read_bytes(path: &Path) -> Result<Vec<u8>, String> {
+    read_bytes_from(mounted_pack(), root(), path)
+}
+
+pub fn read_to_string(path: &Path) -> Result<String, String> {
+    let bytes = read_bytes(path)?;
+    String::from_utf8(bytes).map_err(|_| "stream did not contain valid UTF-8".to_string())
+}
+
+/// True if `path` is in the mounted pack or on disk.
+pub fn exists(path: &Path) -> bool {
+    mounted_pack().is_some_and(|pack| pack.contains(&normalize_pack_path(path)))
+        || resolve(path).exists()
+}
+
+/// Modification time of `path` on disk, for hot reload polling. Packed
+/// files never change, so only the disk copy is checked.
+pub fn modified(path: &Path) -> Option<SystemTime> {
+    fs::metadata(resolve(path)).ok()?.modified().ok()
+}
+
+fn resolve_in(root: Option<&Path>, path: &Path) -> PathBuf {
+    match root {
+        Some(root) if path.is_relative() => root.join(path),
+        _ => path.to_path_buf(),
+    }
+}
+
+fn read_bytes_from(
+    pack: Option<&PackArchive>,
+    root: Option<&Path>,
+    path: &Path,
+) -> Result<Vec<u8>, String> {
+    if let Some(pack) = pack {
+        let key = normalize_pack_path(path);
+        if pack.contains(&key) {
+            return pack.read(&key);
+        }
+    }
+    fs::read(resolve_in(root, path)).map_err(|e| e.to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::pack::PackWriter;
+
+    #[test]
+    fn pack_entries_shadow_disk_and_missing_entries_fall_back() {
+        let disk_path =
+            std::env::temp_dir().join(format!("sme_assets_test_{}.json", std::process::id()));
+        fs::write(&disk_path, b"from disk").expect("write temp file");
+
+        let mut writer = PackWriter::new();
+        writer
+            .add("assets/scenes/a.json", b"from pack".to_vec())
+            .expect("add entry");
+        let pack = PackArchive::from_bytes(writer.finish()).expect("open pack");
+
+        let packed = read_bytes_from(Some(&pack), None, Path::new("./assets/scenes/a.json"))
+            .expect("pack read");
+        assert_eq!(packed, b"from pack");
+        let loose = read_bytes_from(Some(&pack), None, &disk_path).expect("disk fallback");
+        assert_eq!(loose, b"from disk");
+        assert!(read_bytes_from(Some(&pack), None, Path::new("assets/nope.json")).is_err());
+
+        let _ = fs::remove_file(disk_path);
+    }
+
+    #[test]
+    fn relative_paths_resolve_against_the_root_and_packs_ignore_it() {
+        let root = std::env::temp_dir().join(format!("sme_assets_root_{}", std::process::id()));
+        fs::create_dir_all(root.join("assets/scenes")).expect("create root");
+        fs::write(root.join("assets/scenes/a.json"), b"from root").expect("write file");
+
+        let key = Path::new("assets/scenes/a.json");
+        assert_eq!(resolve_in(Some(&root), key), root.join(key));
+        assert_eq!(resolve_in(None, key), key);
+        let absolute = std::env::temp_dir().join("x.json");
+        assert_eq!(resolve_in(Some(&root), &absolute), absolute);
+
+        let loose = read_bytes_from(None, Some(&root), key).expect("root read");
+        assert_eq!(loose, b"from root");
+        let mut writer = PackWriter::new();
+        writer
+            .add("assets/scenes/a.json", b"from pack".to_vec())
+            .expect("add entry");
+        let pack = PackArchive::from_bytes(writer.finish()).expect("open pack");
+        let packed = read_bytes_from(Some(&pack), Some(&root), key).expect("pack read");
+        assert_eq!(packed, b"from pack");
+
+        let _ = fs::remove_dir_all(root);
+    }
+
+    #[test]
+    fn config_file_root_is_relative_to_the_file() {
+        let dir = std::env::temp_dir().join(format!("sme_assets_config_{}", std::process::id()));
+        fs::create_dir_all(&dir).expect("create dir");
+        let path = dir.join(ENGINE_CONFIG_FILE);
+        assert_eq!(root_from_config_file(&path), Ok(None));
+
+        fs::write(&path, "asset_root = \"../game\"\n").expect("write config");
+        assert_eq!(root_from_config_file(&path), Ok(Some(dir.join("../game"))));
+        fs::write(&path, "# nothing set\n").expect("write config");
+        assert_eq!(root_from_config_file(&path), Ok(None));
+        fs::write(&path, "asset_root = 3\n").expect("write config");
+        assert!(root_from_config_file(&path).is_err());
+
+        let _ = fs::remove_dir_all(dir);
+    }
+}
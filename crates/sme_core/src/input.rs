@@ -8,9 +8,20 @@
 //!   the main loop calls only after at least one fixed simulation step has consumed
 //!   them. This prevents a press from being silently lost on a frame that has zero
 //!   simulation steps (when the accumulator hasn't built up enough time).
+//!
+//! - **Wheel:** scroll is accumulated into `wheel_delta()` and cleared by
+//!   `end_frame()` on the same terms, so notches scrolled across several
+//!   zero-step frames all reach the next step.
+//!
+//! - **Touch:** `TouchControls` turns fingers into the same `Key` presses the
+//!   keyboard produces — a virtual joystick on the left of the screen holds
+//!   `Left`/`Right`, a jump button on the right holds `Space` — so gameplay code
+//!   and Lua scripts run unchanged on touch devices.
 
 use std::collections::HashSet;
 
+use serde::{Deserialize, Serialize};
+
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
 pub enum Key {
     Left,
@@ -22,14 +33,18 @@ pub enum Key {
     F3,
     F4,
     F5,
+    F6,
+    F7,
     W,
     A,
     S,
     D,
     R,
+    Shift,
 }
 
-#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
 pub enum MouseBtn {
     Left,
     Right,
@@ -46,6 +61,8 @@ pub struct InputState {
     mouse_just_released: HashSet<MouseBtn>,
 
     pub mouse_position: (f64, f64),
+    /// Wheel notches since the last `end_frame()`; positive is away from the user.
+    wheel_delta: f32,
 }
 
 impl InputState {
@@ -58,6 +75,7 @@ impl InputState {
             mouse_just_pressed: HashSet::new(),
             mouse_just_released: HashSet::new(),
             mouse_position: (0.0, 0.0),
+            wheel_delta: 0.0,
         }
     }
 
@@ -85,6 +103,14 @@ impl InputState {
         }
     }
 
+    pub fn mouse_wheel(&mut self, notches: f32) {
+        self.wheel_delta += notches;
+    }
+
+    pub fn wheel_delta(&self) -> f32 {
+        self.wheel_delta
+    }
+
     pub fn is_held(&self, key: Key) -> bool {
         self.held.contains(&key)
     }
@@ -114,6 +140,7 @@ impl InputState {
         self.just_released.clear();
         self.mouse_just_pressed.clear();
         self.mouse_just_released.clear();
+        self.wheel_delta = 0.0;
     }
 }
 
@@ -123,6 +150,126 @@ impl Default for InputState {
     }
 }
 
+/// Screen area a touch started in.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TouchRegion {
+    Joystick,
+    JumpButton,
+}
+
+/// Virtual joystick + jump button. Each region is owned by the first finger
+/// that lands in it until that finger lifts; extra fingers are ignored.
+///
+/// The joystick is relative: its center is wherever the finger first landed,
+/// and dragging more than `dead_zone_px` left or right of it holds that key.
+#[derive(Debug, Clone)]
+pub struct TouchControls {
+    /// Fraction of the screen width, from the left edge, used by the joystick.
+    pub joystick_width: f32,
+    pub dead_zone_px: f64,
+    /// Finger id and landing x of the joystick finger.
+    joystick: Option<(u64, f64)>,
+    joystick_key: Option<Key>,
+    jump_finger: Option<u64>,
+}
+
+impl TouchControls {
+    pub fn new() -> Self {
+        Self {
+            joystick_width: 0.5,
+            dead_zone_px: 24.0,
+            joystick: None,
+            joystick_key: None,
+            jump_finger: None,
+        }
+    }
+
+    pub fn region_at(&self, x: f64, screen_width: f64) -> TouchRegion {
+        if x < screen_width * self.joystick_width as f64 {
+            TouchRegion::Joystick
+        } else {
+            TouchRegion::JumpButton
+        }
+    }
+
+    /// A finger landed. Returns the region it took over, or `None` if that
+    /// region already has a finger.
+    pub fn touch_start(
+        &mut self,
+        id: u64,
+        position: (f64, f64),
+        screen_width: f64,
+        input: &mut InputState,
+    ) -> Option<TouchRegion> {
+        match self.region_at(position.0, screen_width) {
+            TouchRegion::Joystick if self.joystick.is_none() => {
+                self.joystick = Some((id, position.0));
+                Some(TouchRegion::Joystick)
+            }
+            TouchRegion::JumpButton if self.jump_finger.is_none() => {
+                self.jump_finger = Some(id);
+                input.key_down(Key::Space);
+                Some(TouchRegion::JumpButton)
+            }
+            _ => None,
+        }
+    }
+
+    pub fn touch_move(&mut self, id: u64, position: (f64, f64), input: &mut InputState) {
+        let Some((finger, origin_x)) = self.joystick else {
+            return;
+        };
+        if finger != id {
+            return;
+        }
+        let offset = position.0 - origin_x;
+        let key = if offset <= -self.dead_zone_px {
+            Some(Key::Left)
+        } else if offset >= self.dead_zone_px {
+            Some(Key::Right)
+        } else {
+            None
+        };
+        self.set_joystick_key(key, input);
+    }
+
+    /// A finger lifted or the touch was cancelled.
+    pub fn touch_end(&mut self, id: u64, input: &mut InputState) {
+        if matches!(self.joystick, Some((finger, _)) if finger == id) {
+            self.joystick = None;
+            self.set_joystick_key(None, input);
+        }
+        if self.jump_finger == Some(id) {
+            self.jump_finger = None;
+            input.key_up(Key::Space);
+        }
+    }
+
+    /// Direction the joystick is currently holding.
+    pub fn joystick_key(&self) -> Option<Key> {
+        self.joystick_key
+    }
+
+    fn set_joystick_key(&mut self, key: Option<Key>, input: &mut InputState) {
+        if key == self.joystick_key {
+            return;
+        }
+        if let Some(old) = self.joystick_key {
+            input.key_up(old);
+        }
+        if let Some(new) = key {
+            input.key_down(new);
+        }
+        self.joystick_key = key;
+    }
+}
+
+impl Default for TouchControls {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -220,6 +367,16 @@ mod tests {
         assert!(!input.is_mouse_just_released(MouseBtn::Left));
     }
 
+    #[test]
+    fn test_mouse_wheel_accumulates_until_end_frame() {
+        let mut input = InputState::new();
+        input.mouse_wheel(1.0);
+        input.mouse_wheel(0.5);
+        assert!((input.wheel_delta() - 1.5).abs() < f32::EPSILON);
+        input.end_frame();
+        assert_eq!(input.wheel_delta(), 0.0);
+    }
+
     #[test]
     fn test_mouse_position_tracking() {
         let mut input = InputState::new();
@@ -228,6 +385,52 @@ mod tests {
         assert!((input.mouse_position.1 - 200.0).abs() < f64::EPSILON);
     }
 
+    #[test]
+    fn test_touch_joystick_holds_direction_keys() {
+        let mut input = InputState::new();
+        let mut touch = TouchControls::new();
+        assert_eq!(
+            touch.touch_start(1, (100.0, 500.0), 800.0, &mut input),
+            Some(TouchRegion::Joystick)
+        );
+        touch.touch_move(1, (110.0, 500.0), &mut input);
+        assert!(!input.is_held(Key::Right), "inside dead zone");
+        touch.touch_move(1, (140.0, 500.0), &mut input);
+        assert!(input.is_held(Key::Right));
+        assert!(input.is_just_pressed(Key::Right));
+
+        touch.touch_move(1, (60.0, 500.0), &mut input);
+        assert!(!input.is_held(Key::Right));
+        assert!(input.is_held(Key::Left));
+        assert_eq!(touch.joystick_key(), Some(Key::Left));
+
+        touch.touch_end(1, &mut input);
+        assert!(!input.is_held(Key::Left));
+        assert_eq!(touch.joystick_key(), None);
+    }
+
+    #[test]
+    fn test_touch_jump_button_and_finger_ownership() {
+        let mut input = InputState::new();
+        let mut touch = TouchControls::new();
+        assert_eq!(
+            touch.touch_start(7, (700.0, 500.0), 800.0, &mut input),
+            Some(TouchRegion::JumpButton)
+        );
+        assert!(input.is_just_pressed(Key::Space));
+        // A second finger on the button is ignored, and lifting it does nothing.
+        assert_eq!(
+            touch.touch_start(8, (650.0, 400.0), 800.0, &mut input),
+            None
+        );
+        touch.touch_end(8, &mut input);
+        assert!(input.is_held(Key::Space));
+
+        touch.touch_end(7, &mut input);
+        assert!(!input.is_held(Key::Space));
+        assert!(input.is_just_released(Key::Space));
+    }
+
     #[test]
     fn test_multiple_keys_independent() {
         let mut input = InputState::new();
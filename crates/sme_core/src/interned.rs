@@ -0,0 +1,209 @@
+//! String interning for asset and sprite keys.
+//!
+//! `Id::new` maps a string to a small `Copy` handle, the same one every time
+//! in a process; comparing or hashing an `Id` never touches the string. The
+//! hot paths of mesh building (atlas lookups, texture keys in draw calls,
+//! animation clips) work on `Id`s, and strings are only interned where data
+//! is loaded. Interned strings live for the rest of the process, so intern
+//! names from content files, not per-frame or per-spawn strings.
+//!
+//! `InternedMap` is an insertion-ordered map keyed by `Id`: values live in a
+//! `Vec`, so iteration follows insertion order on every run and platform (a
+//! `HashMap`'s order changes with its random seed). Removal swaps the last
+//! entry into the gap, which is cheap and still the same on every run; the
+//! maps this is for (GPU textures by asset path) drop entries only when a
+//! reload leaves a texture unused.
+
+use std::collections::HashMap;
+use std::fmt;
+use std::sync::{LazyLock, RwLock};
+
+/// Interned string. Ids are handed out in interning order, so they are only
+/// stable within one process; persist `as_str()`, never the number.
+#[derive(Clone, Copy, PartialEq, Eq, Hash)]
+pub struct Id(u32);
+
+#[derive(Default)]
+struct Interner {
+    ids: HashMap<&'static str, Id>,
+    names: Vec<&'static str>,
+}
+
+static INTERNER: LazyLock<RwLock<Interner>> = LazyLock::new(Default::default);
+
+impl Id {
+    /// Intern `name`, or return its existing id.
+    pub fn new(name: &str) -> Self {
+        if let Some(id) = Self::get(name) {
+            return id;
+        }
+        let mut interner = INTERNER.write().expect("interner poisoned");
+        if let Some(&id) = interner.ids.get(name) {
+            return id;
+        }
+        let id = Id(interner.names.len() as u32);
+        let name: &'static str = Box::leak(name.into());
+        interner.names.push(name);
+        interner.ids.insert(name, id);
+        id
+    }
+
+    /// The id of `name` if it was interned, without interning it.
+    pub fn get(name: &str) -> Option<Self> {
+        INTERNER
+            .read()
+            .expect("interner poisoned")
+            .ids
+            .get(name)
+            .copied()
+    }
+
+    pub fn as_str(self) -> &'static str {
+        INTERNER.read().expect("interner poisoned").names[self.0 as usize]
+    }
+}
+
+impl From<&str> for Id {
+    fn from(name: &str) -> Self {
+        Self::new(name)
+    }
+}
+
+impl PartialEq<str> for Id {
+    fn eq(&self, other: &str) -> bool {
+        self.as_str() == other
+    }
+}
+
+impl PartialEq<&str> for Id {
+    fn eq(&self, other: &&str) -> bool {
+        self.as_str() == *other
+    }
+}
+
+impl fmt::Debug for Id {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        fmt::Debug::fmt(self.as_str(), f)
+    }
+}
+
+impl fmt::Display for Id {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct InternedMap<T> {
+    index: HashMap<Id, usize>,
+    entries: Vec<(Id, T)>,
+}
+
+impl<T> Default for InternedMap<T> {
+    fn default() -> Self {
+        Self {
+            index: HashMap::new(),
+            entries: Vec::new(),
+        }
+    }
+}
+
+impl<T> InternedMap<T> {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Insert or replace the value for `key`. A replaced value keeps its
+    /// place in iteration order.
+    pub fn insert(&mut self, key: Id, value: T) {
+        match self.index.get(&key) {
+            Some(&slot) => self.entries[slot].1 = value,
+            None => {
+                self.index.insert(key, self.entries.len());
+                self.entries.push((key, value));
+            }
+        }
+    }
+
+    /// Remove the value for `key`. The last entry takes its place in
+    /// iteration order.
+    pub fn remove(&mut self, key: Id) -> Option<T> {
+        let slot = self.index.remove(&key)?;
+        let (_, value) = self.entries.swap_remove(slot);
+        if let Some((moved, _)) = self.entries.get(slot) {
+            self.index.insert(*moved, slot);
+        }
+        Some(value)
+    }
+
+    pub fn get(&self, key: Id) -> Option<&T> {
+        let slot = *self.index.get(&key)?;
+        Some(&self.entries[slot].1)
+    }
+
+    pub fn get_mut(&mut self, key: Id) -> Option<&mut T> {
+        let slot = *self.index.get(&key)?;
+        Some(&mut self.entries[slot].1)
+    }
+
+    pub fn contains_key(&self, key: Id) -> bool {
+        self.index.contains_key(&key)
+    }
+
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+
+    /// Entries in insertion order.
+    pub fn iter(&self) -> impl Iterator<Item = (Id, &T)> {
+        self.entries.iter().map(|(key, value)| (*key, value))
+    }
+
+    /// Values in insertion order.
+    pub fn values(&self) -> impl Iterator<Item = &T> {
+        self.entries.iter().map(|(_, value)| value)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn interning_is_idempotent() {
+        let hero = Id::new("interned_test_hero");
+        assert_eq!(Id::new("interned_test_hero"), hero);
+        assert_eq!(Id::get("interned_test_hero"), Some(hero));
+        assert_ne!(Id::new("interned_test_bat"), hero);
+        assert_eq!(hero.as_str(), "interned_test_hero");
+        assert_eq!(hero, "interned_test_hero");
+        assert!(Id::get("interned_test_never_seen").is_none());
+    }
+
+    #[test]
+    fn iteration_follows_first_insertion() {
+        let [zebra, apple, mango] = ["zebra.png", "apple.png", "mango.png"].map(Id::new);
+        let mut map = InternedMap::new();
+        map.insert(zebra, 1);
+        map.insert(apple, 2);
+        map.insert(mango, 3);
+        map.insert(zebra, 10);
+
+        let keys: Vec<&str> = map.iter().map(|(key, _)| key.as_str()).collect();
+        assert_eq!(keys, ["zebra.png", "apple.png", "mango.png"]);
+        assert_eq!(map.values().copied().collect::<Vec<_>>(), [10, 2, 3]);
+        assert_eq!(map.get(apple), Some(&2));
+        assert!(map.get(Id::new("missing.png")).is_none());
+        assert_eq!(map.len(), 3);
+
+        assert_eq!(map.remove(zebra), Some(10));
+        assert_eq!(map.remove(zebra), None);
+        assert_eq!(map.get(mango), Some(&3));
+        let keys: Vec<&str> = map.iter().map(|(key, _)| key.as_str()).collect();
+        assert_eq!(keys, ["mango.png", "apple.png"]);
+    }
+}
@@ -1,4 +1,12 @@
 pub mod animation;
+pub mod assets;
 pub mod input;
+pub mod interned;
+pub mod loc;
+pub mod logging;
+pub mod migrate;
+pub mod pack;
+pub mod rng;
 pub mod tier;
 pub mod time;
+pub mod triple_buffer;
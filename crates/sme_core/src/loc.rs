@@ -0,0 +1,249 @@
+//! Localized string tables.
+//!
+//! Each language lives in its own JSON file (`assets/loc/<language>.json`)
+//! mapping dotted keys (`"menu.start"`) to display text. A table may name a
+//! `fallback` language; lookups walk the active language, then its fallback
+//! chain, then the default language, so a partially translated locale still
+//! shows something sensible. Keys missing everywhere resolve to the key itself
+//! so gaps are visible on screen instead of rendering as blank text.
+
+use serde::Deserialize;
+use std::collections::HashMap;
+use std::path::Path;
+
+use crate::assets;
+use crate::migrate::SchemaMigrations;
+
+/// String table schema versions and the upgrade path to the current one.
+pub const LOC_SCHEMA: SchemaMigrations = SchemaMigrations {
+    kind: "String table",
+    current: "0.1",
+    steps: &[],
+};
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct StringTable {
+    pub version: String,
+    pub language: String,
+    #[serde(default)]
+    pub fallback: Option<String>,
+    pub strings: HashMap<String, String>,
+}
+
+pub fn load_string_table(path: &Path) -> Result<StringTable, String> {
+    let raw = assets::read_to_string(path)
+        .map_err(|e| format!("Failed to read string table {}: {e}", path.display()))?;
+    let mut value: serde_json::Value = serde_json::from_str(&raw)
+        .map_err(|e| format!("Failed to parse string table {}: {e}", path.display()))?;
+    LOC_SCHEMA.migrate_with_warning(&mut value, &path.display().to_string())?;
+    let table: StringTable = serde_json::from_value(value)
+        .map_err(|e| format!("Failed to parse string table {}: {e}", path.display()))?;
+    validate_string_table(&table)?;
+    Ok(table)
+}
+
+fn validate_string_table(table: &StringTable) -> Result<(), String> {
+    if table.language.is_empty() {
+        return Err("String table validation failed: language is empty".to_string());
+    }
+    if table.fallback.as_deref() == Some(table.language.as_str()) {
+        return Err(format!(
+            "String table validation failed: '{}' lists itself as fallback",
+            table.language
+        ));
+    }
+    if let Some(key) = table.strings.keys().find(|key| key.is_empty()) {
+        return Err(format!(
+            "String table validation failed: empty key in '{}' ({key:?})",
+            table.language
+        ));
+    }
+    Ok(())
+}
+
+/// All loaded string tables plus the active language.
+#[derive(Debug, Clone)]
+pub struct Localization {
+    tables: HashMap<String, StringTable>,
+    language: String,
+    default_language: String,
+}
+
+impl Localization {
+    pub fn new(default_language: &str) -> Self {
+        Self {
+            tables: HashMap::new(),
+            language: default_language.to_string(),
+            default_language: default_language.to_string(),
+        }
+    }
+
+    /// Add or replace (on hot reload) the table for `table.language`.
+    pub fn insert_table(&mut self, table: StringTable) {
+        self.tables.insert(table.language.clone(), table);
+    }
+
+    pub fn language(&self) -> &str {
+        &self.language
+    }
+
+    pub fn set_language(&mut self, language: &str) {
+        if !self.tables.contains_key(language) {
+            log::warn!("No string table loaded for language '{language}'");
+        }
+        self.language = language.to_string();
+    }
+
+    /// Languages consulted for a lookup, in order. Stops at the first repeat so
+    /// a fallback cycle between files cannot loop.
+    pub fn fallback_chain(&self) -> Vec<&str> {
+        let mut chain: Vec<&str> = Vec::new();
+        let mut next = Some(self.language.as_str());
+        while let Some(language) = next {
+            if chain.contains(&language) {
+                break;
+            }
+            chain.push(language);
+            next = self
+                .tables
+                .get(language)
+                .and_then(|table| table.fallback.as_deref());
+        }
+        if !chain.contains(&self.default_language.as_str()) {
+            chain.push(&self.default_language);
+        }
+        chain
+    }
+
+    pub fn lookup(&self, key: &str) -> Option<&str> {
+        self.fallback_chain().into_iter().find_map(|language| {
+            self.tables
+                .get(language)
+                .and_then(|table| table.strings.get(key))
+                .map(String::as_str)
+        })
+    }
+
+    /// Localized text for `key`, or `key` itself when no table has it.
+    pub fn text<'a>(&'a self, key: &'a str) -> &'a str {
+        self.lookup(key).unwrap_or(key)
+    }
+
+    /// Every key resolved through the fallback chain, e.g. for handing the
+    /// whole table to a script runtime in one pass.
+    pub fn resolved_strings(&self) -> HashMap<String, String> {
+        let mut resolved = HashMap::new();
+        for language in self.fallback_chain().into_iter().rev() {
+            if let Some(table) = self.tables.get(language) {
+                for (key, text) in &table.strings {
+                    resolved.insert(key.clone(), text.clone());
+                }
+            }
+        }
+        resolved
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::fs;
+    use std::time::{SystemTime, UNIX_EPOCH};
+
+    fn temp_file_path(name_hint: &str) -> std::path::PathBuf {
+        let nanos = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .expect("system time before unix epoch")
+            .as_nanos();
+        std::env::temp_dir().join(format!(
+            "sme_loc_test_{}_{}_{}.json",
+            name_hint,
+            std::process::id(),
+            nanos
+        ))
+    }
+
+    fn table(language: &str, fallback: Option<&str>, strings: &[(&str, &str)]) -> StringTable {
+        StringTable {
+            version: "0.1".to_string(),
+            language: language.to_string(),
+            fallback: fallback.map(str::to_string),
+            strings: strings
+                .iter()
+                .map(|(k, v)| (k.to_string(), v.to_string()))
+                .collect(),
+        }
+    }
+
+    fn sample_localization() -> Localization {
+        let mut loc = Localization::new("en");
+        loc.insert_table(table(
+            "en",
+            None,
+            &[
+                ("menu.start", "Start"),
+                ("menu.quit", "Quit"),
+                ("hud.score", "Score"),
+            ],
+        ));
+        loc.insert_table(table("es", None, &[("menu.start", "Empezar")]));
+        loc.insert_table(table("es-MX", Some("es"), &[("menu.quit", "Salir")]));
+        loc
+    }
+
+    #[test]
+    fn lookup_walks_fallback_chain_then_default() {
+        let mut loc = sample_localization();
+        loc.set_language("es-MX");
+        assert_eq!(loc.fallback_chain(), ["es-MX", "es", "en"]);
+        assert_eq!(loc.text("menu.quit"), "Salir");
+        assert_eq!(loc.text("menu.start"), "Empezar");
+        assert_eq!(loc.text("hud.score"), "Score");
+        assert_eq!(loc.text("missing.key"), "missing.key");
+    }
+
+    #[test]
+    fn resolved_strings_prefers_most_specific_language() {
+        let mut loc = sample_localization();
+        loc.set_language("es-MX");
+        let resolved = loc.resolved_strings();
+        assert_eq!(resolved["menu.start"], "Empezar");
+        assert_eq!(resolved["menu.quit"], "Salir");
+        assert_eq!(resolved["hud.score"], "Score");
+    }
+
+    #[test]
+    fn fallback_cycle_terminates() {
+        let mut loc = Localization::new("en");
+        loc.insert_table(table("a", Some("b"), &[]));
+        loc.insert_table(table("b", Some("a"), &[]));
+        loc.set_language("a");
+        assert_eq!(loc.fallback_chain(), ["a", "b", "en"]);
+    }
+
+    #[test]
+    fn load_string_table_parses_and_validates() {
+        let path = temp_file_path("valid");
+        fs::write(
+            &path,
+            r#"{ "version": "0.1", "language": "fr", "fallback": "en",
+                 "strings": { "menu.start": "Commencer" } }"#,
+        )
+        .expect("write temp file");
+        let table = load_string_table(&path).expect("should parse");
+        assert_eq!(table.language, "fr");
+        assert_eq!(table.fallback.as_deref(), Some("en"));
+        assert_eq!(table.strings["menu.start"], "Commencer");
+        let _ = fs::remove_file(path);
+
+        let path = temp_file_path("self_fallback");
+        fs::write(
+            &path,
+            r#"{ "version": "0.1", "language": "fr", "fallback": "fr", "strings": {} }"#,
+        )
+        .expect("write temp file");
+        let err = load_string_table(&path).expect_err("self fallback should fail");
+        assert!(err.contains("lists itself as fallback"));
+        let _ = fs::remove_file(path);
+    }
+}
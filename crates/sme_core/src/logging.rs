@@ -0,0 +1,455 @@
+//! Engine logging: a `log` backend configured from a TOML file.
+//!
+//! `config/logging.toml` sets a default level, per-module levels and an
+//! optional rolling file sink:
+//!
+//! ```toml
+//! level = "info"
+//!
+//! [modules]
+//! wgpu_core = "warn"
+//! "sme_game::lua_bridge" = "debug"
+//!
+//! [file]
+//! dir = "logs"        # engine.log, engine.1.log, ...
+//! max_size_kb = 1024  # rotate once engine.log would pass this
+//! keep = 5            # rotated files kept besides engine.log
+//! ```
+//!
+//! A module entry applies to its target and everything below it
+//! (`sme_game` covers `sme_game::scene`); the longest match wins. `RUST_LOG`
+//! still works and overrides the file, in its simple `level,module=level`
+//! form. Every line goes to stderr and, if configured, the file sink.
+//!
+//! The level table stays editable after `init`: the debug overlay's Logging
+//! section reads it with `levels()` and writes it back with `set_levels()`.
+//! The last `RECENT_LINES` lines are also kept in memory for crash reports
+//! (`recent_lines()`).
+
+use std::collections::{BTreeMap, VecDeque};
+use std::fs::{File, OpenOptions};
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use std::sync::{Mutex, OnceLock, RwLock};
+
+use log::{LevelFilter, Log, Metadata, Record};
+use serde::Deserialize;
+use web_time::Instant;
+
+/// Where `init_from_file` looks, relative to the working directory.
+pub const LOG_CONFIG_PATH: &str = "config/logging.toml";
+
+/// Formatted lines kept in memory for `recent_lines`.
+pub const RECENT_LINES: usize = 100;
+
+/// Name of the live log file inside the sink directory.
+const LOG_FILE_STEM: &str = "engine";
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct LogConfig {
+    pub levels: LogLevels,
+    pub file: Option<FileSink>,
+}
+
+impl Default for LogConfig {
+    fn default() -> Self {
+        Self {
+            levels: LogLevels {
+                default: LevelFilter::Info,
+                modules: BTreeMap::new(),
+            },
+            file: None,
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct FileSink {
+    pub dir: PathBuf,
+    pub max_bytes: u64,
+    /// Rotated files kept besides the live one.
+    pub keep: usize,
+}
+
+/// Default level plus per-module overrides, keyed by target prefix.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct LogLevels {
+    pub default: LevelFilter,
+    pub modules: BTreeMap<String, LevelFilter>,
+}
+
+impl LogLevels {
+    /// Level for `target`: its longest configured module prefix, else the
+    /// default.
+    pub fn level_for(&self, target: &str) -> LevelFilter {
+        self.modules
+            .iter()
+            .filter(|(module, _)| {
+                target
+                    .strip_prefix(module.as_str())
+                    .is_some_and(|rest| rest.is_empty() || rest.starts_with("::"))
+            })
+            .max_by_key(|(module, _)| module.len())
+            .map_or(self.default, |(_, level)| *level)
+    }
+
+    fn most_verbose(&self) -> LevelFilter {
+        self.modules
+            .values()
+            .copied()
+            .fold(self.default, std::cmp::max)
+    }
+}
+
+#[derive(Debug, Default, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+struct LogConfigFile {
+    level: Option<String>,
+    modules: BTreeMap<String, String>,
+    file: Option<FileSinkFile>,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+struct FileSinkFile {
+    #[serde(default = "default_log_dir")]
+    dir: PathBuf,
+    #[serde(default = "default_max_size_kb")]
+    max_size_kb: u64,
+    #[serde(default = "default_keep")]
+    keep: usize,
+}
+
+fn default_log_dir() -> PathBuf {
+    PathBuf::from("logs")
+}
+
+fn default_max_size_kb() -> u64 {
+    1024
+}
+
+fn default_keep() -> usize {
+    5
+}
+
+pub fn parse_level(text: &str) -> Result<LevelFilter, String> {
+    text.trim()
+        .parse()
+        .map_err(|_| format!("unknown log level '{text}'"))
+}
+
+pub fn parse_log_config(text: &str) -> Result<LogConfig, String> {
+    let file: LogConfigFile = toml::from_str(text).map_err(|e| e.to_string())?;
+    let mut config = LogConfig::default();
+    if let Some(level) = &file.level {
+        config.levels.default = parse_level(level)?;
+    }
+    for (module, level) in &file.modules {
+        let level = parse_level(level).map_err(|e| format!("module '{module}': {e}"))?;
+        config.levels.modules.insert(module.clone(), level);
+    }
+    if let Some(sink) = file.file {
+        if sink.max_size_kb == 0 {
+            return Err("file.max_size_kb must be at least 1".to_string());
+        }
+        config.file = Some(FileSink {
+            dir: sink.dir,
+            max_bytes: sink.max_size_kb * 1024,
+            keep: sink.keep,
+        });
+    }
+    Ok(config)
+}
+
+/// Apply a `RUST_LOG`-style spec (`"warn,sme_game=debug"`) on top of
+/// `levels`.
+pub fn apply_env_filter(levels: &mut LogLevels, spec: &str) -> Result<(), String> {
+    for directive in spec.split(',').map(str::trim).filter(|d| !d.is_empty()) {
+        match directive.split_once('=') {
+            Some((module, level)) => {
+                levels
+                    .modules
+                    .insert(module.trim().to_string(), parse_level(level)?);
+            }
+            None => levels.default = parse_level(directive)?,
+        }
+    }
+    Ok(())
+}
+
+/// Install the engine logger from `path` (defaults if the file is missing),
+/// with `RUST_LOG` applied on top. Problems with either are logged as
+/// warnings once the logger is up.
+pub fn init_from_file(path: &Path) {
+    let mut problems = Vec::new();
+    let mut config = match std::fs::read_to_string(path) {
+        Ok(text) => parse_log_config(&text).unwrap_or_else(|e| {
+            problems.push(format!("Ignoring {}: {e}", path.display()));
+            LogConfig::default()
+        }),
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => LogConfig::default(),
+        Err(e) => {
+            problems.push(format!("Failed to read {}: {e}", path.display()));
+            LogConfig::default()
+        }
+    };
+    if let Ok(spec) = std::env::var("RUST_LOG") {
+        if let Err(e) = apply_env_filter(&mut config.levels, &spec) {
+            problems.push(format!("Ignoring part of RUST_LOG: {e}"));
+        }
+    }
+    if let Err(e) = init(config) {
+        problems.push(e);
+    }
+    for problem in problems {
+        log::warn!("{problem}");
+    }
+}
+
+/// Install the engine logger. Fails if a logger is already installed; a
+/// file sink that cannot be opened is reported but stderr logging still
+/// starts.
+pub fn init(config: LogConfig) -> Result<(), String> {
+    let mut sink_error = None;
+    let file = config.file.and_then(|sink| match RollingFile::open(sink) {
+        Ok(file) => Some(Mutex::new(file)),
+        Err(e) => {
+            sink_error = Some(e);
+            None
+        }
+    });
+    let max_level = config.levels.most_verbose();
+    let logger = LOGGER.get_or_init(|| EngineLogger {
+        levels: RwLock::new(config.levels),
+        file,
+        recent: Mutex::new(VecDeque::with_capacity(RECENT_LINES)),
+        start: Instant::now(),
+    });
+    log::set_logger(logger).map_err(|e| format!("Logger already installed: {e}"))?;
+    log::set_max_level(max_level);
+    match sink_error {
+        Some(e) => Err(e),
+        None => Ok(()),
+    }
+}
+
+/// The live level table, or `None` before `init`.
+pub fn levels() -> Option<LogLevels> {
+    let logger = LOGGER.get()?;
+    Some(logger.levels.read().ok()?.clone())
+}
+
+/// Replace the live level table.
+pub fn set_levels(levels: LogLevels) {
+    let Some(logger) = LOGGER.get() else {
+        return;
+    };
+    log::set_max_level(levels.most_verbose());
+    if let Ok(mut live) = logger.levels.write() {
+        *live = levels;
+    }
+}
+
+/// The last `RECENT_LINES` lines logged, oldest first, each ending in a
+/// newline. Empty before `init`, or if the buffer is locked by the calling
+/// thread (a panic while logging), so this is safe to call from a panic hook.
+pub fn recent_lines() -> Vec<String> {
+    let Some(logger) = LOGGER.get() else {
+        return Vec::new();
+    };
+    match logger.recent.try_lock() {
+        Ok(recent) => recent.iter().cloned().collect(),
+        Err(_) => Vec::new(),
+    }
+}
+
+static LOGGER: OnceLock<EngineLogger> = OnceLock::new();
+
+struct EngineLogger {
+    levels: RwLock<LogLevels>,
+    file: Option<Mutex<RollingFile>>,
+    recent: Mutex<VecDeque<String>>,
+    start: Instant,
+}
+
+impl Log for EngineLogger {
+    fn enabled(&self, metadata: &Metadata) -> bool {
+        self.levels
+            .read()
+            .is_ok_and(|levels| metadata.level() <= levels.level_for(metadata.target()))
+    }
+
+    fn log(&self, record: &Record) {
+        if !self.enabled(record.metadata()) {
+            return;
+        }
+        let line = format!(
+            "[{:>9.3}s {:<5} {}] {}\n",
+            self.start.elapsed().as_secs_f64(),
+            record.level(),
+            record.target(),
+            record.args()
+        );
+        let _ = std::io::stderr().write_all(line.as_bytes());
+        if let Some(file) = &self.file {
+            if let Ok(mut file) = file.lock() {
+                file.write_line(&line);
+            }
+        }
+        if let Ok(mut recent) = self.recent.lock() {
+            if recent.len() == RECENT_LINES {
+                recent.pop_front();
+            }
+            recent.push_back(line);
+        }
+    }
+
+    fn flush(&self) {
+        if let Some(file) = &self.file {
+            if let Ok(mut file) = file.lock() {
+                let _ = file.file.flush();
+            }
+        }
+    }
+}
+
+/// `engine.log` in the sink directory, rotated to `engine.1.log`,
+/// `engine.2.log`, ... when it would grow past `max_bytes`.
+struct RollingFile {
+    sink: FileSink,
+    file: File,
+    written: u64,
+}
+
+impl RollingFile {
+    fn open(sink: FileSink) -> Result<Self, String> {
+        std::fs::create_dir_all(&sink.dir)
+            .map_err(|e| format!("Failed to create log dir {}: {e}", sink.dir.display()))?;
+        let path = rotated_path(&sink.dir, 0);
+        let file = OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(&path)
+            .map_err(|e| format!("Failed to open log file {}: {e}", path.display()))?;
+        let written = file.metadata().map_or(0, |m| m.len());
+        Ok(Self {
+            sink,
+            file,
+            written,
+        })
+    }
+
+    fn write_line(&mut self, line: &str) {
+        let len = line.len() as u64;
+        if self.written > 0 && self.written + len > self.sink.max_bytes {
+            self.rotate();
+        }
+        if self.file.write_all(line.as_bytes()).is_ok() {
+            self.written += len;
+        }
+    }
+
+    fn rotate(&mut self) {
+        let dir = &self.sink.dir;
+        if self.sink.keep > 0 {
+            let _ = std::fs::remove_file(rotated_path(dir, self.sink.keep));
+            for n in (0..self.sink.keep).rev() {
+                let _ = std::fs::rename(rotated_path(dir, n), rotated_path(dir, n + 1));
+            }
+        }
+        let reopened = OpenOptions::new()
+            .create(true)
+            .write(true)
+            .truncate(true)
+            .open(rotated_path(dir, 0));
+        if let Ok(file) = reopened {
+            self.file = file;
+            self.written = 0;
+        }
+    }
+}
+
+/// `engine.log` for 0, `engine.<n>.log` otherwise.
+fn rotated_path(dir: &Path, n: usize) -> PathBuf {
+    if n == 0 {
+        dir.join(format!("{LOG_FILE_STEM}.log"))
+    } else {
+        dir.join(format!("{LOG_FILE_STEM}.{n}.log"))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::time::{SystemTime, UNIX_EPOCH};
+
+    fn temp_dir(name_hint: &str) -> PathBuf {
+        let nanos = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .expect("system time before unix epoch")
+            .as_nanos();
+        std::env::temp_dir().join(format!(
+            "sme_logging_test_{}_{}_{}",
+            name_hint,
+            std::process::id(),
+            nanos
+        ))
+    }
+
+    #[test]
+    fn config_parses_and_longest_module_wins() {
+        let config = parse_log_config(
+            r#"
+            level = "warn"
+            [modules]
+            sme_game = "info"
+            "sme_game::lua_bridge" = "trace"
+            [file]
+            max_size_kb = 4
+            "#,
+        )
+        .expect("valid config");
+        let levels = &config.levels;
+        assert_eq!(levels.level_for("wgpu_core::device"), LevelFilter::Warn);
+        assert_eq!(levels.level_for("sme_game"), LevelFilter::Info);
+        assert_eq!(levels.level_for("sme_game::scene"), LevelFilter::Info);
+        assert_eq!(levels.level_for("sme_game::lua_bridge"), LevelFilter::Trace);
+        assert_eq!(levels.level_for("sme_game_extra"), LevelFilter::Warn);
+        assert_eq!(
+            config.file,
+            Some(FileSink {
+                dir: PathBuf::from("logs"),
+                max_bytes: 4096,
+                keep: 5,
+            })
+        );
+
+        let mut levels = config.levels;
+        apply_env_filter(&mut levels, "error,sme_game=debug").expect("valid spec");
+        assert_eq!(levels.default, LevelFilter::Error);
+        assert_eq!(levels.level_for("sme_game::scene"), LevelFilter::Debug);
+
+        let err = parse_log_config("level = \"loud\"").expect_err("bad level");
+        assert!(err.contains("unknown log level 'loud'"), "{err}");
+    }
+
+    #[test]
+    fn rolling_file_keeps_the_newest_files() {
+        let dir = temp_dir("rotate");
+        let mut file = RollingFile::open(FileSink {
+            dir: dir.clone(),
+            max_bytes: 10,
+            keep: 2,
+        })
+        .expect("open sink");
+        for line in ["aaaaaa\n", "bbbbbb\n", "cccccc\n", "dddddd\n"] {
+            file.write_line(line);
+        }
+        let read = |n| std::fs::read_to_string(rotated_path(&dir, n)).unwrap_or_default();
+        assert_eq!(read(0), "dddddd\n");
+        assert_eq!(read(1), "cccccc\n");
+        assert_eq!(read(2), "bbbbbb\n");
+        assert!(!rotated_path(&dir, 3).exists());
+        let _ = std::fs::remove_dir_all(dir);
+    }
+}
@@ -0,0 +1,169 @@
+//! Schema migrations for versioned content files.
+//!
+//! Every content format (scene, atlas, animation, collision) carries a
+//! `version` string. Instead of hard-rejecting anything but the newest version,
+//! loaders parse the raw JSON into a `serde_json::Value`, run it through the
+//! format's migration chain until it reaches the current version, and only then
+//! deserialize into the typed model. Older content therefore keeps loading (with
+//! a warning) each time a schema advances.
+//!
+//! Versions with no registered upgrade path -- including versions newer than the
+//! engine -- are still rejected, so content authored for a future build fails
+//! loudly instead of being silently misread.
+
+use serde_json::Value;
+
+/// One upgrade step between two adjacent schema versions.
+pub struct Migration {
+    pub from: &'static str,
+    pub to: &'static str,
+    /// Rewrites the raw JSON from `from` to `to`. The `version` field is
+    /// updated by the framework after `apply` succeeds.
+    pub apply: fn(&mut Value) -> Result<(), String>,
+}
+
+/// The migration chain for one content format.
+pub struct SchemaMigrations {
+    /// Capitalized format name used in error messages (e.g. "Scene").
+    pub kind: &'static str,
+    pub current: &'static str,
+    pub steps: &'static [Migration],
+}
+
+impl SchemaMigrations {
+    /// Upgrade `value` in place to the current version.
+    ///
+    /// Returns the version the content started at when a migration happened,
+    /// or `None` when it was already current.
+    pub fn migrate(&self, value: &mut Value) -> Result<Option<String>, String> {
+        let original = read_version(value)
+            .ok_or_else(|| format!("{} validation failed: missing version", self.kind))?;
+        let mut version = original.clone();
+
+        // Each step must move forward; bounding the loop guards against a
+        // miswired chain that cycles back on itself.
+        for _ in 0..=self.steps.len() {
+            if version == self.current {
+                return Ok(if version == original {
+                    None
+                } else {
+                    Some(original)
+                });
+            }
+            let Some(step) = self.steps.iter().find(|step| step.from == version) else {
+                break;
+            };
+            (step.apply)(value).map_err(|e| {
+                format!(
+                    "{} migration {} -> {} failed: {e}",
+                    self.kind, step.from, step.to
+                )
+            })?;
+            if let Some(object) = value.as_object_mut() {
+                object.insert("version".to_string(), Value::from(step.to));
+            }
+            version = step.to.to_string();
+        }
+
+        Err(format!(
+            "{} validation failed: unsupported version '{}'",
+            self.kind, original
+        ))
+    }
+
+    /// Like [`SchemaMigrations::migrate`], but logs a warning naming `source`
+    /// when an upgrade happened so authors know to rewrite the file.
+    pub fn migrate_with_warning(&self, value: &mut Value, source: &str) -> Result<(), String> {
+        if let Some(from) = self.migrate(value)? {
+            log::warn!(
+                "{} '{}' uses schema {} -- migrated to {} in memory. \
+                 Run with --migrate-in-place to update the file.",
+                self.kind,
+                source,
+                from,
+                self.current
+            );
+        }
+        Ok(())
+    }
+}
+
+fn read_version(value: &Value) -> Option<String> {
+    value.get("version")?.as_str().map(str::to_string)
+}
+
+/// Migration step for schema bumps that only added optional fields: the old
+/// shape is already valid under the new version.
+pub fn additive_only(_value: &mut Value) -> Result<(), String> {
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn rename_name_to_id(value: &mut Value) -> Result<(), String> {
+        let object = value.as_object_mut().ok_or("expected object")?;
+        let name = object.remove("name").ok_or("missing 'name'")?;
+        object.insert("id".to_string(), name);
+        Ok(())
+    }
+
+    const TEST_SCHEMA: SchemaMigrations = SchemaMigrations {
+        kind: "Widget",
+        current: "0.3",
+        steps: &[
+            Migration {
+                from: "0.1",
+                to: "0.2",
+                apply: rename_name_to_id,
+            },
+            Migration {
+                from: "0.2",
+                to: "0.3",
+                apply: additive_only,
+            },
+        ],
+    };
+
+    #[test]
+    fn current_version_is_untouched() {
+        let mut value = json!({ "version": "0.3", "id": "w" });
+        let before = value.clone();
+        assert_eq!(TEST_SCHEMA.migrate(&mut value).expect("migrate"), None);
+        assert_eq!(value, before);
+    }
+
+    #[test]
+    fn old_version_runs_full_chain() {
+        let mut value = json!({ "version": "0.1", "name": "w" });
+        let from = TEST_SCHEMA.migrate(&mut value).expect("migrate");
+        assert_eq!(from.as_deref(), Some("0.1"));
+        assert_eq!(value["version"], "0.3");
+        assert_eq!(value["id"], "w");
+        assert!(value.get("name").is_none());
+    }
+
+    #[test]
+    fn unknown_version_is_rejected() {
+        let mut value = json!({ "version": "9.9" });
+        let err = TEST_SCHEMA.migrate(&mut value).expect_err("future version");
+        assert!(err.contains("unsupported version '9.9'"));
+    }
+
+    #[test]
+    fn missing_version_is_rejected() {
+        let mut value = json!({ "id": "w" });
+        let err = TEST_SCHEMA.migrate(&mut value).expect_err("no version");
+        assert!(err.contains("missing version"));
+    }
+
+    #[test]
+    fn failing_step_reports_versions() {
+        let mut value = json!({ "version": "0.1" });
+        let err = TEST_SCHEMA.migrate(&mut value).expect_err("step fails");
+        assert!(err.contains("0.1 -> 0.2"));
+        assert!(err.contains("missing 'name'"));
+    }
+}
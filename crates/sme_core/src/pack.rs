@@ -0,0 +1,324 @@
+//! `.smepack` bundle format for shipping builds.
+//!
+//! Dev builds read loose JSON/PNG files straight from `assets/`. Release builds
+//! instead ship a single pack file holding every asset, deflate-compressed and
+//! checksummed so a truncated or hand-edited pack is rejected on read.
+//!
+//! Layout (all integers little-endian):
+//!
+//! ```text
+//! header   magic "SMEPACK\0" | format u32 | entry_count u32 | index_offset u64
+//! entries  deflate streams, back to back
+//! index    per entry: path_len u16 | path (UTF-8) | offset u64 |
+//!          stored_len u64 | raw_len u64 | checksum u64
+//! ```
+//!
+//! Entry paths are the same working-directory-relative paths the loaders use
+//! (e.g. `assets/scenes/m5_scene.json`), normalized to `/` separators.
+
+use std::collections::HashMap;
+use std::fs;
+use std::path::{Component, Path};
+
+pub const PACK_MAGIC: &[u8; 8] = b"SMEPACK\0";
+pub const PACK_FORMAT_VERSION: u32 = 1;
+pub const PACK_EXTENSION: &str = "smepack";
+
+const HEADER_LEN: usize = 24;
+const COMPRESSION_LEVEL: u8 = 6;
+
+#[derive(Debug, Clone, Copy)]
+struct PackEntry {
+    offset: u64,
+    stored_len: u64,
+    raw_len: u64,
+    checksum: u64,
+}
+
+/// A pack file loaded into memory. Entries are decompressed on demand.
+pub struct PackArchive {
+    data: Vec<u8>,
+    entries: HashMap<String, PackEntry>,
+}
+
+impl PackArchive {
+    pub fn open(path: &Path) -> Result<Self, String> {
+        let data =
+            fs::read(path).map_err(|e| format!("Failed to read pack {}: {e}", path.display()))?;
+        Self::from_bytes(data).map_err(|e| format!("{e} ({})", path.display()))
+    }
+
+    pub fn from_bytes(data: Vec<u8>) -> Result<Self, String> {
+        if data.len() < HEADER_LEN || &data[..8] != PACK_MAGIC {
+            return Err("Pack validation failed: not an .smepack file".to_string());
+        }
+        let mut header = ByteReader::new(&data[8..HEADER_LEN]);
+        let format = header.u32()?;
+        if format != PACK_FORMAT_VERSION {
+            return Err(format!(
+                "Pack validation failed: unsupported format version {format}"
+            ));
+        }
+        let entry_count = header.u32()?;
+        let index_offset = header.u64()?;
+
+        let index_start = usize::try_from(index_offset)
+            .ok()
+            .filter(|&start| start >= HEADER_LEN && start <= data.len())
+            .ok_or("Pack validation failed: index offset out of range")?;
+        let mut index = ByteReader::new(&data[index_start..]);
+        let mut entries = HashMap::new();
+        for _ in 0..entry_count {
+            let path_len = index.u16()? as usize;
+            let path = std::str::from_utf8(index.bytes(path_len)?)
+                .map_err(|_| "Pack validation failed: entry path is not UTF-8")?
+                .to_string();
+            let entry = PackEntry {
+                offset: index.u64()?,
+                stored_len: index.u64()?,
+                raw_len: index.u64()?,
+                checksum: index.u64()?,
+            };
+            let end = entry.offset.checked_add(entry.stored_len);
+            if entry.offset < HEADER_LEN as u64 || end.is_none_or(|end| end > index_offset) {
+                return Err(format!(
+                    "Pack validation failed: entry '{path}' lies outside the data section"
+                ));
+            }
+            if entries.insert(path.clone(), entry).is_some() {
+                return Err(format!("Pack validation failed: duplicate entry '{path}'"));
+            }
+        }
+
+        Ok(Self { data, entries })
+    }
+
+    pub fn contains(&self, path: &str) -> bool {
+        self.entries.contains_key(path)
+    }
+
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+
+    /// Entry paths in sorted order.
+    pub fn entry_paths(&self) -> Vec<&str> {
+        let mut paths: Vec<&str> = self.entries.keys().map(String::as_str).collect();
+        paths.sort_unstable();
+        paths
+    }
+
+    /// Decompress one entry and verify its checksum.
+    pub fn read(&self, path: &str) -> Result<Vec<u8>, String> {
+        let entry = self
+            .entries
+            .get(path)
+            .ok_or_else(|| format!("'{path}' is not in the pack"))?;
+        let start = entry.offset as usize;
+        let stored = &self.data[start..start + entry.stored_len as usize];
+        let raw =
+            miniz_oxide::inflate::decompress_to_vec_with_limit(stored, entry.raw_len as usize)
+                .map_err(|e| format!("Pack entry '{path}' is corrupt: {e:?}"))?;
+        if raw.len() as u64 != entry.raw_len || checksum(&raw) != entry.checksum {
+            return Err(format!("Pack entry '{path}' failed checksum verification"));
+        }
+        Ok(raw)
+    }
+}
+
+/// Builds a pack in memory. Entries keep insertion order in the output.
+#[derive(Default)]
+pub struct PackWriter {
+    entries: Vec<(String, Vec<u8>)>,
+}
+
+impl PackWriter {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn add(&mut self, path: &str, contents: Vec<u8>) -> Result<(), String> {
+        if path.is_empty() || path.len() > u16::MAX as usize {
+            return Err(format!("Invalid pack entry path '{path}'"));
+        }
+        if self.entries.iter().any(|(existing, _)| existing == path) {
+            return Err(format!("Duplicate pack entry '{path}'"));
+        }
+        self.entries.push((path.to_string(), contents));
+        Ok(())
+    }
+
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+
+    pub fn finish(self) -> Vec<u8> {
+        let mut out = vec![0u8; HEADER_LEN];
+        let mut index = Vec::new();
+        for (path, raw) in &self.entries {
+            let stored = miniz_oxide::deflate::compress_to_vec(raw, COMPRESSION_LEVEL);
+            index.extend_from_slice(&(path.len() as u16).to_le_bytes());
+            index.extend_from_slice(path.as_bytes());
+            index.extend_from_slice(&(out.len() as u64).to_le_bytes());
+            index.extend_from_slice(&(stored.len() as u64).to_le_bytes());
+            index.extend_from_slice(&(raw.len() as u64).to_le_bytes());
+            index.extend_from_slice(&checksum(raw).to_le_bytes());
+            out.extend_from_slice(&stored);
+        }
+        let index_offset = out.len() as u64;
+        out.extend_from_slice(&index);
+
+        out[..8].copy_from_slice(PACK_MAGIC);
+        out[8..12].copy_from_slice(&PACK_FORMAT_VERSION.to_le_bytes());
+        out[12..16].copy_from_slice(&(self.entries.len() as u32).to_le_bytes());
+        out[16..24].copy_from_slice(&index_offset.to_le_bytes());
+        out
+    }
+}
+
+/// Normalize a loader path into its pack entry key: drops `.` components and
+/// joins the rest with `/` so keys match across platforms.
+pub fn normalize_pack_path(path: &Path) -> String {
+    path.components()
+        .filter_map(|component| match component {
+            Component::Normal(part) => Some(part.to_string_lossy().into_owned()),
+            Component::ParentDir => Some("..".to_string()),
+            _ => None,
+        })
+        .collect::<Vec<_>>()
+        .join("/")
+}
+
+/// FNV-1a 64. Catches corruption and casual edits; not a signature.
+fn checksum(bytes: &[u8]) -> u64 {
+    bytes.iter().fold(0xcbf2_9ce4_8422_2325, |hash, &b| {
+        (hash ^ b as u64).wrapping_mul(0x0000_0100_0000_01b3)
+    })
+}
+
+struct ByteReader<'a> {
+    bytes: &'a [u8],
+}
+
+impl<'a> ByteReader<'a> {
+    fn new(bytes: &'a [u8]) -> Self {
+        Self { bytes }
+    }
+
+    fn bytes(&mut self, len: usize) -> Result<&'a [u8], String> {
+        if self.bytes.len() < len {
+            return Err("Pack validation failed: truncated index".to_string());
+        }
+        let (head, tail) = self.bytes.split_at(len);
+        self.bytes = tail;
+        Ok(head)
+    }
+
+    fn u16(&mut self) -> Result<u16, String> {
+        Ok(u16::from_le_bytes(self.bytes(2)?.try_into().unwrap()))
+    }
+
+    fn u32(&mut self) -> Result<u32, String> {
+        Ok(u32::from_le_bytes(self.bytes(4)?.try_into().unwrap()))
+    }
+
+    fn u64(&mut self) -> Result<u64, String> {
+        Ok(u64::from_le_bytes(self.bytes(8)?.try_into().unwrap()))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn sample_pack() -> Vec<u8> {
+        let mut writer = PackWriter::new();
+        writer
+            .add("assets/scenes/a.json", br#"{"version":"0.3"}"#.to_vec())
+            .expect("add scene");
+        writer
+            .add("assets/textures/b.png", vec![7u8; 4096])
+            .expect("add texture");
+        writer.finish()
+    }
+
+    #[test]
+    fn round_trip_preserves_entries() {
+        let pack = PackArchive::from_bytes(sample_pack()).expect("pack should open");
+        assert_eq!(
+            pack.entry_paths(),
+            ["assets/scenes/a.json", "assets/textures/b.png"]
+        );
+        assert_eq!(
+            pack.read("assets/scenes/a.json").expect("read scene"),
+            br#"{"version":"0.3"}"#
+        );
+        assert_eq!(
+            pack.read("assets/textures/b.png").expect("read png"),
+            vec![7u8; 4096]
+        );
+        assert!(pack.read("assets/missing.json").is_err());
+    }
+
+    #[test]
+    fn entries_are_compressed() {
+        let bytes = sample_pack();
+        assert!(bytes.len() < 4096, "4 KiB of repeated bytes should deflate");
+    }
+
+    #[test]
+    fn tampered_entry_fails_checksum() {
+        let mut writer = PackWriter::new();
+        writer.add("a.txt", b"hello".to_vec()).expect("add entry");
+        let mut bytes = writer.finish();
+        // Swap the stored stream for a valid stream of different content.
+        let other = miniz_oxide::deflate::compress_to_vec(b"HELLO", COMPRESSION_LEVEL);
+        let stored_len = u64::from_le_bytes(
+            bytes[bytes.len() - 24..bytes.len() - 16]
+                .try_into()
+                .unwrap(),
+        );
+        assert_eq!(other.len() as u64, stored_len);
+        bytes[HEADER_LEN..HEADER_LEN + other.len()].copy_from_slice(&other);
+
+        let pack = PackArchive::from_bytes(bytes).expect("index still valid");
+        let err = pack.read("a.txt").expect_err("tampered entry");
+        assert!(err.contains("checksum"));
+    }
+
+    #[test]
+    fn rejects_bad_magic_and_truncation() {
+        let err = PackArchive::from_bytes(b"not a pack at all, clearly".to_vec())
+            .err()
+            .expect("bad magic");
+        assert!(err.contains("not an .smepack"));
+
+        let mut bytes = sample_pack();
+        bytes.truncate(bytes.len() - 4);
+        let err = PackArchive::from_bytes(bytes).err().expect("truncated");
+        assert!(err.contains("truncated"));
+    }
+
+    #[test]
+    fn writer_rejects_duplicates() {
+        let mut writer = PackWriter::new();
+        writer.add("a", Vec::new()).expect("first add");
+        assert!(writer.add("a", Vec::new()).is_err());
+    }
+
+    #[test]
+    fn normalize_pack_path_uses_forward_slashes() {
+        assert_eq!(
+            normalize_pack_path(Path::new("./assets/scenes/a.json")),
+            "assets/scenes/a.json"
+        );
+    }
+}
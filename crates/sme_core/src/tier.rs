@@ -31,6 +31,24 @@ impl FidelityTier {
         }
     }
 
+    /// Largest screen-shake intensity (0..=1) this tier allows. Phones are
+    /// held close to the face, so the mobile tier keeps shake gentle.
+    pub fn shake_limit(self) -> f32 {
+        match self {
+            Self::Tier0 => 0.5,
+            Self::Tier2 => 1.0,
+        }
+    }
+
+    /// Multiplier on ambient weather particle counts. Mobile keeps rain and
+    /// snow sparser to save fill rate.
+    pub fn ambient_density(self) -> f32 {
+        match self {
+            Self::Tier0 => 0.4,
+            Self::Tier2 => 1.0,
+        }
+    }
+
     /// Cycle to the next tier (wraps around).
     pub fn next(self) -> Self {
         match self {
@@ -67,6 +85,20 @@ mod tests {
         assert!(FidelityTier::Tier2.label().contains("Tier 2"));
     }
 
+    #[test]
+    fn shake_limit_is_gentler_on_mobile() {
+        assert!(FidelityTier::Tier0.shake_limit() < FidelityTier::Tier2.shake_limit());
+        for &tier in FidelityTier::ALL {
+            assert!((0.0..=1.0).contains(&tier.shake_limit()));
+        }
+    }
+
+    #[test]
+    fn ambient_density_is_sparser_on_mobile() {
+        assert!(FidelityTier::Tier0.ambient_density() < FidelityTier::Tier2.ambient_density());
+        assert_eq!(FidelityTier::Tier2.ambient_density(), 1.0);
+    }
+
     #[test]
     fn display_matches_label() {
         for &tier in FidelityTier::ALL {
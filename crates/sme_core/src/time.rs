@@ -11,6 +11,28 @@
 //! dozens of catch-up steps, which themselves take time, creating a feedback loop
 //! that makes the game unrecoverably slow.
 //!
+//! **Stalls and suspends:** a gap longer than `stall_threshold` (2s) is not a
+//! slow frame but the machine sleeping or a debugger break, so it is dropped
+//! instead of capped. While the app is suspended the clock is paused outright
+//! (`pause_clock` / `resume_clock`), so resuming never starts with a backlog.
+//!
+//! **Game feel:** `time_scale` multiplies the wall-clock time fed into the
+//! accumulator (slow motion below 1.0), and `hitstop(steps)` swallows the next
+//! `steps` fixed-step slots without simulating them. Both are counted in fixed
+//! steps, never wall time — a timed scale expires after a set number of
+//! simulated steps — so replays stay deterministic. Rendering is unaffected.
+//!
+//! **Pause and UI clock:** `pause_simulation` stops the simulation clock
+//! (`total_time`, `fixed_step_count`, time scale and hitstop countdowns) without
+//! stopping the frame. While paused, `should_step()` still hands out one slot
+//! per frame that has one, so the caller can poll hotkeys and reloads, but
+//! `is_step_paused()` tells it not to simulate, and every whole slot in the
+//! accumulator is dropped with it, so resuming never catches up on the time
+//! spent paused. `request_step()` simulates exactly one step while paused.
+//! The UI clock (`ui_time`, `ui_dt`) follows wall time whether or not the
+//! simulation is paused, time-scaled or in hitstop; screen-space animation and
+//! the overlay run on it. It only stops while the app is suspended.
+//!
 //! After all fixed steps are consumed, `end_frame()` computes `interpolation_alpha`
 //! (the fractional leftover in the accumulator) for optional visual interpolation
 //! between the last two simulation states.
@@ -20,16 +42,40 @@
 use web_time::Instant;
 
 const FPS_SAMPLE_COUNT: usize = 60;
+/// Allowed range for `set_time_scale`. Zero is excluded: a timed scale would
+/// never expire because no steps would run.
+pub const MIN_TIME_SCALE: f64 = 0.05;
+pub const MAX_TIME_SCALE: f64 = 4.0;
 
 pub struct TimeState {
     pub fixed_dt: f64,
     pub max_accumulator: f64,
+    /// Wall-clock gaps longer than this are discarded entirely.
+    pub stall_threshold: f64,
     accumulator: f64,
+    clock_paused: bool,
+    time_scale: f64,
+    /// Simulated steps left before `time_scale` returns to 1.0; `None` holds it.
+    time_scale_steps: Option<u64>,
+    hitstop_steps: u32,
+    simulation_paused: bool,
+    step_requested: bool,
+    /// Whether the slot `should_step` last handed out is a paused one.
+    step_paused: bool,
+    /// Slots dropped this frame because the simulation is paused.
+    pub paused_steps_this_frame: u32,
+    /// Slots swallowed by hitstop this frame.
+    pub hitstop_steps_this_frame: u32,
     pub total_time: f64,
     pub fixed_step_count: u64,
     pub frame_count: u64,
     pub steps_this_frame: u32,
     pub real_dt: f64,
+    /// Wall seconds since startup, less suspends and dropped stalls; keeps
+    /// running while the simulation is paused.
+    pub ui_time: f64,
+    /// Wall seconds this frame added to `ui_time`: `real_dt`, unscaled.
+    pub ui_dt: f64,
     last_instant: Instant,
     pub interpolation_alpha: f64,
 
@@ -44,12 +90,24 @@ impl TimeState {
         Self {
             fixed_dt: 1.0 / 60.0,
             max_accumulator: 0.25,
+            stall_threshold: 2.0,
             accumulator: 0.0,
+            clock_paused: false,
+            time_scale: 1.0,
+            time_scale_steps: None,
+            hitstop_steps: 0,
+            simulation_paused: false,
+            step_requested: false,
+            step_paused: false,
+            paused_steps_this_frame: 0,
+            hitstop_steps_this_frame: 0,
             total_time: 0.0,
             fixed_step_count: 0,
             frame_count: 0,
             steps_this_frame: 0,
             real_dt: 0.0,
+            ui_time: 0.0,
+            ui_dt: 0.0,
             last_instant: Instant::now(),
             interpolation_alpha: 0.0,
             fps_samples: [1.0 / 60.0; FPS_SAMPLE_COUNT],
@@ -61,8 +119,90 @@ impl TimeState {
 
     pub fn begin_frame(&mut self) {
         let now = Instant::now();
-        self.real_dt = now.duration_since(self.last_instant).as_secs_f64();
+        let dt = if self.clock_paused {
+            0.0
+        } else {
+            now.duration_since(self.last_instant).as_secs_f64()
+        };
         self.last_instant = now;
+        self.accumulate(dt);
+    }
+
+    /// Stop feeding wall-clock time into the simulation (app suspended).
+    pub fn pause_clock(&mut self) {
+        self.clock_paused = true;
+    }
+
+    /// Restart the clock from now; time spent paused is never simulated.
+    pub fn resume_clock(&mut self) {
+        self.clock_paused = false;
+        self.last_instant = Instant::now();
+    }
+
+    pub fn is_clock_paused(&self) -> bool {
+        self.clock_paused
+    }
+
+    /// Scale simulated time against wall time. With `duration` (simulated
+    /// seconds, rounded to whole steps) the scale reverts to 1.0 afterwards.
+    pub fn set_time_scale(&mut self, scale: f64, duration: Option<f64>) {
+        self.time_scale = scale.clamp(MIN_TIME_SCALE, MAX_TIME_SCALE);
+        self.time_scale_steps =
+            duration.map(|seconds| (seconds.max(0.0) / self.fixed_dt).round() as u64);
+        if self.time_scale_steps == Some(0) {
+            self.time_scale = 1.0;
+            self.time_scale_steps = None;
+        }
+    }
+
+    pub fn time_scale(&self) -> f64 {
+        self.time_scale
+    }
+
+    /// Freeze the simulation for the next `steps` fixed steps. A longer
+    /// request replaces a shorter running one; they don't stack.
+    pub fn hitstop(&mut self, steps: u32) {
+        self.hitstop_steps = self.hitstop_steps.max(steps);
+    }
+
+    pub fn hitstop_remaining(&self) -> u32 {
+        self.hitstop_steps
+    }
+
+    /// Stop or restart the simulation clock (debug pause). Frames, and the UI
+    /// clock, keep running.
+    pub fn pause_simulation(&mut self, paused: bool) {
+        self.simulation_paused = paused;
+        if !paused {
+            self.step_requested = false;
+        }
+    }
+
+    pub fn is_simulation_paused(&self) -> bool {
+        self.simulation_paused
+    }
+
+    /// While paused, simulate the next available slot.
+    pub fn request_step(&mut self) {
+        if self.simulation_paused {
+            self.step_requested = true;
+        }
+    }
+
+    /// Whether the slot `should_step` just handed out must not be simulated.
+    pub fn is_step_paused(&self) -> bool {
+        self.step_paused
+    }
+
+    fn accumulate(&mut self, dt: f64) {
+        self.real_dt = dt;
+        if self.real_dt > self.stall_threshold {
+            log::info!(
+                "Dropping {:.1}s stall (sleep, suspend or debugger)",
+                self.real_dt
+            );
+            self.real_dt = 0.0;
+        }
 
         // Spiral-of-death cap
         if self.real_dt > self.max_accumulator {
@@ -74,8 +214,12 @@ impl TimeState {
             self.real_dt = self.max_accumulator;
         }
 
-        self.accumulator += self.real_dt;
+        self.accumulator += self.real_dt * self.time_scale;
+        self.ui_dt = self.real_dt;
+        self.ui_time += self.ui_dt;
         self.steps_this_frame = 0;
+        self.hitstop_steps_this_frame = 0;
+        self.paused_steps_this_frame = 0;
         self.frame_count += 1;
 
         // FPS smoothing
@@ -87,15 +231,37 @@ impl TimeState {
     }
 
     pub fn should_step(&mut self) -> bool {
-        if self.accumulator >= self.fixed_dt {
+        while self.accumulator >= self.fixed_dt {
+            if self.simulation_paused && !self.step_requested {
+                // One paused slot stands for every whole slot this frame;
+                // after a requested step they are dropped silently.
+                let slots = (self.accumulator / self.fixed_dt).floor();
+                self.accumulator -= slots * self.fixed_dt;
+                self.paused_steps_this_frame += slots as u32;
+                self.step_paused = true;
+                return self.steps_this_frame == 0;
+            }
+            self.step_paused = false;
             self.accumulator -= self.fixed_dt;
+            if self.hitstop_steps > 0 {
+                self.hitstop_steps -= 1;
+                self.hitstop_steps_this_frame += 1;
+                continue;
+            }
+            self.step_requested = false;
             self.total_time += self.fixed_dt;
             self.fixed_step_count += 1;
             self.steps_this_frame += 1;
-            true
-        } else {
-            false
+            if let Some(remaining) = &mut self.time_scale_steps {
+                *remaining -= 1;
+                if *remaining == 0 {
+                    self.time_scale = 1.0;
+                    self.time_scale_steps = None;
+                }
+            }
+            return true;
         }
+        false
     }
 
     pub fn end_frame(&mut self) {
@@ -112,21 +278,8 @@ impl Default for TimeState {
 #[cfg(test)]
 impl TimeState {
     /// Simulate a frame with a known delta time, bypassing `Instant::now()`.
-    /// Mirrors the logic of `begin_frame()` but with an injected dt.
     fn simulate_frame(&mut self, dt: f64) {
-        self.real_dt = dt;
-        if self.real_dt > self.max_accumulator {
-            self.real_dt = self.max_accumulator;
-        }
-        self.accumulator += self.real_dt;
-        self.steps_this_frame = 0;
-        self.frame_count += 1;
-
-        self.fps_samples[self.fps_sample_index] = self.real_dt;
-        self.fps_sample_index = (self.fps_sample_index + 1) % FPS_SAMPLE_COUNT;
-        let avg_dt: f64 = self.fps_samples.iter().sum::<f64>() / FPS_SAMPLE_COUNT as f64;
-        self.smoothed_frame_time_ms = avg_dt * 1000.0;
-        self.smoothed_fps = if avg_dt > 0.0 { 1.0 / avg_dt } else { 0.0 };
+        self.accumulate(dt);
     }
 }
 
@@ -204,6 +357,64 @@ mod tests {
         assert_eq!(ts.steps_this_frame, 15);
     }
 
+    #[test]
+    fn test_stall_is_dropped_not_capped() {
+        let mut ts = TimeState::new();
+        ts.simulate_frame(30.0); // laptop asleep for 30s
+        assert_eq!(ts.real_dt, 0.0);
+        assert!(!ts.should_step());
+        assert_eq!(ts.frame_count, 1);
+    }
+
+    #[test]
+    fn test_paused_clock_feeds_nothing() {
+        let mut ts = TimeState::new();
+        ts.pause_clock();
+        std::thread::sleep(std::time::Duration::from_millis(20));
+        ts.begin_frame();
+        assert!(ts.is_clock_paused());
+        assert_eq!(ts.real_dt, 0.0);
+        assert!(!ts.should_step());
+
+        ts.resume_clock();
+        ts.begin_frame();
+        assert!(!ts.is_clock_paused());
+        assert!(ts.real_dt < 0.015, "resume must not see the paused gap");
+    }
+
+    #[test]
+    fn test_time_scale_slows_accumulation_and_expires_by_steps() {
+        let mut ts = TimeState::new();
+        ts.set_time_scale(0.5, Some(2.0 * ts.fixed_dt));
+        ts.simulate_frame(4.0 * ts.fixed_dt);
+        let mut steps = 0;
+        while ts.should_step() {
+            steps += 1;
+        }
+        assert_eq!(steps, 2);
+        // Two simulated steps used up the duration.
+        assert!((ts.time_scale() - 1.0).abs() < EPSILON);
+
+        ts.set_time_scale(10.0, None);
+        assert!((ts.time_scale() - MAX_TIME_SCALE).abs() < EPSILON);
+    }
+
+    #[test]
+    fn test_hitstop_swallows_steps_without_advancing() {
+        let mut ts = TimeState::new();
+        ts.hitstop(3);
+        ts.hitstop(2); // shorter request doesn't cut the running one
+        ts.simulate_frame(5.0 * ts.fixed_dt + 1e-6);
+        let mut steps = 0;
+        while ts.should_step() {
+            steps += 1;
+        }
+        assert_eq!(steps, 2);
+        assert_eq!(ts.hitstop_steps_this_frame, 3);
+        assert_eq!(ts.fixed_step_count, 2);
+        assert_eq!(ts.hitstop_remaining(), 0);
+    }
+
     #[test]
     fn test_interpolation_alpha() {
         let mut ts = TimeState::new();
@@ -296,4 +507,54 @@ mod tests {
             );
         }
     }
+
+    #[test]
+    fn test_pause_stops_simulation_clock_but_not_ui_clock() {
+        let mut ts = TimeState::new();
+        let dt = 1.0 / 60.0;
+        ts.pause_simulation(true);
+        ts.hitstop(2);
+
+        // A slow paused frame hands out one paused slot and drops the rest.
+        ts.simulate_frame(3.5 * dt);
+        assert!(ts.should_step());
+        assert!(ts.is_step_paused());
+        assert!(!ts.should_step());
+        assert_eq!(ts.paused_steps_this_frame, 3);
+        assert_eq!(ts.steps_this_frame, 0);
+        assert_eq!(ts.fixed_step_count, 0);
+        assert_eq!(ts.hitstop_remaining(), 2, "hitstop waits out the pause");
+        assert!((ts.total_time - 0.0).abs() < EPSILON);
+        assert!((ts.ui_time - 3.5 * dt).abs() < EPSILON);
+
+        // Resuming doesn't catch up on the paused time.
+        ts.pause_simulation(false);
+        ts.simulate_frame(dt);
+        let mut count = 0;
+        while ts.should_step() {
+            count += 1;
+        }
+        assert_eq!(count, 0, "the one slot goes to hitstop");
+        assert!((ts.ui_time - 4.5 * dt).abs() < EPSILON);
+    }
+
+    #[test]
+    fn test_requested_step_runs_once_while_paused() {
+        let mut ts = TimeState::new();
+        let dt = 1.0 / 60.0;
+        ts.pause_simulation(true);
+        ts.request_step();
+
+        ts.simulate_frame(2.0 * dt);
+        assert!(ts.should_step());
+        assert!(!ts.is_step_paused());
+        assert!(!ts.should_step(), "the rest drain silently");
+        assert_eq!(ts.fixed_step_count, 1);
+        assert_eq!(ts.paused_steps_this_frame, 1);
+
+        ts.simulate_frame(dt);
+        assert!(ts.should_step());
+        assert!(ts.is_step_paused());
+        assert_eq!(ts.fixed_step_count, 1);
+    }
 }
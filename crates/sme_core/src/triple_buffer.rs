@@ -0,0 +1,140 @@
+//! Latest-value handoff between a producer thread and a consumer thread.
+//!
+//! The value the writer is building, the published one and the one the
+//! reader is using are three separate buffers, so neither side waits on the
+//! other: `Writer::write` replaces the published value even if the reader
+//! never took it, and the reader always gets the newest value. The engine
+//! hands each frame's render snapshot to the render thread this way; a slow
+//! GPU drops stale frames instead of holding up the simulation.
+//!
+//! Dropping the `Writer` closes the buffer, which ends `Reader::wait` once
+//! the last published value has been taken.
+
+use std::sync::{Arc, Condvar, Mutex};
+
+struct Slot<T> {
+    published: Option<T>,
+    closed: bool,
+}
+
+struct Shared<T> {
+    slot: Mutex<Slot<T>>,
+    ready: Condvar,
+}
+
+pub struct Writer<T> {
+    shared: Arc<Shared<T>>,
+}
+
+pub struct Reader<T> {
+    shared: Arc<Shared<T>>,
+}
+
+/// A connected writer and reader.
+pub fn triple_buffer<T>() -> (Writer<T>, Reader<T>) {
+    let shared = Arc::new(Shared {
+        slot: Mutex::new(Slot {
+            published: None,
+            closed: false,
+        }),
+        ready: Condvar::new(),
+    });
+    (
+        Writer {
+            shared: shared.clone(),
+        },
+        Reader { shared },
+    )
+}
+
+impl<T> Writer<T> {
+    /// Publish `value`. Returns the previously published value if the reader
+    /// never took it.
+    pub fn write(&mut self, value: T) -> Option<T> {
+        let stale = self
+            .shared
+            .slot
+            .lock()
+            .expect("triple buffer poisoned")
+            .published
+            .replace(value);
+        self.shared.ready.notify_one();
+        stale
+    }
+}
+
+impl<T> Drop for Writer<T> {
+    fn drop(&mut self) {
+        if let Ok(mut slot) = self.shared.slot.lock() {
+            slot.closed = true;
+        }
+        self.shared.ready.notify_one();
+    }
+}
+
+impl<T> Reader<T> {
+    /// Take the newest published value, if there is one.
+    pub fn read(&mut self) -> Option<T> {
+        self.shared
+            .slot
+            .lock()
+            .expect("triple buffer poisoned")
+            .published
+            .take()
+    }
+
+    /// Block until a value is published and take it. `None` once the writer
+    /// is gone and nothing is left to read.
+    pub fn wait(&mut self) -> Option<T> {
+        let mut slot = self.shared.slot.lock().expect("triple buffer poisoned");
+        loop {
+            if let Some(value) = slot.published.take() {
+                return Some(value);
+            }
+            if slot.closed {
+                return None;
+            }
+            slot = self
+                .shared
+                .ready
+                .wait(slot)
+                .expect("triple buffer poisoned");
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn reader_gets_newest_value_and_stale_ones_come_back() {
+        let (mut writer, mut reader) = triple_buffer();
+        assert_eq!(reader.read(), None);
+        assert_eq!(writer.write(1), None);
+        assert_eq!(writer.write(2), Some(1));
+        assert_eq!(reader.read(), Some(2));
+        assert_eq!(reader.read(), None);
+        assert_eq!(writer.write(3), None);
+        assert_eq!(reader.wait(), Some(3));
+    }
+
+    #[test]
+    fn wait_ends_when_writer_drops() {
+        let (mut writer, mut reader) = triple_buffer();
+        let consumer = std::thread::spawn(move || {
+            let mut last = 0;
+            while let Some(value) = reader.wait() {
+                assert!(value > last, "values arrive in order");
+                last = value;
+            }
+            last
+        });
+        for value in 1..=100 {
+            writer.write(value);
+        }
+        drop(writer);
+        // Whatever was skipped, the last value is always delivered.
+        assert_eq!(consumer.join().unwrap(), 100);
+    }
+}
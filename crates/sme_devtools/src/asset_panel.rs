@@ -0,0 +1,121 @@
+//! "Assets" window: every watched file, how its last reload went, and a
+//! button to reload just that file.
+//!
+//! The game lists its watched files as `AssetView`s before the overlay runs.
+//! A Reload click comes back as `OverlayActions::reload_asset`, and the game
+//! reloads that one file at the next frame boundary instead of everything the
+//! R key reloads. The buttons above the list reload every file of one kind,
+//! or all of them; those skip atlases whose file has not changed. Status and
+//! times come from `ReloadErrors`, which remembers the outcome of every
+//! reload.
+
+use std::time::Duration;
+
+use crate::reload_toast::{ReloadErrors, ERROR_COLOR};
+
+/// One watched file.
+#[derive(Debug, Clone, Default)]
+pub struct AssetView {
+    /// Asset kind, as in `ReloadError::kind`.
+    pub kind: String,
+    pub path: String,
+}
+
+/// What a click in the Assets window asks the game to reload.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum AssetReload {
+    /// One row, by index, whether or not it changed.
+    One(usize),
+    /// Every file of one `AssetView::kind`.
+    Kind(String),
+    /// Every listed file.
+    All,
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct AssetPanel {
+    pub assets: Vec<AssetView>,
+}
+
+impl AssetPanel {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Draw the window. Returns what a click asked to reload, if anything.
+    pub(crate) fn show(
+        &self,
+        ctx: &egui::Context,
+        reload_errors: &ReloadErrors,
+    ) -> Option<AssetReload> {
+        let mut clicked = None;
+        egui::Window::new("Assets")
+            .default_pos([10.0, 620.0])
+            .default_open(false)
+            .show(ctx, |ui| {
+                ui.horizontal_wrapped(|ui| {
+                    ui.label("Reload every:");
+                    let mut kinds: Vec<&str> = Vec::new();
+                    for asset in &self.assets {
+                        if !kinds.contains(&asset.kind.as_str()) {
+                            kinds.push(&asset.kind);
+                        }
+                    }
+                    for kind in kinds {
+                        if ui.small_button(kind).clicked() {
+                            clicked = Some(AssetReload::Kind(kind.to_string()));
+                        }
+                    }
+                    if ui.small_button("all").clicked() {
+                        clicked = Some(AssetReload::All);
+                    }
+                });
+                ui.separator();
+                egui::ScrollArea::vertical()
+                    .max_height(320.0)
+                    .show(ui, |ui| {
+                        egui::Grid::new("asset_grid")
+                            .num_columns(5)
+                            .striped(true)
+                            .show(ui, |ui| {
+                                ui.label("Kind");
+                                ui.label("File");
+                                ui.label("Status");
+                                ui.label("Last reload");
+                                ui.end_row();
+                                for (index, asset) in self.assets.iter().enumerate() {
+                                    ui.label(&asset.kind);
+                                    ui.label(&asset.path);
+                                    match reload_errors.error_for(&asset.path) {
+                                        Some(error) => {
+                                            ui.colored_label(ERROR_COLOR, "error")
+                                                .on_hover_text(&error.reason);
+                                        }
+                                        None => {
+                                            ui.label("ok");
+                                        }
+                                    }
+                                    ui.label(match reload_errors.last_reload(&asset.path) {
+                                        Some(record) => age_label(record.at.elapsed()),
+                                        None => "not since start".to_string(),
+                                    });
+                                    if ui.small_button("Reload").clicked() {
+                                        clicked = Some(AssetReload::One(index));
+                                    }
+                                    ui.end_row();
+                                }
+                            });
+                    });
+            });
+        clicked
+    }
+}
+
+fn age_label(elapsed: Duration) -> String {
+    let seconds = elapsed.as_secs();
+    match seconds {
+        0..60 => format!("{seconds} s ago"),
+        60..3600 => format!("{} min ago", seconds / 60),
+        _ => format!("{} h ago", seconds / 3600),
+    }
+}
@@ -0,0 +1,173 @@
+//! Performance budgets from the project manifest, and the banner that shows
+//! when a stat goes over one.
+//!
+//! Each budget is optional. The Debug window shows a stat over its budget in
+//! red. The banner at the top center lists every budget exceeded since it was
+//! last dismissed, with the worst value seen, so a brief spike (a particle
+//! burst, a hitch while streaming) stays visible after the stat recovers. It
+//! is drawn whether or not the debug window is visible.
+
+use crate::reload_toast::ERROR_COLOR;
+
+/// Limits a project sets in its manifest's `[budget]` table.
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct PerformanceBudget {
+    pub max_draw_calls: Option<u32>,
+    pub max_sprites: Option<u32>,
+    /// GPU texture memory, in megabytes.
+    pub max_texture_mb: Option<f32>,
+    /// Smoothed frame time, in milliseconds.
+    pub max_frame_ms: Option<f32>,
+}
+
+/// A stat a budget can limit.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum BudgetStat {
+    DrawCalls,
+    Sprites,
+    TextureMemory,
+    FrameTime,
+}
+
+impl BudgetStat {
+    pub const ALL: [BudgetStat; 4] = [
+        BudgetStat::DrawCalls,
+        BudgetStat::Sprites,
+        BudgetStat::TextureMemory,
+        BudgetStat::FrameTime,
+    ];
+
+    fn format(self, value: f32) -> String {
+        match self {
+            BudgetStat::DrawCalls => format!("{value:.0} draw calls"),
+            BudgetStat::Sprites => format!("{value:.0} sprites"),
+            BudgetStat::TextureMemory => format!("{value:.1} MB of textures"),
+            BudgetStat::FrameTime => format!("{value:.2} ms frames"),
+        }
+    }
+}
+
+/// This frame's value of every `BudgetStat`.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct BudgetSample {
+    pub draw_calls: u32,
+    pub sprites: u32,
+    pub texture_mb: f32,
+    pub frame_ms: f32,
+}
+
+impl BudgetSample {
+    fn value(&self, stat: BudgetStat) -> f32 {
+        match stat {
+            BudgetStat::DrawCalls => self.draw_calls as f32,
+            BudgetStat::Sprites => self.sprites as f32,
+            BudgetStat::TextureMemory => self.texture_mb,
+            BudgetStat::FrameTime => self.frame_ms,
+        }
+    }
+}
+
+impl PerformanceBudget {
+    pub fn limit(&self, stat: BudgetStat) -> Option<f32> {
+        match stat {
+            BudgetStat::DrawCalls => self.max_draw_calls.map(|limit| limit as f32),
+            BudgetStat::Sprites => self.max_sprites.map(|limit| limit as f32),
+            BudgetStat::TextureMemory => self.max_texture_mb,
+            BudgetStat::FrameTime => self.max_frame_ms,
+        }
+    }
+}
+
+/// A budget and the worst overrun of each stat since the banner was last
+/// dismissed.
+#[derive(Debug, Clone, Default)]
+pub struct BudgetWarnings {
+    pub budget: PerformanceBudget,
+    sample: BudgetSample,
+    /// Indexed like `BudgetStat::ALL`.
+    worst: [Option<f32>; 4],
+}
+
+impl BudgetWarnings {
+    pub fn new(budget: PerformanceBudget) -> Self {
+        Self {
+            budget,
+            ..Self::default()
+        }
+    }
+
+    /// Record this frame's stats.
+    pub fn update(&mut self, sample: BudgetSample) {
+        self.sample = sample;
+        for (i, stat) in BudgetStat::ALL.into_iter().enumerate() {
+            if self.over(stat) {
+                let value = sample.value(stat);
+                self.worst[i] = Some(self.worst[i].map_or(value, |worst| worst.max(value)));
+            }
+        }
+    }
+
+    /// Whether the last recorded value of `stat` is over its budget.
+    pub fn over(&self, stat: BudgetStat) -> bool {
+        self.budget
+            .limit(stat)
+            .is_some_and(|limit| self.sample.value(stat) > limit)
+    }
+
+    /// Budgets exceeded since the last dismissal, with the worst value of
+    /// each.
+    pub fn overruns(&self) -> impl Iterator<Item = (BudgetStat, f32)> + '_ {
+        BudgetStat::ALL
+            .iter()
+            .zip(&self.worst)
+            .filter_map(|(stat, worst)| worst.map(|worst| (*stat, worst)))
+    }
+
+    pub fn dismiss(&mut self) {
+        self.worst = [None; 4];
+    }
+
+    /// A label for the Debug window, red when `stat` is over budget.
+    pub(crate) fn label(&self, ui: &mut egui::Ui, stat: BudgetStat, text: String) {
+        if self.over(stat) {
+            ui.colored_label(ERROR_COLOR, text);
+        } else {
+            ui.label(text);
+        }
+    }
+
+    pub(crate) fn show(&mut self, ctx: &egui::Context) {
+        if self.overruns().next().is_none() {
+            return;
+        }
+        let mut dismissed = false;
+        egui::Area::new(egui::Id::new("budget_warnings"))
+            .anchor(egui::Align2::CENTER_TOP, [0.0, 10.0])
+            .show(ctx, |ui| {
+                egui::Frame::popup(ui.style())
+                    .fill(egui::Color32::from_rgba_unmultiplied(60, 0, 0, 220))
+                    .show(ui, |ui| {
+                        ui.horizontal(|ui| {
+                            ui.label(
+                                egui::RichText::new("Over performance budget")
+                                    .strong()
+                                    .color(ERROR_COLOR),
+                            );
+                            dismissed = ui.small_button("Dismiss").clicked();
+                        });
+                        for (stat, worst) in self.overruns() {
+                            let limit = self.budget.limit(stat).unwrap_or_default();
+                            let now = if self.over(stat) { " (now)" } else { "" };
+                            ui.label(format!(
+                                "{}, budget {}{now}",
+                                stat.format(worst),
+                                stat.format(limit)
+                            ));
+                        }
+                    });
+            });
+        if dismissed {
+            self.dismiss();
+        }
+    }
+}
@@ -0,0 +1,108 @@
+//! Script console: lines scripts wrote with `print` or `engine.log.*`,
+//! shown in the Debug window under the Lua errors, and a REPL line under
+//! them whose input the game runs in the script's Lua state.
+
+/// One script log line ready for display.
+#[derive(Debug, Clone)]
+pub struct ConsoleLineView {
+    pub level: log::Level,
+    /// Script and line, e.g. `controller.lua:12`.
+    pub location: String,
+    pub message: String,
+}
+
+pub(crate) fn show_console(ui: &mut egui::Ui, lines: &[ConsoleLineView]) {
+    egui::ScrollArea::vertical()
+        .id_salt("script_console")
+        .max_height(160.0)
+        .stick_to_bottom(true)
+        .show(ui, |ui| {
+            for line in lines {
+                let color = match line.level {
+                    log::Level::Error => egui::Color32::from_rgb(255, 110, 110),
+                    log::Level::Warn => egui::Color32::from_rgb(255, 200, 90),
+                    _ => ui.visuals().text_color(),
+                };
+                ui.horizontal_wrapped(|ui| {
+                    ui.label(egui::RichText::new(&line.location).monospace().weak());
+                    ui.label(egui::RichText::new(&line.message).monospace().color(color));
+                });
+            }
+        });
+}
+
+/// Entries the REPL's Up/Down recall.
+const REPL_HISTORY: usize = 32;
+
+/// The console's REPL line and its history.
+#[derive(Debug, Default)]
+pub(crate) struct ReplInput {
+    text: String,
+    history: Vec<String>,
+    /// Index into `history` while recalling with Up/Down.
+    recalled: Option<usize>,
+}
+
+impl ReplInput {
+    /// Show the input line; returns the code entered with Enter.
+    pub(crate) fn show(&mut self, ui: &mut egui::Ui) -> Option<String> {
+        let response = ui.add(
+            egui::TextEdit::singleline(&mut self.text)
+                .id_salt("lua_repl")
+                .font(egui::TextStyle::Monospace)
+                .hint_text("Lua, e.g. engine.actor.x")
+                .desired_width(f32::INFINITY),
+        );
+        if response.has_focus() {
+            let (up, down) = ui.input(|input| {
+                (
+                    input.key_pressed(egui::Key::ArrowUp),
+                    input.key_pressed(egui::Key::ArrowDown),
+                )
+            });
+            if up {
+                self.recall(-1);
+            } else if down {
+                self.recall(1);
+            }
+        }
+        let entered =
+            response.lost_focus() && ui.input(|input| input.key_pressed(egui::Key::Enter));
+        if !entered {
+            return None;
+        }
+        // Keep typing after running a line.
+        response.request_focus();
+        let code = std::mem::take(&mut self.text).trim().to_string();
+        if code.is_empty() {
+            return None;
+        }
+        self.recalled = None;
+        if self.history.last() != Some(&code) {
+            if self.history.len() == REPL_HISTORY {
+                self.history.remove(0);
+            }
+            self.history.push(code.clone());
+        }
+        Some(code)
+    }
+
+    /// Step through the history: -1 is older, 1 newer; past the newest
+    /// entry the line clears.
+    fn recall(&mut self, direction: isize) {
+        if self.history.is_empty() {
+            return;
+        }
+        let newest = self.history.len() - 1;
+        self.recalled = match (self.recalled, direction < 0) {
+            (None, true) => Some(newest),
+            (None, false) => None,
+            (Some(index), true) => Some(index.saturating_sub(1)),
+            (Some(index), false) => (index < newest).then_some(index + 1),
+        };
+        self.text = self
+            .recalled
+            .map(|index| self.history[index].clone())
+            .unwrap_or_default();
+    }
+}
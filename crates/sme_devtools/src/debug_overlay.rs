@@ -9,11 +9,41 @@
 //!   3. `paint()`   -- render into a new render pass with `forget_lifetime()`
 //!   4. `cleanup()` -- free textures egui no longer references
 //!
+//! `prepare()` belongs to `DebugOverlay`, which lives with the window and
+//! its input; the GPU phases belong to `OverlayPainter`, which the game runs
+//! on its render thread.
+//!
 //! The overlay only runs UI logic when `visible` is true (toggled by F3),
 //! but egui event handling is always active so the overlay can intercept
-//! clicks when it is shown.
+//! clicks when it is shown. The in-game dialog box (`dialog_panel`) shares the
+//! same egui pass and is drawn regardless of `visible`, as is the banner of
+//! failed hot reloads (`reload_toast`) and the banner of stats over the
+//! project's performance budget (`budget`).
+//!
+//! Values reported by gameplay scripts (`script_watch`) get their own section
+//! at the bottom of the Debug window, and their `print` / `engine.log.*`
+//! output folds out as the Console (`console_panel`) under the Lua errors. Scene layers are tuned in a separate
+//! "Layers" window (`layer_panel`), shown alongside it, and watched files are
+//! listed with their reload status in the "Assets" window (`asset_panel`). The Memory section
+//! (`memory_panel`) folds out under the GPU estimate, per-layer sprite counts
+//! (`layer_stats`) under the sprite count, and log levels can be
+//! changed at runtime in the Logging section (`log_panel`). Debug-draw text
+//! (`world_label`) is painted behind everything else in the same pass.
 
 use sme_core::time::TimeState;
+
+use crate::asset_panel::{AssetPanel, AssetReload};
+use crate::budget::{BudgetSample, BudgetStat, BudgetWarnings};
+use crate::console_panel::{show_console, ConsoleLineView, ReplInput};
+use crate::dialog_panel::{show_dialog_panel, DialogView};
+use crate::layer_panel::LayerPanel;
+use crate::layer_stats::{show_layer_stats, LayerStatsView};
+use crate::log_panel::LogPanel;
+use crate::lua_error_panel::{show_lua_errors, LuaErrorView};
+use crate::memory_panel::MemoryStats;
+use crate::reload_toast::ReloadErrors;
+use crate::script_watch::ScriptWatches;
+use crate::world_label::{show_world_labels, WorldLabel};
 use winit::window::Window;
 
 #[derive(Debug, Clone, Default)]
@@ -21,18 +51,51 @@ pub struct OverlayStats {
     pub draw_calls: u32,
     pub atlas_binds: u32,
     pub sprite_count: u32,
+    /// Sprites per scene layer, drawn and culled, in scene order
+    pub layers: Vec<LayerStatsView>,
     /// Estimated GPU memory usage in megabytes
     pub memory_estimate_mb: f32,
+    /// The part of `memory_estimate_mb` taken by textures
+    pub texture_memory_mb: f32,
+    /// CPU-side breakdown for the Memory section
+    pub memory: MemoryStats,
     /// Current fidelity tier label (e.g. "Tier 0 (Mobile)")
     pub tier_label: String,
     /// Lua runtime status label (e.g. "Lua: loaded")
     pub lua_status_label: String,
     /// Whether simulation is paused
     pub paused: bool,
+    /// Progress of a running `StepUntil` (e.g. "Stepping: 4 of 10")
+    pub stepping: Option<String>,
+    /// Fixed steps the rewind buffer can go back from the live head
+    pub rewind_available: usize,
+    /// How many steps behind the live head the shown state is
+    pub rewind_offset: usize,
     /// Number of loaded atlases
     pub atlas_count: u32,
     /// Number of active animation states
     pub active_animations: u32,
+    /// Live projectiles and the pool size
+    pub projectiles: (usize, usize),
+    /// Camera position and zoom (e.g. "(120, 48) zoom 1.00")
+    pub camera_label: String,
+    /// Camera parts held back by scene constraints (e.g. "zoom, x"), if any
+    pub camera_clamp: Option<String>,
+    /// Recent Lua errors, newest first
+    pub lua_errors: Vec<LuaErrorView>,
+    /// Recent script log lines, oldest first
+    pub console: Vec<ConsoleLineView>,
+}
+
+/// Run fixed steps from a pause until this holds, then pause again.
+#[derive(Debug, Clone, PartialEq)]
+pub enum StepUntil {
+    /// This many steps have run.
+    Steps(u32),
+    /// The player's grounded flag flipped.
+    GroundedChanges,
+    /// A `trigger` event named this trigger.
+    Trigger(String),
 }
 
 #[derive(Debug, Clone, Default)]
@@ -43,21 +106,55 @@ pub struct OverlayActions {
     pub toggle_pause: bool,
     /// User clicked the single-step button (advance one fixed step while paused)
     pub single_step: bool,
+    /// User clicked one of the multi-step buttons while paused
+    pub step_until: Option<StepUntil>,
+    /// User clicked Back (restore the previous fixed step while paused)
+    pub step_back: bool,
+    /// User clicked Forward (restore the next recorded fixed step while paused)
+    pub step_forward: bool,
+    /// Player clicked a dialog choice (index into `DialogView::choices`)
+    pub dialog_choice: Option<usize>,
+    /// A setting in the Layers window was edited
+    pub layers_changed: bool,
+    /// A layer group checkbox in the Layers window was toggled
+    pub layer_groups_changed: bool,
+    /// User clicked "Save to scene file" in the Layers window
+    pub save_layers: bool,
+    /// User clicked a Reload button in the Assets window
+    pub reload_asset: Option<AssetReload>,
+    /// Lua entered in the Console's REPL line, to run in the script's state
+    pub repl: Option<String>,
 }
 
 pub struct DebugOverlay {
     pub egui_ctx: egui::Context,
     pub egui_winit_state: egui_winit::State,
-    pub egui_renderer: egui_wgpu::Renderer,
     pub visible: bool,
+    /// Contents of the Layers window; the game fills and reads it back.
+    pub layer_panel: LayerPanel,
+    /// Rows of the Assets window; the game refills it while visible.
+    pub asset_panel: AssetPanel,
+    /// Runtime log levels, in the Debug window's Logging section.
+    pub log_panel: LogPanel,
+    /// Debug-draw labels for this frame; the game refills it before `prepare`.
+    pub world_labels: Vec<WorldLabel>,
+    /// The project's performance budget and the overruns it has seen.
+    pub budget: BudgetWarnings,
+    /// Trigger name typed for "Until trigger".
+    step_trigger: String,
+    /// Expand the Console section on the next `prepare`.
+    open_console: bool,
+    /// The Console section's Lua input line.
+    repl: ReplInput,
+}
+
+/// Draws the primitives `DebugOverlay::prepare` produced.
+pub struct OverlayPainter {
+    pub egui_renderer: egui_wgpu::Renderer,
 }
 
 impl DebugOverlay {
-    pub fn new(
-        device: &wgpu::Device,
-        surface_format: wgpu::TextureFormat,
-        window: &Window,
-    ) -> Self {
+    pub fn new(window: &Window) -> Self {
         let egui_ctx = egui::Context::default();
         let egui_winit_state = egui_winit::State::new(
             egui_ctx.clone(),
@@ -67,16 +164,29 @@ impl DebugOverlay {
             None,
             None,
         );
-        let egui_renderer = egui_wgpu::Renderer::new(device, surface_format, None, 1, false);
 
         Self {
             egui_ctx,
             egui_winit_state,
-            egui_renderer,
             visible: false,
+            layer_panel: LayerPanel::new(),
+            asset_panel: AssetPanel::new(),
+            log_panel: LogPanel::new(),
+            world_labels: Vec::new(),
+            budget: BudgetWarnings::default(),
+            step_trigger: String::new(),
+            open_console: false,
+            repl: ReplInput::default(),
         }
     }
 
+    /// Show the overlay with its Console section expanded, e.g. at a Lua
+    /// breakpoint.
+    pub fn show_console(&mut self) {
+        self.visible = true;
+        self.open_console = true;
+    }
+
     pub fn handle_window_event(
         &mut self,
         window: &Window,
@@ -96,31 +206,98 @@ impl DebugOverlay {
         window: &Window,
         time: &TimeState,
         stats: Option<OverlayStats>,
+        dialog: Option<&DialogView>,
+        script_watches: Option<&ScriptWatches>,
+        reload_errors: &ReloadErrors,
     ) -> (
         Vec<egui::ClippedPrimitive>,
         egui::TexturesDelta,
         OverlayActions,
     ) {
         let mut actions = OverlayActions::default();
-        let raw_input = self.egui_winit_state.take_egui_input(window);
+        if let Some(ref stats) = stats {
+            self.budget.update(BudgetSample {
+                draw_calls: stats.draw_calls,
+                sprites: stats.sprite_count,
+                texture_mb: stats.texture_memory_mb,
+                frame_ms: time.smoothed_frame_time_ms as f32,
+            });
+        }
+        let mut raw_input = self.egui_winit_state.take_egui_input(window);
+        // egui animates on the UI clock, which runs while the simulation is paused.
+        raw_input.time = Some(time.ui_time);
         let full_output = self.egui_ctx.run(raw_input, |ctx| {
+            show_world_labels(ctx, &self.world_labels);
+            if let Some(view) = dialog {
+                actions.dialog_choice = show_dialog_panel(ctx, view);
+            }
+            reload_errors.show(ctx);
+            self.budget.show(ctx);
             if self.visible {
+                (
+                    actions.layers_changed,
+                    actions.layer_groups_changed,
+                    actions.save_layers,
+                ) = self.layer_panel.show(ctx);
+                actions.reload_asset = self.asset_panel.show(ctx, reload_errors);
                 egui::Window::new("Debug")
                     .default_pos([10.0, 10.0])
                     .show(ctx, |ui| {
                         ui.label(format!("FPS: {:.1}", time.smoothed_fps));
-                        ui.label(format!("Frame time: {:.2} ms", time.smoothed_frame_time_ms));
+                        self.budget.label(
+                            ui,
+                            BudgetStat::FrameTime,
+                            format!("Frame time: {:.2} ms", time.smoothed_frame_time_ms),
+                        );
                         ui.label(format!("Steps this frame: {}", time.steps_this_frame));
                         ui.label(format!("Total steps: {}", time.fixed_step_count));
                         ui.label(format!("Frame: {}", time.frame_count));
+                        if (time.time_scale() - 1.0).abs() > f64::EPSILON {
+                            ui.label(format!("Time scale: {:.2}x", time.time_scale()));
+                        }
+                        if time.hitstop_remaining() > 0 || time.hitstop_steps_this_frame > 0 {
+                            ui.label(format!("Hitstop: {} steps left", time.hitstop_remaining()));
+                        }
                         if let Some(ref stats) = stats {
                             ui.separator();
-                            ui.label(format!("Draw calls: {}", stats.draw_calls));
+                            self.budget.label(
+                                ui,
+                                BudgetStat::DrawCalls,
+                                format!("Draw calls: {}", stats.draw_calls),
+                            );
                             ui.label(format!("Atlas binds: {}", stats.atlas_binds));
-                            ui.label(format!("Sprites: {}", stats.sprite_count));
-                            ui.label(format!("Memory: {:.1} MB", stats.memory_estimate_mb));
+                            self.budget.label(
+                                ui,
+                                BudgetStat::Sprites,
+                                format!("Sprites: {}", stats.sprite_count),
+                            );
+                            ui.collapsing("Layer sprites", |ui| {
+                                show_layer_stats(ui, &stats.layers)
+                            });
+                            self.budget.label(
+                                ui,
+                                BudgetStat::TextureMemory,
+                                format!(
+                                    "Memory: {:.1} MB ({:.1} MB textures)",
+                                    stats.memory_estimate_mb, stats.texture_memory_mb
+                                ),
+                            );
+                            ui.collapsing("Memory details", |ui| stats.memory.show(ui));
                             ui.label(format!("Atlases: {}", stats.atlas_count));
                             ui.label(format!("Animations: {}", stats.active_animations));
+                            ui.label(format!(
+                                "Projectiles: {} / {}",
+                                stats.projectiles.0, stats.projectiles.1
+                            ));
+                            ui.horizontal(|ui| {
+                                ui.label(format!("Camera: {}", stats.camera_label));
+                                if let Some(clamp) = &stats.camera_clamp {
+                                    ui.colored_label(
+                                        egui::Color32::from_rgb(240, 170, 60),
+                                        format!("clamped: {clamp}"),
+                                    );
+                                }
+                            });
                         }
 
                         // --- M5: Fidelity Tier ---
@@ -135,6 +312,17 @@ impl DebugOverlay {
 
                             // --- M5: Lua Status ---
                             ui.label(&stats.lua_status_label);
+                            show_lua_errors(ui, &stats.lua_errors);
+                            egui::CollapsingHeader::new(format!(
+                                "Console ({})",
+                                stats.console.len()
+                            ))
+                            .id_salt("console")
+                            .open(std::mem::take(&mut self.open_console).then_some(true))
+                            .show(ui, |ui| {
+                                show_console(ui, &stats.console);
+                                actions.repl = self.repl.show(ui);
+                            });
 
                             // --- M5: Simulation Controls ---
                             ui.separator();
@@ -143,14 +331,78 @@ impl DebugOverlay {
                                 if ui.button(pause_label).clicked() {
                                     actions.toggle_pause = true;
                                 }
-                                if stats.paused && ui.button("Step").clicked() {
-                                    actions.single_step = true;
+                                if stats.paused {
+                                    let can_back = stats.rewind_offset < stats.rewind_available;
+                                    if ui
+                                        .add_enabled(can_back, egui::Button::new("\u{25c0} Back"))
+                                        .clicked()
+                                    {
+                                        actions.step_back = true;
+                                    }
+                                    if ui
+                                        .add_enabled(
+                                            stats.rewind_offset > 0,
+                                            egui::Button::new("Forward \u{25b6}"),
+                                        )
+                                        .clicked()
+                                    {
+                                        actions.step_forward = true;
+                                    }
+                                    if ui.button("Step").clicked() {
+                                        actions.single_step = true;
+                                    }
                                 }
                             });
+                            if stats.paused {
+                                ui.horizontal(|ui| {
+                                    if ui.button("Step 10").clicked() {
+                                        actions.step_until = Some(StepUntil::Steps(10));
+                                    }
+                                    if ui.button("Until grounded changes").clicked() {
+                                        actions.step_until = Some(StepUntil::GroundedChanges);
+                                    }
+                                });
+                                ui.horizontal(|ui| {
+                                    ui.add(
+                                        egui::TextEdit::singleline(&mut self.step_trigger)
+                                            .hint_text("trigger")
+                                            .desired_width(100.0),
+                                    );
+                                    let trigger = self.step_trigger.trim();
+                                    if ui
+                                        .add_enabled(
+                                            !trigger.is_empty(),
+                                            egui::Button::new("Until trigger fires"),
+                                        )
+                                        .clicked()
+                                    {
+                                        actions.step_until =
+                                            Some(StepUntil::Trigger(trigger.to_string()));
+                                    }
+                                });
+                            }
+                            if let Some(label) = &stats.stepping {
+                                ui.label(label);
+                            }
                             if stats.paused {
                                 ui.label("\u{23f8} PAUSED");
+                                if stats.rewind_offset > 0 {
+                                    ui.label(format!(
+                                        "Rewound {} of {} steps; Step or Resume re-simulates from here",
+                                        stats.rewind_offset, stats.rewind_available
+                                    ));
+                                }
                             }
                         }
+
+                        ui.separator();
+                        ui.collapsing("Logging", |ui| self.log_panel.show(ui));
+
+                        if let Some(watches) = script_watches.filter(|w| !w.is_empty()) {
+                            ui.separator();
+                            ui.label(egui::RichText::new("Script").strong());
+                            watches.show(ui);
+                        }
                     });
             }
         });
@@ -164,6 +416,14 @@ impl DebugOverlay {
 
         (primitives, full_output.textures_delta, actions)
     }
+}
+
+impl OverlayPainter {
+    pub fn new(device: &wgpu::Device, surface_format: wgpu::TextureFormat) -> Self {
+        Self {
+            egui_renderer: egui_wgpu::Renderer::new(device, surface_format, None, 1, false),
+        }
+    }
 
     /// Upload textures and update buffers. Call before creating the egui render pass.
     pub fn upload(
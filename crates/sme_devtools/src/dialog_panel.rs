@@ -0,0 +1,47 @@
+//! Dialog box drawn with egui.
+//!
+//! The game has no text pipeline yet, so dialog reuses the overlay's egui
+//! context. It is drawn whether or not the debug window is visible. Callers
+//! hand over display-ready strings (already localized); the panel only lays
+//! them out and reports clicks.
+
+/// One dialog node ready for display.
+#[derive(Debug, Clone, Default)]
+pub struct DialogView {
+    pub speaker: Option<String>,
+    pub text: String,
+    pub choices: Vec<String>,
+    /// Highlighted entry in `choices`.
+    pub selected: usize,
+}
+
+/// Draw the dialog box anchored to the bottom of the screen. Returns the
+/// index of a clicked choice, or `None` when nothing was
+/// clicked. Choice-less nodes show a "continue" button reported as index 0.
+pub fn show_dialog_panel(ctx: &egui::Context, view: &DialogView) -> Option<usize> {
+    let mut clicked = None;
+    egui::TopBottomPanel::bottom("dialog_panel")
+        .resizable(false)
+        .min_height(120.0)
+        .show(ctx, |ui| {
+            ui.add_space(8.0);
+            if let Some(speaker) = &view.speaker {
+                ui.label(egui::RichText::new(speaker).strong().size(18.0));
+            }
+            ui.label(egui::RichText::new(&view.text).size(16.0));
+            ui.add_space(6.0);
+            if view.choices.is_empty() {
+                if ui.button("Continue \u{25b6}").clicked() {
+                    clicked = Some(0);
+                }
+            } else {
+                for (i, choice) in view.choices.iter().enumerate() {
+                    if ui.selectable_label(i == view.selected, choice).clicked() {
+                        clicked = Some(i);
+                    }
+                }
+            }
+            ui.add_space(8.0);
+        });
+    clicked
+}
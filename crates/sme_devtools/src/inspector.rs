@@ -0,0 +1,86 @@
+//! "Inspector" tool window: frame timing and engine stats in their own OS
+//! window (F7), so they can sit on a second monitor instead of over the game.
+
+use std::collections::VecDeque;
+use std::sync::Arc;
+
+use sme_core::time::TimeState;
+use sme_render::GpuContext;
+use winit::event::WindowEvent;
+use winit::window::{Window, WindowId};
+
+use crate::debug_overlay::OverlayStats;
+use crate::script_watch::{show_sparkline, PLOT_HISTORY};
+use crate::tool_window::ToolWindow;
+
+pub struct Inspector {
+    window: ToolWindow,
+    /// Frame times in milliseconds, oldest first.
+    frame_ms: VecDeque<f32>,
+}
+
+impl Inspector {
+    pub fn new(gpu: &GpuContext, window: Arc<Window>) -> Self {
+        Self {
+            window: ToolWindow::new(gpu, window),
+            frame_ms: VecDeque::with_capacity(PLOT_HISTORY),
+        }
+    }
+
+    pub fn id(&self) -> WindowId {
+        self.window.id()
+    }
+
+    pub fn handle_window_event(&mut self, gpu: &GpuContext, event: &WindowEvent) {
+        self.window.handle_window_event(gpu, event);
+    }
+
+    /// Record this frame's timing and redraw the window.
+    pub fn render(&mut self, gpu: &GpuContext, time: &TimeState, stats: &OverlayStats) {
+        if self.frame_ms.len() == PLOT_HISTORY {
+            self.frame_ms.pop_front();
+        }
+        self.frame_ms.push_back((time.real_dt * 1000.0) as f32);
+        let frame_ms = &self.frame_ms;
+        self.window.render(gpu, |ctx| {
+            egui::CentralPanel::default().show(ctx, |ui| {
+                ui.heading("Frame");
+                ui.label(format!(
+                    "{:.1} FPS, {:.2} ms avg",
+                    time.smoothed_fps, time.smoothed_frame_time_ms
+                ));
+                show_sparkline(ui, frame_ms);
+                ui.label(format!(
+                    "Steps this frame: {}  total: {}",
+                    time.steps_this_frame, time.fixed_step_count
+                ));
+                ui.separator();
+                ui.heading("Scene");
+                egui::Grid::new("inspector_stats")
+                    .num_columns(2)
+                    .striped(true)
+                    .show(ui, |ui| {
+                        let rows = [
+                            ("Draw calls", stats.draw_calls.to_string()),
+                            ("Atlas binds", stats.atlas_binds.to_string()),
+                            ("Sprites", stats.sprite_count.to_string()),
+                            ("Atlases", stats.atlas_count.to_string()),
+                            ("Animations", stats.active_animations.to_string()),
+                            ("Memory", format!("{:.1} MB", stats.memory_estimate_mb)),
+                            ("Camera", stats.camera_label.clone()),
+                            ("Fidelity", stats.tier_label.clone()),
+                            ("Script", stats.lua_status_label.clone()),
+                        ];
+                        for (name, value) in rows {
+                            ui.label(name);
+                            ui.label(value);
+                            ui.end_row();
+                        }
+                    });
+                if stats.paused {
+                    ui.label("\u{23f8} PAUSED");
+                }
+            });
+        });
+    }
+}
@@ -0,0 +1,100 @@
+//! "Layers" window for tuning scene layers live.
+//!
+//! The game copies each layer's settings into a `LayerView` before the
+//! overlay runs and copies them back when `OverlayActions::layers_changed` is
+//! set, so slider drags show up on the next frame. Saving is a separate,
+//! explicit click; until then edits are lost on the next scene reload.
+//! Layer group checkboxes are runtime state and are never saved.
+
+/// Editable settings of one scene layer.
+#[derive(Debug, Clone, Default)]
+pub struct LayerView {
+    pub id: String,
+    pub parallax: f32,
+    pub visible: bool,
+    pub opacity: f32,
+    /// Screen-space layers ignore parallax, so the slider is disabled.
+    pub screen_space: bool,
+}
+
+/// Shown/hidden state of one layer group.
+#[derive(Debug, Clone, Default)]
+pub struct LayerGroupView {
+    pub name: String,
+    pub visible: bool,
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct LayerPanel {
+    pub layers: Vec<LayerView>,
+    pub groups: Vec<LayerGroupView>,
+    /// Edits not yet saved to the scene file.
+    pub unsaved: bool,
+    /// Outcome of the last save, shown under the button.
+    pub status: Option<String>,
+}
+
+impl LayerPanel {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Draw the window. Returns `(changed, groups_changed, save_clicked)`.
+    pub(crate) fn show(&mut self, ctx: &egui::Context) -> (bool, bool, bool) {
+        let mut changed = false;
+        let mut groups_changed = false;
+        let mut save = false;
+        egui::Window::new("Layers")
+            .default_pos([10.0, 420.0])
+            .default_open(false)
+            .show(ctx, |ui| {
+                egui::Grid::new("layer_grid")
+                    .num_columns(4)
+                    .striped(true)
+                    .show(ui, |ui| {
+                        ui.label("Layer");
+                        ui.label("Visible");
+                        ui.label("Parallax");
+                        ui.label("Opacity");
+                        ui.end_row();
+                        for layer in &mut self.layers {
+                            ui.label(&layer.id);
+                            changed |= ui.checkbox(&mut layer.visible, "").changed();
+                            changed |= ui
+                                .add_enabled(
+                                    !layer.screen_space,
+                                    egui::Slider::new(&mut layer.parallax, 0.0..=2.0),
+                                )
+                                .changed();
+                            changed |= ui
+                                .add(egui::Slider::new(&mut layer.opacity, 0.0..=1.0))
+                                .changed();
+                            ui.end_row();
+                        }
+                    });
+                if !self.groups.is_empty() {
+                    ui.separator();
+                    ui.horizontal_wrapped(|ui| {
+                        ui.label("Groups:");
+                        for group in &mut self.groups {
+                            groups_changed |=
+                                ui.checkbox(&mut group.visible, &group.name).changed();
+                        }
+                    });
+                }
+                ui.separator();
+                ui.horizontal(|ui| {
+                    save = ui
+                        .add_enabled(self.unsaved, egui::Button::new("Save to scene file"))
+                        .clicked();
+                    if self.unsaved {
+                        ui.label("unsaved changes");
+                    }
+                });
+                if let Some(status) = &self.status {
+                    ui.label(status);
+                }
+            });
+        (changed, groups_changed, save)
+    }
+}
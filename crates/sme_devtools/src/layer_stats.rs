@@ -0,0 +1,60 @@
+//! "Layer sprites" section of the debug overlay: each scene layer's sprites
+//! in the last mesh build, split into drawn and culled (outside the view),
+//! as a table that folds out under the Sprites count.
+
+/// One scene layer's sprites in the last mesh build.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct LayerStatsView {
+    pub id: String,
+    /// Sprites the layer holds, spawned actors included and collected
+    /// pickups left out
+    pub sprites: u32,
+    /// Sprites with a quad in the mesh
+    pub drawn: u32,
+    /// Sprites left out for lying wholly outside the view
+    pub culled: u32,
+    /// `space: "screen"` layer
+    pub screen: bool,
+    /// Left out by a hidden layer group; nothing of it was built
+    pub hidden: bool,
+}
+
+/// The table, one row per layer in scene order, with totals.
+pub fn show_layer_stats(ui: &mut egui::Ui, layers: &[LayerStatsView]) {
+    if layers.is_empty() {
+        ui.weak("No layers");
+        return;
+    }
+    egui::Grid::new("layer_stats")
+        .num_columns(4)
+        .striped(true)
+        .show(ui, |ui| {
+            for heading in ["Layer", "Sprites", "Drawn", "Culled"] {
+                ui.strong(heading);
+            }
+            ui.end_row();
+            for layer in layers {
+                let mut name = layer.id.clone();
+                if layer.screen {
+                    name.push_str(" (screen)");
+                }
+                if layer.hidden {
+                    ui.weak(format!("{name} (hidden)"));
+                } else {
+                    ui.label(name);
+                }
+                ui.label(layer.sprites.to_string());
+                ui.label(layer.drawn.to_string());
+                ui.label(layer.culled.to_string());
+                ui.end_row();
+            }
+            let total = |count: fn(&LayerStatsView) -> u32| {
+                layers.iter().map(count).sum::<u32>().to_string()
+            };
+            ui.strong("Total");
+            ui.strong(total(|layer| layer.sprites));
+            ui.strong(total(|layer| layer.drawn));
+            ui.strong(total(|layer| layer.culled));
+            ui.end_row();
+        });
+}
@@ -1,3 +1,31 @@
+pub mod asset_panel;
+pub mod budget;
+pub mod console_panel;
 pub mod debug_overlay;
+pub mod dialog_panel;
+pub mod inspector;
+pub mod layer_panel;
+pub mod layer_stats;
+pub mod log_panel;
+pub mod lua_error_panel;
+pub mod memory_panel;
+pub mod reload_toast;
+pub mod script_watch;
+pub mod tool_window;
+pub mod world_label;
 
-pub use debug_overlay::{DebugOverlay, OverlayActions, OverlayStats};
+pub use asset_panel::{AssetPanel, AssetReload, AssetView};
+pub use budget::{BudgetSample, BudgetStat, BudgetWarnings, PerformanceBudget};
+pub use console_panel::ConsoleLineView;
+pub use debug_overlay::{DebugOverlay, OverlayActions, OverlayPainter, OverlayStats, StepUntil};
+pub use dialog_panel::DialogView;
+pub use inspector::Inspector;
+pub use layer_panel::{LayerGroupView, LayerPanel, LayerView};
+pub use layer_stats::LayerStatsView;
+pub use log_panel::LogPanel;
+pub use lua_error_panel::LuaErrorView;
+pub use memory_panel::{FrameAllocations, MemoryStats};
+pub use reload_toast::{ReloadError, ReloadErrors, ReloadRecord};
+pub use script_watch::ScriptWatches;
+pub use tool_window::ToolWindow;
+pub use world_label::WorldLabel;
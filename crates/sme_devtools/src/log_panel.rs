@@ -0,0 +1,90 @@
+//! "Logging" section of the Debug window: the engine logger's default and
+//! per-module levels, editable at runtime.
+//!
+//! Edits go straight to `sme_core::logging::set_levels` and last until the
+//! game exits; `config/logging.toml` is never rewritten from here.
+
+use log::LevelFilter;
+use sme_core::logging::{self, LogLevels};
+
+const LEVELS: [LevelFilter; 6] = [
+    LevelFilter::Off,
+    LevelFilter::Error,
+    LevelFilter::Warn,
+    LevelFilter::Info,
+    LevelFilter::Debug,
+    LevelFilter::Trace,
+];
+
+#[derive(Debug, Clone, Default)]
+pub struct LogPanel {
+    /// Module path typed into the "Add" field.
+    new_module: String,
+}
+
+impl LogPanel {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub(crate) fn show(&mut self, ui: &mut egui::Ui) {
+        let Some(mut levels) = logging::levels() else {
+            ui.weak("Engine logger not installed");
+            return;
+        };
+        let before = levels.clone();
+        egui::Grid::new("log_levels")
+            .num_columns(3)
+            .striped(true)
+            .show(ui, |ui| {
+                ui.label("default");
+                level_combo(ui, "log_default", &mut levels.default);
+                ui.end_row();
+                let mut removed = None;
+                for (module, level) in levels.modules.iter_mut() {
+                    ui.label(module);
+                    level_combo(ui, module, level);
+                    if ui.small_button("x").clicked() {
+                        removed = Some(module.clone());
+                    }
+                    ui.end_row();
+                }
+                if let Some(module) = removed {
+                    levels.modules.remove(&module);
+                }
+            });
+        ui.horizontal(|ui| {
+            ui.text_edit_singleline(&mut self.new_module);
+            let module = self.new_module.trim();
+            if ui
+                .add_enabled(!module.is_empty(), egui::Button::new("Add"))
+                .clicked()
+            {
+                let level = levels.level_for(module);
+                levels.modules.insert(module.to_string(), level);
+                self.new_module.clear();
+            }
+        });
+        if levels != before {
+            apply(levels);
+        }
+    }
+}
+
+fn level_combo(ui: &mut egui::Ui, id: &str, level: &mut LevelFilter) {
+    egui::ComboBox::from_id_salt(id)
+        .selected_text(level.as_str())
+        .show_ui(ui, |ui| {
+            for option in LEVELS {
+                ui.selectable_value(level, option, option.as_str());
+            }
+        });
+}
+
+fn apply(levels: LogLevels) {
+    log::info!(
+        "Log levels changed from the overlay: default {}",
+        levels.default
+    );
+    logging::set_levels(levels);
+}
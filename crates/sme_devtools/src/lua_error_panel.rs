@@ -0,0 +1,53 @@
+//! Recent Lua errors, shown in the Debug window under the Lua status line.
+//!
+//! Each error is a collapsible entry: the message, the script lines around
+//! the failure with the offending line highlighted, and the stack traceback.
+//! The newest error starts expanded.
+
+/// One script error ready for display.
+#[derive(Debug, Clone, Default)]
+pub struct LuaErrorView {
+    /// Where the error surfaced, e.g. `on_update`.
+    pub context: String,
+    pub message: String,
+    pub traceback: Option<String>,
+    /// Line the error points at; highlighted in `excerpt`.
+    pub line: Option<usize>,
+    /// `(line_number, text)` around `line`.
+    pub excerpt: Vec<(usize, String)>,
+}
+
+const HIGHLIGHT: egui::Color32 = egui::Color32::from_rgb(255, 110, 110);
+
+pub(crate) fn show_lua_errors(ui: &mut egui::Ui, errors: &[LuaErrorView]) {
+    for (i, error) in errors.iter().enumerate() {
+        let title = match error.line {
+            Some(line) => format!("{} (line {line})", error.context),
+            None => error.context.clone(),
+        };
+        egui::CollapsingHeader::new(egui::RichText::new(title).color(HIGHLIGHT))
+            .id_salt(("lua_error", i))
+            .default_open(i == 0)
+            .show(ui, |ui| {
+                ui.label(&error.message);
+                if !error.excerpt.is_empty() {
+                    ui.add_space(4.0);
+                    for (number, text) in &error.excerpt {
+                        let row = egui::RichText::new(format!("{number:>4} | {text}")).monospace();
+                        if Some(*number) == error.line {
+                            ui.label(row.color(egui::Color32::BLACK).background_color(HIGHLIGHT));
+                        } else {
+                            ui.label(row);
+                        }
+                    }
+                }
+                if let Some(traceback) = &error.traceback {
+                    egui::CollapsingHeader::new("stack traceback")
+                        .id_salt(("lua_traceback", i))
+                        .show(ui, |ui| {
+                            ui.label(egui::RichText::new(traceback).monospace().small());
+                        });
+                }
+            });
+    }
+}
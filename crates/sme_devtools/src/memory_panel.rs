@@ -0,0 +1,68 @@
+//! "Memory" section of the debug overlay: estimated bytes per asset
+//! category, the CPU mesh vectors' fill against their capacity, and heap
+//! allocations during the last frame when the game was built with its
+//! `alloc-stats` feature.
+
+/// Heap activity between two frames, from the game's counting allocator.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct FrameAllocations {
+    /// Calls to `alloc`, `alloc_zeroed` and `realloc`
+    pub count: u64,
+    /// Bytes those calls asked for
+    pub bytes: u64,
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct MemoryStats {
+    /// Estimated bytes per category (e.g. "Textures (GPU)"), in display order
+    pub categories: Vec<(&'static str, usize)>,
+    /// CPU mesh vertices: (used, capacity)
+    pub mesh_vertices: (usize, usize),
+    /// CPU mesh indices: (used, capacity)
+    pub mesh_indices: (usize, usize),
+    /// `None` unless the counting allocator is installed
+    pub frame_allocations: Option<FrameAllocations>,
+}
+
+impl MemoryStats {
+    pub fn show(&self, ui: &mut egui::Ui) {
+        egui::Grid::new("memory_categories")
+            .num_columns(2)
+            .striped(true)
+            .show(ui, |ui| {
+                for (category, bytes) in &self.categories {
+                    ui.label(*category);
+                    ui.label(format_bytes(*bytes as u64));
+                    ui.end_row();
+                }
+            });
+        ui.label(format!(
+            "Mesh vertices: {} / {}",
+            self.mesh_vertices.0, self.mesh_vertices.1
+        ));
+        ui.label(format!(
+            "Mesh indices: {} / {}",
+            self.mesh_indices.0, self.mesh_indices.1
+        ));
+        match self.frame_allocations {
+            Some(allocations) => ui.label(format!(
+                "Allocations this frame: {} ({})",
+                allocations.count,
+                format_bytes(allocations.bytes)
+            )),
+            None => ui.weak("Allocation counts: build with --features alloc-stats"),
+        };
+    }
+}
+
+fn format_bytes(bytes: u64) -> String {
+    const KB: f64 = 1024.0;
+    let value = bytes as f64;
+    if value >= KB * KB {
+        format!("{:.1} MB", value / (KB * KB))
+    } else if value >= KB {
+        format!("{:.1} KB", value / KB)
+    } else {
+        format!("{bytes} B")
+    }
+}
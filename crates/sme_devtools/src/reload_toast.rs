@@ -0,0 +1,123 @@
+//! Banner listing content that failed to hot reload.
+//!
+//! When a watched file (scene, atlas, animation, collision, Lua, ...) fails to
+//! load or validate, the engine keeps running on the previous version and
+//! records the failure here. The banner sits in the top-right corner, is drawn
+//! whether or not the debug window is visible, and never takes input. An entry
+//! disappears as soon as the same file reloads successfully.
+//!
+//! The outcome of every reload, good or bad, is also kept per file for the
+//! Assets window (`asset_panel`).
+
+use std::collections::HashMap;
+use std::time::Instant;
+
+/// Failures kept at once; older ones drop off.
+pub const MAX_RELOAD_ERRORS: usize = 6;
+
+const BANNER_WIDTH: f32 = 420.0;
+pub(crate) const ERROR_COLOR: egui::Color32 = egui::Color32::from_rgb(255, 110, 110);
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ReloadError {
+    /// Asset kind, e.g. `scene` or `lua`.
+    pub kind: String,
+    pub path: String,
+    pub reason: String,
+}
+
+/// When a file last reloaded, and whether that worked.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ReloadRecord {
+    pub at: Instant,
+    pub ok: bool,
+}
+
+/// Most recent failure per file, newest first.
+#[derive(Debug, Clone, Default)]
+pub struct ReloadErrors {
+    errors: Vec<ReloadError>,
+    last_reload: HashMap<String, ReloadRecord>,
+}
+
+impl ReloadErrors {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Record a failed reload of `path`, replacing any earlier failure of it.
+    pub fn failed(&mut self, kind: &str, path: &str, reason: &str) {
+        self.errors.retain(|error| error.path != path);
+        self.errors.insert(
+            0,
+            ReloadError {
+                kind: kind.to_string(),
+                path: path.to_string(),
+                reason: reason.to_string(),
+            },
+        );
+        self.errors.truncate(MAX_RELOAD_ERRORS);
+        self.record(path, false);
+    }
+
+    /// `path` reloaded cleanly; dismiss its failure, if any.
+    pub fn succeeded(&mut self, path: &str) {
+        self.errors.retain(|error| error.path != path);
+        self.record(path, true);
+    }
+
+    fn record(&mut self, path: &str, ok: bool) {
+        let record = ReloadRecord {
+            at: Instant::now(),
+            ok,
+        };
+        self.last_reload.insert(path.to_string(), record);
+    }
+
+    /// The last reload of `path` since startup, if any.
+    pub fn last_reload(&self, path: &str) -> Option<ReloadRecord> {
+        self.last_reload.get(path).copied()
+    }
+
+    /// The failure `path` is still showing, if any.
+    pub fn error_for(&self, path: &str) -> Option<&ReloadError> {
+        self.errors.iter().find(|error| error.path == path)
+    }
+
+    pub fn errors(&self) -> &[ReloadError] {
+        &self.errors
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.errors.is_empty()
+    }
+
+    pub(crate) fn show(&self, ctx: &egui::Context) {
+        if self.errors.is_empty() {
+            return;
+        }
+        egui::Area::new(egui::Id::new("reload_errors"))
+            .anchor(egui::Align2::RIGHT_TOP, [-10.0, 10.0])
+            .interactable(false)
+            .show(ctx, |ui| {
+                egui::Frame::popup(ui.style())
+                    .fill(egui::Color32::from_rgba_unmultiplied(60, 0, 0, 220))
+                    .show(ui, |ui| {
+                        ui.set_max_width(BANNER_WIDTH);
+                        ui.label(
+                            egui::RichText::new("Reload failed -- running previous version")
+                                .strong()
+                                .color(ERROR_COLOR),
+                        );
+                        for error in &self.errors {
+                            ui.separator();
+                            ui.label(
+                                egui::RichText::new(format!("[{}] {}", error.kind, error.path))
+                                    .strong(),
+                            );
+                            ui.label(egui::RichText::new(&error.reason).small());
+                        }
+                    });
+            });
+    }
+}
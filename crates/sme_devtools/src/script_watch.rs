@@ -0,0 +1,117 @@
+//! Script-defined rows and plots for the Debug window.
+//!
+//! Gameplay scripts report values with `engine.debug.watch(name, value)` and
+//! `engine.debug.plot(name, v)`. The engine forwards them here once per fixed
+//! step; the overlay draws every watch as a `name: value` row and every plot
+//! as a sparkline over its last `PLOT_HISTORY` samples. Entries keep the
+//! order in which a script first reported them, so rows don't jump around.
+
+use std::collections::VecDeque;
+
+/// Samples kept per plot (4 seconds at 60 Hz).
+pub const PLOT_HISTORY: usize = 240;
+
+const PLOT_SIZE: egui::Vec2 = egui::vec2(220.0, 40.0);
+
+#[derive(Debug, Clone, Default)]
+pub struct ScriptWatches {
+    watches: Vec<(String, String)>,
+    plots: Vec<(String, VecDeque<f32>)>,
+}
+
+impl ScriptWatches {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Set the displayed value of watch `name`.
+    pub fn set_watch(&mut self, name: &str, value: String) {
+        match self.watches.iter_mut().find(|(n, _)| n == name) {
+            Some((_, current)) => *current = value,
+            None => self.watches.push((name.to_string(), value)),
+        }
+    }
+
+    /// Append a sample to plot `name`, dropping the oldest past `PLOT_HISTORY`.
+    pub fn push_plot(&mut self, name: &str, value: f32) {
+        let samples = match self.plots.iter().position(|(n, _)| n == name) {
+            Some(i) => &mut self.plots[i].1,
+            None => {
+                self.plots.push((name.to_string(), VecDeque::new()));
+                &mut self.plots.last_mut().expect("just pushed").1
+            }
+        };
+        if samples.len() == PLOT_HISTORY {
+            samples.pop_front();
+        }
+        samples.push_back(value);
+    }
+
+    /// Forget everything, e.g. when the script reloads.
+    pub fn clear(&mut self) {
+        self.watches.clear();
+        self.plots.clear();
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.watches.is_empty() && self.plots.is_empty()
+    }
+
+    pub(crate) fn show(&self, ui: &mut egui::Ui) {
+        for (name, value) in &self.watches {
+            ui.label(format!("{name}: {value}"));
+        }
+        for (name, samples) in &self.plots {
+            let latest = samples.back().copied().unwrap_or(0.0);
+            ui.label(format!("{name}: {latest:.3}"));
+            show_sparkline(ui, samples);
+        }
+    }
+}
+
+/// Line plot of `samples`, scaled to their own min/max.
+pub(crate) fn show_sparkline(ui: &mut egui::Ui, samples: &VecDeque<f32>) {
+    let (rect, _) = ui.allocate_exact_size(PLOT_SIZE, egui::Sense::hover());
+    let painter = ui.painter_at(rect);
+    painter.rect_filled(rect, 2.0, egui::Color32::from_black_alpha(96));
+    if samples.len() < 2 {
+        return;
+    }
+
+    let (min, max) = samples
+        .iter()
+        .fold((f32::INFINITY, f32::NEG_INFINITY), |(lo, hi), &v| {
+            (lo.min(v), hi.max(v))
+        });
+    let span = if max > min { max - min } else { 1.0 };
+    let step = rect.width() / (PLOT_HISTORY - 1) as f32;
+    let points: Vec<egui::Pos2> = samples
+        .iter()
+        .enumerate()
+        .map(|(i, &v)| {
+            egui::pos2(
+                rect.left() + i as f32 * step,
+                rect.bottom() - (v - min) / span * rect.height(),
+            )
+        })
+        .collect();
+    painter.add(egui::Shape::line(
+        points,
+        egui::Stroke::new(1.5, egui::Color32::LIGHT_GREEN),
+    ));
+    let small = egui::FontId::monospace(10.0);
+    painter.text(
+        rect.left_top(),
+        egui::Align2::LEFT_TOP,
+        format!("{max:.2}"),
+        small.clone(),
+        egui::Color32::GRAY,
+    );
+    painter.text(
+        rect.left_bottom(),
+        egui::Align2::LEFT_BOTTOM,
+        format!("{min:.2}"),
+        small,
+        egui::Color32::GRAY,
+    );
+}
@@ -0,0 +1,116 @@
+//! Detached egui windows that share the game's GPU device.
+//!
+//! A `ToolWindow` owns its OS window, surface and egui context. The game
+//! routes `WindowEvent`s to it by `WindowId` and renders it once per main-loop
+//! frame, after the game window. Unlike the overlay it can never cover the
+//! scene or swallow game input, which makes it the base for editor panels.
+
+use std::sync::Arc;
+
+use sme_render::{GpuContext, WindowSurface};
+use winit::event::WindowEvent;
+use winit::window::{Window, WindowId};
+
+pub struct ToolWindow {
+    surface: WindowSurface,
+    egui_ctx: egui::Context,
+    egui_winit_state: egui_winit::State,
+    egui_renderer: egui_wgpu::Renderer,
+}
+
+impl ToolWindow {
+    pub fn new(gpu: &GpuContext, window: Arc<Window>) -> Self {
+        let surface = gpu.create_window_surface(window.clone());
+        let egui_ctx = egui::Context::default();
+        let egui_winit_state = egui_winit::State::new(
+            egui_ctx.clone(),
+            egui_ctx.viewport_id(),
+            &window,
+            None,
+            None,
+            None,
+        );
+        let egui_renderer = egui_wgpu::Renderer::new(&gpu.device, surface.format(), None, 1, false);
+        Self {
+            surface,
+            egui_ctx,
+            egui_winit_state,
+            egui_renderer,
+        }
+    }
+
+    pub fn id(&self) -> WindowId {
+        self.surface.window().id()
+    }
+
+    /// Feed an event addressed to this window to egui, resizing the surface
+    /// when needed.
+    pub fn handle_window_event(&mut self, gpu: &GpuContext, event: &WindowEvent) {
+        if let WindowEvent::Resized(size) = event {
+            self.surface.resize(&gpu.device, size.width, size.height);
+        }
+        let _ = self
+            .egui_winit_state
+            .on_window_event(self.surface.window(), event);
+    }
+
+    /// Run `ui` and present the result. Skipped while the window is minimized.
+    pub fn render(&mut self, gpu: &GpuContext, ui: impl FnMut(&egui::Context)) {
+        let Some((output, view)) = self.surface.begin_frame(&gpu.device) else {
+            return;
+        };
+        let window = self.surface.window().clone();
+        let raw_input = self.egui_winit_state.take_egui_input(&window);
+        let full_output = self.egui_ctx.run(raw_input, ui);
+        self.egui_winit_state
+            .handle_platform_output(&window, full_output.platform_output);
+        let primitives = self
+            .egui_ctx
+            .tessellate(full_output.shapes, full_output.pixels_per_point);
+        let screen_descriptor = egui_wgpu::ScreenDescriptor {
+            size_in_pixels: [self.surface.size.0, self.surface.size.1],
+            pixels_per_point: full_output.pixels_per_point,
+        };
+
+        let mut encoder = gpu
+            .device
+            .create_command_encoder(&wgpu::CommandEncoderDescriptor {
+                label: Some("Tool Window Encoder"),
+            });
+        for (id, image_delta) in &full_output.textures_delta.set {
+            self.egui_renderer
+                .update_texture(&gpu.device, &gpu.queue, *id, image_delta);
+        }
+        self.egui_renderer.update_buffers(
+            &gpu.device,
+            &gpu.queue,
+            &mut encoder,
+            &primitives,
+            &screen_descriptor,
+        );
+        {
+            let mut pass = encoder
+                .begin_render_pass(&wgpu::RenderPassDescriptor {
+                    label: Some("Tool Window Pass"),
+                    color_attachments: &[Some(wgpu::RenderPassColorAttachment {
+                        view: &view,
+                        resolve_target: None,
+                        ops: wgpu::Operations {
+                            load: wgpu::LoadOp::Clear(wgpu::Color::BLACK),
+                            store: wgpu::StoreOp::Store,
+                        },
+                    })],
+                    depth_stencil_attachment: None,
+                    ..Default::default()
+                })
+                .forget_lifetime();
+            self.egui_renderer
+                .render(&mut pass, &primitives, &screen_descriptor);
+        }
+        gpu.queue.submit(std::iter::once(encoder.finish()));
+        output.present();
+        for id in &full_output.textures_delta.free {
+            self.egui_renderer.free_texture(id);
+        }
+    }
+}
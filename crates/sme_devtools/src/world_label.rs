@@ -0,0 +1,36 @@
+//! Text labels pinned to world positions (`engine.debug.draw_text`).
+//!
+//! The sprite mesh has no text rendering, so the game projects each label to
+//! the window and hands it over before `DebugOverlay::prepare`. Labels are
+//! painted behind every egui window, whether or not the overlay is visible.
+
+/// One label, already projected to window pixels (origin top-left).
+#[derive(Debug, Clone, PartialEq)]
+pub struct WorldLabel {
+    pub screen_px: [f32; 2],
+    pub text: String,
+    pub color: [f32; 4],
+}
+
+const LABEL_FONT_SIZE: f32 = 13.0;
+
+pub(crate) fn show_world_labels(ctx: &egui::Context, labels: &[WorldLabel]) {
+    if labels.is_empty() {
+        return;
+    }
+    let painter = ctx.layer_painter(egui::LayerId::background());
+    let pixels_per_point = ctx.pixels_per_point();
+    for label in labels {
+        let [r, g, b, a] = label
+            .color
+            .map(|c| (c.clamp(0.0, 1.0) * 255.0).round() as u8);
+        let position = egui::pos2(label.screen_px[0], label.screen_px[1]) / pixels_per_point;
+        painter.text(
+            position,
+            egui::Align2::LEFT_TOP,
+            &label.text,
+            egui::FontId::monospace(LABEL_FONT_SIZE),
+            egui::Color32::from_rgba_unmultiplied(r, g, b, a),
+        );
+    }
+}
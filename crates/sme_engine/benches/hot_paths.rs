@@ -0,0 +1,73 @@
+//! Baselines for the engine's per-frame and per-step hot paths.
+//!
+//! `cargo bench -p sme_engine` runs them all; `cargo bench -p sme_engine -- mesh`
+//! runs one group. Criterion keeps the last run under `target/criterion` and
+//! reports the change against it, so run the suite on the base branch first
+//! and again on the change being measured.
+
+use std::hint::black_box;
+
+use criterion::{criterion_group, criterion_main, BenchmarkId, Criterion, Throughput};
+use sme_engine::bench::{AnimationFixture, AtlasFixture, CollisionFixture, MeshFixture};
+
+/// Sprite mesh building for one world layer. The largest size goes through
+/// the parallel path.
+fn mesh(c: &mut Criterion) {
+    let mut group = c.benchmark_group("mesh");
+    for sprites in [1_000, 10_000, 50_000] {
+        let fixture = MeshFixture::new(sprites);
+        group.throughput(Throughput::Elements(sprites as u64));
+        group.bench_with_input(
+            BenchmarkId::from_parameter(sprites),
+            &fixture,
+            |b, fixture| b.iter(|| black_box(fixture.build())),
+        );
+    }
+    group.finish();
+}
+
+/// One collision sweep per body against a platform grid.
+fn collision(c: &mut Criterion) {
+    let mut group = c.benchmark_group("collision");
+    for bodies in [100, 1_000, 10_000] {
+        let fixture = CollisionFixture::new(bodies);
+        group.throughput(Throughput::Elements(bodies as u64));
+        group.bench_with_input(
+            BenchmarkId::from_parameter(bodies),
+            &fixture,
+            |b, fixture| b.iter(|| black_box(fixture.sweep())),
+        );
+    }
+    group.finish();
+}
+
+/// One fixed step of animation ticking.
+fn animation(c: &mut Criterion) {
+    let mut group = c.benchmark_group("animation");
+    for states in [1_000, 5_000, 20_000] {
+        let mut fixture = AnimationFixture::new(states);
+        group.throughput(Throughput::Elements(states as u64));
+        group.bench_function(BenchmarkId::from_parameter(states), |b| {
+            b.iter(|| black_box(fixture.tick()))
+        });
+    }
+    group.finish();
+}
+
+/// Sprite lookups across several atlases, by interned id and by name.
+fn atlas(c: &mut Criterion) {
+    const LOOKUPS: usize = 10_000;
+    let fixture = AtlasFixture::new(LOOKUPS);
+    let mut group = c.benchmark_group("atlas");
+    group.throughput(Throughput::Elements(LOOKUPS as u64));
+    group.bench_function("resolve_id", |b| {
+        b.iter(|| black_box(fixture.resolve_ids()))
+    });
+    group.bench_function("resolve_name", |b| {
+        b.iter(|| black_box(fixture.resolve_names()))
+    });
+    group.finish();
+}
+
+criterion_group!(benches, mesh, collision, animation, atlas);
+criterion_main!(benches);
@@ -0,0 +1,296 @@
+//! Actors spawned and despawned during play.
+//!
+//! `engine.spawn(template, x, y)` copies a scene sprite -- the template, or
+//! "prefab" -- or, when no scene sprite has that id, makes a bare sprite from
+//! an atlas `sprite_id`. The copy is named `"<template>#<n>"` by `ActorIds`, a
+//! counter that only advances when something spawns, so the same inputs give
+//! the same ids; it is saved with every rewind snapshot. `engine.despawn(id)`
+//! removes an actor again.
+//!
+//! Each spawned actor is a physics body: a `CharacterController` sized from
+//! its sprite, stepped with no input after the player every fixed step, so it
+//! falls and collides with the grid. The sprite follows the body's center and
+//! is drawn in the template's layer (atlas-only spawns use the frontmost world
+//! layer); a template `animation` starts playing on spawn. The body moves with
+//! the controller preset the template names (`"controller": "heavy"`), or the
+//! base tuning, and is retuned when the tuning file reloads. Spawned actors are
+//! listed in `engine.actors` and are dropped when the scene reloads.
+
+use crate::collision::{Aabb, CollisionGrid};
+use crate::controller::{CharacterController, ControllerConfig, ControllerInput, ControllerTuning};
+use crate::scene::{LayerSpace, SceneFile, SceneSprite};
+
+/// Deterministic id allocator for spawned actors.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct ActorIds {
+    spawned: u64,
+}
+
+impl ActorIds {
+    pub fn allocate(&mut self, template: &str) -> String {
+        self.spawned += 1;
+        format!("{template}#{}", self.spawned)
+    }
+}
+
+/// Spawn or despawn request queued by a script during `on_update`.
+#[derive(Debug, Clone, PartialEq)]
+pub enum ActorCommand {
+    Spawn {
+        id: String,
+        template: String,
+        x: f32,
+        y: f32,
+    },
+    Despawn(String),
+}
+
+#[derive(Debug, Clone)]
+pub struct SpawnedActor {
+    /// Scene layer the sprite is drawn in.
+    pub layer_id: String,
+    /// Template copy with the actor's id; `x`/`y` track `body`.
+    pub sprite: SceneSprite,
+    pub body: CharacterController,
+}
+
+/// The scene sprite `template` names and its layer, or `None` if no
+/// world-space sprite has that id.
+pub fn find_template(scene: &SceneFile, template: &str) -> Option<(String, SceneSprite)> {
+    scene
+        .layers
+        .iter()
+        .filter(|layer| layer.space == LayerSpace::World)
+        .find_map(|layer| {
+            layer
+                .sprites
+                .iter()
+                .find(|sprite| sprite.id == template)
+                .map(|sprite| (layer.id.clone(), sprite.clone()))
+        })
+}
+
+/// Template for an atlas sprite that no scene sprite uses as an id, placed
+/// in the frontmost world layer.
+pub fn atlas_template(scene: &SceneFile, sprite_id: &str) -> Option<(String, SceneSprite)> {
+    let layer = scene
+        .layers
+        .iter()
+        .rev()
+        .find(|layer| layer.space == LayerSpace::World)?;
+    let sprite = SceneSprite {
+        id: sprite_id.to_string(),
+        asset: None,
+        sprite_id: Some(sprite_id.to_string()),
+        animation: None,
+        animation_source: None,
+        x: 0.0,
+        y: 0.0,
+        z: 0.0,
+        rotation_deg: 0.0,
+        scale_x: 1.0,
+        scale_y: 1.0,
+        trail: None,
+        effect: None,
+        pickup: None,
+        tweens: Vec::new(),
+        controller: None,
+        pivot: None,
+    };
+    Some((layer.id.clone(), sprite))
+}
+
+/// Live spawned actors, in spawn order.
+#[derive(Debug, Clone, Default)]
+pub struct SpawnedActors {
+    actors: Vec<SpawnedActor>,
+}
+
+impl SpawnedActors {
+    /// Add an actor built from `template`, with a body of `half_size`
+    /// centered on `(x, y)` that moves with `config`.
+    pub fn spawn(
+        &mut self,
+        id: String,
+        (layer_id, template): (String, SceneSprite),
+        half_size: (f32, f32),
+        config: ControllerConfig,
+        x: f32,
+        y: f32,
+    ) -> Result<(), String> {
+        if self.get(&id).is_some() {
+            return Err(format!("actor '{id}' already exists"));
+        }
+        let mut sprite = template;
+        sprite.id = id;
+        sprite.x = x;
+        sprite.y = y;
+        let mut body = CharacterController::new(Aabb {
+            center_x: x,
+            center_y: y,
+            half_w: half_size.0,
+            half_h: half_size.1,
+        });
+        body.config = config;
+        self.actors.push(SpawnedActor {
+            layer_id,
+            sprite,
+            body,
+        });
+        Ok(())
+    }
+
+    /// Remove an actor; false if there was none with that id.
+    pub fn despawn(&mut self, id: &str) -> bool {
+        let before = self.actors.len();
+        self.actors.retain(|actor| actor.sprite.id != id);
+        self.actors.len() != before
+    }
+
+    /// Give every body the config its template's preset now has.
+    pub fn retune(&mut self, tuning: &ControllerTuning) {
+        for actor in &mut self.actors {
+            actor.body.config = tuning.config(actor.sprite.controller.as_deref());
+        }
+    }
+
+    /// Advance every body one fixed step and move its sprite along.
+    pub fn step(&mut self, dt: f32, grid: &CollisionGrid) {
+        let idle = ControllerInput {
+            move_x: 0.0,
+            jump_pressed: false,
+            crouch: false,
+            dash: false,
+        };
+        for actor in &mut self.actors {
+            actor.body.step(idle, dt, grid);
+            actor.sprite.x = actor.body.aabb.center_x;
+            actor.sprite.y = actor.body.aabb.center_y;
+        }
+    }
+
+    pub fn get(&self, id: &str) -> Option<&SpawnedActor> {
+        self.actors.iter().find(|actor| actor.sprite.id == id)
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = &SpawnedActor> {
+        self.actors.iter()
+    }
+
+    /// Sprites of the actors drawn in `layer_id`.
+    pub fn sprites_in_layer<'a>(
+        &'a self,
+        layer_id: &'a str,
+    ) -> impl Iterator<Item = &'a SceneSprite> + 'a {
+        self.actors
+            .iter()
+            .filter(move |actor| actor.layer_id == layer_id)
+            .map(|actor| &actor.sprite)
+    }
+
+    pub fn clear(&mut self) {
+        self.actors.clear();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::collision::{CollisionFile, GridCell, GridOrigin};
+
+    fn scene() -> SceneFile {
+        serde_json::from_str(
+            r#"{
+                "version": "0.3",
+                "scene_id": "actors",
+                "layers": [
+                    { "id": "hud", "parallax": 0.0, "space": "screen",
+                      "sprites": [{ "id": "coin", "asset": "hud.png", "x": 0, "y": 0 }] },
+                    { "id": "mid", "parallax": 1.0,
+                      "sprites": [{ "id": "coin", "sprite_id": "coin_0", "x": 5, "y": 5,
+                                    "animation": "spin" }] },
+                    { "id": "front", "parallax": 1.0, "sprites": [] }
+                ]
+            }"#,
+        )
+        .expect("scene")
+    }
+
+    fn floor() -> CollisionGrid {
+        CollisionGrid::from_file(CollisionFile {
+            version: "0.1".to_string(),
+            collision_id: "floor".to_string(),
+            cell_size: 32,
+            origin: GridOrigin { x: 0, y: 0 },
+            width: 4,
+            height: 4,
+            solids: (0..4).map(|x| GridCell { x, y: 0 }).collect(),
+            fluids: Vec::new(),
+            surfaces: Vec::new(),
+            time_volumes: Vec::new(),
+        })
+    }
+
+    #[test]
+    fn ids_count_up_per_spawn() {
+        let mut ids = ActorIds::default();
+        assert_eq!(ids.allocate("coin"), "coin#1");
+        let saved = ids;
+        assert_eq!(ids.allocate("bat"), "bat#2");
+        let mut restored = saved;
+        assert_eq!(restored.allocate("bat"), "bat#2");
+    }
+
+    #[test]
+    fn templates_come_from_world_layers_or_the_atlas() {
+        let scene = scene();
+        let (layer, sprite) = find_template(&scene, "coin").expect("coin");
+        assert_eq!(layer, "mid");
+        assert_eq!(sprite.animation.as_deref(), Some("spin"));
+        assert!(find_template(&scene, "coin_0").is_none());
+
+        let (layer, sprite) = atlas_template(&scene, "coin_0").expect("atlas");
+        assert_eq!(layer, "front");
+        assert_eq!(sprite.sprite_id.as_deref(), Some("coin_0"));
+    }
+
+    #[test]
+    fn spawned_actors_fall_onto_the_grid_until_despawned() {
+        let scene = scene();
+        let mut actors = SpawnedActors::default();
+        let template = find_template(&scene, "coin").expect("coin");
+        actors
+            .spawn(
+                "coin#1".to_string(),
+                template.clone(),
+                (8.0, 8.0),
+                ControllerConfig::default(),
+                48.0,
+                96.0,
+            )
+            .expect("spawn");
+        let duplicate = actors.spawn(
+            "coin#1".to_string(),
+            template,
+            (8.0, 8.0),
+            ControllerConfig::default(),
+            0.0,
+            0.0,
+        );
+        assert!(duplicate.is_err());
+
+        for _ in 0..120 {
+            actors.step(1.0 / 60.0, &floor());
+        }
+        let coin = actors.get("coin#1").expect("coin");
+        assert!(coin.body.grounded);
+        // Resting on top of the floor row (y = 0..32).
+        assert!((coin.sprite.y - 40.0).abs() < 0.01, "{}", coin.sprite.y);
+        assert_eq!(actors.sprites_in_layer("mid").count(), 1);
+        assert_eq!(actors.sprites_in_layer("front").count(), 0);
+
+        assert!(actors.despawn("coin#1"));
+        assert!(!actors.despawn("coin#1"));
+        assert_eq!(actors.iter().count(), 0);
+    }
+}
@@ -0,0 +1,82 @@
+//! Heap allocation counting for the overlay's Memory panel.
+//!
+//! With the `alloc-stats` feature, `CountingAllocator` wraps the system
+//! allocator as the global allocator and bumps two relaxed atomics on every
+//! allocation; `take_frame_allocations` reads and resets them once per frame.
+//! Without the feature nothing is installed and the panel says so.
+
+use sme_devtools::FrameAllocations;
+
+#[cfg(feature = "alloc-stats")]
+mod counting {
+    use std::alloc::{GlobalAlloc, Layout, System};
+    use std::sync::atomic::{AtomicU64, Ordering};
+
+    pub static ALLOCATIONS: AtomicU64 = AtomicU64::new(0);
+    pub static ALLOCATED_BYTES: AtomicU64 = AtomicU64::new(0);
+
+    pub struct CountingAllocator;
+
+    fn count(bytes: usize) {
+        ALLOCATIONS.fetch_add(1, Ordering::Relaxed);
+        ALLOCATED_BYTES.fetch_add(bytes as u64, Ordering::Relaxed);
+    }
+
+    // SAFETY: every call is forwarded unchanged to `System`; counting does
+    // not allocate.
+    unsafe impl GlobalAlloc for CountingAllocator {
+        unsafe fn alloc(&self, layout: Layout) -> *mut u8 {
+            count(layout.size());
+            System.alloc(layout)
+        }
+
+        unsafe fn alloc_zeroed(&self, layout: Layout) -> *mut u8 {
+            count(layout.size());
+            System.alloc_zeroed(layout)
+        }
+
+        unsafe fn dealloc(&self, ptr: *mut u8, layout: Layout) {
+            System.dealloc(ptr, layout)
+        }
+
+        unsafe fn realloc(&self, ptr: *mut u8, layout: Layout, new_size: usize) -> *mut u8 {
+            count(new_size);
+            System.realloc(ptr, layout, new_size)
+        }
+    }
+
+    #[global_allocator]
+    static GLOBAL: CountingAllocator = CountingAllocator;
+}
+
+/// Allocations since the previous call, or `None` without `alloc-stats`.
+#[cfg(feature = "alloc-stats")]
+pub fn take_frame_allocations() -> Option<FrameAllocations> {
+    use std::sync::atomic::Ordering;
+
+    Some(FrameAllocations {
+        count: counting::ALLOCATIONS.swap(0, Ordering::Relaxed),
+        bytes: counting::ALLOCATED_BYTES.swap(0, Ordering::Relaxed),
+    })
+}
+
+/// Allocations since the previous call, or `None` without `alloc-stats`.
+#[cfg(not(feature = "alloc-stats"))]
+pub fn take_frame_allocations() -> Option<FrameAllocations> {
+    None
+}
+
+#[cfg(all(test, feature = "alloc-stats"))]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn allocations_are_counted_and_reset() {
+        take_frame_allocations();
+        let buffer: Vec<u8> = Vec::with_capacity(4096);
+        let counted = take_frame_allocations().expect("allocator installed");
+        assert!(counted.count >= 1);
+        assert!(counted.bytes >= 4096);
+        drop(buffer);
+    }
+}
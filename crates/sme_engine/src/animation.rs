@@ -2,12 +2,14 @@
 //!
 //! Wraps the core `AnimationFile`/`AnimationClip` types from `sme_core::animation`
 //! and provides a registry that can hold multiple animation files, resolve clips
-//! by name, and cross-validate sprite_ids against the atlas registry.
+//! by name, and cross-validate sprite_ids against the atlas registry. Clips
+//! are keyed by interned ids, so per-step lookups do not hash strings.
 
 use std::collections::HashMap;
 use std::path::Path;
 
-use sme_core::animation::{load_animation_file, AnimationClip};
+use sme_core::animation::{load_animation_file, AnimationClip, AnimationFile};
+use sme_core::interned::Id;
 
 use crate::atlas::MultiAtlasRegistry;
 
@@ -18,42 +20,80 @@ use crate::atlas::MultiAtlasRegistry;
 /// and global search (first match across all files).
 pub struct AnimationRegistry {
     /// animation_id -> clip_name -> clip
-    clips: HashMap<String, HashMap<String, AnimationClip>>,
+    clips: HashMap<Id, HashMap<Id, AnimationClip>>,
+    /// File path -> the animation_id it was loaded under.
+    files: HashMap<Id, Id>,
 }
 
 impl AnimationRegistry {
     pub fn new() -> Self {
         Self {
             clips: HashMap::new(),
+            files: HashMap::new(),
         }
     }
 
     /// Load an animation file and register its clips under its `animation_id`.
     pub fn load_file(&mut self, path: &Path) -> Result<(), String> {
         let file = load_animation_file(path)?;
-        self.clips.insert(file.animation_id, file.animations);
+        self.files.insert(
+            Id::new(&path.to_string_lossy()),
+            Id::new(&file.animation_id),
+        );
+        self.add_file(file);
         Ok(())
     }
 
+    /// The `animation_id` of the file loaded from `path`, if it loaded.
+    pub fn animation_id_of(&self, path: &Path) -> Option<Id> {
+        self.files.get(&Id::get(&path.to_string_lossy())?).copied()
+    }
+
+    /// The sprite_id of every frame of every clip under `animation_id`.
+    pub fn frame_sprite_ids(&self, animation_id: Id) -> impl Iterator<Item = Id> + '_ {
+        self.clips
+            .get(&animation_id)
+            .into_iter()
+            .flat_map(HashMap::values)
+            .flat_map(|clip| clip.frames.iter().map(|frame| frame.sprite_id))
+    }
+
+    /// Register the clips of an already loaded file under its `animation_id`.
+    pub fn add_file(&mut self, file: AnimationFile) {
+        let clips = file
+            .animations
+            .into_iter()
+            .map(|(name, clip)| (Id::new(&name), clip))
+            .collect();
+        self.clips.insert(Id::new(&file.animation_id), clips);
+    }
+
     /// Remove all clips from a previously loaded animation file.
     pub fn remove_file(&mut self, animation_id: &str) {
-        self.clips.remove(animation_id);
+        if let Some(id) = Id::get(animation_id) {
+            self.clips.remove(&id);
+            self.files.retain(|_, file_id| *file_id != id);
+        }
     }
 
     /// Clear all loaded animation data.
     #[allow(dead_code)]
     pub fn clear(&mut self) {
         self.clips.clear();
+        self.files.clear();
     }
 
     /// Resolve a clip by name. If `source` is given, only search that animation file.
     /// If `source` is None, search all loaded files (first match wins).
-    pub fn resolve_clip(&self, source: Option<&str>, name: &str) -> Option<&AnimationClip> {
+    pub fn resolve_clip(&self, source: Option<Id>, name: Id) -> Option<&AnimationClip> {
         if let Some(source_id) = source {
-            return self.clips.get(source_id).and_then(|clips| clips.get(name));
+            return self
+                .clips
+                .get(&source_id)
+                .and_then(|clips| clips.get(&name));
         }
         for file_clips in self.clips.values() {
-            if let Some(clip) = file_clips.get(name) {
+            if let Some(clip) = file_clips.get(&name) {
                 return Some(clip);
             }
         }
@@ -66,7 +106,7 @@ impl AnimationRegistry {
         for (anim_id, file_clips) in &self.clips {
             for (clip_name, clip) in file_clips {
                 for frame in &clip.frames {
-                    if multi_atlas.resolve(&frame.sprite_id).is_none() {
+                    if multi_atlas.resolve_id(frame.sprite_id).is_none() {
                         return Err(format!(
                             "Animation '{}' clip '{}' references missing sprite_id '{}'",
                             anim_id, clip_name, frame.sprite_id
@@ -129,18 +169,26 @@ mod tests {
         let mut entries = HashMap::new();
         for &id in sprite_ids {
             entries.insert(
-                id.to_string(),
+                Id::new(id),
                 AtlasSpriteEntry {
-                    texture_path: "test.png".to_string(),
+                    texture: Id::new("test.png"),
                     size_px: (32, 32),
                     uv: [0.0, 0.0, 1.0, 1.0],
+                    rotated: false,
                     pivot: (0.5, 0.5),
+                    nine_slice: None,
                 },
             );
         }
         let reg = AtlasRegistry {
             atlas_id: "test".to_string(),
+            texture: Id::new("test.png"),
+            half_res_texture: None,
+            premultiplied_alpha: false,
             sprite_entries: entries,
+            sprite_names: HashMap::new(),
+            parallel: None,
+            source_hash: 0,
         };
         let mut multi = MultiAtlasRegistry::new();
         multi.add_atlas("test.json", reg).unwrap();
@@ -155,9 +203,15 @@ mod tests {
         let mut registry = AnimationRegistry::new();
         registry.load_file(&path).expect("should load");
 
-        assert!(registry.resolve_clip(Some("hero"), "idle").is_some());
-        assert!(registry.resolve_clip(Some("hero"), "jump").is_some());
-        assert!(registry.resolve_clip(Some("hero"), "nonexistent").is_none());
+        assert!(registry
+            .resolve_clip(Some(Id::new("hero")), Id::new("idle"))
+            .is_some());
+        assert!(registry
+            .resolve_clip(Some(Id::new("hero")), Id::new("jump"))
+            .is_some());
+        assert!(registry
+            .resolve_clip(Some(Id::new("hero")), Id::new("nonexistent"))
+            .is_none());
 
         let _ = fs::remove_file(path);
     }
@@ -171,8 +225,10 @@ mod tests {
         registry.load_file(&path).expect("should load");
 
         // Without source, should still find by name
-        assert!(registry.resolve_clip(None, "idle").is_some());
-        assert!(registry.resolve_clip(None, "nonexistent").is_none());
+        assert!(registry.resolve_clip(None, Id::new("idle")).is_some());
+        assert!(registry
+            .resolve_clip(None, Id::new("nonexistent"))
+            .is_none());
 
         let _ = fs::remove_file(path);
     }
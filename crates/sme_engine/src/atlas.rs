@@ -0,0 +1,1003 @@
+//! Atlas metadata loading and sprite ID resolution.
+//!
+//! The atlas packer (tools/atlas_packer) outputs a JSON metadata file that maps
+//! stable `sprite_id` strings to texture regions (UV rects, pixel sizes, pivots).
+//! Sprite IDs are content-addressed hashes, so renaming or re-packing the atlas
+//! sheet does not break scene references.
+//!
+//! `AtlasRegistry::resolve(sprite_id)` is the primary lookup used at render time.
+//! It returns an `AtlasSpriteEntry` containing the texture path, UV rect, and
+//! pixel dimensions needed to build a sprite quad.
+//!
+//! An atlas with a `parallel` block is the secondary texture set (normal
+//! maps or emissive masks) of the atlas it names: its sprites pair with that
+//! atlas's sprites by `name`, and `MultiAtlasRegistry::resolve_secondary`
+//! returns the secondary texture and UVs for a primary sprite_id.
+//!
+//! An atlas may also name a `half_res` copy of its texture for low fidelity
+//! tiers (`MultiAtlasRegistry::texture_file`). UVs apply to both files, and
+//! `AtlasSpriteEntry::size_px` stays in design units (the full-size texture's
+//! pixels), so switching tiers never changes layout.
+//!
+//! Sprites the packer turned 90° clockwise to fit (`rotated`) keep their
+//! upright `size_px`; only their `quad_tex_coords` differ, so nothing past
+//! quad building knows about rotation.
+//!
+//! Atlases packed with `--premultiply` store premultiplied alpha. The sprite
+//! pipeline draws one convention at a time, so all primary atlases must
+//! agree, and an atlas reloaded with the other convention is rejected.
+
+use serde::Deserialize;
+use sme_core::interned::Id;
+use sme_core::migrate::SchemaMigrations;
+use sme_core::tier::FidelityTier;
+use std::collections::{HashMap, HashSet};
+use std::hash::Hasher;
+use std::path::Path;
+
+use crate::determinism::StateHasher;
+
+/// Atlas metadata schema versions and the upgrade path to the current one.
+pub const ATLAS_SCHEMA: SchemaMigrations = SchemaMigrations {
+    kind: "Atlas",
+    current: "0.1",
+    steps: &[],
+};
+
+#[derive(Debug, Deserialize, Clone)]
+pub struct AtlasFile {
+    #[allow(dead_code)]
+    pub version: String,
+    pub atlas_id: String,
+    pub texture: AtlasTexture,
+    pub sprites: Vec<AtlasSprite>,
+    #[serde(default)]
+    pub parallel: Option<AtlasParallel>,
+}
+
+/// Marks an atlas as the secondary texture set of atlas `atlas_id`.
+#[derive(Debug, Deserialize, Clone, PartialEq, Eq)]
+pub struct AtlasParallel {
+    pub atlas_id: String,
+    pub kind: SecondaryKind,
+}
+
+/// What a parallel atlas holds. Normal maps are sampled as linear data,
+/// emissive masks as sRGB color.
+#[derive(Debug, Deserialize, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum SecondaryKind {
+    Normal,
+    Emissive,
+}
+
+/// Where a primary sprite's secondary texels are.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct SecondarySprite {
+    pub texture: Id,
+    /// See `AtlasSpriteEntry::tex_coords`.
+    pub tex_coords: [[f32; 2]; 4],
+    pub kind: SecondaryKind,
+}
+
+#[derive(Debug, Deserialize, Clone)]
+pub struct AtlasTexture {
+    pub path: String,
+    pub width: u32,
+    pub height: u32,
+    /// The same texture at exactly half the size, loaded on Tier 0.
+    #[serde(default)]
+    pub half_res: Option<AtlasTextureFile>,
+    /// Color is stored multiplied by alpha.
+    #[serde(default)]
+    pub premultiplied_alpha: bool,
+}
+
+#[derive(Debug, Deserialize, Clone)]
+pub struct AtlasTextureFile {
+    pub path: String,
+    pub width: u32,
+    pub height: u32,
+}
+
+#[derive(Debug, Deserialize, Clone)]
+pub struct AtlasSprite {
+    pub sprite_id: String,
+    pub name: Option<String>,
+    #[allow(dead_code)]
+    pub source_path: String,
+    pub rect_px: AtlasRectPx,
+    pub uv: AtlasUvRect,
+    #[serde(default)]
+    pub pivot: AtlasPivot,
+    #[serde(default)]
+    pub nine_slice: Option<AtlasNineSlice>,
+    /// Set when the packer cropped transparent borders; `pivot` already
+    /// accounts for the crop.
+    #[serde(default)]
+    #[allow(dead_code)]
+    pub trim: Option<AtlasTrim>,
+    /// Stored turned 90° clockwise; `rect_px` and `uv` cover the turned
+    /// pixels.
+    #[serde(default)]
+    pub rotated: bool,
+}
+
+#[derive(Debug, Deserialize, Clone, Copy)]
+pub struct AtlasRectPx {
+    pub x: u32,
+    pub y: u32,
+    pub w: u32,
+    pub h: u32,
+}
+
+#[derive(Debug, Deserialize, Clone, Copy)]
+pub struct AtlasUvRect {
+    pub u0: f32,
+    pub v0: f32,
+    pub u1: f32,
+    pub v1: f32,
+}
+
+#[derive(Debug, Deserialize, Clone, Copy)]
+pub struct AtlasPivot {
+    pub x: f32,
+    pub y: f32,
+}
+
+/// Nine-slice borders in pixels, measured inward from each edge.
+#[derive(Debug, Deserialize, Clone, Copy, PartialEq, Eq)]
+pub struct AtlasNineSlice {
+    pub left: u32,
+    pub right: u32,
+    pub top: u32,
+    pub bottom: u32,
+}
+
+#[derive(Debug, Deserialize, Clone, Copy)]
+#[allow(dead_code)]
+pub struct AtlasTrim {
+    pub x: u32,
+    pub y: u32,
+    pub source_w: u32,
+    pub source_h: u32,
+}
+
+impl Default for AtlasPivot {
+    fn default() -> Self {
+        Self { x: 0.5, y: 0.5 }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct AtlasSpriteEntry {
+    /// Asset path of the atlas texture.
+    pub texture: Id,
+    /// In design units: pixels of the full-size texture, whichever file is
+    /// loaded. Upright, even when the atlas stores the sprite rotated.
+    pub size_px: (u32, u32),
+    /// `[u0, v0, u1, v1]` of the rect in the atlas.
+    pub uv: [f32; 4],
+    pub rotated: bool,
+    pub pivot: (f32, f32),
+    #[allow(dead_code)]
+    pub nine_slice: Option<AtlasNineSlice>,
+}
+
+impl AtlasSpriteEntry {
+    /// Texture coordinates for the quad's bottom-left, bottom-right,
+    /// top-right and top-left corners.
+    pub fn tex_coords(&self) -> [[f32; 2]; 4] {
+        quad_tex_coords(self.uv, self.rotated)
+    }
+}
+
+/// Corner texture coordinates (bottom-left, bottom-right, top-right,
+/// top-left) of the rect `[u0, v0, u1, v1]`, v0 at the top edge. A rotated
+/// rect holds the image turned 90° clockwise, so its top-left texel is the
+/// image's bottom-left.
+pub const fn quad_tex_coords(uv: [f32; 4], rotated: bool) -> [[f32; 2]; 4] {
+    let [u0, v0, u1, v1] = uv;
+    if rotated {
+        [[u0, v0], [u0, v1], [u1, v1], [u1, v0]]
+    } else {
+        [[u0, v1], [u1, v1], [u1, v0], [u0, v0]]
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct AtlasRegistry {
+    #[allow(dead_code)]
+    pub atlas_id: String,
+    /// Asset path of the atlas texture.
+    pub texture: Id,
+    /// Asset path of its half-resolution copy, if the atlas has one.
+    pub half_res_texture: Option<Id>,
+    pub premultiplied_alpha: bool,
+    pub sprite_entries: HashMap<Id, AtlasSpriteEntry>,
+    /// Sprite name -> sprite_id, for pairing with a parallel atlas.
+    pub sprite_names: HashMap<String, Id>,
+    pub parallel: Option<AtlasParallel>,
+    /// Hash of the metadata file it was built from (`atlas_source_hash`).
+    pub source_hash: u64,
+}
+
+impl AtlasRegistry {
+    #[allow(dead_code)]
+    pub fn resolve(&self, sprite_id: &str) -> Option<&AtlasSpriteEntry> {
+        self.sprite_entries.get(&Id::get(sprite_id)?)
+    }
+
+    /// Err unless the atlas stores alpha the way a pipeline drawing
+    /// `premultiplied` alpha expects. Parallel atlases hold data, not color,
+    /// and always pass.
+    pub fn check_alpha_convention(&self, premultiplied: bool) -> Result<(), String> {
+        if self.parallel.is_some() || self.premultiplied_alpha == premultiplied {
+            return Ok(());
+        }
+        Err(format!(
+            "Atlas '{}' has {} alpha but sprites are drawn with {} alpha",
+            self.atlas_id,
+            alpha_convention_name(self.premultiplied_alpha),
+            alpha_convention_name(premultiplied)
+        ))
+    }
+}
+
+fn alpha_convention_name(premultiplied: bool) -> &'static str {
+    if premultiplied {
+        "premultiplied"
+    } else {
+        "straight"
+    }
+}
+
+pub fn load_atlas_from_path(path: &Path) -> Result<AtlasRegistry, String> {
+    let raw = sme_core::assets::read_to_string(path)
+        .map_err(|e| format!("Failed to read atlas metadata {}: {e}", path.display()))?;
+    let source_hash = hash_source(&raw);
+    let mut value: serde_json::Value = serde_json::from_str(&raw)
+        .map_err(|e| format!("Failed to parse atlas metadata {}: {e}", path.display()))?;
+    ATLAS_SCHEMA.migrate_with_warning(&mut value, &path.display().to_string())?;
+    let atlas: AtlasFile = serde_json::from_value(value)
+        .map_err(|e| format!("Failed to parse atlas metadata {}: {e}", path.display()))?;
+    validate_atlas(&atlas)?;
+
+    let texture = Id::new(&atlas.texture.path);
+    let mut sprite_entries = HashMap::new();
+    let mut sprite_names = HashMap::new();
+    for sprite in &atlas.sprites {
+        let sprite_id = Id::new(&sprite.sprite_id);
+        if let Some(name) = &sprite.name {
+            sprite_names.insert(name.clone(), sprite_id);
+        }
+        let size_px = if sprite.rotated {
+            (sprite.rect_px.h, sprite.rect_px.w)
+        } else {
+            (sprite.rect_px.w, sprite.rect_px.h)
+        };
+        sprite_entries.insert(
+            sprite_id,
+            AtlasSpriteEntry {
+                texture,
+                size_px,
+                uv: [sprite.uv.u0, sprite.uv.v0, sprite.uv.u1, sprite.uv.v1],
+                rotated: sprite.rotated,
+                pivot: (sprite.pivot.x, sprite.pivot.y),
+                nine_slice: sprite.nine_slice,
+            },
+        );
+    }
+
+    Ok(AtlasRegistry {
+        atlas_id: atlas.atlas_id,
+        texture,
+        half_res_texture: atlas.texture.half_res.map(|half| Id::new(&half.path)),
+        premultiplied_alpha: atlas.texture.premultiplied_alpha,
+        sprite_entries,
+        sprite_names,
+        parallel: atlas.parallel,
+        source_hash,
+    })
+}
+
+/// Hash of the metadata at `path` as it is on disk now. Equal to a loaded
+/// registry's `source_hash` when reloading it would change nothing.
+pub fn atlas_source_hash(path: &Path) -> Result<u64, String> {
+    sme_core::assets::read_to_string(path)
+        .map(|raw| hash_source(&raw))
+        .map_err(|e| format!("Failed to read atlas metadata {}: {e}", path.display()))
+}
+
+fn hash_source(raw: &str) -> u64 {
+    let mut hasher = StateHasher::new();
+    hasher.write(raw.as_bytes());
+    hasher.finish()
+}
+
+fn validate_atlas(atlas: &AtlasFile) -> Result<(), String> {
+    if atlas.texture.width == 0 || atlas.texture.height == 0 {
+        return Err("Atlas validation failed: texture width/height must be > 0".to_string());
+    }
+    if let Some(half) = &atlas.texture.half_res {
+        if half.path.is_empty() {
+            return Err("Atlas validation failed: half_res path must not be empty".to_string());
+        }
+        if half.width.checked_mul(2) != Some(atlas.texture.width)
+            || half.height.checked_mul(2) != Some(atlas.texture.height)
+        {
+            return Err(format!(
+                "Atlas validation failed: half_res texture is {}x{}, expected half of {}x{}",
+                half.width, half.height, atlas.texture.width, atlas.texture.height
+            ));
+        }
+    }
+
+    let mut ids = std::collections::HashSet::new();
+    for sprite in &atlas.sprites {
+        if !ids.insert(sprite.sprite_id.clone()) {
+            return Err(format!(
+                "Atlas validation failed: duplicate sprite_id '{}'",
+                sprite.sprite_id
+            ));
+        }
+        if sprite.rect_px.w == 0 || sprite.rect_px.h == 0 {
+            return Err(format!(
+                "Atlas validation failed: sprite '{}' has zero-sized rect",
+                sprite.sprite_id
+            ));
+        }
+        let right = sprite
+            .rect_px
+            .x
+            .checked_add(sprite.rect_px.w)
+            .ok_or_else(|| {
+                format!(
+                    "Atlas validation failed: sprite '{}' rect overflows u32 range",
+                    sprite.sprite_id
+                )
+            })?;
+        let bottom = sprite
+            .rect_px
+            .y
+            .checked_add(sprite.rect_px.h)
+            .ok_or_else(|| {
+                format!(
+                    "Atlas validation failed: sprite '{}' rect overflows u32 range",
+                    sprite.sprite_id
+                )
+            })?;
+        if right > atlas.texture.width || bottom > atlas.texture.height {
+            return Err(format!(
+                "Atlas validation failed: sprite '{}' rect exceeds atlas bounds",
+                sprite.sprite_id
+            ));
+        }
+        if !(0.0..=1.0).contains(&sprite.uv.u0)
+            || !(0.0..=1.0).contains(&sprite.uv.v0)
+            || !(0.0..=1.0).contains(&sprite.uv.u1)
+            || !(0.0..=1.0).contains(&sprite.uv.v1)
+        {
+            return Err(format!(
+                "Atlas validation failed: sprite '{}' has UV outside [0, 1]",
+                sprite.sprite_id
+            ));
+        }
+        if sprite.uv.u0 >= sprite.uv.u1 || sprite.uv.v0 >= sprite.uv.v1 {
+            return Err(format!(
+                "Atlas validation failed: sprite '{}' has invalid UV range",
+                sprite.sprite_id
+            ));
+        }
+        if sprite.rotated && sprite.nine_slice.is_some() {
+            return Err(format!(
+                "Atlas validation failed: sprite '{}' is nine-sliced and cannot be rotated",
+                sprite.sprite_id
+            ));
+        }
+        // Turning a normal map would turn the normals it encodes too.
+        if sprite.rotated
+            && atlas
+                .parallel
+                .as_ref()
+                .is_some_and(|parallel| parallel.kind == SecondaryKind::Normal)
+        {
+            return Err(format!(
+                "Atlas validation failed: normal map sprite '{}' cannot be rotated",
+                sprite.sprite_id
+            ));
+        }
+        if let Some(nine_slice) = sprite.nine_slice {
+            if nine_slice.left.saturating_add(nine_slice.right) > sprite.rect_px.w
+                || nine_slice.top.saturating_add(nine_slice.bottom) > sprite.rect_px.h
+            {
+                return Err(format!(
+                    "Atlas validation failed: sprite '{}' nine_slice borders exceed its rect",
+                    sprite.sprite_id
+                ));
+            }
+        }
+    }
+
+    Ok(())
+}
+
+/// Registry that spans multiple atlases with a flat O(1) sprite lookup.
+///
+/// Each atlas is stored separately (keyed by its file path) so individual
+/// atlases can be hot-reloaded without rebuilding the entire index.
+/// The `sprite_index` provides a unified view across all loaded atlases.
+/// Parallel atlases stay out of it; their sprites are only reachable through
+/// `secondary_index`, which is rebuilt whenever an atlas is added or removed.
+#[derive(Debug, Clone)]
+pub struct MultiAtlasRegistry {
+    registries: HashMap<String, AtlasRegistry>,
+    sprite_index: HashMap<Id, AtlasSpriteEntry>,
+    /// Primary sprite_id -> its texels in the parallel atlas.
+    secondary_index: HashMap<Id, SecondarySprite>,
+}
+
+impl MultiAtlasRegistry {
+    pub fn new() -> Self {
+        Self {
+            registries: HashMap::new(),
+            sprite_index: HashMap::new(),
+            secondary_index: HashMap::new(),
+        }
+    }
+
+    /// Add an atlas keyed by its file path. Rejects duplicate sprite_ids across atlases,
+    /// a second parallel atlas for the same primary atlas, and a primary atlas
+    /// whose alpha convention differs from the ones already loaded.
+    pub fn add_atlas(&mut self, key: &str, registry: AtlasRegistry) -> Result<(), String> {
+        if let Some(premultiplied) = self.premultiplied_alpha() {
+            registry
+                .check_alpha_convention(premultiplied)
+                .map_err(|e| format!("{e} by the other atlases (adding '{key}')"))?;
+        }
+        if let Some(parallel) = &registry.parallel {
+            let taken = self.registries.iter().any(|(other_key, other)| {
+                other_key != key
+                    && other
+                        .parallel
+                        .as_ref()
+                        .is_some_and(|p| p.atlas_id == parallel.atlas_id)
+            });
+            if taken {
+                return Err(format!(
+                    "Atlas '{}' already has a parallel atlas (adding '{}')",
+                    parallel.atlas_id, key
+                ));
+            }
+            self.registries.insert(key.to_string(), registry);
+            self.link_secondaries();
+            return Ok(());
+        }
+        for sprite_id in registry.sprite_entries.keys() {
+            if self.sprite_index.contains_key(sprite_id) {
+                return Err(format!(
+                    "Duplicate sprite_id '{}' across atlases (adding '{}')",
+                    sprite_id, key
+                ));
+            }
+        }
+        for (sprite_id, entry) in &registry.sprite_entries {
+            self.sprite_index.insert(*sprite_id, entry.clone());
+        }
+        self.registries.insert(key.to_string(), registry);
+        self.link_secondaries();
+        Ok(())
+    }
+
+    /// Remove an atlas and all its sprite_ids from the flat index.
+    pub fn remove_atlas(&mut self, key: &str) {
+        if let Some(registry) = self.registries.remove(key) {
+            if registry.parallel.is_none() {
+                for sprite_id in registry.sprite_entries.keys() {
+                    self.sprite_index.remove(sprite_id);
+                }
+            }
+            self.link_secondaries();
+        }
+    }
+
+    /// Pair every primary sprite with the same-named sprite of its parallel
+    /// atlas, if both are loaded.
+    fn link_secondaries(&mut self) {
+        self.secondary_index.clear();
+        for secondary in self.registries.values() {
+            let Some(parallel) = &secondary.parallel else {
+                continue;
+            };
+            let primaries = self
+                .registries
+                .values()
+                .filter(|r| r.parallel.is_none() && r.atlas_id == parallel.atlas_id);
+            for primary in primaries {
+                for (name, sprite_id) in &primary.sprite_names {
+                    let Some(entry) = secondary
+                        .sprite_names
+                        .get(name)
+                        .and_then(|id| secondary.sprite_entries.get(id))
+                    else {
+                        continue;
+                    };
+                    self.secondary_index.insert(
+                        *sprite_id,
+                        SecondarySprite {
+                            texture: entry.texture,
+                            tex_coords: entry.tex_coords(),
+                            kind: parallel.kind,
+                        },
+                    );
+                }
+            }
+        }
+    }
+
+    /// Resolve a sprite_id across all loaded atlases.
+    pub fn resolve(&self, sprite_id: &str) -> Option<&AtlasSpriteEntry> {
+        self.resolve_id(Id::get(sprite_id)?)
+    }
+
+    /// Resolve an interned sprite_id (e.g. an animation frame) without
+    /// touching the string.
+    pub fn resolve_id(&self, sprite_id: Id) -> Option<&AtlasSpriteEntry> {
+        self.sprite_index.get(&sprite_id)
+    }
+
+    /// Secondary texels for a primary sprite_id, if its atlas has a parallel
+    /// atlas with a sprite of the same name.
+    pub fn resolve_secondary(&self, sprite_id: Id) -> Option<&SecondarySprite> {
+        self.secondary_index.get(&sprite_id)
+    }
+
+    /// Primary texture path -> the secondary texture bound alongside it.
+    pub fn secondary_textures(&self) -> HashMap<Id, (Id, SecondaryKind)> {
+        self.secondary_index
+            .iter()
+            .filter_map(|(sprite_id, secondary)| {
+                let primary = self.sprite_index.get(sprite_id)?;
+                Some((primary.texture, (secondary.texture, secondary.kind)))
+            })
+            .collect()
+    }
+
+    /// Return the set of unique texture paths across all loaded atlases.
+    pub fn texture_paths(&self) -> HashSet<Id> {
+        self.sprite_index.values().map(|e| e.texture).collect()
+    }
+
+    /// The file to load for atlas texture `texture` at `tier`: its half-res
+    /// copy on Tier 0 when the atlas declares one, otherwise `texture`
+    /// itself (also for textures no atlas owns).
+    pub fn texture_file(&self, texture: Id, tier: FidelityTier) -> Id {
+        if tier != FidelityTier::Tier0 {
+            return texture;
+        }
+        self.registries
+            .values()
+            .filter(|registry| registry.texture == texture)
+            .find_map(|registry| registry.half_res_texture)
+            .unwrap_or(texture)
+    }
+
+    /// The atlas loaded under `key`, if any.
+    pub fn get(&self, key: &str) -> Option<&AtlasRegistry> {
+        self.registries.get(key)
+    }
+
+    /// Key of the primary atlas that packs `sprite_id`, if one is loaded.
+    pub fn atlas_key_of(&self, sprite_id: Id) -> Option<&str> {
+        self.registries
+            .iter()
+            .find(|(_, registry)| {
+                registry.parallel.is_none() && registry.sprite_entries.contains_key(&sprite_id)
+            })
+            .map(|(key, _)| key.as_str())
+    }
+
+    pub fn atlas_count(&self) -> usize {
+        self.registries.len()
+    }
+
+    /// Whether the loaded primary atlases store premultiplied alpha, or
+    /// `None` while there are none.
+    pub fn premultiplied_alpha(&self) -> Option<bool> {
+        self.registries
+            .values()
+            .find(|registry| registry.parallel.is_none())
+            .map(|registry| registry.premultiplied_alpha)
+    }
+
+    /// Check if any atlases are loaded.
+    pub fn is_empty(&self) -> bool {
+        self.registries.is_empty()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::fs;
+    use std::time::{SystemTime, UNIX_EPOCH};
+
+    fn temp_file_path(name_hint: &str) -> std::path::PathBuf {
+        let nanos = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .expect("system time before unix epoch")
+            .as_nanos();
+        std::env::temp_dir().join(format!(
+            "sme_atlas_test_{}_{}_{}.json",
+            name_hint,
+            std::process::id(),
+            nanos
+        ))
+    }
+
+    #[test]
+    fn load_atlas_from_path_parses_valid_file() {
+        let path = temp_file_path("valid");
+        let json = r#"
+        {
+          "version": "0.1",
+          "atlas_id": "test",
+          "texture": { "path": "assets/generated/test.png", "width": 64, "height": 64 },
+          "sprites": [
+            {
+              "sprite_id": "id-1",
+              "source_path": "assets/textures/a.png",
+              "rect_px": { "x": 0, "y": 0, "w": 32, "h": 32 },
+              "uv": { "u0": 0.0, "v0": 0.0, "u1": 0.5, "v1": 0.5 }
+            }
+          ]
+        }
+        "#;
+        fs::write(&path, json).expect("failed to write temp atlas file");
+
+        let atlas = load_atlas_from_path(&path).expect("atlas should load");
+        assert_eq!(atlas.atlas_id, "test");
+        assert!(atlas.resolve("id-1").is_some());
+
+        assert_eq!(atlas_source_hash(&path), Ok(atlas.source_hash));
+        fs::write(&path, json.replace("\"w\": 32", "\"w\": 16")).expect("rewrite atlas");
+        assert_ne!(atlas_source_hash(&path), Ok(atlas.source_hash));
+
+        let _ = fs::remove_file(path);
+    }
+
+    #[test]
+    fn load_atlas_from_path_rejects_overflowing_rect() {
+        let path = temp_file_path("overflow_rect");
+        let json = r#"
+        {
+          "version": "0.1",
+          "atlas_id": "test",
+          "texture": { "path": "assets/generated/test.png", "width": 64, "height": 64 },
+          "sprites": [
+            {
+              "sprite_id": "id-overflow",
+              "source_path": "assets/textures/a.png",
+              "rect_px": { "x": 4294967295, "y": 0, "w": 8, "h": 8 },
+              "uv": { "u0": 0.0, "v0": 0.0, "u1": 0.5, "v1": 0.5 }
+            }
+          ]
+        }
+        "#;
+        fs::write(&path, json).expect("failed to write temp atlas file");
+
+        let err = load_atlas_from_path(&path).expect_err("overflow rect should fail");
+        assert!(err.contains("rect overflows u32 range"));
+
+        let _ = fs::remove_file(path);
+    }
+
+    #[test]
+    fn load_atlas_from_path_reads_nine_slice_and_checks_borders() {
+        let path = temp_file_path("nine_slice");
+        let sprite_json = |borders: &str| {
+            format!(
+                r#"{{
+              "version": "0.1",
+              "atlas_id": "test",
+              "texture": {{ "path": "assets/generated/test.png", "width": 64, "height": 64 }},
+              "sprites": [
+                {{
+                  "sprite_id": "panel",
+                  "source_path": "assets/textures/panel.png",
+                  "rect_px": {{ "x": 0, "y": 0, "w": 16, "h": 16 }},
+                  "uv": {{ "u0": 0.0, "v0": 0.0, "u1": 0.25, "v1": 0.25 }},
+                  "pivot": {{ "x": 0.5, "y": 0.0 }},
+                  "nine_slice": {borders},
+                  "trim": {{ "x": 2, "y": 0, "source_w": 20, "source_h": 16 }}
+                }}
+              ]
+            }}"#
+            )
+        };
+
+        fs::write(
+            &path,
+            sprite_json(r#"{ "left": 4, "right": 4, "top": 6, "bottom": 6 }"#),
+        )
+        .expect("failed to write temp atlas file");
+        let atlas = load_atlas_from_path(&path).expect("atlas should load");
+        let entry = atlas.resolve("panel").expect("panel");
+        assert_eq!(entry.pivot, (0.5, 0.0));
+        assert_eq!(
+            entry.nine_slice,
+            Some(AtlasNineSlice {
+                left: 4,
+                right: 4,
+                top: 6,
+                bottom: 6
+            })
+        );
+
+        fs::write(
+            &path,
+            sprite_json(r#"{ "left": 10, "right": 10, "top": 0, "bottom": 0 }"#),
+        )
+        .expect("failed to write temp atlas file");
+        let err = load_atlas_from_path(&path).expect_err("oversized borders should fail");
+        assert!(err.contains("nine_slice borders exceed"), "{err}");
+
+        let _ = fs::remove_file(path);
+    }
+
+    #[test]
+    fn half_res_texture_is_validated_and_picked_on_tier0() {
+        let path = temp_file_path("half_res");
+        let atlas_json = |half_res: &str| {
+            format!(
+                r#"{{
+              "version": "0.1",
+              "atlas_id": "test",
+              "texture": {{
+                "path": "assets/generated/test.png", "width": 64, "height": 32,
+                "half_res": {half_res}
+              }},
+              "sprites": [
+                {{
+                  "sprite_id": "id-1",
+                  "source_path": "assets/textures/a.png",
+                  "rect_px": {{ "x": 0, "y": 0, "w": 32, "h": 32 }},
+                  "uv": {{ "u0": 0.0, "v0": 0.0, "u1": 0.5, "v1": 1.0 }}
+                }}
+              ]
+            }}"#
+            )
+        };
+
+        fs::write(
+            &path,
+            atlas_json(r#"{ "path": "assets/generated/test@1x.png", "width": 32, "height": 16 }"#),
+        )
+        .expect("failed to write temp atlas file");
+        let atlas = load_atlas_from_path(&path).expect("atlas should load");
+        let mut multi = MultiAtlasRegistry::new();
+        multi.add_atlas("test.json", atlas).expect("add");
+        let texture = Id::new("assets/generated/test.png");
+        assert_eq!(
+            multi.texture_file(texture, FidelityTier::Tier0),
+            "assets/generated/test@1x.png"
+        );
+        assert_eq!(multi.texture_file(texture, FidelityTier::Tier2), texture);
+        // Layout stays in full-size pixels whichever file is loaded.
+        assert_eq!(multi.resolve("id-1").expect("sprite").size_px, (32, 32));
+
+        fs::write(
+            &path,
+            atlas_json(r#"{ "path": "assets/generated/test@1x.png", "width": 32, "height": 32 }"#),
+        )
+        .expect("failed to write temp atlas file");
+        let err = load_atlas_from_path(&path).expect_err("wrong half-res size");
+        assert!(err.contains("expected half of 64x32"), "{err}");
+
+        let _ = fs::remove_file(path);
+    }
+
+    #[test]
+    fn rotated_sprite_keeps_its_upright_size_and_turns_its_uvs() {
+        let path = temp_file_path("rotated");
+        fs::write(
+            &path,
+            r#"{
+              "version": "0.1",
+              "atlas_id": "test",
+              "texture": { "path": "assets/generated/test.png", "width": 64, "height": 64 },
+              "sprites": [
+                {
+                  "sprite_id": "id-1",
+                  "source_path": "assets/textures/tall.png",
+                  "rect_px": { "x": 0, "y": 0, "w": 32, "h": 16 },
+                  "uv": { "u0": 0.0, "v0": 0.0, "u1": 0.5, "v1": 0.25 },
+                  "rotated": true
+                }
+              ]
+            }"#,
+        )
+        .expect("failed to write temp atlas file");
+        let atlas = load_atlas_from_path(&path).expect("atlas should load");
+        let entry = atlas.resolve("id-1").expect("sprite");
+        assert_eq!(entry.size_px, (16, 32));
+        // The image's bottom-left corner is stored at the rect's top-left.
+        assert_eq!(
+            entry.tex_coords(),
+            [[0.0, 0.0], [0.0, 0.25], [0.5, 0.25], [0.5, 0.0]]
+        );
+        let _ = fs::remove_file(path);
+    }
+
+    fn make_test_registry(atlas_id: &str, sprites: &[(&str, &str)]) -> AtlasRegistry {
+        let mut sprite_entries = HashMap::new();
+        for &(id, tex) in sprites {
+            sprite_entries.insert(
+                Id::new(id),
+                AtlasSpriteEntry {
+                    texture: Id::new(tex),
+                    size_px: (32, 32),
+                    uv: [0.0, 0.0, 1.0, 1.0],
+                    rotated: false,
+                    pivot: (0.5, 0.5),
+                    nine_slice: None,
+                },
+            );
+        }
+        AtlasRegistry {
+            atlas_id: atlas_id.to_string(),
+            texture: Id::new(sprites.first().map_or("", |&(_, tex)| tex)),
+            half_res_texture: None,
+            premultiplied_alpha: false,
+            sprite_names: sprites
+                .iter()
+                .map(|&(id, _)| (id.to_string(), Id::new(id)))
+                .collect(),
+            sprite_entries,
+            parallel: None,
+            source_hash: 0,
+        }
+    }
+
+    #[test]
+    fn multi_atlas_single_atlas_resolve() {
+        let mut multi = MultiAtlasRegistry::new();
+        let reg = make_test_registry(
+            "chars",
+            &[("sprite-a", "chars.png"), ("sprite-b", "chars.png")],
+        );
+        multi
+            .add_atlas("chars.json", reg)
+            .expect("add should succeed");
+
+        assert_eq!(multi.atlas_count(), 1);
+        assert!(multi.resolve("sprite-a").is_some());
+        assert!(multi.resolve("sprite-b").is_some());
+        assert!(multi.resolve("nonexistent").is_none());
+    }
+
+    #[test]
+    fn multi_atlas_cross_atlas_resolve() {
+        let mut multi = MultiAtlasRegistry::new();
+        let reg1 = make_test_registry("chars", &[("sprite-a", "chars.png")]);
+        let reg2 = make_test_registry("env", &[("sprite-b", "env.png")]);
+        multi.add_atlas("chars.json", reg1).expect("add chars");
+        multi.add_atlas("env.json", reg2).expect("add env");
+
+        assert_eq!(multi.atlas_count(), 2);
+        assert!(multi.resolve("sprite-a").is_some());
+        assert!(multi.resolve("sprite-b").is_some());
+
+        let paths = multi.texture_paths();
+        assert!(paths.contains(&Id::new("chars.png")));
+        assert!(paths.contains(&Id::new("env.png")));
+    }
+
+    #[test]
+    fn multi_atlas_rejects_duplicate_sprite_ids() {
+        let mut multi = MultiAtlasRegistry::new();
+        let reg1 = make_test_registry("chars", &[("sprite-a", "chars.png")]);
+        let reg2 = make_test_registry("env", &[("sprite-a", "env.png")]);
+        multi.add_atlas("chars.json", reg1).expect("add chars");
+
+        let err = multi
+            .add_atlas("env.json", reg2)
+            .expect_err("duplicate should fail");
+        assert!(err.contains("Duplicate sprite_id"));
+    }
+
+    #[test]
+    fn multi_atlas_remove_and_readd() {
+        let mut multi = MultiAtlasRegistry::new();
+        let reg = make_test_registry("chars", &[("sprite-a", "chars.png")]);
+        multi.add_atlas("chars.json", reg).expect("add");
+
+        assert!(multi.resolve("sprite-a").is_some());
+        multi.remove_atlas("chars.json");
+        assert!(multi.resolve("sprite-a").is_none());
+        assert_eq!(multi.atlas_count(), 0);
+
+        // Re-add should work
+        let reg2 = make_test_registry("chars_v2", &[("sprite-a", "chars_v2.png")]);
+        multi.add_atlas("chars.json", reg2).expect("re-add");
+        assert!(multi.resolve("sprite-a").is_some());
+    }
+
+    #[test]
+    fn multi_atlas_texture_paths_union() {
+        let mut multi = MultiAtlasRegistry::new();
+        let reg1 = make_test_registry("a", &[("s1", "tex1.png"), ("s2", "tex1.png")]);
+        let reg2 = make_test_registry("b", &[("s3", "tex2.png")]);
+        multi.add_atlas("a.json", reg1).unwrap();
+        multi.add_atlas("b.json", reg2).unwrap();
+
+        let paths = multi.texture_paths();
+        assert_eq!(paths.len(), 2);
+        assert!(paths.contains(&Id::new("tex1.png")));
+        assert!(paths.contains(&Id::new("tex2.png")));
+    }
+
+    #[test]
+    fn parallel_atlas_pairs_sprites_by_name() {
+        let mut normals = make_test_registry("chars_n", &[("normal-a", "chars_n.png")]);
+        // Same source name as the primary sprite, different packer-assigned id.
+        normals.sprite_names = HashMap::from([("sprite-a".to_string(), Id::new("normal-a"))]);
+        normals.parallel = Some(AtlasParallel {
+            atlas_id: "chars".to_string(),
+            kind: SecondaryKind::Normal,
+        });
+
+        // The parallel atlas may load before its primary.
+        let mut multi = MultiAtlasRegistry::new();
+        multi.add_atlas("chars_n.json", normals.clone()).unwrap();
+        let chars = make_test_registry(
+            "chars",
+            &[("sprite-a", "chars.png"), ("sprite-b", "chars.png")],
+        );
+        multi.add_atlas("chars.json", chars).unwrap();
+
+        let secondary = multi
+            .resolve_secondary(Id::new("sprite-a"))
+            .expect("paired");
+        assert_eq!(secondary.texture, "chars_n.png");
+        assert_eq!(secondary.kind, SecondaryKind::Normal);
+        assert!(multi.resolve_secondary(Id::new("sprite-b")).is_none());
+        assert!(multi.resolve("normal-a").is_none());
+        assert!(!multi.texture_paths().contains(&Id::new("chars_n.png")));
+        assert_eq!(
+            multi.secondary_textures().get(&Id::new("chars.png")),
+            Some(&(Id::new("chars_n.png"), SecondaryKind::Normal))
+        );
+
+        let err = multi
+            .add_atlas("chars_e.json", normals)
+            .expect_err("second parallel atlas");
+        assert!(err.contains("already has a parallel atlas"), "{err}");
+
+        multi.remove_atlas("chars_n.json");
+        assert!(multi.resolve_secondary(Id::new("sprite-a")).is_none());
+        assert!(multi.resolve("sprite-a").is_some());
+    }
+
+    #[test]
+    fn primary_atlases_must_agree_on_premultiplied_alpha() {
+        let mut multi = MultiAtlasRegistry::new();
+        assert_eq!(multi.premultiplied_alpha(), None);
+        let mut chars = make_test_registry("chars", &[("sprite-a", "chars.png")]);
+        chars.premultiplied_alpha = true;
+        multi.add_atlas("chars.json", chars).unwrap();
+        assert_eq!(multi.premultiplied_alpha(), Some(true));
+
+        let props = make_test_registry("props", &[("sprite-b", "props.png")]);
+        let err = multi.add_atlas("props.json", props).unwrap_err();
+        assert!(err.contains("has straight alpha"), "{err}");
+
+        // Normal maps are data, whichever way the color atlas is stored.
+        let mut normals = make_test_registry("chars_n", &[("normal-a", "chars_n.png")]);
+        normals.parallel = Some(AtlasParallel {
+            atlas_id: "chars".to_string(),
+            kind: SecondaryKind::Normal,
+        });
+        multi.add_atlas("chars_n.json", normals).unwrap();
+    }
+}
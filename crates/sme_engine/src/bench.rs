@@ -0,0 +1,386 @@
+//! Fixtures for the criterion benchmarks in `benches/`.
+//!
+//! Most hot paths are crate-private, so each fixture owns a synthetic setup
+//! for one of them and exposes the call to time: sprite mesh building,
+//! collision sweeps, animation ticking and atlas lookups. They run the same
+//! code as a frame or a fixed step, without a window, GPU, Lua or asset
+//! files. Not part of the engine's API.
+
+use std::collections::HashMap;
+
+use sme_core::animation::{AnimationClip, AnimationFile, AnimationFrame, AnimationState};
+use sme_core::interned::{Id, InternedMap};
+use sme_core::tier::FidelityTier;
+
+use crate::animation::AnimationRegistry;
+use crate::atlas::{AtlasRegistry, AtlasSpriteEntry, MultiAtlasRegistry};
+use crate::collision::{Aabb, CollisionFile, CollisionGrid, GridCell, GridOrigin};
+use crate::controller::CharacterController;
+use crate::determinism::StateMap;
+use crate::scene::{LayerFilter, LayerSpace, SceneLayer, SceneSprite, SortMode};
+use crate::tween::Tweens;
+use crate::world::World;
+use crate::{SpriteMeshSource, FIXED_DT_US};
+
+/// Sprites in each synthetic atlas.
+const SPRITES_PER_ATLAS: usize = 64;
+/// Atlases (and so textures) the synthetic sprites are spread over.
+const ATLAS_COUNT: usize = 4;
+/// Every this many sprites, one plays an animation.
+const ANIMATED_EVERY: usize = 4;
+const ANIMATION_ID: &str = "bench_anim";
+const CLIP_NAME: &str = "cycle";
+
+fn sprite_id(index: usize) -> String {
+    format!("bench_sprite_{}", index % (SPRITES_PER_ATLAS * ATLAS_COUNT))
+}
+
+fn atlas_texture(atlas: usize) -> Id {
+    Id::new(&format!("bench/atlas_{atlas}.png"))
+}
+
+/// `ATLAS_COUNT` atlases of `SPRITES_PER_ATLAS` 32x32 sprites each.
+fn synthetic_atlases() -> MultiAtlasRegistry {
+    let mut multi_atlas = MultiAtlasRegistry::new();
+    for atlas in 0..ATLAS_COUNT {
+        let mut sprite_entries = HashMap::new();
+        let mut sprite_names = HashMap::new();
+        for slot in 0..SPRITES_PER_ATLAS {
+            let id = Id::new(&sprite_id(atlas * SPRITES_PER_ATLAS + slot));
+            let u = (slot % 8) as f32 / 8.0;
+            let v = (slot / 8) as f32 / 8.0;
+            sprite_entries.insert(
+                id,
+                AtlasSpriteEntry {
+                    texture: atlas_texture(atlas),
+                    size_px: (32, 32),
+                    uv: [u, v, u + 0.125, v + 0.125],
+                    rotated: false,
+                    pivot: (0.5, 0.5),
+                    nine_slice: None,
+                },
+            );
+            sprite_names.insert(format!("sprite_{slot}"), id);
+        }
+        let registry = AtlasRegistry {
+            atlas_id: format!("bench_atlas_{atlas}"),
+            texture: atlas_texture(atlas),
+            half_res_texture: None,
+            premultiplied_alpha: false,
+            sprite_entries,
+            sprite_names,
+            parallel: None,
+            source_hash: 0,
+        };
+        multi_atlas
+            .add_atlas(&format!("bench/atlas_{atlas}.json"), registry)
+            .expect("synthetic atlases do not overlap");
+    }
+    multi_atlas
+}
+
+/// One looping four-frame clip over the first atlas.
+fn synthetic_animations() -> AnimationRegistry {
+    let frames = (0..4)
+        .map(|frame| AnimationFrame {
+            sprite_id: Id::new(&sprite_id(frame)),
+            duration_us: 100_000,
+        })
+        .collect();
+    let mut registry = AnimationRegistry::new();
+    registry.add_file(AnimationFile {
+        version: "0.1".to_string(),
+        animation_id: ANIMATION_ID.to_string(),
+        animations: HashMap::from([(
+            CLIP_NAME.to_string(),
+            AnimationClip {
+                frames,
+                looping: true,
+            },
+        )]),
+    });
+    registry
+}
+
+fn player() -> CharacterController {
+    CharacterController::new(Aabb {
+        center_x: 0.0,
+        center_y: 0.0,
+        half_w: 12.0,
+        half_h: 16.0,
+    })
+}
+
+/// `count` animation states named `sprite_<n>`.
+fn animation_states(count: usize) -> impl Iterator<Item = (String, AnimationState)> {
+    (0..count).map(|i| {
+        let mut state = AnimationState::new(ANIMATION_ID, CLIP_NAME);
+        // Stagger the states so they change frame on different steps.
+        state.elapsed_us = (i as u64 * 7_919) % 100_000;
+        (format!("sprite_{i}"), state)
+    })
+}
+
+/// A y-sorted world layer of atlas sprites, some of them animated.
+pub struct MeshFixture {
+    world: World,
+    animation_registry: AnimationRegistry,
+    multi_atlas: MultiAtlasRegistry,
+    texture_sizes: InternedMap<(u32, u32)>,
+    trails: StateMap<String, crate::trail::Trail>,
+    tweens: Tweens,
+    effects: HashMap<String, sme_render::SpriteEffect>,
+    layer: SceneLayer,
+}
+
+impl MeshFixture {
+    pub fn new(sprites: usize) -> Self {
+        let columns = (sprites as f32).sqrt().ceil().max(1.0) as usize;
+        let sprites: Vec<SceneSprite> = (0..sprites)
+            .map(|i| SceneSprite {
+                id: format!("sprite_{i}"),
+                asset: None,
+                sprite_id: Some(sprite_id(i)),
+                animation: (i % ANIMATED_EVERY == 0).then(|| CLIP_NAME.to_string()),
+                animation_source: (i % ANIMATED_EVERY == 0).then(|| ANIMATION_ID.to_string()),
+                x: (i % columns) as f32 * 24.0,
+                y: (i / columns) as f32 * 24.0,
+                z: 0.0,
+                rotation_deg: if i % 3 == 0 { 15.0 } else { 0.0 },
+                scale_x: 1.0,
+                scale_y: 1.0,
+                trail: None,
+                effect: None,
+                pickup: None,
+                tweens: Vec::new(),
+                controller: None,
+                pivot: None,
+            })
+            .collect();
+        let mut world = World::new(player());
+        world.reset_animations(animation_states(sprites.len()).step_by(ANIMATED_EVERY));
+        let mut texture_sizes = InternedMap::new();
+        for atlas in 0..ATLAS_COUNT {
+            texture_sizes.insert(atlas_texture(atlas), (256, 256));
+        }
+        Self {
+            world,
+            animation_registry: synthetic_animations(),
+            multi_atlas: synthetic_atlases(),
+            texture_sizes,
+            trails: StateMap::default(),
+            tweens: Tweens::default(),
+            effects: HashMap::new(),
+            layer: SceneLayer {
+                id: "bench".to_string(),
+                parallax: 1.0,
+                sort_mode: SortMode::Y,
+                occlusion: false,
+                visible: true,
+                opacity: 1.0,
+                space: LayerSpace::World,
+                material: None,
+                filter: LayerFilter::Nearest,
+                group: None,
+                sprites,
+            },
+        }
+    }
+
+    /// Build the layer's quads the way a frame does, including the parallel
+    /// split for big layers. Returns the vertex count.
+    pub fn build(&self) -> usize {
+        let mut vertices = Vec::new();
+        let mut indices = Vec::new();
+        let mut draw_calls = Vec::new();
+        self.source().append_layers(
+            &[(&self.layer, (0.0, 0.0))],
+            [1.0; 4],
+            &mut vertices,
+            &mut indices,
+            &mut draw_calls,
+        );
+        vertices.len()
+    }
+
+    fn source(&self) -> SpriteMeshSource<'_> {
+        SpriteMeshSource {
+            world: &self.world,
+            animation_registry: &self.animation_registry,
+            multi_atlas: &self.multi_atlas,
+            textures: &self.texture_sizes,
+            trails: &self.trails,
+            tweens: &self.tweens,
+            effects: &self.effects,
+            tier: FidelityTier::Tier2,
+        }
+    }
+}
+
+/// Bodies scattered over a walled grid with a floor and platforms.
+pub struct CollisionFixture {
+    grid: CollisionGrid,
+    bodies: Vec<Aabb>,
+}
+
+impl CollisionFixture {
+    const CELL: i32 = 16;
+    const WIDTH: i32 = 256;
+    const HEIGHT: i32 = 64;
+
+    pub fn new(bodies: usize) -> Self {
+        let mut solids = Vec::new();
+        for x in 0..Self::WIDTH {
+            solids.push(GridCell { x, y: 0 });
+            // A platform row every 8 cells, with gaps to fall through.
+            for y in (8..Self::HEIGHT).step_by(8) {
+                if (x / 4 + y / 8) % 3 != 0 {
+                    solids.push(GridCell { x, y });
+                }
+            }
+        }
+        for y in 0..Self::HEIGHT {
+            solids.push(GridCell { x: 0, y });
+            solids.push(GridCell {
+                x: Self::WIDTH - 1,
+                y,
+            });
+        }
+        let grid = CollisionGrid::from_file(CollisionFile {
+            version: "0.1".to_string(),
+            collision_id: "bench".to_string(),
+            cell_size: Self::CELL,
+            origin: GridOrigin::default(),
+            width: Self::WIDTH,
+            height: Self::HEIGHT,
+            solids,
+            fluids: Vec::new(),
+            surfaces: Vec::new(),
+            time_volumes: Vec::new(),
+        });
+        let cell = Self::CELL as f32;
+        let bodies = (0..bodies)
+            .map(|i| Aabb {
+                center_x: cell * (2.5 + (i * 37 % (Self::WIDTH as usize - 5)) as f32),
+                center_y: cell * (1.5 + (i * 11 % (Self::HEIGHT as usize - 3)) as f32),
+                half_w: cell * 0.35,
+                half_h: cell * 0.45,
+            })
+            .collect();
+        Self { grid, bodies }
+    }
+
+    /// Move every body one step diagonally into the geometry. Returns how
+    /// many hit something.
+    pub fn sweep(&self) -> usize {
+        let cell = Self::CELL as f32;
+        self.bodies
+            .iter()
+            .enumerate()
+            .filter(|(i, aabb)| {
+                let dx = if i % 2 == 0 { cell * 0.6 } else { -cell * 0.6 };
+                let result = self.grid.move_and_collide_detailed(**aabb, dx, -cell * 0.8);
+                result.collided_y || result.blocked_left || result.blocked_right
+            })
+            .count()
+    }
+}
+
+/// Animation states ticked through the world like a fixed step does.
+pub struct AnimationFixture {
+    world: World,
+    registry: AnimationRegistry,
+}
+
+impl AnimationFixture {
+    pub fn new(states: usize) -> Self {
+        let mut world = World::new(player());
+        world.reset_animations(animation_states(states));
+        Self {
+            world,
+            registry: synthetic_animations(),
+        }
+    }
+
+    /// Advance every state by one fixed step. Returns how many finished.
+    pub fn tick(&mut self) -> usize {
+        self.world
+            .tick_animations(&self.registry, FIXED_DT_US)
+            .len()
+    }
+}
+
+/// Sprite ids resolved against several loaded atlases.
+pub struct AtlasFixture {
+    multi_atlas: MultiAtlasRegistry,
+    ids: Vec<Id>,
+    names: Vec<String>,
+}
+
+impl AtlasFixture {
+    pub fn new(lookups: usize) -> Self {
+        let names: Vec<String> = (0..lookups).map(|i| sprite_id(i * 7)).collect();
+        Self {
+            multi_atlas: synthetic_atlases(),
+            ids: names.iter().map(|name| Id::new(name)).collect(),
+            names,
+        }
+    }
+
+    /// Resolve every id, as mesh building does. Returns the hit count.
+    pub fn resolve_ids(&self) -> usize {
+        self.ids
+            .iter()
+            .filter(|&&id| self.multi_atlas.resolve_id(id).is_some())
+            .count()
+    }
+
+    /// Resolve every id by name, as scripts and projectiles do.
+    pub fn resolve_names(&self) -> usize {
+        self.names
+            .iter()
+            .filter(|name| self.multi_atlas.resolve(name).is_some())
+            .count()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn fixtures_exercise_their_hot_paths() {
+        // Four vertices per sprite: every synthetic sprite resolves.
+        assert_eq!(MeshFixture::new(100).build(), 400);
+        let collisions = CollisionFixture::new(200).sweep();
+        assert!(collisions > 0 && collisions < 200);
+        // Looping clips never finish.
+        assert_eq!(AnimationFixture::new(50).tick(), 0);
+        let atlas = AtlasFixture::new(300);
+        assert_eq!((atlas.resolve_ids(), atlas.resolve_names()), (300, 300));
+    }
+
+    #[test]
+    fn sprites_outside_the_view_are_culled_and_counted() {
+        // Big enough to build in parallel runs.
+        let fixture = MeshFixture::new(10_000);
+        let mut mesh = (Vec::new(), Vec::new(), Vec::new());
+        let view = (glam::Vec2::splat(-10.0), glam::Vec2::splat(200.0));
+        let counts = fixture.source().append_layers_in_view(
+            &[
+                (&fixture.layer, (0.0, 0.0)),
+                (&fixture.layer, (5000.0, 0.0)),
+            ],
+            Some(view),
+            [1.0; 4],
+            &mut mesh.0,
+            &mut mesh.1,
+            &mut mesh.2,
+        );
+        let [near, far] = [counts[0], counts[1]];
+        assert!(near.drawn > 0 && near.drawn < 200, "{near:?}");
+        assert_eq!(near.drawn + near.culled, 10_000);
+        assert_eq!((far.drawn, far.culled), (0, 10_000));
+        assert_eq!(mesh.0.len(), near.drawn * 4);
+    }
+}
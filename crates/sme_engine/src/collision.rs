@@ -0,0 +1,1317 @@
+//! Collision underlay: a grid-based collision representation separate from visuals.
+//!
+//! The engine's "sprite-scene-first" model means art is authored as layered
+//! illustrations, but gameplay truth lives here in a simplified grid. This
+//! keeps collision logic fast and independent of visual complexity.
+//!
+//! Grid-based collision is chosen over polygon-based for two reasons:
+//!  1. **Performance** -- HashSet lookup per cell is O(1), no broad-phase needed
+//!  2. **Simplicity** -- 2D platformer levels map naturally to a tile grid
+//!
+//! The core algorithm is **axis-separable move-and-slide**: resolve X movement
+//! first against the grid, then resolve Y using the already-corrected X position.
+//! This prevents diagonal tunneling and produces the "slide along walls" behavior
+//! players expect from platformers. Each axis only checks where the body
+//! ends up, so moves longer than a cell go through `sweep_and_collide`,
+//! which splits them into half-cell substeps.
+//!
+//! Besides solids, a collision file can declare `fluids`: rectangles of cells
+//! (water, mud) that bodies pass through but move differently in. The
+//! controller asks `CollisionGrid::fluid_at` for the volume around a body's
+//! center and scales its gravity, fall speed and acceleration by it.
+//!
+//! Solid cells can also carry a surface material (`surfaces`): ice is
+//! slippery and a conveyor carries whatever stands on it. The controller asks
+//! `CollisionGrid::surface_under` what a grounded body stands on.
+//!
+//! `time_volumes` are rectangles of slow motion: a body whose center is inside
+//! one simulates with the fixed step times the volume's `scale`
+//! (`CollisionGrid::time_scale_at`), on top of the global time scale, while
+//! rendering and everything else keep running at full speed.
+//!
+//! `cell_size` and `origin` are pixels, unless the grid is loaded with the
+//! `units` of a scene authored in other units (`load_collision_in_units`).
+//! In y-down units, `origin` is the grid's top-left corner, row 0 is the top
+//! row, and volumes name their top-left cell.
+
+use serde::{Deserialize, Serialize};
+use sme_core::migrate::SchemaMigrations;
+
+use crate::units::SceneUnits;
+use std::collections::{HashMap, HashSet};
+use std::path::Path;
+
+/// Collision schema versions and the upgrade path to the current one.
+pub const COLLISION_SCHEMA: SchemaMigrations = SchemaMigrations {
+    kind: "Collision",
+    current: "0.1",
+    steps: &[],
+};
+
+#[derive(Debug, Deserialize, Clone)]
+pub struct CollisionFile {
+    pub version: String,
+    pub collision_id: String,
+    pub cell_size: i32,
+    #[serde(default)]
+    pub origin: GridOrigin,
+    pub width: i32,
+    pub height: i32,
+    pub solids: Vec<GridCell>,
+    #[serde(default)]
+    pub fluids: Vec<FluidVolume>,
+    /// Materials of solid cells; cells not listed are `normal`.
+    #[serde(default)]
+    pub surfaces: Vec<SurfaceCell>,
+    #[serde(default)]
+    pub time_volumes: Vec<TimeVolume>,
+}
+
+#[derive(Debug, Deserialize, Clone, Copy, Default)]
+pub struct GridOrigin {
+    pub x: i32,
+    pub y: i32,
+}
+
+#[derive(Debug, Deserialize, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct GridCell {
+    pub x: i32,
+    pub y: i32,
+}
+
+/// What a solid cell's top is made of, for bodies standing on it.
+#[derive(Debug, Deserialize, Clone, Copy, PartialEq, Default)]
+#[serde(tag = "material", rename_all = "lowercase")]
+pub enum SurfaceMaterial {
+    #[default]
+    Normal,
+    /// Ground friction and acceleration are multiplied by `friction`.
+    Ice {
+        #[serde(default = "default_ice_friction")]
+        friction: f32,
+    },
+    /// Moves standing bodies by `speed` units per second; positive is right.
+    Conveyor { speed: f32 },
+}
+
+fn default_ice_friction() -> f32 {
+    0.1
+}
+
+/// `{ "x": 4, "y": 0, "material": "ice" }` or
+/// `{ "x": 5, "y": 0, "material": "conveyor", "speed": -60 }`.
+#[derive(Debug, Deserialize, Clone, Copy, PartialEq)]
+pub struct SurfaceCell {
+    pub x: i32,
+    pub y: i32,
+    #[serde(flatten)]
+    pub material: SurfaceMaterial,
+}
+
+/// A rectangle of cells that bodies swim through instead of falling:
+/// `{ "id": "pool", "x": 4, "y": 1, "width": 6, "height": 3 }`.
+#[derive(Debug, Deserialize, Clone, PartialEq)]
+pub struct FluidVolume {
+    /// Named in the `fluid_enter` / `fluid_exit` events.
+    pub id: String,
+    /// Bottom-left cell.
+    pub x: i32,
+    pub y: i32,
+    /// Size in cells.
+    pub width: i32,
+    pub height: i32,
+    /// Multiplies the controller's `gravity`.
+    #[serde(default = "default_gravity_scale")]
+    pub gravity_scale: f32,
+    /// Multiplies the controller's `max_fall_speed`; must be > 0.
+    #[serde(default = "default_fall_speed_scale")]
+    pub fall_speed_scale: f32,
+    /// Multiplies the controller's ground and air acceleration.
+    #[serde(default = "default_accel_scale")]
+    pub accel_scale: f32,
+    /// Upward speed a swim stroke sets; the jump action swims while inside.
+    #[serde(default = "default_swim_speed")]
+    pub swim_speed: f32,
+    /// Splash particles when a body crosses the surface; 0 for none.
+    #[serde(default = "default_splash")]
+    pub splash: u32,
+    /// Color of the splash particles.
+    #[serde(default = "default_splash_color")]
+    pub splash_color: [f32; 4],
+}
+
+fn default_gravity_scale() -> f32 {
+    0.3
+}
+
+fn default_fall_speed_scale() -> f32 {
+    0.25
+}
+
+fn default_accel_scale() -> f32 {
+    0.5
+}
+
+fn default_swim_speed() -> f32 {
+    260.0
+}
+
+fn default_splash() -> u32 {
+    12
+}
+
+fn default_splash_color() -> [f32; 4] {
+    [0.7, 0.85, 1.0, 0.8]
+}
+
+impl FluidVolume {
+    fn contains(&self, cell: GridCell) -> bool {
+        (self.x..self.x + self.width).contains(&cell.x)
+            && (self.y..self.y + self.height).contains(&cell.y)
+    }
+}
+
+/// A rectangle of cells where bodies move in slow motion:
+/// `{ "id": "vault", "x": 4, "y": 1, "width": 3, "height": 2, "scale": 0.3 }`.
+#[derive(Debug, Deserialize, Clone, PartialEq)]
+pub struct TimeVolume {
+    pub id: String,
+    /// Bottom-left cell.
+    pub x: i32,
+    pub y: i32,
+    /// Size in cells.
+    pub width: i32,
+    pub height: i32,
+    /// Multiplies the step of bodies inside, in `(0, 1]`.
+    pub scale: f32,
+}
+
+impl TimeVolume {
+    fn contains(&self, cell: GridCell) -> bool {
+        (self.x..self.x + self.width).contains(&cell.x)
+            && (self.y..self.y + self.height).contains(&cell.y)
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct Aabb {
+    pub center_x: f32,
+    pub center_y: f32,
+    pub half_w: f32,
+    pub half_h: f32,
+}
+
+impl Aabb {
+    /// Do the boxes share any area? Touching edges do not count.
+    pub fn overlaps(&self, other: Aabb) -> bool {
+        (self.center_x - other.center_x).abs() < self.half_w + other.half_w
+            && (self.center_y - other.center_y).abs() < self.half_h + other.half_h
+    }
+}
+
+/// First solid cell a `CollisionGrid::raycast` segment enters.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct RayHit {
+    /// Fraction of the segment travelled before the hit, `0.0..=1.0`.
+    pub t: f32,
+    pub x: f32,
+    pub y: f32,
+    pub cell: GridCell,
+    /// Outward normal of the face that was hit; zero if the segment starts
+    /// inside a solid cell.
+    pub normal: (f32, f32),
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct CollisionMoveResult {
+    pub aabb: Aabb,
+    pub collided_y: bool,
+    pub blocked_left: bool,
+    pub blocked_right: bool,
+    pub blocked_down: bool,
+    pub blocked_up: bool,
+}
+
+#[derive(Debug, Clone)]
+pub struct CollisionGrid {
+    pub version: String,
+    pub collision_id: String,
+    pub cell_size: i32,
+    pub origin: GridOrigin,
+    pub width: i32,
+    pub height: i32,
+    solids: HashSet<GridCell>,
+    fluids: Vec<FluidVolume>,
+    surfaces: HashMap<GridCell, SurfaceMaterial>,
+    time_volumes: Vec<TimeVolume>,
+}
+
+impl CollisionGrid {
+    pub fn from_file(file: CollisionFile) -> Self {
+        let solids = file.solids.into_iter().collect();
+        Self {
+            version: file.version,
+            collision_id: file.collision_id,
+            cell_size: file.cell_size,
+            origin: file.origin,
+            width: file.width,
+            height: file.height,
+            solids,
+            fluids: file.fluids,
+            surfaces: file
+                .surfaces
+                .into_iter()
+                .map(|surface| {
+                    let cell = GridCell {
+                        x: surface.x,
+                        y: surface.y,
+                    };
+                    (cell, surface.material)
+                })
+                .collect(),
+            time_volumes: file.time_volumes,
+        }
+    }
+
+    pub fn is_solid(&self, x: i32, y: i32) -> bool {
+        if x < 0 || x >= self.width || y < 0 || y >= self.height {
+            return false;
+        }
+        self.solids.contains(&GridCell { x, y })
+    }
+
+    pub fn solids_iter(&self) -> impl Iterator<Item = &GridCell> {
+        self.solids.iter()
+    }
+
+    /// True if the world-space point lies inside a solid cell.
+    pub fn is_solid_at(&self, world_x: f32, world_y: f32) -> bool {
+        self.is_solid(self.world_to_cell_x(world_x), self.world_to_cell_y(world_y))
+    }
+
+    /// The fluid volume whose cells contain the world-space point; the first
+    /// declared wins where volumes overlap.
+    pub fn fluid_at(&self, world_x: f32, world_y: f32) -> Option<&FluidVolume> {
+        let cell = GridCell {
+            x: self.world_to_cell_x(world_x),
+            y: self.world_to_cell_y(world_y),
+        };
+        self.fluids.iter().find(|fluid| fluid.contains(cell))
+    }
+
+    pub fn fluids(&self) -> &[FluidVolume] {
+        &self.fluids
+    }
+
+    /// World-space y of the fluid's surface (the top edge of its cells).
+    pub fn fluid_surface_y(&self, fluid: &FluidVolume) -> f32 {
+        self.cell_top_world(fluid.y + fluid.height - 1)
+    }
+
+    /// Step multiplier at the world-space point: the `scale` of the time
+    /// volume containing it (the first declared where they overlap), or 1.0.
+    pub fn time_scale_at(&self, world_x: f32, world_y: f32) -> f32 {
+        let cell = GridCell {
+            x: self.world_to_cell_x(world_x),
+            y: self.world_to_cell_y(world_y),
+        };
+        self.time_volumes
+            .iter()
+            .find(|volume| volume.contains(cell))
+            .map_or(1.0, |volume| volume.scale)
+    }
+
+    pub fn time_volumes(&self) -> &[TimeVolume] {
+        &self.time_volumes
+    }
+
+    /// Material of the cell at `(x, y)`; `Normal` unless listed in
+    /// `surfaces`.
+    pub fn surface(&self, x: i32, y: i32) -> SurfaceMaterial {
+        self.surfaces
+            .get(&GridCell { x, y })
+            .copied()
+            .unwrap_or_default()
+    }
+
+    /// Material a body resting on the grid stands on: the solid cell below
+    /// its center, or else the leftmost solid cell under its feet.
+    pub fn surface_under(&self, aabb: Aabb) -> SurfaceMaterial {
+        const EPS: f32 = 0.001;
+        let y = self.world_to_cell_y(aabb.center_y - aabb.half_h - EPS);
+        let center = self.world_to_cell_x(aabb.center_x);
+        let x0 = self.world_to_cell_x(aabb.center_x - aabb.half_w + EPS);
+        let x1 = self.world_to_cell_x(aabb.center_x + aabb.half_w - EPS);
+        std::iter::once(center)
+            .chain(x0..=x1)
+            .find(|x| self.is_solid(*x, y))
+            .map_or(SurfaceMaterial::Normal, |x| self.surface(x, y))
+    }
+
+    /// True if any solid cell overlaps `aabb`. Merely touching a cell edge
+    /// does not count, so an actor resting on the ground is not "overlapping".
+    pub fn overlaps_aabb(&self, aabb: Aabb) -> bool {
+        const EPS: f32 = 0.001;
+        let x0 = self.world_to_cell_x(aabb.center_x - aabb.half_w + EPS);
+        let x1 = self.world_to_cell_x(aabb.center_x + aabb.half_w - EPS);
+        let y0 = self.world_to_cell_y(aabb.center_y - aabb.half_h + EPS);
+        let y1 = self.world_to_cell_y(aabb.center_y + aabb.half_h - EPS);
+        (x0..=x1).any(|x| (y0..=y1).any(|y| self.is_solid(x, y)))
+    }
+
+    /// Solid cells sharing an edge with `aabb`, within `skin` world units.
+    /// Cells that only meet it at a corner are left out.
+    pub fn touching_cells(&self, aabb: Aabb, skin: f32) -> Vec<GridCell> {
+        const EPS: f32 = 0.001;
+        let cells_in = |min_x: f32, max_x: f32, min_y: f32, max_y: f32| {
+            let x0 = self.world_to_cell_x(min_x);
+            let x1 = self.world_to_cell_x(max_x);
+            let y0 = self.world_to_cell_y(min_y);
+            let y1 = self.world_to_cell_y(max_y);
+            (x0..=x1).flat_map(move |x| (y0..=y1).map(move |y| GridCell { x, y }))
+        };
+        let (left, right) = (aabb.center_x - aabb.half_w, aabb.center_x + aabb.half_w);
+        let (bottom, top) = (aabb.center_y - aabb.half_h, aabb.center_y + aabb.half_h);
+        let mut cells: Vec<GridCell> = cells_in(left - skin, right + skin, bottom + EPS, top - EPS)
+            .chain(cells_in(left + EPS, right - EPS, bottom - skin, top + skin))
+            .filter(|cell| self.is_solid(cell.x, cell.y))
+            .collect();
+        cells.sort_by_key(|cell| (cell.y, cell.x));
+        cells.dedup();
+        cells
+    }
+
+    /// First solid cell along the segment from `(from_x, from_y)` to
+    /// `(to_x, to_y)`, walking the cells it crosses in order (a DDA grid
+    /// traversal), so fast movers cannot tunnel through thin walls.
+    pub fn raycast(&self, from_x: f32, from_y: f32, to_x: f32, to_y: f32) -> Option<RayHit> {
+        let mut cell = GridCell {
+            x: self.world_to_cell_x(from_x),
+            y: self.world_to_cell_y(from_y),
+        };
+        if self.is_solid(cell.x, cell.y) {
+            return Some(RayHit {
+                t: 0.0,
+                x: from_x,
+                y: from_y,
+                cell,
+                normal: (0.0, 0.0),
+            });
+        }
+        let (dx, dy) = (to_x - from_x, to_y - from_y);
+        let end = GridCell {
+            x: self.world_to_cell_x(to_x),
+            y: self.world_to_cell_y(to_y),
+        };
+        let size = self.cell_size as f32;
+        // Per axis: cell step, `t` at the next cell boundary, `t` per cell.
+        let axis = |d: f32, next_edge: f32, prev_edge: f32, from: f32| {
+            if d > 0.0 {
+                (1, (next_edge - from) / d, size / d)
+            } else if d < 0.0 {
+                (-1, (prev_edge - from) / d, -size / d)
+            } else {
+                (0, f32::INFINITY, f32::INFINITY)
+            }
+        };
+        let (step_x, mut t_max_x, t_delta_x) = axis(
+            dx,
+            self.cell_right_world(cell.x),
+            self.cell_left_world(cell.x),
+            from_x,
+        );
+        let (step_y, mut t_max_y, t_delta_y) = axis(
+            dy,
+            self.cell_top_world(cell.y),
+            self.cell_bottom_world(cell.y),
+            from_y,
+        );
+
+        while cell != end {
+            let (t, normal) = if t_max_x < t_max_y {
+                cell.x += step_x;
+                let t = t_max_x;
+                t_max_x += t_delta_x;
+                (t, (-step_x as f32, 0.0))
+            } else {
+                cell.y += step_y;
+                let t = t_max_y;
+                t_max_y += t_delta_y;
+                (t, (0.0, -step_y as f32))
+            };
+            if t > 1.0 {
+                break;
+            }
+            if self.is_solid(cell.x, cell.y) {
+                return Some(RayHit {
+                    t,
+                    x: from_x + dx * t,
+                    y: from_y + dy * t,
+                    cell,
+                    normal,
+                });
+            }
+        }
+        None
+    }
+
+    #[allow(dead_code)]
+    pub fn move_and_collide(&self, aabb: Aabb, dx: f32, dy: f32) -> Aabb {
+        self.move_and_collide_detailed(aabb, dx, dy).aabb
+    }
+
+    pub fn move_and_collide_detailed(&self, aabb: Aabb, dx: f32, dy: f32) -> CollisionMoveResult {
+        const EPS: f32 = 0.0001;
+
+        // Axis-separable move-and-slide:
+        // resolve X first, then resolve Y using updated X position.
+        let resolved_x = self.resolve_axis_x(aabb, dx);
+        let x_expected = aabb.center_x + dx;
+        let collided_x = (resolved_x - x_expected).abs() > EPS;
+
+        let mut moved = aabb;
+        moved.center_x = resolved_x;
+        let resolved_y = self.resolve_axis_y(moved, dy);
+        let y_expected = aabb.center_y + dy;
+        let collided_y = (resolved_y - y_expected).abs() > EPS;
+        moved.center_y = resolved_y;
+
+        // Directional block flags are consumed by controller code to zero
+        // velocities only when motion was actually blocked on that side.
+        let blocked_left = collided_x && dx < 0.0;
+        let blocked_right = collided_x && dx > 0.0;
+        let blocked_down = collided_y && dy < 0.0;
+        let blocked_up = collided_y && dy > 0.0;
+
+        CollisionMoveResult {
+            aabb: moved,
+            collided_y,
+            blocked_left,
+            blocked_right,
+            blocked_down,
+            blocked_up,
+        }
+    }
+
+    /// `move_and_collide_detailed` split into substeps of at most half a cell,
+    /// so a fast body cannot step over a thin wall. A move that already fits
+    /// in one substep resolves exactly as the unswept version.
+    pub fn sweep_and_collide(&self, aabb: Aabb, dx: f32, dy: f32) -> CollisionMoveResult {
+        let max_step = self.cell_size as f32 * 0.5;
+        let steps = (dx.abs().max(dy.abs()) / max_step).ceil().max(1.0) as u32;
+        if steps == 1 {
+            return self.move_and_collide_detailed(aabb, dx, dy);
+        }
+        let (mut step_x, mut step_y) = (dx / steps as f32, dy / steps as f32);
+        let mut result = CollisionMoveResult {
+            aabb,
+            collided_y: false,
+            blocked_left: false,
+            blocked_right: false,
+            blocked_down: false,
+            blocked_up: false,
+        };
+        for _ in 0..steps {
+            let next = self.move_and_collide_detailed(result.aabb, step_x, step_y);
+            result.aabb = next.aabb;
+            result.collided_y |= next.collided_y;
+            result.blocked_left |= next.blocked_left;
+            result.blocked_right |= next.blocked_right;
+            result.blocked_down |= next.blocked_down;
+            result.blocked_up |= next.blocked_up;
+            // Stop pushing into whatever blocked an axis.
+            if next.blocked_left || next.blocked_right {
+                step_x = 0.0;
+            }
+            if next.collided_y {
+                step_y = 0.0;
+            }
+        }
+        result
+    }
+
+    fn resolve_axis_x(&self, aabb: Aabb, dx: f32) -> f32 {
+        if dx == 0.0 {
+            return aabb.center_x;
+        }
+
+        const EPS: f32 = 0.001;
+        let mut candidate_x = aabb.center_x + dx;
+        let min_y = aabb.center_y - aabb.half_h + EPS;
+        let max_y = aabb.center_y + aabb.half_h - EPS;
+        let y0 = self.world_to_cell_y(min_y);
+        let y1 = self.world_to_cell_y(max_y);
+
+        if dx > 0.0 {
+            let max_x = candidate_x + aabb.half_w - EPS;
+            let x_cell = self.world_to_cell_x(max_x);
+            for y in y0..=y1 {
+                if self.is_solid(x_cell, y) {
+                    let cell_left = self.cell_left_world(x_cell);
+                    candidate_x = candidate_x.min(cell_left - aabb.half_w);
+                }
+            }
+            // Guardrail: never push opposite direction during resolution.
+            candidate_x = candidate_x.max(aabb.center_x);
+        } else {
+            let min_x = candidate_x - aabb.half_w + EPS;
+            let x_cell = self.world_to_cell_x(min_x);
+            for y in y0..=y1 {
+                if self.is_solid(x_cell, y) {
+                    let cell_right = self.cell_right_world(x_cell);
+                    candidate_x = candidate_x.max(cell_right + aabb.half_w);
+                }
+            }
+            // Guardrail: never push opposite direction during resolution.
+            candidate_x = candidate_x.min(aabb.center_x);
+        }
+
+        candidate_x
+    }
+
+    fn resolve_axis_y(&self, aabb: Aabb, dy: f32) -> f32 {
+        if dy == 0.0 {
+            return aabb.center_y;
+        }
+
+        const EPS: f32 = 0.001;
+        let mut candidate_y = aabb.center_y + dy;
+        let min_x = aabb.center_x - aabb.half_w + EPS;
+        let max_x = aabb.center_x + aabb.half_w - EPS;
+        let x0 = self.world_to_cell_x(min_x);
+        let x1 = self.world_to_cell_x(max_x);
+
+        if dy > 0.0 {
+            let max_y = candidate_y + aabb.half_h - EPS;
+            let y_cell = self.world_to_cell_y(max_y);
+            for x in x0..=x1 {
+                if self.is_solid(x, y_cell) {
+                    let cell_bottom = self.cell_bottom_world(y_cell);
+                    candidate_y = candidate_y.min(cell_bottom - aabb.half_h);
+                }
+            }
+            // Guardrail: never push opposite direction during resolution.
+            candidate_y = candidate_y.max(aabb.center_y);
+        } else {
+            let min_y = candidate_y - aabb.half_h + EPS;
+            let y_cell = self.world_to_cell_y(min_y);
+            for x in x0..=x1 {
+                if self.is_solid(x, y_cell) {
+                    let cell_top = self.cell_top_world(y_cell);
+                    candidate_y = candidate_y.max(cell_top + aabb.half_h);
+                }
+            }
+            // Guardrail: never push opposite direction during resolution.
+            candidate_y = candidate_y.min(aabb.center_y);
+        }
+
+        candidate_y
+    }
+
+    fn world_to_cell_x(&self, world_x: f32) -> i32 {
+        ((world_x - self.origin.x as f32) / self.cell_size as f32).floor() as i32
+    }
+
+    fn world_to_cell_y(&self, world_y: f32) -> i32 {
+        ((world_y - self.origin.y as f32) / self.cell_size as f32).floor() as i32
+    }
+
+    fn cell_left_world(&self, x: i32) -> f32 {
+        self.origin.x as f32 + (x * self.cell_size) as f32
+    }
+
+    fn cell_right_world(&self, x: i32) -> f32 {
+        self.origin.x as f32 + ((x + 1) * self.cell_size) as f32
+    }
+
+    fn cell_bottom_world(&self, y: i32) -> f32 {
+        self.origin.y as f32 + (y * self.cell_size) as f32
+    }
+
+    fn cell_top_world(&self, y: i32) -> f32 {
+        self.origin.y as f32 + ((y + 1) * self.cell_size) as f32
+    }
+}
+
+pub fn load_collision_from_path(path: &Path) -> Result<CollisionGrid, String> {
+    load_collision_in_units(path, SceneUnits::default())
+}
+
+/// Load a grid whose `cell_size` and `origin` are in `units`, converting
+/// them to pixels. Both must come out as whole pixels.
+pub fn load_collision_in_units(path: &Path, units: SceneUnits) -> Result<CollisionGrid, String> {
+    let raw = sme_core::assets::read_to_string(path)
+        .map_err(|e| format!("Failed to read {}: {e}", path.display()))?;
+    let mut value: serde_json::Value = serde_json::from_str(&raw)
+        .map_err(|e| format!("Failed to parse collision JSON {}: {e}", path.display()))?;
+    COLLISION_SCHEMA.migrate_with_warning(&mut value, &path.display().to_string())?;
+    let mut file: CollisionFile = serde_json::from_value(value)
+        .map_err(|e| format!("Failed to parse collision JSON {}: {e}", path.display()))?;
+    validate_collision_file(&file)?;
+    if !units.is_pixels() {
+        let in_pixels = |value, what| {
+            units
+                .grid_length(value, what)
+                .map_err(|e| format!("Collision validation failed: {e}"))
+        };
+        file.cell_size = in_pixels(file.cell_size, "cell_size")?;
+        file.origin.x = in_pixels(file.origin.x, "origin.x")?;
+        file.origin.y = in_pixels(file.origin.y, "origin.y")?;
+        if units.y_down() {
+            flip_rows(&mut file);
+        }
+    }
+    Ok(CollisionGrid::from_file(file))
+}
+
+/// Turn a grid authored y-down, `origin` at its top-left corner and row 0 at
+/// the top, into the same cells y-up.
+fn flip_rows(file: &mut CollisionFile) {
+    let height = file.height;
+    file.origin.y = -file.origin.y - height * file.cell_size;
+    for cell in &mut file.solids {
+        cell.y = height - 1 - cell.y;
+    }
+    for surface in &mut file.surfaces {
+        surface.y = height - 1 - surface.y;
+    }
+    for fluid in &mut file.fluids {
+        fluid.y = height - fluid.y - fluid.height;
+    }
+    for volume in &mut file.time_volumes {
+        volume.y = height - volume.y - volume.height;
+    }
+}
+
+fn validate_collision_file(file: &CollisionFile) -> Result<(), String> {
+    if file.cell_size <= 0 {
+        return Err("Collision validation failed: cell_size must be > 0".to_string());
+    }
+    if file.width <= 0 || file.height <= 0 {
+        return Err("Collision validation failed: width and height must be > 0".to_string());
+    }
+
+    let mut seen = HashSet::new();
+    for cell in &file.solids {
+        if cell.x < 0 || cell.x >= file.width || cell.y < 0 || cell.y >= file.height {
+            return Err(format!(
+                "Collision validation failed: solid cell out of bounds ({}, {})",
+                cell.x, cell.y
+            ));
+        }
+        if !seen.insert(*cell) {
+            return Err(format!(
+                "Collision validation failed: duplicate solid cell ({}, {})",
+                cell.x, cell.y
+            ));
+        }
+    }
+
+    let solids: HashSet<GridCell> = file.solids.iter().copied().collect();
+    let mut surfaced = HashSet::new();
+    for surface in &file.surfaces {
+        let cell = GridCell {
+            x: surface.x,
+            y: surface.y,
+        };
+        if !solids.contains(&cell) {
+            return Err(format!(
+                "Collision validation failed: surface cell ({}, {}) is not solid",
+                cell.x, cell.y
+            ));
+        }
+        if !surfaced.insert(cell) {
+            return Err(format!(
+                "Collision validation failed: duplicate surface cell ({}, {})",
+                cell.x, cell.y
+            ));
+        }
+        if let SurfaceMaterial::Ice { friction } = surface.material {
+            if !(0.0..=1.0).contains(&friction) {
+                return Err(format!(
+                    "Collision validation failed: ice friction at ({}, {}) must be in 0..=1",
+                    cell.x, cell.y
+                ));
+            }
+        }
+    }
+
+    let mut fluid_ids = HashSet::new();
+    for fluid in &file.fluids {
+        let fail = |reason: &str| {
+            Err(format!(
+                "Collision validation failed: fluid '{}' {reason}",
+                fluid.id
+            ))
+        };
+        if fluid.id.is_empty() {
+            return Err("Collision validation failed: fluid id must not be empty".to_string());
+        }
+        if !fluid_ids.insert(fluid.id.as_str()) {
+            return fail("is declared twice");
+        }
+        if fluid.width <= 0 || fluid.height <= 0 {
+            return fail("width and height must be > 0");
+        }
+        if fluid.x < 0
+            || fluid.y < 0
+            || fluid.x + fluid.width > file.width
+            || fluid.y + fluid.height > file.height
+        {
+            return fail("is out of bounds");
+        }
+        if fluid.gravity_scale < 0.0 || fluid.accel_scale < 0.0 || fluid.swim_speed < 0.0 {
+            return fail("gravity_scale, accel_scale and swim_speed must be >= 0");
+        }
+        if fluid.fall_speed_scale <= 0.0 {
+            return fail("fall_speed_scale must be > 0");
+        }
+    }
+
+    let mut volume_ids = HashSet::new();
+    for volume in &file.time_volumes {
+        let fail = |reason: &str| {
+            Err(format!(
+                "Collision validation failed: time volume '{}' {reason}",
+                volume.id
+            ))
+        };
+        if volume.id.is_empty() {
+            return Err(
+                "Collision validation failed: time volume id must not be empty".to_string(),
+            );
+        }
+        if !volume_ids.insert(volume.id.as_str()) {
+            return fail("is declared twice");
+        }
+        if volume.width <= 0 || volume.height <= 0 {
+            return fail("width and height must be > 0");
+        }
+        if volume.x < 0
+            || volume.y < 0
+            || volume.x + volume.width > file.width
+            || volume.y + volume.height > file.height
+        {
+            return fail("is out of bounds");
+        }
+        if !(volume.scale > 0.0 && volume.scale <= 1.0) {
+            return fail("scale must be in (0, 1]");
+        }
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::units::YAxis;
+    use std::fs;
+    use std::time::{SystemTime, UNIX_EPOCH};
+
+    fn temp_file_path(name_hint: &str) -> std::path::PathBuf {
+        let nanos = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .expect("system time before unix epoch")
+            .as_nanos();
+        std::env::temp_dir().join(format!(
+            "sme_collision_test_{}_{}_{}.json",
+            name_hint,
+            std::process::id(),
+            nanos
+        ))
+    }
+
+    #[test]
+    fn load_collision_valid_file_parses() {
+        let path = temp_file_path("valid");
+        fs::write(
+            &path,
+            r#"{
+              "version":"0.1",
+              "collision_id":"test",
+              "cell_size":32,
+              "origin":{"x":0,"y":0},
+              "width":4,
+              "height":4,
+              "solids":[{"x":1,"y":1},{"x":2,"y":1}]
+            }"#,
+        )
+        .expect("write temp file");
+
+        let grid = load_collision_from_path(&path).expect("valid collision should load");
+        assert_eq!(grid.cell_size, 32);
+        assert!(grid.is_solid(1, 1));
+        assert!(!grid.is_solid(0, 0));
+        let _ = fs::remove_file(path);
+    }
+
+    #[test]
+    fn load_collision_in_units_converts_cells_to_pixels() {
+        let path = temp_file_path("units");
+        fs::write(
+            &path,
+            r#"{
+              "version":"0.1",
+              "collision_id":"test",
+              "cell_size":2,
+              "origin":{"x":-1,"y":0},
+              "width":4,
+              "height":4,
+              "solids":[{"x":1,"y":1}]
+            }"#,
+        )
+        .expect("write temp file");
+
+        let units = |pixels_per_unit| SceneUnits {
+            pixels_per_unit,
+            y_axis: YAxis::Up,
+        };
+        let grid = load_collision_in_units(&path, units(16.0)).expect("whole pixels");
+        assert_eq!((grid.cell_size, grid.origin.x), (32, -16));
+        assert!(grid.is_solid_at(30.0, 40.0));
+
+        let err = load_collision_in_units(&path, units(1.25)).expect_err("fractional cell size");
+        assert!(err.contains("cell_size 2"), "{err}");
+        let _ = fs::remove_file(path);
+    }
+
+    #[test]
+    fn load_collision_in_y_down_units_flips_rows() {
+        let path = temp_file_path("y_down");
+        fs::write(
+            &path,
+            r#"{
+              "version":"0.1",
+              "collision_id":"test",
+              "cell_size":10,
+              "origin":{"x":0,"y":20},
+              "width":4,
+              "height":3,
+              "solids":[{"x":1,"y":0}],
+              "surfaces":[{"x":1,"y":0,"material":"ice"}],
+              "fluids":[{"id":"pool","x":0,"y":1,"width":2,"height":2}]
+            }"#,
+        )
+        .expect("write temp file");
+
+        let units = SceneUnits {
+            pixels_per_unit: 1.0,
+            y_axis: YAxis::Down,
+        };
+        let grid = load_collision_in_units(&path, units).expect("y-down grid");
+        // Top-left at world y -20; three 10px rows below it.
+        assert_eq!(grid.origin.y, -50);
+        assert!(grid.is_solid(1, 2));
+        assert!(grid.is_solid_at(15.0, -25.0));
+        assert!(matches!(grid.surface(1, 2), SurfaceMaterial::Ice { .. }));
+        let pool = grid.fluid_at(5.0, -45.0).expect("pool at the bottom");
+        assert_eq!((pool.y, grid.fluid_surface_y(pool)), (0, -30.0));
+        assert!(grid.fluid_at(5.0, -25.0).is_none());
+        let _ = fs::remove_file(path);
+    }
+
+    #[test]
+    fn fluids_cover_their_cells_and_are_validated() {
+        let path = temp_file_path("fluids");
+        let write = |fluids: &str| {
+            fs::write(
+                &path,
+                format!(
+                    r#"{{
+                      "version":"0.1",
+                      "collision_id":"test",
+                      "cell_size":32,
+                      "width":8,
+                      "height":4,
+                      "solids":[],
+                      "fluids":{fluids}
+                    }}"#
+                ),
+            )
+            .expect("write temp file");
+        };
+
+        write(r#"[{ "id": "pool", "x": 2, "y": 0, "width": 3, "height": 2, "swim_speed": 300 }]"#);
+        let grid = load_collision_from_path(&path).expect("valid fluids should load");
+        let pool = grid.fluid_at(80.0, 40.0).expect("inside the pool");
+        assert_eq!(pool.id, "pool");
+        assert_eq!(pool.swim_speed, 300.0);
+        assert_eq!(pool.gravity_scale, default_gravity_scale());
+        assert_eq!(grid.fluid_surface_y(pool), 64.0);
+        assert!(grid.fluid_at(80.0, 70.0).is_none());
+        assert!(grid.fluid_at(170.0, 10.0).is_none());
+
+        write(r#"[{ "id": "pool", "x": 6, "y": 0, "width": 3, "height": 2 }]"#);
+        let err = load_collision_from_path(&path).expect_err("out of bounds");
+        assert!(err.contains("out of bounds"), "{err}");
+        write(
+            r#"[{ "id": "pool", "x": 0, "y": 0, "width": 1, "height": 1 },
+                { "id": "pool", "x": 1, "y": 0, "width": 1, "height": 1 }]"#,
+        );
+        let err = load_collision_from_path(&path).expect_err("duplicate id");
+        assert!(err.contains("declared twice"), "{err}");
+        write(
+            r#"[{ "id": "tar", "x": 0, "y": 0, "width": 1, "height": 1, "fall_speed_scale": 0 }]"#,
+        );
+        assert!(load_collision_from_path(&path).is_err());
+        let _ = fs::remove_file(path);
+    }
+
+    #[test]
+    fn surfaces_are_read_under_the_feet() {
+        let path = temp_file_path("surfaces");
+        fs::write(
+            &path,
+            r#"{
+              "version":"0.1",
+              "collision_id":"test",
+              "cell_size":32,
+              "width":4,
+              "height":4,
+              "solids":[{"x":0,"y":0},{"x":1,"y":0},{"x":2,"y":0}],
+              "surfaces":[
+                {"x":1,"y":0,"material":"ice"},
+                {"x":2,"y":0,"material":"conveyor","speed":-60}
+              ]
+            }"#,
+        )
+        .expect("write temp file");
+        let grid = load_collision_from_path(&path).expect("valid surfaces should load");
+        let standing_at = |center_x: f32| Aabb {
+            center_x,
+            center_y: 32.0 + 14.0,
+            half_w: 10.0,
+            half_h: 14.0,
+        };
+        assert_eq!(
+            grid.surface_under(standing_at(16.0)),
+            SurfaceMaterial::Normal
+        );
+        assert_eq!(
+            grid.surface_under(standing_at(48.0)),
+            SurfaceMaterial::Ice { friction: 0.1 }
+        );
+        assert_eq!(
+            grid.surface_under(standing_at(80.0)),
+            SurfaceMaterial::Conveyor { speed: -60.0 }
+        );
+        // Center over the gap: the conveyor is the only cell underfoot.
+        assert_eq!(
+            grid.surface_under(standing_at(100.0)),
+            SurfaceMaterial::Conveyor { speed: -60.0 }
+        );
+
+        fs::write(
+            &path,
+            r#"{
+              "version":"0.1",
+              "collision_id":"test",
+              "cell_size":32,
+              "width":4,
+              "height":4,
+              "solids":[{"x":0,"y":0}],
+              "surfaces":[{"x":3,"y":0,"material":"ice"}]
+            }"#,
+        )
+        .expect("write temp file");
+        let err = load_collision_from_path(&path).expect_err("surface on empty cell");
+        assert!(err.contains("is not solid"), "{err}");
+        let _ = fs::remove_file(path);
+    }
+
+    #[test]
+    fn load_collision_rejects_duplicate_cells() {
+        let path = temp_file_path("dup");
+        fs::write(
+            &path,
+            r#"{
+              "version":"0.1",
+              "collision_id":"test",
+              "cell_size":32,
+              "width":4,
+              "height":4,
+              "solids":[{"x":1,"y":1},{"x":1,"y":1}]
+            }"#,
+        )
+        .expect("write temp file");
+
+        let err = load_collision_from_path(&path).expect_err("duplicate cells should fail");
+        assert!(err.contains("duplicate solid cell"));
+        let _ = fs::remove_file(path);
+    }
+
+    #[test]
+    fn time_volumes_scale_their_cells_and_are_validated() {
+        let path = temp_file_path("time_volumes");
+        let write = |volumes: &str| {
+            fs::write(
+                &path,
+                format!(
+                    r#"{{
+                      "version":"0.1",
+                      "collision_id":"test",
+                      "cell_size":32,
+                      "width":8,
+                      "height":4,
+                      "solids":[],
+                      "time_volumes":{volumes}
+                    }}"#
+                ),
+            )
+            .expect("write temp file");
+        };
+
+        write(
+            r#"[{ "id": "vault", "x": 2, "y": 0, "width": 2, "height": 2, "scale": 0.25 },
+                { "id": "wide", "x": 0, "y": 0, "width": 8, "height": 1, "scale": 0.5 }]"#,
+        );
+        let grid = load_collision_from_path(&path).expect("valid time volumes should load");
+        assert_eq!(grid.time_scale_at(80.0, 10.0), 0.25, "first declared wins");
+        assert_eq!(grid.time_scale_at(200.0, 10.0), 0.5);
+        assert_eq!(grid.time_scale_at(200.0, 40.0), 1.0);
+
+        write(r#"[{ "id": "vault", "x": 2, "y": 0, "width": 2, "height": 2, "scale": 0 }]"#);
+        let err = load_collision_from_path(&path).expect_err("zero scale");
+        assert!(err.contains("scale must be"), "{err}");
+        write(r#"[{ "id": "vault", "x": 7, "y": 0, "width": 2, "height": 2, "scale": 0.5 }]"#);
+        let err = load_collision_from_path(&path).expect_err("out of bounds");
+        assert!(err.contains("out of bounds"), "{err}");
+        let _ = fs::remove_file(path);
+    }
+
+    #[test]
+    fn move_and_collide_blocks_motion_into_wall() {
+        let grid = CollisionGrid::from_file(CollisionFile {
+            version: "0.1".to_string(),
+            collision_id: "test".to_string(),
+            cell_size: 32,
+            origin: GridOrigin { x: 0, y: 0 },
+            width: 8,
+            height: 8,
+            solids: vec![GridCell { x: 2, y: 1 }],
+            fluids: Vec::new(),
+            surfaces: Vec::new(),
+            time_volumes: Vec::new(),
+        });
+
+        let start = Aabb {
+            center_x: 32.0 + 8.0,
+            center_y: 32.0 + 8.0,
+            half_w: 8.0,
+            half_h: 8.0,
+        };
+        let moved = grid.move_and_collide(start, 40.0, 0.0);
+        assert!(
+            moved.center_x <= 64.0 - start.half_w + 0.001,
+            "AABB should stop at left edge of wall cell"
+        );
+    }
+
+    #[test]
+    fn move_up_against_obstacle_does_not_push_downward() {
+        let grid = CollisionGrid::from_file(CollisionFile {
+            version: "0.1".to_string(),
+            collision_id: "test".to_string(),
+            cell_size: 32,
+            origin: GridOrigin { x: 0, y: 0 },
+            width: 8,
+            height: 8,
+            solids: vec![
+                // floor
+                GridCell { x: 0, y: 0 },
+                GridCell { x: 1, y: 0 },
+                GridCell { x: 2, y: 0 },
+                GridCell { x: 3, y: 0 },
+                // side obstacle to the right of player
+                GridCell { x: 2, y: 1 },
+            ],
+            fluids: Vec::new(),
+            surfaces: Vec::new(),
+            time_volumes: Vec::new(),
+        });
+
+        let start = Aabb {
+            // Standing on floor and touching near side obstacle.
+            center_x: 48.0,
+            center_y: 40.0,
+            half_w: 8.0,
+            half_h: 8.0,
+        };
+
+        let moved = grid.move_and_collide(start, 0.0, 10.0);
+        assert!(
+            moved.center_y >= start.center_y - 0.0001,
+            "Moving up should never push the character downward"
+        );
+    }
+
+    #[test]
+    fn move_and_collide_detailed_sets_blocked_direction_flags() {
+        let grid = CollisionGrid::from_file(CollisionFile {
+            version: "0.1".to_string(),
+            collision_id: "test".to_string(),
+            cell_size: 32,
+            origin: GridOrigin { x: 0, y: 0 },
+            width: 8,
+            height: 8,
+            solids: vec![GridCell { x: 2, y: 1 }],
+            fluids: Vec::new(),
+            surfaces: Vec::new(),
+            time_volumes: Vec::new(),
+        });
+
+        let start = Aabb {
+            center_x: 32.0 + 8.0,
+            center_y: 32.0 + 8.0,
+            half_w: 8.0,
+            half_h: 8.0,
+        };
+
+        let moved = grid.move_and_collide_detailed(start, 40.0, 0.0);
+        assert!(moved.blocked_right);
+        assert!(!moved.blocked_left);
+        assert!(!moved.collided_y);
+    }
+
+    #[test]
+    fn sweep_and_collide_does_not_tunnel_through_thin_walls() {
+        let grid = CollisionGrid::from_file(CollisionFile {
+            version: "0.1".to_string(),
+            collision_id: "test".to_string(),
+            cell_size: 32,
+            origin: GridOrigin { x: 0, y: 0 },
+            width: 8,
+            height: 8,
+            solids: vec![GridCell { x: 3, y: 1 }],
+            fluids: Vec::new(),
+            surfaces: Vec::new(),
+            time_volumes: Vec::new(),
+        });
+
+        let start = Aabb {
+            center_x: 32.0 + 8.0,
+            center_y: 32.0 + 8.0,
+            half_w: 8.0,
+            half_h: 8.0,
+        };
+        // One unswept move lands past the wall.
+        let tunneled = grid.move_and_collide_detailed(start, 120.0, 0.0);
+        assert!(!tunneled.blocked_right);
+
+        let swept = grid.sweep_and_collide(start, 120.0, 0.0);
+        assert!(swept.blocked_right);
+        assert!((swept.aabb.center_x - (96.0 - start.half_w)).abs() < 0.001);
+
+        let short = grid.sweep_and_collide(start, 10.0, 3.0);
+        assert_eq!(short, grid.move_and_collide_detailed(start, 10.0, 3.0));
+    }
+
+    #[test]
+    fn world_space_queries_match_cells() {
+        let grid = CollisionGrid::from_file(CollisionFile {
+            version: "0.1".to_string(),
+            collision_id: "test".to_string(),
+            cell_size: 32,
+            origin: GridOrigin { x: -64, y: 0 },
+            width: 8,
+            height: 8,
+            solids: vec![GridCell { x: 2, y: 0 }],
+            fluids: Vec::new(),
+            surfaces: Vec::new(),
+            time_volumes: Vec::new(),
+        });
+
+        assert!(grid.is_solid_at(16.0, 16.0));
+        assert!(!grid.is_solid_at(-16.0, 16.0));
+
+        let resting_on_top = Aabb {
+            center_x: 16.0,
+            center_y: 32.0 + 8.0,
+            half_w: 8.0,
+            half_h: 8.0,
+        };
+        assert!(
+            !grid.overlaps_aabb(resting_on_top),
+            "touching is not overlap"
+        );
+        let sunk = Aabb {
+            center_y: 32.0 + 4.0,
+            ..resting_on_top
+        };
+        assert!(grid.overlaps_aabb(sunk));
+    }
+
+    #[test]
+    fn touching_cells_finds_edge_contacts_only() {
+        let grid = CollisionGrid::from_file(CollisionFile {
+            version: "0.1".to_string(),
+            collision_id: "test".to_string(),
+            cell_size: 32,
+            origin: GridOrigin { x: 0, y: 0 },
+            width: 8,
+            height: 8,
+            solids: vec![
+                GridCell { x: 0, y: 0 },
+                GridCell { x: 1, y: 0 },
+                GridCell { x: 2, y: 0 },
+                // Wall to the right of the actor.
+                GridCell { x: 2, y: 1 },
+            ],
+            fluids: Vec::new(),
+            surfaces: Vec::new(),
+            time_volumes: Vec::new(),
+        });
+
+        // Resting on the floor, pressed against the wall.
+        let actor = Aabb {
+            center_x: 64.0 - 8.0,
+            center_y: 32.0 + 8.0,
+            half_w: 8.0,
+            half_h: 8.0,
+        };
+        // (2, 0) only meets the actor's bottom-right corner.
+        assert_eq!(
+            grid.touching_cells(actor, 0.5),
+            vec![GridCell { x: 1, y: 0 }, GridCell { x: 2, y: 1 }]
+        );
+
+        let airborne = Aabb {
+            center_x: 16.0,
+            center_y: 48.0,
+            ..actor
+        };
+        assert!(grid.touching_cells(airborne, 0.5).is_empty());
+    }
+
+    #[test]
+    fn raycast_stops_at_the_first_solid_face() {
+        let grid = CollisionGrid::from_file(CollisionFile {
+            version: "0.1".to_string(),
+            collision_id: "test".to_string(),
+            cell_size: 32,
+            origin: GridOrigin { x: 0, y: 0 },
+            width: 8,
+            height: 8,
+            solids: vec![GridCell { x: 4, y: 1 }, GridCell { x: 6, y: 1 }],
+            fluids: Vec::new(),
+            surfaces: Vec::new(),
+            time_volumes: Vec::new(),
+        });
+
+        // A fast segment crossing both walls hits the nearer one.
+        let hit = grid.raycast(16.0, 48.0, 250.0, 48.0).expect("hit");
+        assert_eq!(hit.cell, GridCell { x: 4, y: 1 });
+        assert_eq!(hit.normal, (-1.0, 0.0));
+        assert!((hit.x - 128.0).abs() < 1e-3, "{}", hit.x);
+        assert!((hit.t - 112.0 / 234.0).abs() < 1e-5);
+
+        // Diagonal down onto the top face of the same cell.
+        let hit = grid.raycast(144.0, 120.0, 144.0 + 10.0, 40.0).expect("hit");
+        assert_eq!(hit.normal, (0.0, 1.0));
+        assert!((hit.y - 64.0).abs() < 1e-3);
+
+        assert!(grid.raycast(16.0, 48.0, 120.0, 48.0).is_none());
+        let inside = grid.raycast(140.0, 40.0, 0.0, 0.0).expect("inside");
+        assert_eq!((inside.t, inside.normal), (0.0, (0.0, 0.0)));
+    }
+}
@@ -0,0 +1,1159 @@
+//! Character controller: translates player intents into physics-resolved movement.
+//!
+//! This is an intent-driven controller, not a direct-mutation one. Each fixed
+//! step receives a `ControllerInput` (move direction + jump), applies acceleration,
+//! gravity, and friction, then delegates to `CollisionGrid::sweep_and_collide`
+//! for slide-based collision resolution.
+//!
+//! Grounded state is determined entirely from collision contact flags (not from
+//! position heuristics like "is y near the floor?"). This keeps the controller
+//! correct regardless of level geometry shape.
+//!
+//! Inside a collision `fluids` volume (checked at the body's center when the
+//! step starts) gravity, max fall speed and acceleration are scaled by the
+//! volume, and the jump action becomes a swim stroke that works off the
+//! ground too.
+//!
+//! Holding crouch shrinks the body's AABB to `crouch_height` of its standing
+//! height, feet kept in place, and slows it to `crouch_speed`. Releasing it
+//! only stands the body back up once the cells overhead leave room, so a body
+//! that crouched under a low ceiling stays down until it is clear.
+//!
+//! A grounded body also reads the surface material under its feet
+//! (`CollisionGrid::surface_under`): ice scales ground acceleration and
+//! friction down, and a conveyor moves the body along without changing its
+//! velocity, so stepping off leaves no leftover push.
+//!
+//! Dash is edge-triggered: the body bursts `dash_distance` units in the
+//! direction it faces over `dash_time` seconds, ignoring gravity and input,
+//! then cannot dash again for `dash_cooldown` seconds. A dash ends early
+//! against a wall. While it lasts the body is invulnerable
+//! (`is_invulnerable`), for scripts that deal damage.
+//!
+//! External pushes (knockback, explosions) go through `add_impulse`. Queued
+//! impulses are added to the velocity in the order they arrived at the start
+//! of the next step, before input acceleration, and the resulting speed on
+//! each axis is clamped to `max_impulse_speed` so stacked hits cannot launch
+//! a body through the level. A dash ignores them.
+//!
+//! Inside a collision `time_volumes` rectangle the body steps with the fixed
+//! `dt` times the volume's scale (`time_scale`), sampled once at the body's
+//! center when the step starts, so a body crossing the edge mid-step is not
+//! split between two rates. Everything the body times itself, dash and
+//! cooldown included, runs on that scaled step; the result depends only on
+//! the body's own state, so replays stay exact.
+//!
+//! Movement goes through `CollisionGrid::sweep_and_collide`, so neither a
+//! dash nor a long fall steps over a thin wall.
+//!
+//! Movement is tuned in `assets/config/controller.json` (see
+//! `ControllerTuning`), which hot-reloads like other content: top-level keys
+//! tune every body, and named `presets` ("heavy", "floaty", ...) give the
+//! scene sprites that reference them (`"controller": "heavy"`) their own feel.
+//! Keys left out keep their defaults. Replays record the player's config and
+//! the tuning's `tuning_hash`, so playing one back under different tuning is
+//! refused.
+
+use std::collections::BTreeMap;
+use std::hash::{Hash, Hasher};
+use std::path::Path;
+
+use serde::{Deserialize, Serialize};
+use sme_core::migrate::SchemaMigrations;
+
+use crate::collision::{Aabb, CollisionGrid, CollisionMoveResult, SurfaceMaterial};
+use crate::determinism::StateHasher;
+
+/// Controller tuning schema versions and the upgrade path to the current one.
+pub const CONTROLLER_SCHEMA: SchemaMigrations = SchemaMigrations {
+    kind: "Controller",
+    current: "0.1",
+    steps: &[],
+};
+
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct ControllerInput {
+    pub move_x: f32,
+    pub jump_pressed: bool,
+    /// Held, not edge-triggered.
+    pub crouch: bool,
+    /// Edge-triggered, like `jump_pressed`.
+    pub dash: bool,
+}
+
+/// Movement tuning; speeds in units per second, accelerations in units per
+/// second squared.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct ControllerConfig {
+    pub max_speed: f32,
+    pub accel_ground: f32,
+    pub accel_air: f32,
+    pub friction_ground: f32,
+    /// Negative: pulls down.
+    pub gravity: f32,
+    /// Negative: the fastest downward speed.
+    pub max_fall_speed: f32,
+    pub jump_speed: f32,
+    /// Crouched height as a fraction of standing height, in `0..=1`.
+    pub crouch_height: f32,
+    /// Crouched `max_speed` as a fraction of the standing one.
+    pub crouch_speed: f32,
+    /// How far a dash carries the body; 0 disables dashing.
+    pub dash_distance: f32,
+    /// Seconds a dash lasts; must be > 0.
+    pub dash_time: f32,
+    /// Seconds after a dash ends before the next one can start.
+    pub dash_cooldown: f32,
+    /// Fastest speed, per axis, that impulses can push a body to.
+    pub max_impulse_speed: f32,
+}
+
+impl Default for ControllerConfig {
+    fn default() -> Self {
+        Self {
+            max_speed: 180.0,
+            accel_ground: 1600.0,
+            accel_air: 900.0,
+            friction_ground: 2000.0,
+            gravity: -1800.0,
+            max_fall_speed: -900.0,
+            jump_speed: 620.0,
+            crouch_height: 0.5,
+            crouch_speed: 0.5,
+            dash_distance: 96.0,
+            dash_time: 0.15,
+            dash_cooldown: 0.6,
+            max_impulse_speed: 1200.0,
+        }
+    }
+}
+
+impl ControllerConfig {
+    /// Fingerprint of the tuning, fixed across builds (see `StateHasher`).
+    pub fn tuning_hash(&self) -> u64 {
+        let mut hasher = StateHasher::new();
+        for value in self.values() {
+            hasher.write_f32(value);
+        }
+        hasher.finish()
+    }
+
+    fn values(&self) -> [f32; 13] {
+        [
+            self.max_speed,
+            self.accel_ground,
+            self.accel_air,
+            self.friction_ground,
+            self.gravity,
+            self.max_fall_speed,
+            self.jump_speed,
+            self.crouch_height,
+            self.crouch_speed,
+            self.dash_distance,
+            self.dash_time,
+            self.dash_cooldown,
+            self.max_impulse_speed,
+        ]
+    }
+
+    fn validate(&self) -> Result<(), String> {
+        if self.values().iter().any(|value| !value.is_finite()) {
+            return Err("Controller validation failed: values must be finite".to_string());
+        }
+        if self.max_speed <= 0.0 {
+            return Err("Controller validation failed: max_speed must be > 0".to_string());
+        }
+        if self.accel_ground < 0.0
+            || self.accel_air < 0.0
+            || self.friction_ground < 0.0
+            || self.jump_speed < 0.0
+        {
+            return Err(
+                "Controller validation failed: accelerations, friction and jump_speed must be >= 0"
+                    .to_string(),
+            );
+        }
+        if self.gravity > 0.0 || self.max_fall_speed >= 0.0 {
+            return Err(
+                "Controller validation failed: gravity must be <= 0 and max_fall_speed < 0"
+                    .to_string(),
+            );
+        }
+        if self.crouch_height <= 0.0 || self.crouch_height > 1.0 || self.crouch_speed < 0.0 {
+            return Err(
+                "Controller validation failed: crouch_height must be in (0, 1] and crouch_speed >= 0"
+                    .to_string(),
+            );
+        }
+        if self.dash_distance < 0.0
+            || self.dash_time <= 0.0
+            || self.dash_cooldown < 0.0
+            || self.max_impulse_speed < 0.0
+        {
+            return Err(
+                "Controller validation failed: dash_distance, dash_cooldown and max_impulse_speed must be >= 0 and dash_time > 0"
+                    .to_string(),
+            );
+        }
+        Ok(())
+    }
+}
+
+/// Every body's movement tuning: the base config and named presets.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct ControllerTuning {
+    /// Bodies whose sprite names no preset, the player included.
+    pub base: ControllerConfig,
+    pub presets: BTreeMap<String, ControllerConfig>,
+}
+
+impl ControllerTuning {
+    /// Config for a body whose sprite names `preset`; unknown presets get
+    /// the base config (see `unknown_preset`).
+    pub fn config(&self, preset: Option<&str>) -> ControllerConfig {
+        preset
+            .and_then(|name| self.presets.get(name))
+            .copied()
+            .unwrap_or(self.base)
+    }
+
+    /// `preset` if it is named but not defined.
+    pub fn unknown_preset<'a>(&self, preset: Option<&'a str>) -> Option<&'a str> {
+        preset.filter(|name| !self.presets.contains_key(*name))
+    }
+
+    /// Fingerprint of the base config and every preset. Without presets it
+    /// is the base config's own hash.
+    pub fn tuning_hash(&self) -> u64 {
+        if self.presets.is_empty() {
+            return self.base.tuning_hash();
+        }
+        let mut hasher = StateHasher::new();
+        hasher.write_u64(self.base.tuning_hash());
+        for (name, config) in &self.presets {
+            name.hash(&mut hasher);
+            hasher.write_u64(config.tuning_hash());
+        }
+        hasher.finish()
+    }
+}
+
+/// Read a tuning file: a `version`, any `ControllerConfig` keys for the base
+/// config, and optional `presets` of the same keys by name.
+pub fn load_controller_tuning_from_path(path: &Path) -> Result<ControllerTuning, String> {
+    let raw = sme_core::assets::read_to_string(path)
+        .map_err(|e| format!("Failed to read {}: {e}", path.display()))?;
+    let mut value: serde_json::Value = serde_json::from_str(&raw)
+        .map_err(|e| format!("Failed to parse controller JSON {}: {e}", path.display()))?;
+    CONTROLLER_SCHEMA.migrate_with_warning(&mut value, &path.display().to_string())?;
+    let presets = match value.as_object_mut() {
+        Some(fields) => {
+            fields.remove("version");
+            fields.remove("presets")
+        }
+        None => None,
+    };
+    let parse_error =
+        |e: serde_json::Error| format!("Failed to parse controller JSON {}: {e}", path.display());
+    let base: ControllerConfig = serde_json::from_value(value).map_err(parse_error)?;
+    base.validate()?;
+    let presets: BTreeMap<String, ControllerConfig> = match presets {
+        Some(presets) => serde_json::from_value(presets).map_err(parse_error)?,
+        None => BTreeMap::new(),
+    };
+    for (name, preset) in &presets {
+        preset
+            .validate()
+            .map_err(|e| format!("{e} (preset '{name}')"))?;
+    }
+    Ok(ControllerTuning { base, presets })
+}
+
+/// Impulses one body can queue per step; more are dropped.
+pub const MAX_IMPULSES: usize = 8;
+
+#[derive(Debug, Clone, Copy)]
+pub struct CharacterController {
+    pub aabb: Aabb,
+    pub velocity_x: f32,
+    pub velocity_y: f32,
+    pub grounded: bool,
+    pub contacts: ContactState,
+    pub config: ControllerConfig,
+    /// Speed lost to collisions during the last step (landing, ceiling or
+    /// wall), for impact feedback such as screen shake.
+    pub impact_speed: f32,
+    /// Whether the last step started inside a fluid volume.
+    pub swimming: bool,
+    /// Whether the AABB is at crouch height.
+    pub crouching: bool,
+    /// `aabb.half_h` when standing.
+    pub stand_half_h: f32,
+    /// 1.0 facing right, -1.0 facing left; the last direction moved.
+    pub facing: f32,
+    /// Seconds left in the current dash; 0 when not dashing.
+    pub dash_left: f32,
+    /// Seconds until the next dash may start.
+    pub dash_cooldown_left: f32,
+    /// Whether a dash started during the last step.
+    pub dash_started: bool,
+    /// Step multiplier of the time volume the last step started in; 1.0
+    /// outside them.
+    pub time_scale: f32,
+    /// Velocity changes waiting for the next step, in arrival order; only
+    /// the first `impulse_count` are live.
+    impulses: [(f32, f32); MAX_IMPULSES],
+    impulse_count: usize,
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct ContactState {
+    pub left: bool,
+    pub right: bool,
+    pub down: bool,
+    pub up: bool,
+}
+
+impl CharacterController {
+    pub fn new(aabb: Aabb) -> Self {
+        Self {
+            aabb,
+            velocity_x: 0.0,
+            velocity_y: 0.0,
+            grounded: false,
+            contacts: ContactState::default(),
+            config: ControllerConfig::default(),
+            impact_speed: 0.0,
+            swimming: false,
+            crouching: false,
+            stand_half_h: aabb.half_h,
+            facing: 1.0,
+            dash_left: 0.0,
+            dash_cooldown_left: 0.0,
+            dash_started: false,
+            time_scale: 1.0,
+            impulses: [(0.0, 0.0); MAX_IMPULSES],
+            impulse_count: 0,
+        }
+    }
+
+    /// Queue a velocity change for the next step. Returns false, dropping
+    /// it, when the queue is full or the impulse is not finite.
+    pub fn add_impulse(&mut self, x: f32, y: f32) -> bool {
+        if self.impulse_count == MAX_IMPULSES || !x.is_finite() || !y.is_finite() {
+            return false;
+        }
+        self.impulses[self.impulse_count] = (x, y);
+        self.impulse_count += 1;
+        true
+    }
+
+    /// Impulses queued for the next step.
+    pub fn pending_impulses(&self) -> &[(f32, f32)] {
+        &self.impulses[..self.impulse_count]
+    }
+
+    pub fn is_dashing(&self) -> bool {
+        self.dash_left > 0.0
+    }
+
+    /// I-frames: damage should pass the body by. True while dashing.
+    pub fn is_invulnerable(&self) -> bool {
+        self.is_dashing()
+    }
+
+    pub fn step(&mut self, input: ControllerInput, dt: f32, collision_grid: &CollisionGrid) {
+        self.time_scale = collision_grid.time_scale_at(self.aabb.center_x, self.aabb.center_y);
+        let dt = dt * self.time_scale;
+        self.update_crouch(input.crouch, collision_grid);
+        if input.move_x != 0.0 {
+            self.facing = input.move_x.signum();
+        }
+        self.dash_cooldown_left = (self.dash_cooldown_left - dt).max(0.0);
+        self.apply_impulses();
+        self.dash_started = input.dash
+            && !self.is_dashing()
+            && self.dash_cooldown_left == 0.0
+            && self.config.dash_distance > 0.0;
+        if self.dash_started {
+            self.dash_left = self.config.dash_time;
+        }
+        if self.is_dashing() {
+            self.step_dash(dt, collision_grid);
+            return;
+        }
+
+        let fluid = collision_grid.fluid_at(self.aabb.center_x, self.aabb.center_y);
+        self.swimming = fluid.is_some();
+        let mut gravity = self.config.gravity;
+        let mut max_fall_speed = self.config.max_fall_speed;
+        let mut accel_scale = 1.0;
+        if let Some(fluid) = fluid {
+            gravity *= fluid.gravity_scale;
+            max_fall_speed *= fluid.fall_speed_scale;
+            accel_scale = fluid.accel_scale;
+        }
+
+        let surface = if self.grounded {
+            collision_grid.surface_under(self.aabb)
+        } else {
+            SurfaceMaterial::Normal
+        };
+        let (grip, carried) = match surface {
+            SurfaceMaterial::Normal => (1.0, 0.0),
+            SurfaceMaterial::Ice { friction } => (friction, 0.0),
+            SurfaceMaterial::Conveyor { speed } => (1.0, speed),
+        };
+
+        // Horizontal control: accelerate toward intent, friction when grounded and idle.
+        let accel = accel_scale
+            * if self.grounded {
+                self.config.accel_ground * grip
+            } else {
+                self.config.accel_air
+            };
+
+        let max_speed = if self.crouching {
+            self.config.max_speed * self.config.crouch_speed
+        } else {
+            self.config.max_speed
+        };
+        if input.move_x != 0.0 {
+            let target = input.move_x * max_speed;
+            self.velocity_x = move_towards(self.velocity_x, target, accel * dt);
+        } else if self.grounded {
+            let friction = self.config.friction_ground * grip;
+            self.velocity_x = move_towards(self.velocity_x, 0.0, friction * dt);
+        }
+
+        // Jump is edge-triggered and only legal from grounded state; in a
+        // fluid it is a swim stroke, legal anywhere.
+        if let Some(fluid) = fluid.filter(|_| input.jump_pressed) {
+            self.velocity_y = self.velocity_y.max(fluid.swim_speed);
+            self.grounded = false;
+        } else if input.jump_pressed && self.grounded {
+            self.velocity_y = self.config.jump_speed;
+            self.grounded = false;
+        }
+
+        // Gravity is always applied in fixed-step simulation.
+        self.velocity_y = (self.velocity_y + gravity * dt).max(max_fall_speed);
+
+        let dx = (self.velocity_x + carried) * dt;
+        let dy = self.velocity_y * dt;
+        let result = collision_grid.sweep_and_collide(self.aabb, dx, dy);
+        self.apply_collision_result(result);
+    }
+
+    /// Add the queued impulses to the velocity, oldest first, and clamp it.
+    fn apply_impulses(&mut self) {
+        if self.impulse_count == 0 {
+            return;
+        }
+        let limit = self.config.max_impulse_speed;
+        let impulses = self.impulses;
+        for &(x, y) in &impulses[..self.impulse_count] {
+            self.velocity_x += x;
+            self.velocity_y += y;
+        }
+        self.velocity_x = self.velocity_x.clamp(-limit, limit);
+        self.velocity_y = self.velocity_y.clamp(-limit, limit);
+        // Knocked upward off the ground: no friction or jump this step.
+        if self.velocity_y > 0.0 {
+            self.grounded = false;
+        }
+        self.impulse_count = 0;
+    }
+
+    /// One step of a dash: a straight, gravity-free burst along `facing`.
+    fn step_dash(&mut self, dt: f32, collision_grid: &CollisionGrid) {
+        let speed = self.config.dash_distance / self.config.dash_time;
+        let dash_dt = dt.min(self.dash_left);
+        self.velocity_x = self.facing * speed;
+        self.velocity_y = 0.0;
+        let result = collision_grid.sweep_and_collide(self.aabb, self.velocity_x * dash_dt, 0.0);
+        self.apply_collision_result(result);
+        // The burst has no vertical motion to land with: probe the floor so a
+        // ground dash stays grounded and one off a ledge does not.
+        self.grounded = collision_grid
+            .move_and_collide_detailed(self.aabb, 0.0, -1.0)
+            .blocked_down;
+        self.contacts.down = self.grounded;
+        self.dash_left -= dash_dt;
+        if result.blocked_left || result.blocked_right {
+            self.dash_left = 0.0;
+        }
+        if !self.is_dashing() {
+            self.dash_left = 0.0;
+            self.dash_cooldown_left = self.config.dash_cooldown;
+            self.velocity_x = self
+                .velocity_x
+                .clamp(-self.config.max_speed, self.config.max_speed);
+        }
+    }
+
+    /// Crouch or stand up, keeping the feet where they are. Standing up waits
+    /// while the standing AABB would overlap a solid cell.
+    fn update_crouch(&mut self, crouch: bool, collision_grid: &CollisionGrid) {
+        if crouch == self.crouching {
+            return;
+        }
+        let half_h = if crouch {
+            self.stand_half_h * self.config.crouch_height
+        } else {
+            self.stand_half_h
+        };
+        let feet = self.aabb.center_y - self.aabb.half_h;
+        let resized = Aabb {
+            center_y: feet + half_h,
+            half_h,
+            ..self.aabb
+        };
+        if !crouch && collision_grid.overlaps_aabb(resized) {
+            return;
+        }
+        self.aabb = resized;
+        self.crouching = crouch;
+    }
+
+    fn apply_collision_result(&mut self, result: CollisionMoveResult) {
+        self.aabb = result.aabb;
+        self.contacts = ContactState {
+            left: result.blocked_left,
+            right: result.blocked_right,
+            down: result.blocked_down,
+            up: result.blocked_up,
+        };
+        self.impact_speed = 0.0;
+
+        if (result.blocked_left && self.velocity_x < 0.0)
+            || (result.blocked_right && self.velocity_x > 0.0)
+        {
+            self.impact_speed = self.velocity_x.abs();
+            self.velocity_x = 0.0;
+        }
+
+        if result.blocked_up && self.velocity_y > 0.0 {
+            self.impact_speed = self.impact_speed.max(self.velocity_y);
+            self.velocity_y = 0.0;
+        }
+        // Grounded is driven from collision contact, not from y-position heuristics.
+        if result.blocked_down && self.velocity_y < 0.0 {
+            self.impact_speed = self.impact_speed.max(-self.velocity_y);
+            self.velocity_y = 0.0;
+            self.grounded = true;
+        } else if result.collided_y {
+            self.velocity_y = 0.0;
+            self.grounded = false;
+        } else {
+            self.grounded = false;
+        }
+    }
+
+    #[allow(dead_code)]
+    pub fn is_grounded(&self) -> bool {
+        self.grounded
+    }
+
+    #[allow(dead_code)]
+    pub fn is_blocked_left(&self) -> bool {
+        self.contacts.left
+    }
+
+    #[allow(dead_code)]
+    pub fn is_blocked_right(&self) -> bool {
+        self.contacts.right
+    }
+
+    #[allow(dead_code)]
+    pub fn is_blocked_up(&self) -> bool {
+        self.contacts.up
+    }
+
+    #[allow(dead_code)]
+    pub fn is_blocked_down(&self) -> bool {
+        self.contacts.down
+    }
+}
+
+/// Linearly move `current` toward `target` by at most `max_delta`.
+/// Used for smooth acceleration and friction instead of instant velocity
+/// snapping, which would feel jerky at 60 Hz fixed step.
+fn move_towards(current: f32, target: f32, max_delta: f32) -> f32 {
+    if (target - current).abs() <= max_delta {
+        target
+    } else if target > current {
+        current + max_delta
+    } else {
+        current - max_delta
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::collision::{CollisionFile, GridCell, GridOrigin};
+
+    fn sample_grid() -> CollisionGrid {
+        CollisionGrid::from_file(CollisionFile {
+            version: "0.1".to_string(),
+            collision_id: "test".to_string(),
+            cell_size: 32,
+            origin: GridOrigin { x: -320, y: -192 },
+            width: 20,
+            height: 12,
+            solids: vec![
+                GridCell { x: 0, y: 0 },
+                GridCell { x: 1, y: 0 },
+                GridCell { x: 2, y: 0 },
+                GridCell { x: 3, y: 0 },
+                GridCell { x: 4, y: 0 },
+                GridCell { x: 5, y: 0 },
+                GridCell { x: 6, y: 0 },
+                GridCell { x: 7, y: 0 },
+                GridCell { x: 8, y: 0 },
+                GridCell { x: 9, y: 0 },
+                GridCell { x: 10, y: 0 },
+                GridCell { x: 11, y: 0 },
+                GridCell { x: 12, y: 0 },
+                GridCell { x: 13, y: 0 },
+                GridCell { x: 14, y: 0 },
+                GridCell { x: 15, y: 0 },
+                GridCell { x: 16, y: 0 },
+                GridCell { x: 17, y: 0 },
+                GridCell { x: 18, y: 0 },
+                GridCell { x: 19, y: 0 },
+                GridCell { x: 6, y: 1 },
+                GridCell { x: 6, y: 2 },
+                GridCell { x: 10, y: 1 },
+                GridCell { x: 10, y: 2 },
+            ],
+            fluids: Vec::new(),
+            surfaces: Vec::new(),
+            time_volumes: Vec::new(),
+        })
+    }
+
+    #[test]
+    fn deterministic_sequence_reaches_same_final_state() {
+        let grid = sample_grid();
+        let start = Aabb {
+            center_x: grid.origin.x as f32 + 64.0,
+            center_y: grid.origin.y as f32 + 96.0,
+            half_w: 10.0,
+            half_h: 14.0,
+        };
+
+        let mut inputs = Vec::new();
+        for _ in 0..60 {
+            inputs.push(ControllerInput {
+                move_x: 1.0,
+                jump_pressed: false,
+                crouch: false,
+                dash: false,
+            });
+        }
+        inputs.push(ControllerInput {
+            move_x: 1.0,
+            jump_pressed: true,
+            crouch: false,
+            dash: false,
+        });
+        for _ in 0..120 {
+            inputs.push(ControllerInput {
+                move_x: 1.0,
+                jump_pressed: false,
+                crouch: false,
+                dash: false,
+            });
+        }
+        for _ in 0..60 {
+            inputs.push(ControllerInput {
+                move_x: -1.0,
+                jump_pressed: false,
+                crouch: false,
+                dash: false,
+            });
+        }
+
+        let dt = 1.0 / 60.0;
+        let mut run_a = CharacterController::new(start);
+        let mut run_b = CharacterController::new(start);
+
+        for input in &inputs {
+            run_a.step(*input, dt, &grid);
+        }
+        for input in &inputs {
+            run_b.step(*input, dt, &grid);
+        }
+
+        assert!((run_a.aabb.center_x - run_b.aabb.center_x).abs() < 0.0001);
+        assert!((run_a.aabb.center_y - run_b.aabb.center_y).abs() < 0.0001);
+        assert!((run_a.velocity_x - run_b.velocity_x).abs() < 0.0001);
+        assert!((run_a.velocity_y - run_b.velocity_y).abs() < 0.0001);
+        assert_eq!(run_a.grounded, run_b.grounded);
+    }
+
+    #[test]
+    fn jump_only_activates_when_grounded() {
+        let grid = sample_grid();
+        let start = Aabb {
+            center_x: grid.origin.x as f32 + 64.0,
+            center_y: grid.origin.y as f32 + 96.0,
+            half_w: 10.0,
+            half_h: 14.0,
+        };
+
+        let mut controller = CharacterController::new(start);
+        controller.grounded = false;
+        controller.step(
+            ControllerInput {
+                move_x: 0.0,
+                jump_pressed: true,
+                crouch: false,
+                dash: false,
+            },
+            1.0 / 60.0,
+            &grid,
+        );
+        assert!(controller.velocity_y <= 0.0);
+    }
+
+    #[test]
+    fn crouching_fits_under_ceilings_and_stands_once_clear() {
+        // 16-unit cells: floor on row 0, a low ceiling on row 2 over x 5..=10.
+        let solids = (0..30)
+            .map(|x| GridCell { x, y: 0 })
+            .chain((5..=10).map(|x| GridCell { x, y: 2 }))
+            .collect();
+        let grid = CollisionGrid::from_file(CollisionFile {
+            version: "0.1".to_string(),
+            collision_id: "tunnel".to_string(),
+            cell_size: 16,
+            origin: GridOrigin { x: 0, y: 0 },
+            width: 30,
+            height: 8,
+            solids,
+            fluids: Vec::new(),
+            surfaces: Vec::new(),
+            time_volumes: Vec::new(),
+        });
+        let mut controller = CharacterController::new(Aabb {
+            center_x: 40.0,
+            center_y: 16.0 + 14.0,
+            half_w: 6.0,
+            half_h: 14.0,
+        });
+        controller.grounded = true;
+        let dt = 1.0 / 60.0;
+        let crouch_right = ControllerInput {
+            move_x: 1.0,
+            jump_pressed: false,
+            crouch: true,
+            dash: false,
+        };
+        controller.step(crouch_right, dt, &grid);
+        assert!(controller.crouching);
+        assert_eq!(controller.aabb.half_h, 7.0);
+        // Feet stay on the floor.
+        assert!((controller.aabb.center_y - 23.0).abs() < 0.01);
+
+        while controller.aabb.center_x < 120.0 {
+            controller.step(crouch_right, dt, &grid);
+        }
+        let walk_right = ControllerInput {
+            crouch: false,
+            ..crouch_right
+        };
+        controller.step(walk_right, dt, &grid);
+        assert!(controller.crouching, "stood up under the ceiling");
+        assert!(controller.velocity_x <= controller.config.max_speed * 0.5);
+
+        while controller.aabb.center_x < 200.0 {
+            controller.step(walk_right, dt, &grid);
+        }
+        assert!(!controller.crouching);
+        assert_eq!(controller.aabb.half_h, 14.0);
+    }
+
+    #[test]
+    fn dashes_cover_their_distance_and_stop_at_thin_walls() {
+        // 16-unit cells: floor on row 0, a one-cell-thick wall at x 12.
+        let solids = (0..30)
+            .map(|x| GridCell { x, y: 0 })
+            .chain((1..=3).map(|y| GridCell { x: 12, y }))
+            .collect();
+        let grid = CollisionGrid::from_file(CollisionFile {
+            version: "0.1".to_string(),
+            collision_id: "dash".to_string(),
+            cell_size: 16,
+            origin: GridOrigin { x: 0, y: 0 },
+            width: 30,
+            height: 8,
+            solids,
+            fluids: Vec::new(),
+            surfaces: Vec::new(),
+            time_volumes: Vec::new(),
+        });
+        let mut controller = CharacterController::new(Aabb {
+            center_x: 40.0,
+            center_y: 16.0 + 14.0,
+            half_w: 6.0,
+            half_h: 14.0,
+        });
+        controller.grounded = true;
+        // Two cells a step: fast enough to skip the wall without sweeping.
+        controller.config.dash_time = 0.05;
+        let dt = 1.0 / 60.0;
+        let idle = ControllerInput::default();
+        let dash = ControllerInput { dash: true, ..idle };
+
+        controller.step(dash, dt, &grid);
+        assert!(controller.dash_started && controller.is_invulnerable());
+        while controller.is_dashing() {
+            controller.step(idle, dt, &grid);
+        }
+        assert!((controller.aabb.center_x - 136.0).abs() < 0.01);
+        assert!(controller.grounded);
+        assert_eq!(controller.velocity_x, controller.config.max_speed);
+
+        controller.step(dash, dt, &grid);
+        assert!(!controller.is_dashing(), "dashed during the cooldown");
+        while controller.dash_cooldown_left > 0.0 {
+            controller.step(idle, dt, &grid);
+        }
+        controller.step(dash, dt, &grid);
+        while controller.is_dashing() {
+            controller.step(idle, dt, &grid);
+        }
+        assert!((controller.aabb.center_x - (192.0 - 6.0)).abs() < 0.01);
+        assert!(controller.contacts.right);
+    }
+
+    #[test]
+    fn impulses_apply_before_input_and_are_clamped() {
+        let grid = sample_grid();
+        let mut controller = CharacterController::new(Aabb {
+            center_x: grid.origin.x as f32 + 128.0,
+            center_y: grid.origin.y as f32 + 300.0,
+            half_w: 10.0,
+            half_h: 14.0,
+        });
+        controller.config.max_impulse_speed = 500.0;
+        assert!(controller.add_impulse(-400.0, 300.0));
+        assert!(controller.add_impulse(-400.0, 0.0));
+        assert!(!controller.add_impulse(f32::NAN, 0.0));
+        assert_eq!(controller.pending_impulses().len(), 2);
+
+        let dt = 1.0 / 60.0;
+        let right = ControllerInput {
+            move_x: 1.0,
+            ..ControllerInput::default()
+        };
+        controller.step(right, dt, &grid);
+        assert!(controller.pending_impulses().is_empty());
+        // Clamped to -500 first, then the air acceleration toward the input.
+        let accel = controller.config.accel_air * dt;
+        assert!((controller.velocity_x - (-500.0 + accel)).abs() < 0.01);
+        let gravity = controller.config.gravity * dt;
+        assert!((controller.velocity_y - (300.0 + gravity)).abs() < 0.01);
+
+        for _ in 0..MAX_IMPULSES {
+            assert!(controller.add_impulse(1.0, 0.0));
+        }
+        assert!(!controller.add_impulse(1.0, 0.0), "queue overflowed");
+    }
+
+    #[test]
+    fn landing_reports_impact_speed_once() {
+        let grid = sample_grid();
+        let start = Aabb {
+            center_x: grid.origin.x as f32 + 128.0,
+            center_y: grid.origin.y as f32 + 300.0,
+            half_w: 10.0,
+            half_h: 14.0,
+        };
+
+        let mut controller = CharacterController::new(start);
+        let idle = ControllerInput {
+            move_x: 0.0,
+            jump_pressed: false,
+            crouch: false,
+            dash: false,
+        };
+        let mut landing_impact = 0.0;
+        for _ in 0..120 {
+            let falling_speed = -controller.velocity_y;
+            controller.step(idle, 1.0 / 60.0, &grid);
+            if controller.grounded {
+                landing_impact = controller.impact_speed;
+                assert!(landing_impact >= falling_speed);
+                break;
+            }
+            assert_eq!(controller.impact_speed, 0.0);
+        }
+        assert!(landing_impact > 400.0, "impact {landing_impact}");
+
+        // Standing still only "lands" one step's worth of gravity.
+        controller.step(idle, 1.0 / 60.0, &grid);
+        assert!(controller.impact_speed < 40.0);
+    }
+
+    #[test]
+    fn contact_state_reports_wall_block() {
+        let grid = sample_grid();
+        let start = Aabb {
+            center_x: grid.origin.x as f32 + (6.0 * 32.0) - 12.0,
+            center_y: grid.origin.y as f32 + (1.0 * 32.0) + 20.0,
+            half_w: 10.0,
+            half_h: 14.0,
+        };
+
+        let mut controller = CharacterController::new(start);
+        controller.grounded = true;
+        let mut hit_right_wall = false;
+        for _ in 0..120 {
+            controller.step(
+                ControllerInput {
+                    move_x: 1.0,
+                    jump_pressed: false,
+                    crouch: false,
+                    dash: false,
+                },
+                1.0 / 60.0,
+                &grid,
+            );
+            if controller.is_blocked_right() {
+                hit_right_wall = true;
+                break;
+            }
+        }
+
+        assert!(
+            hit_right_wall,
+            "controller should eventually hit right wall"
+        );
+    }
+
+    #[test]
+    fn time_volumes_slow_the_bodies_inside() {
+        // Open air; the left half of the map runs at quarter speed.
+        let grid = CollisionGrid::from_file(CollisionFile {
+            version: "0.1".to_string(),
+            collision_id: "bullet_time".to_string(),
+            cell_size: 32,
+            origin: GridOrigin { x: 0, y: 0 },
+            width: 10,
+            height: 10,
+            solids: Vec::new(),
+            fluids: Vec::new(),
+            surfaces: Vec::new(),
+            time_volumes: vec![serde_json::from_str(
+                r#"{ "id": "slow", "x": 0, "y": 0, "width": 5, "height": 10, "scale": 0.25 }"#,
+            )
+            .expect("time volume")],
+        });
+        let body = |x: f32| {
+            CharacterController::new(Aabb {
+                center_x: x,
+                center_y: 300.0,
+                half_w: 10.0,
+                half_h: 14.0,
+            })
+        };
+        let (mut slow, mut fast) = (body(60.0), body(260.0));
+        slow.dash_cooldown_left = 1.0;
+        fast.dash_cooldown_left = 1.0;
+        let dt = 1.0 / 60.0;
+        for _ in 0..8 {
+            slow.step(ControllerInput::default(), dt, &grid);
+            fast.step(ControllerInput::default(), dt, &grid);
+        }
+        assert_eq!(slow.time_scale, 0.25);
+        assert_eq!(fast.time_scale, 1.0);
+        let gravity = slow.config.gravity * dt;
+        assert!((slow.velocity_y - 8.0 * 0.25 * gravity).abs() < 0.01);
+        assert!((fast.velocity_y - 8.0 * gravity).abs() < 0.01);
+        assert!((slow.dash_cooldown_left - (1.0 - 2.0 * dt)).abs() < 1e-4);
+        assert!((fast.dash_cooldown_left - (1.0 - 8.0 * dt)).abs() < 1e-4);
+    }
+
+    #[test]
+    fn fluids_slow_falls_and_turn_jumps_into_strokes() {
+        // Floor on row 0, water over rows 1..=4 (surface at y = 160).
+        let grid = CollisionGrid::from_file(CollisionFile {
+            version: "0.1".to_string(),
+            collision_id: "pool".to_string(),
+            cell_size: 32,
+            origin: GridOrigin { x: 0, y: 0 },
+            width: 10,
+            height: 10,
+            solids: (0..10).map(|x| GridCell { x, y: 0 }).collect(),
+            fluids: vec![serde_json::from_str(
+                r#"{ "id": "pool", "x": 0, "y": 1, "width": 10, "height": 4 }"#,
+            )
+            .expect("fluid")],
+            surfaces: Vec::new(),
+            time_volumes: Vec::new(),
+        });
+        let mut controller = CharacterController::new(Aabb {
+            center_x: 160.0,
+            center_y: 300.0,
+            half_w: 10.0,
+            half_h: 14.0,
+        });
+        let idle = ControllerInput {
+            move_x: 0.0,
+            jump_pressed: false,
+            crouch: false,
+            dash: false,
+        };
+        let dt = 1.0 / 60.0;
+        while !controller.swimming {
+            controller.step(idle, dt, &grid);
+            assert!(controller.aabb.center_y > 0.0, "never reached the water");
+        }
+        let max_fall = controller.config.max_fall_speed * 0.25;
+        assert!(
+            controller.velocity_y >= max_fall,
+            "{}",
+            controller.velocity_y
+        );
+        assert!(!controller.grounded);
+
+        controller.step(
+            ControllerInput {
+                move_x: 0.0,
+                jump_pressed: true,
+                crouch: false,
+                dash: false,
+            },
+            dt,
+            &grid,
+        );
+        assert!(controller.velocity_y > 200.0, "{}", controller.velocity_y);
+    }
+
+    #[test]
+    fn ice_slides_and_conveyors_carry() {
+        let grid_with = |surfaces: &str| {
+            CollisionGrid::from_file(CollisionFile {
+                version: "0.1".to_string(),
+                collision_id: "surfaces".to_string(),
+                cell_size: 32,
+                origin: GridOrigin { x: 0, y: 0 },
+                width: 20,
+                height: 4,
+                solids: (0..20).map(|x| GridCell { x, y: 0 }).collect(),
+                fluids: Vec::new(),
+                surfaces: serde_json::from_str(surfaces).expect("surfaces"),
+                time_volumes: Vec::new(),
+            })
+        };
+        let all = |material: &str| {
+            let cells: Vec<String> = (0..20)
+                .map(|x| format!(r#"{{ "x": {x}, "y": 0, {material} }}"#))
+                .collect();
+            format!("[{}]", cells.join(","))
+        };
+        let standing = Aabb {
+            center_x: 320.0,
+            center_y: 32.0 + 14.0,
+            half_w: 10.0,
+            half_h: 14.0,
+        };
+        let idle = ControllerInput {
+            move_x: 0.0,
+            jump_pressed: false,
+            crouch: false,
+            dash: false,
+        };
+        let dt = 1.0 / 60.0;
+        // Let go at full speed and measure how far the body slides.
+        let slide = |grid: &CollisionGrid| {
+            let mut controller = CharacterController::new(standing);
+            controller.grounded = true;
+            controller.velocity_x = controller.config.max_speed;
+            for _ in 0..30 {
+                controller.step(idle, dt, grid);
+            }
+            controller.aabb.center_x - standing.center_x
+        };
+        let normal = slide(&grid_with("[]"));
+        let ice = slide(&grid_with(&all(r#""material": "ice""#)));
+        assert!(ice > normal * 2.0, "ice {ice} vs normal {normal}");
+
+        let belt = grid_with(&all(r#""material": "conveyor", "speed": -60"#));
+        let mut controller = CharacterController::new(standing);
+        controller.grounded = true;
+        for _ in 0..60 {
+            controller.step(idle, dt, &belt);
+        }
+        assert!(controller.grounded);
+        assert_eq!(controller.velocity_x, 0.0);
+        let carried = standing.center_x - controller.aabb.center_x;
+        assert!((carried - 60.0).abs() < 0.5, "carried {carried}");
+    }
+
+    fn temp_tuning_path(name_hint: &str) -> std::path::PathBuf {
+        std::env::temp_dir().join(format!(
+            "sme_controller_test_{name_hint}_{}.json",
+            std::process::id()
+        ))
+    }
+
+    #[test]
+    fn tuning_file_overrides_only_the_keys_it_sets() {
+        let path = temp_tuning_path("partial");
+        std::fs::write(&path, r#"{ "version": "0.1", "gravity": -1200.0 }"#).expect("write tuning");
+        let tuning = load_controller_tuning_from_path(&path).expect("valid tuning");
+        assert_eq!(tuning.base.gravity, -1200.0);
+        assert_eq!(
+            tuning.base.jump_speed,
+            ControllerConfig::default().jump_speed
+        );
+        assert_ne!(
+            tuning.tuning_hash(),
+            ControllerTuning::default().tuning_hash()
+        );
+
+        std::fs::write(&path, r#"{ "version": "0.1", "gravity": 300.0 }"#).expect("write tuning");
+        assert!(load_controller_tuning_from_path(&path).is_err());
+        std::fs::write(&path, r#"{ "version": "0.1", "gravty": -1.0 }"#).expect("write tuning");
+        assert!(load_controller_tuning_from_path(&path).is_err());
+        let _ = std::fs::remove_file(path);
+    }
+
+    #[test]
+    fn presets_tune_the_bodies_that_name_them() {
+        let path = temp_tuning_path("presets");
+        std::fs::write(
+            &path,
+            r#"{
+                "version": "0.1",
+                "max_speed": 200.0,
+                "presets": { "heavy": { "gravity": -2600.0 } }
+            }"#,
+        )
+        .expect("write tuning");
+        let tuning = load_controller_tuning_from_path(&path).expect("valid tuning");
+        let heavy = tuning.config(Some("heavy"));
+        assert_eq!(heavy.gravity, -2600.0);
+        // Presets start from the defaults, not from the base config.
+        assert_eq!(heavy.max_speed, ControllerConfig::default().max_speed);
+        assert_eq!(tuning.config(None).max_speed, 200.0);
+        assert_eq!(tuning.config(Some("floaty")), tuning.base);
+        assert_eq!(tuning.unknown_preset(Some("floaty")), Some("floaty"));
+        assert_eq!(tuning.unknown_preset(Some("heavy")), None);
+
+        std::fs::write(
+            &path,
+            r#"{ "version": "0.1", "presets": { "broken": { "max_speed": 0.0 } } }"#,
+        )
+        .expect("write tuning");
+        let err = load_controller_tuning_from_path(&path).unwrap_err();
+        assert!(err.contains("broken"), "{err}");
+        let _ = std::fs::remove_file(path);
+    }
+}
@@ -0,0 +1,158 @@
+//! Crash reports for desktop builds.
+//!
+//! `install` adds a panic hook that writes `crash_reports/crash-<unix ms>.txt`
+//! with the panic message and location, the engine version, the scene being
+//! played, a backtrace and the last lines of the engine log, then runs the
+//! previous hook (which prints the usual panic message). `run` catches the
+//! unwinding panic afterwards to drop the GPU surface before the window goes
+//! away (`App::release_gpu`).
+
+use std::backtrace::Backtrace;
+use std::fs::OpenOptions;
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+/// Where reports are written, relative to the working directory.
+pub const CRASH_REPORT_DIR: &str = "crash_reports";
+
+/// Scene path recorded in reports; set when the scene is loaded.
+static SCENE_PATH: Mutex<Option<String>> = Mutex::new(None);
+
+pub fn set_scene_path(path: &Path) {
+    if let Ok(mut scene) = SCENE_PATH.lock() {
+        *scene = Some(path.to_string_lossy().into_owned());
+    }
+}
+
+/// Install the crash report hook in front of the current panic hook.
+pub fn install() {
+    let previous = std::panic::take_hook();
+    std::panic::set_hook(Box::new(move |info| {
+        let report = CrashReport::capture(info);
+        match report.write(Path::new(CRASH_REPORT_DIR)) {
+            Ok(path) => eprintln!("Crash report written to {}", path.display()),
+            Err(e) => eprintln!("{e}"),
+        }
+        previous(info);
+    }));
+}
+
+struct CrashReport {
+    unix_ms: u128,
+    version: &'static str,
+    thread: String,
+    message: String,
+    location: Option<String>,
+    scene: Option<String>,
+    backtrace: String,
+    log_lines: Vec<String>,
+}
+
+impl CrashReport {
+    fn capture(info: &std::panic::PanicHookInfo) -> Self {
+        let payload = info.payload();
+        let message = payload
+            .downcast_ref::<&str>()
+            .map(|s| s.to_string())
+            .or_else(|| payload.downcast_ref::<String>().cloned())
+            .unwrap_or_else(|| "<non-string panic payload>".to_string());
+        Self {
+            unix_ms: SystemTime::now()
+                .duration_since(UNIX_EPOCH)
+                .map_or(0, |since| since.as_millis()),
+            version: env!("CARGO_PKG_VERSION"),
+            thread: std::thread::current()
+                .name()
+                .unwrap_or("<unnamed>")
+                .to_string(),
+            message,
+            location: info
+                .location()
+                .map(|at| format!("{}:{}:{}", at.file(), at.line(), at.column())),
+            // try_lock: the panic may have happened while the lock was held.
+            scene: SCENE_PATH.try_lock().ok().and_then(|scene| scene.clone()),
+            backtrace: Backtrace::force_capture().to_string(),
+            log_lines: sme_core::logging::recent_lines(),
+        }
+    }
+
+    fn render(&self) -> String {
+        let mut text = format!(
+            "Saturday Morning Engine crash report\n\
+             version: {}\n\
+             time: {} ms since unix epoch\n\
+             thread: {}\n\
+             location: {}\n\
+             scene: {}\n\
+             \n\
+             panic:\n{}\n\
+             \n\
+             backtrace:\n{}\n\
+             \n\
+             last {} log lines:\n",
+            self.version,
+            self.unix_ms,
+            self.thread,
+            self.location.as_deref().unwrap_or("unknown"),
+            self.scene.as_deref().unwrap_or("none loaded"),
+            self.message,
+            self.backtrace.trim_end(),
+            self.log_lines.len(),
+        );
+        for line in &self.log_lines {
+            text.push_str(line);
+        }
+        text
+    }
+
+    fn write(&self, dir: &Path) -> Result<PathBuf, String> {
+        std::fs::create_dir_all(dir)
+            .map_err(|e| format!("Failed to create crash report dir {}: {e}", dir.display()))?;
+        let path = dir.join(format!("crash-{}.txt", self.unix_ms));
+        let mut file = OpenOptions::new()
+            .write(true)
+            .create_new(true)
+            .open(&path)
+            .map_err(|e| format!("Failed to create crash report {}: {e}", path.display()))?;
+        file.write_all(self.render().as_bytes())
+            .map_err(|e| format!("Failed to write crash report {}: {e}", path.display()))?;
+        Ok(path)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn report_is_written_with_context_and_log_tail() {
+        let dir = std::env::temp_dir().join(format!("sme_crash_test_{}", std::process::id()));
+        let report = CrashReport {
+            unix_ms: 1_700_000_000_123,
+            version: "0.1.0",
+            thread: "main".to_string(),
+            message: "index out of bounds".to_string(),
+            location: Some("src/lib.rs:10:5".to_string()),
+            scene: Some("assets/scenes/test.json".to_string()),
+            backtrace: "   0: sme_game::run\n".to_string(),
+            log_lines: vec!["[  1.000s INFO  sme_game] loaded\n".to_string()],
+        };
+
+        let path = report.write(&dir).expect("report written");
+        assert_eq!(path, dir.join("crash-1700000000123.txt"));
+        let text = std::fs::read_to_string(&path).expect("report readable");
+        for expected in [
+            "version: 0.1.0",
+            "location: src/lib.rs:10:5",
+            "scene: assets/scenes/test.json",
+            "panic:\nindex out of bounds",
+            "backtrace:\n   0: sme_game::run\n",
+            "last 1 log lines:\n[  1.000s INFO  sme_game] loaded\n",
+        ] {
+            assert!(text.contains(expected), "missing {expected:?} in\n{text}");
+        }
+        let _ = std::fs::remove_dir_all(dir);
+    }
+}
@@ -0,0 +1,167 @@
+//! Immediate-mode debug drawing in world space.
+//!
+//! Gameplay code queues lines, rect and circle outlines, and text labels on a
+//! `DebugDraw`; Lua does the same through `engine.debug.draw_*`. The queue is
+//! cleared at the start of every fixed step, so a shape stays visible for as
+//! long as something keeps drawing it (and stays put while paused).
+//!
+//! Shapes are flushed into the sprite mesh as thin quads over the scene.
+//! Text has no place in the sprite mesh, so labels are handed to the egui
+//! overlay at their projected screen positions instead.
+
+use glam::Vec2;
+
+/// Straight segments used to outline a circle.
+pub const CIRCLE_SEGMENTS: usize = 24;
+
+/// Color used when a script passes none.
+pub const DEFAULT_DEBUG_COLOR: [f32; 4] = [1.0, 0.9, 0.2, 1.0];
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum DebugShape {
+    Line {
+        a: Vec2,
+        b: Vec2,
+    },
+    /// Outline of an axis-aligned rect.
+    Rect {
+        center: Vec2,
+        size: Vec2,
+    },
+    Circle {
+        center: Vec2,
+        radius: f32,
+    },
+    Text {
+        position: Vec2,
+        text: String,
+    },
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct DebugDraw {
+    shapes: Vec<(DebugShape, [f32; 4])>,
+}
+
+impl DebugDraw {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn line(&mut self, a: Vec2, b: Vec2, color: [f32; 4]) {
+        self.shapes.push((DebugShape::Line { a, b }, color));
+    }
+
+    pub fn rect(&mut self, center: Vec2, size: Vec2, color: [f32; 4]) {
+        self.shapes.push((DebugShape::Rect { center, size }, color));
+    }
+
+    pub fn circle(&mut self, center: Vec2, radius: f32, color: [f32; 4]) {
+        self.shapes
+            .push((DebugShape::Circle { center, radius }, color));
+    }
+
+    /// A label whose top-left corner sits at `position`.
+    pub fn text(&mut self, position: Vec2, text: impl Into<String>, color: [f32; 4]) {
+        let text = text.into();
+        self.shapes
+            .push((DebugShape::Text { position, text }, color));
+    }
+
+    pub fn clear(&mut self) {
+        self.shapes.clear();
+    }
+
+    /// Every line, rect and circle as straight segments.
+    pub fn segments(&self) -> Vec<(Vec2, Vec2, [f32; 4])> {
+        let mut segments = Vec::new();
+        for (shape, color) in &self.shapes {
+            match shape {
+                DebugShape::Line { a, b } => segments.push((*a, *b, *color)),
+                DebugShape::Rect { center, size } => {
+                    let half = *size * 0.5;
+                    let corners = [
+                        *center + Vec2::new(-half.x, -half.y),
+                        *center + Vec2::new(half.x, -half.y),
+                        *center + Vec2::new(half.x, half.y),
+                        *center + Vec2::new(-half.x, half.y),
+                    ];
+                    for i in 0..4 {
+                        segments.push((corners[i], corners[(i + 1) % 4], *color));
+                    }
+                }
+                DebugShape::Circle { center, radius } => {
+                    let point = |i: usize| {
+                        let angle = i as f32 / CIRCLE_SEGMENTS as f32 * std::f32::consts::TAU;
+                        *center + Vec2::from_angle(angle) * *radius
+                    };
+                    for i in 0..CIRCLE_SEGMENTS {
+                        segments.push((point(i), point(i + 1), *color));
+                    }
+                }
+                DebugShape::Text { .. } => {}
+            }
+        }
+        segments
+    }
+
+    /// Queued text labels.
+    pub fn labels(&self) -> impl Iterator<Item = (Vec2, &str, [f32; 4])> {
+        self.shapes.iter().filter_map(|(shape, color)| match shape {
+            DebugShape::Text { position, text } => Some((*position, text.as_str(), *color)),
+            _ => None,
+        })
+    }
+}
+
+/// Corners of a `thickness`-wide quad covering the segment `a`..`b`, in
+/// counter-clockwise order.
+pub fn segment_quad(a: Vec2, b: Vec2, thickness: f32) -> [Vec2; 4] {
+    let along = (b - a).normalize_or(Vec2::X);
+    let side = along.perp() * (thickness * 0.5);
+    // Extend the ends by half the width so joined segments meet at corners.
+    let a = a - along * (thickness * 0.5);
+    let b = b + along * (thickness * 0.5);
+    [a - side, b - side, b + side, a + side]
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const RED: [f32; 4] = [1.0, 0.0, 0.0, 1.0];
+
+    #[test]
+    fn shapes_become_segments_and_labels() {
+        let mut draw = DebugDraw::new();
+        draw.line(Vec2::ZERO, Vec2::new(10.0, 0.0), RED);
+        draw.rect(Vec2::new(5.0, 5.0), Vec2::new(4.0, 2.0), RED);
+        draw.circle(Vec2::ZERO, 3.0, RED);
+        draw.text(Vec2::new(1.0, 2.0), "hello", RED);
+
+        let segments = draw.segments();
+        assert_eq!(segments.len(), 1 + 4 + CIRCLE_SEGMENTS);
+        assert_eq!(segments[1].0, Vec2::new(3.0, 4.0));
+        for (a, _, _) in &segments[5..] {
+            assert!((a.length() - 3.0).abs() < 1e-4);
+        }
+        let labels: Vec<_> = draw.labels().collect();
+        assert_eq!(labels, [(Vec2::new(1.0, 2.0), "hello", RED)]);
+
+        draw.clear();
+        assert!(draw.segments().is_empty());
+        assert_eq!(draw.labels().count(), 0);
+    }
+
+    #[test]
+    fn segment_quad_has_requested_width() {
+        let [p0, p1, p2, p3] = segment_quad(Vec2::ZERO, Vec2::new(0.0, 10.0), 2.0);
+        assert_eq!(p0.distance(p3), 2.0);
+        assert_eq!(p1.distance(p2), 2.0);
+        // Extended by half the width at both ends.
+        assert_eq!(p0.distance(p1), 12.0);
+        // Degenerate segments still produce a visible dot.
+        let dot = segment_quad(Vec2::ONE, Vec2::ONE, 2.0);
+        assert!(dot[0].distance(dot[2]) > 0.0);
+    }
+}
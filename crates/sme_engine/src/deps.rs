@@ -0,0 +1,272 @@
+//! Which loaded assets use which, for cascading hot reloads.
+//!
+//! A scene uses its atlases, its animation files and any loose images it
+//! draws (sprite `asset`s, an `image` background). An atlas uses its texture
+//! files: the full-size one and its `half_res` copy. An animation file uses
+//! every atlas that packs one of its frames' sprite_ids. `AssetGraph` holds
+//! these edges, keyed by asset path, and is rebuilt whenever the scene or
+//! one of its files reloads.
+//!
+//! When a file changes, `affected_by` lists everything that uses it,
+//! directly or through other assets, so the engine rebuilds exactly those:
+//! a repacked atlas re-uploads its textures and restarts the animations
+//! drawn from it, a retouched texture is re-uploaded on its own.
+
+use std::collections::{HashMap, HashSet};
+use std::path::{Path, PathBuf};
+
+use sme_core::interned::Id;
+
+use crate::animation::AnimationRegistry;
+use crate::atlas::MultiAtlasRegistry;
+use crate::scene::{SceneBackground, SceneFile};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum AssetKind {
+    Scene,
+    Atlas,
+    Animation,
+    Texture,
+}
+
+#[derive(Debug, Default)]
+pub struct AssetGraph {
+    kinds: HashMap<Id, AssetKind>,
+    /// Asset -> the assets it uses directly.
+    uses: HashMap<Id, HashSet<Id>>,
+}
+
+impl AssetGraph {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// The graph of a loaded scene: its atlases and animation files as
+    /// loaded now, and the textures they name.
+    pub fn for_scene(
+        scene_path: &Path,
+        scene: &SceneFile,
+        atlas_paths: &[PathBuf],
+        multi_atlas: &MultiAtlasRegistry,
+        animation_paths: &[PathBuf],
+        animations: &AnimationRegistry,
+    ) -> Self {
+        let mut graph = Self::new();
+        let scene_id = graph.add(&scene_path.to_string_lossy(), AssetKind::Scene);
+
+        for atlas_path in atlas_paths {
+            let key = atlas_path.to_string_lossy();
+            let atlas = graph.add(&key, AssetKind::Atlas);
+            graph.link(scene_id, atlas);
+            let Some(registry) = multi_atlas.get(&key) else {
+                continue;
+            };
+            for texture in std::iter::once(registry.texture).chain(registry.half_res_texture) {
+                graph.kinds.insert(texture, AssetKind::Texture);
+                graph.link(atlas, texture);
+            }
+        }
+
+        for animation_path in animation_paths {
+            let animation = graph.add(&animation_path.to_string_lossy(), AssetKind::Animation);
+            graph.link(scene_id, animation);
+            let Some(animation_id) = animations.animation_id_of(animation_path) else {
+                continue;
+            };
+            for sprite_id in animations.frame_sprite_ids(animation_id) {
+                if let Some(key) = multi_atlas.atlas_key_of(sprite_id) {
+                    let atlas = graph.add(key, AssetKind::Atlas);
+                    graph.link(animation, atlas);
+                }
+            }
+        }
+
+        let background = match &scene.background {
+            Some(SceneBackground::Image { asset }) => Some(asset),
+            _ => None,
+        };
+        let sprite_assets = scene
+            .layers
+            .iter()
+            .flat_map(|layer| &layer.sprites)
+            .filter_map(|sprite| sprite.asset.as_ref());
+        for asset in background.into_iter().chain(sprite_assets) {
+            let texture = graph.add(asset, AssetKind::Texture);
+            graph.link(scene_id, texture);
+        }
+        graph
+    }
+
+    pub fn add(&mut self, path: &str, kind: AssetKind) -> Id {
+        let id = Id::new(path);
+        self.kinds.insert(id, kind);
+        id
+    }
+
+    /// Record that `user` uses `used`.
+    pub fn link(&mut self, user: Id, used: Id) {
+        self.uses.entry(user).or_default().insert(used);
+    }
+
+    pub fn kind(&self, asset: Id) -> Option<AssetKind> {
+        self.kinds.get(&asset).copied()
+    }
+
+    /// Every asset of `kind`, sorted by path.
+    pub fn assets_of(&self, kind: AssetKind) -> Vec<Id> {
+        let mut assets: Vec<Id> = self
+            .kinds
+            .iter()
+            .filter(|(_, asset_kind)| **asset_kind == kind)
+            .map(|(asset, _)| *asset)
+            .collect();
+        assets.sort_by_key(|asset| asset.as_str());
+        assets
+    }
+
+    /// The assets `asset` uses directly, sorted by path.
+    pub fn uses(&self, asset: Id) -> Vec<Id> {
+        let mut used: Vec<Id> = self
+            .uses
+            .get(&asset)
+            .into_iter()
+            .flatten()
+            .copied()
+            .collect();
+        used.sort_by_key(|asset| asset.as_str());
+        used
+    }
+
+    /// Every asset that uses `changed`, directly or through other assets,
+    /// nearest first and sorted by path at each step.
+    pub fn affected_by(&self, changed: Id) -> Vec<Id> {
+        let mut affected = Vec::new();
+        let mut seen = HashSet::from([changed]);
+        let mut frontier = vec![changed];
+        while !frontier.is_empty() {
+            let mut users: Vec<Id> = self
+                .uses
+                .iter()
+                .filter(|(user, used)| {
+                    !seen.contains(*user) && frontier.iter().any(|asset| used.contains(asset))
+                })
+                .map(|(user, _)| *user)
+                .collect();
+            users.sort_by_key(|asset| asset.as_str());
+            seen.extend(users.iter().copied());
+            affected.extend(users.iter().copied());
+            frontier = users;
+        }
+        affected
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::atlas::{AtlasRegistry, AtlasSpriteEntry};
+
+    fn atlas(texture: &str, half_res: Option<&str>, sprite_ids: &[&str]) -> AtlasRegistry {
+        let texture = Id::new(texture);
+        AtlasRegistry {
+            atlas_id: "deps_test".to_string(),
+            texture,
+            half_res_texture: half_res.map(Id::new),
+            premultiplied_alpha: false,
+            sprite_entries: sprite_ids
+                .iter()
+                .map(|sprite_id| {
+                    let entry = AtlasSpriteEntry {
+                        texture,
+                        size_px: (8, 8),
+                        uv: [0.0, 0.0, 1.0, 1.0],
+                        rotated: false,
+                        pivot: (0.5, 0.5),
+                        nine_slice: None,
+                    };
+                    (Id::new(sprite_id), entry)
+                })
+                .collect(),
+            sprite_names: HashMap::new(),
+            parallel: None,
+            source_hash: 0,
+        }
+    }
+
+    #[test]
+    fn changes_reach_every_asset_that_uses_them() {
+        let dir = std::env::temp_dir().join(format!("sme_deps_test_{}", std::process::id()));
+        std::fs::create_dir_all(&dir).expect("create dir");
+        let hero_path = dir.join("hero_anim.json");
+        std::fs::write(
+            &hero_path,
+            r#"{
+              "version": "0.1",
+              "animation_id": "deps_hero",
+              "animations": {
+                "run": { "frames": [{ "sprite_id": "deps-run-0", "duration_ms": 100 }] }
+              }
+            }"#,
+        )
+        .expect("write animation");
+        let mut animations = AnimationRegistry::new();
+        animations.load_file(&hero_path).expect("animation");
+
+        let mut multi_atlas = MultiAtlasRegistry::new();
+        let atlas_paths = vec![
+            PathBuf::from("deps/chars.json"),
+            PathBuf::from("deps/props.json"),
+        ];
+        multi_atlas
+            .add_atlas(
+                "deps/chars.json",
+                atlas(
+                    "deps/chars.png",
+                    Some("deps/chars_half.png"),
+                    &["deps-run-0"],
+                ),
+            )
+            .expect("chars");
+        multi_atlas
+            .add_atlas(
+                "deps/props.json",
+                atlas("deps/props.png", None, &["deps-crate"]),
+            )
+            .expect("props");
+        let scene: SceneFile = serde_json::from_str(
+            r#"{ "version": "0.2", "background": { "kind": "image", "asset": "deps/sky.png" } }"#,
+        )
+        .expect("scene");
+
+        let graph = AssetGraph::for_scene(
+            Path::new("deps/level.json"),
+            &scene,
+            &atlas_paths,
+            &multi_atlas,
+            std::slice::from_ref(&hero_path),
+            &animations,
+        );
+        let id = |path: &str| Id::new(path);
+        let hero = Id::new(&hero_path.to_string_lossy());
+
+        assert_eq!(
+            graph.uses(id("deps/chars.json")),
+            vec![id("deps/chars.png"), id("deps/chars_half.png")]
+        );
+        assert_eq!(graph.kind(id("deps/sky.png")), Some(AssetKind::Texture));
+        // The half-res texture reaches the scene through the atlas and the
+        // animation drawn from it.
+        assert_eq!(
+            graph.affected_by(id("deps/chars_half.png")),
+            vec![id("deps/chars.json"), hero, id("deps/level.json")]
+        );
+        assert_eq!(
+            graph.affected_by(id("deps/props.png")),
+            vec![id("deps/props.json"), id("deps/level.json")]
+        );
+        assert!(graph.affected_by(id("deps/level.json")).is_empty());
+        assert_eq!(graph.assets_of(AssetKind::Texture).len(), 4);
+
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+}
@@ -0,0 +1,180 @@
+//! Determinism audit: helpers for catching simulation state that depends on
+//! iteration order or otherwise differs between two runs of the same inputs.
+//!
+//! Maps the fixed step iterates (so far the afterimage trails) are `StateMap`s. In a
+//! normal build that is a `HashMap`, whose order changes from run to run;
+//! with the `determinism-audit` feature it is a `BTreeMap`, and step code that
+//! walks one calls `assert_stable_order` so a map slipped in under another
+//! type fails loudly instead of reordering work. Entities and their components
+//! are already iterated in insertion order (see `world`).
+//!
+//! `StateHasher` folds simulation state into a `u64` per step, and
+//! `audit_runs` runs a simulation twice in-process and reports the first step
+//! whose hashes differ. The replay harness (`sme_sim_test`) runs every
+//! recorded replay through it when built with the feature.
+
+use std::hash::Hasher;
+
+use crate::controller::CharacterController;
+
+/// Map iterated during a fixed step. Ordered under `determinism-audit`.
+#[cfg(not(feature = "determinism-audit"))]
+pub type StateMap<K, V> = std::collections::HashMap<K, V>;
+/// Map iterated during a fixed step. Ordered under `determinism-audit`.
+#[cfg(feature = "determinism-audit")]
+pub type StateMap<K, V> = std::collections::BTreeMap<K, V>;
+
+/// Panic if `keys` are not strictly ascending. Call it where a fixed-step
+/// system walks a `StateMap`; a no-op without `determinism-audit`.
+#[cfg(feature = "determinism-audit")]
+pub fn assert_stable_order<'a, K: Ord + std::fmt::Debug + 'a + ?Sized>(
+    label: &str,
+    keys: impl IntoIterator<Item = &'a K>,
+) {
+    let mut previous: Option<&K> = None;
+    for key in keys {
+        if let Some(previous) = previous {
+            assert!(
+                previous < key,
+                "{label}: iteration order is not stable ({previous:?} before {key:?})"
+            );
+        }
+        previous = Some(key);
+    }
+}
+
+/// Panic if `keys` are not strictly ascending. Call it where a fixed-step
+/// system walks a `StateMap`; a no-op without `determinism-audit`.
+#[cfg(not(feature = "determinism-audit"))]
+#[inline]
+pub fn assert_stable_order<'a, K: Ord + std::fmt::Debug + 'a + ?Sized>(
+    _label: &str,
+    _keys: impl IntoIterator<Item = &'a K>,
+) {
+}
+
+/// 64-bit FNV-1a. Unlike `DefaultHasher` its output is fixed, so hashes can
+/// be compared across builds as well as runs.
+#[derive(Debug, Clone, Copy)]
+pub struct StateHasher(u64);
+
+impl Default for StateHasher {
+    fn default() -> Self {
+        Self(0xcbf2_9ce4_8422_2325)
+    }
+}
+
+impl StateHasher {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Floats are hashed by bit pattern: `0.0` and `-0.0` differ on purpose.
+    pub fn write_f32(&mut self, value: f32) {
+        self.write_u32(value.to_bits());
+    }
+
+    pub fn write_body(&mut self, body: &CharacterController) {
+        let aabb = body.aabb;
+        for value in [
+            aabb.center_x,
+            aabb.center_y,
+            aabb.half_w,
+            aabb.half_h,
+            body.velocity_x,
+            body.velocity_y,
+            body.impact_speed,
+            body.stand_half_h,
+            body.facing,
+            body.dash_left,
+            body.dash_cooldown_left,
+            body.time_scale,
+        ] {
+            self.write_f32(value);
+        }
+        let contacts = body.contacts;
+        for flag in [
+            body.grounded,
+            contacts.left,
+            contacts.right,
+            contacts.down,
+            contacts.up,
+            body.swimming,
+            body.crouching,
+            body.dash_started,
+        ] {
+            self.write_u8(flag as u8);
+        }
+        let impulses = body.pending_impulses();
+        self.write_u8(impulses.len() as u8);
+        for &(x, y) in impulses {
+            self.write_f32(x);
+            self.write_f32(y);
+        }
+    }
+}
+
+impl Hasher for StateHasher {
+    fn finish(&self) -> u64 {
+        self.0
+    }
+
+    fn write(&mut self, bytes: &[u8]) {
+        for byte in bytes {
+            self.0 ^= u64::from(*byte);
+            self.0 = self.0.wrapping_mul(0x0100_0000_01b3);
+        }
+    }
+}
+
+/// Run `simulate` twice and compare the per-step state hashes it returns.
+/// The error names the first step that differs.
+pub fn audit_runs(mut simulate: impl FnMut() -> Vec<u64>) -> Result<(), String> {
+    let first = simulate();
+    let second = simulate();
+    if let Some(step) = first.iter().zip(&second).position(|(a, b)| a != b) {
+        return Err(format!(
+            "runs diverged at step {step}: state hash {:016x} vs {:016x}",
+            first[step], second[step]
+        ));
+    }
+    if first.len() != second.len() {
+        return Err(format!(
+            "first run took {} steps, second run {}",
+            first.len(),
+            second.len()
+        ));
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn audit_reports_the_first_divergent_step() {
+        assert!(audit_runs(|| vec![1, 2, 3]).is_ok());
+
+        let mut run = 0;
+        let err = audit_runs(|| {
+            run += 1;
+            vec![1, 2, run, 4]
+        })
+        .expect_err("second run differs");
+        assert!(err.starts_with("runs diverged at step 2"), "{err}");
+    }
+
+    #[test]
+    fn hasher_output_is_fixed() {
+        let mut hasher = StateHasher::new();
+        hasher.write(b"a");
+        assert_eq!(hasher.finish(), 0xaf63_dc4c_8601_ec8c);
+
+        let mut positive = StateHasher::new();
+        positive.write_f32(0.0);
+        let mut negative = StateHasher::new();
+        negative.write_f32(-0.0);
+        assert_ne!(positive.finish(), negative.finish());
+    }
+}
@@ -0,0 +1,324 @@
+//! Data-driven dialog trees.
+//!
+//! A dialog file is a graph of nodes keyed by id. Each node shows one line of
+//! text (a string-table key, resolved through `sme_core::loc` at display time)
+//! and either a list of choices or a `next` node to advance to. Choices may
+//! carry a Lua `condition` expression that hides them when falsy, and an
+//! `on_select` Lua function name that runs when the player picks them.
+//!
+//! This module owns only the graph and the cursor through it. Condition
+//! evaluation is injected as a closure so the runtime stays testable without a
+//! Lua state; the engine passes `LuaBridge::evaluate_condition`.
+
+use serde::Deserialize;
+use sme_core::migrate::SchemaMigrations;
+use std::collections::HashMap;
+use std::path::Path;
+
+/// Dialog schema versions and the upgrade path to the current one.
+pub const DIALOG_SCHEMA: SchemaMigrations = SchemaMigrations {
+    kind: "Dialog",
+    current: "0.1",
+    steps: &[],
+};
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct DialogFile {
+    #[allow(dead_code)]
+    pub version: String,
+    pub dialog_id: String,
+    pub start: String,
+    pub nodes: HashMap<String, DialogNode>,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct DialogNode {
+    #[serde(default)]
+    pub speaker: Option<String>,
+    pub text: String,
+    #[serde(default)]
+    pub choices: Vec<DialogChoice>,
+    /// Where to go when the node has no (visible) choices. `None` ends the dialog.
+    #[serde(default)]
+    pub next: Option<String>,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct DialogChoice {
+    pub text: String,
+    /// Node to jump to; `None` ends the dialog.
+    #[serde(default)]
+    pub next: Option<String>,
+    /// Lua expression; the choice is hidden unless it evaluates truthy.
+    #[serde(default)]
+    pub condition: Option<String>,
+    /// Name of a global Lua function called as `fn(dialog_id, node_id)`.
+    #[serde(default)]
+    pub on_select: Option<String>,
+}
+
+pub fn load_dialog_from_path(path: &Path) -> Result<DialogFile, String> {
+    let raw = sme_core::assets::read_to_string(path)
+        .map_err(|e| format!("Failed to read dialog {}: {e}", path.display()))?;
+    let mut value: serde_json::Value = serde_json::from_str(&raw)
+        .map_err(|e| format!("Failed to parse dialog {}: {e}", path.display()))?;
+    DIALOG_SCHEMA.migrate_with_warning(&mut value, &path.display().to_string())?;
+    let dialog: DialogFile = serde_json::from_value(value)
+        .map_err(|e| format!("Failed to parse dialog {}: {e}", path.display()))?;
+    validate_dialog(&dialog)?;
+    Ok(dialog)
+}
+
+fn validate_dialog(dialog: &DialogFile) -> Result<(), String> {
+    if dialog.dialog_id.is_empty() {
+        return Err("Dialog validation failed: dialog_id is empty".to_string());
+    }
+    if !dialog.nodes.contains_key(&dialog.start) {
+        return Err(format!(
+            "Dialog validation failed: start node '{}' does not exist",
+            dialog.start
+        ));
+    }
+    for (node_id, node) in &dialog.nodes {
+        if node.text.is_empty() {
+            return Err(format!(
+                "Dialog validation failed: node '{}' has empty text",
+                node_id
+            ));
+        }
+        let targets = node
+            .choices
+            .iter()
+            .filter_map(|choice| choice.next.as_ref())
+            .chain(node.next.as_ref());
+        for target in targets {
+            if !dialog.nodes.contains_key(target) {
+                return Err(format!(
+                    "Dialog validation failed: node '{}' links to missing node '{}'",
+                    node_id, target
+                ));
+            }
+        }
+        if let Some(i) = node.choices.iter().position(|c| c.text.is_empty()) {
+            return Err(format!(
+                "Dialog validation failed: node '{}' choice {} has empty text",
+                node_id, i
+            ));
+        }
+    }
+    Ok(())
+}
+
+/// Loaded dialog files keyed by `dialog_id`.
+#[derive(Debug, Default)]
+pub struct DialogRegistry {
+    dialogs: HashMap<String, DialogFile>,
+}
+
+impl DialogRegistry {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Load (or on hot reload, replace) a dialog file. Returns its id.
+    pub fn load_file(&mut self, path: &Path) -> Result<String, String> {
+        let dialog = load_dialog_from_path(path)?;
+        let id = dialog.dialog_id.clone();
+        self.dialogs.insert(id.clone(), dialog);
+        Ok(id)
+    }
+
+    pub fn get(&self, dialog_id: &str) -> Option<&DialogFile> {
+        self.dialogs.get(dialog_id)
+    }
+}
+
+/// What the caller must do after the player confirms the current node.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DialogConfirm {
+    /// Lua callback to run before entering the next node.
+    pub on_select: Option<String>,
+    /// Node to enter next; `None` means the dialog is over.
+    pub next: Option<String>,
+}
+
+/// Cursor into a running dialog.
+#[derive(Debug, Clone)]
+pub struct ActiveDialog {
+    pub dialog_id: String,
+    pub node_id: String,
+    /// Indices into the current node's `choices` whose conditions passed.
+    pub visible_choices: Vec<usize>,
+    /// Index into `visible_choices`.
+    pub selected: usize,
+}
+
+impl ActiveDialog {
+    pub fn start(dialog: &DialogFile, evaluate: &mut dyn FnMut(&str) -> bool) -> Self {
+        let mut active = Self {
+            dialog_id: dialog.dialog_id.clone(),
+            node_id: String::new(),
+            visible_choices: Vec::new(),
+            selected: 0,
+        };
+        active.enter(dialog, &dialog.start, evaluate);
+        active
+    }
+
+    /// Move to `node_id` and re-evaluate which choices are visible.
+    pub fn enter(
+        &mut self,
+        dialog: &DialogFile,
+        node_id: &str,
+        evaluate: &mut dyn FnMut(&str) -> bool,
+    ) {
+        self.node_id = node_id.to_string();
+        self.selected = 0;
+        self.visible_choices = dialog
+            .nodes
+            .get(node_id)
+            .map(|node| {
+                node.choices
+                    .iter()
+                    .enumerate()
+                    .filter(|(_, choice)| choice.condition.as_deref().is_none_or(&mut *evaluate))
+                    .map(|(i, _)| i)
+                    .collect()
+            })
+            .unwrap_or_default();
+    }
+
+    pub fn node<'a>(&self, dialog: &'a DialogFile) -> Option<&'a DialogNode> {
+        dialog.nodes.get(&self.node_id)
+    }
+
+    /// Move the highlighted choice, wrapping at both ends.
+    pub fn move_selection(&mut self, delta: i32) {
+        let count = self.visible_choices.len() as i32;
+        if count > 0 {
+            self.selected = (self.selected as i32 + delta).rem_euclid(count) as usize;
+        }
+    }
+
+    /// Accept the highlighted choice (or advance a choice-less node).
+    pub fn confirm(&self, dialog: &DialogFile) -> DialogConfirm {
+        let Some(node) = self.node(dialog) else {
+            return DialogConfirm {
+                on_select: None,
+                next: None,
+            };
+        };
+        match self
+            .visible_choices
+            .get(self.selected)
+            .and_then(|&i| node.choices.get(i))
+        {
+            Some(choice) => DialogConfirm {
+                on_select: choice.on_select.clone(),
+                next: choice.next.clone(),
+            },
+            None => DialogConfirm {
+                on_select: None,
+                next: node.next.clone(),
+            },
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::fs;
+    use std::time::{SystemTime, UNIX_EPOCH};
+
+    fn temp_file_path(name_hint: &str) -> std::path::PathBuf {
+        let nanos = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .expect("system time before unix epoch")
+            .as_nanos();
+        std::env::temp_dir().join(format!(
+            "sme_dialog_test_{}_{}_{}.json",
+            name_hint,
+            std::process::id(),
+            nanos
+        ))
+    }
+
+    const SAMPLE_DIALOG: &str = r#"
+    {
+      "version": "0.1",
+      "dialog_id": "intro",
+      "start": "greet",
+      "nodes": {
+        "greet": {
+          "speaker": "npc.reaper",
+          "text": "dialog.greet",
+          "choices": [
+            { "text": "dialog.accept", "next": "thanks", "on_select": "on_accept" },
+            { "text": "dialog.secret", "next": "thanks", "condition": "has_key" },
+            { "text": "dialog.leave" }
+          ]
+        },
+        "thanks": { "text": "dialog.thanks" }
+      }
+    }
+    "#;
+
+    fn load_sample() -> DialogFile {
+        let path = temp_file_path("sample");
+        fs::write(&path, SAMPLE_DIALOG).expect("write temp file");
+        let dialog = load_dialog_from_path(&path).expect("dialog should load");
+        let _ = fs::remove_file(path);
+        dialog
+    }
+
+    #[test]
+    fn conditions_filter_visible_choices() {
+        let dialog = load_sample();
+        let active = ActiveDialog::start(&dialog, &mut |_| false);
+        assert_eq!(active.node_id, "greet");
+        assert_eq!(active.visible_choices, vec![0, 2]);
+
+        let active = ActiveDialog::start(&dialog, &mut |expr| expr == "has_key");
+        assert_eq!(active.visible_choices, vec![0, 1, 2]);
+    }
+
+    #[test]
+    fn confirm_reports_callback_and_walks_to_end() {
+        let dialog = load_sample();
+        let mut active = ActiveDialog::start(&dialog, &mut |_| false);
+        let confirm = active.confirm(&dialog);
+        assert_eq!(confirm.on_select.as_deref(), Some("on_accept"));
+        assert_eq!(confirm.next.as_deref(), Some("thanks"));
+
+        active.enter(&dialog, "thanks", &mut |_| false);
+        let confirm = active.confirm(&dialog);
+        assert_eq!(confirm.on_select, None);
+        assert_eq!(confirm.next, None, "choice-less node without next ends");
+    }
+
+    #[test]
+    fn move_selection_wraps() {
+        let dialog = load_sample();
+        let mut active = ActiveDialog::start(&dialog, &mut |_| false);
+        active.move_selection(-1);
+        assert_eq!(active.selected, 1);
+        assert_eq!(active.confirm(&dialog).next, None, "leave ends dialog");
+        active.move_selection(1);
+        assert_eq!(active.selected, 0);
+    }
+
+    #[test]
+    fn rejects_dangling_links() {
+        let path = temp_file_path("dangling");
+        fs::write(
+            &path,
+            r#"{ "version": "0.1", "dialog_id": "d", "start": "a",
+                 "nodes": { "a": { "text": "t", "next": "nowhere" } } }"#,
+        )
+        .expect("write temp file");
+        let err = load_dialog_from_path(&path).expect_err("dangling link should fail");
+        assert!(err.contains("missing node 'nowhere'"));
+        let _ = fs::remove_file(path);
+    }
+}
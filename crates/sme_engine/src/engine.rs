@@ -0,0 +1,479 @@
+//! Embedding API: build a game binary on the engine instead of forking
+//! `sme_game`.
+//!
+//! ```no_run
+//! use sme_engine::{Engine, EngineConfig, System, SystemContext};
+//!
+//! /// Counts the steps the player spends in the air.
+//! #[derive(Default)]
+//! struct AirTime {
+//!     steps: u32,
+//! }
+//!
+//! impl System for AirTime {
+//!     fn step(&mut self, ctx: &mut SystemContext) {
+//!         if ctx.player().grounded {
+//!             self.steps = 0;
+//!         } else {
+//!             self.steps += 1;
+//!             if self.steps == 120 {
+//!                 ctx.emit("long_jump", serde_json::json!({ "steps": self.steps }));
+//!             }
+//!         }
+//!     }
+//! }
+//!
+//! fn main() -> Result<(), String> {
+//!     let mut engine = Engine::new(EngineConfig {
+//!         scene: "assets/scenes/level_1.json".into(),
+//!         ..EngineConfig::default()
+//!     });
+//!     engine.add_system(AirTime::default());
+//!     engine.run()
+//! }
+//! ```
+//!
+//! A `System` runs once per fixed step, after every built-in system that
+//! moves things and before the step's events reach Lua
+//! (`world::FixedSystem::Custom`), so events it emits are handled in the same
+//! step. Systems run in the order they were added. State a system keeps in
+//! itself is not part of rewind snapshots, so stepping back through rewind
+//! does not restore it.
+//!
+//! `EngineConfig::from_args` reads the same flags and project manifest
+//! (`sme.toml`, see `project`) as `sme_game`, for binaries that want to keep
+//! them; `EngineConfig::from_project` reads only the manifest.
+//!
+//! Content paths in the config (and in scenes) are asset keys such as
+//! `assets/scenes/m4_scene.json`, resolved on disk against the asset root
+//! (see `sme_core::assets::resolve`). The root is, in order: `asset_root` in
+//! the config (`--asset-root`), the `SME_ASSET_ROOT` environment variable,
+//! `asset_root` in an `engine.toml` next to the executable, or else the
+//! working directory. Paths of packs and replays are not asset keys and stay
+//! relative to the working directory.
+
+use std::path::{Path, PathBuf};
+use std::sync::Once;
+
+use sme_core::tier::FidelityTier;
+use sme_devtools::PerformanceBudget;
+use sme_platform::window::PlatformConfig;
+use winit::event_loop::{ControlFlow, EventLoop};
+
+use crate::collision::CollisionGrid;
+use crate::controller::CharacterController;
+use crate::event_record::load_event_recording_from_path;
+use crate::events::{EngineEvent, EventBus};
+use crate::project::{load_manifest_from_path, PROJECT_FILE};
+use crate::replay::load_replay_from_path;
+use crate::transition::{SceneLoad, SceneTransition};
+use crate::{App, ReplayMode};
+
+/// Fallback string table language, and the default `EngineConfig::language`.
+pub(crate) const DEFAULT_LANGUAGE: &str = "en";
+
+const PROJECT_FLAG: &str = "--project";
+const ASSET_ROOT_FLAG: &str = "--asset-root";
+const PACK_FLAG: &str = "--pack";
+const SCENE_FLAG: &str = "--scene";
+const COLLISION_FLAG: &str = "--collision";
+const LANG_FLAG: &str = "--lang";
+const SEED_FLAG: &str = "--seed";
+const RUN_IN_BACKGROUND_FLAG: &str = "--run-in-background";
+const RECORD_REPLAY_FLAG: &str = "--record-replay";
+const PLAY_REPLAY_FLAG: &str = "--play-replay";
+const RECORD_EVENTS_FLAG: &str = "--record-events";
+const PLAY_EVENTS_FLAG: &str = "--play-events";
+const BREAK_ON_FLAG: &str = "--break-on";
+
+/// What an engine instance loads and how it runs.
+#[derive(Debug, Clone, PartialEq)]
+pub struct EngineConfig {
+    /// Directory asset paths are resolved against; see the module docs for
+    /// what applies when this is `None`.
+    pub asset_root: Option<PathBuf>,
+    /// Scene the session starts with.
+    pub scene: PathBuf,
+    /// Collision grid the session starts with.
+    pub collision: PathBuf,
+    /// Lua script driving the player (see `lua_bridge`).
+    pub script: PathBuf,
+    /// In-game UI layout (see `ui`).
+    pub ui: PathBuf,
+    /// Player movement tuning (see `controller`); defaults apply if the file
+    /// is missing.
+    pub controller: PathBuf,
+    /// String table language, e.g. `"en"`.
+    pub language: String,
+    /// String tables, one file per language.
+    pub strings: Vec<PathBuf>,
+    /// Dialog files (see `dialog`).
+    pub dialogs: Vec<PathBuf>,
+    /// Item definition files (see `items`).
+    pub items: Vec<PathBuf>,
+    /// Fidelity tier the session starts at; F2 still cycles it.
+    pub tier: FidelityTier,
+    /// Title and size of the game window.
+    pub window: PlatformConfig,
+    /// Limits the debug overlay warns about; none by default.
+    pub budget: PerformanceBudget,
+    /// Seed of the script RNG (`engine.rand`). A played-back replay uses
+    /// its recorded seed instead.
+    pub seed: u64,
+    /// Serve assets from this content pack (see `sme_core::pack`).
+    pub pack: Option<PathBuf>,
+    /// Keep simulating, with audio and full-rate redraws, while unfocused.
+    pub run_in_background: bool,
+    /// Save the session's controller inputs here on exit.
+    pub record_replay: Option<PathBuf>,
+    /// Play this replay back instead of live input; wins over
+    /// `record_replay`.
+    pub play_replay: Option<PathBuf>,
+    /// Save the session's keyboard and mouse events here on exit (see
+    /// `event_record`).
+    pub record_events: Option<PathBuf>,
+    /// Play these keyboard and mouse events back instead of live ones; wins
+    /// over `record_events`.
+    pub play_events: Option<PathBuf>,
+    /// Lua functions that pause the simulation when entered (see
+    /// `lua_bridge`); `--break-on` takes them comma-separated.
+    pub break_on: Vec<String>,
+}
+
+impl Default for EngineConfig {
+    fn default() -> Self {
+        Self {
+            asset_root: None,
+            scene: "assets/scenes/m4_scene.json".into(),
+            collision: "assets/collision/m3_collision.json".into(),
+            script: "assets/scripts/controller.lua".into(),
+            ui: "assets/ui/hud.json".into(),
+            controller: "assets/config/controller.json".into(),
+            language: DEFAULT_LANGUAGE.to_string(),
+            strings: vec!["assets/loc/en.json".into(), "assets/loc/es.json".into()],
+            dialogs: vec!["assets/dialog/courier_intro.json".into()],
+            items: vec!["assets/items/grim_items.json".into()],
+            tier: FidelityTier::default(),
+            window: PlatformConfig::default(),
+            budget: PerformanceBudget::default(),
+            seed: sme_core::rng::DEFAULT_RNG_SEED,
+            pack: None,
+            run_in_background: false,
+            record_replay: None,
+            play_replay: None,
+            record_events: None,
+            play_events: None,
+            break_on: Vec::new(),
+        }
+    }
+}
+
+impl EngineConfig {
+    /// Defaults, overridden by the project manifest (see `project`), then by
+    /// `sme_game`'s command-line flags. The manifest is `--project <path>`, or
+    /// else `sme.toml` at the asset root if there is one. `args` excludes the
+    /// program name; arguments that are not engine flags are ignored.
+    pub fn from_args(args: &[String]) -> Result<Self, String> {
+        let flag_root = flag_value(args, ASSET_ROOT_FLAG).map(PathBuf::from);
+        let manifest_path = match flag_value(args, PROJECT_FLAG) {
+            Some(path) => Some(PathBuf::from(path)),
+            None => {
+                let root = flag_root
+                    .clone()
+                    .or_else(|| std::env::var_os(sme_core::assets::ASSET_ROOT_ENV).map(Into::into))
+                    .unwrap_or_default();
+                Some(root.join(PROJECT_FILE)).filter(|path| path.is_file())
+            }
+        };
+        let mut config = match manifest_path {
+            Some(path) => Self::from_project(&path)?,
+            None => Self::default(),
+        };
+
+        if let Some(seed) = flag_value(args, SEED_FLAG) {
+            config.seed = seed
+                .parse()
+                .map_err(|e| format!("Invalid {SEED_FLAG} value: {e}"))?;
+        }
+        if flag_root.is_some() {
+            config.asset_root = flag_root;
+        }
+        if let Some(scene) = flag_value(args, SCENE_FLAG) {
+            config.scene = scene.into();
+        }
+        if let Some(collision) = flag_value(args, COLLISION_FLAG) {
+            config.collision = collision.into();
+        }
+        if let Some(language) = flag_value(args, LANG_FLAG) {
+            config.language = language.into();
+        }
+        config.pack = flag_value(args, PACK_FLAG).map(Into::into);
+        config.run_in_background = args.iter().any(|arg| arg == RUN_IN_BACKGROUND_FLAG);
+        config.record_replay = flag_value(args, RECORD_REPLAY_FLAG).map(Into::into);
+        config.play_replay = flag_value(args, PLAY_REPLAY_FLAG).map(Into::into);
+        config.record_events = flag_value(args, RECORD_EVENTS_FLAG).map(Into::into);
+        config.play_events = flag_value(args, PLAY_EVENTS_FLAG).map(Into::into);
+        if let Some(names) = flag_value(args, BREAK_ON_FLAG) {
+            config.break_on = names
+                .split(',')
+                .map(str::trim)
+                .filter(|name| !name.is_empty())
+                .map(str::to_string)
+                .collect();
+        }
+        Ok(config)
+    }
+
+    /// Defaults overridden by the project manifest at `path`.
+    pub fn from_project(path: &Path) -> Result<Self, String> {
+        let manifest = load_manifest_from_path(path)?;
+        let mut config = Self::default();
+        manifest.apply(path.parent().unwrap_or(Path::new("")), &mut config);
+        log::info!("Project manifest: {}", path.display());
+        Ok(config)
+    }
+}
+
+/// Value following `flag` on the command line, if present.
+fn flag_value<'a>(args: &'a [String], flag: &str) -> Option<&'a str> {
+    let index = args.iter().position(|arg| arg == flag)?;
+    args.get(index + 1).map(String::as_str)
+}
+
+/// Game code that runs every fixed step; see the module docs.
+pub trait System {
+    fn step(&mut self, ctx: &mut SystemContext);
+}
+
+/// What a `System` can see and change during a fixed step.
+pub struct SystemContext<'a> {
+    pub(crate) dt: f32,
+    pub(crate) player: &'a mut CharacterController,
+    pub(crate) collision: &'a CollisionGrid,
+    pub(crate) events: &'a mut EventBus,
+    pub(crate) scene_transition: &'a mut SceneTransition,
+}
+
+impl SystemContext<'_> {
+    /// Length of the step in seconds.
+    pub fn dt(&self) -> f32 {
+        self.dt
+    }
+
+    pub fn player(&self) -> &CharacterController {
+        self.player
+    }
+
+    /// The player's body, already moved this step.
+    pub fn player_mut(&mut self) -> &mut CharacterController {
+        self.player
+    }
+
+    pub fn collision(&self) -> &CollisionGrid {
+        self.collision
+    }
+
+    /// Queue an event for the script's `engine.events.on(name, fn)`
+    /// handlers; `payload` is the table they receive.
+    pub fn emit(&mut self, name: &str, payload: serde_json::Value) {
+        self.events
+            .emit(EngineEvent::Custom(name.to_string(), payload));
+    }
+
+    /// Move to another scene, as `engine.scene.load` does: after the fade
+    /// (if any), between two fixed steps.
+    pub fn load_scene(&mut self, request: SceneLoad) {
+        self.scene_transition.start(request);
+    }
+}
+
+/// One engine instance: a window, its simulation and renderer.
+pub struct Engine {
+    config: EngineConfig,
+    systems: Vec<Box<dyn System>>,
+}
+
+impl Engine {
+    pub fn new(config: EngineConfig) -> Self {
+        Self {
+            config,
+            systems: Vec::new(),
+        }
+    }
+
+    pub fn config(&self) -> &EngineConfig {
+        &self.config
+    }
+
+    /// Run `system` every fixed step, after the systems added before it.
+    pub fn add_system(&mut self, system: impl System + 'static) -> &mut Self {
+        self.systems.push(Box::new(system));
+        self
+    }
+
+    /// Open the window and run until it closes. Sets up logging from
+    /// `config/logging.toml` and the crash reporter (see `crash`) first,
+    /// unless [`crate::run`] already did.
+    pub fn run(self) -> Result<(), String> {
+        init_process();
+        let mut config = self.config;
+        if let Some(root) = asset_root(&config)? {
+            log::info!("Asset root: {}", root.display());
+            sme_core::assets::set_root(root)?;
+        }
+        if let Some(pack) = &config.pack {
+            mount_content_pack(pack)?;
+        }
+        let replay = if let Some(path) = &config.play_replay {
+            let replay = load_replay_from_path(path)?;
+            // The script RNG must follow the recording, whatever the config says.
+            if config.seed != EngineConfig::default().seed && config.seed != replay.seed {
+                log::warn!(
+                    "Ignoring seed {}; the replay was recorded with seed {}",
+                    config.seed,
+                    replay.seed
+                );
+            }
+            config.seed = replay.seed;
+            ReplayMode::Play(replay)
+        } else if let Some(path) = &config.record_replay {
+            ReplayMode::Record(path.clone())
+        } else {
+            ReplayMode::Live
+        };
+        let events = match &config.play_events {
+            Some(path) => Some(load_event_recording_from_path(path)?),
+            None => None,
+        };
+
+        log::info!("Saturday Morning Engine starting...");
+        let event_loop =
+            EventLoop::new().map_err(|e| format!("Failed to create event loop: {e}"))?;
+        event_loop.set_control_flow(ControlFlow::Poll);
+        let mut app = App::new(config, replay, events, self.systems);
+        let result = std::panic::catch_unwind(std::panic::AssertUnwindSafe(|| {
+            event_loop.run_app(&mut app)
+        }));
+        match result {
+            Ok(result) => result.map_err(|e| format!("Event loop error: {e}")),
+            Err(panic) => {
+                // The crash report is already written by the panic hook.
+                app.release_gpu();
+                std::panic::resume_unwind(panic);
+            }
+        }
+    }
+}
+
+/// Once per process: the iOS working directory, logging and the crash
+/// reporter.
+pub(crate) fn init_process() {
+    static INIT: Once = Once::new();
+    INIT.call_once(|| {
+        // iOS starts apps in `/`; content paths are relative to the bundle.
+        #[cfg(target_os = "ios")]
+        if let Err(e) = sme_platform::mobile::enter_bundle_dir() {
+            eprintln!("{e}");
+            std::process::exit(1);
+        }
+        sme_core::logging::init_from_file(std::path::Path::new(sme_core::logging::LOG_CONFIG_PATH));
+        crate::crash::install();
+    });
+}
+
+/// The asset root for `config`, by the precedence in the module docs.
+fn asset_root(config: &EngineConfig) -> Result<Option<PathBuf>, String> {
+    if let Some(root) = &config.asset_root {
+        return Ok(Some(root.clone()));
+    }
+    if let Some(root) = std::env::var_os(sme_core::assets::ASSET_ROOT_ENV) {
+        return Ok(Some(root.into()));
+    }
+    let Some(exe_dir) = std::env::current_exe()
+        .ok()
+        .and_then(|exe| exe.parent().map(Path::to_path_buf))
+    else {
+        return Ok(None);
+    };
+    sme_core::assets::root_from_config_file(&exe_dir.join(sme_core::assets::ENGINE_CONFIG_FILE))
+}
+
+fn mount_content_pack(pack_path: &Path) -> Result<(), String> {
+    let pack = sme_core::pack::PackArchive::open(pack_path)?;
+    log::info!(
+        "Mounted content pack '{}' ({} entries)",
+        pack_path.display(),
+        pack.len()
+    );
+    sme_core::assets::mount_pack(pack)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn args(list: &[&str]) -> Vec<String> {
+        list.iter().map(|arg| arg.to_string()).collect()
+    }
+
+    #[test]
+    fn config_from_args_overrides_defaults() {
+        assert_eq!(EngineConfig::from_args(&[]), Ok(EngineConfig::default()));
+
+        let config = EngineConfig::from_args(&args(&[
+            "--asset-root",
+            "../game",
+            "--scene",
+            "levels/one.json",
+            "--seed",
+            "42",
+            "--run-in-background",
+            "--play-replay",
+            "run.json",
+            "--play-events",
+            "overlay_toggle.json",
+            "--break-on",
+            "on_trigger_enter, Player.on_land",
+        ]))
+        .expect("valid flags");
+        assert_eq!(config.asset_root, Some(PathBuf::from("../game")));
+        assert_eq!(config.scene, PathBuf::from("levels/one.json"));
+        assert_eq!(config.collision, EngineConfig::default().collision);
+        assert_eq!(config.seed, 42);
+        assert!(config.run_in_background);
+        assert_eq!(config.play_replay, Some(PathBuf::from("run.json")));
+        assert_eq!(config.record_replay, None);
+        assert_eq!(
+            config.play_events,
+            Some(PathBuf::from("overlay_toggle.json"))
+        );
+        assert_eq!(config.break_on, ["on_trigger_enter", "Player.on_land"]);
+
+        let err = EngineConfig::from_args(&args(&["--seed", "soon"])).unwrap_err();
+        assert!(err.contains("--seed"), "{err}");
+    }
+
+    #[test]
+    fn flags_override_the_project_manifest() {
+        let dir = std::env::temp_dir().join(format!("sme_project_test_{}", std::process::id()));
+        std::fs::create_dir_all(&dir).expect("create dir");
+        let manifest = dir.join(PROJECT_FILE);
+        std::fs::write(
+            &manifest,
+            "scene = \"assets/scenes/a.json\"\nlanguage = \"es\"\n",
+        )
+        .expect("write manifest");
+
+        let project = manifest.to_string_lossy().to_string();
+        let config = EngineConfig::from_args(&args(&["--project", &project, "--lang", "en"]))
+            .expect("valid project");
+        assert_eq!(config.asset_root, Some(dir.clone()));
+        assert_eq!(config.scene, PathBuf::from("assets/scenes/a.json"));
+        assert_eq!(config.language, "en");
+
+        let missing = dir.join("missing.toml").to_string_lossy().to_string();
+        assert!(EngineConfig::from_args(&args(&["--project", &missing])).is_err());
+
+        let _ = std::fs::remove_dir_all(dir);
+    }
+}
@@ -0,0 +1,176 @@
+//! Global ambient color for time-of-day looks.
+//!
+//! The ambient color multiplies every world-space sprite in the vertex shader
+//! (`CameraUniform::ambient`), so dusk or night is a tint rather than
+//! re-authored art; screen-space layers are never tinted. It comes from, in
+//! order of precedence:
+//!  - a script override, `engine.environment.set_ambient(r, g, b)`, held until
+//!    `engine.environment.clear_ambient()`
+//!  - the scene's `day_cycle`, keyed on simulated time
+//!  - white
+//!
+//! Like sprite effects, the ambient color is presentation state: it is not
+//! part of rewind snapshots.
+
+use serde::Deserialize;
+
+const WHITE: [f32; 3] = [1.0; 3];
+
+/// One color stop of a `DayCycle`.
+#[derive(Debug, Deserialize, Clone, Copy, PartialEq)]
+pub struct DayCycleKey {
+    /// Position in the cycle, 0..1.
+    pub at: f32,
+    pub color: [f32; 3],
+}
+
+/// `{ "period": 240.0, "start": 0.25, "keys": [{ "at": 0.0, "color": [1, 1, 1] }, ...] }`:
+/// the ambient color loops through `keys` every `period` simulated seconds,
+/// blending linearly between neighbors and from the last key back to the
+/// first. `start` is where in the cycle the scene begins.
+#[derive(Debug, Deserialize, Clone, PartialEq)]
+pub struct DayCycle {
+    pub period: f32,
+    #[serde(default)]
+    pub start: f32,
+    pub keys: Vec<DayCycleKey>,
+}
+
+impl DayCycle {
+    pub fn validate(&self) -> Result<(), String> {
+        if !(self.period > 0.0 && self.period.is_finite()) {
+            return Err(format!("day_cycle period {} must be positive", self.period));
+        }
+        if !(0.0..1.0).contains(&self.start) {
+            return Err(format!(
+                "day_cycle start {} must be within 0.0..1.0",
+                self.start
+            ));
+        }
+        if self.keys.is_empty() {
+            return Err("day_cycle needs at least one key".to_string());
+        }
+        let mut previous = f32::NEG_INFINITY;
+        for key in &self.keys {
+            if !(0.0..1.0).contains(&key.at) || key.at <= previous {
+                return Err(format!(
+                    "day_cycle key at {} must be within 0.0..1.0 and after the previous key",
+                    key.at
+                ));
+            }
+            if key.color.iter().any(|c| !(*c >= 0.0 && c.is_finite())) {
+                return Err(format!(
+                    "day_cycle key at {} has a negative color {:?}",
+                    key.at, key.color
+                ));
+            }
+            previous = key.at;
+        }
+        Ok(())
+    }
+
+    /// Ambient color `seconds` of simulated time into the scene.
+    pub fn color_at(&self, seconds: f64) -> [f32; 3] {
+        let phase = (self.start as f64 + seconds / self.period as f64).rem_euclid(1.0) as f32;
+        let next = self.keys.partition_point(|key| key.at <= phase);
+        let (from, to) = match next {
+            0 => (self.keys[self.keys.len() - 1], self.keys[0]),
+            n if n == self.keys.len() => (self.keys[n - 1], self.keys[0]),
+            n => (self.keys[n - 1], self.keys[n]),
+        };
+        // Wrapping spans run past 1.0 to the first key of the next cycle.
+        let span = (to.at - from.at).rem_euclid(1.0);
+        let t = if span > 0.0 {
+            (phase - from.at).rem_euclid(1.0) / span
+        } else {
+            0.0
+        };
+        std::array::from_fn(|i| from.color[i] + (to.color[i] - from.color[i]) * t)
+    }
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct Environment {
+    /// Set by scripts; wins over the cycle.
+    ambient_override: Option<[f32; 3]>,
+}
+
+impl Environment {
+    pub fn set_ambient(&mut self, color: Option<[f32; 3]>) {
+        self.ambient_override = color;
+    }
+
+    /// Ambient color at `seconds` of simulated time under `cycle`.
+    pub fn ambient(&self, cycle: Option<&DayCycle>, seconds: f64) -> [f32; 3] {
+        self.ambient_override
+            .or_else(|| cycle.map(|cycle| cycle.color_at(seconds)))
+            .unwrap_or(WHITE)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn cycle() -> DayCycle {
+        DayCycle {
+            period: 100.0,
+            start: 0.0,
+            keys: vec![
+                DayCycleKey {
+                    at: 0.0,
+                    color: [1.0, 1.0, 1.0],
+                },
+                DayCycleKey {
+                    at: 0.5,
+                    color: [0.2, 0.2, 0.6],
+                },
+            ],
+        }
+    }
+
+    fn assert_color(actual: [f32; 3], expected: [f32; 3]) {
+        for (a, e) in actual.iter().zip(expected) {
+            assert!(
+                (a - e).abs() < 1e-4,
+                "expected {expected:?}, got {actual:?}"
+            );
+        }
+    }
+
+    #[test]
+    fn cycle_blends_between_keys_and_wraps() {
+        let cycle = cycle();
+        assert!(cycle.validate().is_ok());
+        assert_color(cycle.color_at(0.0), [1.0, 1.0, 1.0]);
+        assert_color(cycle.color_at(25.0), [0.6, 0.6, 0.8]);
+        assert_color(cycle.color_at(50.0), [0.2, 0.2, 0.6]);
+        // Past the last key it blends back toward the first.
+        assert_color(cycle.color_at(75.0), [0.6, 0.6, 0.8]);
+        assert_color(cycle.color_at(125.0), [0.6, 0.6, 0.8]);
+
+        let started = DayCycle {
+            start: 0.5,
+            ..cycle.clone()
+        };
+        assert_color(started.color_at(0.0), [0.2, 0.2, 0.6]);
+
+        let unordered = DayCycle {
+            keys: cycle.keys.iter().rev().copied().collect(),
+            ..cycle
+        };
+        assert!(unordered.validate().unwrap_err().contains("previous key"));
+    }
+
+    #[test]
+    fn override_wins_over_cycle() {
+        let cycle = cycle();
+        let mut environment = Environment::default();
+        assert_color(environment.ambient(None, 10.0), WHITE);
+        assert_color(environment.ambient(Some(&cycle), 50.0), [0.2, 0.2, 0.6]);
+        environment.set_ambient(Some([0.5, 0.4, 0.3]));
+        assert_color(environment.ambient(Some(&cycle), 50.0), [0.5, 0.4, 0.3]);
+        environment.set_ambient(None);
+        assert_color(environment.ambient(Some(&cycle), 0.0), WHITE);
+    }
+}
@@ -0,0 +1,340 @@
+//! Event recordings: the raw keyboard and mouse events of a session, by
+//! frame, for end-to-end tests of the overlay, pause/step and hotkeys.
+//!
+//! `--record-events <path>` records the main window's key, cursor, button
+//! and wheel events from startup and writes them on exit; `--play-events
+//! <path>` feeds a recording back, each event at the start of the frame it
+//! arrived before, through the same handling as live events
+//! (`EngineState::input_event`), and ignores live keyboard and mouse input
+//! until the recording ends. Unlike controller replays (`replay`), these
+//! reach everything input drives: F3 and the other hotkeys, Lua's
+//! `engine.input`, in-game UI clicks and the debug overlay's windows. A
+//! recording that ends with Escape also ends the session it plays in.
+//!
+//! Frames are not fixed steps: played on a faster or slower machine, the
+//! events arrive on the same frames but the simulation may have run more or
+//! fewer steps by then. Controller replays are the tool for simulation
+//! regressions. Touches and text entry are not recorded, and cursor
+//! positions are window pixels, so play recordings at the size they were
+//! made at.
+
+use std::collections::VecDeque;
+use std::fs;
+use std::path::Path;
+
+use serde::{Deserialize, Serialize};
+use sme_core::input::MouseBtn;
+use winit::event::{ElementState, MouseScrollDelta, WindowEvent};
+use winit::keyboard::{KeyCode, PhysicalKey};
+
+use crate::replay::ENGINE_VERSION;
+use crate::settings::BINDABLE_KEYS;
+use crate::{map_mouse_button, WHEEL_PIXELS_PER_LINE};
+
+/// Keys a recording can hold besides the bindable ones: every key the engine
+/// reacts to unbound.
+const HOTKEYS: &[KeyCode] = &[
+    KeyCode::Escape,
+    KeyCode::F1,
+    KeyCode::F2,
+    KeyCode::F3,
+    KeyCode::F4,
+    KeyCode::F5,
+    KeyCode::F6,
+    KeyCode::F7,
+    KeyCode::F8,
+    KeyCode::F9,
+    KeyCode::F10,
+    KeyCode::F11,
+    KeyCode::F12,
+];
+
+/// A keyboard or mouse event, as the engine handles it.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum InputEvent {
+    /// A physical key, by its winit `KeyCode` name (e.g. `"F3"`, `"KeyR"`).
+    Key {
+        #[serde(with = "key_name")]
+        code: KeyCode,
+        pressed: bool,
+    },
+    /// Cursor position in physical window pixels.
+    CursorMoved {
+        x: f64,
+        y: f64,
+    },
+    MouseButton {
+        button: MouseBtn,
+        pressed: bool,
+    },
+    /// Wheel notches; positive is away from the user.
+    MouseWheel {
+        lines: f32,
+    },
+}
+
+impl InputEvent {
+    /// The event `event` is, if it is one a recording can hold.
+    pub fn from_window_event(event: &WindowEvent) -> Option<Self> {
+        match event {
+            WindowEvent::KeyboardInput { event, .. } => match event.physical_key {
+                PhysicalKey::Code(code) if is_recordable(code) => Some(Self::Key {
+                    code,
+                    pressed: event.state == ElementState::Pressed,
+                }),
+                _ => None,
+            },
+            WindowEvent::CursorMoved { position, .. } => Some(Self::CursorMoved {
+                x: position.x,
+                y: position.y,
+            }),
+            WindowEvent::MouseInput { state, button, .. } => {
+                map_mouse_button(*button).map(|button| Self::MouseButton {
+                    button,
+                    pressed: *state == ElementState::Pressed,
+                })
+            }
+            WindowEvent::MouseWheel { delta, .. } => Some(Self::MouseWheel {
+                lines: match delta {
+                    MouseScrollDelta::LineDelta(_, y) => *y,
+                    MouseScrollDelta::PixelDelta(pos) => pos.y as f32 / WHEEL_PIXELS_PER_LINE,
+                },
+            }),
+            _ => None,
+        }
+    }
+
+    /// The egui event a live `WindowEvent` like this one would have given the
+    /// overlay. `cursor` is the last cursor position, in points.
+    pub(crate) fn to_egui(&self, pixels_per_point: f32, cursor: egui::Pos2) -> Option<egui::Event> {
+        match *self {
+            Self::Key { code, pressed } => {
+                let name = format!("{code:?}");
+                let name = name
+                    .strip_prefix("Key")
+                    .or_else(|| name.strip_prefix("Digit"))
+                    .unwrap_or(&name);
+                Some(egui::Event::Key {
+                    key: egui::Key::from_name(name)?,
+                    physical_key: None,
+                    pressed,
+                    repeat: false,
+                    modifiers: egui::Modifiers::default(),
+                })
+            }
+            Self::CursorMoved { x, y } => Some(egui::Event::PointerMoved(egui::pos2(
+                x as f32 / pixels_per_point,
+                y as f32 / pixels_per_point,
+            ))),
+            Self::MouseButton { button, pressed } => Some(egui::Event::PointerButton {
+                pos: cursor,
+                button: match button {
+                    MouseBtn::Left => egui::PointerButton::Primary,
+                    MouseBtn::Right => egui::PointerButton::Secondary,
+                    MouseBtn::Middle => egui::PointerButton::Middle,
+                },
+                pressed,
+                modifiers: egui::Modifiers::default(),
+            }),
+            Self::MouseWheel { lines } => Some(egui::Event::MouseWheel {
+                unit: egui::MouseWheelUnit::Line,
+                delta: egui::vec2(0.0, lines),
+                modifiers: egui::Modifiers::default(),
+            }),
+        }
+    }
+}
+
+fn is_recordable(code: KeyCode) -> bool {
+    BINDABLE_KEYS.contains(&code) || HOTKEYS.contains(&code)
+}
+
+/// `KeyCode`s by their `Debug` names, as `settings` spells bindings.
+mod key_name {
+    use serde::{Deserialize, Deserializer, Serializer};
+    use winit::keyboard::KeyCode;
+
+    use super::{BINDABLE_KEYS, HOTKEYS};
+
+    pub fn serialize<S: Serializer>(code: &KeyCode, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_str(&format!("{code:?}"))
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(deserializer: D) -> Result<KeyCode, D::Error> {
+        let name = String::deserialize(deserializer)?;
+        BINDABLE_KEYS
+            .iter()
+            .chain(HOTKEYS)
+            .copied()
+            .find(|code| format!("{code:?}") == name)
+            .ok_or_else(|| serde::de::Error::custom(format!("unknown key '{name}'")))
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct RecordedEvent {
+    /// `TimeState::frame_count` when the event arrived: it is handled
+    /// before that frame's successor runs.
+    pub frame: u64,
+    #[serde(flatten)]
+    pub event: InputEvent,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct EventRecording {
+    pub engine_version: String,
+    /// Window size in physical pixels when recording started.
+    pub window_size: (u32, u32),
+    pub events: Vec<RecordedEvent>,
+}
+
+/// Collects the input events of live play.
+#[derive(Debug, Clone)]
+pub struct EventRecorder {
+    recording: EventRecording,
+}
+
+impl EventRecorder {
+    pub fn new(window_size: (u32, u32)) -> Self {
+        Self {
+            recording: EventRecording {
+                engine_version: ENGINE_VERSION.to_string(),
+                window_size,
+                events: Vec::new(),
+            },
+        }
+    }
+
+    /// Record `event`, arrived during `frame`. Cursor moves within a frame
+    /// keep only the last position.
+    pub fn record(&mut self, frame: u64, event: InputEvent) {
+        let events = &mut self.recording.events;
+        if let (Some(last), InputEvent::CursorMoved { .. }) = (events.last_mut(), &event) {
+            if last.frame == frame && matches!(last.event, InputEvent::CursorMoved { .. }) {
+                last.event = event;
+                return;
+            }
+        }
+        events.push(RecordedEvent { frame, event });
+    }
+
+    pub fn recording(&self) -> &EventRecording {
+        &self.recording
+    }
+}
+
+/// Hands out the events of a recording as their frames come up.
+#[derive(Debug, Clone)]
+pub struct EventPlayer {
+    events: VecDeque<RecordedEvent>,
+}
+
+impl EventPlayer {
+    pub fn new(recording: EventRecording) -> Self {
+        Self {
+            events: recording.events.into(),
+        }
+    }
+
+    /// Events recorded during `frame` or earlier, oldest first.
+    pub fn due(&mut self, frame: u64) -> Vec<InputEvent> {
+        let count = self
+            .events
+            .iter()
+            .take_while(|event| event.frame <= frame)
+            .count();
+        self.events
+            .drain(..count)
+            .map(|recorded| recorded.event)
+            .collect()
+    }
+
+    pub fn is_finished(&self) -> bool {
+        self.events.is_empty()
+    }
+}
+
+pub fn load_event_recording_from_path(path: &Path) -> Result<EventRecording, String> {
+    let raw =
+        fs::read_to_string(path).map_err(|e| format!("Failed to read {}: {e}", path.display()))?;
+    serde_json::from_str(&raw)
+        .map_err(|e| format!("Failed to parse event recording {}: {e}", path.display()))
+}
+
+pub fn save_event_recording(path: &Path, recording: &EventRecording) -> Result<(), String> {
+    let json = serde_json::to_string_pretty(recording)
+        .map_err(|e| format!("Failed to serialize event recording: {e}"))?;
+    fs::write(path, json).map_err(|e| format!("Failed to write {}: {e}", path.display()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn recordings_round_trip_and_play_back_by_frame() {
+        let mut recorder = EventRecorder::new((1280, 720));
+        let key = |code, pressed| InputEvent::Key { code, pressed };
+        recorder.record(3, key(KeyCode::F3, true));
+        recorder.record(3, InputEvent::CursorMoved { x: 10.0, y: 20.0 });
+        recorder.record(3, InputEvent::CursorMoved { x: 12.0, y: 24.0 });
+        recorder.record(5, key(KeyCode::F3, false));
+        recorder.record(
+            9,
+            InputEvent::MouseButton {
+                button: MouseBtn::Left,
+                pressed: true,
+            },
+        );
+
+        let json = serde_json::to_string(recorder.recording()).expect("serialize");
+        assert!(json.contains(r#"{"frame":3,"kind":"key","code":"F3","pressed":true}"#));
+        let recording: EventRecording = serde_json::from_str(&json).expect("parse");
+        assert_eq!(&recording, recorder.recording());
+        assert_eq!(recording.events.len(), 4, "cursor moves in a frame merge");
+
+        let mut player = EventPlayer::new(recording);
+        assert!(player.due(2).is_empty());
+        assert_eq!(
+            player.due(4),
+            vec![
+                key(KeyCode::F3, true),
+                InputEvent::CursorMoved { x: 12.0, y: 24.0 }
+            ]
+        );
+        assert_eq!(player.due(8), vec![key(KeyCode::F3, false)]);
+        assert!(!player.is_finished());
+        assert_eq!(player.due(20).len(), 1);
+        assert!(player.is_finished());
+    }
+
+    #[test]
+    fn unknown_keys_are_rejected() {
+        let err = serde_json::from_str::<RecordedEvent>(
+            r#"{ "frame": 0, "kind": "key", "code": "Hyper", "pressed": true }"#,
+        )
+        .unwrap_err();
+        assert!(err.to_string().contains("unknown key 'Hyper'"), "{err}");
+    }
+
+    #[test]
+    fn replayed_events_reach_egui_in_points() {
+        let moved = InputEvent::CursorMoved { x: 200.0, y: 100.0 };
+        assert_eq!(
+            moved.to_egui(2.0, egui::Pos2::ZERO),
+            Some(egui::Event::PointerMoved(egui::pos2(100.0, 50.0)))
+        );
+        let key = InputEvent::Key {
+            code: KeyCode::KeyR,
+            pressed: true,
+        };
+        assert!(matches!(
+            key.to_egui(1.0, egui::Pos2::ZERO),
+            Some(egui::Event::Key {
+                key: egui::Key::R,
+                ..
+            })
+        ));
+    }
+}
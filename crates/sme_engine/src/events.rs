@@ -0,0 +1,200 @@
+//! Engine event bus shared by Rust systems and Lua.
+//!
+//! Systems push `EngineEvent`s onto the `EventBus` while a fixed step runs:
+//! the controller when a body starts touching a wall, floor or ceiling or
+//! moves into or out of a fluid volume, the player when it crouches, stands or dashes, the projectile pool on impact, pickups when they are collected, and animations
+//! when a non-looping clip ends. Scripts add their own with
+//! `engine.events.emit(name, table)`. At the end of the step the queue is
+//! drained in emit order and every event is handed to the Lua handlers
+//! registered with `engine.events.on(name, fn)`, so gameplay, audio, particles
+//! and UI react to the same events without calling each other. Events emitted
+//! by a handler are queued for the next step, which keeps a handler that
+//! re-emits its own event from looping forever.
+
+use glam::Vec2;
+use serde_json::{json, Value};
+
+use crate::controller::ContactState;
+
+/// Events queued past this in one step are dropped with a warning.
+pub const MAX_EVENTS_PER_STEP: usize = 1024;
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum EngineEvent {
+    /// `actor` started touching `other` (`"world"` or an actor id);
+    /// `normal` points away from what was hit.
+    Collision {
+        actor: String,
+        other: String,
+        normal: Vec2,
+    },
+    /// `actor` entered the trigger `trigger`, e.g. collected a pickup.
+    Trigger { actor: String, trigger: String },
+    /// A non-looping clip reached its last frame.
+    AnimationFinished { sprite: String, clip: String },
+    /// `actor` crouched (`crouching`) or stood back up, e.g. to switch its
+    /// animation.
+    Crouch { actor: String, crouching: bool },
+    /// `actor` started a dash; `direction` is -1.0 (left) or 1.0 (right),
+    /// e.g. to start a trail or a whoosh.
+    Dash { actor: String, direction: f32 },
+    /// `actor`'s center moved into (`entered`) or out of the fluid volume
+    /// `fluid`; `(x, y)` is on the volume's surface above it, and
+    /// `velocity_y` is the body's vertical speed, for sizing a splash.
+    Fluid {
+        actor: String,
+        fluid: String,
+        entered: bool,
+        x: f32,
+        y: f32,
+        velocity_y: f32,
+    },
+    /// Anything else, by name; scripts emit these with `engine.events.emit`.
+    Custom(String, Value),
+}
+
+impl EngineEvent {
+    /// Name Lua handlers subscribe to.
+    pub fn name(&self) -> &str {
+        match self {
+            Self::Collision { .. } => "collision",
+            Self::Trigger { .. } => "trigger",
+            Self::AnimationFinished { .. } => "animation_finished",
+            Self::Crouch {
+                crouching: true, ..
+            } => "crouch",
+            Self::Crouch {
+                crouching: false, ..
+            } => "stand",
+            Self::Dash { .. } => "dash",
+            Self::Fluid { entered: true, .. } => "fluid_enter",
+            Self::Fluid { entered: false, .. } => "fluid_exit",
+            Self::Custom(name, _) => name,
+        }
+    }
+
+    /// The table handlers receive.
+    pub fn payload(&self) -> Value {
+        match self {
+            Self::Collision {
+                actor,
+                other,
+                normal,
+            } => json!({
+                "actor": actor,
+                "other": other,
+                "normal_x": normal.x,
+                "normal_y": normal.y,
+            }),
+            Self::Trigger { actor, trigger } => json!({ "actor": actor, "trigger": trigger }),
+            Self::AnimationFinished { sprite, clip } => json!({ "sprite": sprite, "clip": clip }),
+            Self::Crouch { actor, .. } => json!({ "actor": actor }),
+            Self::Dash { actor, direction } => json!({ "actor": actor, "direction": direction }),
+            Self::Fluid {
+                actor,
+                fluid,
+                x,
+                y,
+                velocity_y,
+                ..
+            } => json!({
+                "actor": actor,
+                "fluid": fluid,
+                "x": x,
+                "y": y,
+                "velocity_y": velocity_y,
+            }),
+            Self::Custom(_, payload) => payload.clone(),
+        }
+    }
+}
+
+/// Collision events for the contact edges `actor` gained this step.
+pub fn contact_events(
+    actor: &str,
+    before: ContactState,
+    after: ContactState,
+) -> impl Iterator<Item = EngineEvent> + '_ {
+    [
+        (before.left, after.left, Vec2::X),
+        (before.right, after.right, Vec2::NEG_X),
+        (before.down, after.down, Vec2::Y),
+        (before.up, after.up, Vec2::NEG_Y),
+    ]
+    .into_iter()
+    .filter(|(was, is, _)| !was && *is)
+    .map(move |(_, _, normal)| EngineEvent::Collision {
+        actor: actor.to_string(),
+        other: "world".to_string(),
+        normal,
+    })
+}
+
+/// Events waiting for the end of the current fixed step.
+#[derive(Debug, Clone, Default)]
+pub struct EventBus {
+    queue: Vec<EngineEvent>,
+}
+
+impl EventBus {
+    pub fn emit(&mut self, event: EngineEvent) {
+        if self.queue.len() >= MAX_EVENTS_PER_STEP {
+            log::warn!(
+                "Event '{}' dropped: more than {MAX_EVENTS_PER_STEP} events this step",
+                event.name()
+            );
+            return;
+        }
+        self.queue.push(event);
+    }
+
+    /// Take every queued event, oldest first.
+    pub fn drain(&mut self) -> Vec<EngineEvent> {
+        std::mem::take(&mut self.queue)
+    }
+
+    pub fn clear(&mut self) {
+        self.queue.clear();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn only_new_contacts_become_collisions() {
+        let before = ContactState {
+            down: true,
+            ..ContactState::default()
+        };
+        let after = ContactState {
+            down: true,
+            right: true,
+            ..ContactState::default()
+        };
+        let events: Vec<_> = contact_events("player", before, after).collect();
+        assert_eq!(
+            events,
+            [EngineEvent::Collision {
+                actor: "player".to_string(),
+                other: "world".to_string(),
+                normal: Vec2::NEG_X,
+            }]
+        );
+        assert_eq!(events[0].name(), "collision");
+        assert_eq!(events[0].payload()["normal_x"], json!(-1.0));
+    }
+
+    #[test]
+    fn bus_drains_in_order_and_caps_each_step() {
+        let mut bus = EventBus::default();
+        for i in 0..MAX_EVENTS_PER_STEP + 5 {
+            bus.emit(EngineEvent::Custom("tick".to_string(), json!(i)));
+        }
+        let drained = bus.drain();
+        assert_eq!(drained.len(), MAX_EVENTS_PER_STEP);
+        assert_eq!(drained[3].payload(), json!(3));
+        assert!(bus.drain().is_empty());
+    }
+}
@@ -0,0 +1,345 @@
+//! Item definitions, world pickups and per-actor inventories.
+//!
+//! An item file lists item definitions: an id, the atlas `sprite_id` that
+//! shows it, how many fit in one inventory stack, and an optional `on_pickup`
+//! Lua hook. A scene sprite with a `pickup` block is a trigger: when the
+//! player's box overlaps it, its items go into the player's inventory, the
+//! sprite disappears, and the item's hook runs as
+//! `fn(actor_id, item_id, count, pickup_id)`. A pickup whose items do not all
+//! fit stays in the world.
+//!
+//! `Inventories` keeps one `Inventory` per actor id (`"player"` or a spawned
+//! actor), each a list of at most `INVENTORY_SLOTS` stacks. Scripts query and
+//! change them through `engine.inventory`.
+
+use std::collections::{BTreeMap, HashMap, HashSet};
+use std::path::{Path, PathBuf};
+
+use serde::Deserialize;
+use sme_core::migrate::SchemaMigrations;
+
+/// Item schema versions and the upgrade path to the current one.
+pub const ITEM_SCHEMA: SchemaMigrations = SchemaMigrations {
+    kind: "Items",
+    current: "0.1",
+    steps: &[],
+};
+
+/// Stacks one actor can hold.
+pub const INVENTORY_SLOTS: usize = 24;
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct ItemFile {
+    #[allow(dead_code)]
+    pub version: String,
+    pub items: Vec<ItemDef>,
+}
+
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+pub struct ItemDef {
+    pub id: String,
+    #[serde(default)]
+    pub sprite_id: Option<String>,
+    /// Most of this item one inventory slot holds.
+    #[serde(default = "default_stack_size")]
+    pub stack_size: u32,
+    /// Global Lua function called as `fn(actor_id, item_id, count, pickup_id)`.
+    #[serde(default)]
+    pub on_pickup: Option<String>,
+}
+
+fn default_stack_size() -> u32 {
+    1
+}
+
+/// `{ "item": "soul_coin", "count": 3 }` on a scene sprite.
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+pub struct ScenePickup {
+    pub item: String,
+    #[serde(default = "default_pickup_count")]
+    pub count: u32,
+}
+
+fn default_pickup_count() -> u32 {
+    1
+}
+
+impl ScenePickup {
+    pub fn validate(&self) -> Result<(), String> {
+        if self.item.is_empty() {
+            return Err("pickup item is empty".to_string());
+        }
+        if self.count == 0 {
+            return Err(format!("pickup of '{}' has count 0", self.item));
+        }
+        Ok(())
+    }
+}
+
+pub fn load_items_from_path(path: &Path) -> Result<ItemFile, String> {
+    let raw = sme_core::assets::read_to_string(path)
+        .map_err(|e| format!("Failed to read items {}: {e}", path.display()))?;
+    let mut value: serde_json::Value = serde_json::from_str(&raw)
+        .map_err(|e| format!("Failed to parse items {}: {e}", path.display()))?;
+    ITEM_SCHEMA.migrate_with_warning(&mut value, &path.display().to_string())?;
+    let file: ItemFile = serde_json::from_value(value)
+        .map_err(|e| format!("Failed to parse items {}: {e}", path.display()))?;
+    validate_items(&file)?;
+    Ok(file)
+}
+
+fn validate_items(file: &ItemFile) -> Result<(), String> {
+    let mut ids = HashSet::new();
+    for item in &file.items {
+        if item.id.is_empty() {
+            return Err("Item validation failed: item id is empty".to_string());
+        }
+        if !ids.insert(item.id.as_str()) {
+            return Err(format!(
+                "Item validation failed: duplicate item id '{}'",
+                item.id
+            ));
+        }
+        if item.stack_size == 0 {
+            return Err(format!(
+                "Item validation failed: item '{}' has stack_size 0",
+                item.id
+            ));
+        }
+    }
+    Ok(())
+}
+
+/// Loaded item definitions keyed by id.
+#[derive(Debug, Default)]
+pub struct ItemRegistry {
+    items: HashMap<String, ItemDef>,
+    /// Ids each file defined, so a reload drops items it no longer lists.
+    files: HashMap<PathBuf, Vec<String>>,
+}
+
+impl ItemRegistry {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Load (or on hot reload, replace) an item file. Returns how many items
+    /// it defines.
+    pub fn load_file(&mut self, path: &Path) -> Result<usize, String> {
+        let file = load_items_from_path(path)?;
+        let ids: Vec<String> = file.items.iter().map(|item| item.id.clone()).collect();
+        if let Some(clash) = ids.iter().find(|id| {
+            self.files
+                .iter()
+                .any(|(other, other_ids)| other != path && other_ids.contains(id))
+        }) {
+            return Err(format!(
+                "Item validation failed: item '{clash}' is already defined by another file"
+            ));
+        }
+        for old in self.files.remove(path).unwrap_or_default() {
+            self.items.remove(&old);
+        }
+        for item in file.items {
+            self.items.insert(item.id.clone(), item);
+        }
+        let count = ids.len();
+        self.files.insert(path.to_path_buf(), ids);
+        Ok(count)
+    }
+
+    pub fn get(&self, item_id: &str) -> Option<&ItemDef> {
+        self.items.get(item_id)
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ItemStack {
+    pub item: String,
+    pub count: u32,
+}
+
+/// One actor's stacks, in the order they were filled.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Inventory {
+    stacks: Vec<ItemStack>,
+}
+
+impl Inventory {
+    /// How many of `item` could still be added.
+    pub fn room_for(&self, item: &ItemDef) -> u32 {
+        let in_stacks: u32 = self
+            .stacks
+            .iter()
+            .filter(|stack| stack.item == item.id)
+            .map(|stack| item.stack_size - stack.count)
+            .sum();
+        let free_slots = INVENTORY_SLOTS.saturating_sub(self.stacks.len()) as u32;
+        in_stacks.saturating_add(free_slots.saturating_mul(item.stack_size))
+    }
+
+    /// Add up to `count`, topping up existing stacks before opening new
+    /// ones. Returns how many were added.
+    pub fn add(&mut self, item: &ItemDef, count: u32) -> u32 {
+        let mut left = count;
+        for stack in self.stacks.iter_mut().filter(|stack| stack.item == item.id) {
+            let moved = left.min(item.stack_size - stack.count);
+            stack.count += moved;
+            left -= moved;
+        }
+        while left > 0 && self.stacks.len() < INVENTORY_SLOTS {
+            let moved = left.min(item.stack_size);
+            self.stacks.push(ItemStack {
+                item: item.id.clone(),
+                count: moved,
+            });
+            left -= moved;
+        }
+        count - left
+    }
+
+    /// Remove up to `count` of `item`, newest stacks first. Returns how many
+    /// were removed.
+    pub fn remove(&mut self, item_id: &str, count: u32) -> u32 {
+        let mut left = count;
+        for stack in self.stacks.iter_mut().rev() {
+            if left == 0 {
+                break;
+            }
+            if stack.item == item_id {
+                let moved = left.min(stack.count);
+                stack.count -= moved;
+                left -= moved;
+            }
+        }
+        self.stacks.retain(|stack| stack.count > 0);
+        count - left
+    }
+
+    pub fn count(&self, item_id: &str) -> u32 {
+        self.stacks
+            .iter()
+            .filter(|stack| stack.item == item_id)
+            .map(|stack| stack.count)
+            .sum()
+    }
+
+    pub fn stacks(&self) -> &[ItemStack] {
+        &self.stacks
+    }
+}
+
+/// Every actor's inventory, keyed by actor id.
+#[derive(Debug, Clone, Default)]
+pub struct Inventories {
+    actors: BTreeMap<String, Inventory>,
+}
+
+impl Inventories {
+    pub fn get(&self, actor_id: &str) -> Option<&Inventory> {
+        self.actors.get(actor_id)
+    }
+
+    pub fn get_mut(&mut self, actor_id: &str) -> &mut Inventory {
+        self.actors.entry(actor_id.to_string()).or_default()
+    }
+
+    pub fn count(&self, actor_id: &str, item_id: &str) -> u32 {
+        self.get(actor_id)
+            .map_or(0, |inventory| inventory.count(item_id))
+    }
+
+    pub fn clear(&mut self) {
+        self.actors.clear();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::fs;
+    use std::time::{SystemTime, UNIX_EPOCH};
+
+    fn temp_file_path(name_hint: &str) -> PathBuf {
+        let nanos = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .expect("system time before unix epoch")
+            .as_nanos();
+        std::env::temp_dir().join(format!(
+            "sme_items_test_{}_{}_{}.json",
+            name_hint,
+            std::process::id(),
+            nanos
+        ))
+    }
+
+    fn coin() -> ItemDef {
+        ItemDef {
+            id: "coin".to_string(),
+            sprite_id: None,
+            stack_size: 10,
+            on_pickup: None,
+        }
+    }
+
+    #[test]
+    fn stacks_fill_before_new_slots_open() {
+        let coin = coin();
+        let mut inventory = Inventory::default();
+        assert_eq!(inventory.add(&coin, 25), 25);
+        let counts: Vec<u32> = inventory.stacks().iter().map(|s| s.count).collect();
+        assert_eq!(counts, [10, 10, 5]);
+
+        assert_eq!(inventory.remove("coin", 7), 7);
+        assert_eq!(inventory.count("coin"), 18);
+        assert_eq!(inventory.add(&coin, 2), 2);
+        let counts: Vec<u32> = inventory.stacks().iter().map(|s| s.count).collect();
+        assert_eq!(counts, [10, 10]);
+        assert_eq!(inventory.remove("coin", 50), 20);
+        assert!(inventory.stacks().is_empty());
+    }
+
+    #[test]
+    fn full_inventories_take_what_fits() {
+        let coin = coin();
+        let mut inventory = Inventory::default();
+        let capacity = INVENTORY_SLOTS as u32 * coin.stack_size;
+        assert_eq!(inventory.room_for(&coin), capacity);
+        assert_eq!(inventory.add(&coin, capacity - 3), capacity - 3);
+        assert_eq!(inventory.room_for(&coin), 3);
+        assert_eq!(inventory.add(&coin, 5), 3);
+        assert_eq!(inventory.room_for(&coin), 0);
+    }
+
+    #[test]
+    fn reload_replaces_a_files_items() {
+        let path = temp_file_path("reload");
+        fs::write(
+            &path,
+            r#"{ "version": "0.1", "items": [
+                { "id": "coin", "stack_size": 99, "on_pickup": "on_coin" },
+                { "id": "key" } ] }"#,
+        )
+        .expect("write temp file");
+        let mut registry = ItemRegistry::new();
+        assert_eq!(registry.load_file(&path), Ok(2));
+        assert_eq!(registry.get("key").map(|item| item.stack_size), Some(1));
+
+        fs::write(
+            &path,
+            r#"{ "version": "0.1", "items": [{ "id": "coin" }] }"#,
+        )
+        .expect("rewrite temp file");
+        assert_eq!(registry.load_file(&path), Ok(1));
+        assert!(registry.get("key").is_none());
+
+        fs::write(
+            &path,
+            r#"{ "version": "0.1", "items": [{ "id": "a" }, { "id": "a" }] }"#,
+        )
+        .expect("rewrite temp file");
+        let err = registry.load_file(&path).expect_err("duplicate ids");
+        assert!(err.contains("duplicate item id 'a'"), "{err}");
+        let _ = fs::remove_file(path);
+    }
+}
@@ -0,0 +1,5567 @@
+//! Saturday Morning Engine -- main loop and application entry points.
+//!
+//! Architecture: winit drives the event loop via `ApplicationHandler`. All simulation
+//! runs inside `RedrawRequested` using a **fixed-timestep** model (see `TimeState`):
+//!
+//!   1. `begin_frame()` -- measure wall-clock delta, feed accumulator
+//!   2. `while should_step()` -- consume fixed-dt slices for deterministic simulation
+//!   3. Rebuild the sprite mesh from scene + debug overlays
+//!   4. Run the egui overlay and hand a `RenderFrame` snapshot to the render thread,
+//!      which uploads uniforms and the mesh, issues draw calls, composites egui and
+//!      presents (see `render_thread`)
+//!
+//! The engine uses a **Lua-first, Rust-fallback** controller pattern: each fixed step
+//! asks Lua for a movement intent; if Lua is unavailable (no script, parse error, etc.)
+//! an identical Rust controller takes over seamlessly.
+//!
+//! Hot reload: scene JSON, collision JSON, atlas metadata, string tables, UI layouts, and Lua
+//! scripts are all watched via mtime polling and reloaded at frame boundaries (between fixed steps).
+//!
+//! Scene layers marked `space: "screen"` and the in-game UI (see `ui`) are appended to the
+//! same sprite mesh after the world and drawn under a second, screen-space camera that ignores
+//! camera movement; egui is reserved for the debug overlay. Animations on those layers
+//! and the overlay run on `TimeState`'s UI clock, so they keep moving while the simulation
+//! is paused (see `world`).
+//!
+//! Shipping builds pass `--pack <content.smepack>` to serve assets from a packed
+//! bundle (see `sme_core::pack`); pack contents are immutable, so nothing in the
+//! pack hot reloads.
+//!
+//! `--scene <path>` and `--collision <path>` load another scene and collision
+//! grid in place of the demo ones, e.g. a stress scene from `sme_stress`.
+//! `--asset-root <dir>` resolves asset paths against another directory than
+//! the working one (see `engine`).
+//!
+//! `--seed <u64>` fixes the seed of the script RNG (`engine.rand`) so a session can
+//! be reproduced. `--record-replay <path>` saves the session's controller inputs
+//! on exit, and `--play-replay <path>` plays such a file back (see `replay`).
+//! `--record-events <path>` and `--play-events <path>` do the same for raw
+//! keyboard and mouse events, hotkeys included (see `event_record`).
+//! `--break-on <name,...>` pauses after any step that entered one of the
+//! named Lua functions and shows the calls in the overlay console (see
+//! `lua_bridge`).
+//!
+//! Scripts spawn and despawn actors at runtime with `engine.spawn` /
+//! `engine.despawn` (see `actors`); spawned actors are simple physics bodies
+//! drawn with their template sprite. `engine.projectile.spawn` fires pooled
+//! projectiles instead (see `projectiles`): no body or animation, one raycast
+//! and one quad each per step.
+//!
+//! Item definitions load from `assets/items/` (see `items`). Scene sprites with
+//! a `pickup` block are collected into the player's inventory on touch, after
+//! every body and projectile has stepped.
+//!
+//! The player's body and all sprite animations live in a small entity/component
+//! store (see `world`); one fixed step runs the systems in
+//! `world::FIXED_STEP_ORDER`, one after another.
+//!
+//! Systems report what happened during a step as `EngineEvent`s (see `events`);
+//! the step ends by draining the bus into the script's `engine.events.on`
+//! handlers.
+//!
+//! The last ten seconds of fixed steps are kept as snapshots (see `rewind`);
+//! while paused, the F3 overlay steps back and forth through them, and
+//! stepping or resuming from a rewound point simulates forward from there.
+//!
+//! While the window is unfocused the `BackgroundPolicy` pauses the simulation,
+//! mutes audio and throttles redraws to 10 Hz; `--run-in-background` turns all
+//! of that off.
+//!
+//! `thumbnail` renders a scene, sprite or animation frame to an image with
+//! no window, for tools.
+//!
+//! `script_test` runs a gameplay script against scripted input and expected
+//! intents with no window or scene (the `sme_script_test` tool).
+//!
+//! A panic in [`run`] writes a crash report (see `crash`) and releases the GPU
+//! surface before the panic continues.
+//!
+//! Entry points: the desktop and iOS binary calls [`run`]; the Android shell
+//! crate (`sme_android`) calls [`run_android`] from `android_main`. On mobile the
+//! surface is dropped on `suspended` and recreated on the next `resumed`; all
+//! other engine state survives backgrounding.
+
+mod actors;
+mod alloc_stats;
+mod animation;
+mod atlas;
+#[doc(hidden)]
+pub mod bench;
+pub mod collision;
+pub mod controller;
+mod crash;
+mod debug_draw;
+mod deps;
+pub mod determinism;
+mod dialog;
+mod engine;
+mod environment;
+pub mod event_record;
+mod events;
+mod items;
+mod jobs;
+mod lua_bridge;
+mod migrate_cli;
+mod particles;
+mod project;
+mod projectiles;
+mod render_thread;
+pub mod replay;
+mod rewind;
+mod save;
+mod scene;
+pub mod scene_stats;
+pub mod script_test;
+mod settings;
+mod stepping;
+pub mod thumbnail;
+mod tiles;
+mod trail;
+mod transition;
+mod tween;
+mod ui;
+mod ui_font;
+pub mod units;
+mod weather;
+mod world;
+
+use std::collections::{BTreeSet, HashMap, HashSet};
+use std::sync::Arc;
+
+pub use engine::{Engine, EngineConfig, System, SystemContext};
+pub use transition::SceneLoad;
+
+use winit::application::ApplicationHandler;
+use winit::event::{TouchPhase, WindowEvent};
+use winit::event_loop::{ActiveEventLoop, ControlFlow};
+use winit::keyboard::KeyCode;
+use winit::window::{Window, WindowId};
+
+use actors::{atlas_template, find_template, ActorCommand, SpawnedActors};
+use animation::AnimationRegistry;
+use atlas::{
+    atlas_source_hash, load_atlas_from_path, quad_tex_coords, AtlasRegistry, AtlasSpriteEntry,
+    MultiAtlasRegistry, SecondaryKind,
+};
+use collision::{load_collision_in_units, Aabb, CollisionGrid, SurfaceMaterial};
+use controller::{
+    load_controller_tuning_from_path, CharacterController, ContactState, ControllerInput,
+    ControllerTuning,
+};
+use debug_draw::{segment_quad, DebugDraw};
+use deps::{AssetGraph, AssetKind};
+use determinism::StateMap;
+use dialog::{ActiveDialog, DialogRegistry};
+use environment::Environment;
+use event_record::{save_event_recording, EventPlayer, EventRecorder, EventRecording, InputEvent};
+use events::{contact_events, EngineEvent};
+use items::ScenePickup;
+use lua_bridge::{
+    ActorSnapshot, BreakpointHit, InputSnapshot, LuaBridge, LuaStatus, ScriptDebugValue,
+};
+use particles::{ParticleSystem, SPLASH_GRAVITY};
+use projectiles::{CollisionMask, HitTarget, ProjectileTarget};
+use render_thread::{FrameDraw, FrameMesh, RenderFrame, RenderThread};
+use replay::{save_replay_to_path, ReplayHeader, ReplayRecorder, ReplaySequence, ENGINE_VERSION};
+use rewind::{RewindBuffer, RewindStep, WorldSnapshot, REWIND_CAPACITY};
+use scene::{
+    load_scene_from_path, save_layer_settings, LayerFilter, LayerGroups, LayerSpace,
+    SceneBackground, SceneFile, SceneWatcher, SortMode,
+};
+use settings::{KeyBindings, Settings, SettingsStore};
+use sme_core::animation::AnimationState;
+use sme_core::input::{InputState, Key, MouseBtn, TouchControls};
+use sme_core::interned::{Id, InternedMap};
+use sme_core::loc::{load_string_table, Localization};
+use sme_core::tier::FidelityTier;
+use sme_core::time::TimeState;
+use sme_devtools::{
+    AssetReload, AssetView, BudgetWarnings, ConsoleLineView, DebugOverlay, DialogView, Inspector,
+    LayerGroupView, LayerStatsView, LayerView, LuaErrorView, MemoryStats, OverlayStats,
+    ReloadErrors, ScriptWatches, StepUntil, WorldLabel,
+};
+use sme_platform::window::{BackgroundPolicy, PlatformConfig};
+use sme_render::sprite_pipeline::{SPRITE_EFFECT_SHADER_PATH, SPRITE_SHADER_PATH};
+use sme_render::{
+    AlphaMode, Camera2D, CameraClamp, CameraConstraints, CameraShake, ColorLut, GpuContext,
+    MaterialUniform, PostProcess, SamplerDesc, SamplerFilter, SpriteEffect, SpritePipeline,
+    SpriteVertex, Texels, Texture, TextureBinding,
+};
+use stepping::Stepping;
+use tiles::{load_tileset_from_path, TileStreamer};
+use trail::{Trail, TrailConfig, TrailSample, MAX_TRAIL_LENGTH};
+use transition::SceneTransition;
+use tween::{SpriteProps, Tweens};
+use ui::{load_ui_from_path, UiLayer};
+use weather::AmbientEmitter;
+use world::{FixedSystem, World, FIXED_STEP_ORDER};
+
+const LEGACY_ATLAS_PATH: &str = "assets/generated/m4_sample_atlas.json";
+const STRICT_SPRITE_ID_RESOLUTION: bool = true;
+const FIXED_DT_US: u64 = 16_667;
+const FALLBACK_TEXTURE_BYTES: &[u8] = include_bytes!("../../../assets/textures/test_sprite.png");
+const DEBUG_WHITE_ASSET: &str = "__debug_white";
+const PLAYER_ASSET: &str = "__player";
+/// Trail id addressing the player quad, which is not a scene sprite.
+const PLAYER_TRAIL_ID: &str = "player";
+const FULL_TEX_COORDS: [[f32; 2]; 4] = quad_tex_coords([0.0, 0.0, 1.0, 1.0], false);
+/// Debug-draw line width in screen pixels, whatever the zoom.
+const DEBUG_LINE_PX: f32 = 2.0;
+// F4 collision overlay.
+const SOLID_CELL_COLOR: [f32; 4] = [0.15, 0.9, 0.15, 0.35];
+const GRID_LINE_COLOR: [f32; 4] = [0.15, 0.9, 0.15, 0.25];
+const GRID_LINE_PX: f32 = 1.0;
+const TOUCHED_CELL_COLOR: [f32; 4] = [1.0, 0.85, 0.2, 0.55];
+const ICE_CELL_COLOR: [f32; 4] = [0.6, 0.95, 1.0, 0.45];
+const CONVEYOR_CELL_COLOR: [f32; 4] = [0.95, 0.5, 0.15, 0.45];
+/// Fill of fluid volumes in the collision overlay.
+const FLUID_CELL_COLOR: [f32; 4] = [0.2, 0.45, 1.0, 0.3];
+/// Fill of slow-motion time volumes in the collision overlay.
+const TIME_VOLUME_COLOR: [f32; 4] = [0.75, 0.35, 1.0, 0.25];
+/// How close a solid cell must be to the player's AABB to count as touched.
+const TOUCH_SKIN: f32 = 0.5;
+const CONTACT_LEFT_COLOR: [f32; 4] = [1.0, 0.5, 0.1, 1.0];
+const CONTACT_RIGHT_COLOR: [f32; 4] = [0.9, 0.2, 0.9, 1.0];
+const CONTACT_UP_COLOR: [f32; 4] = [1.0, 0.2, 0.2, 1.0];
+const CONTACT_DOWN_COLOR: [f32; 4] = [0.2, 0.9, 1.0, 1.0];
+const VELOCITY_COLOR: [f32; 4] = [1.0, 1.0, 1.0, 0.9];
+/// The velocity arrow shows where the player would be this far ahead.
+const VELOCITY_ARROW_SECONDS: f32 = 0.25;
+const VELOCITY_ARROW_HEAD_PX: f32 = 8.0;
+/// Free-cam zoom multiplier per wheel notch.
+const FREE_CAMERA_ZOOM_STEP: f32 = 1.1;
+const FREE_CAMERA_MIN_ZOOM: f32 = 0.05;
+const FREE_CAMERA_MAX_ZOOM: f32 = 32.0;
+/// Touchpad scroll pixels counted as one wheel notch.
+const WHEEL_PIXELS_PER_LINE: f32 = 40.0;
+/// Impacts slower than this (a normal jump landing) don't shake the camera.
+const HARD_IMPACT_SPEED: f32 = 700.0;
+/// Trauma added per unit of impact speed above `HARD_IMPACT_SPEED`.
+const IMPACT_TRAUMA_PER_SPEED: f32 = 1.0 / 400.0;
+/// Body half-extent for a spawned actor whose sprite size is unknown.
+const SPAWN_FALLBACK_HALF_SIZE: f32 = 8.0;
+/// Base seed of the ambient weather layers' random streams.
+const AMBIENT_SEED: u64 = 0x0A3B_1E47;
+/// Splash droplets alive at once; further splashes are cut short.
+const SPLASH_CAPACITY: usize = 512;
+
+/// Tint of projectiles drawn without an atlas sprite.
+const PROJECTILE_FALLBACK_COLOR: [f32; 4] = [1.0, 0.9, 0.4, 1.0];
+
+/// Sprites per mesh job; longer layers are split into several jobs.
+const SPRITES_PER_MESH_JOB: usize = 4096;
+/// Scenes with fewer sprites build their mesh on the main thread.
+const PARALLEL_MESH_MIN_SPRITES: usize = 8192;
+
+/// What the systems of one fixed step hand to the ones after them.
+struct StepContext {
+    dt: f32,
+    /// Set by `FixedSystem::Script`.
+    input: Option<ControllerInput>,
+    /// Events the step raised, kept by `FixedSystem::Events` after dispatch.
+    events: Vec<EngineEvent>,
+    /// Contacts before any body moved, for `FixedSystem::Contacts`.
+    contacts_before: Vec<ContactState>,
+    /// Fluid volume around each body before any moved, likewise.
+    fluids_before: Vec<Option<String>>,
+}
+
+/// A contiguous run of indices that share the same texture binding.
+/// Draw calls are merged when consecutive quads use the same texture,
+/// minimizing GPU bind-group switches during the render pass.
+#[derive(Debug, Clone)]
+struct DrawCall {
+    texture_key: Id,
+    index_start: u32,
+    index_count: u32,
+    pipeline: DrawPipeline,
+    sampler: SamplerDesc,
+}
+
+/// Which pipeline a draw call needs; batches never span two.
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum DrawPipeline {
+    Plain,
+    /// `SpritePipeline::effect_pipeline`, for sprites with a `SpriteEffect`.
+    Effects,
+    /// A layer material, keyed by snippet path.
+    Material(Arc<str>),
+}
+
+/// A layer material snippet and its compiled pipeline. A failed reload keeps
+/// the last good pipeline; `None` (never compiled) draws with plain shading.
+struct LayerMaterial {
+    watcher: SceneWatcher,
+    pipeline: Option<wgpu::RenderPipeline>,
+}
+
+/// The scene's `color_lut` file. A failed reload keeps the last good LUT.
+struct ColorLutSource {
+    path: String,
+    watcher: SceneWatcher,
+}
+
+/// A watched file the Assets window can reload on its own.
+#[derive(Debug, Clone, PartialEq)]
+enum WatchedAsset {
+    Scene,
+    Collision,
+    Atlas(usize),
+    Animation(usize),
+    Script,
+    StringTable(usize),
+    Dialog(usize),
+    Items(usize),
+    Ui,
+    Controller,
+    Material(Arc<str>),
+    ColorLut,
+    SpriteShader,
+}
+
+/// Quads for a run of sprites, with indices and draw calls relative to the
+/// run; `append_to` rebases them onto the frame's mesh.
+#[derive(Default)]
+struct MeshChunk {
+    vertices: Vec<SpriteVertex>,
+    indices: Vec<u32>,
+    draw_calls: Vec<DrawCall>,
+    sprites: LayerSpriteCounts,
+}
+
+/// A layer's sprites in one mesh build; sprites whose asset didn't resolve
+/// are in neither count.
+#[derive(Debug, Clone, Copy, Default)]
+struct LayerSpriteCounts {
+    drawn: usize,
+    /// Left out for lying wholly outside the view.
+    culled: usize,
+}
+
+impl MeshChunk {
+    fn append_to(
+        self,
+        vertices: &mut Vec<SpriteVertex>,
+        indices: &mut Vec<u32>,
+        draw_calls: &mut Vec<DrawCall>,
+    ) {
+        let vertex_base = vertices.len() as u32;
+        let index_base = indices.len() as u32;
+        vertices.extend(self.vertices);
+        indices.extend(self.indices.iter().map(|index| index + vertex_base));
+        // Merging across the seam gives the same batches as one serial pass.
+        for call in self.draw_calls {
+            push_draw_call(
+                draw_calls,
+                call.texture_key,
+                call.index_start + index_base,
+                call.index_count,
+                call.pipeline,
+                call.sampler,
+            );
+        }
+    }
+}
+
+/// One mesh job: a run of a layer's sprites, in draw order.
+struct SpriteJob<'a> {
+    layer: &'a scene::SceneLayer,
+    /// Index of `layer` in the layers being built.
+    slot: usize,
+    order: &'a [usize],
+    offset: (f32, f32),
+    /// Tier tint with the layer's opacity applied.
+    color: [f32; 4],
+    /// Min and max corners of the view in the layer's space; quads wholly
+    /// outside are culled. `None` builds every quad.
+    view: Option<(glam::Vec2, glam::Vec2)>,
+}
+
+struct QuadSpec {
+    texture_key: Id,
+    center_x: f32,
+    center_y: f32,
+    width: f32,
+    height: f32,
+    color: [f32; 4],
+    /// Bottom-left, bottom-right, top-right, top-left (see
+    /// `AtlasSpriteEntry::tex_coords`).
+    tex_coords: [[f32; 2]; 4],
+    effect: SpriteEffect,
+}
+
+struct GpuSpriteTexture {
+    texture: Texture,
+    /// Secondary texture bound alongside it, if any.
+    secondary: Option<Id>,
+    /// Asset the texels came from: the key itself, or its half-res copy.
+    file: Id,
+    /// Pixel size in design units, i.e. of the full-size texture even when
+    /// `file` is the half-res copy.
+    size: (u32, u32),
+}
+
+/// All mutable engine state lives here. Constructed lazily in `ApplicationHandler::resumed`
+/// once the window and GPU surface are available.
+///
+/// Ownership is split into three conceptual groups:
+///  - **Core systems** (time, input, camera) -- updated every frame
+///  - **Content** (scene, collision, atlas, textures) -- loaded from disk, hot-reloadable
+///  - **GPU resources** (vertex/index/camera buffers, draw calls) -- rebuilt when content changes
+struct EngineState {
+    window: Arc<Window>,
+    gpu: GpuContext,
+    time: TimeState,
+    input: InputState,
+    /// Virtual joystick + jump button; feeds `input` like a keyboard.
+    touch: TouchControls,
+    camera: Camera2D,
+    sprite_pipeline: SpritePipeline,
+    debug_overlay: DebugOverlay,
+    /// Values scripts report via `engine.debug.*`, shown in the Debug window.
+    script_watches: ScriptWatches,
+    /// Failed hot reloads shown as a banner until the file reloads cleanly.
+    reload_errors: ReloadErrors,
+
+    // --- Hot-reloadable content -------------------------------------------------
+    scene_path: std::path::PathBuf,
+    scene_watcher: SceneWatcher,
+    scene_include_watchers: Vec<SceneWatcher>,
+    scene: SceneFile,
+    collision_path: std::path::PathBuf,
+    collision_watcher: SceneWatcher,
+    collision_grid: CollisionGrid,
+    atlas_paths: Vec<std::path::PathBuf>,
+    atlas_watchers: Vec<SceneWatcher>,
+    multi_atlas: MultiAtlasRegistry,
+    animation_paths: Vec<std::path::PathBuf>,
+    animation_watchers: Vec<SceneWatcher>,
+    animation_registry: AnimationRegistry,
+    /// Which of the files above use which, and the textures they name.
+    asset_graph: AssetGraph,
+    /// Every texture in `asset_graph`, by path.
+    texture_watchers: Vec<(Id, SceneWatcher)>,
+    /// Afterimage trails by sprite id; `PLAYER_TRAIL_ID` is the player quad.
+    trails: StateMap<String, Trail>,
+    /// Sprite property tweens from the scene and `engine.tween.to`.
+    tweens: Tweens,
+    /// Shader effects by sprite id, from the scene or `engine.sprite.set_effect`.
+    effects: HashMap<String, SpriteEffect>,
+    /// Layer materials by snippet path, for the layers that reference one.
+    materials: HashMap<Arc<str>, LayerMaterial>,
+    color_lut: Option<ColorLutSource>,
+    /// The scene's rain, snow and fog, in `ambient` order.
+    ambient: Vec<AmbientEmitter>,
+    /// Which tiles of a `tiled` scene background are loaded.
+    background_tiles: Option<TileStreamer>,
+    /// Droplets from bodies crossing fluid surfaces; visual only.
+    splashes: ParticleSystem,
+    /// Script ambient-color override over the scene's `day_cycle`.
+    environment: Environment,
+    /// Layer groups hidden by scripts or the Layers window.
+    layer_groups: LayerGroups,
+    /// Pending `engine.scene.load` and its fade.
+    scene_transition: SceneTransition,
+    /// Tier 2 color grading; only drawn through while the scene has a LUT.
+    post_process: PostProcess,
+    /// `sprite.wgsl` and `sprite_effects.wgsl`, reloaded together.
+    shader_watchers: Vec<SceneWatcher>,
+    loc_paths: Vec<std::path::PathBuf>,
+    loc_watchers: Vec<SceneWatcher>,
+    localization: Localization,
+    dialog_paths: Vec<std::path::PathBuf>,
+    dialog_watchers: Vec<SceneWatcher>,
+    dialog_registry: DialogRegistry,
+    /// Running conversation; the fixed-step simulation is suspended while set.
+    active_dialog: Option<ActiveDialog>,
+    /// Item definition files; the definitions themselves live on the Lua
+    /// bridge, which `engine.inventory` reads.
+    item_paths: Vec<std::path::PathBuf>,
+    item_watchers: Vec<SceneWatcher>,
+    /// Scene pickup sprites already collected; hidden until the scene reloads.
+    collected: HashSet<String>,
+    ui_path: std::path::PathBuf,
+    ui_watcher: SceneWatcher,
+    ui: Option<UiLayer>,
+    /// Movement tuning for every body; each body keeps its own copy of the
+    /// config it uses (see `apply_controller_tuning`).
+    controller_path: std::path::PathBuf,
+    controller_watcher: SceneWatcher,
+    controller_tuning: ControllerTuning,
+    /// Set when hover, Lua commands, reloads or resizes change the UI layout.
+    ui_dirty: bool,
+    /// The player's body and every animation (see `world`).
+    world: World,
+    /// Actors created by `engine.spawn`, stepped after the player.
+    spawned: SpawnedActors,
+    show_collision_debug: bool,
+    /// `--record-replay`: where to save, and the inputs so far.
+    replay_recorder: Option<(std::path::PathBuf, ReplayRecorder)>,
+    /// `--play-replay`: remaining inputs; live input resumes when they run out.
+    replay_inputs: Option<std::vec::IntoIter<ControllerInput>>,
+    /// `--record-events`: where to save, and the events so far.
+    event_recorder: Option<(std::path::PathBuf, EventRecorder)>,
+    /// `--play-events`: remaining events; live input resumes when they run out.
+    event_player: Option<EventPlayer>,
+    /// Shapes queued by gameplay code and `engine.debug.draw_*`; cleared at
+    /// the start of every fixed step.
+    debug_draw: DebugDraw,
+    tier: FidelityTier,
+    /// Physical keys to engine keys, with the player's bindings.
+    key_bindings: KeyBindings,
+    lua_bridge: LuaBridge,
+    /// The overlay's "Step 10" and step-until controls, while running.
+    stepping: Option<Stepping>,
+    /// Snapshots of recent fixed steps for the overlay's Back/Forward.
+    rewind: RewindBuffer<WorldSnapshot>,
+    /// Back/Forward clicked this frame; applied at the next frame boundary.
+    rewind_request: Option<RewindStep>,
+    /// Files to reload from the Assets window, each with whether to skip it
+    /// when unchanged; applied at the next frame boundary.
+    asset_reload_requests: Vec<(WatchedAsset, bool)>,
+    /// What the scene's camera constraints corrected this frame.
+    camera_clamp: CameraClamp,
+    /// Debug free-cam (F6): the camera ignores the player and scene
+    /// constraints, and follows wheel zoom and middle-drag panning instead.
+    /// Holds the zoom to restore when free-cam is switched off.
+    free_camera: Option<f32>,
+    /// Set when free-cam input moved the camera outside the step loop.
+    free_camera_moved: bool,
+    /// Screen shake; offsets only the camera uniform, never `camera`.
+    shake: CameraShake,
+    /// Detached stats window (F7); events for it are routed by `WindowId`.
+    inspector: Option<Inspector>,
+    /// What happens while the game window is unfocused.
+    background: BackgroundPolicy,
+    focused: bool,
+    /// Game systems added with `Engine::add_system`, in order.
+    systems: Vec<Box<dyn System>>,
+    /// Start of the last redraw; paces redraws under `background.redraw_hz`.
+    last_redraw: std::time::Instant,
+    /// GPU textures by asset path, in load order.
+    textures: InternedMap<GpuSpriteTexture>,
+    /// Normal maps and emissive masks from parallel atlases, by asset path.
+    /// Loaded the first time a tier above Tier 0 binds them.
+    secondary_textures: InternedMap<Texture>,
+
+    // --- Per-frame mesh state ---------------------------------------------------
+    // The sprite mesh is rebuilt on the CPU each frame and shared with the
+    // render thread, which streams it into GPU buffers. Buffers grow
+    // (power-of-two) but never shrink.
+    render_thread: RenderThread,
+    mesh: Arc<FrameMesh>,
+    mesh_vertex_capacity: usize,
+    mesh_index_capacity: usize,
+    draw_calls: Vec<DrawCall>,
+    /// Screen-space draws (screen layers, then UI) that follow the world draws
+    /// in the shared buffers.
+    screen_draw_calls: Vec<DrawCall>,
+    sprite_count: usize,
+    /// Per-layer sprite counts of the last mesh build, for the Debug window.
+    layer_stats: Vec<LayerStatsView>,
+    /// (len, capacity) of the last CPU-side vertex and index vectors.
+    cpu_mesh_vertices: (usize, usize),
+    cpu_mesh_indices: (usize, usize),
+}
+
+impl EngineState {
+    fn new(
+        window: Arc<Window>,
+        config: &EngineConfig,
+        settings: SettingsStore,
+        systems: Vec<Box<dyn System>>,
+    ) -> Self {
+        let mut gpu = GpuContext::new(window.clone());
+        if !settings.settings().vsync {
+            gpu.set_vsync(false);
+        }
+        let key_bindings = KeyBindings::new(&settings.settings().bindings);
+        let time = TimeState::new();
+        let input = InputState::new();
+        let mut sprite_pipeline = SpritePipeline::new(&gpu.device, gpu.surface_format);
+        let post_process = PostProcess::new(&gpu.device, gpu.surface_format);
+        let mut debug_overlay = DebugOverlay::new(&window);
+        debug_overlay.budget = BudgetWarnings::new(config.budget);
+        let render_thread = RenderThread::new(&gpu, &sprite_pipeline);
+
+        let scene_path = config.scene.clone();
+        crash::set_scene_path(&scene_path);
+        let scene_watcher = SceneWatcher::new(scene_path.clone());
+        let scene = load_scene_from_path(&scene_path).unwrap_or_else(|err| {
+            panic!(
+                "Failed to load initial scene '{}': {}",
+                scene_path.display(),
+                err
+            );
+        });
+        let scene_include_watchers = build_include_watchers(&scene);
+        let collision_path = config.collision.clone();
+        let collision_watcher = SceneWatcher::new(collision_path.clone());
+        let units = scene.units.unwrap_or_default();
+        let collision_grid =
+            load_collision_in_units(&collision_path, units).unwrap_or_else(|err| {
+                panic!(
+                    "Failed to load initial collision '{}': {}",
+                    collision_path.display(),
+                    err
+                );
+            });
+        // Build multi-atlas from scene-declared atlases (v0.2) or legacy fallback (v0.1)
+        let atlas_path_strings = if scene.atlases.is_empty() {
+            vec![LEGACY_ATLAS_PATH.to_string()]
+        } else {
+            scene.atlases.clone()
+        };
+        let mut multi_atlas = MultiAtlasRegistry::new();
+        let mut atlas_paths = Vec::new();
+        let mut atlas_watchers = Vec::new();
+        for atlas_path_str in &atlas_path_strings {
+            let atlas_path = std::path::PathBuf::from(atlas_path_str);
+            atlas_watchers.push(SceneWatcher::new(atlas_path.clone()));
+            if sme_core::assets::exists(&atlas_path) {
+                match load_atlas_from_path(&atlas_path) {
+                    Ok(registry) => {
+                        if let Err(err) = multi_atlas.add_atlas(atlas_path_str, registry) {
+                            log::error!("Failed to add atlas '{}': {}", atlas_path.display(), err);
+                        }
+                    }
+                    Err(err) => {
+                        log::error!(
+                            "Failed to load initial atlas '{}': {}",
+                            atlas_path.display(),
+                            err
+                        );
+                    }
+                }
+            } else {
+                log::warn!(
+                    "Atlas metadata '{}' was not found. sprite_id references will fail to resolve.",
+                    atlas_path.display()
+                );
+            }
+            atlas_paths.push(atlas_path);
+        }
+        if let Err(err) = validate_scene_sprite_references(&scene, &multi_atlas) {
+            panic!(
+                "Initial scene '{}' failed sprite reference validation: {}",
+                scene_path.display(),
+                err
+            );
+        }
+        // The atlases pick the blend mode; switching it takes a restart.
+        if multi_atlas.premultiplied_alpha() == Some(true) {
+            if let Err(err) = sprite_pipeline.set_alpha_mode(&gpu.device, AlphaMode::Premultiplied)
+            {
+                panic!("Premultiplied atlases cannot be drawn: {}", err);
+            }
+        }
+        if let Err(err) = preflight_multi_atlas_textures(&gpu.device, &gpu.queue, &multi_atlas) {
+            panic!("Initial atlas set failed texture preflight: {}", err);
+        }
+
+        // Load animation files
+        let mut animation_registry = AnimationRegistry::new();
+        let mut animation_paths = Vec::new();
+        let mut animation_watchers = Vec::new();
+        for anim_path_str in &scene.animations {
+            let anim_path = std::path::PathBuf::from(anim_path_str);
+            animation_watchers.push(SceneWatcher::new(anim_path.clone()));
+            if sme_core::assets::exists(&anim_path) {
+                if let Err(err) = animation_registry.load_file(&anim_path) {
+                    log::error!(
+                        "Failed to load animation '{}': {}",
+                        anim_path.display(),
+                        err
+                    );
+                }
+            } else {
+                log::warn!("Animation file '{}' not found.", anim_path.display());
+            }
+            animation_paths.push(anim_path);
+        }
+
+        // Init animation states for sprites that declare animations
+        let trails = build_trails(&scene);
+        let tweens = build_tweens(&scene);
+        let effects = build_effects(&scene);
+        let ambient = build_ambient(&scene);
+        let background_tiles = build_background_tiles(&scene);
+
+        let mut localization = Localization::new(engine::DEFAULT_LANGUAGE);
+        let mut loc_paths = Vec::new();
+        let mut loc_watchers = Vec::new();
+        for loc_path in &config.strings {
+            let loc_path = loc_path.clone();
+            loc_watchers.push(SceneWatcher::new(loc_path.clone()));
+            match load_string_table(&loc_path) {
+                Ok(table) => localization.insert_table(table),
+                Err(err) => log::error!("Failed to load string table: {err}"),
+            }
+            loc_paths.push(loc_path);
+        }
+        localization.set_language(&config.language);
+
+        let mut dialog_registry = DialogRegistry::new();
+        let mut dialog_paths = Vec::new();
+        let mut dialog_watchers = Vec::new();
+        for dialog_path in &config.dialogs {
+            let dialog_path = dialog_path.clone();
+            dialog_watchers.push(SceneWatcher::new(dialog_path.clone()));
+            if let Err(err) = dialog_registry.load_file(&dialog_path) {
+                log::error!("Failed to load dialog: {err}");
+            }
+            dialog_paths.push(dialog_path);
+        }
+        let ui_path = config.ui.clone();
+        let ui_watcher = SceneWatcher::new(ui_path.clone());
+        let ui = match load_ui_from_path(&ui_path) {
+            Ok(document) => Some(UiLayer::new(document)),
+            Err(err) => {
+                log::error!("Failed to load UI: {err}");
+                None
+            }
+        };
+        let mut lua_bridge = LuaBridge::new(config.script.clone());
+        lua_bridge.set_loc_strings(localization.resolved_strings());
+        lua_bridge.set_rng_seed(config.seed);
+        if !config.break_on.is_empty() {
+            lua_bridge.set_breakpoints(config.break_on.clone());
+        }
+        lua_bridge.set_collision_grid(collision_grid.clone());
+        let save_dir =
+            sme_platform::paths::data_dir(&config.window.title).map(|dir| dir.join(save::SAVE_DIR));
+        match &save_dir {
+            Some(dir) => log::info!("Save games: {}", dir.display()),
+            None => log::warn!("No data directory; engine.save.write will fail"),
+        }
+        lua_bridge.save().set_dir(save_dir);
+        *lua_bridge.settings() = settings;
+        log::info!("Script RNG seed: {}", config.seed);
+        let mut item_paths = Vec::new();
+        let mut item_watchers = Vec::new();
+        for item_path in &config.items {
+            let item_path = item_path.clone();
+            item_watchers.push(SceneWatcher::new(item_path.clone()));
+            if let Err(err) = lua_bridge.items().load_file(&item_path) {
+                log::error!("Failed to load items: {err}");
+            }
+            item_paths.push(item_path);
+        }
+
+        let mut camera = Camera2D::new(gpu.size.0, gpu.size.1);
+        if let Some(scene_camera) = &scene.camera {
+            camera.position.x = scene_camera.start_x;
+            camera.position.y = scene_camera.start_y;
+            camera.zoom = scene_camera.zoom;
+        }
+        let controller_path = config.controller.clone();
+        let controller_watcher = SceneWatcher::new(controller_path.clone());
+        let controller_tuning =
+            load_controller_tuning_from_path(&controller_path).unwrap_or_else(|err| {
+                log::warn!("{err}; using default controller tuning");
+                ControllerTuning::default()
+            });
+        let character = CharacterController::new(player_start(&collision_grid));
+        let mut world = World::new(character);
+        world.reset_animations(build_animation_states(&scene, &animation_registry));
+        mark_ui_clock_animations(&mut world, &scene);
+
+        let mut state = Self {
+            window,
+            gpu,
+            time,
+            input,
+            touch: TouchControls::new(),
+            camera,
+            sprite_pipeline,
+            debug_overlay,
+            script_watches: ScriptWatches::new(),
+            reload_errors: ReloadErrors::new(),
+            scene_path,
+            scene_watcher,
+            scene_include_watchers,
+            scene,
+            collision_path,
+            collision_watcher,
+            collision_grid,
+            atlas_paths,
+            atlas_watchers,
+            multi_atlas,
+            animation_paths,
+            animation_watchers,
+            animation_registry,
+            asset_graph: AssetGraph::new(),
+            texture_watchers: Vec::new(),
+            trails,
+            tweens,
+            effects,
+            materials: HashMap::new(),
+            color_lut: None,
+            ambient,
+            background_tiles,
+            splashes: ParticleSystem::new(SPLASH_CAPACITY),
+            environment: Environment::default(),
+            layer_groups: LayerGroups::default(),
+            scene_transition: SceneTransition::default(),
+            post_process,
+            shader_watchers: [SPRITE_SHADER_PATH, SPRITE_EFFECT_SHADER_PATH]
+                .iter()
+                .map(|path| SceneWatcher::new(std::path::PathBuf::from(path)))
+                .collect(),
+            loc_paths,
+            loc_watchers,
+            localization,
+            dialog_paths,
+            dialog_watchers,
+            dialog_registry,
+            active_dialog: None,
+            item_paths,
+            item_watchers,
+            collected: HashSet::new(),
+            ui_path,
+            ui_watcher,
+            controller_path,
+            controller_watcher,
+            controller_tuning,
+            ui,
+            ui_dirty: false,
+            world,
+            spawned: SpawnedActors::default(),
+            show_collision_debug: true,
+            replay_recorder: None,
+            replay_inputs: None,
+            event_recorder: None,
+            event_player: None,
+            debug_draw: DebugDraw::new(),
+            tier: config.tier,
+            key_bindings,
+            lua_bridge,
+            camera_clamp: CameraClamp::default(),
+            free_camera: None,
+            free_camera_moved: false,
+            shake: CameraShake::new(),
+            inspector: None,
+            background: if config.run_in_background {
+                BackgroundPolicy::keep_running()
+            } else {
+                BackgroundPolicy::default()
+            },
+            focused: true,
+            systems,
+            last_redraw: std::time::Instant::now(),
+            stepping: None,
+            rewind: RewindBuffer::new(REWIND_CAPACITY),
+            rewind_request: None,
+            asset_reload_requests: Vec::new(),
+            textures: InternedMap::new(),
+            secondary_textures: InternedMap::new(),
+            render_thread,
+            mesh: Arc::new(FrameMesh {
+                vertices: Vec::new(),
+                indices: Vec::new(),
+                vertex_capacity: 0,
+                index_capacity: 0,
+            }),
+            mesh_vertex_capacity: 0,
+            mesh_index_capacity: 0,
+            draw_calls: Vec::new(),
+            screen_draw_calls: Vec::new(),
+            sprite_count: 0,
+            layer_stats: Vec::new(),
+            cpu_mesh_vertices: (0, 0),
+            cpu_mesh_indices: (0, 0),
+        };
+
+        // Startup order matters: load textures before building the first mesh.
+        state.rebuild_asset_graph();
+        state.ensure_textures_for_scene();
+        // The built-in shader is the file as of the last build; pick up edits
+        // made since. Materials compile on top of whichever one is in use.
+        if sme_core::assets::exists(std::path::Path::new(SPRITE_SHADER_PATH)) {
+            state.reload_sprite_shader("startup");
+        }
+        state.sync_materials();
+        state.sync_color_lut();
+        state.warn_unknown_pickup_items();
+        state.apply_controller_tuning();
+        state.ensure_mesh_capacity(4, 6);
+        state.rebuild_scene_mesh();
+        state
+    }
+
+    /// Refresh the Layers window from the live scene.
+    fn sync_layer_panel(&mut self) {
+        self.debug_overlay.layer_panel.layers = self
+            .scene
+            .layers
+            .iter()
+            .map(|layer| LayerView {
+                id: layer.id.clone(),
+                parallax: layer.parallax,
+                visible: layer.visible,
+                opacity: layer.opacity,
+                screen_space: layer.space == LayerSpace::Screen,
+            })
+            .collect();
+        self.debug_overlay.layer_panel.groups = self
+            .scene
+            .layer_groups()
+            .into_iter()
+            .map(|name| LayerGroupView {
+                name: name.to_string(),
+                visible: self.layer_groups.is_visible(name),
+            })
+            .collect();
+    }
+
+    /// Copy Layers window group toggles into the live groups.
+    fn apply_layer_group_panel(&mut self) {
+        for view in &self.debug_overlay.layer_panel.groups {
+            self.layer_groups.set_visible(&view.name, view.visible);
+        }
+        self.rebuild_scene_mesh();
+    }
+
+    /// Copy Layers window edits into the live scene.
+    fn apply_layer_panel(&mut self) {
+        for view in &self.debug_overlay.layer_panel.layers {
+            if let Some(layer) = self.scene.layers.iter_mut().find(|l| l.id == view.id) {
+                layer.parallax = view.parallax;
+                layer.visible = view.visible;
+                layer.opacity = view.opacity;
+            }
+        }
+        self.debug_overlay.layer_panel.unsaved = true;
+        self.debug_overlay.layer_panel.status = None;
+        self.rebuild_scene_mesh();
+    }
+
+    /// Write every layer's tuned settings back to the scene files. The scene
+    /// watcher then reloads the saved values, which match the live ones.
+    fn save_layer_panel(&mut self) {
+        let mut written = Vec::new();
+        for layer in &self.scene.layers {
+            match save_layer_settings(&self.scene_path, layer) {
+                Ok(path) => {
+                    if !written.contains(&path) {
+                        written.push(path);
+                    }
+                }
+                Err(err) => {
+                    log::error!("Saving layer settings failed: {err}");
+                    self.debug_overlay.layer_panel.status = Some(format!("Save failed: {err}"));
+                    return;
+                }
+            }
+        }
+        let files: Vec<String> = written.iter().map(|p| p.display().to_string()).collect();
+        log::info!("Saved layer settings to {}", files.join(", "));
+        self.debug_overlay.layer_panel.unsaved = false;
+        self.debug_overlay.layer_panel.status = Some(format!("Saved to {}", files.join(", ")));
+    }
+
+    /// Whether sprites are drawn with premultiplied alpha.
+    fn premultiplied_alpha(&self) -> bool {
+        self.sprite_pipeline.alpha_mode() == AlphaMode::Premultiplied
+    }
+
+    /// Every file the engine watches, with its reload kind and key.
+    fn watched_assets(&self) -> Vec<(WatchedAsset, &'static str, String)> {
+        let key = |path: &std::path::Path| path.to_string_lossy().to_string();
+        let mut assets = vec![
+            (WatchedAsset::Scene, "scene", key(&self.scene_path)),
+            (
+                WatchedAsset::Collision,
+                "collision",
+                key(&self.collision_path),
+            ),
+        ];
+        let indexed = |paths: &[std::path::PathBuf], kind, asset: fn(usize) -> WatchedAsset| {
+            paths
+                .iter()
+                .enumerate()
+                .map(|(index, path)| (asset(index), kind, key(path)))
+                .collect::<Vec<_>>()
+        };
+        assets.extend(indexed(&self.atlas_paths, "atlas", WatchedAsset::Atlas));
+        assets.extend(indexed(
+            &self.animation_paths,
+            "animation",
+            WatchedAsset::Animation,
+        ));
+        assets.push((
+            WatchedAsset::Script,
+            "lua",
+            key(self.lua_bridge.script_path()),
+        ));
+        assets.extend(indexed(
+            &self.loc_paths,
+            "strings",
+            WatchedAsset::StringTable,
+        ));
+        assets.extend(indexed(&self.dialog_paths, "dialog", WatchedAsset::Dialog));
+        assets.extend(indexed(&self.item_paths, "items", WatchedAsset::Items));
+        assets.push((WatchedAsset::Ui, "ui", key(&self.ui_path)));
+        assets.push((
+            WatchedAsset::Controller,
+            "controller",
+            key(&self.controller_path),
+        ));
+        let mut materials: Vec<&Arc<str>> = self.materials.keys().collect();
+        materials.sort();
+        for path in materials {
+            assets.push((
+                WatchedAsset::Material(path.clone()),
+                "material",
+                path.to_string(),
+            ));
+        }
+        if let Some(lut) = &self.color_lut {
+            assets.push((WatchedAsset::ColorLut, "color LUT", lut.path.clone()));
+        }
+        assets.push((
+            WatchedAsset::SpriteShader,
+            "shader",
+            SPRITE_SHADER_PATH.to_string(),
+        ));
+        assets
+    }
+
+    /// Refresh the Assets window's rows.
+    fn sync_asset_panel(&mut self) {
+        self.debug_overlay.asset_panel.assets = self
+            .watched_assets()
+            .into_iter()
+            .map(|(_, kind, path)| AssetView {
+                kind: kind.to_string(),
+                path,
+            })
+            .collect();
+    }
+
+    /// Reload one file, as the file watcher would on a change. With
+    /// `if_changed`, an atlas whose file is unchanged is skipped.
+    fn reload_asset(&mut self, asset: WatchedAsset, if_changed: bool) {
+        let reason = "Assets window";
+        match asset {
+            WatchedAsset::Scene => {
+                self.reload_scene(reason);
+            }
+            WatchedAsset::Collision => {
+                self.reload_collision(reason);
+            }
+            WatchedAsset::Atlas(index) if if_changed => self.reload_atlas_if_changed(index, reason),
+            WatchedAsset::Atlas(index) => self.reload_atlas(index, reason),
+            WatchedAsset::Animation(index) => self.reload_animation(index, reason),
+            WatchedAsset::Script => {
+                self.lua_bridge.force_reload();
+                self.script_watches.clear();
+                self.note_script_reload();
+            }
+            WatchedAsset::StringTable(index) => self.reload_string_table(index, reason),
+            WatchedAsset::Dialog(index) => self.reload_dialog(index, reason),
+            WatchedAsset::Items(index) => self.reload_items(index, reason),
+            WatchedAsset::Ui => self.reload_ui(reason),
+            WatchedAsset::Controller => self.reload_controller_config(reason),
+            WatchedAsset::Material(path) => self.reload_material(&path, reason),
+            WatchedAsset::ColorLut => self.reload_color_lut(reason),
+            WatchedAsset::SpriteShader => self.reload_sprite_shader(reason),
+        }
+    }
+
+    /// Returns whether the scene was replaced.
+    fn reload_scene(&mut self, reason: &str) -> bool {
+        match load_scene_from_path(&self.scene_path) {
+            Ok(scene_candidate) => {
+                // Rebuild atlas set from new scene's atlas declarations
+                let atlas_path_strings = if scene_candidate.atlases.is_empty() {
+                    vec![LEGACY_ATLAS_PATH.to_string()]
+                } else {
+                    scene_candidate.atlases.clone()
+                };
+                let mut new_multi = MultiAtlasRegistry::new();
+                let mut new_atlas_paths = Vec::new();
+                let mut new_atlas_watchers = Vec::new();
+                // Textures of atlases parsed again, to upload again.
+                let mut reloaded_textures = Vec::new();
+                let premultiplied = self.premultiplied_alpha();
+                for atlas_path_str in &atlas_path_strings {
+                    let atlas_path = std::path::PathBuf::from(atlas_path_str);
+                    new_atlas_watchers.push(SceneWatcher::new(atlas_path.clone()));
+                    if sme_core::assets::exists(&atlas_path) {
+                        // An atlas whose file is unchanged is reused, not parsed again.
+                        let loaded = match self.unchanged_atlas(&atlas_path) {
+                            Some(registry) => Ok(registry.clone()),
+                            None => load_atlas_from_path(&atlas_path).inspect(|registry| {
+                                reloaded_textures.push(registry.texture);
+                                reloaded_textures.extend(registry.half_res_texture);
+                            }),
+                        };
+                        match loaded {
+                            Ok(registry) => match registry
+                                .check_alpha_convention(premultiplied)
+                                .map_err(|e| format!("{e}; restart to switch"))
+                                .and_then(|()| new_multi.add_atlas(atlas_path_str, registry))
+                            {
+                                Ok(()) => self.reload_errors.succeeded(atlas_path_str),
+                                Err(err) => {
+                                    log::error!("Scene reload ({reason}): atlas add error: {err}");
+                                    self.reload_errors.failed("atlas", atlas_path_str, &err);
+                                }
+                            },
+                            Err(err) => {
+                                log::error!("Scene reload ({reason}): atlas load error: {err}");
+                                self.reload_errors.failed("atlas", atlas_path_str, &err);
+                            }
+                        }
+                    }
+                    new_atlas_paths.push(atlas_path);
+                }
+
+                if let Err(err) = validate_scene_sprite_references(&scene_candidate, &new_multi) {
+                    log::error!("Scene reload failed ({reason}): {err}");
+                    self.reload_errors
+                        .failed("scene", &self.scene_path.to_string_lossy(), &err);
+                    return false;
+                }
+
+                // Rebuild animation set from new scene
+                let mut new_anim_registry = AnimationRegistry::new();
+                let mut new_anim_paths = Vec::new();
+                let mut new_anim_watchers = Vec::new();
+                for anim_path_str in &scene_candidate.animations {
+                    let anim_path = std::path::PathBuf::from(anim_path_str);
+                    new_anim_watchers.push(SceneWatcher::new(anim_path.clone()));
+                    if sme_core::assets::exists(&anim_path) {
+                        match new_anim_registry.load_file(&anim_path) {
+                            Ok(()) => self.reload_errors.succeeded(anim_path_str),
+                            Err(err) => {
+                                log::error!("Scene reload ({reason}): anim load error: {err}");
+                                self.reload_errors.failed("animation", anim_path_str, &err);
+                            }
+                        }
+                    }
+                    new_anim_paths.push(anim_path);
+                }
+
+                self.multi_atlas = new_multi;
+                self.atlas_paths = new_atlas_paths;
+                self.atlas_watchers = new_atlas_watchers;
+                self.animation_registry = new_anim_registry;
+                self.animation_paths = new_anim_paths;
+                self.animation_watchers = new_anim_watchers;
+                self.scene_include_watchers = build_include_watchers(&scene_candidate);
+                let units_changed = scene_candidate.units != self.scene.units;
+                self.scene = scene_candidate;
+                // Reloading discards any unsaved Layers window edits.
+                self.debug_overlay.layer_panel.unsaved = false;
+                self.world.reset_animations(build_animation_states(
+                    &self.scene,
+                    &self.animation_registry,
+                ));
+                mark_ui_clock_animations(&mut self.world, &self.scene);
+                // Spawned actors belong to the old scene, as do the
+                // snapshots of its animations and camera.
+                self.spawned.clear();
+                self.lua_bridge.projectiles().clear();
+                // Pickups come back, and what they gave is taken away again.
+                self.collected.clear();
+                self.lua_bridge.inventories().clear();
+                self.lua_bridge.events().clear();
+                self.warn_unknown_pickup_items();
+                self.apply_controller_tuning();
+                self.rewind.clear();
+                // Lua-enabled trails are dropped too; scripts re-enable them.
+                self.trails = build_trails(&self.scene);
+                self.tweens = build_tweens(&self.scene);
+                self.effects = build_effects(&self.scene);
+                self.ambient = build_ambient(&self.scene);
+                let tile_textures: Vec<Id> = self
+                    .background_tiles
+                    .iter()
+                    .flat_map(TileStreamer::textures)
+                    .collect();
+                for texture in tile_textures {
+                    self.forget_texture(texture);
+                }
+                self.background_tiles = build_background_tiles(&self.scene);
+                self.environment = Environment::default();
+                self.layer_groups = LayerGroups::default();
+
+                if let Some(scene_camera) = &self.scene.camera {
+                    self.camera.position.x = scene_camera.start_x;
+                    self.camera.position.y = scene_camera.start_y;
+                    self.camera.zoom = scene_camera.zoom;
+                }
+                self.rebuild_asset_graph();
+                self.evict_unused_textures();
+                for texture in reloaded_textures {
+                    self.reload_texture(texture, reason);
+                }
+                self.ensure_textures_for_scene();
+                self.sync_materials();
+                self.sync_color_lut();
+                self.rebuild_scene_mesh();
+                self.reload_errors
+                    .succeeded(&self.scene_path.to_string_lossy());
+                if units_changed {
+                    // The grid's cell size and origin are in the scene's units.
+                    self.reload_collision(reason);
+                }
+                log::info!(
+                    "Scene reloaded ({reason}): {} ({})",
+                    self.scene.scene_id,
+                    self.scene.version
+                );
+                true
+            }
+            Err(err) => {
+                log::error!("Scene reload failed ({reason}): {err}");
+                self.reload_errors
+                    .failed("scene", &self.scene_path.to_string_lossy(), &err);
+                false
+            }
+        }
+    }
+
+    /// Swap in the scene of an `engine.scene.load` request, then its
+    /// collision grid, and put the player at the grid's start. A scene that
+    /// fails to load leaves the current one running.
+    fn load_scene(&mut self, request: SceneLoad) {
+        let reason = "scene load";
+        let previous = std::mem::replace(&mut self.scene_path, request.scene);
+        if !self.reload_scene(reason) {
+            self.scene_path = previous;
+            return;
+        }
+        self.scene_watcher = SceneWatcher::new(self.scene_path.clone());
+        crash::set_scene_path(&self.scene_path);
+
+        let collision = request
+            .collision
+            .or_else(|| self.scene.collision.as_ref().map(Into::into));
+        if let Some(collision) = collision.filter(|path| *path != self.collision_path) {
+            let previous = std::mem::replace(&mut self.collision_path, collision);
+            if self.reload_collision(reason) {
+                self.collision_watcher = SceneWatcher::new(self.collision_path.clone());
+            } else {
+                self.collision_path = previous;
+            }
+        }
+        let config = self.world.player_body().config;
+        let player = self.world.player_body_mut();
+        *player = CharacterController::new(player_start(&self.collision_grid));
+        player.config = config;
+        if self.free_camera.is_none() {
+            self.camera.position.x = player.aabb.center_x;
+            self.camera.position.y = player.aabb.center_y;
+        }
+    }
+
+    /// Returns whether the grid was replaced.
+    fn reload_collision(&mut self, reason: &str) -> bool {
+        let units = self.scene.units.unwrap_or_default();
+        match load_collision_in_units(&self.collision_path, units) {
+            Ok(grid) => {
+                self.lua_bridge.set_collision_grid(grid.clone());
+                self.collision_grid = grid;
+                self.rebuild_scene_mesh();
+                self.reload_errors
+                    .succeeded(&self.collision_path.to_string_lossy());
+                log::info!(
+                    "Collision reloaded ({reason}): {} ({})",
+                    self.collision_grid.collision_id,
+                    self.collision_grid.version
+                );
+                true
+            }
+            Err(err) => {
+                log::error!("Collision reload failed ({reason}): {err}");
+                self.reload_errors.failed(
+                    "collision",
+                    &self.collision_path.to_string_lossy(),
+                    &err,
+                );
+                false
+            }
+        }
+    }
+
+    /// The atlas loaded from `atlas_path`, if its file still holds the same
+    /// metadata.
+    fn unchanged_atlas(&self, atlas_path: &std::path::Path) -> Option<&AtlasRegistry> {
+        let loaded = self.multi_atlas.get(&atlas_path.to_string_lossy())?;
+        let hash = atlas_source_hash(atlas_path).ok()?;
+        (hash == loaded.source_hash).then_some(loaded)
+    }
+
+    /// Rebuild `asset_graph` from what is loaded now, and watch any texture
+    /// it gained.
+    fn rebuild_asset_graph(&mut self) {
+        self.asset_graph = AssetGraph::for_scene(
+            &self.scene_path,
+            &self.scene,
+            &self.atlas_paths,
+            &self.multi_atlas,
+            &self.animation_paths,
+            &self.animation_registry,
+        );
+        let mut watchers = std::mem::take(&mut self.texture_watchers);
+        self.texture_watchers = self
+            .asset_graph
+            .assets_of(AssetKind::Texture)
+            .into_iter()
+            .map(
+                |texture| match watchers.iter().position(|(path, _)| *path == texture) {
+                    Some(index) => watchers.swap_remove(index),
+                    None => (
+                        texture,
+                        SceneWatcher::new(std::path::PathBuf::from(texture.as_str())),
+                    ),
+                },
+            )
+            .collect();
+    }
+
+    /// Animation files with a frame in the atlas or texture `changed`, by
+    /// `animation_id`.
+    fn affected_animations(&self, changed: Id) -> Vec<Id> {
+        self.asset_graph
+            .affected_by(changed)
+            .into_iter()
+            .filter(|asset| self.asset_graph.kind(*asset) == Some(AssetKind::Animation))
+            .filter_map(|asset| {
+                self.animation_registry
+                    .animation_id_of(std::path::Path::new(asset.as_str()))
+            })
+            .collect()
+    }
+
+    /// Restart the animation of every scene sprite that may play a clip of
+    /// `animation_ids`: those naming one as `animation_source`, and those
+    /// naming none, whose clip is looked up in every file.
+    fn restart_animations(&mut self, animation_ids: &[Id]) {
+        if animation_ids.is_empty() {
+            return;
+        }
+        for sprite in self.scene.layers.iter().flat_map(|layer| &layer.sprites) {
+            if sprite.animation.is_none() {
+                continue;
+            }
+            let source = sprite.animation_source.as_deref().map(Id::new);
+            if source.is_some_and(|source| !animation_ids.contains(&source)) {
+                continue;
+            }
+            match sprite_animation_state(sprite, &self.animation_registry) {
+                Some(state) => self.world.set_animation(&sprite.id, state),
+                None => {
+                    self.world.remove_animation(&sprite.id);
+                }
+            }
+        }
+        mark_ui_clock_animations(&mut self.world, &self.scene);
+    }
+
+    /// Drop the GPU copies of `textures`, full size, half-res or secondary,
+    /// so the next `ensure_textures_for_scene` uploads them from disk again.
+    fn drop_textures(&mut self, textures: &[Id]) {
+        let keys: Vec<Id> = self
+            .textures
+            .iter()
+            .filter(|(key, texture)| textures.contains(key) || textures.contains(&texture.file))
+            .map(|(key, _)| key)
+            .collect();
+        for key in keys {
+            self.forget_texture(key);
+        }
+        for texture in textures {
+            let Some(secondary) = self.secondary_textures.remove(*texture) else {
+                continue;
+            };
+            self.sprite_pipeline.invalidate_texture(secondary.id);
+            // Unpaired, so `bind_secondary_textures` binds the new upload.
+            let keys: Vec<Id> = self.textures.iter().map(|(key, _)| key).collect();
+            for key in keys {
+                if let Some(gpu_texture) = self.textures.get_mut(key) {
+                    if gpu_texture.secondary == Some(*texture) {
+                        gpu_texture.secondary = None;
+                    }
+                }
+            }
+        }
+    }
+
+    /// Drop textures nothing loaded refers to any more: images of a previous
+    /// scene, or atlas textures an atlas stopped naming. Without this the
+    /// cache grows with every reload that changes a path.
+    fn evict_unused_textures(&mut self) {
+        let mut used: HashSet<Id> = self
+            .asset_graph
+            .assets_of(AssetKind::Texture)
+            .into_iter()
+            .collect();
+        used.extend(
+            self.background_tiles
+                .iter()
+                .flat_map(TileStreamer::textures),
+        );
+        used.extend([Id::new(DEBUG_WHITE_ASSET), Id::new(PLAYER_ASSET)]);
+        let unused: Vec<Id> = self
+            .textures
+            .iter()
+            .map(|(key, _)| key)
+            .chain(self.secondary_textures.iter().map(|(key, _)| key))
+            .filter(|key| !used.contains(key))
+            .collect();
+        if unused.is_empty() {
+            return;
+        }
+        log::debug!("Evicting {} unused textures", unused.len());
+        self.drop_textures(&unused);
+    }
+
+    /// Upload texture file `texture` again from disk, if anything has it
+    /// loaded. Copies of the same size are rewritten in place, keeping their
+    /// allocation and bind groups; a resized image gets new textures. A file
+    /// that fails to read or decode (say, caught half-written) leaves the old
+    /// texels in use. Returns whether anything was uploaded.
+    fn reupload_texture(&mut self, texture: Id) -> Result<bool, String> {
+        // Its watcher catches up, so the upload isn't repeated next frame.
+        for (path, watcher) in &mut self.texture_watchers {
+            if *path == texture {
+                watcher.should_reload();
+            }
+        }
+        // Loose images are premultiplied on load when atlases are; decode
+        // once for each convention a copy needs.
+        let premultiplied = self.premultiplied_alpha();
+        let atlas_textures = self.multi_atlas.texture_paths();
+        let mut copies: Vec<(&Texture, bool)> = self
+            .textures
+            .iter()
+            .filter(|(_, gpu_texture)| gpu_texture.file == texture)
+            .map(|(key, gpu_texture)| {
+                let premultiply = premultiplied && !atlas_textures.contains(&key);
+                (&gpu_texture.texture, premultiply)
+            })
+            .collect();
+        copies.extend(self.secondary_textures.get(texture).map(|t| (t, false)));
+        if copies.is_empty() {
+            return Ok(false);
+        }
+        let key = texture.as_str();
+        let bytes = sme_core::assets::read_bytes(std::path::Path::new(key))
+            .map_err(|e| format!("Failed to read texture '{key}': {e}"))?;
+        let mut decoded: [Option<Texels>; 2] = [None, None];
+        let mut resized = false;
+        for (copy, premultiply) in copies {
+            let texels = match &mut decoded[usize::from(premultiply)] {
+                Some(texels) => texels,
+                slot => slot.insert(
+                    Texels::decode(&bytes, premultiply).map_err(|e| format!("{e} ('{key}')"))?,
+                ),
+            };
+            resized |= !copy.rewrite(&self.gpu.queue, texels);
+        }
+        if resized {
+            self.drop_textures(&[texture]);
+            self.ensure_textures_for_scene();
+            self.rebuild_scene_mesh();
+        }
+        Ok(true)
+    }
+
+    /// Upload texture file `texture` again after it changed.
+    fn reload_texture(&mut self, texture: Id, reason: &str) {
+        let key = texture.as_str();
+        match self.reupload_texture(texture) {
+            Ok(uploaded) => {
+                self.reload_errors.succeeded(key);
+                if uploaded {
+                    log::info!("Texture reloaded ({reason}): {key}");
+                }
+            }
+            Err(err) => {
+                log::error!("Texture reload failed ({reason}): {err}");
+                self.reload_errors.failed("texture", key, &err);
+            }
+        }
+    }
+
+    /// Reload atlas `atlas_index` unless its file is unchanged since it loaded.
+    fn reload_atlas_if_changed(&mut self, atlas_index: usize, reason: &str) {
+        if self
+            .unchanged_atlas(&self.atlas_paths[atlas_index])
+            .is_some()
+        {
+            log::debug!(
+                "Atlas unchanged, not reloaded ({reason}): {}",
+                self.atlas_paths[atlas_index].display()
+            );
+            return;
+        }
+        self.reload_atlas(atlas_index, reason);
+    }
+
+    fn reload_atlas(&mut self, atlas_index: usize, reason: &str) {
+        let atlas_path = &self.atlas_paths[atlas_index];
+        let atlas_key = atlas_path.to_string_lossy().to_string();
+        match load_atlas_from_path(atlas_path) {
+            Ok(registry_candidate) => {
+                if let Err(err) =
+                    registry_candidate.check_alpha_convention(self.premultiplied_alpha())
+                {
+                    let err = format!("{err}; restart to switch");
+                    log::error!("Atlas reload failed ({reason}): {err}");
+                    self.reload_errors.failed("atlas", &atlas_key, &err);
+                    return;
+                }
+                // Textures it named before are dropped unless still used;
+                // the ones it names now are uploaded again.
+                let atlas_id = Id::new(&atlas_key);
+                let mut textures = self.asset_graph.uses(atlas_id);
+                textures.push(registry_candidate.texture);
+                textures.extend(registry_candidate.half_res_texture);
+                self.multi_atlas.remove_atlas(&atlas_key);
+                if let Err(err) = self.multi_atlas.add_atlas(&atlas_key, registry_candidate) {
+                    log::error!("Atlas reload failed ({reason}): {err}");
+                    self.reload_errors.failed("atlas", &atlas_key, &err);
+                    return;
+                }
+                if let Err(err) = validate_scene_sprite_references(&self.scene, &self.multi_atlas) {
+                    log::error!("Atlas reload failed ({reason}): {err}");
+                    self.reload_errors.failed("atlas", &atlas_key, &err);
+                    return;
+                }
+                self.rebuild_asset_graph();
+                self.evict_unused_textures();
+                for texture in textures {
+                    self.reload_texture(texture, reason);
+                }
+                let animations = self.affected_animations(atlas_id);
+                self.restart_animations(&animations);
+                self.ensure_textures_for_scene();
+                self.rebuild_scene_mesh();
+                self.reload_errors.succeeded(&atlas_key);
+                log::info!("Atlas reloaded ({reason}): {}", atlas_key);
+            }
+            Err(err) => {
+                log::error!("Atlas reload failed ({reason}): {err}");
+                self.reload_errors.failed("atlas", &atlas_key, &err);
+            }
+        }
+    }
+
+    fn reload_animation(&mut self, anim_index: usize, reason: &str) {
+        let anim_path = &self.animation_paths[anim_index];
+        let anim_key = anim_path.to_string_lossy().to_string();
+        match sme_core::animation::load_animation_file(anim_path) {
+            Ok(file) => {
+                // Remove old, add new under its animation_id
+                self.animation_registry.remove_file(&file.animation_id);
+                if let Err(err) = self.animation_registry.load_file(anim_path) {
+                    log::error!("Animation reload failed ({reason}): {err}");
+                    self.reload_errors.failed("animation", &anim_key, &err);
+                    return;
+                }
+                self.restart_animations(&[Id::new(&file.animation_id)]);
+                // Its frames may now come from an atlas nothing drew yet.
+                self.rebuild_asset_graph();
+                self.ensure_textures_for_scene();
+                self.reload_errors.succeeded(&anim_key);
+                log::info!("Animation reloaded ({reason}): {}", file.animation_id);
+            }
+            Err(err) => {
+                log::error!("Animation reload failed ({reason}): {err}");
+                self.reload_errors.failed("animation", &anim_key, &err);
+            }
+        }
+    }
+
+    fn reload_string_table(&mut self, loc_index: usize, reason: &str) {
+        let loc_key = self.loc_paths[loc_index].to_string_lossy().to_string();
+        match load_string_table(&self.loc_paths[loc_index]) {
+            Ok(table) => {
+                let language = table.language.clone();
+                self.localization.insert_table(table);
+                self.lua_bridge
+                    .set_loc_strings(self.localization.resolved_strings());
+                self.reload_errors.succeeded(&loc_key);
+                log::info!("String table reloaded ({reason}): {language}");
+            }
+            Err(err) => {
+                log::error!("String table reload failed ({reason}): {err}");
+                self.reload_errors.failed("strings", &loc_key, &err);
+            }
+        }
+    }
+
+    fn reload_dialog(&mut self, dialog_index: usize, reason: &str) {
+        let dialog_key = self.dialog_paths[dialog_index]
+            .to_string_lossy()
+            .to_string();
+        match self
+            .dialog_registry
+            .load_file(&self.dialog_paths[dialog_index])
+        {
+            Ok(dialog_id) => {
+                // A running conversation may point at nodes that no longer exist.
+                if self
+                    .active_dialog
+                    .as_ref()
+                    .is_some_and(|active| active.dialog_id == dialog_id)
+                {
+                    self.active_dialog = None;
+                    log::info!("Active dialog '{dialog_id}' closed by reload");
+                }
+                self.reload_errors.succeeded(&dialog_key);
+                log::info!("Dialog reloaded ({reason}): {dialog_id}");
+            }
+            Err(err) => {
+                log::error!("Dialog reload failed ({reason}): {err}");
+                self.reload_errors.failed("dialog", &dialog_key, &err);
+            }
+        }
+    }
+
+    fn reload_items(&mut self, item_index: usize, reason: &str) {
+        let item_key = self.item_paths[item_index].to_string_lossy().to_string();
+        let loaded = self
+            .lua_bridge
+            .items()
+            .load_file(&self.item_paths[item_index]);
+        match loaded {
+            Ok(count) => {
+                self.reload_errors.succeeded(&item_key);
+                log::info!("Items reloaded ({reason}): {item_key} ({count} items)");
+                self.warn_unknown_pickup_items();
+            }
+            Err(err) => {
+                log::error!("Item reload failed ({reason}): {err}");
+                self.reload_errors.failed("items", &item_key, &err);
+            }
+        }
+    }
+
+    /// Pickups naming an undefined item are never collected; say so once per
+    /// load instead of every step the player stands on one.
+    fn warn_unknown_pickup_items(&self) {
+        let items = self.lua_bridge.items();
+        for sprite in self.scene.layers.iter().flat_map(|layer| &layer.sprites) {
+            if let Some(pickup) = &sprite.pickup {
+                if items.get(&pickup.item).is_none() {
+                    log::warn!(
+                        "Pickup '{}' gives unknown item '{}'; it cannot be collected",
+                        sprite.id,
+                        pickup.item
+                    );
+                }
+            }
+        }
+    }
+
+    /// Give the player and every spawned actor the config their sprite's
+    /// `controller` preset names, warning about presets the tuning lacks.
+    fn apply_controller_tuning(&mut self) {
+        let mut player_preset = None;
+        for sprite in self.scene.layers.iter().flat_map(|layer| &layer.sprites) {
+            if let Some(preset) = self
+                .controller_tuning
+                .unknown_preset(sprite.controller.as_deref())
+            {
+                log::warn!(
+                    "Sprite '{}' uses unknown controller preset '{preset}'; using the base tuning",
+                    sprite.id
+                );
+            }
+            if sprite.id == "player" && player_preset.is_none() {
+                player_preset = sprite.controller.as_deref();
+            }
+        }
+        self.world.player_body_mut().config = self.controller_tuning.config(player_preset);
+        self.spawned.retune(&self.controller_tuning);
+    }
+
+    fn reload_ui(&mut self, reason: &str) {
+        match load_ui_from_path(&self.ui_path) {
+            Ok(document) => {
+                log::info!("UI reloaded ({reason}): {}", document.ui_id);
+                self.ui = Some(UiLayer::new(document));
+                self.ensure_textures_for_scene();
+                self.ui_dirty = true;
+                self.reload_errors
+                    .succeeded(&self.ui_path.to_string_lossy());
+            }
+            Err(err) => {
+                log::error!("UI reload failed ({reason}): {err}");
+                self.reload_errors
+                    .failed("ui", &self.ui_path.to_string_lossy(), &err);
+            }
+        }
+    }
+
+    fn reload_controller_config(&mut self, reason: &str) {
+        let controller_key = self.controller_path.to_string_lossy().to_string();
+        // A replay checks the tuning once, at its start.
+        if !self.can_rewind() {
+            log::warn!("Controller tuning not reloaded ({reason}) while a replay runs");
+            return;
+        }
+        match load_controller_tuning_from_path(&self.controller_path) {
+            Ok(tuning) => {
+                self.controller_tuning = tuning;
+                self.apply_controller_tuning();
+                self.reload_errors.succeeded(&controller_key);
+                log::info!(
+                    "Controller tuning reloaded ({reason}): {controller_key} ({:016x})",
+                    self.controller_tuning.tuning_hash()
+                );
+            }
+            Err(err) => {
+                log::error!("Controller tuning reload failed ({reason}): {err}");
+                self.reload_errors
+                    .failed("controller", &controller_key, &err);
+            }
+        }
+    }
+
+    /// Compile materials newly referenced by the scene and drop unused ones.
+    fn sync_materials(&mut self) {
+        let referenced: HashSet<Arc<str>> = self
+            .scene
+            .layers
+            .iter()
+            .filter_map(|l| l.material.as_deref().map(Arc::from))
+            .collect();
+        self.materials.retain(|path, _| referenced.contains(path));
+        for path in referenced {
+            if !self.materials.contains_key(&path) {
+                self.materials.insert(
+                    path.clone(),
+                    LayerMaterial {
+                        watcher: SceneWatcher::new(std::path::PathBuf::from(&*path)),
+                        pipeline: None,
+                    },
+                );
+                self.reload_material(&path, "scene load");
+            }
+        }
+    }
+
+    fn reload_material(&mut self, path: &Arc<str>, reason: &str) {
+        let result =
+            sme_core::assets::read_to_string(std::path::Path::new(&**path)).and_then(|snippet| {
+                self.sprite_pipeline.create_material_pipeline(
+                    &self.gpu.device,
+                    &format!("Material {path}"),
+                    &snippet,
+                )
+            });
+        match result {
+            Ok(pipeline) => {
+                if let Some(material) = self.materials.get_mut(path) {
+                    material.pipeline = Some(pipeline);
+                }
+                self.reload_errors.succeeded(path);
+                log::info!("Material loaded ({reason}): {path}");
+            }
+            Err(err) => {
+                log::error!("Material '{path}' failed ({reason}): {err}");
+                self.reload_errors.failed("material", path, &err);
+            }
+        }
+    }
+
+    /// Load the scene's `color_lut` if it names a different file, or drop
+    /// the LUT if the scene has none.
+    fn sync_color_lut(&mut self) {
+        let wanted = self.scene.color_lut.clone();
+        if self.color_lut.as_ref().map(|lut| &lut.path) == wanted.as_ref() {
+            return;
+        }
+        match wanted {
+            Some(path) => {
+                self.color_lut = Some(ColorLutSource {
+                    watcher: SceneWatcher::new(std::path::PathBuf::from(&path)),
+                    path,
+                });
+                self.reload_color_lut("scene load");
+            }
+            None => {
+                self.color_lut = None;
+                self.post_process
+                    .set_lut(&self.gpu.device, &self.gpu.queue, None);
+            }
+        }
+    }
+
+    fn reload_color_lut(&mut self, reason: &str) {
+        let Some(path) = self.color_lut.as_ref().map(|lut| lut.path.clone()) else {
+            return;
+        };
+        let result = sme_core::assets::read_bytes(std::path::Path::new(&path))
+            .and_then(|bytes| ColorLut::from_png_bytes(&bytes));
+        match result {
+            Ok(lut) => {
+                self.post_process
+                    .set_lut(&self.gpu.device, &self.gpu.queue, Some(&lut));
+                self.reload_errors.succeeded(&path);
+                log::info!("Color LUT loaded ({reason}): {path}");
+            }
+            Err(err) => {
+                log::error!("Color LUT '{path}' failed ({reason}): {err}");
+                self.reload_errors.failed("color LUT", &path, &err);
+            }
+        }
+    }
+
+    /// Rebuild the sprite pipelines from the shader files, then every
+    /// material on top of them. On failure the last good pipelines stay.
+    fn reload_sprite_shader(&mut self, reason: &str) {
+        let result = sme_core::assets::read_to_string(std::path::Path::new(SPRITE_SHADER_PATH))
+            .and_then(|shader| {
+                let effects = sme_core::assets::read_to_string(std::path::Path::new(
+                    SPRITE_EFFECT_SHADER_PATH,
+                ))?;
+                self.sprite_pipeline
+                    .reload_shaders(&self.gpu.device, &shader, &effects)
+            });
+        match result {
+            Ok(()) => {
+                self.reload_errors.succeeded(SPRITE_SHADER_PATH);
+                log::info!("Sprite shader loaded ({reason})");
+                let paths: Vec<Arc<str>> = self.materials.keys().cloned().collect();
+                for path in paths {
+                    self.reload_material(&path, reason);
+                }
+            }
+            Err(err) => {
+                log::error!("Sprite shader failed ({reason}): {err}");
+                self.reload_errors
+                    .failed("shader", SPRITE_SHADER_PATH, &err);
+            }
+        }
+    }
+
+    fn pipeline_for(&self, pipeline: &DrawPipeline) -> &wgpu::RenderPipeline {
+        match pipeline {
+            DrawPipeline::Plain => &self.sprite_pipeline.render_pipeline,
+            DrawPipeline::Effects => &self.sprite_pipeline.effect_pipeline,
+            DrawPipeline::Material(path) => self
+                .materials
+                .get(path)
+                .and_then(|m| m.pipeline.as_ref())
+                .unwrap_or(&self.sprite_pipeline.render_pipeline),
+        }
+    }
+
+    /// The environment a replay recorded now would need to play back in.
+    fn replay_header(&self) -> ReplayHeader {
+        ReplayHeader {
+            engine_version: ENGINE_VERSION.to_string(),
+            scene_path: self.scene_path.to_string_lossy().to_string(),
+            collision_path: self.collision_path.to_string_lossy().to_string(),
+            controller: self.world.player_body().config,
+            controller_hash: Some(self.controller_tuning.tuning_hash()),
+            start: self.world.player_body().aabb,
+        }
+    }
+
+    /// Start recording from the current state; call before the first step.
+    fn start_recording(&mut self, path: std::path::PathBuf) {
+        let recorder = ReplayRecorder::new(
+            self.replay_header(),
+            self.time.fixed_dt as f32,
+            self.lua_bridge.rng_seed(),
+        );
+        log::info!("Recording replay to {}", path.display());
+        self.replay_recorder = Some((path, recorder));
+    }
+
+    /// Check `replay` against this engine and queue its inputs, or refuse it.
+    fn start_playback(&mut self, replay: &ReplaySequence) -> Result<(), String> {
+        let warnings =
+            replay.check_environment(&self.replay_header(), self.time.fixed_dt as f32)?;
+        for warning in warnings {
+            log::warn!("Replay: {warning}");
+        }
+        let inputs = replay.expanded_inputs();
+        log::info!("Playing replay: {} steps", inputs.len());
+        self.replay_inputs = Some(inputs.into_iter());
+        Ok(())
+    }
+
+    /// Save the `--record-replay` and `--record-events` files, if recording.
+    fn finish_recording(&mut self) {
+        if let Some((path, recorder)) = self.event_recorder.take() {
+            match save_event_recording(&path, recorder.recording()) {
+                Ok(()) => log::info!("Event recording saved to {}", path.display()),
+                Err(err) => log::error!("Event recording not saved: {err}"),
+            }
+        }
+        let Some((path, recorder)) = self.replay_recorder.take() else {
+            return;
+        };
+        match save_replay_to_path(&path, recorder.sequence()) {
+            Ok(()) => log::info!("Replay saved to {}", path.display()),
+            Err(err) => log::error!("Replay not saved: {err}"),
+        }
+    }
+
+    fn start_event_recording(&mut self, path: std::path::PathBuf) {
+        log::info!("Recording input events to {}", path.display());
+        self.event_recorder = Some((path, EventRecorder::new(self.gpu.size)));
+    }
+
+    fn start_event_playback(&mut self, recording: EventRecording) {
+        if recording.engine_version != ENGINE_VERSION {
+            log::warn!(
+                "Event recording made with engine {}, running {ENGINE_VERSION}",
+                recording.engine_version
+            );
+        }
+        if recording.window_size != self.gpu.size {
+            log::warn!(
+                "Event recording made at {}x{}, window is {}x{}; cursor positions will be off",
+                recording.window_size.0,
+                recording.window_size.1,
+                self.gpu.size.0,
+                self.gpu.size.1
+            );
+        }
+        log::info!("Playing {} input events", recording.events.len());
+        self.event_player = Some(EventPlayer::new(recording));
+    }
+
+    /// Hand the recorded events due this frame to egui and the game, as if
+    /// they had just arrived from the window.
+    fn play_due_events(&mut self) {
+        let Some(player) = &mut self.event_player else {
+            return;
+        };
+        let events = player.due(self.time.frame_count);
+        if player.is_finished() {
+            self.event_player = None;
+            log::info!("Event playback finished; live input resumed");
+        }
+        for event in events {
+            let egui_consumed = self.replay_to_overlay(&event);
+            self.input_event(&event, egui_consumed);
+        }
+    }
+
+    /// Queue a replayed event for the overlay's next egui pass. Returns
+    /// whether egui claims it, by the rules `egui_winit` applies to live
+    /// events.
+    fn replay_to_overlay(&mut self, event: &InputEvent) -> bool {
+        let overlay = &mut self.debug_overlay;
+        let ctx = &overlay.egui_ctx;
+        let pixels_per_point = ctx.pixels_per_point();
+        let cursor = ctx
+            .input(|input| input.pointer.latest_pos())
+            .unwrap_or(egui::Pos2::ZERO);
+        let consumed = match event {
+            InputEvent::Key { .. } => ctx.wants_keyboard_input(),
+            InputEvent::CursorMoved { .. } => ctx.is_using_pointer(),
+            InputEvent::MouseButton { .. } | InputEvent::MouseWheel { .. } => {
+                ctx.wants_pointer_input()
+            }
+        };
+        if let Some(egui_event) = event.to_egui(pixels_per_point, cursor) {
+            let input = overlay.egui_winit_state.egui_input_mut();
+            input.events.push(egui_event);
+        }
+        consumed
+    }
+
+    /// A keyboard or mouse event, live or replayed. `egui_consumed` is
+    /// whether the overlay claimed it.
+    fn input_event(&mut self, event: &InputEvent, egui_consumed: bool) {
+        match *event {
+            InputEvent::Key { code, pressed } if !egui_consumed => {
+                if let Some(engine_key) = self.key_bindings.map(code) {
+                    if pressed {
+                        self.input.key_down(engine_key);
+                    } else {
+                        self.input.key_up(engine_key);
+                    }
+                }
+            }
+            InputEvent::Key { .. } => {}
+
+            InputEvent::CursorMoved { x, y } => {
+                let (last_x, last_y) = self.input.mouse_position;
+                if self.free_camera.is_some() && self.input.is_mouse_held(MouseBtn::Middle) {
+                    let zoom = self.camera.zoom;
+                    self.camera.position.x -= (x - last_x) as f32 / zoom;
+                    self.camera.position.y += (y - last_y) as f32 / zoom;
+                    self.free_camera_moved = true;
+                }
+                self.input.mouse_position = (x, y);
+                self.hover_ui();
+            }
+
+            // Presses over egui stay with egui, but releases always reach the
+            // game so a drag that ends over a window doesn't leave the button held.
+            InputEvent::MouseButton { button, pressed } => match pressed {
+                true if egui_consumed => {}
+                true => {
+                    self.input.mouse_down(button);
+                    if button == MouseBtn::Left {
+                        self.click_ui();
+                    }
+                }
+                false => self.input.mouse_up(button),
+            },
+
+            InputEvent::MouseWheel { .. } if egui_consumed => {}
+            InputEvent::MouseWheel { lines } => {
+                // Free-cam owns the wheel; otherwise it goes to the script.
+                if self.free_camera.is_none() {
+                    self.input.mouse_wheel(lines);
+                    return;
+                }
+                let target = (self.camera.zoom * FREE_CAMERA_ZOOM_STEP.powf(lines))
+                    .clamp(FREE_CAMERA_MIN_ZOOM, FREE_CAMERA_MAX_ZOOM);
+                let (x, y) = self.input.mouse_position;
+                let cursor = glam::Vec2::new(x as f32, y as f32);
+                self.camera.zoom_about(cursor, target / self.camera.zoom);
+                self.free_camera_moved = true;
+            }
+        }
+    }
+
+    /// Pause after a step that entered a breakpoint function and show what
+    /// it saw in the overlay console.
+    fn break_at(&mut self, hits: &[BreakpointHit]) {
+        let step = self.time.fixed_step_count;
+        for hit in hits {
+            let caller = hit.caller.as_deref().map_or_else(
+                || "called by the engine".to_string(),
+                |c| format!("called from {c}"),
+            );
+            let message = format!(
+                "Breakpoint {}() at step {step}, {caller}; actor {}",
+                hit.function, hit.actor
+            );
+            log::warn!("{message}");
+            self.lua_bridge
+                .log_to_console(log::Level::Warn, &hit.script, hit.line, message);
+        }
+        self.stepping = None;
+        self.time.pause_simulation(true);
+        self.debug_overlay.show_console();
+    }
+
+    /// Resume the simulation until `until` holds (see `stepping`).
+    fn start_stepping(&mut self, until: StepUntil) {
+        let stepping = Stepping::new(until, self.world.player_body().grounded);
+        log::info!("{}", stepping.label());
+        self.stepping = Some(stepping);
+        self.time.pause_simulation(false);
+    }
+
+    /// Rewinding would desync a replay being recorded or played back.
+    fn can_rewind(&self) -> bool {
+        self.replay_recorder.is_none() && self.replay_inputs.is_none()
+    }
+
+    fn world_snapshot(&self) -> WorldSnapshot {
+        WorldSnapshot {
+            world: self.world.clone(),
+            spawned: self.spawned.clone(),
+            actor_ids: self.lua_bridge.actor_ids(),
+            projectiles: self.lua_bridge.projectiles().clone(),
+            inventories: self.lua_bridge.inventories().clone(),
+            collected: self.collected.clone(),
+            events: self.lua_bridge.events().clone(),
+            camera_position: self.camera.position,
+            camera_zoom: self.camera.zoom,
+            shake: self.shake.clone(),
+            tweens: self.tweens.clone(),
+            rng: self.lua_bridge.rng_state(),
+        }
+    }
+
+    /// Move through the rewind buffer; free-cam keeps its own view.
+    fn apply_rewind(&mut self, step: RewindStep) {
+        let snapshot = match step {
+            RewindStep::Back => {
+                let current = self.world_snapshot();
+                self.rewind.step_back(|| current)
+            }
+            RewindStep::Forward => self.rewind.step_forward(),
+        };
+        let Some(snapshot) = snapshot else {
+            return;
+        };
+        self.world = snapshot.world;
+        self.spawned = snapshot.spawned;
+        self.lua_bridge.restore_actor_ids(snapshot.actor_ids);
+        *self.lua_bridge.projectiles() = snapshot.projectiles;
+        *self.lua_bridge.inventories() = snapshot.inventories;
+        self.collected = snapshot.collected;
+        *self.lua_bridge.events() = snapshot.events;
+        if self.free_camera.is_none() {
+            self.camera.position = snapshot.camera_position;
+            self.camera.zoom = snapshot.camera_zoom;
+        }
+        self.shake = snapshot.shake;
+        self.tweens = snapshot.tweens;
+        self.lua_bridge.restore_rng(snapshot.rng);
+    }
+
+    /// Surface (or dismiss) a failed script load in the reload banner.
+    fn note_script_reload(&mut self) {
+        let path = self.lua_bridge.script_path().to_string_lossy().to_string();
+        match (self.lua_bridge.status(), self.lua_bridge.last_error()) {
+            (LuaStatus::Error, Some(err)) => self.reload_errors.failed("lua", &path, err),
+            _ => self.reload_errors.succeeded(&path),
+        }
+    }
+
+    /// Apply UI commands queued by the script since the last drain.
+    fn apply_ui_commands(&mut self) {
+        let commands = self.lua_bridge.drain_ui_commands();
+        if commands.is_empty() {
+            return;
+        }
+        if let Some(ui) = &mut self.ui {
+            for command in &commands {
+                ui.apply(command);
+            }
+            self.ui_dirty = true;
+        }
+    }
+
+    /// Enable, resize or (with `length == 0`) remove a sprite's trail. New
+    /// trails take their interval and alpha from the scene, if it has one.
+    fn set_trail(&mut self, sprite_id: &str, length: u32) {
+        if length == 0 {
+            self.trails.remove(sprite_id);
+            return;
+        }
+        let length = length.min(MAX_TRAIL_LENGTH);
+        if let Some(trail) = self.trails.get_mut(sprite_id) {
+            if trail.config.length != length {
+                trail.set_config(TrailConfig {
+                    length,
+                    ..trail.config
+                });
+            }
+            return;
+        }
+        let scene_config = self
+            .scene
+            .layers
+            .iter()
+            .flat_map(|l| &l.sprites)
+            .find(|s| s.id == sprite_id)
+            .and_then(|s| s.trail);
+        let config = TrailConfig {
+            length,
+            ..scene_config.unwrap_or(TrailConfig::new(length))
+        };
+        self.trails
+            .insert(sprite_id.to_string(), Trail::new(config));
+    }
+
+    fn effect_for(&self, sprite_id: &str) -> SpriteEffect {
+        self.effects.get(sprite_id).copied().unwrap_or_default()
+    }
+
+    /// Apply a spawn or despawn queued by the script this step.
+    fn apply_actor_command(&mut self, command: ActorCommand) {
+        match command {
+            ActorCommand::Spawn { id, template, x, y } => {
+                let found = find_template(&self.scene, &template).or_else(|| {
+                    self.multi_atlas
+                        .resolve(&template)
+                        .and_then(|_| atlas_template(&self.scene, &template))
+                });
+                let Some((layer_id, sprite)) = found else {
+                    log::warn!("engine.spawn: no scene sprite or atlas sprite_id '{template}'");
+                    return;
+                };
+                let half_size = self.sprite_half_size(&sprite);
+                let animation = sprite_animation_state(&sprite, &self.animation_registry);
+                let config = self.controller_tuning.config(sprite.controller.as_deref());
+                match self
+                    .spawned
+                    .spawn(id.clone(), (layer_id, sprite), half_size, config, x, y)
+                {
+                    Ok(()) => {
+                        if let Some(state) = animation {
+                            self.world.set_animation(&id, state);
+                        }
+                    }
+                    Err(err) => log::warn!("engine.spawn: {err}"),
+                }
+            }
+            ActorCommand::Despawn(id) => {
+                if self.spawned.despawn(&id) {
+                    self.world.remove_animation(&id);
+                    self.trails.remove(&id);
+                    self.effects.remove(&id);
+                } else {
+                    log::warn!("engine.despawn: no spawned actor '{id}'");
+                }
+            }
+        }
+    }
+
+    /// One fixed step: every system in `FIXED_STEP_ORDER`, in order.
+    fn fixed_step(&mut self) {
+        let mut step = StepContext {
+            dt: self.time.fixed_dt as f32,
+            input: None,
+            events: Vec::new(),
+            contacts_before: Vec::new(),
+            fluids_before: Vec::new(),
+        };
+        self.lua_bridge.save().advance(self.time.fixed_dt);
+        for system in FIXED_STEP_ORDER {
+            self.run_system(system, &mut step);
+        }
+        let hits = self.lua_bridge.take_breakpoint_hits();
+        if !hits.is_empty() {
+            self.break_at(&hits);
+        }
+        if let Some(stepping) = &mut self.stepping {
+            if stepping.after_step(self.world.player_body().grounded, &step.events) {
+                log::info!("{}; paused", stepping.label());
+                self.stepping = None;
+                self.time.pause_simulation(true);
+            }
+        }
+        // One line per step, so two runs of a replay can be diffed.
+        #[cfg(feature = "determinism-audit")]
+        log::debug!(
+            target: "sme_engine::determinism",
+            "step state {:016x}",
+            self.world.state_hash()
+        );
+    }
+
+    fn run_system(&mut self, system: FixedSystem, step: &mut StepContext) {
+        let dt = step.dt;
+        match system {
+            FixedSystem::Script => step.input = Some(self.run_script(dt)),
+            FixedSystem::Player => {
+                let input = step
+                    .input
+                    .expect("the script system runs before the player");
+                step.contacts_before = self.body_contacts();
+                step.fluids_before = self.body_fluids();
+                let player = self.world.player_body_mut();
+                let was_crouching = player.crouching;
+                player.step(input, dt, &self.collision_grid);
+                if player.crouching != was_crouching {
+                    self.lua_bridge.events().emit(EngineEvent::Crouch {
+                        actor: "player".to_string(),
+                        crouching: player.crouching,
+                    });
+                }
+                if player.dash_started {
+                    self.lua_bridge.events().emit(EngineEvent::Dash {
+                        actor: "player".to_string(),
+                        direction: player.facing,
+                    });
+                }
+            }
+            FixedSystem::Bodies => self.spawned.step(dt, &self.collision_grid),
+            FixedSystem::Contacts => {
+                self.emit_contact_events(&step.contacts_before);
+                self.emit_fluid_events(&step.fluids_before);
+            }
+            FixedSystem::Projectiles => self.step_projectiles(dt),
+            FixedSystem::Pickups => self.collect_pickups(),
+            FixedSystem::Shake => {
+                let impact = self.world.player_body().impact_speed - HARD_IMPACT_SPEED;
+                if impact > 0.0 {
+                    self.shake.add_trauma(impact * IMPACT_TRAUMA_PER_SPEED);
+                }
+                self.shake.step(dt);
+            }
+            FixedSystem::Tweens => self.tweens.step(dt),
+            FixedSystem::Transition => self.scene_transition.step(dt),
+            FixedSystem::Trails => self.record_trails(),
+            FixedSystem::Animations => {
+                let finished = self
+                    .world
+                    .tick_animations(&self.animation_registry, FIXED_DT_US);
+                for (sprite, clip) in finished {
+                    self.lua_bridge
+                        .events()
+                        .emit(EngineEvent::AnimationFinished { sprite, clip });
+                }
+            }
+            FixedSystem::Camera => {
+                if self.free_camera.is_none() {
+                    let player = self.world.player_body().aabb;
+                    self.camera.position.x = player.center_x;
+                    self.camera.position.y = player.center_y;
+                }
+            }
+            FixedSystem::Custom => {
+                let mut events = self.lua_bridge.events();
+                let mut ctx = SystemContext {
+                    dt,
+                    player: self.world.player_body_mut(),
+                    collision: &self.collision_grid,
+                    events: &mut events,
+                    scene_transition: &mut self.scene_transition,
+                };
+                for system in &mut self.systems {
+                    system.step(&mut ctx);
+                }
+            }
+            FixedSystem::Events => {
+                let events = self.lua_bridge.events().drain();
+                for event in &events {
+                    log::debug!("Event {}: {}", event.name(), event.payload());
+                }
+                self.lua_bridge.dispatch_events(&events);
+                step.events = events;
+            }
+        }
+    }
+
+    /// Publish actors and input to Lua and apply what `on_update` asks for,
+    /// or run the Rust fallback controller; replay input overrides either.
+    fn run_script(&mut self, dt: f32) -> ControllerInput {
+        // Build input snapshot for Lua
+        let input_snapshot = build_input_snapshot(&self.input, &self.camera);
+
+        // Find the player sprite's animation state for the Lua snapshot
+        let player_snapshot =
+            actor_snapshot(self.world.player_body(), self.world.animation("player"));
+        // The player is listed too, so scripts can address every
+        // actor the same way.
+        let spawned_snapshots: Vec<(&str, ActorSnapshot)> = self
+            .spawned
+            .iter()
+            .map(|actor| {
+                let id = actor.sprite.id.as_str();
+                (id, actor_snapshot(&actor.body, self.world.animation(id)))
+            })
+            .collect();
+        let actors: Vec<(&str, &ActorSnapshot)> = std::iter::once(("player", &player_snapshot))
+            .chain(spawned_snapshots.iter().map(|(id, actor)| (*id, actor)))
+            .collect();
+        self.lua_bridge.set_actors(&actors);
+
+        // Try Lua controller first, fall back to Rust
+        let controller_input = if let Some(intent) =
+            self.lua_bridge
+                .call_update(dt, &input_snapshot, &player_snapshot)
+        {
+            if let Some(dialog_id) = &intent.start_dialog {
+                self.start_dialog(dialog_id);
+            }
+            if let Some((scale, duration)) = intent.time_scale {
+                self.time.set_time_scale(scale, duration);
+            }
+            if intent.hitstop > 0 {
+                self.time.hitstop(intent.hitstop);
+            }
+            self.shake.add_trauma(intent.shake);
+            let player = self.world.player_body_mut();
+            for &(x, y) in &intent.impulses {
+                if !player.add_impulse(x, y) {
+                    log::warn!("engine.actor.add_impulse: dropped ({x}, {y})");
+                }
+            }
+            for (sprite_id, length) in &intent.trails {
+                self.set_trail(sprite_id, *length);
+            }
+            for (sprite_id, effect) in &intent.effects {
+                if effect.is_none() {
+                    self.effects.remove(sprite_id);
+                } else {
+                    self.effects.insert(sprite_id.clone(), *effect);
+                }
+            }
+            if let Some(ambient) = intent.ambient {
+                self.environment.set_ambient(ambient);
+            }
+            for (group, visible) in &intent.layer_groups {
+                self.layer_groups.set_visible(group, *visible);
+            }
+            if let Some(request) = &intent.scene_load {
+                self.scene_transition.start(request.clone());
+            }
+            for (sprite_id, spec) in &intent.tweens {
+                match self.sprite_by_id(sprite_id) {
+                    Some(sprite) => {
+                        let base = sprite_props(sprite);
+                        self.tweens.start(sprite_id, base, spec);
+                    }
+                    None => log::warn!("engine.tween.to: no sprite '{sprite_id}'"),
+                }
+            }
+            self.apply_ui_commands();
+            for value in self.lua_bridge.drain_debug_values() {
+                match value {
+                    ScriptDebugValue::Watch { name, text } => {
+                        self.script_watches.set_watch(&name, text)
+                    }
+                    ScriptDebugValue::Plot { name, value } => {
+                        self.script_watches.push_plot(&name, value)
+                    }
+                }
+            }
+            self.lua_bridge.drain_debug_draws(&mut self.debug_draw);
+            for command in self.lua_bridge.drain_actor_commands() {
+                self.apply_actor_command(command);
+            }
+
+            // Apply animation intents from Lua
+            if intent.stop_animation {
+                self.world.remove_animation("player");
+            } else if let Some(anim_name) = &intent.play_animation {
+                // Only switch if it's a different animation. A name that was
+                // never interned cannot be a loaded clip.
+                let anim_id = Id::get(anim_name);
+                let should_switch = anim_id.is_some_and(|id| {
+                    self.world
+                        .animation("player")
+                        .is_none_or(|s| s.clip_name != id)
+                });
+                if should_switch {
+                    // Find source from the scene sprite definition
+                    let source = self
+                        .scene
+                        .layers
+                        .iter()
+                        .flat_map(|l| &l.sprites)
+                        .find(|s| s.id == "player")
+                        .and_then(|s| s.animation_source.as_deref())
+                        .unwrap_or("");
+                    let source_opt = if source.is_empty() {
+                        None
+                    } else {
+                        Some(Id::new(source))
+                    };
+                    if anim_id.is_some_and(|id| {
+                        self.animation_registry
+                            .resolve_clip(source_opt, id)
+                            .is_some()
+                    }) {
+                        let effective_source = if source.is_empty() {
+                            anim_name.as_str()
+                        } else {
+                            source
+                        };
+                        self.world.set_animation(
+                            "player",
+                            AnimationState::new(effective_source, anim_name),
+                        );
+                    }
+                }
+            }
+
+            ControllerInput {
+                move_x: intent.move_x,
+                jump_pressed: intent.jump_pressed,
+                crouch: intent.crouch,
+                dash: intent.dash,
+            }
+        } else {
+            // Rust fallback controller (identical logic to the Lua script)
+            let mut move_x: f32 = 0.0;
+            if self.input.is_held(Key::Left) || self.input.is_held(Key::A) {
+                move_x -= 1.0;
+            }
+            if self.input.is_held(Key::Right) || self.input.is_held(Key::D) {
+                move_x += 1.0;
+            }
+            let jump_pressed = self.input.is_just_pressed(Key::Space)
+                || self.input.is_just_pressed(Key::W)
+                || self.input.is_just_pressed(Key::Up);
+            let crouch = self.input.is_held(Key::S);
+            let dash = self.input.is_just_pressed(Key::Shift);
+            ControllerInput {
+                move_x,
+                jump_pressed,
+                crouch,
+                dash,
+            }
+        };
+
+        let controller_input = match self.replay_inputs.as_mut().map(Iterator::next) {
+            Some(Some(replayed)) => replayed,
+            Some(None) => {
+                log::info!("Replay finished; back to live input");
+                self.replay_inputs = None;
+                controller_input
+            }
+            None => controller_input,
+        };
+        if let Some((_, recorder)) = &mut self.replay_recorder {
+            recorder.record(controller_input);
+        }
+        controller_input
+    }
+
+    /// Contact flags of the player and every spawned actor, in that order.
+    fn body_contacts(&self) -> Vec<ContactState> {
+        std::iter::once(self.world.player_body().contacts)
+            .chain(self.spawned.iter().map(|actor| actor.body.contacts))
+            .collect()
+    }
+
+    /// Queue a collision event for every contact edge a body gained since
+    /// `before` (from `body_contacts`). Actors spawned this step are skipped.
+    fn emit_contact_events(&self, before: &[ContactState]) {
+        let bodies = std::iter::once(("player", self.world.player_body().contacts)).chain(
+            self.spawned
+                .iter()
+                .map(|actor| (actor.sprite.id.as_str(), actor.body.contacts)),
+        );
+        let mut bus = self.lua_bridge.events();
+        for ((actor, after), before) in bodies.zip(before) {
+            for event in contact_events(actor, *before, after) {
+                bus.emit(event);
+            }
+        }
+    }
+
+    /// Fluid volume around the player and every spawned actor, in that order.
+    fn body_fluids(&self) -> Vec<Option<String>> {
+        std::iter::once(self.world.player_body())
+            .chain(self.spawned.iter().map(|actor| &actor.body))
+            .map(|body| {
+                self.collision_grid
+                    .fluid_at(body.aabb.center_x, body.aabb.center_y)
+                    .map(|fluid| fluid.id.clone())
+            })
+            .collect()
+    }
+
+    /// Queue `fluid_exit` / `fluid_enter` events for every body that changed
+    /// fluid volume since `before` (from `body_fluids`), and splash at the
+    /// surface it crossed. Actors spawned this step are skipped.
+    fn emit_fluid_events(&mut self, before: &[Option<String>]) {
+        let grid = &self.collision_grid;
+        let bodies = std::iter::once(("player", self.world.player_body())).chain(
+            self.spawned
+                .iter()
+                .map(|actor| (actor.sprite.id.as_str(), &actor.body)),
+        );
+        let mut bus = self.lua_bridge.events();
+        for ((actor, body), before) in bodies.zip(before) {
+            let after = grid.fluid_at(body.aabb.center_x, body.aabb.center_y);
+            if before.as_deref() == after.map(|fluid| fluid.id.as_str()) {
+                continue;
+            }
+            let exited = before
+                .as_deref()
+                .and_then(|id| grid.fluids().iter().find(|fluid| fluid.id == id));
+            let crossings = exited
+                .map(|fluid| (fluid, false))
+                .into_iter()
+                .chain(after.map(|fluid| (fluid, true)));
+            for (fluid, entered) in crossings {
+                let surface = glam::Vec2::new(body.aabb.center_x, grid.fluid_surface_y(fluid));
+                for droplet in particles::splash(
+                    surface,
+                    fluid.splash,
+                    fluid.splash_color,
+                    body.velocity_y.abs(),
+                ) {
+                    self.splashes.spawn(droplet);
+                }
+                bus.emit(EngineEvent::Fluid {
+                    actor: actor.to_string(),
+                    fluid: fluid.id.clone(),
+                    entered,
+                    x: surface.x,
+                    y: surface.y,
+                    velocity_y: body.velocity_y,
+                });
+            }
+        }
+    }
+
+    /// Move projectiles after every body has stepped, and hand the impacts
+    /// to the script as `engine.projectile.hits`.
+    fn step_projectiles(&mut self, dt: f32) {
+        let targets: Vec<ProjectileTarget> = std::iter::once((
+            "player",
+            self.world.player_body().aabb,
+            CollisionMask::PLAYER,
+        ))
+        .chain(self.spawned.iter().map(|actor| {
+            (
+                actor.sprite.id.as_str(),
+                actor.body.aabb,
+                CollisionMask::ACTORS,
+            )
+        }))
+        .collect();
+        let hits = self
+            .lua_bridge
+            .projectiles()
+            .step(dt, &self.collision_grid, &targets);
+        let mut bus = self.lua_bridge.events();
+        for hit in &hits {
+            let (other, normal) = match &hit.target {
+                HitTarget::World { normal } => ("world".to_string(), *normal),
+                HitTarget::Actor(id) => (id.clone(), glam::Vec2::ZERO),
+            };
+            bus.emit(EngineEvent::Collision {
+                actor: format!("projectile#{}", hit.id),
+                other,
+                normal,
+            });
+        }
+        drop(bus);
+        self.lua_bridge.set_projectile_hits(&hits);
+    }
+
+    /// Hand the player every pickup they now overlap whose items fit, hide
+    /// or despawn it, and run the item's `on_pickup` hook.
+    fn collect_pickups(&mut self) {
+        let player = self.world.player_body().aabb;
+        let scene_pickups = self
+            .scene
+            .layers
+            .iter()
+            .filter(|layer| self.layer_groups.shows(layer) && layer.space == LayerSpace::World)
+            .flat_map(|layer| &layer.sprites)
+            .filter(|sprite| !self.collected.contains(&sprite.id))
+            .filter_map(|sprite| {
+                let pickup = sprite.pickup.as_ref()?;
+                let (half_w, half_h) = self.sprite_half_size(sprite);
+                let bounds = Aabb {
+                    center_x: sprite.x,
+                    center_y: sprite.y,
+                    half_w,
+                    half_h,
+                };
+                Some((sprite.id.clone(), pickup.clone(), bounds, false))
+            });
+        let spawned_pickups = self.spawned.iter().filter_map(|actor| {
+            let pickup = actor.sprite.pickup.as_ref()?;
+            Some((
+                actor.sprite.id.clone(),
+                pickup.clone(),
+                actor.body.aabb,
+                true,
+            ))
+        });
+        let touched: Vec<(String, ScenePickup, bool)> = scene_pickups
+            .chain(spawned_pickups)
+            .filter(|(_, _, bounds, _)| bounds.overlaps(player))
+            .map(|(id, pickup, _, spawned)| (id, pickup, spawned))
+            .collect();
+
+        for (pickup_id, pickup, spawned) in touched {
+            let hook = {
+                let items = self.lua_bridge.items();
+                let Some(item) = items.get(&pickup.item) else {
+                    continue;
+                };
+                let mut inventories = self.lua_bridge.inventories();
+                let inventory = inventories.get_mut("player");
+                if inventory.room_for(item) < pickup.count {
+                    continue;
+                }
+                inventory.add(item, pickup.count);
+                item.on_pickup.clone()
+            };
+            if spawned {
+                self.apply_actor_command(ActorCommand::Despawn(pickup_id.clone()));
+            } else {
+                self.collected.insert(pickup_id.clone());
+            }
+            log::info!("Picked up {} x{} ({pickup_id})", pickup.item, pickup.count);
+            self.lua_bridge.events().emit(EngineEvent::Trigger {
+                actor: "player".to_string(),
+                trigger: pickup_id.clone(),
+            });
+            if let Some(hook) = hook {
+                self.lua_bridge.call_pickup_hook(
+                    &hook,
+                    "player",
+                    &pickup.item,
+                    pickup.count,
+                    &pickup_id,
+                );
+            }
+        }
+    }
+
+    /// Half the drawn size of `sprite`, for a spawned actor's body.
+    fn sprite_half_size(&self, sprite: &scene::SceneSprite) -> (f32, f32) {
+        let size = self
+            .resolve_sprite_entry(sprite)
+            .map(|entry| match entry.size_px {
+                (0, 0) => self
+                    .textures
+                    .get(entry.texture)
+                    .map_or((0, 0), |texture| texture.size),
+                size => size,
+            })
+            .unwrap_or((0, 0));
+        if size.0 == 0 || size.1 == 0 {
+            return (SPAWN_FALLBACK_HALF_SIZE, SPAWN_FALLBACK_HALF_SIZE);
+        }
+        (
+            size.0 as f32 * sprite.scale_x.abs() * 0.5,
+            size.1 as f32 * sprite.scale_y.abs() * 0.5,
+        )
+    }
+
+    fn player_trail_sample(&self) -> TrailSample {
+        TrailSample::at(
+            self.world.player_body().aabb.center_x,
+            self.world.player_body().aabb.center_y,
+        )
+    }
+
+    /// Sample every trailed sprite; called once per fixed step.
+    fn record_trails(&mut self) {
+        let player = self.player_trail_sample();
+        determinism::assert_stable_order("trails", self.trails.keys());
+        for (sprite_id, trail) in &mut self.trails {
+            let sample = if sprite_id == PLAYER_TRAIL_ID {
+                Some(player)
+            } else {
+                find_sprite(&self.scene, &self.spawned, sprite_id).map(|sprite| {
+                    sprite_trail_sample(self.tweens.apply(sprite_id, sprite_props(sprite)))
+                })
+            };
+            if let Some(sample) = sample {
+                trail.record(sample);
+            }
+        }
+    }
+
+    fn cursor_position(&self) -> (f32, f32) {
+        let (x, y) = self.input.mouse_position;
+        (x as f32, y as f32)
+    }
+
+    fn set_focused(&mut self, focused: bool) {
+        if focused == self.focused {
+            return;
+        }
+        self.focused = focused;
+        if !focused {
+            // Releases that happen in another window never reach us.
+            self.input = InputState::new();
+            self.touch = TouchControls::new();
+        }
+        self.sync_clock();
+        let audio_muted = self.audio_muted();
+        self.lua_bridge.set_app_state(focused, audio_muted);
+        log::info!(
+            "Window {}{}",
+            if focused { "focused" } else { "unfocused" },
+            if self.time.is_clock_paused() {
+                "; simulation paused"
+            } else {
+                ""
+            }
+        );
+    }
+
+    /// Whether audio should be silent. Nothing plays sound yet; this is the
+    /// flag a mixer reads, and scripts see it as `engine.app.audio_muted`.
+    fn audio_muted(&self) -> bool {
+        !self.focused && self.background.mute_audio
+    }
+
+    /// Run the simulation clock unless suspended or paused by the
+    /// background policy.
+    fn sync_clock(&mut self) {
+        let run = !self.gpu.is_suspended() && (self.focused || !self.background.pause_simulation);
+        if run && self.time.is_clock_paused() {
+            self.time.resume_clock();
+        } else if !run && !self.time.is_clock_paused() {
+            self.time.pause_clock();
+        }
+    }
+
+    /// Earliest time of the next redraw while unfocused, if redraws are
+    /// throttled and it hasn't come yet.
+    fn throttled_until(&self) -> Option<std::time::Instant> {
+        let hz = self.background.redraw_hz.filter(|_| !self.focused)?;
+        let next = self.last_redraw + std::time::Duration::from_secs_f64(1.0 / hz.max(1) as f64);
+        (std::time::Instant::now() < next).then_some(next)
+    }
+
+    /// F7: open the Inspector in its own OS window, or close it.
+    fn toggle_inspector(&mut self, event_loop: &ActiveEventLoop) {
+        if self.inspector.take().is_some() {
+            log::info!("Inspector window closed");
+            return;
+        }
+        let config = PlatformConfig {
+            title: "SME Inspector".to_string(),
+            width: 360,
+            height: 420,
+        };
+        let window = sme_platform::window::create_window(event_loop, &config);
+        self.inspector = Some(Inspector::new(&self.gpu, window));
+        log::info!("Inspector window opened");
+    }
+
+    /// Events for windows other than the game window.
+    fn tool_window_event(&mut self, window_id: WindowId, event: &WindowEvent) {
+        let Some(inspector) = self.inspector.as_mut().filter(|i| i.id() == window_id) else {
+            return;
+        };
+        if matches!(event, WindowEvent::CloseRequested) {
+            self.inspector = None;
+            log::info!("Inspector window closed");
+        } else {
+            inspector.handle_window_event(&self.gpu, event);
+        }
+    }
+
+    /// F6: detach the camera from the player, or snap it back. Leaving
+    /// free-cam restores the zoom it started with and re-centers on the
+    /// player; the scene constraints apply again from the same frame.
+    /// Apply settings changed through `engine.settings`.
+    fn apply_settings(&mut self, settings: &Settings) {
+        if settings.width.is_some() || settings.height.is_some() {
+            let current = self
+                .window
+                .inner_size()
+                .to_logical::<u32>(self.window.scale_factor());
+            let size = winit::dpi::LogicalSize::new(
+                settings.width.unwrap_or(current.width),
+                settings.height.unwrap_or(current.height),
+            );
+            if size != current {
+                let _ = self.window.request_inner_size(size);
+            }
+        }
+        self.gpu.set_vsync(settings.vsync);
+        if let Some(tier) = settings.tier().filter(|&tier| tier != self.tier) {
+            self.tier = tier;
+            self.ensure_textures_for_scene();
+            log::info!("Fidelity tier (settings): {}", self.tier);
+        }
+        self.key_bindings = KeyBindings::new(&settings.bindings);
+    }
+
+    fn toggle_free_camera(&mut self) {
+        match self.free_camera.take() {
+            Some(zoom) => {
+                self.camera.zoom = zoom;
+                self.camera.position.x = self.world.player_body().aabb.center_x;
+                self.camera.position.y = self.world.player_body().aabb.center_y;
+                log::info!("Free camera: OFF");
+            }
+            None => {
+                self.free_camera = Some(self.camera.zoom);
+                log::info!("Free camera: ON (wheel zooms, middle-drag pans)");
+            }
+        }
+    }
+
+    fn hover_ui(&mut self) {
+        let cursor = self.cursor_position();
+        if let Some(ui) = &mut self.ui {
+            if ui.update_hover(cursor, self.gpu.size) {
+                self.ui_dirty = true;
+            }
+        }
+    }
+
+    /// Dispatch a left click to the UI button under the cursor, if any.
+    fn click_ui(&mut self) {
+        let cursor = self.cursor_position();
+        let Some((widget_id, on_click)) = self
+            .ui
+            .as_ref()
+            .and_then(|ui| ui.click(cursor, self.gpu.size))
+        else {
+            return;
+        };
+        log::debug!("UI button clicked: {widget_id}");
+        if let Some(callback) = on_click {
+            self.lua_bridge.call_ui_callback(&callback, &widget_id);
+            self.apply_ui_commands();
+        }
+    }
+
+    fn start_dialog(&mut self, dialog_id: &str) {
+        if self.active_dialog.is_some() {
+            return;
+        }
+        let Some(dialog) = self.dialog_registry.get(dialog_id) else {
+            log::warn!("Dialog '{dialog_id}' is not loaded");
+            return;
+        };
+        let lua = &self.lua_bridge;
+        let active = ActiveDialog::start(dialog, &mut |expr| lua.evaluate_condition(expr));
+        log::info!("Dialog started: {dialog_id}");
+        self.active_dialog = Some(active);
+    }
+
+    /// Dialog input for one frame: Up/Down (W/S) move the highlight, Space confirms.
+    fn step_dialog(&mut self) {
+        let Some(active) = &mut self.active_dialog else {
+            return;
+        };
+        if self.input.is_just_pressed(Key::Up) || self.input.is_just_pressed(Key::W) {
+            active.move_selection(-1);
+        }
+        if self.input.is_just_pressed(Key::Down) || self.input.is_just_pressed(Key::S) {
+            active.move_selection(1);
+        }
+        if self.input.is_just_pressed(Key::Space) {
+            self.confirm_dialog();
+        }
+    }
+
+    /// Accept the highlighted choice: run its Lua callback, then move to the
+    /// next node or end the dialog (resuming simulation).
+    fn confirm_dialog(&mut self) {
+        let Some(mut active) = self.active_dialog.take() else {
+            return;
+        };
+        let Some(dialog) = self.dialog_registry.get(&active.dialog_id) else {
+            return;
+        };
+        let confirm = active.confirm(dialog);
+        if let Some(callback) = &confirm.on_select {
+            self.lua_bridge
+                .call_dialog_callback(callback, &active.dialog_id, &active.node_id);
+        }
+        match confirm.next {
+            Some(next) => {
+                let lua = &self.lua_bridge;
+                active.enter(dialog, &next, &mut |expr| lua.evaluate_condition(expr));
+                self.active_dialog = Some(active);
+            }
+            None => log::info!("Dialog ended: {}", active.dialog_id),
+        }
+    }
+
+    /// Localized, display-ready view of the current dialog node.
+    fn dialog_view(&self) -> Option<DialogView> {
+        let active = self.active_dialog.as_ref()?;
+        let dialog = self.dialog_registry.get(&active.dialog_id)?;
+        let node = active.node(dialog)?;
+        Some(DialogView {
+            speaker: node
+                .speaker
+                .as_deref()
+                .map(|key| self.localization.text(key).to_string()),
+            text: self.localization.text(&node.text).to_string(),
+            choices: active
+                .visible_choices
+                .iter()
+                .map(|&i| self.localization.text(&node.choices[i].text).to_string())
+                .collect(),
+            selected: active.selected,
+        })
+    }
+
+    /// Resolve a scene sprite to its atlas entry. Lookup chain:
+    ///  1. If the sprite has an active animation state, use the current frame's sprite_id.
+    ///  2. If `sprite_id` is set, look it up in the multi-atlas registry (stable hash ID).
+    ///  3. Otherwise fall back to the raw `asset` path (legacy/direct-texture mode).
+    fn sprite_mesh_source(&self) -> SpriteMeshSource<'_> {
+        SpriteMeshSource {
+            world: &self.world,
+            animation_registry: &self.animation_registry,
+            multi_atlas: &self.multi_atlas,
+            textures: &self.textures,
+            trails: &self.trails,
+            tweens: &self.tweens,
+            effects: &self.effects,
+            tier: self.tier,
+        }
+    }
+
+    fn resolve_sprite_entry(&self, sprite: &scene::SceneSprite) -> Option<AtlasSpriteEntry> {
+        self.sprite_mesh_source().resolve_sprite_entry(sprite)
+    }
+
+    fn sprite_by_id(&self, sprite_id: &str) -> Option<&scene::SceneSprite> {
+        find_sprite(&self.scene, &self.spawned, sprite_id)
+    }
+
+    fn ensure_textures_for_scene(&mut self) {
+        // Sorted, so textures load (and list) in the same order every run.
+        let mut required_assets = BTreeSet::new();
+        for layer in &self.scene.layers {
+            for sprite in &layer.sprites {
+                if let Some(entry) = self.resolve_sprite_entry(sprite) {
+                    required_assets.insert(entry.texture.as_str());
+                }
+            }
+        }
+
+        if let Some(SceneBackground::Image { asset }) = &self.scene.background {
+            required_assets.insert(asset.as_str());
+        }
+
+        // Later frames of an animation may come from atlases no sprite
+        // draws from yet.
+        for animation in self.asset_graph.assets_of(AssetKind::Animation) {
+            for atlas in self.asset_graph.uses(animation) {
+                if let Some(registry) = self.multi_atlas.get(atlas.as_str()) {
+                    required_assets.insert(registry.texture.as_str());
+                }
+            }
+        }
+
+        if let Some(ui) = &self.ui {
+            for sprite_id in ui.sprite_ids() {
+                match self.multi_atlas.resolve(sprite_id) {
+                    Some(entry) => {
+                        required_assets.insert(entry.texture.as_str());
+                    }
+                    None => log::warn!("UI references missing sprite_id '{}'", sprite_id),
+                }
+            }
+        }
+
+        // Textures already loaded are revisited too: a tier change may want
+        // the other resolution. Keys stay the full-size paths either way.
+        let mut asset_paths = required_assets;
+        asset_paths.extend(self.textures.iter().map(|(key, _)| key.as_str()));
+        let atlas_textures = self.multi_atlas.texture_paths();
+        for asset_path in asset_paths {
+            let key = Id::new(asset_path);
+            let file = self.multi_atlas.texture_file(key, self.tier);
+            if self
+                .textures
+                .get(key)
+                .is_some_and(|texture| texture.file == file)
+            {
+                continue;
+            }
+            // Packed atlases already match the pipeline; loose images don't.
+            let mut texture = load_texture_asset(
+                &self.gpu.device,
+                &self.gpu.queue,
+                file.as_str(),
+                self.premultiplied_alpha() && !atlas_textures.contains(&key),
+            );
+            if file != key {
+                texture.size = (texture.size.0 * 2, texture.size.1 * 2);
+            }
+            if let Some(replaced) = self.textures.get(key) {
+                self.sprite_pipeline.invalidate_texture(replaced.texture.id);
+            }
+            self.textures.insert(key, texture);
+        }
+
+        if !self.textures.contains_key(Id::new(DEBUG_WHITE_ASSET)) {
+            let texture = Texture::from_rgba8(
+                &self.gpu.device,
+                &self.gpu.queue,
+                &[255, 255, 255, 255],
+                1,
+                1,
+                "debug_white",
+            );
+            self.textures.insert(
+                Id::new(DEBUG_WHITE_ASSET),
+                GpuSpriteTexture {
+                    size: texture.size,
+                    texture,
+                    secondary: None,
+                    file: Id::new(DEBUG_WHITE_ASSET),
+                },
+            );
+        }
+        if !self.textures.contains_key(Id::new(PLAYER_ASSET)) {
+            let texture = Texture::from_rgba8(
+                &self.gpu.device,
+                &self.gpu.queue,
+                &[255, 64, 64, 255],
+                1,
+                1,
+                "player_debug",
+            );
+            self.textures.insert(
+                Id::new(PLAYER_ASSET),
+                GpuSpriteTexture {
+                    size: texture.size,
+                    texture,
+                    secondary: None,
+                    file: Id::new(PLAYER_ASSET),
+                },
+            );
+        }
+        self.bind_secondary_textures();
+    }
+
+    /// Pair each sprite texture with the secondary texture of its parallel
+    /// atlas, or with none on Tier 0; the pair picks its bind group when
+    /// drawn. `ensure_textures_for_scene` calls this, and is called again
+    /// after the tier changes.
+    fn bind_secondary_textures(&mut self) {
+        let wanted = if self.tier == FidelityTier::Tier0 {
+            HashMap::new()
+        } else {
+            self.multi_atlas.secondary_textures()
+        };
+        let keys: Vec<Id> = self.textures.iter().map(|(key, _)| key).collect();
+        for key in keys {
+            let mut secondary = wanted.get(&key).copied();
+            if let Some((path, kind)) = secondary {
+                if !self.secondary_textures.contains_key(path) {
+                    match load_secondary_texture(
+                        &self.gpu.device,
+                        &self.gpu.queue,
+                        path.as_str(),
+                        kind,
+                    ) {
+                        Ok(texture) => self.secondary_textures.insert(path, texture),
+                        Err(e) => {
+                            log::warn!("{e}");
+                            secondary = None;
+                        }
+                    }
+                }
+            }
+            if let Some(gpu_texture) = self.textures.get_mut(key) {
+                gpu_texture.secondary = secondary.map(|(path, _)| path);
+            }
+        }
+    }
+
+    fn estimate_memory_mb(&self) -> f32 {
+        let mut bytes = self.texture_memory_bytes();
+        // GPU buffer memory
+        bytes += self.mesh_vertex_capacity * std::mem::size_of::<SpriteVertex>();
+        bytes += self.mesh_index_capacity * std::mem::size_of::<u32>();
+        bytes as f32 / (1024.0 * 1024.0)
+    }
+
+    /// Texture memory (width * height * 4 bytes per pixel), secondary
+    /// textures included.
+    fn texture_memory_bytes(&self) -> usize {
+        let sizes = self.textures.values().map(|tex| tex.texture.size);
+        sizes
+            .chain(self.secondary_textures.values().map(|tex| tex.size))
+            .map(|(w, h)| (w as usize) * (h as usize) * 4)
+            .sum()
+    }
+
+    /// Byte counts for the overlay's Memory section. Scene and rewind sizes
+    /// are shallow (struct sizes, not the strings and vectors they own).
+    fn memory_stats(&self) -> MemoryStats {
+        let texture_bytes: usize = self
+            .textures
+            .values()
+            .map(|tex| tex.texture.size.0 as usize * tex.texture.size.1 as usize * 4)
+            .sum();
+        let mesh_buffer_bytes = self.mesh_vertex_capacity * std::mem::size_of::<SpriteVertex>()
+            + self.mesh_index_capacity * std::mem::size_of::<u32>();
+        let cpu_mesh_bytes = self.cpu_mesh_vertices.1 * std::mem::size_of::<SpriteVertex>()
+            + self.cpu_mesh_indices.1 * std::mem::size_of::<u32>();
+        let scene_sprites: usize = self.scene.layers.iter().map(|l| l.sprites.len()).sum();
+        MemoryStats {
+            categories: vec![
+                ("Textures (GPU)", texture_bytes),
+                ("Mesh buffers (GPU)", mesh_buffer_bytes),
+                ("Mesh (CPU)", cpu_mesh_bytes),
+                (
+                    "Scene sprites",
+                    scene_sprites * std::mem::size_of::<scene::SceneSprite>(),
+                ),
+                (
+                    "Rewind snapshots",
+                    self.rewind.available() * std::mem::size_of::<WorldSnapshot>(),
+                ),
+                ("Lua heap", self.lua_bridge.used_memory()),
+            ],
+            mesh_vertices: self.cpu_mesh_vertices,
+            mesh_indices: self.cpu_mesh_indices,
+            frame_allocations: alloc_stats::take_frame_allocations(),
+        }
+    }
+
+    fn rebuild_scene_mesh(&mut self) {
+        // Build a single CPU-side mesh each frame from scene + debug overlays;
+        // the render thread streams it into GPU buffers.
+        // Rows by scene layer position; layers the build skips stay hidden.
+        let mut layer_stats: Vec<LayerStatsView> = self
+            .scene
+            .layers
+            .iter()
+            .map(|layer| LayerStatsView {
+                id: layer.id.clone(),
+                sprites: layer.sprites.len() as u32,
+                screen: layer.space == LayerSpace::Screen,
+                hidden: true,
+                ..Default::default()
+            })
+            .collect();
+        let (mut vertices, mut indices, draw_calls) = self.build_mesh(&mut layer_stats);
+        let screen_draw_calls =
+            self.append_screen_mesh(&mut vertices, &mut indices, &mut layer_stats);
+        self.ensure_mesh_capacity(vertices.len(), indices.len());
+        self.sprite_count = vertices.len() / 4;
+        self.layer_stats = layer_stats;
+        self.cpu_mesh_vertices = (vertices.len(), vertices.capacity());
+        self.cpu_mesh_indices = (indices.len(), indices.capacity());
+        self.draw_calls = draw_calls;
+        self.screen_draw_calls = screen_draw_calls;
+        self.ui_dirty = false;
+        self.mesh = Arc::new(FrameMesh {
+            vertices,
+            indices,
+            vertex_capacity: self.mesh_vertex_capacity,
+            index_capacity: self.mesh_index_capacity,
+        });
+    }
+
+    /// What the draw call for `texture_key` with `sampler` binds, if the
+    /// texture is loaded.
+    fn texture_binding(&self, texture_key: Id, sampler: SamplerDesc) -> Option<TextureBinding> {
+        let texture = self.textures.get(texture_key)?;
+        Some(TextureBinding {
+            texture: texture.texture.id,
+            secondary: texture
+                .secondary
+                .and_then(|path| self.secondary_textures.get(path))
+                .map(|secondary| secondary.id),
+            sampler,
+        })
+    }
+
+    /// Make the bind group of every texture and sampler this frame draws
+    /// with; the pipeline caches them, so this only creates new pairings.
+    fn prepare_bind_groups(&mut self) {
+        for draw in self.draw_calls.iter().chain(&self.screen_draw_calls) {
+            let Some(texture) = self.textures.get(draw.texture_key) else {
+                continue;
+            };
+            let secondary = texture
+                .secondary
+                .and_then(|path| self.secondary_textures.get(path));
+            self.sprite_pipeline.texture_bind_group(
+                &self.gpu.device,
+                &texture.texture,
+                secondary,
+                draw.sampler,
+            );
+        }
+    }
+
+    /// `draw_calls` resolved to the GPU handles they bind, after
+    /// `prepare_bind_groups`. Draws whose texture is not loaded are left out.
+    fn frame_draws(&self, draw_calls: &[DrawCall]) -> Vec<FrameDraw> {
+        draw_calls
+            .iter()
+            .filter_map(|draw| {
+                let binding = self.texture_binding(draw.texture_key, draw.sampler)?;
+                let texture = self.sprite_pipeline.cached_texture_bind_group(&binding)?;
+                Some(FrameDraw {
+                    pipeline: self.pipeline_for(&draw.pipeline).clone(),
+                    texture: texture.clone(),
+                    indices: draw.index_start..(draw.index_start + draw.index_count),
+                })
+            })
+            .collect()
+    }
+
+    /// Remove texture `key` and the bind groups made for it.
+    fn forget_texture(&mut self, key: Id) {
+        if let Some(texture) = self.textures.remove(key) {
+            self.sprite_pipeline.invalidate_texture(texture.texture.id);
+        }
+    }
+
+    fn tier_color(&self) -> [f32; 4] {
+        // Tier2 gets a subtle warm color boost for "PC polish" feel.
+        match self.tier {
+            FidelityTier::Tier0 => [1.0, 1.0, 1.0, 1.0],
+            FidelityTier::Tier2 => [1.05, 1.02, 0.98, 1.0],
+        }
+    }
+
+    /// The world part of the frame's mesh; each world layer built fills its
+    /// row of `layer_stats`, which holds one per scene layer.
+    fn build_mesh(
+        &self,
+        layer_stats: &mut [LayerStatsView],
+    ) -> (Vec<SpriteVertex>, Vec<u32>, Vec<DrawCall>) {
+        let tier_color = self.tier_color();
+
+        let sprite_count_estimate: usize = self
+            .scene
+            .layers
+            .iter()
+            .filter(|l| self.layer_groups.shows(l))
+            .map(|l| l.sprites.len())
+            .sum::<usize>()
+            + 64; // padding for debug overlays + player
+        let mut vertices = Vec::with_capacity(sprite_count_estimate * 4);
+        let mut indices = Vec::with_capacity(sprite_count_estimate * 6);
+        let mut draw_calls = Vec::with_capacity(16);
+
+        self.append_background(&mut vertices, &mut indices, &mut draw_calls);
+
+        // Visual scene layers render back-to-front according to authored order.
+        // Screen-space layers are drawn later under the screen camera.
+        let mut extended_layers = Vec::new();
+        let mut world_layers = Vec::new();
+        let mut world_positions = Vec::new();
+        for (position, layer) in self.scene.layers.iter().enumerate() {
+            if !self.layer_groups.shows(layer) || layer.space == LayerSpace::Screen {
+                continue;
+            }
+            world_positions.push(position);
+            // Parallax is implemented as a per-layer camera-space offset.
+            let parallax_offset = self.camera.position * (1.0 - layer.parallax);
+            // Spawned actors sort and draw with the layer they were copied
+            // from; collected pickups are left out.
+            let mut spawned = self.spawned.sprites_in_layer(&layer.id).peekable();
+            let has_collected = layer
+                .sprites
+                .iter()
+                .any(|sprite| self.collected.contains(&sprite.id));
+            if spawned.peek().is_some() || has_collected {
+                let mut extended = layer.clone();
+                extended
+                    .sprites
+                    .retain(|sprite| !self.collected.contains(&sprite.id));
+                extended.sprites.extend(spawned.cloned());
+                extended_layers.push((world_layers.len(), extended));
+            }
+            world_layers.push((layer, (parallax_offset.x, parallax_offset.y)));
+        }
+        for (slot, extended) in &extended_layers {
+            world_layers[*slot].0 = extended;
+        }
+        // Widened like the background, so shake never uncovers a culled edge.
+        let (view_min, view_max) = self.view_rect();
+        let margin = glam::Vec2::splat(self.shake.max_offset_px / self.camera.zoom);
+        let rows = self.append_layer_sprites(
+            &world_layers,
+            (view_min - margin, view_max + margin),
+            tier_color,
+            &mut vertices,
+            &mut indices,
+            &mut draw_calls,
+        );
+        for (position, row) in world_positions.into_iter().zip(rows) {
+            layer_stats[position] = row;
+        }
+
+        // Debug collision overlay is rendered as translucent quads in world space.
+        if self.show_collision_debug {
+            self.append_collision_overlay(&mut vertices, &mut indices, &mut draw_calls);
+        }
+
+        // Player visualization uses a simple debug quad driven by controller AABB.
+        let player = self.player_trail_sample();
+        let mut placements = self
+            .trails
+            .get(PLAYER_TRAIL_ID)
+            .map(|trail| trail.copies(&player))
+            .unwrap_or_default();
+        placements.push((player, 1.0));
+        for (placement, alpha) in placements {
+            add_quad(
+                &mut vertices,
+                &mut indices,
+                &mut draw_calls,
+                QuadSpec {
+                    texture_key: Id::new(PLAYER_ASSET),
+                    center_x: placement.x,
+                    center_y: placement.y,
+                    width: self.world.player_body().aabb.half_w * 2.0,
+                    height: self.world.player_body().aabb.half_h * 2.0,
+                    color: [1.0, 0.3, 0.3, 0.9 * alpha],
+                    tex_coords: FULL_TEX_COORDS,
+                    effect: self.effect_for(PLAYER_TRAIL_ID),
+                },
+            );
+        }
+
+        self.append_projectiles(&mut vertices, &mut indices, &mut draw_calls);
+        self.append_ambient(&mut vertices, &mut indices, &mut draw_calls);
+
+        // Contacts and velocity go over the player quad.
+        let thickness = DEBUG_LINE_PX / self.camera.zoom;
+        if self.show_collision_debug {
+            append_debug_draw(
+                &mut vertices,
+                &mut indices,
+                &mut draw_calls,
+                &self.player_contact_overlay(),
+                thickness,
+            );
+        }
+
+        // Debug draws go on top of the world; labels are drawn by the overlay.
+        append_debug_draw(
+            &mut vertices,
+            &mut indices,
+            &mut draw_calls,
+            &self.debug_draw,
+            thickness,
+        );
+
+        (vertices, indices, draw_calls)
+    }
+
+    /// One quad per live projectile, sized by its radius; projectiles
+    /// without a resolvable atlas sprite are plain squares.
+    fn append_projectiles(
+        &self,
+        vertices: &mut Vec<SpriteVertex>,
+        indices: &mut Vec<u32>,
+        draw_calls: &mut Vec<DrawCall>,
+    ) {
+        for projectile in self.lua_bridge.projectiles().iter() {
+            let (texture_key, tex_coords, color) = match projectile
+                .sprite
+                .as_deref()
+                .and_then(|sprite_id| self.multi_atlas.resolve(sprite_id))
+            {
+                Some(entry) if self.textures.contains_key(entry.texture) => {
+                    (entry.texture, entry.tex_coords(), [1.0; 4])
+                }
+                _ => (
+                    Id::new(DEBUG_WHITE_ASSET),
+                    FULL_TEX_COORDS,
+                    PROJECTILE_FALLBACK_COLOR,
+                ),
+            };
+            add_quad(
+                vertices,
+                indices,
+                draw_calls,
+                QuadSpec {
+                    texture_key,
+                    center_x: projectile.position.x,
+                    center_y: projectile.position.y,
+                    width: projectile.radius * 2.0,
+                    height: projectile.radius * 2.0,
+                    color,
+                    tex_coords,
+                    effect: SpriteEffect::None,
+                },
+            );
+        }
+    }
+
+    /// Bottom-left and top-right world corners of the camera view, before
+    /// screen shake.
+    fn view_rect(&self) -> (glam::Vec2, glam::Vec2) {
+        let (view_w, view_h) = self.camera.viewport;
+        let corner_a = self.camera.screen_to_world(glam::Vec2::ZERO);
+        let corner_b = self
+            .camera
+            .screen_to_world(glam::Vec2::new(view_w as f32, view_h as f32));
+        (corner_a.min(corner_b), corner_a.max(corner_b))
+    }
+
+    /// Gradient and image backgrounds are one quad over the view, widened by
+    /// the largest shake offset; solid colors are the clear color instead.
+    /// Tiled backgrounds are a quad per loaded tile, fixed in the world.
+    fn append_background(
+        &self,
+        vertices: &mut Vec<SpriteVertex>,
+        indices: &mut Vec<u32>,
+        draw_calls: &mut Vec<DrawCall>,
+    ) {
+        if let Some(SceneBackground::Tiled { .. }) = &self.scene.background {
+            for tile in self
+                .background_tiles
+                .iter()
+                .flat_map(|s| s.resident_tiles())
+            {
+                let corners = [
+                    tile.min,
+                    glam::Vec2::new(tile.max.x, tile.min.y),
+                    tile.max,
+                    glam::Vec2::new(tile.min.x, tile.max.y),
+                ];
+                add_corner_quad(
+                    vertices,
+                    indices,
+                    draw_calls,
+                    tile.texture,
+                    corners,
+                    [[1.0; 4]; 4],
+                );
+            }
+            return;
+        }
+        let (view_min, view_max) = self.view_rect();
+        let margin = glam::Vec2::splat(self.shake.max_offset_px / self.camera.zoom);
+        append_view_background(
+            vertices,
+            indices,
+            draw_calls,
+            self.scene.background.as_ref(),
+            &self.textures,
+            (view_min - margin, view_max + margin),
+        );
+    }
+
+    /// Load the background tiles that came near the view and drop the ones
+    /// left far behind. Returns whether any changed.
+    fn stream_background_tiles(&mut self) -> bool {
+        let (view_min, view_max) = self.view_rect();
+        let Some(streamer) = &mut self.background_tiles else {
+            return false;
+        };
+        let changes = streamer.update(view_min, view_max);
+        for texture in &changes.load {
+            let gpu_texture = load_texture_asset(
+                &self.gpu.device,
+                &self.gpu.queue,
+                texture.as_str(),
+                self.sprite_pipeline.alpha_mode() == AlphaMode::Premultiplied,
+            );
+            self.textures.insert(*texture, gpu_texture);
+        }
+        let changed = !changes.is_empty();
+        for texture in changes.evict {
+            self.forget_texture(texture);
+        }
+        changed
+    }
+
+    /// Solid scene background or the tier's default, under the ambient tint.
+    fn clear_color(&self, ambient: [f32; 4]) -> wgpu::Color {
+        let color = background_clear_color(self.scene.background.as_ref(), self.tier);
+        let [r, g, b] = std::array::from_fn(|i| (color[i] * ambient[i]) as f64);
+        wgpu::Color { r, g, b, a: 1.0 }
+    }
+
+    /// Advance the ambient layers by `dt` and refit them to the current view.
+    fn step_ambient(&mut self, dt: f32) {
+        let (view_min, view_max) = self.view_rect();
+        for emitter in &mut self.ambient {
+            emitter.step(dt, view_min, view_max, self.tier);
+        }
+    }
+
+    /// Splash droplets arc under gravity and expire.
+    fn step_splashes(&mut self, dt: f32) {
+        for droplet in self.splashes.iter_mut() {
+            droplet.velocity.y -= SPLASH_GRAVITY * dt;
+        }
+        self.splashes.step(dt);
+    }
+
+    /// Ambient weather and splashes go over the world and under the debug
+    /// draws.
+    fn append_ambient(
+        &self,
+        vertices: &mut Vec<SpriteVertex>,
+        indices: &mut Vec<u32>,
+        draw_calls: &mut Vec<DrawCall>,
+    ) {
+        for (corners, color) in self.ambient.iter().flat_map(AmbientEmitter::quads) {
+            add_debug_segment(vertices, indices, draw_calls, corners, color);
+        }
+        for droplet in self.splashes.iter() {
+            let half = droplet.size * 0.5;
+            let corners = [
+                droplet.position - half,
+                droplet.position + glam::Vec2::new(half.x, -half.y),
+                droplet.position + half,
+                droplet.position + glam::Vec2::new(-half.x, half.y),
+            ];
+            add_debug_segment(vertices, indices, draw_calls, corners, droplet.color);
+        }
+    }
+
+    /// Grid lines over the visible part of the collision grid, fluid
+    /// volumes, solid cells tinted by surface material, and the cells the
+    /// player is touching.
+    fn append_collision_overlay(
+        &self,
+        vertices: &mut Vec<SpriteVertex>,
+        indices: &mut Vec<u32>,
+        draw_calls: &mut Vec<DrawCall>,
+    ) {
+        let grid = &self.collision_grid;
+        let cell = grid.cell_size as f32;
+        let origin = glam::Vec2::new(grid.origin.x as f32, grid.origin.y as f32);
+        let extent = glam::Vec2::new(grid.width as f32, grid.height as f32) * cell;
+
+        // Clipped to the view so zooming far out stays cheap.
+        let (view_min, view_max) = self.view_rect();
+        let view_min = view_min.max(origin);
+        let view_max = view_max.min(origin + extent);
+        let mut lines = DebugDraw::new();
+        if view_min.x < view_max.x && view_min.y < view_max.y {
+            let first = ((view_min - origin) / cell).ceil();
+            let last = ((view_max - origin) / cell).floor();
+            for i in first.x as i32..=last.x as i32 {
+                let x = origin.x + i as f32 * cell;
+                lines.line(
+                    glam::Vec2::new(x, view_min.y),
+                    glam::Vec2::new(x, view_max.y),
+                    GRID_LINE_COLOR,
+                );
+            }
+            for j in first.y as i32..=last.y as i32 {
+                let y = origin.y + j as f32 * cell;
+                lines.line(
+                    glam::Vec2::new(view_min.x, y),
+                    glam::Vec2::new(view_max.x, y),
+                    GRID_LINE_COLOR,
+                );
+            }
+        }
+        append_debug_draw(
+            vertices,
+            indices,
+            draw_calls,
+            &lines,
+            GRID_LINE_PX / self.camera.zoom,
+        );
+
+        let fluids = grid.fluids().iter().map(|fluid| {
+            (
+                (fluid.x, fluid.y, fluid.width, fluid.height),
+                FLUID_CELL_COLOR,
+            )
+        });
+        let time_volumes = grid.time_volumes().iter().map(|volume| {
+            let rect = (volume.x, volume.y, volume.width, volume.height);
+            (rect, TIME_VOLUME_COLOR)
+        });
+        for ((x, y, width, height), color) in fluids.chain(time_volumes) {
+            let (width, height) = (width as f32 * cell, height as f32 * cell);
+            add_quad(
+                vertices,
+                indices,
+                draw_calls,
+                QuadSpec {
+                    texture_key: Id::new(DEBUG_WHITE_ASSET),
+                    center_x: origin.x + x as f32 * cell + width * 0.5,
+                    center_y: origin.y + y as f32 * cell + height * 0.5,
+                    width,
+                    height,
+                    color,
+                    tex_coords: FULL_TEX_COORDS,
+                    effect: SpriteEffect::None,
+                },
+            );
+        }
+
+        let touched = grid.touching_cells(self.world.player_body().aabb, TOUCH_SKIN);
+        let solids = grid.solids_iter().map(|solid| {
+            let color = match grid.surface(solid.x, solid.y) {
+                SurfaceMaterial::Normal => SOLID_CELL_COLOR,
+                SurfaceMaterial::Ice { .. } => ICE_CELL_COLOR,
+                SurfaceMaterial::Conveyor { .. } => CONVEYOR_CELL_COLOR,
+            };
+            (*solid, color)
+        });
+        let highlights = touched.into_iter().map(|cell| (cell, TOUCHED_CELL_COLOR));
+        for (solid, color) in solids.chain(highlights) {
+            add_quad(
+                vertices,
+                indices,
+                draw_calls,
+                QuadSpec {
+                    texture_key: Id::new(DEBUG_WHITE_ASSET),
+                    center_x: origin.x + (solid.x as f32 + 0.5) * cell,
+                    center_y: origin.y + (solid.y as f32 + 0.5) * cell,
+                    width: cell,
+                    height: cell,
+                    color,
+                    tex_coords: FULL_TEX_COORDS,
+                    effect: SpriteEffect::None,
+                },
+            );
+        }
+    }
+
+    /// The player's contact flags as colored AABB edges, plus an arrow
+    /// showing its velocity.
+    fn player_contact_overlay(&self) -> DebugDraw {
+        use glam::Vec2;
+
+        let aabb = self.world.player_body().aabb;
+        let min = Vec2::new(aabb.center_x - aabb.half_w, aabb.center_y - aabb.half_h);
+        let max = Vec2::new(aabb.center_x + aabb.half_w, aabb.center_y + aabb.half_h);
+        let contacts = self.world.player_body().contacts;
+        let mut draw = DebugDraw::new();
+        for (touching, a, b, color) in [
+            (
+                contacts.left,
+                min,
+                Vec2::new(min.x, max.y),
+                CONTACT_LEFT_COLOR,
+            ),
+            (
+                contacts.right,
+                Vec2::new(max.x, min.y),
+                max,
+                CONTACT_RIGHT_COLOR,
+            ),
+            (contacts.up, Vec2::new(min.x, max.y), max, CONTACT_UP_COLOR),
+            (
+                contacts.down,
+                min,
+                Vec2::new(max.x, min.y),
+                CONTACT_DOWN_COLOR,
+            ),
+        ] {
+            if touching {
+                draw.line(a, b, color);
+            }
+        }
+
+        let center = Vec2::new(aabb.center_x, aabb.center_y);
+        let velocity = Vec2::new(
+            self.world.player_body().velocity_x,
+            self.world.player_body().velocity_y,
+        );
+        if velocity.length() > 1.0 {
+            let tip = center + velocity * VELOCITY_ARROW_SECONDS;
+            let back = -velocity.normalize() * (VELOCITY_ARROW_HEAD_PX / self.camera.zoom);
+            draw.line(center, tip, VELOCITY_COLOR);
+            for angle in [0.5f32, -0.5] {
+                draw.line(
+                    tip,
+                    tip + Vec2::from_angle(angle).rotate(back),
+                    VELOCITY_COLOR,
+                );
+            }
+        }
+        draw
+    }
+
+    /// Append the sprites of `layers` inside `view`; returns a Debug window
+    /// row for each layer, in `layers` order.
+    fn append_layer_sprites(
+        &self,
+        layers: &[(&scene::SceneLayer, (f32, f32))],
+        view: (glam::Vec2, glam::Vec2),
+        tier_color: [f32; 4],
+        vertices: &mut Vec<SpriteVertex>,
+        indices: &mut Vec<u32>,
+        draw_calls: &mut Vec<DrawCall>,
+    ) -> Vec<LayerStatsView> {
+        let counts = self.sprite_mesh_source().append_layers_in_view(
+            layers,
+            Some(view),
+            tier_color,
+            vertices,
+            indices,
+            draw_calls,
+        );
+        layers
+            .iter()
+            .zip(counts)
+            .map(|((layer, _), counts)| LayerStatsView {
+                id: layer.id.clone(),
+                sprites: layer.sprites.len() as u32,
+                drawn: counts.drawn as u32,
+                culled: counts.culled as u32,
+                screen: layer.space == LayerSpace::Screen,
+                hidden: false,
+            })
+            .collect()
+    }
+
+    /// Append screen-space geometry (origin bottom-left, y up, 1 unit = 1 px)
+    /// after the world and return its draw calls: `space: "screen"` scene
+    /// layers first, then in-game UI widgets on top.
+    fn append_screen_mesh(
+        &self,
+        vertices: &mut Vec<SpriteVertex>,
+        indices: &mut Vec<u32>,
+        layer_stats: &mut [LayerStatsView],
+    ) -> Vec<DrawCall> {
+        let mut draw_calls = Vec::new();
+        let (screen_positions, screen_layers): (Vec<usize>, Vec<_>) = self
+            .scene
+            .layers
+            .iter()
+            .enumerate()
+            .filter(|(_, layer)| {
+                self.layer_groups.shows(layer) && layer.space == LayerSpace::Screen
+            })
+            .map(|(position, layer)| (position, (layer, (0.0, 0.0))))
+            .unzip();
+        let screen = glam::Vec2::new(self.gpu.size.0 as f32, self.gpu.size.1 as f32);
+        let rows = self.append_layer_sprites(
+            &screen_layers,
+            (glam::Vec2::ZERO, screen),
+            self.tier_color(),
+            vertices,
+            indices,
+            &mut draw_calls,
+        );
+        for (position, row) in screen_positions.into_iter().zip(rows) {
+            layer_stats[position] = row;
+        }
+
+        let screen_h = self.gpu.size.1 as f32;
+        let ui_quads = self
+            .ui
+            .as_ref()
+            .map(|ui| ui.layout(self.gpu.size))
+            .unwrap_or_default();
+        for quad in ui_quads {
+            let (texture_key, tex_coords) = match &quad.sprite_id {
+                Some(sprite_id) => match self.multi_atlas.resolve(sprite_id) {
+                    Some(entry) if self.textures.contains_key(entry.texture) => {
+                        (entry.texture, entry.tex_coords())
+                    }
+                    _ => (Id::new(DEBUG_WHITE_ASSET), FULL_TEX_COORDS),
+                },
+                None => (Id::new(DEBUG_WHITE_ASSET), FULL_TEX_COORDS),
+            };
+            add_quad(
+                vertices,
+                indices,
+                &mut draw_calls,
+                QuadSpec {
+                    texture_key,
+                    center_x: quad.rect.x + quad.rect.width * 0.5,
+                    center_y: screen_h - (quad.rect.y + quad.rect.height * 0.5),
+                    width: quad.rect.width,
+                    height: quad.rect.height,
+                    color: quad.color,
+                    tex_coords,
+                    effect: SpriteEffect::None,
+                },
+            );
+        }
+
+        // The scene transition fades over everything, UI included.
+        let fade = self.scene_transition.alpha();
+        if fade > 0.0 {
+            let screen_w = self.gpu.size.0 as f32;
+            add_quad(
+                vertices,
+                indices,
+                &mut draw_calls,
+                QuadSpec {
+                    texture_key: Id::new(DEBUG_WHITE_ASSET),
+                    center_x: screen_w * 0.5,
+                    center_y: screen_h * 0.5,
+                    width: screen_w,
+                    height: screen_h,
+                    color: [0.0, 0.0, 0.0, fade],
+                    tex_coords: FULL_TEX_COORDS,
+                    effect: SpriteEffect::None,
+                },
+            );
+        }
+        draw_calls
+    }
+
+    fn ensure_mesh_capacity(&mut self, vertex_count: usize, index_count: usize) {
+        let needed_vertices = vertex_count.max(1);
+        if needed_vertices > self.mesh_vertex_capacity {
+            self.mesh_vertex_capacity = needed_vertices.next_power_of_two();
+        }
+
+        let needed_indices = index_count.max(1);
+        if needed_indices > self.mesh_index_capacity {
+            self.mesh_index_capacity = needed_indices.next_power_of_two();
+        }
+    }
+}
+
+/// What to do with replays this session.
+#[derive(Default)]
+enum ReplayMode {
+    #[default]
+    Live,
+    Record(std::path::PathBuf),
+    Play(ReplaySequence),
+}
+
+struct App {
+    engine: EngineConfig,
+    /// Applied once the engine state exists.
+    replay: ReplayMode,
+    /// `--play-events` recording, played once the engine state exists.
+    events: Option<EventRecording>,
+    /// Handed to the engine state when it is created.
+    systems: Vec<Box<dyn System>>,
+    state: Option<EngineState>,
+}
+
+impl App {
+    fn new(
+        engine: EngineConfig,
+        replay: ReplayMode,
+        events: Option<EventRecording>,
+        systems: Vec<Box<dyn System>>,
+    ) -> Self {
+        Self {
+            engine,
+            replay,
+            events,
+            systems,
+            state: None,
+        }
+    }
+
+    /// Tear down the engine after a panic escaped the event loop: the
+    /// surface goes first, while the window it was created from still exists.
+    fn release_gpu(&mut self) {
+        if let Some(mut state) = self.state.take() {
+            state.inspector = None;
+            state.gpu.suspend();
+            log::error!("Engine stopped after a panic; GPU surface released");
+        }
+    }
+}
+
+impl ApplicationHandler for App {
+    fn resumed(&mut self, event_loop: &ActiveEventLoop) {
+        if let Some(state) = &mut self.state {
+            if state.gpu.is_suspended() {
+                state.gpu.resume(state.window.clone());
+                // The window may have been resized or rotated meanwhile.
+                state.camera.viewport = state.gpu.size;
+                state.ui_dirty = true;
+                state.sync_clock();
+                log::info!("Resumed; surface recreated");
+            }
+            return;
+        }
+        let settings = SettingsStore::load(&self.engine.window.title);
+        settings.settings().apply_to(&mut self.engine);
+        let window = sme_platform::window::create_window(event_loop, &self.engine.window);
+        log::info!(
+            "Window created: {}x{}",
+            self.engine.window.width,
+            self.engine.window.height
+        );
+        let state = self.state.insert(EngineState::new(
+            window,
+            &self.engine,
+            settings,
+            std::mem::take(&mut self.systems),
+        ));
+        match std::mem::take(&mut self.replay) {
+            ReplayMode::Live => {}
+            ReplayMode::Record(path) => state.start_recording(path),
+            ReplayMode::Play(replay) => {
+                if let Err(err) = state.start_playback(&replay) {
+                    log::error!("{err}");
+                    event_loop.exit();
+                }
+            }
+        }
+        if let Some(recording) = self.events.take() {
+            state.start_event_playback(recording);
+        } else if let Some(path) = self.engine.record_events.clone() {
+            state.start_event_recording(path);
+        }
+    }
+
+    fn suspended(&mut self, _event_loop: &ActiveEventLoop) {
+        if let Some(state) = &mut self.state {
+            state.inspector = None;
+            state.gpu.suspend();
+            state.sync_clock();
+            // Key-ups and touch ends are not delivered while backgrounded.
+            state.input = InputState::new();
+            state.touch = TouchControls::new();
+            log::info!("Suspended; surface dropped, clock paused");
+        }
+    }
+
+    fn about_to_wait(&mut self, event_loop: &ActiveEventLoop) {
+        let Some(state) = &self.state else {
+            return;
+        };
+        if state.gpu.is_suspended() {
+            return;
+        }
+        match state.throttled_until() {
+            Some(next) => event_loop.set_control_flow(ControlFlow::WaitUntil(next)),
+            None => {
+                event_loop.set_control_flow(ControlFlow::Poll);
+                state.window.request_redraw();
+            }
+        }
+    }
+
+    fn window_event(
+        &mut self,
+        event_loop: &ActiveEventLoop,
+        window_id: WindowId,
+        event: WindowEvent,
+    ) {
+        let state = match self.state.as_mut() {
+            Some(s) => s,
+            None => return,
+        };
+        if window_id != state.window.id() {
+            state.tool_window_event(window_id, &event);
+            return;
+        }
+
+        // Keyboard and mouse input goes through `input_event`, where it can
+        // be recorded, and stays out entirely while a recording plays.
+        let input = InputEvent::from_window_event(&event);
+        if input.is_some() && state.event_player.is_some() {
+            return;
+        }
+        let egui_consumed = state
+            .debug_overlay
+            .handle_window_event(&state.window, &event);
+        if let Some(input) = input {
+            if let Some((_, recorder)) = &mut state.event_recorder {
+                recorder.record(state.time.frame_count, input.clone());
+            }
+            state.input_event(&input, egui_consumed);
+            return;
+        }
+
+        match event {
+            WindowEvent::CloseRequested => {
+                log::info!("Close requested, exiting.");
+                state.finish_recording();
+                event_loop.exit();
+            }
+
+            WindowEvent::Focused(focused) => state.set_focused(focused),
+
+            WindowEvent::Resized(physical_size) => {
+                let w = physical_size.width;
+                let h = physical_size.height;
+                if w > 0 && h > 0 {
+                    state.gpu.resize(w, h);
+                    state.camera.viewport = (w, h);
+                    state.ui_dirty = true;
+                    log::info!("Resized to {}x{}", w, h);
+                }
+            }
+
+            // Touches that start over egui stay with egui; moves and lifts of
+            // fingers the controls already own always get through.
+            WindowEvent::Touch(touch) => {
+                let position = (touch.location.x, touch.location.y);
+                match touch.phase {
+                    TouchPhase::Started if egui_consumed => {}
+                    TouchPhase::Started => {
+                        let width = state.gpu.size.0 as f64;
+                        state
+                            .touch
+                            .touch_start(touch.id, position, width, &mut state.input);
+                    }
+                    TouchPhase::Moved => {
+                        state.touch.touch_move(touch.id, position, &mut state.input)
+                    }
+                    TouchPhase::Ended | TouchPhase::Cancelled => {
+                        state.touch.touch_end(touch.id, &mut state.input)
+                    }
+                }
+            }
+
+            WindowEvent::RedrawRequested => {
+                // Nothing runs while suspended; the clock is paused too, so
+                // resuming doesn't fast-forward.
+                if state.gpu.is_suspended() || state.gpu.size.0 == 0 || state.gpu.size.1 == 0 {
+                    return;
+                }
+
+                // Recorded events arrive where live ones would have: after
+                // the previous frame, before this one begins.
+                state.play_due_events();
+
+                // Fixed-step simulation phase.
+                state.last_redraw = std::time::Instant::now();
+                state.time.begin_frame();
+                let mut scene_changed = false;
+
+                // Check for Lua script reload at frame boundary (safe point)
+                let mut script_reloaded = state.lua_bridge.check_reload();
+                if state.input.is_just_pressed(Key::R) {
+                    state.lua_bridge.force_reload();
+                    state.script_watches.clear();
+                    script_reloaded = true;
+                }
+                if script_reloaded {
+                    state.note_script_reload();
+                }
+                if let Some(step) = state.rewind_request.take() {
+                    state.apply_rewind(step);
+                    scene_changed = true;
+                }
+                for (asset, if_changed) in std::mem::take(&mut state.asset_reload_requests) {
+                    state.reload_asset(asset, if_changed);
+                    scene_changed = true;
+                }
+
+                while state.time.should_step() {
+                    if state.input.is_just_pressed(Key::Escape) {
+                        state.finish_recording();
+                        event_loop.exit();
+                        return;
+                    }
+                    if state.input.is_just_pressed(Key::F3) {
+                        state.debug_overlay.toggle();
+                    }
+                    if state.input.is_just_pressed(Key::F4) {
+                        state.show_collision_debug = !state.show_collision_debug;
+                        scene_changed = true;
+                        log::info!(
+                            "Collision debug: {}",
+                            if state.show_collision_debug {
+                                "ON"
+                            } else {
+                                "OFF"
+                            }
+                        );
+                    }
+                    if state.input.is_just_pressed(Key::F7) {
+                        state.toggle_inspector(event_loop);
+                    }
+                    if state.input.is_just_pressed(Key::F6) {
+                        state.toggle_free_camera();
+                        scene_changed = true;
+                    }
+                    if state.input.is_just_pressed(Key::F5) {
+                        state.tier = state.tier.next();
+                        state.ensure_textures_for_scene();
+                        log::info!("Fidelity tier: {}", state.tier);
+                    }
+
+                    if state.input.is_just_pressed(Key::R) {
+                        state.reload_scene("manual trigger (R)");
+                        state.reload_collision("manual trigger (R)");
+                        for i in 0..state.atlas_paths.len() {
+                            state.reload_atlas_if_changed(i, "manual trigger (R)");
+                        }
+                        for i in 0..state.animation_paths.len() {
+                            state.reload_animation(i, "manual trigger (R)");
+                        }
+                        for i in 0..state.loc_paths.len() {
+                            state.reload_string_table(i, "manual trigger (R)");
+                        }
+                        for i in 0..state.dialog_paths.len() {
+                            state.reload_dialog(i, "manual trigger (R)");
+                        }
+                        for i in 0..state.item_paths.len() {
+                            state.reload_items(i, "manual trigger (R)");
+                        }
+                        state.reload_ui("manual trigger (R)");
+                        state.reload_controller_config("manual trigger (R)");
+                        state.reload_sprite_shader("manual trigger (R)");
+                        scene_changed = true;
+                    } else if state.scene_watcher.should_reload()
+                        | poll_watchers(&mut state.scene_include_watchers)
+                    {
+                        state.reload_scene("file watcher");
+                        scene_changed = true;
+                    } else if state.collision_watcher.should_reload() {
+                        state.reload_collision("file watcher");
+                        scene_changed = true;
+                    } else {
+                        for i in 0..state.atlas_watchers.len() {
+                            if state.atlas_watchers[i].should_reload() {
+                                state.reload_atlas(i, "file watcher");
+                                scene_changed = true;
+                            }
+                        }
+                        for i in 0..state.animation_watchers.len() {
+                            if state.animation_watchers[i].should_reload() {
+                                state.reload_animation(i, "file watcher");
+                                scene_changed = true;
+                            }
+                        }
+                        let changed_textures: Vec<Id> = state
+                            .texture_watchers
+                            .iter_mut()
+                            .filter_map(|(path, watcher)| watcher.should_reload().then_some(*path))
+                            .collect();
+                        for texture in changed_textures {
+                            state.reload_texture(texture, "file watcher");
+                            scene_changed = true;
+                        }
+                        for i in 0..state.loc_watchers.len() {
+                            if state.loc_watchers[i].should_reload() {
+                                state.reload_string_table(i, "file watcher");
+                            }
+                        }
+                        for i in 0..state.dialog_watchers.len() {
+                            if state.dialog_watchers[i].should_reload() {
+                                state.reload_dialog(i, "file watcher");
+                            }
+                        }
+                        for i in 0..state.item_watchers.len() {
+                            if state.item_watchers[i].should_reload() {
+                                state.reload_items(i, "file watcher");
+                            }
+                        }
+                        if state.ui_watcher.should_reload() {
+                            state.reload_ui("file watcher");
+                        }
+                        if state.controller_watcher.should_reload() {
+                            state.reload_controller_config("file watcher");
+                        }
+                        let changed_materials: Vec<Arc<str>> = state
+                            .materials
+                            .iter_mut()
+                            .filter_map(|(path, m)| m.watcher.should_reload().then(|| path.clone()))
+                            .collect();
+                        for path in changed_materials {
+                            state.reload_material(&path, "file watcher");
+                        }
+                        if poll_watchers(&mut state.shader_watchers) {
+                            state.reload_sprite_shader("file watcher");
+                        }
+                        if state
+                            .color_lut
+                            .as_mut()
+                            .is_some_and(|lut| lut.watcher.should_reload())
+                        {
+                            state.reload_color_lut("file watcher");
+                        }
+                    }
+
+                    if let Some(request) = state.scene_transition.take_ready() {
+                        state.load_scene(request);
+                        scene_changed = true;
+                    }
+
+                    // An open dialog suspends the simulation until it ends.
+                    if state.active_dialog.is_some() {
+                        state.step_dialog();
+                        break;
+                    }
+
+                    // Paused slots only poll hotkeys and reloads (see `TimeState`).
+                    if state.time.is_step_paused() {
+                        break;
+                    }
+                    state.debug_draw.clear();
+                    if state.can_rewind() {
+                        let snapshot = state.world_snapshot();
+                        state.rewind.record(snapshot);
+                    }
+
+                    state.fixed_step();
+                }
+                state.time.end_frame();
+                let ui_dt_us = (state.time.ui_dt * 1_000_000.0) as u64;
+                if state
+                    .world
+                    .tick_ui_animations(&state.animation_registry, ui_dt_us)
+                {
+                    scene_changed = true;
+                }
+                let changed_settings = state.lua_bridge.settings().take_changed();
+                if let Some(settings) = changed_settings {
+                    state.apply_settings(&settings);
+                }
+                if !state.ambient.is_empty() {
+                    let dt = state.time.steps_this_frame as f64 * state.time.fixed_dt;
+                    state.step_ambient(dt as f32);
+                }
+                if state.splashes.len() > 0 {
+                    let dt = state.time.steps_this_frame as f64 * state.time.fixed_dt;
+                    state.step_splashes(dt as f32);
+                }
+
+                if state.free_camera.is_some() {
+                    state.camera_clamp = CameraClamp::default();
+                    scene_changed |= std::mem::take(&mut state.free_camera_moved);
+                } else {
+                    let camera_before = (state.camera.position, state.camera.zoom);
+                    state.camera_clamp = state
+                        .camera
+                        .apply_constraints(&camera_constraints(&state.scene));
+                    if (state.camera.position, state.camera.zoom) != camera_before {
+                        scene_changed = true;
+                    }
+                }
+
+                if state.stream_background_tiles() {
+                    scene_changed = true;
+                }
+                if scene_changed || state.ui_dirty || state.time.steps_this_frame > 0 {
+                    state.rebuild_scene_mesh();
+                }
+
+                // Render phase: snapshot this frame's finalized simulation
+                // state for the render thread (see `render_thread`).
+                let shake = if state.free_camera.is_some() {
+                    glam::Vec2::ZERO
+                } else {
+                    state.shake.offset_px(state.tier.shake_limit()) / state.camera.zoom
+                };
+                let mut camera_uniform = state.camera.build_uniform_offset(shake);
+                let [r, g, b] = state
+                    .environment
+                    .ambient(state.scene.day_cycle.as_ref(), state.time.total_time);
+                camera_uniform.ambient = [r, g, b, 1.0];
+                let material_uniform = MaterialUniform {
+                    time: state.time.total_time as f32,
+                    zoom: state.camera.zoom,
+                    camera_position: (state.camera.position + shake).to_array(),
+                    viewport: [state.gpu.size.0 as f32, state.gpu.size.1 as f32],
+                    _padding: [0.0; 2],
+                };
+                let ui_camera_uniform = screen_space_camera(state.gpu.size).build_uniform();
+
+                let predicted_bind_count = count_texture_binds(&state.draw_calls)
+                    + count_texture_binds(&state.screen_draw_calls);
+                let dialog_view = state.dialog_view();
+                state.debug_overlay.world_labels = state
+                    .debug_draw
+                    .labels()
+                    .map(|(position, text, color)| WorldLabel {
+                        screen_px: state.camera.world_to_screen(position - shake).to_array(),
+                        text: text.to_string(),
+                        color,
+                    })
+                    .collect();
+                if state.debug_overlay.visible {
+                    state.sync_layer_panel();
+                    state.sync_asset_panel();
+                }
+                let stats = OverlayStats {
+                    draw_calls: (state.draw_calls.len() + state.screen_draw_calls.len()) as u32,
+                    atlas_binds: predicted_bind_count as u32,
+                    sprite_count: state.sprite_count as u32,
+                    layers: state.layer_stats.clone(),
+                    memory_estimate_mb: state.estimate_memory_mb(),
+                    texture_memory_mb: state.texture_memory_bytes() as f32 / (1024.0 * 1024.0),
+                    memory: state.memory_stats(),
+                    tier_label: state.tier.label().to_string(),
+                    lua_status_label: state.lua_bridge.status().label().to_string(),
+                    paused: state.time.is_simulation_paused(),
+                    stepping: state.stepping.as_ref().map(Stepping::label),
+                    rewind_available: if state.can_rewind() {
+                        state.rewind.available()
+                    } else {
+                        0
+                    },
+                    rewind_offset: state.rewind.offset(),
+                    atlas_count: state.multi_atlas.atlas_count() as u32,
+                    active_animations: state.world.animations.len() as u32,
+                    projectiles: {
+                        let pool = state.lua_bridge.projectiles();
+                        (pool.live(), pool.capacity())
+                    },
+                    camera_label: format!(
+                        "({:.0}, {:.0}) zoom {:.2}{}",
+                        state.camera.position.x,
+                        state.camera.position.y,
+                        state.camera.zoom,
+                        if state.free_camera.is_some() {
+                            " [free cam]"
+                        } else {
+                            ""
+                        }
+                    ),
+                    camera_clamp: camera_clamp_label(state.camera_clamp),
+                    lua_errors: if state.debug_overlay.visible {
+                        lua_error_views(&state.lua_bridge)
+                    } else {
+                        Vec::new()
+                    },
+                    console: if state.debug_overlay.visible {
+                        console_views(&state.lua_bridge)
+                    } else {
+                        Vec::new()
+                    },
+                };
+                let (egui_primitives, egui_textures_delta, overlay_actions) =
+                    state.debug_overlay.prepare(
+                        &state.window,
+                        &state.time,
+                        Some(stats.clone()),
+                        dialog_view.as_ref(),
+                        Some(&state.script_watches),
+                        &state.reload_errors,
+                    );
+
+                // Handle overlay button actions
+                if overlay_actions.cycle_tier {
+                    state.tier = state.tier.next();
+                    state.ensure_textures_for_scene();
+                    log::info!("Fidelity tier (overlay): {}", state.tier);
+                }
+                if overlay_actions.toggle_pause {
+                    // Pausing also cancels a running step-until.
+                    let paused = !state.time.is_simulation_paused() || state.stepping.is_some();
+                    state.stepping = None;
+                    state.time.pause_simulation(paused);
+                    log::info!("Simulation {}", if paused { "PAUSED" } else { "RESUMED" });
+                }
+                if overlay_actions.single_step {
+                    state.time.request_step();
+                }
+                if let Some(until) = overlay_actions.step_until {
+                    state.start_stepping(until);
+                }
+                if let Some(code) = overlay_actions.repl {
+                    state.lua_bridge.run_repl(&code);
+                    state.apply_ui_commands();
+                }
+                if overlay_actions.step_back {
+                    state.rewind_request = Some(RewindStep::Back);
+                } else if overlay_actions.step_forward {
+                    state.rewind_request = Some(RewindStep::Forward);
+                }
+                if overlay_actions.layers_changed {
+                    state.apply_layer_panel();
+                }
+                if overlay_actions.layer_groups_changed {
+                    state.apply_layer_group_panel();
+                }
+                if overlay_actions.save_layers {
+                    state.save_layer_panel();
+                }
+                if let Some(reload) = overlay_actions.reload_asset {
+                    let watched = state.watched_assets().into_iter();
+                    state.asset_reload_requests = match reload {
+                        AssetReload::One(row) => watched
+                            .skip(row)
+                            .take(1)
+                            .map(|(asset, _, _)| (asset, false))
+                            .collect(),
+                        AssetReload::Kind(kind) => watched
+                            .filter(|(_, asset_kind, _)| *asset_kind == kind)
+                            .map(|(asset, _, _)| (asset, true))
+                            .collect(),
+                        AssetReload::All => watched.map(|(asset, _, _)| (asset, true)).collect(),
+                    };
+                }
+                if let Some(choice) = overlay_actions.dialog_choice {
+                    if let Some(active) = &mut state.active_dialog {
+                        active.selected = choice;
+                    }
+                    state.confirm_dialog();
+                }
+
+                // Tier 2 with a scene LUT draws the scene into the
+                // post-process texture and grades it onto the surface.
+                let grade = if state.tier == FidelityTier::Tier2 {
+                    state
+                        .post_process
+                        .grade_pass(&state.gpu.device, state.gpu.size)
+                } else {
+                    None
+                };
+                state.prepare_bind_groups();
+                let frame = RenderFrame {
+                    size: state.gpu.size,
+                    camera: camera_uniform,
+                    ui_camera: ui_camera_uniform,
+                    material: material_uniform,
+                    clear_color: state.clear_color(camera_uniform.ambient),
+                    mesh: state.mesh.clone(),
+                    world_draws: state.frame_draws(&state.draw_calls),
+                    screen_draws: state.frame_draws(&state.screen_draw_calls),
+                    grade,
+                    egui_primitives,
+                    pixels_per_point: state.window.scale_factor() as f32,
+                };
+                state.render_thread.submit(frame, egui_textures_delta);
+
+                if let Some(inspector) = &mut state.inspector {
+                    inspector.render(&state.gpu, &state.time, &stats);
+                }
+
+                // Only clear edge-triggered input (just_pressed / just_released)
+                // after at least one fixed step, or paused slot, consumed it.
+                // Otherwise a press that lands on a frame with 0 simulation
+                // steps is silently lost.
+                if state.time.steps_this_frame > 0 || state.time.paused_steps_this_frame > 0 {
+                    state.input.end_frame();
+                }
+            }
+
+            _ => {}
+        }
+    }
+}
+
+/// Pixel sizes of loaded textures by asset key: all mesh building needs to
+/// know about them.
+trait TextureSizes: Sync {
+    fn texture_size(&self, key: Id) -> Option<(u32, u32)>;
+}
+
+impl TextureSizes for InternedMap<GpuSpriteTexture> {
+    fn texture_size(&self, key: Id) -> Option<(u32, u32)> {
+        self.get(key).map(|texture| texture.size)
+    }
+}
+
+impl TextureSizes for InternedMap<(u32, u32)> {
+    fn texture_size(&self, key: Id) -> Option<(u32, u32)> {
+        self.get(key).copied()
+    }
+}
+
+/// The engine state sprite quads are built from. Unlike `EngineState` (whose
+/// Lua bridge is single-threaded) it can be shared with mesh worker threads.
+struct SpriteMeshSource<'a> {
+    world: &'a World,
+    animation_registry: &'a AnimationRegistry,
+    multi_atlas: &'a MultiAtlasRegistry,
+    textures: &'a dyn TextureSizes,
+    trails: &'a StateMap<String, Trail>,
+    tweens: &'a Tweens,
+    effects: &'a HashMap<String, SpriteEffect>,
+    tier: FidelityTier,
+}
+
+impl SpriteMeshSource<'_> {
+    fn effect_for(&self, sprite_id: &str) -> SpriteEffect {
+        self.effects.get(sprite_id).copied().unwrap_or_default()
+    }
+
+    /// The atlas sprite_id to draw: the current animation frame's, else the
+    /// sprite's own.
+    fn effective_sprite_id(&self, sprite: &scene::SceneSprite) -> Option<Id> {
+        // Check if animation state overrides the sprite_id
+        let animated_sprite_id = if let Some(anim_state) = self.world.animation(&sprite.id) {
+            if !anim_state.finished || sprite.sprite_id.is_some() {
+                // Look up the current frame's sprite_id from the animation
+                let clip = self
+                    .animation_registry
+                    .resolve_clip(Some(anim_state.source_id), anim_state.clip_name);
+                clip.and_then(|c| c.frames.get(anim_state.frame_index))
+                    .map(|f| f.sprite_id)
+            } else {
+                None
+            }
+        } else {
+            None
+        };
+
+        animated_sprite_id.or_else(|| sprite.sprite_id.as_deref().map(Id::new))
+    }
+
+    /// Corner coordinates into the sprite's secondary texture; zero when it
+    /// has none or on Tier 0, which binds no secondary textures.
+    fn secondary_tex_coords(&self, sprite: &scene::SceneSprite) -> [[f32; 2]; 4] {
+        if self.tier == FidelityTier::Tier0 {
+            return [[0.0; 2]; 4];
+        }
+        self.effective_sprite_id(sprite)
+            .and_then(|sprite_id| self.multi_atlas.resolve_secondary(sprite_id))
+            .map_or([[0.0; 2]; 4], |secondary| secondary.tex_coords)
+    }
+
+    fn resolve_sprite_entry(&self, sprite: &scene::SceneSprite) -> Option<AtlasSpriteEntry> {
+        if let Some(sprite_id) = self.effective_sprite_id(sprite) {
+            if self.multi_atlas.is_empty() {
+                log::warn!(
+                    "Sprite '{}' references sprite_id '{}' but no atlas is loaded",
+                    sprite.id,
+                    sprite_id
+                );
+                return None;
+            }
+            let Some(entry) = self.multi_atlas.resolve_id(sprite_id) else {
+                log::warn!(
+                    "Sprite '{}' references missing sprite_id '{}'",
+                    sprite.id,
+                    sprite_id
+                );
+                return None;
+            };
+            return Some(entry.clone());
+        }
+
+        let Some(asset) = &sprite.asset else {
+            return None;
+        };
+        Some(AtlasSpriteEntry {
+            texture: Id::new(asset),
+            size_px: (0, 0),
+            uv: [0.0, 0.0, 1.0, 1.0],
+            rotated: false,
+            pivot: (0.5, 0.5),
+            nine_slice: None,
+        })
+    }
+
+    /// Emit the sprites of `layers`, each offset by its `(x, y)` in the
+    /// layer's space. Big scenes are cut into runs of sprites that build in
+    /// parallel (see `jobs`) and are merged back in draw order.
+    fn append_layers(
+        &self,
+        layers: &[(&scene::SceneLayer, (f32, f32))],
+        tier_color: [f32; 4],
+        vertices: &mut Vec<SpriteVertex>,
+        indices: &mut Vec<u32>,
+        draw_calls: &mut Vec<DrawCall>,
+    ) {
+        self.append_layers_in_view(layers, None, tier_color, vertices, indices, draw_calls);
+    }
+
+    /// `append_layers`, culling quads wholly outside `view` (min and max
+    /// corners, after the layer offsets). Returns each layer's sprite counts,
+    /// in `layers` order.
+    fn append_layers_in_view(
+        &self,
+        layers: &[(&scene::SceneLayer, (f32, f32))],
+        view: Option<(glam::Vec2, glam::Vec2)>,
+        tier_color: [f32; 4],
+        vertices: &mut Vec<SpriteVertex>,
+        indices: &mut Vec<u32>,
+        draw_calls: &mut Vec<DrawCall>,
+    ) -> Vec<LayerSpriteCounts> {
+        let orders: Vec<Vec<usize>> = layers
+            .iter()
+            .map(|(layer, _)| layer_draw_order(layer))
+            .collect();
+        let mut sprite_jobs = Vec::new();
+        for (slot, ((layer, offset), order)) in layers.iter().zip(&orders).enumerate() {
+            if layer.occlusion {
+                log::trace!("Rendering occlusion layer '{}'", layer.id);
+            }
+            let color = [
+                tier_color[0],
+                tier_color[1],
+                tier_color[2],
+                tier_color[3] * layer.opacity,
+            ];
+            for run in order.chunks(SPRITES_PER_MESH_JOB) {
+                sprite_jobs.push(SpriteJob {
+                    layer,
+                    slot,
+                    order: run,
+                    offset: *offset,
+                    color,
+                    view,
+                });
+            }
+        }
+
+        let sprite_total: usize = orders.iter().map(Vec::len).sum();
+        let chunks = if sprite_total < PARALLEL_MESH_MIN_SPRITES {
+            sprite_jobs
+                .iter()
+                .map(|job| self.build_sprites(job))
+                .collect()
+        } else {
+            jobs::map_in_order(&sprite_jobs, |job| self.build_sprites(job))
+        };
+        let mut counts = vec![LayerSpriteCounts::default(); layers.len()];
+        for (job, chunk) in sprite_jobs.iter().zip(chunks) {
+            let layer_counts = &mut counts[job.slot];
+            layer_counts.drawn += chunk.sprites.drawn;
+            layer_counts.culled += chunk.sprites.culled;
+            chunk.append_to(vertices, indices, draw_calls);
+        }
+        counts
+    }
+
+    /// Quads for one run of a layer's sprites.
+    fn build_sprites(&self, job: &SpriteJob<'_>) -> MeshChunk {
+        let mut chunk = MeshChunk::default();
+        let material: Option<Arc<str>> = job.layer.material.as_deref().map(Arc::from);
+        let sampler = SamplerDesc {
+            filter: match job.layer.filter {
+                LayerFilter::Nearest => SamplerFilter::Nearest,
+                LayerFilter::Linear => SamplerFilter::Linear,
+            },
+            ..SamplerDesc::default()
+        };
+        for &sprite_idx in job.order {
+            let sprite = &job.layer.sprites[sprite_idx];
+            let Some(sprite_entry) = self.resolve_sprite_entry(sprite) else {
+                log::warn!(
+                    "Skipping sprite '{}' due to unresolved asset reference",
+                    sprite.id
+                );
+                continue;
+            };
+            let Some(texture_size) = self.textures.texture_size(sprite_entry.texture) else {
+                log::warn!("Skipping sprite '{}' due to missing texture", sprite.id);
+                continue;
+            };
+
+            let source_size = if sprite.sprite_id.is_some() || sprite.animation.is_some() {
+                sprite_entry.size_px
+            } else {
+                texture_size
+            };
+            let (pivot_x, pivot_y) = sprite.pivot.unwrap_or(sprite_entry.pivot);
+            let tex_coords = sprite_entry.tex_coords();
+            let tex_coords2 = self.secondary_tex_coords(sprite);
+            let sprite_effect = self.effect_for(&sprite.id);
+            let effect = sprite_effect.vertex_data();
+            // A layer material replaces the fragment stage, effects included.
+            let pipeline = match &material {
+                Some(path) => DrawPipeline::Material(path.clone()),
+                None if sprite_effect.is_none() => DrawPipeline::Plain,
+                None => DrawPipeline::Effects,
+            };
+
+            // Trail copies first (oldest at the back), then the sprite itself.
+            let props = self.tweens.apply(&sprite.id, sprite_props(sprite));
+            let current = sprite_trail_sample(props);
+            let mut placements = self
+                .trails
+                .get(&sprite.id)
+                .map(|trail| trail.copies(&current))
+                .unwrap_or_default();
+            placements.push((current, 1.0));
+
+            let mut drawn = false;
+            for (placement, alpha) in placements {
+                let center_x = placement.x + job.offset.0;
+                let center_y = placement.y + job.offset.1;
+                let sprite_w = source_size.0 as f32 * placement.scale_x;
+                let sprite_h = source_size.1 as f32 * placement.scale_y;
+                let left = -sprite_w * pivot_x;
+                let right = sprite_w * (1.0 - pivot_x);
+                let bottom = -sprite_h * pivot_y;
+                let top = sprite_h * (1.0 - pivot_y);
+                let base_index = chunk.vertices.len() as u32;
+
+                let mut corners = [[left, bottom], [right, bottom], [right, top], [left, top]];
+                let radians = placement.rotation_deg.to_radians();
+                if radians != 0.0 {
+                    let cos_r = radians.cos();
+                    let sin_r = radians.sin();
+                    for c in &mut corners {
+                        let x = c[0];
+                        let y = c[1];
+                        c[0] = x * cos_r - y * sin_r;
+                        c[1] = x * sin_r + y * cos_r;
+                    }
+                }
+
+                if let Some((view_min, view_max)) = job.view {
+                    let center = glam::Vec2::new(center_x, center_y);
+                    let (min, max) = corners.iter().fold(
+                        (glam::Vec2::INFINITY, glam::Vec2::NEG_INFINITY),
+                        |(min, max), corner| {
+                            let corner = center + glam::Vec2::from(*corner);
+                            (min.min(corner), max.max(corner))
+                        },
+                    );
+                    if max.cmplt(view_min).any() || min.cmpgt(view_max).any() {
+                        continue;
+                    }
+                }
+                drawn = true;
+
+                let color = [
+                    job.color[0],
+                    job.color[1],
+                    job.color[2],
+                    job.color[3] * alpha * props.alpha,
+                ];
+                for ((corner, tex_coords), tex_coords2) in
+                    corners.iter().zip(tex_coords).zip(tex_coords2)
+                {
+                    chunk.vertices.push(SpriteVertex {
+                        position: [center_x + corner[0], center_y + corner[1]],
+                        tex_coords,
+                        color,
+                        effect,
+                        tex_coords2,
+                    });
+                }
+
+                let draw_start = chunk.indices.len() as u32;
+                chunk.indices.extend_from_slice(&[
+                    base_index,
+                    base_index + 1,
+                    base_index + 2,
+                    base_index,
+                    base_index + 2,
+                    base_index + 3,
+                ]);
+
+                push_draw_call(
+                    &mut chunk.draw_calls,
+                    sprite_entry.texture,
+                    draw_start,
+                    6,
+                    pipeline.clone(),
+                    sampler,
+                );
+            }
+            if drawn {
+                chunk.sprites.drawn += 1;
+            } else {
+                chunk.sprites.culled += 1;
+            }
+        }
+        chunk
+    }
+}
+
+/// Indices of `layer.sprites` in draw order: authored order, or by y then z
+/// for `SortMode::Y` layers.
+fn layer_draw_order(layer: &scene::SceneLayer) -> Vec<usize> {
+    let mut order: Vec<usize> = (0..layer.sprites.len()).collect();
+    if matches!(layer.sort_mode, SortMode::Y) {
+        order.sort_by(|&a, &b| {
+            layer.sprites[a]
+                .y
+                .partial_cmp(&layer.sprites[b].y)
+                .unwrap_or(std::cmp::Ordering::Equal)
+                .then_with(|| {
+                    layer.sprites[a]
+                        .z
+                        .partial_cmp(&layer.sprites[b].z)
+                        .unwrap_or(std::cmp::Ordering::Equal)
+                })
+        });
+    }
+    order
+}
+
+fn add_quad(
+    vertices: &mut Vec<SpriteVertex>,
+    indices: &mut Vec<u32>,
+    draw_calls: &mut Vec<DrawCall>,
+    spec: QuadSpec,
+) {
+    let half_w = spec.width * 0.5;
+    let half_h = spec.height * 0.5;
+    let base_index = vertices.len() as u32;
+    let effect = spec.effect.vertex_data();
+    let corners = [
+        [spec.center_x - half_w, spec.center_y - half_h],
+        [spec.center_x + half_w, spec.center_y - half_h],
+        [spec.center_x + half_w, spec.center_y + half_h],
+        [spec.center_x - half_w, spec.center_y + half_h],
+    ];
+    for (position, tex_coords) in corners.into_iter().zip(spec.tex_coords) {
+        vertices.push(SpriteVertex {
+            position,
+            tex_coords,
+            color: spec.color,
+            effect,
+            tex_coords2: [0.0, 0.0],
+        });
+    }
+
+    let draw_start = indices.len() as u32;
+    indices.extend_from_slice(&[
+        base_index,
+        base_index + 1,
+        base_index + 2,
+        base_index,
+        base_index + 2,
+        base_index + 3,
+    ]);
+
+    let pipeline = if spec.effect.is_none() {
+        DrawPipeline::Plain
+    } else {
+        DrawPipeline::Effects
+    };
+    push_draw_call(
+        draw_calls,
+        spec.texture_key,
+        draw_start,
+        6,
+        pipeline,
+        SamplerDesc::default(),
+    );
+}
+
+/// Append a draw call, merging with the previous one when the texture matches
+/// and indices are contiguous. This is the core of the batching strategy:
+/// scene sprites are emitted in layer order, so consecutive sprites sharing a
+/// texture atlas collapse into a single `draw_indexed` call.
+/// Flush the lines, rects and circles of `draw` as `thickness`-wide quads.
+fn append_debug_draw(
+    vertices: &mut Vec<SpriteVertex>,
+    indices: &mut Vec<u32>,
+    draw_calls: &mut Vec<DrawCall>,
+    draw: &DebugDraw,
+    thickness: f32,
+) {
+    for (a, b, color) in draw.segments() {
+        add_debug_segment(
+            vertices,
+            indices,
+            draw_calls,
+            segment_quad(a, b, thickness),
+            color,
+        );
+    }
+}
+
+/// A white quad with arbitrary corners, for debug-draw segments.
+fn add_debug_segment(
+    vertices: &mut Vec<SpriteVertex>,
+    indices: &mut Vec<u32>,
+    draw_calls: &mut Vec<DrawCall>,
+    corners: [glam::Vec2; 4],
+    color: [f32; 4],
+) {
+    add_corner_quad(
+        vertices,
+        indices,
+        draw_calls,
+        Id::new(DEBUG_WHITE_ASSET),
+        corners,
+        [color; 4],
+    );
+}
+
+/// Solid background color, or the tier's default behind other backgrounds.
+fn background_clear_color(background: Option<&SceneBackground>, tier: FidelityTier) -> [f32; 3] {
+    match background {
+        Some(SceneBackground::Color { color }) => *color,
+        _ => match tier {
+            FidelityTier::Tier0 => [0.392, 0.584, 0.929],
+            FidelityTier::Tier2 => [0.35, 0.55, 0.95],
+        },
+    }
+}
+
+/// The quad of a gradient or image background over the view `min..max`; an
+/// image covers it, scaled until it fills both axes and centered. Other
+/// backgrounds, and images not loaded, add nothing.
+fn append_view_background(
+    vertices: &mut Vec<SpriteVertex>,
+    indices: &mut Vec<u32>,
+    draw_calls: &mut Vec<DrawCall>,
+    background: Option<&SceneBackground>,
+    textures: &dyn TextureSizes,
+    (mut min, mut max): (glam::Vec2, glam::Vec2),
+) {
+    let (texture_key, colors) = match background {
+        Some(SceneBackground::Gradient { top, bottom }) => {
+            let [top, bottom] = [top, bottom].map(|c| [c[0], c[1], c[2], 1.0]);
+            (Id::new(DEBUG_WHITE_ASSET), [bottom, bottom, top, top])
+        }
+        Some(SceneBackground::Image { asset }) => {
+            let key = Id::new(asset);
+            let Some((width, height)) = textures.texture_size(key) else {
+                return;
+            };
+            let image = glam::Vec2::new(width as f32, height as f32);
+            let scale = ((max - min) / image).max_element();
+            let center = (min + max) * 0.5;
+            min = center - image * scale * 0.5;
+            max = center + image * scale * 0.5;
+            (key, [[1.0; 4]; 4])
+        }
+        _ => return,
+    };
+    let corners = [
+        min,
+        glam::Vec2::new(max.x, min.y),
+        max,
+        glam::Vec2::new(min.x, max.y),
+    ];
+    add_corner_quad(vertices, indices, draw_calls, texture_key, corners, colors);
+}
+
+/// A full-texture quad with arbitrary corners (bottom-left, bottom-right,
+/// top-right, top-left) and a color per corner.
+fn add_corner_quad(
+    vertices: &mut Vec<SpriteVertex>,
+    indices: &mut Vec<u32>,
+    draw_calls: &mut Vec<DrawCall>,
+    texture_key: Id,
+    corners: [glam::Vec2; 4],
+    colors: [[f32; 4]; 4],
+) {
+    let base_index = vertices.len() as u32;
+    let tex_coords = [[0.0, 1.0], [1.0, 1.0], [1.0, 0.0], [0.0, 0.0]];
+    for ((corner, tex_coords), color) in corners.iter().zip(tex_coords).zip(colors) {
+        vertices.push(SpriteVertex {
+            position: corner.to_array(),
+            tex_coords,
+            color,
+            effect: SpriteEffect::None.vertex_data(),
+            tex_coords2: [0.0, 0.0],
+        });
+    }
+    let draw_start = indices.len() as u32;
+    indices.extend_from_slice(&[
+        base_index,
+        base_index + 1,
+        base_index + 2,
+        base_index,
+        base_index + 2,
+        base_index + 3,
+    ]);
+    push_draw_call(
+        draw_calls,
+        texture_key,
+        draw_start,
+        6,
+        DrawPipeline::Plain,
+        SamplerDesc::default(),
+    );
+}
+
+fn push_draw_call(
+    draw_calls: &mut Vec<DrawCall>,
+    texture_key: Id,
+    index_start: u32,
+    index_count: u32,
+    pipeline: DrawPipeline,
+    sampler: SamplerDesc,
+) {
+    if let Some(last) = draw_calls.last_mut() {
+        let contiguous = last.index_start + last.index_count == index_start;
+        if last.texture_key == texture_key
+            && last.pipeline == pipeline
+            && last.sampler == sampler
+            && contiguous
+        {
+            last.index_count += index_count;
+            return;
+        }
+    }
+    draw_calls.push(DrawCall {
+        texture_key,
+        index_start,
+        index_count,
+        pipeline,
+        sampler,
+    });
+}
+
+fn load_texture_asset(
+    device: &wgpu::Device,
+    queue: &wgpu::Queue,
+    asset_path: &str,
+    premultiply: bool,
+) -> GpuSpriteTexture {
+    let bytes_owned;
+    let bytes: &[u8] = match sme_core::assets::read_bytes(std::path::Path::new(asset_path)) {
+        Ok(data) => {
+            bytes_owned = data;
+            &bytes_owned
+        }
+        Err(err) => {
+            log::warn!(
+                "Failed to read texture '{}': {}. Falling back to test sprite.",
+                asset_path,
+                err
+            );
+            FALLBACK_TEXTURE_BYTES
+        }
+    };
+    let texture = if premultiply {
+        Texture::from_bytes_premultiplied(device, queue, bytes, asset_path)
+    } else {
+        Texture::from_bytes(device, queue, bytes, asset_path)
+    };
+    GpuSpriteTexture {
+        size: texture.size,
+        texture,
+        secondary: None,
+        file: Id::new(asset_path),
+    }
+}
+
+fn load_texture_asset_strict(
+    device: &wgpu::Device,
+    queue: &wgpu::Queue,
+    asset_path: &str,
+) -> Result<GpuSpriteTexture, String> {
+    let bytes = sme_core::assets::read_bytes(std::path::Path::new(asset_path))
+        .map_err(|e| format!("Failed to read texture '{}': {e}", asset_path))?;
+    let texture = Texture::from_bytes(device, queue, &bytes, asset_path);
+    Ok(GpuSpriteTexture {
+        size: texture.size,
+        texture,
+        secondary: None,
+        file: Id::new(asset_path),
+    })
+}
+
+fn load_secondary_texture(
+    device: &wgpu::Device,
+    queue: &wgpu::Queue,
+    asset_path: &str,
+    kind: SecondaryKind,
+) -> Result<Texture, String> {
+    let bytes = sme_core::assets::read_bytes(std::path::Path::new(asset_path))
+        .map_err(|e| format!("Failed to read secondary texture '{}': {e}", asset_path))?;
+    Ok(match kind {
+        SecondaryKind::Normal => Texture::from_bytes_linear(device, queue, &bytes, asset_path),
+        SecondaryKind::Emissive => Texture::from_bytes(device, queue, &bytes, asset_path),
+    })
+}
+
+fn preflight_multi_atlas_textures(
+    device: &wgpu::Device,
+    queue: &wgpu::Queue,
+    multi_atlas: &MultiAtlasRegistry,
+) -> Result<(), String> {
+    for texture_path in multi_atlas.texture_paths() {
+        let _ = load_texture_asset_strict(device, queue, texture_path.as_str())?;
+        let half_res = multi_atlas.texture_file(texture_path, FidelityTier::Tier0);
+        if half_res != texture_path {
+            let _ = load_texture_asset_strict(device, queue, half_res.as_str())?;
+        }
+    }
+    for (texture_path, kind) in multi_atlas.secondary_textures().into_values() {
+        let _ = load_secondary_texture(device, queue, texture_path.as_str(), kind)?;
+    }
+    Ok(())
+}
+
+fn build_trails(scene: &SceneFile) -> StateMap<String, Trail> {
+    scene
+        .layers
+        .iter()
+        .flat_map(|l| &l.sprites)
+        .filter_map(|s| Some((s.id.clone(), Trail::new(s.trail?))))
+        .collect()
+}
+
+/// The streamer for a `tiled` background. A tileset that fails to load
+/// leaves the background empty rather than failing the scene.
+fn build_background_tiles(scene: &SceneFile) -> Option<TileStreamer> {
+    let Some(SceneBackground::Tiled { tiles, origin }) = &scene.background else {
+        return None;
+    };
+    match load_tileset_from_path(std::path::Path::new(tiles)) {
+        Ok(tileset) => Some(TileStreamer::new(&tileset, glam::Vec2::from(*origin))),
+        Err(err) => {
+            log::error!("{err}");
+            None
+        }
+    }
+}
+
+/// Weather seeds are fixed per layer index; the layers are visual only and
+/// never draw from the simulation RNG.
+fn build_ambient(scene: &SceneFile) -> Vec<AmbientEmitter> {
+    scene
+        .ambient
+        .iter()
+        .enumerate()
+        .map(|(index, config)| AmbientEmitter::new(*config, AMBIENT_SEED + index as u64))
+        .collect()
+}
+
+fn build_effects(scene: &SceneFile) -> HashMap<String, SpriteEffect> {
+    scene
+        .layers
+        .iter()
+        .flat_map(|l| &l.sprites)
+        .filter_map(|s| {
+            // Validated at load time.
+            let effect = s.effect.as_ref()?.to_effect().ok()?;
+            (!effect.is_none()).then(|| (s.id.clone(), effect))
+        })
+        .collect()
+}
+
+fn sprite_props(sprite: &scene::SceneSprite) -> SpriteProps {
+    SpriteProps {
+        x: sprite.x,
+        y: sprite.y,
+        scale_x: sprite.scale_x,
+        scale_y: sprite.scale_y,
+        rotation_deg: sprite.rotation_deg,
+        alpha: 1.0,
+    }
+}
+
+fn sprite_trail_sample(props: SpriteProps) -> TrailSample {
+    TrailSample {
+        x: props.x,
+        y: props.y,
+        rotation_deg: props.rotation_deg,
+        scale_x: props.scale_x,
+        scale_y: props.scale_y,
+    }
+}
+
+/// A scene sprite, or a spawned actor's sprite, by id.
+fn find_sprite<'a>(
+    scene: &'a SceneFile,
+    spawned: &'a SpawnedActors,
+    sprite_id: &str,
+) -> Option<&'a scene::SceneSprite> {
+    scene
+        .layers
+        .iter()
+        .flat_map(|l| &l.sprites)
+        .find(|s| s.id == sprite_id)
+        .or_else(|| spawned.get(sprite_id).map(|actor| &actor.sprite))
+}
+
+fn build_tweens(scene: &SceneFile) -> Tweens {
+    let mut tweens = Tweens::default();
+    for sprite in scene.layers.iter().flat_map(|l| &l.sprites) {
+        for spec in &sprite.tweens {
+            tweens.start(&sprite.id, sprite_props(sprite), spec);
+        }
+    }
+    tweens
+}
+
+/// Put the animations of screen-space sprites on the UI clock.
+fn mark_ui_clock_animations(world: &mut World, scene: &SceneFile) {
+    for layer in &scene.layers {
+        if layer.space != LayerSpace::Screen {
+            continue;
+        }
+        for sprite in &layer.sprites {
+            world.set_ui_clock(&sprite.id);
+        }
+    }
+}
+
+fn build_animation_states(
+    scene: &SceneFile,
+    animation_registry: &AnimationRegistry,
+) -> Vec<(String, AnimationState)> {
+    let mut states = Vec::new();
+    for layer in &scene.layers {
+        for sprite in &layer.sprites {
+            if let Some(state) = sprite_animation_state(sprite, animation_registry) {
+                states.push((sprite.id.clone(), state));
+            }
+        }
+    }
+    states
+}
+
+/// Starting state for a sprite's `animation`, if it has one that resolves.
+fn sprite_animation_state(
+    sprite: &scene::SceneSprite,
+    animation_registry: &AnimationRegistry,
+) -> Option<AnimationState> {
+    let clip_name = sprite.animation.as_ref()?;
+    let source_id = sprite.animation_source.as_deref().unwrap_or("");
+    // Verify the clip exists before creating state
+    let source_opt = if source_id.is_empty() {
+        None
+    } else {
+        Some(Id::new(source_id))
+    };
+    if animation_registry
+        .resolve_clip(source_opt, Id::new(clip_name))
+        .is_none()
+    {
+        log::warn!(
+            "Sprite '{}' references animation '{}' (source: {:?}) but clip not found",
+            sprite.id,
+            clip_name,
+            sprite.animation_source
+        );
+        return None;
+    }
+    Some(AnimationState::new(
+        if source_id.is_empty() {
+            clip_name
+        } else {
+            source_id
+        },
+        clip_name,
+    ))
+}
+
+/// What `engine.actor` / `engine.actors[id]` report for a body.
+fn actor_snapshot(body: &CharacterController, animation: Option<&AnimationState>) -> ActorSnapshot {
+    ActorSnapshot {
+        x: body.aabb.center_x,
+        y: body.aabb.center_y,
+        half_w: body.aabb.half_w,
+        half_h: body.aabb.half_h,
+        grounded: body.grounded,
+        swimming: body.swimming,
+        crouching: body.crouching,
+        dashing: body.is_dashing(),
+        invulnerable: body.is_invulnerable(),
+        time_scale: body.time_scale,
+        velocity_x: body.velocity_x,
+        velocity_y: body.velocity_y,
+        current_animation: animation.map(|s| s.clip_name.to_string()),
+        animation_finished: animation.is_some_and(|s| s.finished),
+    }
+}
+
+/// One watcher per file pulled in through scene `includes`, so editing a shared
+/// fragment reloads every scene that includes it.
+/// Overlay text naming the clamped axes, e.g. "zoom, x".
+fn camera_clamp_label(clamp: CameraClamp) -> Option<String> {
+    let parts: Vec<&str> = [(clamp.zoom, "zoom"), (clamp.x, "x"), (clamp.y, "y")]
+        .into_iter()
+        .filter_map(|(clamped, name)| clamped.then_some(name))
+        .collect();
+    (!parts.is_empty()).then(|| parts.join(", "))
+}
+
+/// Limits from the scene's `camera` block; unconstrained without one.
+fn camera_constraints(scene: &SceneFile) -> CameraConstraints {
+    let Some(camera) = &scene.camera else {
+        return CameraConstraints::default();
+    };
+    CameraConstraints {
+        min_zoom: camera.min_zoom,
+        max_zoom: camera.max_zoom,
+        bounds: camera.bounds.map(|b| {
+            (
+                glam::Vec2::new(b.min_x, b.min_y),
+                glam::Vec2::new(b.max_x, b.max_y),
+            )
+        }),
+    }
+}
+
+/// Where the player's body starts on `grid`: two cells in from its origin.
+fn player_start(grid: &CollisionGrid) -> Aabb {
+    let cell_world = grid.cell_size as f32;
+    Aabb {
+        center_x: grid.origin.x as f32 + cell_world * 2.0,
+        center_y: grid.origin.y as f32 + cell_world * 2.0,
+        half_w: cell_world * 0.35,
+        half_h: cell_world * 0.45,
+    }
+}
+
+fn build_include_watchers(scene: &SceneFile) -> Vec<SceneWatcher> {
+    scene
+        .included_paths
+        .iter()
+        .map(|path| SceneWatcher::new(path.clone()))
+        .collect()
+}
+
+/// Poll every watcher (no short-circuit) so each one records the latest mtime.
+fn poll_watchers(watchers: &mut [SceneWatcher]) -> bool {
+    watchers
+        .iter_mut()
+        .fold(false, |changed, watcher| watcher.should_reload() | changed)
+}
+
+fn lua_error_views(lua_bridge: &LuaBridge) -> Vec<LuaErrorView> {
+    lua_bridge
+        .recent_errors()
+        .into_iter()
+        .map(|error| LuaErrorView {
+            context: error.context,
+            message: error.message,
+            traceback: error.traceback,
+            line: error.line,
+            excerpt: error.excerpt,
+        })
+        .collect()
+}
+
+fn console_views(lua_bridge: &LuaBridge) -> Vec<ConsoleLineView> {
+    lua_bridge
+        .recent_console()
+        .into_iter()
+        .map(|record| ConsoleLineView {
+            level: record.level,
+            location: match record.line {
+                Some(line) => format!("{}:{line}", record.script),
+                None => record.script,
+            },
+            message: record.message,
+        })
+        .collect()
+}
+
+fn count_texture_binds(draw_calls: &[DrawCall]) -> usize {
+    let mut binds = 0usize;
+    let mut current: Option<Id> = None;
+    for draw in draw_calls {
+        if current != Some(draw.texture_key) {
+            current = Some(draw.texture_key);
+            binds += 1;
+        }
+    }
+    binds
+}
+
+fn map_mouse_button(button: winit::event::MouseButton) -> Option<MouseBtn> {
+    match button {
+        winit::event::MouseButton::Left => Some(MouseBtn::Left),
+        winit::event::MouseButton::Right => Some(MouseBtn::Right),
+        winit::event::MouseButton::Middle => Some(MouseBtn::Middle),
+        _ => None,
+    }
+}
+
+/// Camera whose world units are window pixels with the origin at the
+/// bottom-left corner, used to draw the in-game UI.
+fn screen_space_camera(size: (u32, u32)) -> Camera2D {
+    let mut camera = Camera2D::new(size.0, size.1);
+    camera.position.x = size.0 as f32 * 0.5;
+    camera.position.y = size.1 as f32 * 0.5;
+    camera
+}
+
+fn map_key(key_code: KeyCode) -> Option<Key> {
+    match key_code {
+        KeyCode::ArrowLeft => Some(Key::Left),
+        KeyCode::ArrowRight => Some(Key::Right),
+        KeyCode::ArrowUp => Some(Key::Up),
+        KeyCode::ArrowDown => Some(Key::Down),
+        KeyCode::Escape => Some(Key::Escape),
+        KeyCode::Space => Some(Key::Space),
+        KeyCode::F3 => Some(Key::F3),
+        KeyCode::F4 => Some(Key::F4),
+        KeyCode::F5 => Some(Key::F5),
+        KeyCode::F6 => Some(Key::F6),
+        KeyCode::F7 => Some(Key::F7),
+        KeyCode::KeyW => Some(Key::W),
+        KeyCode::KeyA => Some(Key::A),
+        KeyCode::KeyS => Some(Key::S),
+        KeyCode::KeyD => Some(Key::D),
+        KeyCode::KeyR => Some(Key::R),
+        KeyCode::ShiftLeft | KeyCode::ShiftRight => Some(Key::Shift),
+        _ => None,
+    }
+}
+
+/// Keys scripts see, by their `engine.input` names; also the keys players
+/// can rebind (see `settings`).
+const SCRIPT_KEYS: &[(Key, &str)] = &[
+    (Key::Left, "left"),
+    (Key::Right, "right"),
+    (Key::Up, "up"),
+    (Key::Down, "down"),
+    (Key::Space, "space"),
+    (Key::W, "w"),
+    (Key::A, "a"),
+    (Key::S, "s"),
+    (Key::D, "d"),
+    (Key::Shift, "shift"),
+];
+
+fn build_input_snapshot(input: &InputState, camera: &Camera2D) -> InputSnapshot {
+    let mut held_keys = Vec::new();
+    let mut just_pressed_keys = Vec::new();
+    for &(key, name) in SCRIPT_KEYS {
+        if input.is_held(key) {
+            held_keys.push(name.to_string());
+        }
+        if input.is_just_pressed(key) {
+            just_pressed_keys.push(name.to_string());
+        }
+    }
+
+    let button_names: &[(MouseBtn, &str)] = &[
+        (MouseBtn::Left, "left"),
+        (MouseBtn::Right, "right"),
+        (MouseBtn::Middle, "middle"),
+    ];
+    let mut held_buttons = Vec::new();
+    let mut just_pressed_buttons = Vec::new();
+    for &(button, name) in button_names {
+        if input.is_mouse_held(button) {
+            held_buttons.push(name.to_string());
+        }
+        if input.is_mouse_just_pressed(button) {
+            just_pressed_buttons.push(name.to_string());
+        }
+    }
+
+    let (x, y) = input.mouse_position;
+    let mouse_world = camera.screen_to_world(glam::Vec2::new(x as f32, y as f32));
+    InputSnapshot {
+        held_keys,
+        just_pressed_keys,
+        held_buttons,
+        just_pressed_buttons,
+        mouse_x: mouse_world.x,
+        mouse_y: mouse_world.y,
+        wheel: input.wheel_delta(),
+    }
+}
+
+fn validate_scene_sprite_references(
+    scene: &SceneFile,
+    multi_atlas: &MultiAtlasRegistry,
+) -> Result<(), String> {
+    if !STRICT_SPRITE_ID_RESOLUTION {
+        return Ok(());
+    }
+
+    for layer in &scene.layers {
+        for sprite in &layer.sprites {
+            let Some(sprite_id) = &sprite.sprite_id else {
+                continue;
+            };
+            if multi_atlas.is_empty() {
+                return Err(format!(
+                    "sprite '{}' references sprite_id '{}' but no atlas metadata is loaded",
+                    sprite.id, sprite_id
+                ));
+            }
+            if multi_atlas.resolve(sprite_id).is_none() {
+                return Err(format!(
+                    "sprite '{}' references missing sprite_id '{}'",
+                    sprite.id, sprite_id
+                ));
+            }
+        }
+    }
+
+    Ok(())
+}
+
+/// Desktop/iOS entry point of the `sme_game` binary: parses command-line
+/// flags into an [`EngineConfig`], then runs the game.
+pub fn run() {
+    engine::init_process();
+    let args: Vec<String> = std::env::args().skip(1).collect();
+    if args.first().map(String::as_str) == Some(migrate_cli::MIGRATE_IN_PLACE_FLAG) {
+        if let Err(e) = migrate_cli::migrate_files_in_place(&args[1..]) {
+            log::error!("{e}");
+            std::process::exit(1);
+        }
+        return;
+    }
+    if let Err(e) = EngineConfig::from_args(&args).and_then(|config| Engine::new(config).run()) {
+        log::error!("{e}");
+        std::process::exit(1);
+    }
+}
+
+/// Content pack the Android build ships in the APK's `assets/` folder.
+#[cfg(target_os = "android")]
+const ANDROID_PACK_ASSET: &str = "content.smepack";
+
+/// Android entry point, called from `android_main` once logging is set up.
+/// The APK has no loose files, so all content is served from
+/// [`ANDROID_PACK_ASSET`]; there are no command-line flags.
+#[cfg(target_os = "android")]
+pub fn run_android(app: sme_platform::mobile::AndroidApp) {
+    use winit::platform::android::EventLoopBuilderExtAndroid;
+
+    let pack = sme_platform::mobile::read_apk_asset(&app, ANDROID_PACK_ASSET)
+        .and_then(sme_core::pack::PackArchive::from_bytes)
+        .and_then(|pack| {
+            log::info!(
+                "Mounted content pack '{}' ({} entries)",
+                ANDROID_PACK_ASSET,
+                pack.len()
+            );
+            sme_core::assets::mount_pack(pack)
+        });
+    if let Err(e) = pack {
+        log::error!("{e}");
+        return;
+    }
+
+    log::info!("Saturday Morning Engine starting (Android)...");
+    let event_loop = winit::event_loop::EventLoop::builder()
+        .with_android_app(app)
+        .build()
+        .expect("Failed to create event loop");
+    event_loop.set_control_flow(ControlFlow::Poll);
+    let mut app = App::new(EngineConfig::default(), ReplayMode::Live, None, Vec::new());
+    event_loop.run_app(&mut app).expect("Event loop error");
+}
@@ -0,0 +1,3842 @@
+//! Rust <-> Lua bridge for gameplay scripting.
+//!
+//! Design contract: Lua scripts provide **movement intents** (direction + jump),
+//! never direct mutation of physics state. Rust owns the simulation truth --
+//! Lua only reads actor state (grounded, velocity) and writes into a
+//! `_intent` table that Rust reads back after `on_update(dt)` returns.
+//!
+//! Input is exposed via lookup tables (`_held` / `_just_pressed`) rather than
+//! per-key functions, so the Rust side can bulk-set the entire input snapshot
+//! in one pass without creating closures per key.
+//!
+//! Reload strategy: on file change (mtime polling) or manual trigger (R key),
+//! a **fresh Lua state** is created and the script is re-executed from scratch.
+//! This avoids stale globals and leaked state at the cost of losing any
+//! in-memory Lua variables -- acceptable because all persistent state lives
+//! in Rust (CharacterController, etc.).
+//!
+//! Localized text follows the same bulk-set pattern: Rust resolves the active
+//! language's fallback chain once and installs the result as `engine._loc`;
+//! `engine.loc(key)` is a plain table lookup. The resolved table is kept on the
+//! bridge so a script reload sees the same strings.
+//!
+//! Randomness comes from `engine.rand()` / `engine.rand_range(a, b)`, backed by
+//! a Rust-owned `DeterministicRng`. The generator lives on the bridge (not in
+//! the Lua state), so it survives script reloads, and it may only be drawn from
+//! inside `on_update` (or an event handler, which also runs within the fixed
+//! step) so its state advances strictly with fixed steps.
+//!
+//! Spatial reads go through the same shared-state route: Rust hands the bridge a
+//! copy of the collision grid on load/reload, and `engine.world.*` queries it
+//! directly. Other actors are bulk-set into `engine.actors[id]` each step.
+//!
+//! Script errors are kept in a small ring buffer (`recent_errors`) with the
+//! traceback split out and the offending source lines attached, so the Debug
+//! window can show more than "Lua: ERROR".
+//!
+//! In-game UI changes queue up in `engine._ui_commands` and are drained by Rust
+//! after each call into Lua, so scripts never touch widget state directly.
+//! `engine.debug.*` values for the Debug window use the same queue pattern, as
+//! do `engine.debug.draw_*` shapes, which Rust replays into its `DebugDraw`.
+//!
+//! `engine.spawn` / `engine.despawn` queue `ActorCommand`s the same way. The
+//! spawned actor's id is handed back to the script immediately, from an
+//! `ActorIds` counter that, like the RNG, lives on the bridge and may only
+//! advance inside `on_update`.
+//!
+//! The projectile pool is shared with the Lua closures too: `engine.projectile.spawn`
+//! fires straight into it and returns the projectile's id (or nil when the pool
+//! is full), and Rust writes each step's impacts back as `engine.projectile.hits`.
+//! Item definitions and inventories are shared the same way, so
+//! `engine.inventory.*` reads and changes them directly, as is the event bus:
+//! `engine.events.emit` queues a `Custom` event on it, and `dispatch_events`
+//! calls the handlers scripts registered in `engine._event_handlers`. Event
+//! payloads cross the boundary as JSON values.
+//!
+//! Save game state (see `save`) is shared the same way: `engine.save.*`
+//! declares and changes persistent variables and reads and writes slots
+//! directly. Values cross as JSON, like event payloads. So are player
+//! settings (see `settings`): `engine.settings.*` changes them, and Rust
+//! applies and saves the changes after the frame's steps.
+//!
+//! Breakpoints (`--break-on <name>`, see `set_breakpoints`) install a call
+//! hook that notes every entry into the named functions -- found by name, or
+//! by definition site for handlers Rust calls directly -- with the caller and
+//! `engine.actor` at that moment, into the console. The engine pauses after
+//! the step that hit one. The hook is only installed while breakpoints are set.
+//!
+//! The overlay console's REPL line runs in the script's own Lua state
+//! (`run_repl`), between frames, so it sees and changes the script's globals
+//! under the same rules as the script: only the engine API and Lua's safe
+//! standard libraries, and no `engine.rand*` or `engine.spawn` ids outside a
+//! fixed step. Results and errors are written back into the console.
+
+use std::cell::RefCell;
+use std::collections::{BTreeMap, HashMap, VecDeque};
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex};
+use std::time::SystemTime;
+
+use glam::Vec2;
+use mlua::prelude::*;
+
+use sme_core::rng::{DeterministicRng, DEFAULT_RNG_SEED};
+use sme_render::SpriteEffect;
+
+use crate::actors::{ActorCommand, ActorIds};
+use crate::collision::{Aabb, CollisionGrid};
+use crate::debug_draw::{DebugDraw, DEFAULT_DEBUG_COLOR};
+use crate::events::{EngineEvent, EventBus};
+use crate::items::{Inventories, ItemRegistry};
+use crate::projectiles::{
+    CollisionMask, HitTarget, ProjectileHit, ProjectilePool, ProjectileSpec,
+    DEFAULT_PROJECTILE_LIFETIME, DEFAULT_PROJECTILE_RADIUS,
+};
+use crate::save::SaveState;
+use crate::settings::SettingsStore;
+use crate::transition::SceneLoad;
+use crate::tween::{Easing, Repeat, TweenProperty, TweenSpec};
+use crate::ui::UiCommand;
+
+/// Intent returned by Lua's on_update — describes desired motion, not direct mutation.
+#[derive(Debug, Clone, Default)]
+pub struct LuaIntent {
+    pub move_x: f32,
+    pub jump_pressed: bool,
+    /// Held crouch, the optional third `set_intent` argument.
+    pub crouch: bool,
+    /// `engine.actor.dash()` was called this step.
+    pub dash: bool,
+    /// `engine.actor.add_impulse(x, y)` calls, in call order.
+    pub impulses: Vec<(f32, f32)>,
+    pub play_animation: Option<String>,
+    pub stop_animation: bool,
+    /// Dialog id requested via `engine.dialog.start(id)`.
+    pub start_dialog: Option<String>,
+    /// `engine.time.set_scale(scale, duration)`; duration in simulated seconds.
+    pub time_scale: Option<(f64, Option<f64>)>,
+    /// `engine.time.hitstop(frames)`: fixed steps to freeze.
+    pub hitstop: u32,
+    /// Screen-shake trauma added via `engine.camera.shake(amount)`.
+    pub shake: f32,
+    /// `engine.sprite.set_trail(id, length)` calls; length 0 removes the trail.
+    pub trails: Vec<(String, u32)>,
+    /// `engine.sprite.set_effect(id, kind, param)` calls, by sprite id.
+    pub effects: Vec<(String, SpriteEffect)>,
+    /// `engine.environment.set_ambient(r, g, b)` as `Some(Some(color))`,
+    /// `engine.environment.clear_ambient()` as `Some(None)`; the last call wins.
+    pub ambient: Option<Option<[f32; 3]>>,
+    /// `engine.scene.set_group_visible(group, visible)` calls, by group name.
+    pub layer_groups: Vec<(String, bool)>,
+    /// `engine.tween.to(id, targets, duration, easing, repeat)` calls, in call order.
+    pub tweens: Vec<(String, TweenSpec)>,
+    /// `engine.scene.load(path, opts)`; the last call wins.
+    pub scene_load: Option<SceneLoad>,
+}
+
+/// Script errors kept for the Debug window; older ones are dropped.
+pub const LUA_ERROR_HISTORY: usize = 8;
+/// Source lines shown on each side of the offending line.
+const EXCERPT_CONTEXT: usize = 2;
+
+/// One script error, parsed for display.
+#[derive(Debug, Clone, PartialEq)]
+pub struct LuaErrorRecord {
+    /// Where the error surfaced, e.g. `on_update` or `script load`.
+    pub context: String,
+    /// Error message without the traceback.
+    pub message: String,
+    pub traceback: Option<String>,
+    /// Line in the script file the message points at, if any.
+    pub line: Option<usize>,
+    /// `(line_number, text)` around `line`.
+    pub excerpt: Vec<(usize, String)>,
+}
+
+/// Script `print` / `engine.log.*` lines kept for the overlay console.
+pub const SCRIPT_LOG_HISTORY: usize = 200;
+
+/// One line a script logged.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ScriptLogRecord {
+    pub level: log::Level,
+    /// File name of the calling script, e.g. `controller.lua`.
+    pub script: String,
+    pub line: Option<usize>,
+    pub message: String,
+}
+
+type ScriptConsole = Arc<Mutex<VecDeque<ScriptLogRecord>>>;
+
+/// Calls one breakpoint records at most per step, so a hot function can't
+/// flood the console.
+const BREAKPOINT_HITS_PER_STEP: usize = 8;
+
+/// An entry into a breakpoint function.
+#[derive(Debug, Clone, PartialEq)]
+pub struct BreakpointHit {
+    pub function: String,
+    /// File name of the function's script, e.g. `controller.lua`.
+    pub script: String,
+    /// Line the function is defined on.
+    pub line: Option<usize>,
+    /// `file:line` of the Lua call, or `None` when Rust called it.
+    pub caller: Option<String>,
+    /// `engine.actor` at the call, e.g. `x=12.0 y=40.0 vx=0.0 vy=-3.5 grounded`.
+    pub actor: String,
+}
+
+/// Value reported through `engine.debug.*` for the Debug window.
+#[derive(Debug, Clone, PartialEq)]
+pub enum ScriptDebugValue {
+    /// `engine.debug.watch(name, value)`; `value` already run through `tostring`.
+    Watch { name: String, text: String },
+    /// `engine.debug.plot(name, v)`.
+    Plot { name: String, value: f32 },
+}
+
+/// Status of the Lua runtime for display in the debug overlay.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum LuaStatus {
+    /// Script loaded and running normally.
+    Loaded,
+    /// Script had an error; engine is using Rust fallback controller.
+    Error,
+    /// No script file found; engine is using Rust fallback controller.
+    Fallback,
+}
+
+impl LuaStatus {
+    pub fn label(self) -> &'static str {
+        match self {
+            Self::Loaded => "Lua: loaded",
+            Self::Error => "Lua: ERROR",
+            Self::Fallback => "Lua: fallback",
+        }
+    }
+}
+
+impl std::fmt::Display for LuaStatus {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(self.label())
+    }
+}
+
+/// Snapshot of engine state passed to Lua each frame.
+pub struct ActorSnapshot {
+    /// AABB center in world units.
+    pub x: f32,
+    pub y: f32,
+    pub half_w: f32,
+    pub half_h: f32,
+    pub grounded: bool,
+    /// Center inside a collision fluid volume.
+    pub swimming: bool,
+    /// AABB shrunk to crouch height.
+    pub crouching: bool,
+    pub dashing: bool,
+    /// I-frames; scripts should not damage the actor.
+    pub invulnerable: bool,
+    /// Step multiplier of the time volume the actor is in; 1.0 outside.
+    pub time_scale: f32,
+    pub velocity_x: f32,
+    pub velocity_y: f32,
+    pub current_animation: Option<String>,
+    pub animation_finished: bool,
+}
+
+/// Snapshot of input state passed to Lua each frame.
+#[derive(Debug, Clone, Default)]
+pub struct InputSnapshot {
+    pub held_keys: Vec<String>,
+    pub just_pressed_keys: Vec<String>,
+    /// Mouse buttons by name: "left", "right", "middle".
+    pub held_buttons: Vec<String>,
+    pub just_pressed_buttons: Vec<String>,
+    /// Cursor position in world units.
+    pub mouse_x: f32,
+    pub mouse_y: f32,
+    /// Wheel notches scrolled since the last step; positive is away from the user.
+    pub wheel: f32,
+}
+
+/// RNG shared with the `engine.rand*` closures. `stepping` is only true while
+/// `on_update` runs.
+struct ScriptRng {
+    rng: DeterministicRng,
+    stepping: bool,
+}
+
+pub struct LuaBridge {
+    lua: Lua,
+    script_path: PathBuf,
+    last_modified: Option<SystemTime>,
+    status: LuaStatus,
+    last_error: Option<String>,
+    loc_strings: HashMap<String, String>,
+    /// Window focus as `(focused, audio_muted)`, mirrored into `engine.app`.
+    app_state: (bool, bool),
+    rng: Arc<Mutex<ScriptRng>>,
+    actor_ids: Arc<Mutex<ActorIds>>,
+    projectiles: Arc<Mutex<ProjectilePool>>,
+    items: Arc<Mutex<ItemRegistry>>,
+    inventories: Arc<Mutex<Inventories>>,
+    events: Arc<Mutex<EventBus>>,
+    save: Arc<Mutex<SaveState>>,
+    settings: Arc<Mutex<SettingsStore>>,
+    world: Arc<Mutex<Option<CollisionGrid>>>,
+    errors: RefCell<VecDeque<LuaErrorRecord>>,
+    /// Kept across script reloads.
+    console: ScriptConsole,
+    /// Function names to break on; kept across script reloads.
+    breakpoints: Vec<String>,
+    breakpoint_hits: Arc<Mutex<Vec<BreakpointHit>>>,
+}
+
+impl LuaBridge {
+    /// Create a new LuaBridge. If the script file doesn't exist, starts in Fallback mode.
+    pub fn new(script_path: PathBuf) -> Self {
+        let lua = Lua::new();
+        let mut bridge = Self {
+            lua,
+            script_path,
+            last_modified: None,
+            status: LuaStatus::Fallback,
+            last_error: None,
+            loc_strings: HashMap::new(),
+            app_state: (true, false),
+            rng: Arc::new(Mutex::new(ScriptRng {
+                rng: DeterministicRng::new(DEFAULT_RNG_SEED),
+                stepping: false,
+            })),
+            actor_ids: Arc::new(Mutex::new(ActorIds::default())),
+            projectiles: Arc::new(Mutex::new(ProjectilePool::default())),
+            items: Arc::new(Mutex::new(ItemRegistry::new())),
+            inventories: Arc::new(Mutex::new(Inventories::default())),
+            events: Arc::new(Mutex::new(EventBus::default())),
+            save: Arc::new(Mutex::new(SaveState::default())),
+            settings: Arc::new(Mutex::new(SettingsStore::default())),
+            world: Arc::new(Mutex::new(None)),
+            errors: RefCell::new(VecDeque::new()),
+            console: Arc::new(Mutex::new(VecDeque::new())),
+            breakpoints: Vec::new(),
+            breakpoint_hits: Arc::new(Mutex::new(Vec::new())),
+        };
+        bridge.try_load_script();
+        bridge
+    }
+
+    pub fn status(&self) -> LuaStatus {
+        self.status
+    }
+
+    /// Bytes the Lua heap is using.
+    pub fn used_memory(&self) -> usize {
+        self.lua.used_memory()
+    }
+
+    pub fn last_error(&self) -> Option<&str> {
+        self.last_error.as_deref()
+    }
+
+    /// Most recent script errors, newest first.
+    pub fn recent_errors(&self) -> Vec<LuaErrorRecord> {
+        self.errors.borrow().iter().rev().cloned().collect()
+    }
+
+    /// Recent `print` / `engine.log.*` lines, oldest first.
+    pub fn recent_console(&self) -> Vec<ScriptLogRecord> {
+        self.console
+            .lock()
+            .expect("script console poisoned")
+            .iter()
+            .cloned()
+            .collect()
+    }
+
+    /// Add a line to the overlay console, as if `script` had logged it.
+    pub fn log_to_console(
+        &self,
+        level: log::Level,
+        script: &str,
+        line: Option<usize>,
+        message: String,
+    ) {
+        push_console(
+            &self.console,
+            ScriptLogRecord {
+                level,
+                script: script.to_string(),
+                line,
+                message,
+            },
+        );
+    }
+
+    /// Run a REPL line and write it, its results and any error to the
+    /// console. An expression's values are shown; a statement runs as is.
+    pub fn run_repl(&self, code: &str) {
+        self.log_to_console(log::Level::Info, "repl", None, format!("> {code}"));
+        match self.eval_repl(code) {
+            Ok(text) if text.is_empty() => {}
+            Ok(text) => self.log_to_console(log::Level::Info, "repl", None, text),
+            Err(err) => self.log_to_console(log::Level::Error, "repl", None, err),
+        }
+    }
+
+    fn eval_repl(&self, code: &str) -> Result<String, String> {
+        if self.status != LuaStatus::Loaded {
+            return Err(format!("{}; no script state to run in", self.status));
+        }
+        let values = match self
+            .lua
+            .load(format!("return {code}"))
+            .set_name("=repl")
+            .eval::<LuaMultiValue>()
+        {
+            Err(LuaError::SyntaxError { .. }) => self
+                .lua
+                .load(code)
+                .set_name("=repl")
+                .eval::<LuaMultiValue>(),
+            result => result,
+        }
+        .map_err(|err| err.to_string())?;
+        let tostring: LuaFunction = self
+            .lua
+            .globals()
+            .get("tostring")
+            .map_err(|err| err.to_string())?;
+        let parts = values
+            .into_iter()
+            .map(|value| tostring.call::<String>(value))
+            .collect::<LuaResult<Vec<_>>>()
+            .map_err(|err| err.to_string())?;
+        Ok(parts.join("\t"))
+    }
+
+    /// Break on entry into each function named in `names`: globals, or
+    /// dotted paths into global tables (`Player.on_land`).
+    pub fn set_breakpoints(&mut self, names: Vec<String>) {
+        self.breakpoints = names;
+        self.install_breakpoints();
+    }
+
+    /// Breakpoint calls since the last take, oldest first.
+    pub fn take_breakpoint_hits(&self) -> Vec<BreakpointHit> {
+        std::mem::take(&mut *self.breakpoint_hits.lock().expect("breakpoints poisoned"))
+    }
+
+    /// (Re)install the call hook for `breakpoints` on the current Lua state.
+    fn install_breakpoints(&self) {
+        if self.breakpoints.is_empty() {
+            self.lua.remove_hook();
+            return;
+        }
+        // Definition sites of the named functions that exist now, for calls
+        // Lua can't name (event handlers and callbacks called from Rust).
+        let globals = self.lua.globals();
+        let sites: Vec<(String, String, usize)> = self
+            .breakpoints
+            .iter()
+            .filter_map(|name| {
+                let function = lookup_function(&globals, name)?;
+                let info = function.info();
+                Some((name.clone(), info.source?, info.line_defined?))
+            })
+            .collect();
+        for name in &self.breakpoints {
+            if !sites.iter().any(|(site, _, _)| site == name) {
+                log::warn!("Breakpoint '{name}' names no function yet; matching calls by name");
+            }
+        }
+        let names = self.breakpoints.clone();
+        let hits = self.breakpoint_hits.clone();
+        self.lua
+            .set_hook(LuaHookTriggers::ON_CALLS, move |lua, debug| {
+                let source = debug.source();
+                let called = debug.names().name.map(|name| name.into_owned());
+                let function = sites
+                    .iter()
+                    .find(|(_, chunk, line)| {
+                        source.source.as_deref() == Some(chunk.as_str())
+                            && source.line_defined == Some(*line)
+                    })
+                    .map(|(name, _, _)| name.clone())
+                    .or_else(|| {
+                        // By the last part of the name it was called by.
+                        let called = called?;
+                        names
+                            .iter()
+                            .find(|name| name.rsplit('.').next() == Some(called.as_str()))
+                            .cloned()
+                    });
+                let Some(function) = function else {
+                    return Ok(LuaVmState::Continue);
+                };
+                let mut hits = hits.lock().expect("breakpoints poisoned");
+                if hits.len() < BREAKPOINT_HITS_PER_STEP {
+                    hits.push(BreakpointHit {
+                        function,
+                        script: source
+                            .source
+                            .as_deref()
+                            .map_or_else(|| "?".to_string(), script_file_name),
+                        line: source.line_defined,
+                        caller: lua.inspect_stack(1).and_then(|caller| {
+                            let line = usize::try_from(caller.curr_line()).ok()?;
+                            let chunk = caller.source().source?.into_owned();
+                            Some(format!("{}:{line}", script_file_name(&chunk)))
+                        }),
+                        actor: actor_summary(lua),
+                    });
+                }
+                Ok(LuaVmState::Continue)
+            });
+    }
+
+    /// Log a script error and keep it for the Debug window.
+    fn record_error(&self, context: &str, err: &LuaError) {
+        log::error!("Lua {} error: {}", context, err);
+        let record = parse_lua_error(context, &err.to_string(), &self.script_path);
+        let mut errors = self.errors.borrow_mut();
+        if errors.len() == LUA_ERROR_HISTORY {
+            errors.pop_front();
+        }
+        errors.push_back(record);
+    }
+
+    /// Restart the script RNG from `seed` (e.g. from `--seed` or a replay).
+    pub fn set_rng_seed(&mut self, seed: u64) {
+        self.rng
+            .lock()
+            .expect("script rng poisoned")
+            .rng
+            .reseed(seed);
+    }
+
+    pub fn rng_seed(&self) -> u64 {
+        self.rng.lock().expect("script rng poisoned").rng.seed()
+    }
+
+    /// Copy of the script RNG, for rewind snapshots.
+    pub fn rng_state(&self) -> DeterministicRng {
+        self.rng.lock().expect("script rng poisoned").rng.clone()
+    }
+
+    /// Put the script RNG back to a state from `rng_state`.
+    pub fn restore_rng(&self, rng: DeterministicRng) {
+        self.rng.lock().expect("script rng poisoned").rng = rng;
+    }
+
+    /// Copy of the spawned-actor id counter, for rewind snapshots.
+    pub fn actor_ids(&self) -> ActorIds {
+        *self.actor_ids.lock().expect("actor ids poisoned")
+    }
+
+    pub fn restore_actor_ids(&self, ids: ActorIds) {
+        *self.actor_ids.lock().expect("actor ids poisoned") = ids;
+    }
+
+    /// The projectile pool `engine.projectile.spawn` fires into.
+    pub fn projectiles(&self) -> std::sync::MutexGuard<'_, ProjectilePool> {
+        self.projectiles.lock().expect("projectile pool poisoned")
+    }
+
+    /// Set `engine.projectile.hits` to this step's impacts, for the next
+    /// `on_update`.
+    pub fn set_projectile_hits(&self, hits: &[ProjectileHit]) {
+        if self.status != LuaStatus::Loaded {
+            return;
+        }
+        let result = (|| -> LuaResult<()> {
+            let list = self.lua.create_table()?;
+            for hit in hits {
+                let entry = self.lua.create_table()?;
+                entry.set("id", hit.id)?;
+                entry.set("x", hit.position.x)?;
+                entry.set("y", hit.position.y)?;
+                match &hit.target {
+                    HitTarget::World { normal } => {
+                        entry.set("target", "world")?;
+                        entry.set("normal_x", normal.x)?;
+                        entry.set("normal_y", normal.y)?;
+                    }
+                    HitTarget::Actor(id) => entry.set("target", id.as_str())?,
+                }
+                list.push(entry)?;
+            }
+            let engine: LuaTable = self.lua.globals().get("engine")?;
+            let projectile: LuaTable = engine.get("projectile")?;
+            projectile.set("hits", list)
+        })();
+        if let Err(err) = result {
+            log::error!("Failed to update engine.projectile.hits: {}", err);
+        }
+    }
+
+    /// Item definitions `engine.inventory.add` checks against.
+    pub fn items(&self) -> std::sync::MutexGuard<'_, ItemRegistry> {
+        self.items.lock().expect("item registry poisoned")
+    }
+
+    /// Every actor's inventory, as seen by `engine.inventory.*`.
+    pub fn inventories(&self) -> std::sync::MutexGuard<'_, Inventories> {
+        self.inventories.lock().expect("inventories poisoned")
+    }
+
+    /// The event bus Rust systems and `engine.events.emit` push onto.
+    pub fn events(&self) -> std::sync::MutexGuard<'_, EventBus> {
+        self.events.lock().expect("event bus poisoned")
+    }
+
+    /// Persistent variables, checkpoints and play time behind `engine.save`.
+    pub fn save(&self) -> std::sync::MutexGuard<'_, SaveState> {
+        self.save.lock().expect("save state poisoned")
+    }
+
+    /// Player settings behind `engine.settings`.
+    pub fn settings(&self) -> std::sync::MutexGuard<'_, SettingsStore> {
+        self.settings.lock().expect("settings poisoned")
+    }
+
+    /// Call every handler registered for each event's name, in order. A
+    /// failing handler is recorded and the rest still run. Handlers run inside
+    /// the fixed step, so like `on_update` they may draw random numbers and
+    /// spawn.
+    pub fn dispatch_events(&self, events: &[EngineEvent]) {
+        if self.status != LuaStatus::Loaded || events.is_empty() {
+            return;
+        }
+        let handlers: LuaTable = match self
+            .lua
+            .globals()
+            .get::<LuaTable>("engine")
+            .and_then(|engine| engine.get("_event_handlers"))
+        {
+            Ok(handlers) => handlers,
+            Err(err) => {
+                log::error!("Failed to read engine._event_handlers: {}", err);
+                return;
+            }
+        };
+        self.rng.lock().expect("script rng poisoned").stepping = true;
+        for event in events {
+            let name = event.name();
+            let Ok(Some(list)) = handlers.get::<Option<LuaTable>>(name) else {
+                continue;
+            };
+            let payload = match json_to_lua(&self.lua, &event.payload()) {
+                Ok(payload) => payload,
+                Err(err) => {
+                    log::error!("Failed to convert event '{name}': {err}");
+                    continue;
+                }
+            };
+            for handler in list.sequence_values::<LuaFunction>() {
+                let result = handler.and_then(|handler| handler.call::<()>(payload.clone()));
+                if let Err(err) = result {
+                    self.record_error(&format!("event handler '{name}'"), &err);
+                }
+            }
+        }
+        self.rng.lock().expect("script rng poisoned").stepping = false;
+    }
+
+    /// Replace the collision grid answered by `engine.world.*` queries. Call
+    /// after loading or reloading collision.
+    pub fn set_collision_grid(&self, grid: CollisionGrid) {
+        *self.world.lock().expect("script world poisoned") = Some(grid);
+    }
+
+    /// Bulk-set `engine.actors[id]` for every actor in the scene. Entries carry
+    /// the same read-only fields as `engine.actor`.
+    pub fn set_actors(&self, actors: &[(&str, &ActorSnapshot)]) {
+        if self.status != LuaStatus::Loaded {
+            return;
+        }
+        let result = (|| -> LuaResult<()> {
+            let actors_table = self.lua.create_table()?;
+            for (id, actor) in actors {
+                let entry = self.lua.create_table()?;
+                write_actor_state(&entry, actor)?;
+                actors_table.set(*id, entry)?;
+            }
+            let engine: LuaTable = self.lua.globals().get("engine")?;
+            engine.set("actors", actors_table)
+        })();
+        if let Err(err) = result {
+            log::error!("Failed to update engine.actors: {}", err);
+        }
+    }
+
+    /// Replace the localized strings visible to Lua through `engine.loc(key)`.
+    /// Call after loading string tables, switching language, or a table reload.
+    pub fn set_loc_strings(&mut self, strings: HashMap<String, String>) {
+        self.loc_strings = strings;
+        if let Err(err) = self.install_loc_strings() {
+            log::error!("Failed to update Lua string table: {}", err);
+        }
+    }
+
+    fn install_loc_strings(&self) -> LuaResult<()> {
+        let Ok(engine) = self.lua.globals().get::<LuaTable>("engine") else {
+            // No engine table yet; setup_engine_api installs the strings.
+            return Ok(());
+        };
+        let loc_table = self.lua.create_table()?;
+        for (key, text) in &self.loc_strings {
+            loc_table.set(key.as_str(), text.as_str())?;
+        }
+        engine.set("_loc", loc_table)
+    }
+
+    /// Update `engine.app.focused` / `engine.app.audio_muted`. Kept on the
+    /// bridge so a script reload sees the current values.
+    pub fn set_app_state(&mut self, focused: bool, audio_muted: bool) {
+        self.app_state = (focused, audio_muted);
+        if let Err(err) = self.install_app_state() {
+            log::error!("Failed to update engine.app: {}", err);
+        }
+    }
+
+    fn install_app_state(&self) -> LuaResult<()> {
+        let Ok(engine) = self.lua.globals().get::<LuaTable>("engine") else {
+            // No engine table yet; setup_engine_api installs the state.
+            return Ok(());
+        };
+        let app_table = self.lua.create_table()?;
+        app_table.set("focused", self.app_state.0)?;
+        app_table.set("audio_muted", self.app_state.1)?;
+        engine.set("app", app_table)
+    }
+
+    pub fn script_path(&self) -> &Path {
+        &self.script_path
+    }
+
+    /// Check if the script file has been modified and reload if needed.
+    /// Call this once per frame at a safe boundary (between frames, not mid-step).
+    /// Returns true when a reload was attempted.
+    pub fn check_reload(&mut self) -> bool {
+        let current_mtime = sme_core::assets::modified(&self.script_path);
+        if current_mtime.is_none() {
+            return false;
+        }
+
+        if current_mtime != self.last_modified {
+            log::info!(
+                "Lua script changed, reloading: {}",
+                self.script_path.display()
+            );
+            self.try_load_script();
+            return true;
+        }
+        false
+    }
+
+    /// Force a reload of the script (e.g. when user presses R).
+    pub fn force_reload(&mut self) {
+        log::info!("Lua script force reload: {}", self.script_path.display());
+        self.try_load_script();
+    }
+
+    /// Call the Lua on_update(dt) function with current engine state.
+    /// Returns the intent from Lua, or None if Lua is not available.
+    pub fn call_update(
+        &self,
+        dt: f32,
+        input: &InputSnapshot,
+        actor: &ActorSnapshot,
+    ) -> Option<LuaIntent> {
+        if self.status != LuaStatus::Loaded {
+            return None;
+        }
+
+        match self.call_update_inner(dt, input, actor) {
+            Ok(intent) => Some(intent),
+            Err(err) => {
+                self.record_error("on_update", &err);
+                None
+            }
+        }
+    }
+
+    fn call_update_inner(
+        &self,
+        dt: f32,
+        input: &InputSnapshot,
+        actor: &ActorSnapshot,
+    ) -> LuaResult<LuaIntent> {
+        // Set up the engine.input table
+        let engine: LuaTable = self.lua.globals().get("engine")?;
+        let input_table: LuaTable = engine.get("input")?;
+        let actor_table: LuaTable = engine.get("actor")?;
+
+        // Update held keys set
+        let held_set = self.lua.create_table()?;
+        for key in &input.held_keys {
+            held_set.set(key.as_str(), true)?;
+        }
+        input_table.set("_held", held_set)?;
+
+        // Update just_pressed keys set
+        let pressed_set = self.lua.create_table()?;
+        for key in &input.just_pressed_keys {
+            pressed_set.set(key.as_str(), true)?;
+        }
+        input_table.set("_just_pressed", pressed_set)?;
+
+        let mouse_held_set = self.lua.create_table()?;
+        for button in &input.held_buttons {
+            mouse_held_set.set(button.as_str(), true)?;
+        }
+        input_table.set("_mouse_held", mouse_held_set)?;
+        let mouse_pressed_set = self.lua.create_table()?;
+        for button in &input.just_pressed_buttons {
+            mouse_pressed_set.set(button.as_str(), true)?;
+        }
+        input_table.set("_mouse_just_pressed", mouse_pressed_set)?;
+        input_table.set("mouse_x", input.mouse_x)?;
+        input_table.set("mouse_y", input.mouse_y)?;
+        input_table.set("wheel", input.wheel)?;
+
+        // Update actor state
+        write_actor_state(&actor_table, actor)?;
+
+        // Reset intent
+        let intent_table: LuaTable = engine.get("_intent")?;
+        intent_table.set("move_x", 0.0f32)?;
+        intent_table.set("jump_pressed", false)?;
+        intent_table.set("crouch", false)?;
+        intent_table.set("dash", false)?;
+        intent_table.set("impulses", self.lua.create_table()?)?;
+        intent_table.set("play_animation", LuaValue::Nil)?;
+        intent_table.set("stop_animation", false)?;
+        intent_table.set("start_dialog", LuaValue::Nil)?;
+        intent_table.set("time_scale", LuaValue::Nil)?;
+        intent_table.set("time_scale_duration", LuaValue::Nil)?;
+        intent_table.set("hitstop", 0u32)?;
+        intent_table.set("shake", 0.0f32)?;
+        intent_table.set("trails", self.lua.create_table()?)?;
+        intent_table.set("effects", self.lua.create_table()?)?;
+        intent_table.set("ambient", LuaValue::Nil)?;
+        intent_table.set("layer_groups", self.lua.create_table()?)?;
+        intent_table.set("tweens", self.lua.create_table()?)?;
+        intent_table.set("scene_load", LuaValue::Nil)?;
+
+        // Call on_update(dt); the RNG may only advance inside it.
+        let on_update: LuaFunction = self.lua.globals().get("on_update")?;
+        self.rng.lock().expect("script rng poisoned").stepping = true;
+        let result = on_update.call::<()>(dt);
+        self.rng.lock().expect("script rng poisoned").stepping = false;
+        result?;
+
+        // Read back intent
+        let move_x: f32 = intent_table.get("move_x")?;
+        let jump_pressed: bool = intent_table.get("jump_pressed")?;
+        let crouch: bool = intent_table.get("crouch").unwrap_or(false);
+        let dash: bool = intent_table.get("dash").unwrap_or(false);
+        let mut impulses = Vec::new();
+        if let Ok(impulse_table) = intent_table.get::<LuaTable>("impulses") {
+            for entry in impulse_table.sequence_values::<LuaTable>() {
+                let entry = entry?;
+                impulses.push((entry.get(1)?, entry.get(2)?));
+            }
+        }
+        let play_animation: Option<String> = intent_table.get("play_animation").ok();
+        let stop_animation: bool = intent_table.get("stop_animation").unwrap_or(false);
+        let start_dialog: Option<String> = intent_table.get("start_dialog").ok();
+        let time_scale = intent_table
+            .get::<Option<f64>>("time_scale")?
+            .map(|scale| -> LuaResult<_> {
+                Ok((
+                    scale,
+                    intent_table.get::<Option<f64>>("time_scale_duration")?,
+                ))
+            })
+            .transpose()?;
+        let hitstop: u32 = intent_table.get("hitstop").unwrap_or(0);
+        let shake: f32 = intent_table.get("shake").unwrap_or(0.0);
+        let mut trails = Vec::new();
+        if let Ok(trail_table) = intent_table.get::<LuaTable>("trails") {
+            for pair in trail_table.pairs::<String, u32>() {
+                trails.push(pair?);
+            }
+            // Table iteration order is unspecified; keep it deterministic.
+            trails.sort();
+        }
+        let mut effects = Vec::new();
+        if let Ok(effect_table) = intent_table.get::<LuaTable>("effects") {
+            for pair in effect_table.pairs::<String, LuaTable>() {
+                let (id, entry) = pair?;
+                let kind: String = entry.get("kind")?;
+                let effect = SpriteEffect::parse(&kind, entry.get("param")?)
+                    .map_err(LuaError::RuntimeError)?;
+                effects.push((id, effect));
+            }
+            effects.sort_by(|a, b| a.0.cmp(&b.0));
+        }
+        let ambient = match intent_table.get::<LuaValue>("ambient")? {
+            LuaValue::Table(color) => Some(Some([color.get(1)?, color.get(2)?, color.get(3)?])),
+            LuaValue::Boolean(false) => Some(None),
+            _ => None,
+        };
+        let mut layer_groups = Vec::new();
+        if let Ok(group_table) = intent_table.get::<LuaTable>("layer_groups") {
+            for pair in group_table.pairs::<String, bool>() {
+                layer_groups.push(pair?);
+            }
+            layer_groups.sort();
+        }
+        let mut tweens = Vec::new();
+        if let Ok(tween_table) = intent_table.get::<LuaTable>("tweens") {
+            for entry in tween_table.sequence_values::<LuaTable>() {
+                let entry = entry?;
+                let spec = tween_spec(
+                    entry.get("to")?,
+                    entry.get("duration")?,
+                    entry.get("easing")?,
+                    entry.get("repeat")?,
+                )?;
+                tweens.push((entry.get("id")?, spec));
+            }
+        }
+        let scene_load = intent_table
+            .get::<Option<LuaTable>>("scene_load")?
+            .map(|entry| -> LuaResult<_> {
+                Ok(SceneLoad {
+                    scene: PathBuf::from(entry.get::<String>("path")?),
+                    collision: entry.get::<Option<String>>("collision")?.map(PathBuf::from),
+                    fade: entry.get("fade")?,
+                })
+            })
+            .transpose()?;
+
+        Ok(LuaIntent {
+            move_x,
+            jump_pressed,
+            crouch,
+            dash,
+            impulses,
+            play_animation,
+            stop_animation,
+            start_dialog,
+            time_scale,
+            hitstop,
+            shake,
+            trails,
+            effects,
+            ambient,
+            layer_groups,
+            tweens,
+            scene_load,
+        })
+    }
+
+    /// Evaluate a dialog choice condition (a Lua expression) against the
+    /// script's globals. Errors and a missing script count as false so a bad
+    /// condition hides its choice instead of exposing it.
+    pub fn evaluate_condition(&self, expr: &str) -> bool {
+        if self.status != LuaStatus::Loaded {
+            return false;
+        }
+        match self
+            .lua
+            .load(format!("return ({expr})"))
+            .set_name("dialog condition")
+            .eval::<LuaValue>()
+        {
+            Ok(value) => !matches!(value, LuaValue::Nil | LuaValue::Boolean(false)),
+            Err(err) => {
+                self.record_error(&format!("dialog condition '{expr}'"), &err);
+                false
+            }
+        }
+    }
+
+    /// Call the global Lua function `name(dialog_id, node_id)` for a dialog
+    /// choice's `on_select`.
+    pub fn call_dialog_callback(&self, name: &str, dialog_id: &str, node_id: &str) {
+        if self.status != LuaStatus::Loaded {
+            return;
+        }
+        let result = self
+            .lua
+            .globals()
+            .get::<LuaFunction>(name)
+            .and_then(|callback| callback.call::<()>((dialog_id, node_id)));
+        if let Err(err) = result {
+            self.record_error(&format!("dialog callback '{name}'"), &err);
+        }
+    }
+
+    /// Call an item's `on_pickup` hook as
+    /// `name(actor_id, item_id, count, pickup_id)`.
+    pub fn call_pickup_hook(
+        &self,
+        name: &str,
+        actor_id: &str,
+        item_id: &str,
+        count: u32,
+        pickup_id: &str,
+    ) {
+        if self.status != LuaStatus::Loaded {
+            return;
+        }
+        let result = self
+            .lua
+            .globals()
+            .get::<LuaFunction>(name)
+            .and_then(|hook| hook.call::<()>((actor_id, item_id, count, pickup_id)));
+        if let Err(err) = result {
+            self.record_error(&format!("pickup hook '{name}'"), &err);
+        }
+    }
+
+    /// Call the global Lua function `name(widget_id)` for a UI button's
+    /// `on_click`.
+    pub fn call_ui_callback(&self, name: &str, widget_id: &str) {
+        if self.status != LuaStatus::Loaded {
+            return;
+        }
+        let result = self
+            .lua
+            .globals()
+            .get::<LuaFunction>(name)
+            .and_then(|callback| callback.call::<()>(widget_id));
+        if let Err(err) = result {
+            self.record_error(&format!("UI callback '{name}'"), &err);
+        }
+    }
+
+    /// Take the UI commands queued by `engine.ui.*` since the last drain.
+    pub fn drain_ui_commands(&self) -> Vec<UiCommand> {
+        if self.status != LuaStatus::Loaded {
+            return Vec::new();
+        }
+        match self.drain_ui_commands_inner() {
+            Ok(commands) => commands,
+            Err(err) => {
+                log::error!("Failed to read UI commands: {}", err);
+                Vec::new()
+            }
+        }
+    }
+
+    fn drain_ui_commands_inner(&self) -> LuaResult<Vec<UiCommand>> {
+        let engine: LuaTable = self.lua.globals().get("engine")?;
+        let queue: LuaTable = engine.get("_ui_commands")?;
+        let mut commands = Vec::with_capacity(queue.raw_len());
+        for entry in queue.sequence_values::<LuaTable>() {
+            let entry = entry?;
+            let op: String = entry.get("op")?;
+            let id: String = entry.get("id")?;
+            match op.as_str() {
+                "set_visible" => commands.push(UiCommand::SetVisible {
+                    id,
+                    visible: entry.get("visible")?,
+                }),
+                "set_value" => commands.push(UiCommand::SetValue {
+                    id,
+                    value: entry.get("value")?,
+                }),
+                "set_text" => commands.push(UiCommand::SetText {
+                    id,
+                    text: entry.get("text")?,
+                }),
+                _ => log::warn!("Unknown UI command '{}'", op),
+            }
+        }
+        engine.set("_ui_commands", self.lua.create_table()?)?;
+        Ok(commands)
+    }
+
+    /// Take the values queued by `engine.debug.*` since the last drain.
+    pub fn drain_debug_values(&self) -> Vec<ScriptDebugValue> {
+        if self.status != LuaStatus::Loaded {
+            return Vec::new();
+        }
+        match self.drain_debug_values_inner() {
+            Ok(values) => values,
+            Err(err) => {
+                log::error!("Failed to read debug values: {}", err);
+                Vec::new()
+            }
+        }
+    }
+
+    fn drain_debug_values_inner(&self) -> LuaResult<Vec<ScriptDebugValue>> {
+        let engine: LuaTable = self.lua.globals().get("engine")?;
+        let queue: LuaTable = engine.get("_debug_queue")?;
+        let mut values = Vec::with_capacity(queue.raw_len());
+        for entry in queue.sequence_values::<LuaTable>() {
+            let entry = entry?;
+            let name: String = entry.get("name")?;
+            if entry.get::<bool>("plot")? {
+                values.push(ScriptDebugValue::Plot {
+                    name,
+                    value: entry.get("value")?,
+                });
+            } else {
+                values.push(ScriptDebugValue::Watch {
+                    name,
+                    text: entry.get("value")?,
+                });
+            }
+        }
+        engine.set("_debug_queue", self.lua.create_table()?)?;
+        Ok(values)
+    }
+
+    /// Replay the shapes queued by `engine.debug.draw_*` since the last drain
+    /// into `draw`.
+    pub fn drain_debug_draws(&self, draw: &mut DebugDraw) {
+        if self.status != LuaStatus::Loaded {
+            return;
+        }
+        if let Err(err) = self.drain_debug_draws_inner(draw) {
+            log::error!("Failed to read debug draws: {}", err);
+        }
+    }
+
+    fn drain_debug_draws_inner(&self, draw: &mut DebugDraw) -> LuaResult<()> {
+        let engine: LuaTable = self.lua.globals().get("engine")?;
+        let queue: LuaTable = engine.get("_draw_queue")?;
+        for entry in queue.sequence_values::<LuaTable>() {
+            let entry = entry?;
+            let shape: String = entry.get("shape")?;
+            let args: Vec<f32> = entry.get("args")?;
+            let color: [f32; 4] = entry.get("color")?;
+            match (shape.as_str(), args.as_slice()) {
+                ("line", &[x1, y1, x2, y2]) => {
+                    draw.line(Vec2::new(x1, y1), Vec2::new(x2, y2), color)
+                }
+                ("rect", &[x, y, w, h]) => draw.rect(Vec2::new(x, y), Vec2::new(w, h), color),
+                ("circle", &[x, y, radius]) => draw.circle(Vec2::new(x, y), radius, color),
+                ("text", &[x, y]) => {
+                    draw.text(Vec2::new(x, y), entry.get::<String>("text")?, color)
+                }
+                _ => log::warn!("Ignoring malformed debug draw '{shape}'"),
+            }
+        }
+        engine.set("_draw_queue", self.lua.create_table()?)?;
+        Ok(())
+    }
+
+    /// Take the spawns and despawns queued by `engine.spawn` /
+    /// `engine.despawn` since the last drain.
+    pub fn drain_actor_commands(&self) -> Vec<ActorCommand> {
+        if self.status != LuaStatus::Loaded {
+            return Vec::new();
+        }
+        match self.drain_actor_commands_inner() {
+            Ok(commands) => commands,
+            Err(err) => {
+                log::error!("Failed to read actor commands: {}", err);
+                Vec::new()
+            }
+        }
+    }
+
+    fn drain_actor_commands_inner(&self) -> LuaResult<Vec<ActorCommand>> {
+        let engine: LuaTable = self.lua.globals().get("engine")?;
+        let queue: LuaTable = engine.get("_actor_commands")?;
+        let mut commands = Vec::with_capacity(queue.raw_len());
+        for entry in queue.sequence_values::<LuaTable>() {
+            let entry = entry?;
+            let id: String = entry.get("id")?;
+            match entry.get::<Option<String>>("template")? {
+                Some(template) => commands.push(ActorCommand::Spawn {
+                    id,
+                    template,
+                    x: entry.get("x")?,
+                    y: entry.get("y")?,
+                }),
+                None => commands.push(ActorCommand::Despawn(id)),
+            }
+        }
+        engine.set("_actor_commands", self.lua.create_table()?)?;
+        Ok(commands)
+    }
+
+    fn try_load_script(&mut self) {
+        if !sme_core::assets::exists(&self.script_path) {
+            log::warn!(
+                "Lua script not found: {}. Using Rust fallback.",
+                self.script_path.display()
+            );
+            self.status = LuaStatus::Fallback;
+            self.last_error = None;
+            self.last_modified = None;
+            return;
+        }
+
+        // Record mtime before loading
+        self.last_modified = sme_core::assets::modified(&self.script_path);
+
+        // Create a fresh Lua state to avoid stale globals
+        self.lua = Lua::new();
+
+        if let Err(err) = self.setup_engine_api() {
+            let msg = format!("Failed to setup Lua engine API: {}", err);
+            log::error!("{}", msg);
+            self.status = LuaStatus::Error;
+            self.last_error = Some(msg);
+            return;
+        }
+
+        match sme_core::assets::read_to_string(&self.script_path) {
+            Ok(source) => {
+                match self
+                    .lua
+                    .load(&source)
+                    .set_name(self.script_path.to_string_lossy())
+                    .exec()
+                {
+                    Ok(()) => {
+                        self.status = LuaStatus::Loaded;
+                        self.last_error = None;
+                        log::info!("Lua script loaded: {}", self.script_path.display());
+                        self.install_breakpoints();
+
+                        // Call on_init() if present
+                        if let Ok(on_init) = self.lua.globals().get::<LuaFunction>("on_init") {
+                            if let Err(err) = on_init.call::<()>(()) {
+                                self.record_error("on_init", &err);
+                                // Don't fail the whole load over on_init error
+                            }
+                        }
+                    }
+                    Err(err) => {
+                        let msg = format!("Lua script load error: {}", err);
+                        self.record_error("script load", &err);
+                        self.status = LuaStatus::Error;
+                        self.last_error = Some(msg);
+                    }
+                }
+            }
+            Err(err) => {
+                let msg = format!("Failed to read Lua script: {}", err);
+                log::error!("{}", msg);
+                self.status = LuaStatus::Error;
+                self.last_error = Some(msg);
+            }
+        }
+    }
+
+    /// Build the `engine` global table that Lua scripts interact with.
+    ///
+    /// Layout:
+    ///   engine.input._held        -- table of key->true for currently held keys
+    ///   engine.input._just_pressed -- table of key->true for edge-triggered presses
+    ///   engine.input.is_held(key)  -- convenience wrapper over _held lookup
+    ///   engine.input.is_just_pressed(key) -- convenience wrapper over _just_pressed
+    ///   engine.input.is_mouse_held(button) / is_mouse_just_pressed(button)
+    ///                             -- "left" / "right" / "middle"; same lookup pattern
+    ///   engine.input.mouse_x/y    -- cursor position in world units
+    ///   engine.input.mouse_position() -- returns mouse_x, mouse_y
+    ///   engine.input.wheel        -- wheel notches since the last step
+    ///   engine.actor.grounded     -- read-only bool, set by Rust each frame
+    ///   engine.actor.swimming     -- read-only bool: inside a fluid volume, where jump swims
+    ///   engine.actor.crouching    -- read-only bool: AABB shrunk to crouch height
+    ///   engine.actor.dashing      -- read-only bool: a dash is under way
+    ///   engine.actor.invulnerable -- read-only bool: i-frames (while dashing); skip damage
+    ///   engine.actor.time_scale   -- read-only float: slow-motion volume multiplier, 1.0 outside
+    ///   engine.actor.velocity_x/y -- read-only floats, set by Rust each frame
+    ///   engine.actor.x/y          -- read-only AABB center, set by Rust each frame
+    ///   engine.actor.position()   -- returns x, y
+    ///   engine.actor.set_intent(move_x, jump_pressed, crouch) -- Lua writes intent here;
+    ///                             crouch is held and optional
+    ///   engine.actor.dash()       -- dash the way the actor faces, if off cooldown
+    ///   engine.actor.add_impulse(x, y) -- knockback: velocity change applied next step,
+    ///                             in call order, before input acceleration
+    ///   engine.actors[id]         -- read-only state of every scene actor
+    ///   engine.spawn(template, x, y) -- copy a scene sprite (or atlas sprite_id) as a new
+    ///                             actor centered on (x, y); returns its id (on_update only)
+    ///   engine.despawn(id)        -- remove a spawned actor
+    ///   engine.projectile.spawn(x, y, vx, vy, opts) -- fire a pooled projectile; opts may set
+    ///                             lifetime, sprite, radius and mask; returns its id or nil
+    ///   engine.projectile.hits    -- impacts from the last step: {id, x, y, target, normal_x/y}
+    ///   engine.inventory.add(actor_id, item_id, count) -- returns how many fit
+    ///   engine.inventory.remove(actor_id, item_id, count) -- returns how many were removed
+    ///   engine.inventory.count(actor_id, item_id)
+    ///   engine.inventory.items(actor_id) -- array of {item, count, sprite_id} stacks
+    ///   engine.save.persist(name, default) -- declare a saved variable; returns its value
+    ///   engine.save.get(name) / set(name, value) -- read/change a declared variable
+    ///   engine.save.unlock_checkpoint(id) -- returns true the first time
+    ///   engine.save.checkpoints() -- sorted array of unlocked checkpoint ids
+    ///   engine.save.play_time()  -- simulated seconds played
+    ///   engine.save.write(slot) / read(slot) -- save/load a slot; true, or nil and an error
+    ///   engine.settings.get(name) / set(name, value) -- "width", "height", "vsync",
+    ///                             "volume" (0..1) or "tier" (0 / 2); saved to the config dir
+    ///   engine.settings.bind(key, physical) -- rebind an engine.input key to a winit
+    ///                             KeyCode name ("KeyJ"); nil restores the default
+    ///   engine.events.emit(name, payload) -- queue a custom event for the end of the step
+    ///   engine.events.on(name, fn) -- call fn(payload) for every `name` event
+    ///   engine.events.off(name)   -- drop every handler for `name`
+    ///   engine.world.is_solid(x, y) -- is the world point inside a solid cell?
+    ///   engine.world.overlap_aabb(center_x, center_y, half_w, half_h) -- any solid overlap?
+    ///   engine.time.set_scale(s, duration) -- slow/speed up simulated time; duration in
+    ///                             simulated seconds, omit to hold the scale
+    ///   engine.time.hitstop(frames) -- freeze the next `frames` fixed steps
+    ///   engine.camera.shake(amount) -- add screen-shake trauma (0..1, saturating)
+    ///   engine.sprite.set_trail(id, length) -- afterimage trail; 0 removes it,
+    ///                             "player" is the player quad
+    ///   engine.sprite.set_effect(id, kind, param) -- "flash" / "dissolve" (0..1),
+    ///                             "outline" (width in texels) or "none"; param defaults to 1
+    ///   engine.environment.set_ambient(r, g, b) -- tint the world (0..1 per channel, may
+    ///                             exceed 1 to brighten); overrides the scene's day_cycle
+    ///   engine.environment.clear_ambient() -- hand the tint back to the day_cycle
+    ///   engine.scene.set_group_visible(group, visible) -- show/hide every layer in a group
+    ///   engine.scene.load(path, opts) -- move to another scene between fixed steps; opts may
+    ///                             set collision (default: the scene's own) and fade (seconds)
+    ///   engine.tween.to(id, {x=, y=, scale_x=, scale_y=, rotation=, alpha=}, duration,
+    ///                   easing, repeat) -- ease sprite properties over simulated seconds;
+    ///                             easing "linear" (default) / "ease_in" / "ease_out" /
+    ///                             "ease_in_out", repeat "once" (default) / "loop" / "ping_pong"
+    ///   engine.dialog.start(id)   -- request a dialog; simulation pauses until it ends
+    ///   engine.ui.set_visible(id, visible) -- show/hide an in-game UI widget
+    ///   engine.ui.set_value(id, value)     -- set a bar's fill (0..1)
+    ///   engine.ui.set_text(id, text)       -- set a widget's text
+    ///   engine.rand()             -- deterministic float in [0, 1) (on_update only)
+    ///   engine.rand_range(a, b)   -- deterministic integer in [a, b] (on_update only)
+    ///   engine.debug.watch(name, value) -- show `name: value` in the Debug window
+    ///   engine.debug.plot(name, v)      -- add a sample to a Debug window sparkline
+    ///   engine.debug.draw_line(x1, y1, x2, y2, color) -- world-space line for this step
+    ///   engine.debug.draw_rect(x, y, w, h, color)     -- rect outline centered on (x, y)
+    ///   engine.debug.draw_circle(x, y, radius, color) -- circle outline
+    ///   engine.debug.draw_text(x, y, text, color)     -- label with its top-left at (x, y)
+    ///                             color is {r, g, b} or {r, g, b, a} in 0..1, default yellow
+    ///   engine.log.info(...) / warn(...) / error(...) -- log with the script name and line
+    ///   print(...)                -- same as engine.log.info
+    ///   engine.app.focused        -- false while the game window is in the background
+    ///   engine.app.audio_muted    -- true while the background policy mutes audio
+    ///   engine.loc(key)           -- localized text for key (key itself if missing)
+    ///   engine._loc               -- internal key->text table set by Rust
+    ///   engine._intent            -- internal table read by Rust after on_update
+    ///   engine._ui_commands       -- internal queue drained by Rust
+    ///   engine._debug_queue       -- internal queue drained by Rust
+    ///   engine._draw_queue        -- internal queue drained by Rust
+    ///   engine._actor_commands    -- internal queue drained by Rust
+    fn setup_engine_api(&self) -> LuaResult<()> {
+        let lua = &self.lua;
+        let engine = lua.create_table()?;
+
+        // engine.input table with helper methods
+        let input_table = lua.create_table()?;
+        let held_set = lua.create_table()?;
+        let pressed_set = lua.create_table()?;
+        input_table.set("_held", held_set)?;
+        input_table.set("_just_pressed", pressed_set)?;
+
+        // engine.input.is_held(key) -> bool
+        let is_held = lua.create_function(|lua_ctx, key: String| {
+            let engine: LuaTable = lua_ctx.globals().get("engine")?;
+            let input: LuaTable = engine.get("input")?;
+            let held: LuaTable = input.get("_held")?;
+            let result: bool = held.get::<bool>(key.as_str()).unwrap_or(false);
+            Ok(result)
+        })?;
+        input_table.set("is_held", is_held)?;
+
+        // engine.input.is_just_pressed(key) -> bool
+        let is_just_pressed = lua.create_function(|lua_ctx, key: String| {
+            let engine: LuaTable = lua_ctx.globals().get("engine")?;
+            let input: LuaTable = engine.get("input")?;
+            let pressed: LuaTable = input.get("_just_pressed")?;
+            let result: bool = pressed.get::<bool>(key.as_str()).unwrap_or(false);
+            Ok(result)
+        })?;
+        input_table.set("is_just_pressed", is_just_pressed)?;
+
+        input_table.set("_mouse_held", lua.create_table()?)?;
+        input_table.set("_mouse_just_pressed", lua.create_table()?)?;
+        input_table.set("mouse_x", 0.0f32)?;
+        input_table.set("mouse_y", 0.0f32)?;
+        input_table.set("wheel", 0.0f32)?;
+
+        // engine.input.is_mouse_held(button) -> bool
+        let is_mouse_held = lua.create_function(|lua_ctx, button: String| {
+            let engine: LuaTable = lua_ctx.globals().get("engine")?;
+            let input: LuaTable = engine.get("input")?;
+            let held: LuaTable = input.get("_mouse_held")?;
+            Ok(held.get::<bool>(button.as_str()).unwrap_or(false))
+        })?;
+        input_table.set("is_mouse_held", is_mouse_held)?;
+
+        // engine.input.is_mouse_just_pressed(button) -> bool
+        let is_mouse_just_pressed = lua.create_function(|lua_ctx, button: String| {
+            let engine: LuaTable = lua_ctx.globals().get("engine")?;
+            let input: LuaTable = engine.get("input")?;
+            let pressed: LuaTable = input.get("_mouse_just_pressed")?;
+            Ok(pressed.get::<bool>(button.as_str()).unwrap_or(false))
+        })?;
+        input_table.set("is_mouse_just_pressed", is_mouse_just_pressed)?;
+
+        // engine.input.mouse_position() -> x, y
+        let mouse_position = lua.create_function(|lua_ctx, ()| {
+            let engine: LuaTable = lua_ctx.globals().get("engine")?;
+            let input: LuaTable = engine.get("input")?;
+            let x: f32 = input.get("mouse_x")?;
+            let y: f32 = input.get("mouse_y")?;
+            Ok((x, y))
+        })?;
+        input_table.set("mouse_position", mouse_position)?;
+
+        engine.set("input", input_table)?;
+
+        // engine.actor table (read-only state, updated each frame from Rust)
+        let actor_table = lua.create_table()?;
+        actor_table.set("grounded", false)?;
+        actor_table.set("swimming", false)?;
+        actor_table.set("crouching", false)?;
+        actor_table.set("dashing", false)?;
+        actor_table.set("invulnerable", false)?;
+        actor_table.set("time_scale", 1.0f32)?;
+        actor_table.set("x", 0.0f32)?;
+        actor_table.set("y", 0.0f32)?;
+        actor_table.set("velocity_x", 0.0f32)?;
+        actor_table.set("velocity_y", 0.0f32)?;
+
+        // engine.actor.position() -> x, y
+        let position = lua.create_function(|lua_ctx, ()| {
+            let engine: LuaTable = lua_ctx.globals().get("engine")?;
+            let actor: LuaTable = engine.get("actor")?;
+            Ok((actor.get::<f32>("x")?, actor.get::<f32>("y")?))
+        })?;
+        actor_table.set("position", position)?;
+
+        // engine.actor.set_intent(move_x, jump_pressed, crouch)
+        let set_intent = lua.create_function(
+            |lua_ctx, (move_x, jump_pressed, crouch): (f32, bool, Option<bool>)| {
+                let engine: LuaTable = lua_ctx.globals().get("engine")?;
+                let intent: LuaTable = engine.get("_intent")?;
+                intent.set("move_x", move_x)?;
+                intent.set("jump_pressed", jump_pressed)?;
+                intent.set("crouch", crouch.unwrap_or(false))?;
+                Ok(())
+            },
+        )?;
+        actor_table.set("set_intent", set_intent)?;
+
+        // engine.actor.dash()
+        let dash = lua.create_function(|lua_ctx, ()| {
+            let engine: LuaTable = lua_ctx.globals().get("engine")?;
+            let intent: LuaTable = engine.get("_intent")?;
+            intent.set("dash", true)?;
+            Ok(())
+        })?;
+        actor_table.set("dash", dash)?;
+
+        // engine.actor.add_impulse(x, y)
+        let add_impulse = lua.create_function(|lua_ctx, (x, y): (f32, f32)| {
+            let engine: LuaTable = lua_ctx.globals().get("engine")?;
+            let intent: LuaTable = engine.get("_intent")?;
+            let impulses: LuaTable = intent.get("impulses")?;
+            let impulse = lua_ctx.create_sequence_from([x, y])?;
+            impulses.push(impulse)?;
+            Ok(())
+        })?;
+        actor_table.set("add_impulse", add_impulse)?;
+
+        // engine.actor.play_animation(name)
+        let play_animation = lua.create_function(|lua_ctx, name: String| {
+            let engine: LuaTable = lua_ctx.globals().get("engine")?;
+            let intent: LuaTable = engine.get("_intent")?;
+            intent.set("play_animation", name)?;
+            Ok(())
+        })?;
+        actor_table.set("play_animation", play_animation)?;
+
+        // engine.actor.stop_animation()
+        let stop_animation = lua.create_function(|lua_ctx, ()| {
+            let engine: LuaTable = lua_ctx.globals().get("engine")?;
+            let intent: LuaTable = engine.get("_intent")?;
+            intent.set("stop_animation", true)?;
+            Ok(())
+        })?;
+        actor_table.set("stop_animation", stop_animation)?;
+
+        // Read-only animation state
+        actor_table.set("current_animation", LuaValue::Nil)?;
+        actor_table.set("animation_finished", false)?;
+
+        engine.set("actor", actor_table)?;
+        engine.set("actors", lua.create_table()?)?;
+
+        // engine.world.is_solid(x, y) / engine.world.overlap_aabb(cx, cy, hw, hh)
+        let world_table = lua.create_table()?;
+        let world = Arc::clone(&self.world);
+        let is_solid = lua.create_function(move |_, (x, y): (f32, f32)| {
+            let grid = world.lock().expect("script world poisoned");
+            Ok(grid.as_ref().is_some_and(|grid| grid.is_solid_at(x, y)))
+        })?;
+        world_table.set("is_solid", is_solid)?;
+        let world = Arc::clone(&self.world);
+        let overlap_aabb = lua.create_function(
+            move |_, (center_x, center_y, half_w, half_h): (f32, f32, f32, f32)| {
+                let grid = world.lock().expect("script world poisoned");
+                Ok(grid.as_ref().is_some_and(|grid| {
+                    grid.overlaps_aabb(Aabb {
+                        center_x,
+                        center_y,
+                        half_w,
+                        half_h,
+                    })
+                }))
+            },
+        )?;
+        world_table.set("overlap_aabb", overlap_aabb)?;
+        engine.set("world", world_table)?;
+
+        // engine.time.set_scale(scale, duration?) / engine.time.hitstop(frames)
+        let time_table = lua.create_table()?;
+        let set_scale = lua.create_function(|lua_ctx, (scale, duration): (f64, Option<f64>)| {
+            let engine: LuaTable = lua_ctx.globals().get("engine")?;
+            let intent: LuaTable = engine.get("_intent")?;
+            intent.set("time_scale", scale)?;
+            intent.set("time_scale_duration", duration)?;
+            Ok(())
+        })?;
+        time_table.set("set_scale", set_scale)?;
+        let hitstop = lua.create_function(|lua_ctx, frames: u32| {
+            let engine: LuaTable = lua_ctx.globals().get("engine")?;
+            let intent: LuaTable = engine.get("_intent")?;
+            let current: u32 = intent.get("hitstop").unwrap_or(0);
+            intent.set("hitstop", current.max(frames))?;
+            Ok(())
+        })?;
+        time_table.set("hitstop", hitstop)?;
+        engine.set("time", time_table)?;
+
+        // engine.camera.shake(amount)
+        let camera_table = lua.create_table()?;
+        let shake = lua.create_function(|lua_ctx, amount: f32| {
+            let engine: LuaTable = lua_ctx.globals().get("engine")?;
+            let intent: LuaTable = engine.get("_intent")?;
+            let current: f32 = intent.get("shake").unwrap_or(0.0);
+            intent.set("shake", current + amount.max(0.0))?;
+            Ok(())
+        })?;
+        camera_table.set("shake", shake)?;
+        engine.set("camera", camera_table)?;
+
+        // engine.sprite.set_trail(id, length)
+        let sprite_table = lua.create_table()?;
+        let set_trail = lua.create_function(|lua_ctx, (id, length): (String, u32)| {
+            let engine: LuaTable = lua_ctx.globals().get("engine")?;
+            let intent: LuaTable = engine.get("_intent")?;
+            let trails: LuaTable = intent.get("trails")?;
+            trails.set(id, length)
+        })?;
+        sprite_table.set("set_trail", set_trail)?;
+        let set_effect = lua.create_function(
+            |lua_ctx, (id, kind, param): (String, String, Option<f32>)| {
+                let param = param.unwrap_or(1.0);
+                // Validate now so the error points at the calling line.
+                SpriteEffect::parse(&kind, param).map_err(LuaError::RuntimeError)?;
+                let engine: LuaTable = lua_ctx.globals().get("engine")?;
+                let intent: LuaTable = engine.get("_intent")?;
+                let effects: LuaTable = intent.get("effects")?;
+                let entry = lua_ctx.create_table()?;
+                entry.set("kind", kind)?;
+                entry.set("param", param)?;
+                effects.set(id, entry)
+            },
+        )?;
+        sprite_table.set("set_effect", set_effect)?;
+        engine.set("sprite", sprite_table)?;
+
+        // engine.environment.set_ambient(r, g, b) / engine.environment.clear_ambient()
+        let environment_table = lua.create_table()?;
+        let set_ambient = lua.create_function(|lua_ctx, (r, g, b): (f32, f32, f32)| {
+            if [r, g, b].iter().any(|c| !(*c >= 0.0 && c.is_finite())) {
+                return Err(LuaError::RuntimeError(format!(
+                    "ambient color ({r}, {g}, {b}) must be non-negative"
+                )));
+            }
+            let engine: LuaTable = lua_ctx.globals().get("engine")?;
+            let intent: LuaTable = engine.get("_intent")?;
+            intent.set("ambient", [r, g, b])
+        })?;
+        environment_table.set("set_ambient", set_ambient)?;
+        let clear_ambient = lua.create_function(|lua_ctx, ()| {
+            let engine: LuaTable = lua_ctx.globals().get("engine")?;
+            let intent: LuaTable = engine.get("_intent")?;
+            intent.set("ambient", false)
+        })?;
+        environment_table.set("clear_ambient", clear_ambient)?;
+        engine.set("environment", environment_table)?;
+
+        // engine.scene.set_group_visible(group, visible)
+        let scene_table = lua.create_table()?;
+        let set_group_visible =
+            lua.create_function(|lua_ctx, (group, visible): (String, bool)| {
+                let engine: LuaTable = lua_ctx.globals().get("engine")?;
+                let intent: LuaTable = engine.get("_intent")?;
+                let groups: LuaTable = intent.get("layer_groups")?;
+                groups.set(group, visible)
+            })?;
+        scene_table.set("set_group_visible", set_group_visible)?;
+        // engine.scene.load(path, opts)
+        let scene_load =
+            lua.create_function(|lua_ctx, (path, opts): (String, Option<LuaTable>)| {
+                let (collision, fade) = match &opts {
+                    Some(opts) => (
+                        opts.get::<Option<String>>("collision")?,
+                        opts.get::<Option<f32>>("fade")?.unwrap_or(0.0),
+                    ),
+                    None => (None, 0.0),
+                };
+                if !(fade >= 0.0 && fade.is_finite()) {
+                    return Err(LuaError::RuntimeError(format!(
+                        "scene fade {fade} must be a non-negative number of seconds"
+                    )));
+                }
+                let engine: LuaTable = lua_ctx.globals().get("engine")?;
+                let intent: LuaTable = engine.get("_intent")?;
+                let entry = lua_ctx.create_table()?;
+                entry.set("path", path)?;
+                entry.set("collision", collision)?;
+                entry.set("fade", fade)?;
+                intent.set("scene_load", entry)
+            })?;
+        scene_table.set("load", scene_load)?;
+        engine.set("scene", scene_table)?;
+
+        // engine.tween.to(id, targets, duration, easing, repeat)
+        let tween_table = lua.create_table()?;
+        let tween_to = lua.create_function(
+            |lua_ctx,
+             (id, to, duration, easing, repeat): (
+                String,
+                LuaTable,
+                f32,
+                Option<String>,
+                Option<String>,
+            )| {
+                // Validate now so the error points at the calling line.
+                tween_spec(to.clone(), duration, easing.clone(), repeat.clone())?;
+                let engine: LuaTable = lua_ctx.globals().get("engine")?;
+                let intent: LuaTable = engine.get("_intent")?;
+                let tweens: LuaTable = intent.get("tweens")?;
+                let entry = lua_ctx.create_table()?;
+                entry.set("id", id)?;
+                entry.set("to", to)?;
+                entry.set("duration", duration)?;
+                entry.set("easing", easing)?;
+                entry.set("repeat", repeat)?;
+                tweens.push(entry)
+            },
+        )?;
+        tween_table.set("to", tween_to)?;
+        engine.set("tween", tween_table)?;
+
+        // engine._intent (internal, read by Rust after on_update)
+        let intent_table = lua.create_table()?;
+        intent_table.set("move_x", 0.0f32)?;
+        intent_table.set("jump_pressed", false)?;
+        intent_table.set("impulses", lua.create_table()?)?;
+        intent_table.set("trails", lua.create_table()?)?;
+        intent_table.set("effects", lua.create_table()?)?;
+        intent_table.set("layer_groups", lua.create_table()?)?;
+        intent_table.set("tweens", lua.create_table()?)?;
+        engine.set("_intent", intent_table)?;
+
+        // engine.loc(key) -> localized string, or the key when untranslated
+        let loc = lua.create_function(|lua_ctx, key: String| {
+            let engine: LuaTable = lua_ctx.globals().get("engine")?;
+            let strings: LuaTable = engine.get("_loc")?;
+            let text: Option<String> = strings.get(key.as_str())?;
+            Ok(text.unwrap_or(key))
+        })?;
+        engine.set("loc", loc)?;
+
+        // engine.dialog.start(id)
+        let dialog_table = lua.create_table()?;
+        let start_dialog = lua.create_function(|lua_ctx, dialog_id: String| {
+            let engine: LuaTable = lua_ctx.globals().get("engine")?;
+            let intent: LuaTable = engine.get("_intent")?;
+            intent.set("start_dialog", dialog_id)?;
+            Ok(())
+        })?;
+        dialog_table.set("start", start_dialog)?;
+        engine.set("dialog", dialog_table)?;
+
+        // engine.ui.set_visible(id, visible) / engine.ui.set_value(id, value) /
+        // engine.ui.set_text(id, text)
+        engine.set("_ui_commands", lua.create_table()?)?;
+        let ui_table = lua.create_table()?;
+        let set_visible = lua.create_function(|lua_ctx, (id, visible): (String, bool)| {
+            let engine: LuaTable = lua_ctx.globals().get("engine")?;
+            let queue: LuaTable = engine.get("_ui_commands")?;
+            let command = lua_ctx.create_table()?;
+            command.set("op", "set_visible")?;
+            command.set("id", id)?;
+            command.set("visible", visible)?;
+            queue.push(command)
+        })?;
+        ui_table.set("set_visible", set_visible)?;
+        let set_value = lua.create_function(|lua_ctx, (id, value): (String, f32)| {
+            let engine: LuaTable = lua_ctx.globals().get("engine")?;
+            let queue: LuaTable = engine.get("_ui_commands")?;
+            let command = lua_ctx.create_table()?;
+            command.set("op", "set_value")?;
+            command.set("id", id)?;
+            command.set("value", value)?;
+            queue.push(command)
+        })?;
+        ui_table.set("set_value", set_value)?;
+        let set_text = lua.create_function(|lua_ctx, (id, text): (String, LuaValue)| {
+            let engine: LuaTable = lua_ctx.globals().get("engine")?;
+            let queue: LuaTable = engine.get("_ui_commands")?;
+            let command = lua_ctx.create_table()?;
+            command.set("op", "set_text")?;
+            command.set("id", id)?;
+            // Numbers and other values read as `tostring` would show them.
+            let text: String = match text {
+                LuaValue::String(text) => text.to_str()?.to_string(),
+                other => lua_ctx
+                    .globals()
+                    .get::<LuaFunction>("tostring")?
+                    .call(other)?,
+            };
+            command.set("text", text)?;
+            queue.push(command)
+        })?;
+        ui_table.set("set_text", set_text)?;
+        engine.set("ui", ui_table)?;
+
+        // engine.debug.watch(name, value) / engine.debug.plot(name, v)
+        engine.set("_debug_queue", lua.create_table()?)?;
+        let debug_table = lua.create_table()?;
+        let watch = lua.create_function(|lua_ctx, (name, value): (String, LuaValue)| {
+            let tostring: LuaFunction = lua_ctx.globals().get("tostring")?;
+            let text: String = tostring.call(value)?;
+            let engine: LuaTable = lua_ctx.globals().get("engine")?;
+            let queue: LuaTable = engine.get("_debug_queue")?;
+            let entry = lua_ctx.create_table()?;
+            entry.set("plot", false)?;
+            entry.set("name", name)?;
+            entry.set("value", text)?;
+            queue.push(entry)
+        })?;
+        debug_table.set("watch", watch)?;
+        let plot = lua.create_function(|lua_ctx, (name, value): (String, f32)| {
+            let engine: LuaTable = lua_ctx.globals().get("engine")?;
+            let queue: LuaTable = engine.get("_debug_queue")?;
+            let entry = lua_ctx.create_table()?;
+            entry.set("plot", true)?;
+            entry.set("name", name)?;
+            entry.set("value", value)?;
+            queue.push(entry)
+        })?;
+        debug_table.set("plot", plot)?;
+
+        // engine.debug.draw_line / draw_rect / draw_circle / draw_text
+        engine.set("_draw_queue", lua.create_table()?)?;
+        let draw_line = lua.create_function(
+            |lua_ctx, (x1, y1, x2, y2, color): (f32, f32, f32, f32, Option<LuaTable>)| {
+                queue_debug_draw(lua_ctx, "line", &[x1, y1, x2, y2], None, color)
+            },
+        )?;
+        debug_table.set("draw_line", draw_line)?;
+        let draw_rect = lua.create_function(
+            |lua_ctx, (x, y, w, h, color): (f32, f32, f32, f32, Option<LuaTable>)| {
+                queue_debug_draw(lua_ctx, "rect", &[x, y, w, h], None, color)
+            },
+        )?;
+        debug_table.set("draw_rect", draw_rect)?;
+        let draw_circle = lua.create_function(
+            |lua_ctx, (x, y, radius, color): (f32, f32, f32, Option<LuaTable>)| {
+                queue_debug_draw(lua_ctx, "circle", &[x, y, radius], None, color)
+            },
+        )?;
+        debug_table.set("draw_circle", draw_circle)?;
+        let draw_text = lua.create_function(
+            |lua_ctx, (x, y, text, color): (f32, f32, LuaValue, Option<LuaTable>)| {
+                let tostring: LuaFunction = lua_ctx.globals().get("tostring")?;
+                let text: String = tostring.call(text)?;
+                queue_debug_draw(lua_ctx, "text", &[x, y], Some(text), color)
+            },
+        )?;
+        debug_table.set("draw_text", draw_text)?;
+        engine.set("debug", debug_table)?;
+
+        // print(...) / engine.log.*(...) go to the `log` crate (target "lua")
+        // and the overlay console instead of stdout.
+        let log_table = lua.create_table()?;
+        for (name, level) in [
+            ("info", log::Level::Info),
+            ("warn", log::Level::Warn),
+            ("error", log::Level::Error),
+        ] {
+            let console = self.console.clone();
+            let function = lua.create_function(move |lua_ctx, args: LuaMultiValue| {
+                script_log(lua_ctx, &console, level, args)
+            })?;
+            log_table.set(name, function)?;
+        }
+        engine.set("log", log_table)?;
+        let console = self.console.clone();
+        let print = lua.create_function(move |lua_ctx, args: LuaMultiValue| {
+            script_log(lua_ctx, &console, log::Level::Info, args)
+        })?;
+        lua.globals().set("print", print)?;
+
+        // engine.rand() / engine.rand_range(a, b)
+        let rng = Arc::clone(&self.rng);
+        let rand = lua.create_function(move |_, ()| {
+            let mut slot = rng.lock().expect("script rng poisoned");
+            if !slot.stepping {
+                return Err(LuaError::runtime(
+                    "engine.rand may only be called from on_update or an event handler",
+                ));
+            }
+            Ok(slot.rng.next_f64())
+        })?;
+        engine.set("rand", rand)?;
+        let rng = Arc::clone(&self.rng);
+        let rand_range = lua.create_function(move |_, (low, high): (i64, i64)| {
+            let mut slot = rng.lock().expect("script rng poisoned");
+            if !slot.stepping {
+                return Err(LuaError::runtime(
+                    "engine.rand_range may only be called from on_update or an event handler",
+                ));
+            }
+            slot.rng
+                .range_inclusive(low, high)
+                .map_err(|e| LuaError::runtime(format!("engine.rand_range: {e}")))
+        })?;
+        engine.set("rand_range", rand_range)?;
+
+        // engine.spawn(template, x, y) -> id / engine.despawn(id)
+        engine.set("_actor_commands", lua.create_table()?)?;
+        let rng = Arc::clone(&self.rng);
+        let actor_ids = Arc::clone(&self.actor_ids);
+        let spawn = lua.create_function(move |lua_ctx, (template, x, y): (String, f32, f32)| {
+            if !rng.lock().expect("script rng poisoned").stepping {
+                return Err(LuaError::runtime(
+                    "engine.spawn may only be called from on_update or an event handler",
+                ));
+            }
+            let id = actor_ids
+                .lock()
+                .expect("actor ids poisoned")
+                .allocate(&template);
+            let engine: LuaTable = lua_ctx.globals().get("engine")?;
+            let queue: LuaTable = engine.get("_actor_commands")?;
+            let command = lua_ctx.create_table()?;
+            command.set("id", id.as_str())?;
+            command.set("template", template)?;
+            command.set("x", x)?;
+            command.set("y", y)?;
+            queue.push(command)?;
+            Ok(id)
+        })?;
+        engine.set("spawn", spawn)?;
+        let despawn = lua.create_function(|lua_ctx, id: String| {
+            let engine: LuaTable = lua_ctx.globals().get("engine")?;
+            let queue: LuaTable = engine.get("_actor_commands")?;
+            let command = lua_ctx.create_table()?;
+            command.set("id", id)?;
+            queue.push(command)
+        })?;
+        engine.set("despawn", despawn)?;
+
+        // engine.projectile.spawn(x, y, vx, vy, opts) -> id | nil
+        let projectile_table = lua.create_table()?;
+        projectile_table.set("hits", lua.create_table()?)?;
+        let rng = Arc::clone(&self.rng);
+        let projectiles = Arc::clone(&self.projectiles);
+        let spawn_projectile = lua.create_function(
+            move |_, (x, y, vx, vy, opts): (f32, f32, f32, f32, Option<LuaTable>)| {
+                if !rng.lock().expect("script rng poisoned").stepping {
+                    return Err(LuaError::runtime(
+                        "engine.projectile.spawn may only be called from on_update or an event handler",
+                    ));
+                }
+                let spec = projectile_spec(Vec2::new(x, y), Vec2::new(vx, vy), opts)?;
+                let fired = projectiles
+                    .lock()
+                    .expect("projectile pool poisoned")
+                    .spawn(spec);
+                match fired {
+                    Ok(id) => Ok(Some(id)),
+                    Err(err) => {
+                        log::warn!("engine.projectile.spawn: {err}");
+                        Ok(None)
+                    }
+                }
+            },
+        )?;
+        projectile_table.set("spawn", spawn_projectile)?;
+        engine.set("projectile", projectile_table)?;
+
+        // engine.inventory.* -- per-actor item stacks, keyed by actor id
+        let inventory = lua.create_table()?;
+        let items = Arc::clone(&self.items);
+        let inventories = Arc::clone(&self.inventories);
+        let add = lua.create_function(
+            move |_, (actor_id, item_id, count): (String, String, Option<u32>)| {
+                let items = items.lock().expect("item registry poisoned");
+                let item = items.get(&item_id).ok_or_else(|| {
+                    LuaError::runtime(format!("engine.inventory.add: unknown item '{item_id}'"))
+                })?;
+                let mut inventories = inventories.lock().expect("inventories poisoned");
+                Ok(inventories.get_mut(&actor_id).add(item, count.unwrap_or(1)))
+            },
+        )?;
+        inventory.set("add", add)?;
+        let inventories = Arc::clone(&self.inventories);
+        let remove = lua.create_function(
+            move |_, (actor_id, item_id, count): (String, String, Option<u32>)| {
+                let mut inventories = inventories.lock().expect("inventories poisoned");
+                Ok(inventories
+                    .get_mut(&actor_id)
+                    .remove(&item_id, count.unwrap_or(1)))
+            },
+        )?;
+        inventory.set("remove", remove)?;
+        let inventories = Arc::clone(&self.inventories);
+        let count = lua.create_function(move |_, (actor_id, item_id): (String, String)| {
+            let inventories = inventories.lock().expect("inventories poisoned");
+            Ok(inventories.count(&actor_id, &item_id))
+        })?;
+        inventory.set("count", count)?;
+        let items = Arc::clone(&self.items);
+        let inventories = Arc::clone(&self.inventories);
+        let list_items = lua.create_function(move |lua, actor_id: String| {
+            let items = items.lock().expect("item registry poisoned");
+            let inventories = inventories.lock().expect("inventories poisoned");
+            let list = lua.create_table()?;
+            for stack in inventories
+                .get(&actor_id)
+                .map_or(&[][..], |inv| inv.stacks())
+            {
+                let entry = lua.create_table()?;
+                entry.set("item", stack.item.as_str())?;
+                entry.set("count", stack.count)?;
+                let sprite_id = items
+                    .get(&stack.item)
+                    .and_then(|item| item.sprite_id.as_deref());
+                entry.set("sprite_id", sprite_id)?;
+                list.push(entry)?;
+            }
+            Ok(list)
+        })?;
+        inventory.set("items", list_items)?;
+        engine.set("inventory", inventory)?;
+
+        // engine.save.* -- persistent variables and slots, owned by Rust
+        let save_table = lua.create_table()?;
+        let save = Arc::clone(&self.save);
+        let persist = lua.create_function(move |lua, (name, default): (String, LuaValue)| {
+            let default = lua_to_json(default, 0)
+                .map_err(|e| LuaError::runtime(format!("engine.save.persist('{name}'): {e}")))?;
+            let mut save = save.lock().expect("save state poisoned");
+            json_to_lua(lua, save.persist(&name, default))
+        })?;
+        save_table.set("persist", persist)?;
+        let save = Arc::clone(&self.save);
+        let get = lua.create_function(move |lua, name: String| {
+            let save = save.lock().expect("save state poisoned");
+            match save.get(&name) {
+                Some(value) => json_to_lua(lua, value),
+                None => Ok(LuaValue::Nil),
+            }
+        })?;
+        save_table.set("get", get)?;
+        let save = Arc::clone(&self.save);
+        let set = lua.create_function(move |_, (name, value): (String, LuaValue)| {
+            let value = lua_to_json(value, 0)
+                .map_err(|e| LuaError::runtime(format!("engine.save.set('{name}'): {e}")))?;
+            save.lock()
+                .expect("save state poisoned")
+                .set(&name, value)
+                .map_err(|e| LuaError::runtime(format!("engine.save.set: {e}")))
+        })?;
+        save_table.set("set", set)?;
+        let save = Arc::clone(&self.save);
+        let unlock = lua.create_function(move |_, id: String| {
+            Ok(save
+                .lock()
+                .expect("save state poisoned")
+                .unlock_checkpoint(&id))
+        })?;
+        save_table.set("unlock_checkpoint", unlock)?;
+        let save = Arc::clone(&self.save);
+        let checkpoints = lua.create_function(move |lua, ()| {
+            let save = save.lock().expect("save state poisoned");
+            lua.create_sequence_from(save.checkpoints().iter().map(String::as_str))
+        })?;
+        save_table.set("checkpoints", checkpoints)?;
+        let save = Arc::clone(&self.save);
+        let play_time = lua.create_function(move |_, ()| {
+            Ok(save.lock().expect("save state poisoned").play_time())
+        })?;
+        save_table.set("play_time", play_time)?;
+        let save = Arc::clone(&self.save);
+        let write = lua.create_function(move |_, slot: u32| {
+            match save.lock().expect("save state poisoned").write(slot) {
+                Ok(path) => {
+                    log::info!("Saved slot {slot} to {}", path.display());
+                    Ok((Some(true), None))
+                }
+                Err(err) => {
+                    log::warn!("engine.save.write({slot}): {err}");
+                    Ok((None, Some(err)))
+                }
+            }
+        })?;
+        save_table.set("write", write)?;
+        let save = Arc::clone(&self.save);
+        let read = lua.create_function(move |_, slot: u32| {
+            match save.lock().expect("save state poisoned").read(slot) {
+                Ok(()) => Ok((Some(true), None)),
+                Err(err) => {
+                    log::warn!("engine.save.read({slot}): {err}");
+                    Ok((None, Some(err)))
+                }
+            }
+        })?;
+        save_table.set("read", read)?;
+        engine.set("save", save_table)?;
+
+        // engine.settings.* -- player options; Rust applies and saves changes
+        let settings_table = lua.create_table()?;
+        let settings = Arc::clone(&self.settings);
+        let get_setting = lua.create_function(move |lua, name: String| {
+            let settings = settings.lock().expect("settings poisoned");
+            let value = settings
+                .settings()
+                .get(&name)
+                .map_err(|e| LuaError::runtime(format!("engine.settings.get: {e}")))?;
+            json_to_lua(lua, &value)
+        })?;
+        settings_table.set("get", get_setting)?;
+        let settings = Arc::clone(&self.settings);
+        let set_setting = lua.create_function(move |_, (name, value): (String, LuaValue)| {
+            let value = lua_to_json(value, 0)
+                .map_err(|e| LuaError::runtime(format!("engine.settings.set('{name}'): {e}")))?;
+            settings
+                .lock()
+                .expect("settings poisoned")
+                .update(|settings| settings.set(&name, &value))
+                .map_err(|e| LuaError::runtime(format!("engine.settings.set: {e}")))
+        })?;
+        settings_table.set("set", set_setting)?;
+        let settings = Arc::clone(&self.settings);
+        let bind = lua.create_function(move |_, (key, physical): (String, Option<String>)| {
+            settings
+                .lock()
+                .expect("settings poisoned")
+                .update(|settings| settings.bind(&key, physical.as_deref()))
+                .map_err(|e| LuaError::runtime(format!("engine.settings.bind: {e}")))
+        })?;
+        settings_table.set("bind", bind)?;
+        engine.set("settings", settings_table)?;
+
+        // engine.events.* -- handlers live in the Lua state, events on the bus
+        engine.set("_event_handlers", lua.create_table()?)?;
+        let events_table = lua.create_table()?;
+        let events = Arc::clone(&self.events);
+        let emit = lua.create_function(move |_, (name, payload): (String, LuaValue)| {
+            let payload = lua_to_json(payload, 0)
+                .map_err(|e| LuaError::runtime(format!("engine.events.emit('{name}'): {e}")))?;
+            events
+                .lock()
+                .expect("event bus poisoned")
+                .emit(EngineEvent::Custom(name, payload));
+            Ok(())
+        })?;
+        events_table.set("emit", emit)?;
+        let on = lua.create_function(|lua_ctx, (name, handler): (String, LuaFunction)| {
+            let engine: LuaTable = lua_ctx.globals().get("engine")?;
+            let handlers: LuaTable = engine.get("_event_handlers")?;
+            let list = match handlers.get::<Option<LuaTable>>(name.as_str())? {
+                Some(list) => list,
+                None => {
+                    let list = lua_ctx.create_table()?;
+                    handlers.set(name.as_str(), &list)?;
+                    list
+                }
+            };
+            list.push(handler)
+        })?;
+        events_table.set("on", on)?;
+        let off = lua.create_function(|lua_ctx, name: String| {
+            let engine: LuaTable = lua_ctx.globals().get("engine")?;
+            let handlers: LuaTable = engine.get("_event_handlers")?;
+            handlers.set(name, LuaValue::Nil)
+        })?;
+        events_table.set("off", off)?;
+        engine.set("events", events_table)?;
+
+        lua.globals().set("engine", engine)?;
+        self.install_loc_strings()?;
+        self.install_app_state()?;
+
+        Ok(())
+    }
+}
+
+/// Split an mlua error string into message and traceback, and attach the
+/// script lines around the location it points at. mlua formats locations as
+/// `[string "<chunk name>"]:<line>:`, and the script is loaded with its path
+/// as the chunk name.
+fn parse_lua_error(context: &str, error: &str, script_path: &std::path::Path) -> LuaErrorRecord {
+    let (message, traceback) = match error.split_once("\nstack traceback:\n") {
+        Some((message, traceback)) => (message, Some(traceback.to_string())),
+        None => (error, None),
+    };
+    let location = format!("[string \"{}\"]:", script_path.to_string_lossy());
+    let line = message.find(&location).and_then(|start| {
+        let rest = &message[start + location.len()..];
+        rest.split(':').next()?.parse::<usize>().ok()
+    });
+    let excerpt = match (line, sme_core::assets::read_to_string(script_path)) {
+        (Some(line), Ok(source)) => {
+            let first = line.saturating_sub(EXCERPT_CONTEXT).max(1);
+            source
+                .lines()
+                .enumerate()
+                .map(|(i, text)| (i + 1, text.to_string()))
+                .skip(first - 1)
+                .take(line + EXCERPT_CONTEXT + 1 - first)
+                .collect()
+        }
+        _ => Vec::new(),
+    };
+    LuaErrorRecord {
+        context: context.to_string(),
+        message: message.to_string(),
+        traceback,
+        line,
+        excerpt,
+    }
+}
+
+/// Write the read-only actor fields shared by `engine.actor` and
+/// `engine.actors[id]`.
+/// Append one `engine.debug.draw_*` call to `engine._draw_queue`. The color
+/// is checked here so a bad one errors at the script line that passed it.
+fn queue_debug_draw(
+    lua: &Lua,
+    shape: &str,
+    args: &[f32],
+    text: Option<String>,
+    color: Option<LuaTable>,
+) -> LuaResult<()> {
+    let color = match color {
+        Some(table) => {
+            let channels = table
+                .sequence_values::<f32>()
+                .collect::<LuaResult<Vec<f32>>>()?;
+            match channels[..] {
+                [r, g, b] => [r, g, b, 1.0],
+                [r, g, b, a] => [r, g, b, a],
+                _ => {
+                    return Err(LuaError::runtime(
+                        "debug draw color must be {r, g, b} or {r, g, b, a}",
+                    ))
+                }
+            }
+        }
+        None => DEFAULT_DEBUG_COLOR,
+    };
+    let engine: LuaTable = lua.globals().get("engine")?;
+    let queue: LuaTable = engine.get("_draw_queue")?;
+    let entry = lua.create_table()?;
+    entry.set("shape", shape)?;
+    entry.set("args", args)?;
+    entry.set("color", color)?;
+    if let Some(text) = text {
+        entry.set("text", text)?;
+    }
+    queue.push(entry)
+}
+
+/// Deepest table nesting an event payload may have.
+const MAX_PAYLOAD_DEPTH: usize = 16;
+
+/// Convert an event payload to JSON. Tables whose keys are exactly `1..=n`
+/// become arrays; other tables become objects with string keys.
+fn lua_to_json(value: LuaValue, depth: usize) -> Result<serde_json::Value, String> {
+    use serde_json::Value;
+    Ok(match value {
+        LuaValue::Nil => Value::Null,
+        LuaValue::Boolean(b) => Value::Bool(b),
+        LuaValue::Integer(i) => Value::from(i),
+        LuaValue::Number(n) => serde_json::Number::from_f64(n)
+            .map(Value::Number)
+            .ok_or_else(|| format!("{n} is not a finite number"))?,
+        LuaValue::String(s) => Value::String(s.to_str().map_err(|e| e.to_string())?.to_string()),
+        LuaValue::Table(table) => {
+            if depth >= MAX_PAYLOAD_DEPTH {
+                return Err(format!(
+                    "payload nests deeper than {MAX_PAYLOAD_DEPTH} tables"
+                ));
+            }
+            let len = table.raw_len();
+            let pairs: Vec<(LuaValue, LuaValue)> = table
+                .pairs::<LuaValue, LuaValue>()
+                .collect::<LuaResult<_>>()
+                .map_err(|e| e.to_string())?;
+            if len > 0 && pairs.len() == len {
+                let mut array = Vec::with_capacity(len);
+                for i in 1..=len {
+                    array.push(lua_to_json(
+                        table.raw_get(i).map_err(|e| e.to_string())?,
+                        depth + 1,
+                    )?);
+                }
+                Value::Array(array)
+            } else {
+                let mut object = serde_json::Map::new();
+                for (key, value) in pairs {
+                    let key = match key {
+                        LuaValue::String(s) => s.to_str().map_err(|e| e.to_string())?.to_string(),
+                        LuaValue::Integer(i) => i.to_string(),
+                        LuaValue::Number(n) => n.to_string(),
+                        other => {
+                            return Err(format!("{} keys are not supported", other.type_name()))
+                        }
+                    };
+                    object.insert(key, lua_to_json(value, depth + 1)?);
+                }
+                Value::Object(object)
+            }
+        }
+        other => return Err(format!("{} values are not supported", other.type_name())),
+    })
+}
+
+fn json_to_lua(lua: &Lua, value: &serde_json::Value) -> LuaResult<LuaValue> {
+    use serde_json::Value;
+    Ok(match value {
+        Value::Null => LuaValue::Nil,
+        Value::Bool(b) => LuaValue::Boolean(*b),
+        Value::Number(n) => match n.as_i64() {
+            Some(i) => LuaValue::Integer(i),
+            None => LuaValue::Number(n.as_f64().unwrap_or_default()),
+        },
+        Value::String(s) => LuaValue::String(lua.create_string(s)?),
+        Value::Array(items) => {
+            let table = lua.create_table()?;
+            for item in items {
+                table.push(json_to_lua(lua, item)?)?;
+            }
+            LuaValue::Table(table)
+        }
+        Value::Object(fields) => {
+            let table = lua.create_table()?;
+            for (key, field) in fields {
+                table.set(key.as_str(), json_to_lua(lua, field)?)?;
+            }
+            LuaValue::Table(table)
+        }
+    })
+}
+
+/// Read `engine.projectile.spawn` options; every field is optional.
+fn projectile_spec(
+    position: Vec2,
+    velocity: Vec2,
+    opts: Option<LuaTable>,
+) -> LuaResult<ProjectileSpec> {
+    let mut spec = ProjectileSpec {
+        position,
+        velocity,
+        lifetime: DEFAULT_PROJECTILE_LIFETIME,
+        sprite: None,
+        radius: DEFAULT_PROJECTILE_RADIUS,
+        mask: CollisionMask::WORLD,
+    };
+    let Some(opts) = opts else {
+        return Ok(spec);
+    };
+    if let Some(lifetime) = opts.get::<Option<f32>>("lifetime")? {
+        spec.lifetime = lifetime;
+    }
+    if let Some(sprite) = opts.get::<Option<String>>("sprite")? {
+        spec.sprite = Some(sprite.into());
+    }
+    if let Some(radius) = opts.get::<Option<f32>>("radius")? {
+        spec.radius = radius;
+    }
+    if let Some(mask) = opts.get::<Option<Vec<String>>>("mask")? {
+        spec.mask = CollisionMask::from_names(mask.iter().map(String::as_str))
+            .map_err(|e| LuaError::runtime(format!("engine.projectile.spawn: {e}")))?;
+    }
+    if spec.lifetime.is_nan() || spec.lifetime <= 0.0 {
+        return Err(LuaError::runtime(
+            "engine.projectile.spawn: lifetime must be > 0",
+        ));
+    }
+    Ok(spec)
+}
+
+/// Build and validate an `engine.tween.to` request.
+fn tween_spec(
+    to: LuaTable,
+    duration: f32,
+    easing: Option<String>,
+    repeat: Option<String>,
+) -> LuaResult<TweenSpec> {
+    let err = |e: String| LuaError::runtime(format!("engine.tween.to: {e}"));
+    let mut targets = BTreeMap::new();
+    for pair in to.pairs::<String, f32>() {
+        let (name, value) = pair?;
+        targets.insert(TweenProperty::parse(&name).map_err(err)?, value);
+    }
+    let spec = TweenSpec {
+        to: targets,
+        duration,
+        easing: easing
+            .as_deref()
+            .map(Easing::parse)
+            .transpose()
+            .map_err(err)?
+            .unwrap_or_default(),
+        repeat: repeat
+            .as_deref()
+            .map(Repeat::parse)
+            .transpose()
+            .map_err(err)?
+            .unwrap_or_default(),
+    };
+    spec.validate().map_err(err)?;
+    Ok(spec)
+}
+
+fn write_actor_state(table: &LuaTable, actor: &ActorSnapshot) -> LuaResult<()> {
+    table.set("x", actor.x)?;
+    table.set("y", actor.y)?;
+    table.set("half_w", actor.half_w)?;
+    table.set("half_h", actor.half_h)?;
+    table.set("grounded", actor.grounded)?;
+    table.set("swimming", actor.swimming)?;
+    table.set("crouching", actor.crouching)?;
+    table.set("dashing", actor.dashing)?;
+    table.set("invulnerable", actor.invulnerable)?;
+    table.set("time_scale", actor.time_scale)?;
+    table.set("velocity_x", actor.velocity_x)?;
+    table.set("velocity_y", actor.velocity_y)?;
+    match &actor.current_animation {
+        Some(name) => table.set("current_animation", name.as_str())?,
+        None => table.set("current_animation", LuaValue::Nil)?,
+    }
+    table.set("animation_finished", actor.animation_finished)
+}
+
+/// Join `args` the way Lua's `print` does and log them at `level`, tagged
+/// with the calling script and line.
+fn script_log(
+    lua: &Lua,
+    console: &Mutex<VecDeque<ScriptLogRecord>>,
+    level: log::Level,
+    args: LuaMultiValue,
+) -> LuaResult<()> {
+    let tostring: LuaFunction = lua.globals().get("tostring")?;
+    let mut parts = Vec::with_capacity(args.len());
+    for value in args {
+        parts.push(tostring.call::<String>(value)?);
+    }
+    let message = parts.join("\t");
+    let (script, line) = match lua.inspect_stack(1) {
+        Some(caller) => (
+            caller
+                .source()
+                .source
+                .as_deref()
+                .map_or_else(|| "?".to_string(), script_file_name),
+            usize::try_from(caller.curr_line())
+                .ok()
+                .filter(|line| *line > 0),
+        ),
+        None => ("?".to_string(), None),
+    };
+    match line {
+        Some(line) => log::log!(target: "lua", level, "{script}:{line}: {message}"),
+        None => log::log!(target: "lua", level, "{script}: {message}"),
+    }
+    push_console(
+        console,
+        ScriptLogRecord {
+            level,
+            script,
+            line,
+            message,
+        },
+    );
+    Ok(())
+}
+
+fn push_console(console: &Mutex<VecDeque<ScriptLogRecord>>, record: ScriptLogRecord) {
+    let mut console = console.lock().expect("script console poisoned");
+    if console.len() == SCRIPT_LOG_HISTORY {
+        console.pop_front();
+    }
+    console.push_back(record);
+}
+
+/// The function at `path` (`name` or `table.name`) under `globals`.
+fn lookup_function(globals: &LuaTable, path: &str) -> Option<LuaFunction> {
+    let mut parts = path.split('.');
+    let mut value: LuaValue = globals.get(parts.next()?).ok()?;
+    for part in parts {
+        let LuaValue::Table(table) = value else {
+            return None;
+        };
+        value = table.get(part).ok()?;
+    }
+    match value {
+        LuaValue::Function(function) => Some(function),
+        _ => None,
+    }
+}
+
+/// `engine.actor`'s position, velocity and grounded flag on one line.
+fn actor_summary(lua: &Lua) -> String {
+    let actor = lua
+        .globals()
+        .get::<LuaTable>("engine")
+        .and_then(|engine| engine.get::<LuaTable>("actor"));
+    let Ok(actor) = actor else {
+        return "no engine.actor".to_string();
+    };
+    let number = |key: &str| actor.get::<f32>(key).unwrap_or_default();
+    format!(
+        "x={:.1} y={:.1} vx={:.1} vy={:.1}{}",
+        number("x"),
+        number("y"),
+        number("velocity_x"),
+        number("velocity_y"),
+        if actor.get::<bool>("grounded").unwrap_or_default() {
+            " grounded"
+        } else {
+            ""
+        }
+    )
+}
+
+/// `controller.lua` from a chunk name like `@assets/scripts/controller.lua`.
+fn script_file_name(chunk_name: &str) -> String {
+    let path = chunk_name.trim_start_matches(['@', '=']);
+    Path::new(path).file_name().map_or_else(
+        || path.to_string(),
+        |name| name.to_string_lossy().into_owned(),
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::Write;
+
+    const VALID_LUA_SCRIPT: &str = r#"
+function on_update(dt)
+    engine.actor.set_intent(1.0, true)
+end
+"#;
+
+    const INVALID_LUA_SCRIPT: &str = "this is not valid lua !@#$";
+
+    fn temp_lua_path(name: &str) -> PathBuf {
+        let mut path = std::env::temp_dir();
+        path.push(format!(
+            "sme_test_lua_bridge_{}_{}.lua",
+            name,
+            std::process::id()
+        ));
+        path
+    }
+
+    fn write_temp_script(path: &PathBuf, content: &str) {
+        let mut f = std::fs::File::create(path).expect("failed to create temp script");
+        f.write_all(content.as_bytes())
+            .expect("failed to write temp script");
+        f.flush().expect("failed to flush temp script");
+    }
+
+    fn make_input() -> InputSnapshot {
+        InputSnapshot {
+            held_keys: vec![],
+            just_pressed_keys: vec![],
+            ..Default::default()
+        }
+    }
+
+    fn make_actor() -> ActorSnapshot {
+        ActorSnapshot {
+            x: 0.0,
+            y: 0.0,
+            half_w: 8.0,
+            half_h: 8.0,
+            grounded: false,
+            swimming: false,
+            crouching: false,
+            dashing: false,
+            invulnerable: false,
+            time_scale: 1.0,
+            velocity_x: 0.0,
+            velocity_y: 0.0,
+            current_animation: None,
+            animation_finished: false,
+        }
+    }
+
+    #[test]
+    fn lua_status_labels() {
+        let variants = [LuaStatus::Loaded, LuaStatus::Error, LuaStatus::Fallback];
+        for variant in &variants {
+            let label = variant.label();
+            assert!(
+                !label.is_empty(),
+                "{:?} should have a non-empty label",
+                variant
+            );
+        }
+    }
+
+    #[test]
+    fn lua_status_display() {
+        let variants = [LuaStatus::Loaded, LuaStatus::Error, LuaStatus::Fallback];
+        for variant in &variants {
+            let display = format!("{}", variant);
+            assert_eq!(
+                display,
+                variant.label(),
+                "Display for {:?} should match label()",
+                variant
+            );
+        }
+    }
+
+    #[test]
+    fn lua_intent_default() {
+        let intent = LuaIntent::default();
+        assert_eq!(intent.move_x, 0.0);
+        assert!(!intent.jump_pressed);
+    }
+
+    #[test]
+    fn bridge_fallback_when_no_script() {
+        let path = PathBuf::from("__nonexistent_script_for_test_42__.lua");
+        let bridge = LuaBridge::new(path);
+        assert_eq!(bridge.status(), LuaStatus::Fallback);
+    }
+
+    #[test]
+    fn bridge_loads_valid_script() {
+        let path = temp_lua_path("valid");
+        write_temp_script(&path, VALID_LUA_SCRIPT);
+
+        let bridge = LuaBridge::new(path.clone());
+        assert_eq!(
+            bridge.status(),
+            LuaStatus::Loaded,
+            "Expected Loaded, got {:?}. Error: {:?}",
+            bridge.status(),
+            bridge.last_error()
+        );
+
+        let _ = std::fs::remove_file(&path);
+    }
+
+    #[test]
+    fn bridge_error_on_invalid_script() {
+        let path = temp_lua_path("invalid");
+        write_temp_script(&path, INVALID_LUA_SCRIPT);
+
+        let bridge = LuaBridge::new(path.clone());
+        assert_eq!(bridge.status(), LuaStatus::Error);
+
+        let _ = std::fs::remove_file(&path);
+    }
+
+    #[test]
+    fn dialog_conditions_callbacks_and_start_intent() {
+        let path = temp_lua_path("dialog");
+        write_temp_script(
+            &path,
+            r#"
+has_key = false
+function on_accept(dialog_id, node_id)
+    has_key = dialog_id == "intro" and node_id == "greet"
+end
+function on_update(dt)
+    engine.dialog.start("intro")
+end
+"#,
+        );
+
+        let bridge = LuaBridge::new(path.clone());
+        assert!(!bridge.evaluate_condition("has_key"));
+        assert!(!bridge.evaluate_condition("this is not lua"));
+        bridge.call_dialog_callback("on_accept", "intro", "greet");
+        assert!(bridge.evaluate_condition("has_key"));
+
+        let intent = bridge
+            .call_update(1.0 / 60.0, &make_input(), &make_actor())
+            .expect("should return intent");
+        assert_eq!(intent.start_dialog.as_deref(), Some("intro"));
+
+        let _ = std::fs::remove_file(&path);
+    }
+
+    #[test]
+    fn ui_commands_queue_from_update_and_callbacks() {
+        let path = temp_lua_path("ui");
+        write_temp_script(
+            &path,
+            r#"
+function on_toggle(widget_id)
+    engine.ui.set_visible(widget_id, false)
+end
+function on_update(dt)
+    engine.ui.set_value("speed", 0.25)
+    engine.ui.set_text("score", 12)
+end
+"#,
+        );
+
+        let bridge = LuaBridge::new(path.clone());
+        bridge
+            .call_update(1.0 / 60.0, &make_input(), &make_actor())
+            .expect("should return intent");
+        bridge.call_ui_callback("on_toggle", "panel");
+        assert_eq!(
+            bridge.drain_ui_commands(),
+            vec![
+                UiCommand::SetValue {
+                    id: "speed".to_string(),
+                    value: 0.25
+                },
+                UiCommand::SetText {
+                    id: "score".to_string(),
+                    text: "12".to_string()
+                },
+                UiCommand::SetVisible {
+                    id: "panel".to_string(),
+                    visible: false
+                },
+            ]
+        );
+        assert!(bridge.drain_ui_commands().is_empty(), "queue is cleared");
+
+        let _ = std::fs::remove_file(&path);
+    }
+
+    #[test]
+    fn engine_rand_is_seeded_stepped_only_in_update_and_survives_reload() {
+        let path = temp_lua_path("rand");
+        write_temp_script(
+            &path,
+            r#"
+function roll_outside_update()
+    return engine.rand()
+end
+function on_update(dt)
+    engine.actor.play_animation(engine.rand() .. "|" .. engine.rand_range(1, 6))
+end
+"#,
+        );
+
+        let roll = |bridge: &LuaBridge| {
+            bridge
+                .call_update(1.0 / 60.0, &make_input(), &make_actor())
+                .and_then(|intent| intent.play_animation)
+                .expect("should roll")
+        };
+
+        let mut a = LuaBridge::new(path.clone());
+        let mut b = LuaBridge::new(path.clone());
+        a.set_rng_seed(99);
+        b.set_rng_seed(99);
+        let first = roll(&a);
+        assert_eq!(first, roll(&b));
+        let second = roll(&a);
+        assert_ne!(first, second, "state advances each call");
+        assert_eq!(second, roll(&b));
+
+        // Reloading the script keeps the RNG where it was.
+        b.force_reload();
+        assert_eq!(roll(&a), roll(&b));
+
+        // Calls outside on_update fail instead of advancing the RNG.
+        let outside: LuaResult<f64> = a
+            .lua
+            .globals()
+            .get::<LuaFunction>("roll_outside_update")
+            .and_then(|f| f.call(()));
+        assert!(outside.is_err());
+        assert_eq!(a.rng_seed(), 99);
+
+        let _ = std::fs::remove_file(&path);
+    }
+
+    #[test]
+    fn world_queries_and_actor_transforms() {
+        use crate::collision::{CollisionFile, GridCell, GridOrigin};
+
+        let path = temp_lua_path("world");
+        write_temp_script(
+            &path,
+            r#"
+function on_update(dt)
+    local x, y = engine.actor.position()
+    local wall_ahead = engine.world.is_solid(x + 32, y)
+    local inside = engine.world.overlap_aabb(x, y, 8, 8)
+    local goblin = engine.actors["goblin_1"]
+    engine.actor.play_animation(
+        tostring(wall_ahead) .. "|" .. tostring(inside) .. "|" .. goblin.velocity_y)
+end
+"#,
+        );
+
+        let bridge = LuaBridge::new(path.clone());
+        bridge.set_collision_grid(CollisionGrid::from_file(CollisionFile {
+            version: "0.1".to_string(),
+            collision_id: "test".to_string(),
+            cell_size: 32,
+            origin: GridOrigin { x: 0, y: 0 },
+            width: 8,
+            height: 8,
+            solids: vec![GridCell { x: 2, y: 0 }],
+            fluids: Vec::new(),
+            surfaces: Vec::new(),
+            time_volumes: Vec::new(),
+        }));
+        let player = ActorSnapshot {
+            x: 48.0,
+            y: 16.0,
+            ..make_actor()
+        };
+        let goblin = ActorSnapshot {
+            velocity_y: -3.5,
+            ..make_actor()
+        };
+        bridge.set_actors(&[("player", &player), ("goblin_1", &goblin)]);
+        let intent = bridge
+            .call_update(1.0 / 60.0, &make_input(), &player)
+            .expect("should return intent");
+        assert_eq!(intent.play_animation.as_deref(), Some("true|false|-3.5"));
+
+        let _ = std::fs::remove_file(&path);
+    }
+
+    #[test]
+    fn debug_watch_and_plot_queue_values() {
+        let path = temp_lua_path("debug");
+        write_temp_script(
+            &path,
+            r#"
+function on_update(dt)
+    engine.debug.watch("combo_count", 3)
+    engine.debug.watch("state", nil)
+    engine.debug.plot("speed", 1.5)
+end
+"#,
+        );
+
+        let bridge = LuaBridge::new(path.clone());
+        bridge
+            .call_update(1.0 / 60.0, &make_input(), &make_actor())
+            .expect("should return intent");
+        assert_eq!(
+            bridge.drain_debug_values(),
+            vec![
+                ScriptDebugValue::Watch {
+                    name: "combo_count".to_string(),
+                    text: "3".to_string()
+                },
+                ScriptDebugValue::Watch {
+                    name: "state".to_string(),
+                    text: "nil".to_string()
+                },
+                ScriptDebugValue::Plot {
+                    name: "speed".to_string(),
+                    value: 1.5
+                },
+            ]
+        );
+        assert!(bridge.drain_debug_values().is_empty(), "queue is cleared");
+
+        let _ = std::fs::remove_file(&path);
+    }
+
+    #[test]
+    fn debug_draw_calls_replay_into_the_rust_queue() {
+        use crate::debug_draw::CIRCLE_SEGMENTS;
+
+        let path = temp_lua_path("debug_draw");
+        write_temp_script(
+            &path,
+            r#"
+function on_update(dt)
+    engine.debug.draw_line(0, 0, 10, 0)
+    engine.debug.draw_rect(5, 5, 4, 2, {1, 0, 0})
+    engine.debug.draw_circle(0, 0, 3, {0, 1, 0, 0.5})
+    engine.debug.draw_text(1, 2, 42)
+end
+
+function bad_color()
+    engine.debug.draw_line(0, 0, 1, 1, {1, 0})
+end
+"#,
+        );
+
+        let bridge = LuaBridge::new(path.clone());
+        bridge
+            .call_update(1.0 / 60.0, &make_input(), &make_actor())
+            .expect("should return intent");
+        let mut draw = DebugDraw::new();
+        bridge.drain_debug_draws(&mut draw);
+        let segments = draw.segments();
+        assert_eq!(segments.len(), 1 + 4 + CIRCLE_SEGMENTS);
+        assert_eq!(segments[0].2, DEFAULT_DEBUG_COLOR);
+        assert_eq!(segments[1].2, [1.0, 0.0, 0.0, 1.0]);
+        assert_eq!(segments[5].2, [0.0, 1.0, 0.0, 0.5]);
+        let labels: Vec<_> = draw.labels().collect();
+        assert_eq!(labels, [(Vec2::new(1.0, 2.0), "42", DEFAULT_DEBUG_COLOR)]);
+
+        let mut again = DebugDraw::new();
+        bridge.drain_debug_draws(&mut again);
+        assert!(again.segments().is_empty(), "queue is cleared");
+
+        let bad: LuaResult<()> = bridge
+            .lua
+            .globals()
+            .get::<LuaFunction>("bad_color")
+            .and_then(|f| f.call(()));
+        assert!(bad.is_err());
+
+        let _ = std::fs::remove_file(&path);
+    }
+
+    #[test]
+    fn spawn_returns_counted_ids_and_queues_commands() {
+        let path = temp_lua_path("spawn");
+        write_temp_script(
+            &path,
+            r#"
+function on_update(dt)
+    local coin = engine.spawn("coin", 10, 20)
+    engine.debug.watch("coin", coin)
+    engine.despawn(coin)
+end
+
+function spawn_outside_update()
+    engine.spawn("coin", 0, 0)
+end
+"#,
+        );
+
+        let bridge = LuaBridge::new(path.clone());
+        bridge
+            .call_update(1.0 / 60.0, &make_input(), &make_actor())
+            .expect("should return intent");
+        assert_eq!(
+            bridge.drain_actor_commands(),
+            [
+                ActorCommand::Spawn {
+                    id: "coin#1".to_string(),
+                    template: "coin".to_string(),
+                    x: 10.0,
+                    y: 20.0,
+                },
+                ActorCommand::Despawn("coin#1".to_string()),
+            ]
+        );
+        assert!(bridge.drain_actor_commands().is_empty(), "queue is cleared");
+
+        // Restoring the counter replays the same ids.
+        let saved = bridge.actor_ids();
+        bridge
+            .call_update(1.0 / 60.0, &make_input(), &make_actor())
+            .expect("second step");
+        bridge.restore_actor_ids(saved);
+        bridge
+            .call_update(1.0 / 60.0, &make_input(), &make_actor())
+            .expect("replayed step");
+        let watched: Vec<_> = bridge.drain_debug_values();
+        assert_eq!(
+            watched[1],
+            ScriptDebugValue::Watch {
+                name: "coin".to_string(),
+                text: "coin#2".to_string()
+            }
+        );
+        assert_eq!(watched[1], watched[2]);
+
+        let outside: LuaResult<()> = bridge
+            .lua
+            .globals()
+            .get::<LuaFunction>("spawn_outside_update")
+            .and_then(|f| f.call(()));
+        assert!(outside.is_err());
+
+        let _ = std::fs::remove_file(&path);
+    }
+
+    #[test]
+    fn projectiles_fire_into_the_pool_and_report_hits() {
+        let path = temp_lua_path("projectiles");
+        write_temp_script(
+            &path,
+            r#"
+function on_update(dt)
+    for _, hit in ipairs(engine.projectile.hits) do
+        engine.debug.watch("hit", hit.id .. " " .. hit.target .. " " .. hit.normal_x)
+    end
+    local id = engine.projectile.spawn(1, 2, 300, 0, { lifetime = 0.5, mask = { "world", "actors" } })
+    engine.debug.watch("fired", id)
+end
+
+function bad_mask()
+    engine.projectile.spawn(0, 0, 0, 0, { mask = { "walls" } })
+end
+"#,
+        );
+
+        let bridge = LuaBridge::new(path.clone());
+        bridge
+            .call_update(1.0 / 60.0, &make_input(), &make_actor())
+            .expect("should return intent");
+        {
+            let pool = bridge.projectiles();
+            let fired: Vec<_> = pool.iter().collect();
+            assert_eq!(fired.len(), 1);
+            assert_eq!(fired[0].position, Vec2::new(1.0, 2.0));
+            assert_eq!(fired[0].lifetime, 0.5);
+            assert_eq!(fired[0].mask, CollisionMask::WORLD | CollisionMask::ACTORS);
+        }
+
+        bridge.set_projectile_hits(&[ProjectileHit {
+            id: 1,
+            position: Vec2::new(32.0, 2.0),
+            target: HitTarget::World {
+                normal: Vec2::new(-1.0, 0.0),
+            },
+        }]);
+        bridge
+            .call_update(1.0 / 60.0, &make_input(), &make_actor())
+            .expect("second step");
+        let watched = bridge.drain_debug_values();
+        assert!(watched.contains(&ScriptDebugValue::Watch {
+            name: "hit".to_string(),
+            text: "1 world -1.0".to_string()
+        }));
+        assert!(watched.contains(&ScriptDebugValue::Watch {
+            name: "fired".to_string(),
+            text: "2".to_string()
+        }));
+
+        let bad: LuaResult<()> = bridge
+            .lua
+            .globals()
+            .get::<LuaFunction>("bad_mask")
+            .and_then(|f| f.call(()));
+        assert!(bad.is_err());
+
+        let _ = std::fs::remove_file(&path);
+    }
+
+    #[test]
+    fn inventory_functions_share_the_rust_store() {
+        let path = temp_lua_path("inventory");
+        write_temp_script(
+            &path,
+            r#"
+function on_update(dt)
+    local added = engine.inventory.add("player", "coin", 12)
+    local removed = engine.inventory.remove("player", "coin", 5)
+    local stacks = engine.inventory.items("player")
+    engine.debug.watch("coins", added .. " " .. removed .. " " .. #stacks .. " "
+        .. stacks[1].count .. " " .. stacks[1].sprite_id)
+end
+
+function give_unknown()
+    engine.inventory.add("player", "anvil", 1)
+end
+"#,
+        );
+        let items_path = temp_lua_path("inventory_items").with_extension("json");
+        std::fs::write(
+            &items_path,
+            r#"{ "version": "0.1", "items": [{ "id": "coin", "sprite_id": "coin_0", "stack_size": 5 }] }"#,
+        )
+        .expect("write items");
+
+        let bridge = LuaBridge::new(path.clone());
+        bridge.items().load_file(&items_path).expect("items");
+        bridge
+            .call_update(1.0 / 60.0, &make_input(), &make_actor())
+            .expect("should return intent");
+        assert_eq!(
+            bridge.drain_debug_values(),
+            [ScriptDebugValue::Watch {
+                name: "coins".to_string(),
+                text: "12 5 2 5 coin_0".to_string()
+            }]
+        );
+        assert_eq!(bridge.inventories().count("player", "coin"), 7);
+
+        let unknown: LuaResult<()> = bridge
+            .lua
+            .globals()
+            .get::<LuaFunction>("give_unknown")
+            .and_then(|f| f.call(()));
+        assert!(unknown.is_err());
+
+        let _ = std::fs::remove_file(&path);
+        let _ = std::fs::remove_file(&items_path);
+    }
+
+    #[test]
+    fn settings_changes_are_validated_and_marked() {
+        let path = temp_lua_path("settings");
+        write_temp_script(
+            &path,
+            r#"
+function on_update(dt)
+    engine.settings.set("volume", 0.25)
+    engine.settings.bind("space", "KeyK")
+    engine.debug.watch("vsync", tostring(engine.settings.get("vsync")))
+end
+
+function set_bad_volume()
+    engine.settings.set("volume", 4)
+end
+"#,
+        );
+
+        let bridge = LuaBridge::new(path.clone());
+        bridge
+            .call_update(1.0 / 60.0, &make_input(), &make_actor())
+            .expect("should return intent");
+        assert_eq!(
+            bridge.drain_debug_values(),
+            [ScriptDebugValue::Watch {
+                name: "vsync".to_string(),
+                text: "true".to_string()
+            }]
+        );
+        let changed = bridge.settings().take_changed().expect("settings changed");
+        assert_eq!(changed.volume, 0.25);
+        assert_eq!(
+            changed.bindings.get("space").map(String::as_str),
+            Some("KeyK")
+        );
+        assert!(bridge.settings().take_changed().is_none());
+
+        let bad: LuaResult<()> = bridge
+            .lua
+            .globals()
+            .get::<LuaFunction>("set_bad_volume")
+            .and_then(|f| f.call(()));
+        assert!(bad.is_err());
+        assert!(bridge.settings().take_changed().is_none());
+
+        let _ = std::fs::remove_file(&path);
+    }
+
+    #[test]
+    fn save_variables_persist_in_rust() {
+        let path = temp_lua_path("save");
+        write_temp_script(
+            &path,
+            r#"
+local coins = engine.save.persist("coins", 0)
+
+function on_update(dt)
+    engine.save.set("coins", engine.save.get("coins") + 5)
+    local first = engine.save.unlock_checkpoint("bridge")
+    local again = engine.save.unlock_checkpoint("bridge")
+    local ok, err = engine.save.write(1)
+    engine.debug.watch("save", coins .. " " .. tostring(first) .. " " .. tostring(again)
+        .. " " .. #engine.save.checkpoints() .. " " .. tostring(ok) .. " " .. tostring(err ~= nil))
+end
+
+function set_undeclared()
+    engine.save.set("lives", 3)
+end
+"#,
+        );
+
+        let mut bridge = LuaBridge::new(path.clone());
+        bridge
+            .call_update(1.0 / 60.0, &make_input(), &make_actor())
+            .expect("should return intent");
+        // No data directory was set, so the write fails softly.
+        assert_eq!(
+            bridge.drain_debug_values(),
+            [ScriptDebugValue::Watch {
+                name: "save".to_string(),
+                text: "0 true false 1 nil true".to_string()
+            }]
+        );
+        assert_eq!(bridge.save().get("coins"), Some(&serde_json::json!(5)));
+
+        let undeclared: LuaResult<()> = bridge
+            .lua
+            .globals()
+            .get::<LuaFunction>("set_undeclared")
+            .and_then(|f| f.call(()));
+        assert!(undeclared.is_err());
+
+        // A reloaded script declares the variable again and keeps its value.
+        bridge.try_load_script();
+        assert_eq!(bridge.save().get("coins"), Some(&serde_json::json!(5)));
+
+        let _ = std::fs::remove_file(&path);
+    }
+
+    #[test]
+    fn events_round_trip_through_the_bus_to_handlers() {
+        let path = temp_lua_path("events");
+        write_temp_script(
+            &path,
+            r#"
+engine.events.on("coin", function(p)
+    engine.debug.watch("coin", p.value .. " " .. #p.list .. " " .. p.list[2].name)
+end)
+engine.events.on("collision", function(p)
+    engine.debug.watch("hit", p.actor .. " " .. p.other .. " " .. p.normal_y)
+end)
+
+function on_update(dt)
+    engine.events.emit("coin", { value = 3, list = { 1, { name = "b" } } })
+end
+
+function emit_function()
+    engine.events.emit("bad", { f = print })
+end
+"#,
+        );
+
+        let bridge = LuaBridge::new(path.clone());
+        bridge
+            .call_update(1.0 / 60.0, &make_input(), &make_actor())
+            .expect("should return intent");
+        bridge.events().emit(EngineEvent::Collision {
+            actor: "player".to_string(),
+            other: "world".to_string(),
+            normal: Vec2::Y,
+        });
+        let events = bridge.events().drain();
+        assert_eq!(
+            events[0],
+            EngineEvent::Custom(
+                "coin".to_string(),
+                serde_json::json!({ "value": 3, "list": [1, { "name": "b" }] })
+            )
+        );
+        bridge.dispatch_events(&events);
+        let watched = bridge.drain_debug_values();
+        assert_eq!(
+            watched,
+            [
+                ScriptDebugValue::Watch {
+                    name: "coin".to_string(),
+                    text: "3 2 b".to_string()
+                },
+                ScriptDebugValue::Watch {
+                    name: "hit".to_string(),
+                    text: "player world 1.0".to_string()
+                },
+            ]
+        );
+
+        let bad: LuaResult<()> = bridge
+            .lua
+            .globals()
+            .get::<LuaFunction>("emit_function")
+            .and_then(|f| f.call(()));
+        assert!(bad.is_err());
+        assert!(bridge.events().drain().is_empty());
+
+        let _ = std::fs::remove_file(&path);
+    }
+
+    #[test]
+    fn errors_keep_traceback_and_source_excerpt() {
+        let path = temp_lua_path("errors");
+        write_temp_script(
+            &path,
+            "-- line 1\n-- line 2\nfunction on_update(dt)\n    local t = nil\n    return t.field\nend\n",
+        );
+
+        let bridge = LuaBridge::new(path.clone());
+        for _ in 0..LUA_ERROR_HISTORY + 2 {
+            assert!(bridge
+                .call_update(1.0 / 60.0, &make_input(), &make_actor())
+                .is_none());
+        }
+        let errors = bridge.recent_errors();
+        assert_eq!(errors.len(), LUA_ERROR_HISTORY, "ring buffer is bounded");
+
+        let error = &errors[0];
+        assert_eq!(error.context, "on_update");
+        assert_eq!(error.line, Some(5));
+        assert!(error.message.contains("attempt to index a nil value"));
+        assert!(!error.message.contains("stack traceback"));
+        assert!(error
+            .traceback
+            .as_deref()
+            .is_some_and(|tb| tb.contains("on_update")));
+        let lines: Vec<usize> = error.excerpt.iter().map(|(n, _)| *n).collect();
+        assert_eq!(lines, vec![3, 4, 5, 6], "excerpt clips at end of file");
+        assert_eq!(error.excerpt[2].1, "    return t.field");
+
+        let _ = std::fs::remove_file(&path);
+    }
+
+    #[test]
+    fn engine_app_reports_focus_and_survives_reload() {
+        let path = temp_lua_path("app_focus");
+        write_temp_script(
+            &path,
+            r#"
+function on_update(dt)
+    local move = 0
+    if engine.app.focused then move = 1 end
+    engine.actor.set_intent(move, engine.app.audio_muted)
+end
+"#,
+        );
+        let mut bridge = LuaBridge::new(path.clone());
+        let intent = bridge
+            .call_update(1.0 / 60.0, &make_input(), &make_actor())
+            .expect("intent");
+        assert_eq!(intent.move_x, 1.0);
+        assert!(!intent.jump_pressed);
+
+        bridge.set_app_state(false, true);
+        bridge.force_reload();
+        let intent = bridge
+            .call_update(1.0 / 60.0, &make_input(), &make_actor())
+            .expect("intent");
+        assert_eq!(intent.move_x, 0.0);
+        assert!(intent.jump_pressed);
+
+        let _ = std::fs::remove_file(&path);
+    }
+
+    #[test]
+    fn engine_loc_reads_strings_and_survives_reload() {
+        let path = temp_lua_path("loc");
+        write_temp_script(
+            &path,
+            r#"
+function on_update(dt)
+    engine.actor.play_animation(engine.loc("anim.greeting") .. "|" .. engine.loc("missing.key"))
+end
+"#,
+        );
+
+        let mut bridge = LuaBridge::new(path.clone());
+        bridge.set_loc_strings(HashMap::from([(
+            "anim.greeting".to_string(),
+            "hola".to_string(),
+        )]));
+        let intent = bridge
+            .call_update(1.0 / 60.0, &make_input(), &make_actor())
+            .expect("should return intent");
+        assert_eq!(intent.play_animation.as_deref(), Some("hola|missing.key"));
+
+        bridge.force_reload();
+        let intent = bridge
+            .call_update(1.0 / 60.0, &make_input(), &make_actor())
+            .expect("should return intent after reload");
+        assert_eq!(intent.play_animation.as_deref(), Some("hola|missing.key"));
+
+        let _ = std::fs::remove_file(&path);
+    }
+
+    #[test]
+    fn bridge_call_update_returns_intent() {
+        let path = temp_lua_path("intent");
+        write_temp_script(&path, VALID_LUA_SCRIPT);
+
+        let bridge = LuaBridge::new(path.clone());
+        assert_eq!(bridge.status(), LuaStatus::Loaded);
+
+        let input = make_input();
+        let actor = make_actor();
+        let intent = bridge
+            .call_update(1.0 / 60.0, &input, &actor)
+            .expect("call_update should return Some(intent)");
+
+        assert!(
+            (intent.move_x - 1.0).abs() < f32::EPSILON,
+            "move_x should be 1.0, got {}",
+            intent.move_x
+        );
+        assert!(intent.jump_pressed, "jump_pressed should be true");
+
+        let _ = std::fs::remove_file(&path);
+    }
+
+    #[test]
+    fn mouse_buttons_position_and_wheel_reach_lua() {
+        let path = temp_lua_path("mouse");
+        write_temp_script(
+            &path,
+            r#"
+function on_update(dt)
+    local x, y = engine.input.mouse_position()
+    local move = 0
+    if engine.input.is_mouse_held("left") then move = x end
+    if engine.input.is_mouse_held("right") then move = -1 end
+    engine.actor.set_intent(move + engine.input.wheel, engine.input.is_mouse_just_pressed("left") and y > 0)
+end
+"#,
+        );
+        let bridge = LuaBridge::new(path.clone());
+        assert_eq!(bridge.status(), LuaStatus::Loaded);
+
+        let input = InputSnapshot {
+            held_buttons: vec!["left".to_string()],
+            just_pressed_buttons: vec!["left".to_string()],
+            mouse_x: 12.0,
+            mouse_y: 3.0,
+            wheel: -2.0,
+            ..make_input()
+        };
+        let intent = bridge
+            .call_update(1.0 / 60.0, &input, &make_actor())
+            .expect("intent");
+        assert_eq!(intent.move_x, 10.0);
+        assert!(intent.jump_pressed);
+
+        // The next step without buttons or wheel sees neither.
+        let intent = bridge
+            .call_update(1.0 / 60.0, &make_input(), &make_actor())
+            .expect("intent");
+        assert_eq!(intent.move_x, 0.0);
+        assert!(!intent.jump_pressed);
+
+        let _ = std::fs::remove_file(&path);
+    }
+
+    #[test]
+    fn game_feel_requests_return_as_intent() {
+        let path = temp_lua_path("time_feel");
+        write_temp_script(
+            &path,
+            r#"
+function on_update(dt)
+    if engine.input.is_just_pressed("space") then
+        engine.time.set_scale(0.25, 0.5)
+        engine.time.hitstop(4)
+        engine.time.hitstop(2)
+        engine.camera.shake(0.25)
+        engine.camera.shake(0.25)
+        engine.sprite.set_trail("player", 8)
+        engine.sprite.set_trail("ghost", 0)
+        engine.sprite.set_effect("player", "flash", 0.75)
+        engine.sprite.set_effect("ghost", "outline")
+        engine.environment.clear_ambient()
+        engine.environment.set_ambient(0.4, 0.45, 0.8)
+        engine.scene.set_group_visible("interior", true)
+        engine.scene.set_group_visible("exterior", false)
+        engine.scene.set_group_visible("interior", false)
+        engine.tween.to("door", { x = 64, alpha = 0.5 }, 0.5, "ease_out")
+        engine.tween.to("lift", { y = 200 }, 2, nil, "ping_pong")
+        engine.scene.load("assets/scenes/cellar.json")
+        engine.scene.load("assets/scenes/roof.json", { collision = "assets/collision/roof.json", fade = 0.5 })
+    end
+end
+"#,
+        );
+        let bridge = LuaBridge::new(path.clone());
+        let input = InputSnapshot {
+            just_pressed_keys: vec!["space".to_string()],
+            ..make_input()
+        };
+        let intent = bridge
+            .call_update(1.0 / 60.0, &input, &make_actor())
+            .expect("intent");
+        assert_eq!(intent.time_scale, Some((0.25, Some(0.5))));
+        assert_eq!(intent.hitstop, 4);
+        assert_eq!(intent.shake, 0.5);
+        assert_eq!(
+            intent.trails,
+            [("ghost".to_string(), 0), ("player".to_string(), 8)]
+        );
+        assert_eq!(
+            intent.effects,
+            [
+                ("ghost".to_string(), SpriteEffect::Outline(1.0)),
+                ("player".to_string(), SpriteEffect::Flash(0.75))
+            ]
+        );
+        assert_eq!(intent.ambient, Some(Some([0.4, 0.45, 0.8])));
+        assert_eq!(intent.tweens.len(), 2);
+        assert_eq!(intent.tweens[0].0, "door");
+        assert_eq!(
+            intent.tweens[0].1,
+            TweenSpec {
+                to: [(TweenProperty::X, 64.0), (TweenProperty::Alpha, 0.5)].into(),
+                duration: 0.5,
+                easing: Easing::EaseOut,
+                repeat: Repeat::Once,
+            }
+        );
+        assert_eq!(
+            (intent.tweens[1].1.easing, intent.tweens[1].1.repeat),
+            (Easing::Linear, Repeat::PingPong)
+        );
+        assert_eq!(
+            intent.layer_groups,
+            [
+                ("exterior".to_string(), false),
+                ("interior".to_string(), false)
+            ]
+        );
+        assert_eq!(
+            intent.scene_load,
+            Some(SceneLoad {
+                scene: PathBuf::from("assets/scenes/roof.json"),
+                collision: Some(PathBuf::from("assets/collision/roof.json")),
+                fade: 0.5,
+            })
+        );
+
+        // Requests don't linger into the next step.
+        let intent = bridge
+            .call_update(1.0 / 60.0, &make_input(), &make_actor())
+            .expect("intent");
+        assert_eq!(intent.time_scale, None);
+        assert_eq!(intent.hitstop, 0);
+        assert_eq!(intent.shake, 0.0);
+        assert!(intent.trails.is_empty());
+        assert!(intent.effects.is_empty());
+        assert_eq!(intent.ambient, None);
+        assert!(intent.layer_groups.is_empty());
+        assert!(intent.tweens.is_empty());
+        assert_eq!(intent.scene_load, None);
+
+        let _ = std::fs::remove_file(&path);
+    }
+
+    #[test]
+    fn dash_and_impulses_return_as_intent() {
+        let path = temp_lua_path("impulses");
+        write_temp_script(
+            &path,
+            r#"
+function on_update(dt)
+    if engine.input.is_just_pressed("space") then
+        engine.actor.dash()
+        engine.actor.add_impulse(-300, 200)
+        engine.actor.add_impulse(50, 0)
+    end
+end
+"#,
+        );
+        let bridge = LuaBridge::new(path.clone());
+        let input = InputSnapshot {
+            just_pressed_keys: vec!["space".to_string()],
+            ..make_input()
+        };
+        let intent = bridge
+            .call_update(1.0 / 60.0, &input, &make_actor())
+            .expect("intent");
+        assert!(intent.dash);
+        assert_eq!(intent.impulses, vec![(-300.0, 200.0), (50.0, 0.0)]);
+
+        let intent = bridge
+            .call_update(1.0 / 60.0, &make_input(), &make_actor())
+            .expect("intent");
+        assert!(!intent.dash && intent.impulses.is_empty());
+        let _ = std::fs::remove_file(&path);
+    }
+
+    #[test]
+    fn bridge_call_update_returns_none_when_fallback() {
+        let path = PathBuf::from("__nonexistent_script_for_test_none__.lua");
+        let bridge = LuaBridge::new(path);
+        assert_eq!(bridge.status(), LuaStatus::Fallback);
+
+        let input = make_input();
+        let actor = make_actor();
+        let result = bridge.call_update(1.0 / 60.0, &input, &actor);
+        assert!(
+            result.is_none(),
+            "call_update should return None in Fallback mode"
+        );
+    }
+
+    #[test]
+    fn bridge_force_reload() {
+        let path = temp_lua_path("reload");
+        // Start with no file -- should be Fallback
+        let _ = std::fs::remove_file(&path);
+        let mut bridge = LuaBridge::new(path.clone());
+        assert_eq!(bridge.status(), LuaStatus::Fallback);
+
+        // Now write a valid script and force reload
+        write_temp_script(&path, VALID_LUA_SCRIPT);
+        bridge.force_reload();
+        assert_eq!(
+            bridge.status(),
+            LuaStatus::Loaded,
+            "After force_reload with valid script, status should be Loaded. Error: {:?}",
+            bridge.last_error()
+        );
+
+        let _ = std::fs::remove_file(&path);
+    }
+
+    #[test]
+    fn lua_determinism_same_inputs_same_outputs() {
+        // Write a Lua script that makes decisions based on input state
+        let path = temp_lua_path("determinism");
+        write_temp_script(
+            &path,
+            r#"
+function on_update(dt)
+    local move_x = 0
+    if engine.input.is_held("right") then
+        move_x = 1
+    elseif engine.input.is_held("left") then
+        move_x = -1
+    end
+    local jump = engine.input.is_just_pressed("space")
+    engine.actor.set_intent(move_x, jump)
+end
+"#,
+        );
+
+        let dt = 1.0 / 60.0;
+
+        // Define a fixed input sequence (simulating gameplay)
+        let input_sequence: Vec<InputSnapshot> = vec![
+            // Standing still
+            InputSnapshot {
+                held_keys: vec![],
+                just_pressed_keys: vec![],
+                ..Default::default()
+            },
+            InputSnapshot {
+                held_keys: vec![],
+                just_pressed_keys: vec![],
+                ..Default::default()
+            },
+            // Start moving right
+            InputSnapshot {
+                held_keys: vec!["right".to_string()],
+                just_pressed_keys: vec!["right".to_string()],
+                ..Default::default()
+            },
+            InputSnapshot {
+                held_keys: vec!["right".to_string()],
+                just_pressed_keys: vec![],
+                ..Default::default()
+            },
+            InputSnapshot {
+                held_keys: vec!["right".to_string()],
+                just_pressed_keys: vec![],
+                ..Default::default()
+            },
+            // Jump while moving
+            InputSnapshot {
+                held_keys: vec!["right".to_string(), "space".to_string()],
+                just_pressed_keys: vec!["space".to_string()],
+                ..Default::default()
+            },
+            InputSnapshot {
+                held_keys: vec!["right".to_string()],
+                just_pressed_keys: vec![],
+                ..Default::default()
+            },
+            InputSnapshot {
+                held_keys: vec!["right".to_string()],
+                just_pressed_keys: vec![],
+                ..Default::default()
+            },
+            // Stop moving
+            InputSnapshot {
+                held_keys: vec![],
+                just_pressed_keys: vec![],
+                ..Default::default()
+            },
+            InputSnapshot {
+                held_keys: vec![],
+                just_pressed_keys: vec![],
+                ..Default::default()
+            },
+            // Move left
+            InputSnapshot {
+                held_keys: vec!["left".to_string()],
+                just_pressed_keys: vec!["left".to_string()],
+                ..Default::default()
+            },
+            InputSnapshot {
+                held_keys: vec!["left".to_string()],
+                just_pressed_keys: vec![],
+                ..Default::default()
+            },
+        ];
+
+        let actor = make_actor();
+
+        // Run A
+        let bridge_a = LuaBridge::new(path.clone());
+        assert_eq!(bridge_a.status(), LuaStatus::Loaded);
+        let mut results_a = Vec::new();
+        for input in &input_sequence {
+            let intent = bridge_a.call_update(dt, input, &actor).unwrap();
+            results_a.push((intent.move_x, intent.jump_pressed));
+        }
+
+        // Run B (fresh bridge, same script, same inputs)
+        let bridge_b = LuaBridge::new(path.clone());
+        assert_eq!(bridge_b.status(), LuaStatus::Loaded);
+        let mut results_b = Vec::new();
+        for input in &input_sequence {
+            let intent = bridge_b.call_update(dt, input, &actor).unwrap();
+            results_b.push((intent.move_x, intent.jump_pressed));
+        }
+
+        // Verify identical outputs
+        assert_eq!(results_a.len(), results_b.len());
+        for (i, (a, b)) in results_a.iter().zip(results_b.iter()).enumerate() {
+            assert!(
+                (a.0 - b.0).abs() < f32::EPSILON && a.1 == b.1,
+                "Determinism failure at step {}: run_a=({}, {}), run_b=({}, {})",
+                i,
+                a.0,
+                a.1,
+                b.0,
+                b.1
+            );
+        }
+
+        // Also verify expected values for sanity
+        assert_eq!(results_a[0], (0.0, false), "step 0: idle");
+        assert_eq!(results_a[2], (1.0, false), "step 2: moving right");
+        assert_eq!(results_a[5], (1.0, true), "step 5: jump while moving right");
+        assert_eq!(results_a[8], (0.0, false), "step 8: stopped");
+        assert_eq!(results_a[10], (-1.0, false), "step 10: moving left");
+
+        let _ = std::fs::remove_file(&path);
+    }
+
+    #[test]
+    fn lua_play_animation_returns_intent() {
+        let path = temp_lua_path("play_anim");
+        write_temp_script(
+            &path,
+            r#"
+function on_update(dt)
+    engine.actor.set_intent(0.0, false)
+    engine.actor.play_animation("run")
+end
+"#,
+        );
+
+        let bridge = LuaBridge::new(path.clone());
+        assert_eq!(bridge.status(), LuaStatus::Loaded);
+
+        let intent = bridge
+            .call_update(1.0 / 60.0, &make_input(), &make_actor())
+            .expect("should return intent");
+        assert_eq!(intent.play_animation.as_deref(), Some("run"));
+        assert!(!intent.stop_animation);
+
+        let _ = std::fs::remove_file(&path);
+    }
+
+    #[test]
+    fn lua_stop_animation_returns_intent() {
+        let path = temp_lua_path("stop_anim");
+        write_temp_script(
+            &path,
+            r#"
+function on_update(dt)
+    engine.actor.set_intent(0.0, false)
+    engine.actor.stop_animation()
+end
+"#,
+        );
+
+        let bridge = LuaBridge::new(path.clone());
+        assert_eq!(bridge.status(), LuaStatus::Loaded);
+
+        let intent = bridge
+            .call_update(1.0 / 60.0, &make_input(), &make_actor())
+            .expect("should return intent");
+        assert!(intent.stop_animation);
+
+        let _ = std::fs::remove_file(&path);
+    }
+
+    #[test]
+    fn lua_reads_animation_state() {
+        let path = temp_lua_path("read_anim_state");
+        write_temp_script(
+            &path,
+            r#"
+function on_update(dt)
+    local anim = engine.actor.current_animation
+    local finished = engine.actor.animation_finished
+    -- Use animation state to drive movement
+    if anim == "idle" and not finished then
+        engine.actor.set_intent(0.0, false)
+    else
+        engine.actor.set_intent(1.0, false)
+    end
+end
+"#,
+        );
+
+        let bridge = LuaBridge::new(path.clone());
+        assert_eq!(bridge.status(), LuaStatus::Loaded);
+
+        // With "idle" animation, should get move_x = 0
+        let mut actor = make_actor();
+        actor.current_animation = Some("idle".to_string());
+        actor.animation_finished = false;
+        let intent = bridge
+            .call_update(1.0 / 60.0, &make_input(), &actor)
+            .expect("should return intent");
+        assert_eq!(intent.move_x, 0.0);
+
+        // With no animation, should get move_x = 1
+        let actor2 = make_actor(); // current_animation = None
+        let intent2 = bridge
+            .call_update(1.0 / 60.0, &make_input(), &actor2)
+            .expect("should return intent");
+        assert_eq!(intent2.move_x, 1.0);
+
+        let _ = std::fs::remove_file(&path);
+    }
+
+    #[test]
+    fn repl_runs_in_the_script_state_and_reports_inline() {
+        let path = temp_lua_path("repl");
+        write_temp_script(&path, "souls = 3\nfunction on_update(dt) end\n");
+
+        let bridge = LuaBridge::new(path.clone());
+        bridge.run_repl("souls * 2, engine.actor ~= nil");
+        bridge.run_repl("souls = souls + 1");
+        bridge.run_repl("souls");
+        bridge.run_repl("engine.rand()");
+        bridge.run_repl("nope(");
+        let console: Vec<(log::Level, String)> = bridge
+            .recent_console()
+            .into_iter()
+            .map(|record| (record.level, record.message))
+            .collect();
+        assert_eq!(
+            console[0],
+            (log::Level::Info, "> souls * 2, engine.actor ~= nil".into())
+        );
+        assert_eq!(console[1], (log::Level::Info, "6\ttrue".into()));
+        assert_eq!(console[2].1, "> souls = souls + 1");
+        assert_eq!(console[4], (log::Level::Info, "4".into()));
+        assert_eq!(console[6].0, log::Level::Error, "rand is step-only");
+        assert_eq!(console[8].0, log::Level::Error);
+        assert!(console[8].1.contains("repl:1"), "{}", console[8].1);
+        let _ = std::fs::remove_file(path);
+    }
+
+    #[test]
+    fn breakpoints_note_calls_with_caller_and_actor() {
+        let path = temp_lua_path("breakpoints");
+        write_temp_script(
+            &path,
+            r#"
+Player = {}
+function Player.on_land(speed)
+end
+function on_update(dt)
+    Player.on_land(3)
+end
+"#,
+        );
+
+        let mut bridge = LuaBridge::new(path.clone());
+        bridge.set_breakpoints(vec!["Player.on_land".into(), "on_update".into()]);
+        let mut actor = make_actor();
+        actor.x = 12.0;
+        actor.grounded = true;
+        bridge
+            .call_update(1.0 / 60.0, &make_input(), &actor)
+            .expect("should return intent");
+        let hits = bridge.take_breakpoint_hits();
+        let script = path.file_name().unwrap().to_string_lossy().into_owned();
+        assert_eq!(
+            hits,
+            [
+                BreakpointHit {
+                    function: "on_update".into(),
+                    script: script.clone(),
+                    line: Some(5),
+                    caller: None,
+                    actor: "x=12.0 y=0.0 vx=0.0 vy=0.0 grounded".into(),
+                },
+                BreakpointHit {
+                    function: "Player.on_land".into(),
+                    script: script.clone(),
+                    line: Some(3),
+                    caller: Some(format!("{script}:6")),
+                    actor: "x=12.0 y=0.0 vx=0.0 vy=0.0 grounded".into(),
+                },
+            ]
+        );
+        assert!(bridge.take_breakpoint_hits().is_empty());
+
+        bridge.set_breakpoints(Vec::new());
+        bridge
+            .call_update(1.0 / 60.0, &make_input(), &actor)
+            .expect("should return intent");
+        assert!(bridge.take_breakpoint_hits().is_empty());
+        let _ = std::fs::remove_file(path);
+    }
+
+    #[test]
+    fn print_and_engine_log_reach_the_console_with_their_line() {
+        let path = temp_lua_path("console");
+        write_temp_script(
+            &path,
+            r#"
+print("hello", 3, nil)
+function on_update(dt)
+    engine.log.warn("low souls:", 2)
+end
+"#,
+        );
+
+        let bridge = LuaBridge::new(path.clone());
+        bridge
+            .call_update(1.0 / 60.0, &make_input(), &make_actor())
+            .expect("should return intent");
+        let console = bridge.recent_console();
+        let script = path.file_name().unwrap().to_string_lossy().into_owned();
+        assert_eq!(
+            console,
+            [
+                ScriptLogRecord {
+                    level: log::Level::Info,
+                    script: script.clone(),
+                    line: Some(2),
+                    message: "hello\t3\tnil".to_string(),
+                },
+                ScriptLogRecord {
+                    level: log::Level::Warn,
+                    script,
+                    line: Some(4),
+                    message: "low souls:\t2".to_string(),
+                },
+            ]
+        );
+        let _ = std::fs::remove_file(path);
+    }
+}
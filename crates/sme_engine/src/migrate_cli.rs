@@ -0,0 +1,129 @@
+//! `sme_game --migrate-in-place <files...>`: rewrite content files to the
+//! current schema versions.
+//!
+//! Loaders already migrate old content in memory on every load; this command
+//! makes the upgrade permanent so the warning goes away. The content kind is
+//! detected from its identifying top-level key (`scene_id`, `atlas_id`, ...),
+//! then the matching migration chain from the loader module is applied.
+//! Files already at the current version are left untouched.
+
+use std::fs;
+use std::path::Path;
+
+use serde_json::Value;
+use sme_core::animation::ANIMATION_SCHEMA;
+use sme_core::migrate::SchemaMigrations;
+
+use crate::atlas::ATLAS_SCHEMA;
+use crate::collision::COLLISION_SCHEMA;
+use crate::scene::SCENE_SCHEMA;
+
+pub const MIGRATE_IN_PLACE_FLAG: &str = "--migrate-in-place";
+
+/// Migrate every file in `paths`. Stops at the first failure so a broken file
+/// is never half-written.
+pub fn migrate_files_in_place(paths: &[String]) -> Result<(), String> {
+    if paths.is_empty() {
+        return Err(format!(
+            "Usage: sme_game {MIGRATE_IN_PLACE_FLAG} <content.json> [more.json ...]"
+        ));
+    }
+    for path in paths {
+        match migrate_file_in_place(Path::new(path))? {
+            Some(from) => println!("Migrated {path} ({from} -> current)"),
+            None => println!("Up to date: {path}"),
+        }
+    }
+    Ok(())
+}
+
+/// Returns the version the file was migrated from, or `None` if it was current.
+fn migrate_file_in_place(path: &Path) -> Result<Option<String>, String> {
+    let raw =
+        fs::read_to_string(path).map_err(|e| format!("Failed to read {}: {e}", path.display()))?;
+    let mut value: Value = serde_json::from_str(&raw)
+        .map_err(|e| format!("Failed to parse {}: {e}", path.display()))?;
+    let schema = detect_schema(&value).ok_or_else(|| {
+        format!(
+            "Cannot tell what kind of content {} is (no scene_id/atlas_id/animation_id/collision_id)",
+            path.display()
+        )
+    })?;
+
+    let Some(from) = schema.migrate(&mut value)? else {
+        return Ok(None);
+    };
+    let mut json = serde_json::to_string_pretty(&value)
+        .map_err(|e| format!("Failed to serialize {}: {e}", path.display()))?;
+    json.push('\n');
+    fs::write(path, json).map_err(|e| format!("Failed to write {}: {e}", path.display()))?;
+    Ok(Some(from))
+}
+
+fn detect_schema(value: &Value) -> Option<&'static SchemaMigrations> {
+    let has = |key: &str| value.get(key).is_some();
+    if has("atlas_id") {
+        Some(&ATLAS_SCHEMA)
+    } else if has("animation_id") {
+        Some(&ANIMATION_SCHEMA)
+    } else if has("collision_id") {
+        Some(&COLLISION_SCHEMA)
+    } else if has("scene_id") || has("layers") || has("includes") {
+        // Include-only fragments may omit scene_id.
+        Some(&SCENE_SCHEMA)
+    } else {
+        None
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::time::{SystemTime, UNIX_EPOCH};
+
+    fn temp_file_path(name_hint: &str) -> std::path::PathBuf {
+        let nanos = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .expect("system time before unix epoch")
+            .as_nanos();
+        std::env::temp_dir().join(format!(
+            "sme_migrate_test_{}_{}_{}.json",
+            name_hint,
+            std::process::id(),
+            nanos
+        ))
+    }
+
+    #[test]
+    fn migrate_in_place_rewrites_old_scene_and_keeps_key_order() {
+        let path = temp_file_path("scene");
+        fs::write(
+            &path,
+            r#"{"version":"0.1","scene_id":"old","layers":[{"id":"l","parallax":1.0,"sprites":[]}]}"#,
+        )
+        .expect("write scene");
+
+        let from = migrate_file_in_place(&path).expect("migrate");
+        assert_eq!(from.as_deref(), Some("0.1"));
+
+        let rewritten = fs::read_to_string(&path).expect("read back");
+        let value: Value = serde_json::from_str(&rewritten).expect("valid json");
+        assert_eq!(value["version"], SCENE_SCHEMA.current);
+        let keys: Vec<&String> = value.as_object().expect("object").keys().collect();
+        assert_eq!(keys, ["version", "scene_id", "layers"]);
+
+        // Second run is a no-op.
+        assert_eq!(migrate_file_in_place(&path).expect("migrate again"), None);
+
+        let _ = fs::remove_file(path);
+    }
+
+    #[test]
+    fn migrate_in_place_rejects_unknown_content() {
+        let path = temp_file_path("unknown");
+        fs::write(&path, r#"{"version":"0.1","frames":[]}"#).expect("write");
+        let err = migrate_file_in_place(&path).expect_err("unknown kind");
+        assert!(err.contains("Cannot tell what kind of content"));
+        let _ = fs::remove_file(path);
+    }
+}
@@ -0,0 +1,155 @@
+//! CPU particles for purely visual effects.
+//!
+//! A `ParticleSystem` is a bounded list of points with a velocity, an age and
+//! a lifetime. It is advanced outside the fixed simulation step and never
+//! feeds back into gameplay, so particles are not part of snapshots, replays
+//! or the determinism audit. Ambient weather (`weather`) is built on it, as
+//! are the splashes bodies throw up when they cross a fluid surface.
+
+use std::f32::consts::PI;
+
+use glam::Vec2;
+
+/// Downward pull on splash droplets, in world units per second squared.
+pub const SPLASH_GRAVITY: f32 = 900.0;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Particle {
+    pub position: Vec2,
+    /// World units per second.
+    pub velocity: Vec2,
+    /// Seconds since spawn.
+    pub age: f32,
+    /// Seconds until the particle is removed; `f32::INFINITY` keeps it.
+    pub lifetime: f32,
+    /// Width and height of the drawn quad, in world units.
+    pub size: Vec2,
+    pub color: [f32; 4],
+    /// Per-particle random value in 0..1, for sway and flicker.
+    pub seed: f32,
+}
+
+#[derive(Debug, Clone)]
+pub struct ParticleSystem {
+    particles: Vec<Particle>,
+    capacity: usize,
+}
+
+impl ParticleSystem {
+    pub fn new(capacity: usize) -> Self {
+        Self {
+            particles: Vec::with_capacity(capacity),
+            capacity,
+        }
+    }
+
+    /// Add a particle; `false` (and nothing added) when full.
+    pub fn spawn(&mut self, particle: Particle) -> bool {
+        if self.particles.len() >= self.capacity {
+            return false;
+        }
+        self.particles.push(particle);
+        true
+    }
+
+    /// Move and age every particle, then drop the expired ones.
+    pub fn step(&mut self, dt: f32) {
+        for particle in &mut self.particles {
+            particle.position += particle.velocity * dt;
+            particle.age += dt;
+        }
+        self.particles.retain(|p| p.age < p.lifetime);
+    }
+
+    /// Keep the first `len` particles.
+    pub fn truncate(&mut self, len: usize) {
+        self.particles.truncate(len);
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = &Particle> {
+        self.particles.iter()
+    }
+
+    pub fn iter_mut(&mut self) -> impl Iterator<Item = &mut Particle> {
+        self.particles.iter_mut()
+    }
+
+    pub fn len(&self) -> usize {
+        self.particles.len()
+    }
+
+    pub fn capacity(&self) -> usize {
+        self.capacity
+    }
+}
+
+/// `count` droplets thrown up from `at` by a body crossing a fluid surface at
+/// `speed`, fanned evenly between up-left and up-right. The same inputs give
+/// the same droplets.
+pub fn splash(at: Vec2, count: u32, color: [f32; 4], speed: f32) -> impl Iterator<Item = Particle> {
+    let strength = (speed * 0.4).clamp(60.0, 240.0);
+    (0..count).map(move |i| {
+        let t = if count > 1 {
+            i as f32 / (count - 1) as f32
+        } else {
+            0.5
+        };
+        let seed = (i as f32 * 0.618_034).fract();
+        Particle {
+            position: at,
+            velocity: Vec2::from_angle((0.25 + 0.5 * t) * PI) * strength * (0.7 + 0.6 * seed),
+            age: 0.0,
+            lifetime: 0.4 + 0.3 * seed,
+            size: Vec2::splat(3.0),
+            color,
+            seed,
+        }
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn particle(lifetime: f32) -> Particle {
+        Particle {
+            position: Vec2::ZERO,
+            velocity: Vec2::new(10.0, -20.0),
+            age: 0.0,
+            lifetime,
+            size: Vec2::ONE,
+            color: [1.0; 4],
+            seed: 0.5,
+        }
+    }
+
+    #[test]
+    fn particles_move_expire_and_respect_capacity() {
+        let mut system = ParticleSystem::new(2);
+        assert!(system.spawn(particle(1.0)));
+        assert!(system.spawn(particle(f32::INFINITY)));
+        assert!(!system.spawn(particle(1.0)));
+
+        system.step(0.5);
+        assert_eq!(system.len(), 2);
+        assert_eq!(
+            system.iter().next().unwrap().position,
+            Vec2::new(5.0, -10.0)
+        );
+        system.step(0.5);
+        assert_eq!(system.len(), 1);
+        assert!(system.spawn(particle(1.0)));
+    }
+
+    #[test]
+    fn splashes_fan_upward() {
+        let droplets: Vec<_> = splash(Vec2::new(10.0, 5.0), 5, [1.0; 4], 400.0).collect();
+        assert_eq!(droplets.len(), 5);
+        assert!(droplets.iter().all(|p| p.velocity.y > 0.0));
+        assert!(droplets[0].velocity.x > 0.0 && droplets[4].velocity.x < 0.0);
+        assert_eq!(
+            droplets,
+            splash(Vec2::new(10.0, 5.0), 5, [1.0; 4], 400.0).collect::<Vec<_>>()
+        );
+    }
+}
@@ -0,0 +1,246 @@
+//! Project manifest (`sme.toml`): what a game loads at startup.
+//!
+//! ```toml
+//! scene = "assets/scenes/m4_scene.json"
+//! collision = "assets/collision/m3_collision.json"
+//! script = "assets/scripts/controller.lua"
+//! ui = "assets/ui/hud.json"
+//! controller = "assets/config/controller.json"
+//! language = "en"
+//! strings = ["assets/loc/en.json", "assets/loc/es.json"]
+//! dialogs = ["assets/dialog/courier_intro.json"]
+//! items = ["assets/items/grim_items.json"]
+//! tier = 2
+//!
+//! [window]
+//! title = "Saturday Morning Engine"
+//! width = 1280
+//! height = 720
+//!
+//! [budget]
+//! max_draw_calls = 60
+//! max_sprites = 4000
+//! max_texture_mb = 64.0
+//! max_frame_ms = 16.7
+//! ```
+//!
+//! Every key is optional; a missing key keeps the `EngineConfig` default.
+//! The `[budget]` limits are shown by the debug overlay (see
+//! `sme_devtools::budget`); a missing one is not checked.
+//! Content paths are asset paths, resolved against the asset root, which
+//! defaults to the manifest's directory (`asset_root` in the manifest moves
+//! it, relative to the manifest). One `sme_game` binary can so run several
+//! projects with `--project <dir>/sme.toml`.
+
+use std::path::{Path, PathBuf};
+
+use serde::Deserialize;
+use sme_core::tier::FidelityTier;
+
+use crate::EngineConfig;
+
+/// Manifest `sme_game` looks for at the asset root when no `--project` is
+/// given.
+pub const PROJECT_FILE: &str = "sme.toml";
+
+#[derive(Debug, Default, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct ProjectManifest {
+    asset_root: Option<PathBuf>,
+    scene: Option<PathBuf>,
+    collision: Option<PathBuf>,
+    script: Option<PathBuf>,
+    ui: Option<PathBuf>,
+    controller: Option<PathBuf>,
+    language: Option<String>,
+    strings: Option<Vec<PathBuf>>,
+    dialogs: Option<Vec<PathBuf>>,
+    items: Option<Vec<PathBuf>>,
+    /// `0` or `2`, as in `FidelityTier`.
+    #[serde(default, deserialize_with = "deserialize_tier")]
+    tier: Option<FidelityTier>,
+    #[serde(default)]
+    window: WindowManifest,
+    #[serde(default)]
+    budget: BudgetManifest,
+}
+
+#[derive(Debug, Default, Deserialize)]
+#[serde(deny_unknown_fields)]
+struct WindowManifest {
+    title: Option<String>,
+    width: Option<u32>,
+    height: Option<u32>,
+}
+
+#[derive(Debug, Default, Deserialize)]
+#[serde(deny_unknown_fields)]
+struct BudgetManifest {
+    max_draw_calls: Option<u32>,
+    max_sprites: Option<u32>,
+    max_texture_mb: Option<f32>,
+    max_frame_ms: Option<f32>,
+}
+
+pub fn load_manifest_from_path(path: &Path) -> Result<ProjectManifest, String> {
+    let raw = std::fs::read_to_string(path)
+        .map_err(|e| format!("Failed to read project manifest {}: {e}", path.display()))?;
+    parse_manifest(&raw)
+        .map_err(|e| format!("Failed to parse project manifest {}: {e}", path.display()))
+}
+
+fn parse_manifest(raw: &str) -> Result<ProjectManifest, String> {
+    let manifest: ProjectManifest = toml::from_str(raw).map_err(|e| e.to_string())?;
+    if manifest.window.width == Some(0) || manifest.window.height == Some(0) {
+        return Err("window width and height must be > 0".to_string());
+    }
+    let budget = &manifest.budget;
+    if [budget.max_texture_mb, budget.max_frame_ms]
+        .into_iter()
+        .flatten()
+        .any(|limit| limit.is_nan() || limit <= 0.0)
+    {
+        return Err("budget max_texture_mb and max_frame_ms must be > 0".to_string());
+    }
+    Ok(manifest)
+}
+
+pub(crate) fn tier_from_number(tier: u8) -> Result<FidelityTier, String> {
+    match tier {
+        0 => Ok(FidelityTier::Tier0),
+        2 => Ok(FidelityTier::Tier2),
+        _ => Err(format!("unknown tier {tier}; expected 0 or 2")),
+    }
+}
+
+fn deserialize_tier<'de, D>(deserializer: D) -> Result<Option<FidelityTier>, D::Error>
+where
+    D: serde::Deserializer<'de>,
+{
+    Option::<u8>::deserialize(deserializer)?
+        .map(tier_from_number)
+        .transpose()
+        .map_err(serde::de::Error::custom)
+}
+
+impl ProjectManifest {
+    /// Override `config` with every key the manifest sets. `dir` is the
+    /// manifest's directory, which becomes the asset root.
+    pub fn apply(self, dir: &Path, config: &mut EngineConfig) {
+        let root = match self.asset_root {
+            Some(root) => dir.join(root),
+            None => dir.to_path_buf(),
+        };
+        // A manifest in the working directory needs no root.
+        if !root.as_os_str().is_empty() {
+            config.asset_root = Some(root);
+        }
+        let window = self.window;
+        let fields = [
+            (self.scene, &mut config.scene),
+            (self.collision, &mut config.collision),
+            (self.script, &mut config.script),
+            (self.ui, &mut config.ui),
+            (self.controller, &mut config.controller),
+        ];
+        for (value, field) in fields {
+            if let Some(value) = value {
+                *field = value;
+            }
+        }
+        let lists = [
+            (self.strings, &mut config.strings),
+            (self.dialogs, &mut config.dialogs),
+            (self.items, &mut config.items),
+        ];
+        for (value, field) in lists {
+            if let Some(value) = value {
+                *field = value;
+            }
+        }
+        if let Some(language) = self.language {
+            config.language = language;
+        }
+        if let Some(tier) = self.tier {
+            config.tier = tier;
+        }
+        if let Some(title) = window.title {
+            config.window.title = title;
+        }
+        if let Some(width) = window.width {
+            config.window.width = width;
+        }
+        if let Some(height) = window.height {
+            config.window.height = height;
+        }
+        let budget = self.budget;
+        let limits = &mut config.budget;
+        limits.max_draw_calls = budget.max_draw_calls.or(limits.max_draw_calls);
+        limits.max_sprites = budget.max_sprites.or(limits.max_sprites);
+        limits.max_texture_mb = budget.max_texture_mb.or(limits.max_texture_mb);
+        limits.max_frame_ms = budget.max_frame_ms.or(limits.max_frame_ms);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn manifest_overrides_only_the_keys_it_sets() {
+        let manifest = parse_manifest(
+            r#"
+            scene = "assets/scenes/level_1.json"
+            strings = ["assets/loc/fr.json"]
+            tier = 2
+
+            [window]
+            title = "Grim Delivery"
+
+            [budget]
+            max_draw_calls = 40
+            max_frame_ms = 16.7
+            "#,
+        )
+        .expect("valid manifest");
+        let mut config = EngineConfig::default();
+        manifest.apply(Path::new("games/grim"), &mut config);
+
+        let defaults = EngineConfig::default();
+        assert_eq!(config.asset_root, Some(PathBuf::from("games/grim")));
+        assert_eq!(config.scene, PathBuf::from("assets/scenes/level_1.json"));
+        assert_eq!(config.collision, defaults.collision);
+        assert_eq!(config.strings, vec![PathBuf::from("assets/loc/fr.json")]);
+        assert_eq!(config.dialogs, defaults.dialogs);
+        assert_eq!(config.tier, FidelityTier::Tier2);
+        assert_eq!(config.window.title, "Grim Delivery");
+        assert_eq!(config.window.width, defaults.window.width);
+        assert_eq!(config.budget.max_draw_calls, Some(40));
+        assert_eq!(config.budget.max_frame_ms, Some(16.7));
+        assert_eq!(config.budget.max_sprites, None);
+    }
+
+    #[test]
+    fn manifest_root_is_relative_to_the_manifest() {
+        let mut config = EngineConfig::default();
+        parse_manifest("asset_root = \"content\"")
+            .expect("valid manifest")
+            .apply(Path::new("games/grim"), &mut config);
+        assert_eq!(config.asset_root, Some(PathBuf::from("games/grim/content")));
+
+        let mut config = EngineConfig::default();
+        ProjectManifest::default().apply(Path::new(""), &mut config);
+        assert_eq!(config.asset_root, None);
+    }
+
+    #[test]
+    fn invalid_manifests_are_rejected() {
+        assert!(parse_manifest("tier = 1")
+            .unwrap_err()
+            .contains("unknown tier 1"));
+        assert!(parse_manifest("scnee = \"a.json\"").is_err());
+        assert!(parse_manifest("[window]\nwidth = 0").is_err());
+        assert!(parse_manifest("[budget]\nmax_frame_ms = 0.0").is_err());
+        assert!(parse_manifest("[budget]\nmax_fps = 60").is_err());
+    }
+}
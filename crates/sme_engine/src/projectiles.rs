@@ -0,0 +1,379 @@
+//! Pooled projectiles: bullets, arrows and thrown things that are too many
+//! and too short-lived to be actors.
+//!
+//! A `ProjectilePool` owns a fixed number of slots, `MAX_PROJECTILES`, and
+//! reuses them through a free list, so firing never allocates a body, a sprite
+//! or an animation state. Each live projectile is a point with a velocity, a
+//! lifetime in simulated seconds, an atlas sprite and a `CollisionMask`. Every
+//! fixed step moves it along a straight segment; the segment is raycast
+//! against the collision grid (`CollisionMask::WORLD`) and tested against the
+//! player and spawned actors (`PLAYER` / `ACTORS`), widened by the
+//! projectile's radius. The nearest impact frees the slot and is reported as a
+//! `ProjectileHit`; projectiles that outlive `lifetime` are freed silently.
+//!
+//! Scripts fire with `engine.projectile.spawn` and read the previous step's
+//! impacts from `engine.projectile.hits`. Projectiles are drawn as one quad
+//! each in the sprite mesh, after the world layers.
+
+use std::sync::Arc;
+
+use glam::Vec2;
+
+use crate::collision::{Aabb, CollisionGrid};
+
+/// Pool size; spawning fails once every slot is live.
+pub const MAX_PROJECTILES: usize = 256;
+/// `engine.projectile.spawn` defaults: seconds alive and hit radius.
+pub const DEFAULT_PROJECTILE_LIFETIME: f32 = 2.0;
+pub const DEFAULT_PROJECTILE_RADIUS: f32 = 4.0;
+
+/// What a projectile stops on.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct CollisionMask(u8);
+
+impl CollisionMask {
+    pub const NONE: Self = Self(0);
+    pub const WORLD: Self = Self(1);
+    pub const PLAYER: Self = Self(1 << 1);
+    pub const ACTORS: Self = Self(1 << 2);
+
+    pub fn contains(self, other: Self) -> bool {
+        self.0 & other.0 == other.0 && other.0 != 0
+    }
+
+    /// Mask from names: `"world"`, `"player"`, `"actors"`.
+    pub fn from_names<'a>(names: impl IntoIterator<Item = &'a str>) -> Result<Self, String> {
+        names.into_iter().try_fold(Self::NONE, |mask, name| {
+            let bit = match name {
+                "world" => Self::WORLD,
+                "player" => Self::PLAYER,
+                "actors" => Self::ACTORS,
+                _ => {
+                    return Err(format!(
+                        "unknown collision mask '{name}' (expected world, player or actors)"
+                    ))
+                }
+            };
+            Ok(Self(mask.0 | bit.0))
+        })
+    }
+}
+
+impl std::ops::BitOr for CollisionMask {
+    type Output = Self;
+
+    fn bitor(self, other: Self) -> Self {
+        Self(self.0 | other.0)
+    }
+}
+
+/// Everything needed to fire one projectile.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ProjectileSpec {
+    pub position: Vec2,
+    pub velocity: Vec2,
+    /// Simulated seconds before the projectile expires.
+    pub lifetime: f32,
+    /// Atlas `sprite_id`; `None` draws a plain square.
+    pub sprite: Option<Arc<str>>,
+    /// Half the drawn size, and how far from its center it hits bodies.
+    pub radius: f32,
+    pub mask: CollisionMask,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Projectile {
+    pub id: u64,
+    pub position: Vec2,
+    pub velocity: Vec2,
+    pub age: f32,
+    pub lifetime: f32,
+    pub sprite: Option<Arc<str>>,
+    pub radius: f32,
+    pub mask: CollisionMask,
+}
+
+/// What a projectile struck.
+#[derive(Debug, Clone, PartialEq)]
+pub enum HitTarget {
+    /// A solid grid cell; `normal` is the face that was hit.
+    World { normal: Vec2 },
+    /// `"player"` or a spawned actor's id.
+    Actor(String),
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct ProjectileHit {
+    pub id: u64,
+    pub position: Vec2,
+    pub target: HitTarget,
+}
+
+/// A body projectiles can hit: id, box, and the mask bit it answers to.
+pub type ProjectileTarget<'a> = (&'a str, Aabb, CollisionMask);
+
+#[derive(Debug, Clone)]
+pub struct ProjectilePool {
+    slots: Vec<Option<Projectile>>,
+    /// Free slot indices; the last one is reused first.
+    free: Vec<usize>,
+    /// Projectiles fired so far; the next id.
+    fired: u64,
+}
+
+impl Default for ProjectilePool {
+    fn default() -> Self {
+        Self::with_capacity(MAX_PROJECTILES)
+    }
+}
+
+impl ProjectilePool {
+    pub fn with_capacity(capacity: usize) -> Self {
+        Self {
+            slots: vec![None; capacity],
+            free: (0..capacity).rev().collect(),
+            fired: 0,
+        }
+    }
+
+    /// Fire a projectile and return its id.
+    pub fn spawn(&mut self, spec: ProjectileSpec) -> Result<u64, String> {
+        if spec.lifetime.is_nan() || spec.lifetime <= 0.0 {
+            return Err(format!("lifetime {} must be > 0", spec.lifetime));
+        }
+        let slot = self
+            .free
+            .pop()
+            .ok_or_else(|| format!("all {} projectiles are in flight", self.slots.len()))?;
+        self.fired += 1;
+        self.slots[slot] = Some(Projectile {
+            id: self.fired,
+            position: spec.position,
+            velocity: spec.velocity,
+            age: 0.0,
+            lifetime: spec.lifetime,
+            sprite: spec.sprite,
+            radius: spec.radius.max(0.0),
+            mask: spec.mask,
+        });
+        Ok(self.fired)
+    }
+
+    /// Advance every projectile one fixed step and return this step's hits,
+    /// in slot order.
+    pub fn step(
+        &mut self,
+        dt: f32,
+        grid: &CollisionGrid,
+        targets: &[ProjectileTarget],
+    ) -> Vec<ProjectileHit> {
+        let mut hits = Vec::new();
+        for (index, slot) in self.slots.iter_mut().enumerate() {
+            let Some(projectile) = slot else {
+                continue;
+            };
+            projectile.age += dt;
+            if projectile.age >= projectile.lifetime {
+                *slot = None;
+                self.free.push(index);
+                continue;
+            }
+
+            let from = projectile.position;
+            let to = from + projectile.velocity * dt;
+            let mut nearest: Option<(f32, HitTarget)> = None;
+            if projectile.mask.contains(CollisionMask::WORLD) {
+                if let Some(hit) = grid.raycast(from.x, from.y, to.x, to.y) {
+                    let normal = Vec2::new(hit.normal.0, hit.normal.1);
+                    nearest = Some((hit.t, HitTarget::World { normal }));
+                }
+            }
+            for (id, aabb, kind) in targets {
+                if !projectile.mask.contains(*kind) {
+                    continue;
+                }
+                let widened = Aabb {
+                    half_w: aabb.half_w + projectile.radius,
+                    half_h: aabb.half_h + projectile.radius,
+                    ..*aabb
+                };
+                if let Some(t) = segment_enters_aabb(from, to, widened) {
+                    if nearest.as_ref().is_none_or(|(best, _)| t < *best) {
+                        nearest = Some((t, HitTarget::Actor(id.to_string())));
+                    }
+                }
+            }
+
+            match nearest {
+                Some((t, target)) => {
+                    hits.push(ProjectileHit {
+                        id: projectile.id,
+                        position: from.lerp(to, t),
+                        target,
+                    });
+                    *slot = None;
+                    self.free.push(index);
+                }
+                None => projectile.position = to,
+            }
+        }
+        hits
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = &Projectile> {
+        self.slots.iter().flatten()
+    }
+
+    pub fn live(&self) -> usize {
+        self.slots.len() - self.free.len()
+    }
+
+    pub fn capacity(&self) -> usize {
+        self.slots.len()
+    }
+
+    pub fn clear(&mut self) {
+        *self = Self::with_capacity(self.slots.len());
+    }
+}
+
+/// `t` in `0..=1` where the segment `from`..`to` first touches `aabb`
+/// (slab test); `0` if it starts inside.
+fn segment_enters_aabb(from: Vec2, to: Vec2, aabb: Aabb) -> Option<f32> {
+    let delta = to - from;
+    let center = Vec2::new(aabb.center_x, aabb.center_y);
+    let half = Vec2::new(aabb.half_w, aabb.half_h);
+    let (mut t_enter, mut t_exit) = (0.0f32, 1.0f32);
+    for axis in 0..2 {
+        let (start, d) = (from[axis], delta[axis]);
+        let (min, max) = (center[axis] - half[axis], center[axis] + half[axis]);
+        if d == 0.0 {
+            if start < min || start > max {
+                return None;
+            }
+            continue;
+        }
+        let (t0, t1) = ((min - start) / d, (max - start) / d);
+        t_enter = t_enter.max(t0.min(t1));
+        t_exit = t_exit.min(t0.max(t1));
+        if t_enter > t_exit {
+            return None;
+        }
+    }
+    Some(t_enter)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::collision::{CollisionFile, GridCell, GridOrigin};
+
+    fn wall_grid() -> CollisionGrid {
+        CollisionGrid::from_file(CollisionFile {
+            version: "0.1".to_string(),
+            collision_id: "wall".to_string(),
+            cell_size: 32,
+            origin: GridOrigin { x: 0, y: 0 },
+            width: 8,
+            height: 4,
+            solids: (0..4).map(|y| GridCell { x: 6, y }).collect(),
+            fluids: Vec::new(),
+            surfaces: Vec::new(),
+            time_volumes: Vec::new(),
+        })
+    }
+
+    fn bullet(mask: CollisionMask) -> ProjectileSpec {
+        ProjectileSpec {
+            position: Vec2::new(16.0, 48.0),
+            velocity: Vec2::new(600.0, 0.0),
+            lifetime: 2.0,
+            sprite: None,
+            radius: 2.0,
+            mask,
+        }
+    }
+
+    #[test]
+    fn mask_names_combine() {
+        let mask = CollisionMask::from_names(["world", "actors"]).expect("mask");
+        assert_eq!(mask, CollisionMask::WORLD | CollisionMask::ACTORS);
+        assert!(mask.contains(CollisionMask::ACTORS));
+        assert!(!mask.contains(CollisionMask::PLAYER));
+        assert!(!mask.contains(CollisionMask::NONE));
+        assert!(CollisionMask::from_names(["walls"]).is_err());
+    }
+
+    #[test]
+    fn fast_projectiles_hit_the_wall_without_tunneling() {
+        let grid = wall_grid();
+        let mut pool = ProjectilePool::with_capacity(4);
+        let id = pool.spawn(bullet(CollisionMask::WORLD)).expect("spawn");
+
+        // 10 units per step; the wall face is at x = 192.
+        let mut hits = Vec::new();
+        for _ in 0..30 {
+            hits.extend(pool.step(1.0 / 60.0, &grid, &[]));
+        }
+        assert_eq!(hits.len(), 1);
+        assert_eq!(hits[0].id, id);
+        assert_eq!(
+            hits[0].target,
+            HitTarget::World {
+                normal: Vec2::new(-1.0, 0.0)
+            }
+        );
+        assert!((hits[0].position.x - 192.0).abs() < 1e-3);
+        assert_eq!(pool.live(), 0);
+    }
+
+    #[test]
+    fn nearest_masked_target_wins_and_slots_are_reused() {
+        let grid = wall_grid();
+        let mut pool = ProjectilePool::with_capacity(2);
+        let goblin = Aabb {
+            center_x: 100.0,
+            center_y: 48.0,
+            half_w: 8.0,
+            half_h: 8.0,
+        };
+        let player = Aabb {
+            center_x: 60.0,
+            ..goblin
+        };
+        let targets = [
+            ("goblin#1", goblin, CollisionMask::ACTORS),
+            ("player", player, CollisionMask::PLAYER),
+        ];
+
+        pool.spawn(bullet(CollisionMask::WORLD | CollisionMask::ACTORS))
+            .expect("first");
+        pool.spawn(bullet(CollisionMask::NONE)).expect("second");
+        assert!(
+            pool.spawn(bullet(CollisionMask::NONE)).is_err(),
+            "pool full"
+        );
+
+        let mut hits = Vec::new();
+        for _ in 0..10 {
+            hits.extend(pool.step(1.0 / 60.0, &grid, &targets));
+        }
+        // The player is nearer but not in the mask; the unmasked bullet flies on.
+        assert_eq!(hits.len(), 1);
+        assert_eq!(hits[0].target, HitTarget::Actor("goblin#1".to_string()));
+        assert!((hits[0].position.x - 90.0).abs() < 1e-3);
+        assert_eq!(pool.live(), 1);
+
+        let third = pool
+            .spawn(bullet(CollisionMask::NONE))
+            .expect("slot reused");
+        assert_eq!(third, 3);
+        assert!(pool.spawn(bullet(CollisionMask::NONE)).is_err());
+
+        // Lifetimes (2 s) expire without a hit.
+        for _ in 0..126 {
+            assert!(pool.step(1.0 / 60.0, &grid, &targets).is_empty());
+        }
+        assert_eq!(pool.live(), 0);
+    }
+}
@@ -0,0 +1,397 @@
+//! Render thread: GPU submission off the simulation thread.
+//!
+//! Each frame the main thread simulates, rebuilds the mesh and runs the egui
+//! overlay, then packs what the GPU needs into a `RenderFrame` and hands it
+//! over through a triple buffer (`sme_core::triple_buffer`). The render
+//! thread uploads the mesh and uniforms, records the scene, grading and egui
+//! passes, and presents. Waiting on the surface or the driver now stalls
+//! only the render thread: a frame it could not keep up with is replaced by
+//! a newer one rather than holding up the next fixed steps.
+//!
+//! A `RenderFrame` holds plain values and GPU handles, never references into
+//! engine state: draws carry their resolved pipeline and texture bind group,
+//! and the mesh is shared by frames until the next rebuild. Once a frame is
+//! submitted the simulation is free to change anything. egui texture updates
+//! travel through a channel instead of the frame, so none is lost with a
+//! dropped frame: each is tagged with the frame it belongs to, and the render
+//! thread applies them in order up to the frame it draws, merging those of
+//! skipped frames and holding back any sent for frames after it.
+//!
+//! On wasm32, which has no threads, frames render inline on submit.
+
+use std::ops::Range;
+use std::sync::Arc;
+
+use sme_devtools::OverlayPainter;
+use sme_render::{
+    CameraUniform, GpuContext, GradePass, MaterialUniform, SharedSurface, SpritePipeline,
+    SpriteVertex,
+};
+
+/// The CPU-side sprite mesh of the last rebuild.
+pub struct FrameMesh {
+    pub vertices: Vec<SpriteVertex>,
+    pub indices: Vec<u32>,
+    /// GPU buffer sizes in elements. They grow (power-of-two) but never
+    /// shrink, so the main thread can report them without asking the GPU.
+    pub vertex_capacity: usize,
+    pub index_capacity: usize,
+}
+
+/// A batch of quads with one pipeline and one texture.
+pub struct FrameDraw {
+    pub pipeline: wgpu::RenderPipeline,
+    pub texture: wgpu::BindGroup,
+    pub indices: Range<u32>,
+}
+
+/// Everything one frame draws, as of the end of the frame's simulation.
+pub struct RenderFrame {
+    /// Surface size the frame was laid out for; it is skipped if the surface
+    /// has been resized since.
+    pub size: (u32, u32),
+    pub camera: CameraUniform,
+    pub ui_camera: CameraUniform,
+    pub material: MaterialUniform,
+    pub clear_color: wgpu::Color,
+    pub mesh: Arc<FrameMesh>,
+    pub world_draws: Vec<FrameDraw>,
+    /// Drawn after `world_draws`, under the screen-space camera.
+    pub screen_draws: Vec<FrameDraw>,
+    /// Tier 2 color grading: the scene goes to its texture first.
+    pub grade: Option<GradePass>,
+    pub egui_primitives: Vec<egui::ClippedPrimitive>,
+    pub pixels_per_point: f32,
+}
+
+pub struct RenderThread {
+    #[cfg(not(target_arch = "wasm32"))]
+    frames: Option<sme_core::triple_buffer::Writer<(u64, RenderFrame)>>,
+    #[cfg(not(target_arch = "wasm32"))]
+    textures: std::sync::mpsc::Sender<(u64, egui::TexturesDelta)>,
+    /// Number of the next frame submitted.
+    #[cfg(not(target_arch = "wasm32"))]
+    next_frame: u64,
+    #[cfg(not(target_arch = "wasm32"))]
+    handle: Option<std::thread::JoinHandle<()>>,
+    #[cfg(target_arch = "wasm32")]
+    renderer: FrameRenderer,
+}
+
+impl RenderThread {
+    #[cfg(not(target_arch = "wasm32"))]
+    pub fn new(gpu: &GpuContext, sprite_pipeline: &SpritePipeline) -> Self {
+        let mut renderer = FrameRenderer::new(gpu, sprite_pipeline);
+        let (frames, mut reader) = sme_core::triple_buffer::triple_buffer::<(u64, RenderFrame)>();
+        let (textures, texture_updates) = std::sync::mpsc::channel::<(u64, egui::TexturesDelta)>();
+        let handle = std::thread::Builder::new()
+            .name("sme-render".to_string())
+            .spawn(move || {
+                let mut pending = std::collections::VecDeque::new();
+                while let Some((number, frame)) = reader.wait() {
+                    // Updates are sent before their frame is published, so
+                    // every one up to `number` has arrived; later frames'
+                    // updates wait for theirs.
+                    pending.extend(texture_updates.try_iter());
+                    while let Some((_, delta)) = pending.pop_front_if(|(of, _)| *of <= number) {
+                        renderer.textures.append(delta);
+                    }
+                    renderer.render(&frame);
+                }
+            })
+            .expect("Failed to spawn render thread");
+        Self {
+            frames: Some(frames),
+            textures,
+            next_frame: 0,
+            handle: Some(handle),
+        }
+    }
+
+    #[cfg(target_arch = "wasm32")]
+    pub fn new(gpu: &GpuContext, sprite_pipeline: &SpritePipeline) -> Self {
+        Self {
+            renderer: FrameRenderer::new(gpu, sprite_pipeline),
+        }
+    }
+
+    /// Hand `frame` and this frame's egui texture updates to the renderer. A
+    /// panic on the render thread resumes here, on the main thread.
+    #[cfg(not(target_arch = "wasm32"))]
+    pub fn submit(&mut self, frame: RenderFrame, textures: egui::TexturesDelta) {
+        if let Some(handle) = self.handle.take_if(|handle| handle.is_finished()) {
+            if let Err(panic) = handle.join() {
+                std::panic::resume_unwind(panic);
+            }
+        }
+        let number = self.next_frame;
+        self.next_frame += 1;
+        // The receiver only goes away with the thread, handled above.
+        let _ = self.textures.send((number, textures));
+        if let Some(frames) = &mut self.frames {
+            frames.write((number, frame));
+        }
+    }
+
+    #[cfg(target_arch = "wasm32")]
+    pub fn submit(&mut self, frame: RenderFrame, textures: egui::TexturesDelta) {
+        self.renderer.textures.append(textures);
+        self.renderer.render(&frame);
+    }
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+impl Drop for RenderThread {
+    fn drop(&mut self) {
+        // Closing the buffer ends the thread after its current frame.
+        self.frames = None;
+        if let Some(handle) = self.handle.take() {
+            let _ = handle.join();
+        }
+    }
+}
+
+/// GPU state owned by the render thread.
+struct FrameRenderer {
+    device: wgpu::Device,
+    queue: wgpu::Queue,
+    surface: SharedSurface,
+    overlay: OverlayPainter,
+    /// egui texture updates not applied yet; kept across skipped frames.
+    textures: egui::TexturesDelta,
+    camera_buffer: wgpu::Buffer,
+    camera_bind_group: wgpu::BindGroup,
+    ui_camera_buffer: wgpu::Buffer,
+    ui_camera_bind_group: wgpu::BindGroup,
+    material_buffer: wgpu::Buffer,
+    material_bind_group: wgpu::BindGroup,
+    vertex_buffer: wgpu::Buffer,
+    index_buffer: wgpu::Buffer,
+    vertex_capacity: usize,
+    index_capacity: usize,
+    /// The mesh currently in `vertex_buffer` and `index_buffer`.
+    uploaded: Option<Arc<FrameMesh>>,
+}
+
+impl FrameRenderer {
+    fn new(gpu: &GpuContext, sprite_pipeline: &SpritePipeline) -> Self {
+        let device = &gpu.device;
+        let camera_buffer = create_uniform_buffer::<CameraUniform>(device, "Camera Uniform Buffer");
+        let ui_camera_buffer =
+            create_uniform_buffer::<CameraUniform>(device, "UI Camera Uniform Buffer");
+        let material_buffer =
+            create_uniform_buffer::<MaterialUniform>(device, "Material Uniform Buffer");
+        Self {
+            device: gpu.device.clone(),
+            queue: gpu.queue.clone(),
+            surface: gpu.shared_surface(),
+            overlay: OverlayPainter::new(device, gpu.surface_format),
+            textures: egui::TexturesDelta::default(),
+            camera_bind_group: sprite_pipeline.create_camera_bind_group(device, &camera_buffer),
+            camera_buffer,
+            ui_camera_bind_group: sprite_pipeline
+                .create_camera_bind_group(device, &ui_camera_buffer),
+            ui_camera_buffer,
+            material_bind_group: sprite_pipeline
+                .create_material_bind_group(device, &material_buffer),
+            material_buffer,
+            vertex_buffer: create_vertex_buffer(device, 1),
+            index_buffer: create_index_buffer(device, 1),
+            vertex_capacity: 1,
+            index_capacity: 1,
+            uploaded: None,
+        }
+    }
+
+    fn render(&mut self, frame: &RenderFrame) {
+        let surface = self.surface.clone();
+        let Some(target) = surface.acquire(&self.device, frame.size) else {
+            return;
+        };
+        self.upload(frame);
+
+        let mut encoder = self
+            .device
+            .create_command_encoder(&wgpu::CommandEncoderDescriptor {
+                label: Some("Render Encoder"),
+            });
+        self.draw_scene(&mut encoder, frame, &target.view);
+        if let Some(grade) = &frame.grade {
+            grade.draw(&mut encoder, &target.view);
+        }
+
+        let textures = std::mem::take(&mut self.textures);
+        let screen_descriptor = egui_wgpu::ScreenDescriptor {
+            size_in_pixels: [frame.size.0, frame.size.1],
+            pixels_per_point: frame.pixels_per_point,
+        };
+        self.overlay.upload(
+            &self.device,
+            &self.queue,
+            &mut encoder,
+            &frame.egui_primitives,
+            &textures,
+            &screen_descriptor,
+        );
+        {
+            let mut egui_pass = encoder
+                .begin_render_pass(&wgpu::RenderPassDescriptor {
+                    label: Some("egui Render Pass"),
+                    color_attachments: &[Some(wgpu::RenderPassColorAttachment {
+                        view: &target.view,
+                        resolve_target: None,
+                        ops: wgpu::Operations {
+                            load: wgpu::LoadOp::Load,
+                            store: wgpu::StoreOp::Store,
+                        },
+                    })],
+                    depth_stencil_attachment: None,
+                    ..Default::default()
+                })
+                .forget_lifetime();
+            self.overlay
+                .paint(&mut egui_pass, &frame.egui_primitives, &screen_descriptor);
+        }
+        self.overlay.cleanup(&textures);
+
+        self.queue.submit(std::iter::once(encoder.finish()));
+        target.present();
+    }
+
+    /// Write the frame's uniforms, and its mesh if it changed since the last
+    /// upload.
+    fn upload(&mut self, frame: &RenderFrame) {
+        self.queue.write_buffer(
+            &self.camera_buffer,
+            0,
+            bytemuck::cast_slice(&[frame.camera]),
+        );
+        self.queue.write_buffer(
+            &self.ui_camera_buffer,
+            0,
+            bytemuck::cast_slice(&[frame.ui_camera]),
+        );
+        self.queue.write_buffer(
+            &self.material_buffer,
+            0,
+            bytemuck::cast_slice(&[frame.material]),
+        );
+
+        let mesh = &frame.mesh;
+        if self
+            .uploaded
+            .as_ref()
+            .is_some_and(|uploaded| Arc::ptr_eq(uploaded, mesh))
+        {
+            return;
+        }
+        if mesh.vertex_capacity > self.vertex_capacity {
+            self.vertex_capacity = mesh.vertex_capacity;
+            self.vertex_buffer = create_vertex_buffer(&self.device, self.vertex_capacity);
+        }
+        if mesh.index_capacity > self.index_capacity {
+            self.index_capacity = mesh.index_capacity;
+            self.index_buffer = create_index_buffer(&self.device, self.index_capacity);
+        }
+        if !mesh.vertices.is_empty() {
+            self.queue
+                .write_buffer(&self.vertex_buffer, 0, bytemuck::cast_slice(&mesh.vertices));
+        }
+        if !mesh.indices.is_empty() {
+            self.queue
+                .write_buffer(&self.index_buffer, 0, bytemuck::cast_slice(&mesh.indices));
+        }
+        self.uploaded = Some(mesh.clone());
+    }
+
+    fn draw_scene(
+        &self,
+        encoder: &mut wgpu::CommandEncoder,
+        frame: &RenderFrame,
+        surface_view: &wgpu::TextureView,
+    ) {
+        let view = frame
+            .grade
+            .as_ref()
+            .map_or(surface_view, |grade| &grade.scene_view);
+        let mut render_pass = encoder.begin_render_pass(&wgpu::RenderPassDescriptor {
+            label: Some("Scene Render Pass"),
+            color_attachments: &[Some(wgpu::RenderPassColorAttachment {
+                view,
+                resolve_target: None,
+                ops: wgpu::Operations {
+                    load: wgpu::LoadOp::Clear(frame.clear_color),
+                    store: wgpu::StoreOp::Store,
+                },
+            })],
+            depth_stencil_attachment: None,
+            ..Default::default()
+        });
+
+        render_pass.set_bind_group(0, &self.camera_bind_group, &[]);
+        // Only material pipelines read group 2.
+        render_pass.set_bind_group(2, &self.material_bind_group, &[]);
+        render_pass.set_vertex_buffer(0, self.vertex_buffer.slice(..));
+        render_pass.set_index_buffer(self.index_buffer.slice(..), wgpu::IndexFormat::Uint32);
+
+        let mut bound = BoundState::default();
+        bound.draw(&mut render_pass, &frame.world_draws);
+        // In-game UI: same pipeline and buffers, screen-space camera.
+        if !frame.screen_draws.is_empty() {
+            render_pass.set_bind_group(0, &self.ui_camera_bind_group, &[]);
+            bound.draw(&mut render_pass, &frame.screen_draws);
+        }
+    }
+}
+
+/// Pipeline and texture last bound in a pass, to skip redundant rebinds.
+#[derive(Default)]
+struct BoundState<'a> {
+    pipeline: Option<&'a wgpu::RenderPipeline>,
+    texture: Option<&'a wgpu::BindGroup>,
+}
+
+impl<'a> BoundState<'a> {
+    fn draw(&mut self, render_pass: &mut wgpu::RenderPass<'_>, draws: &'a [FrameDraw]) {
+        for draw in draws {
+            if self.pipeline != Some(&draw.pipeline) {
+                render_pass.set_pipeline(&draw.pipeline);
+                self.pipeline = Some(&draw.pipeline);
+            }
+            if self.texture != Some(&draw.texture) {
+                render_pass.set_bind_group(1, &draw.texture, &[]);
+                self.texture = Some(&draw.texture);
+            }
+            render_pass.draw_indexed(draw.indices.clone(), 0, 0..1);
+        }
+    }
+}
+
+fn create_uniform_buffer<T>(device: &wgpu::Device, label: &str) -> wgpu::Buffer {
+    device.create_buffer(&wgpu::BufferDescriptor {
+        label: Some(label),
+        size: std::mem::size_of::<T>() as u64,
+        usage: wgpu::BufferUsages::UNIFORM | wgpu::BufferUsages::COPY_DST,
+        mapped_at_creation: false,
+    })
+}
+
+fn create_vertex_buffer(device: &wgpu::Device, vertex_capacity: usize) -> wgpu::Buffer {
+    let byte_len = (vertex_capacity * std::mem::size_of::<SpriteVertex>()).max(1) as u64;
+    device.create_buffer(&wgpu::BufferDescriptor {
+        label: Some("Scene Vertex Buffer"),
+        size: byte_len,
+        usage: wgpu::BufferUsages::VERTEX | wgpu::BufferUsages::COPY_DST,
+        mapped_at_creation: false,
+    })
+}
+
+fn create_index_buffer(device: &wgpu::Device, index_capacity: usize) -> wgpu::Buffer {
+    let byte_len = (index_capacity * std::mem::size_of::<u32>()).max(1) as u64;
+    device.create_buffer(&wgpu::BufferDescriptor {
+        label: Some("Scene Index Buffer"),
+        size: byte_len,
+        usage: wgpu::BufferUsages::INDEX | wgpu::BufferUsages::COPY_DST,
+        mapped_at_creation: false,
+    })
+}
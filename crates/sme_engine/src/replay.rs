@@ -0,0 +1,443 @@
+//! Input replays: the controller inputs of a run, one per fixed step.
+//!
+//! `--record-replay <path>` records live play from startup and writes the file
+//! on exit; `--play-replay <path>` feeds a recorded file back in place of live
+//! movement input. Inputs alone only reproduce a run in the same environment,
+//! so recordings start with a `ReplayHeader` describing it. Before playback the
+//! header is compared with the running engine: a different scene, collision
+//! file, player controller config or tuning file (`tuning_hash`), start AABB or step length
+//! refuses the replay, and a different engine version only warns. Older files
+//! without a header still play, unchecked.
+
+use crate::collision::Aabb;
+use crate::controller::{ControllerConfig, ControllerInput};
+use serde::{Deserialize, Serialize};
+use sme_core::rng::DEFAULT_RNG_SEED;
+use std::fs;
+use std::path::Path;
+
+/// Written into recordings; playback only warns when it differs.
+pub const ENGINE_VERSION: &str = env!("CARGO_PKG_VERSION");
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct ReplaySequence {
+    /// Environment at the first recorded step; absent in hand-written files.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub header: Option<ReplayHeader>,
+    #[serde(default = "default_dt")]
+    pub fixed_dt: f32,
+    /// Script RNG seed in effect when the replay was recorded.
+    #[serde(default = "default_seed")]
+    pub seed: u64,
+    pub frames: Vec<ReplayFrame>,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+pub struct ReplayHeader {
+    pub engine_version: String,
+    pub scene_path: String,
+    pub collision_path: String,
+    /// The player's config.
+    pub controller: ControllerConfig,
+    /// `ControllerTuning::tuning_hash()` of the whole tuning file, presets
+    /// included; absent in older files.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub controller_hash: Option<u64>,
+    /// Player AABB before the first step.
+    pub start: Aabb,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+pub struct ReplayFrame {
+    #[serde(default)]
+    pub move_x: f32,
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub jump_pressed: bool,
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub crouch: bool,
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub dash: bool,
+    #[serde(default = "default_repeat")]
+    pub repeat: u32,
+}
+
+impl ReplaySequence {
+    pub fn expanded_inputs(&self) -> Vec<ControllerInput> {
+        // Replay format supports simple run-length compression via `repeat`.
+        let mut out = Vec::new();
+        for frame in &self.frames {
+            for _ in 0..frame.repeat.max(1) {
+                out.push(ControllerInput {
+                    move_x: frame.move_x.clamp(-1.0, 1.0),
+                    jump_pressed: frame.jump_pressed,
+                    crouch: frame.crouch,
+                    dash: frame.dash,
+                });
+            }
+        }
+        out
+    }
+
+    /// Compare the recorded environment with `current`. Returns warnings to
+    /// log, or every mismatch that makes the replay meaningless.
+    pub fn check_environment(
+        &self,
+        current: &ReplayHeader,
+        fixed_dt: f32,
+    ) -> Result<Vec<String>, String> {
+        let Some(recorded) = &self.header else {
+            return Ok(vec![
+                "replay has no header; environment not checked".to_string()
+            ]);
+        };
+        let mut warnings = Vec::new();
+        if recorded.engine_version != current.engine_version {
+            warnings.push(format!(
+                "recorded with engine {}, running {}",
+                recorded.engine_version, current.engine_version
+            ));
+        }
+        let mut mismatches = Vec::new();
+        if recorded.scene_path != current.scene_path {
+            mismatches.push(format!(
+                "scene '{}' (running '{}')",
+                recorded.scene_path, current.scene_path
+            ));
+        }
+        if recorded.collision_path != current.collision_path {
+            mismatches.push(format!(
+                "collision '{}' (running '{}')",
+                recorded.collision_path, current.collision_path
+            ));
+        }
+        if recorded.controller != current.controller {
+            mismatches.push(format!(
+                "controller tuning {:?} (running {:?})",
+                recorded.controller, current.controller
+            ));
+        }
+        if let Some(recorded_hash) = recorded.controller_hash {
+            if current.controller_hash != Some(recorded_hash) {
+                mismatches.push(format!(
+                    "controller tuning file {recorded_hash:016x} (running {:016x})",
+                    current.controller_hash.unwrap_or_default()
+                ));
+            }
+        }
+        if recorded.start != current.start {
+            mismatches.push(format!(
+                "start AABB {:?} (running {:?})",
+                recorded.start, current.start
+            ));
+        }
+        if (self.fixed_dt - fixed_dt).abs() > f32::EPSILON {
+            mismatches.push(format!("fixed_dt {} (running {fixed_dt})", self.fixed_dt));
+        }
+        if mismatches.is_empty() {
+            Ok(warnings)
+        } else {
+            Err(format!(
+                "Replay environment mismatch: {}",
+                mismatches.join("; ")
+            ))
+        }
+    }
+}
+
+/// Collects the inputs of live play, merging identical consecutive steps.
+#[derive(Debug, Clone)]
+pub struct ReplayRecorder {
+    sequence: ReplaySequence,
+}
+
+impl ReplayRecorder {
+    pub fn new(header: ReplayHeader, fixed_dt: f32, seed: u64) -> Self {
+        Self {
+            sequence: ReplaySequence {
+                header: Some(header),
+                fixed_dt,
+                seed,
+                frames: Vec::new(),
+            },
+        }
+    }
+
+    /// Call once per fixed step with the input the controller consumed.
+    pub fn record(&mut self, input: ControllerInput) {
+        let frame = ReplayFrame {
+            move_x: input.move_x,
+            jump_pressed: input.jump_pressed,
+            crouch: input.crouch,
+            dash: input.dash,
+            repeat: 1,
+        };
+        match self.sequence.frames.last_mut() {
+            Some(last)
+                if last.move_x == frame.move_x
+                    && last.jump_pressed == frame.jump_pressed
+                    && last.crouch == frame.crouch
+                    && last.dash == frame.dash =>
+            {
+                last.repeat += 1;
+            }
+            _ => self.sequence.frames.push(frame),
+        }
+    }
+
+    pub fn sequence(&self) -> &ReplaySequence {
+        &self.sequence
+    }
+}
+
+pub fn load_replay_from_path(path: &Path) -> Result<ReplaySequence, String> {
+    let raw =
+        fs::read_to_string(path).map_err(|e| format!("Failed to read {}: {e}", path.display()))?;
+    let replay: ReplaySequence = serde_json::from_str(&raw)
+        .map_err(|e| format!("Failed to parse replay JSON {}: {e}", path.display()))?;
+    validate_replay(&replay)?;
+    Ok(replay)
+}
+
+pub fn save_replay_to_path(path: &Path, replay: &ReplaySequence) -> Result<(), String> {
+    validate_replay(replay)?;
+    let json = serde_json::to_string_pretty(replay)
+        .map_err(|e| format!("Failed to serialize replay: {e}"))?;
+    fs::write(path, json).map_err(|e| format!("Failed to write {}: {e}", path.display()))
+}
+
+fn validate_replay(replay: &ReplaySequence) -> Result<(), String> {
+    if replay.fixed_dt <= 0.0 {
+        return Err("Replay validation failed: fixed_dt must be > 0".to_string());
+    }
+    if replay.frames.is_empty() {
+        return Err("Replay validation failed: frames list is empty".to_string());
+    }
+    Ok(())
+}
+
+const fn default_dt() -> f32 {
+    1.0 / 60.0
+}
+
+const fn default_seed() -> u64 {
+    DEFAULT_RNG_SEED
+}
+
+const fn default_repeat() -> u32 {
+    1
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::collision::{Aabb, CollisionFile, CollisionGrid, GridCell, GridOrigin};
+    use crate::controller::{CharacterController, ControllerTuning};
+    use std::time::{SystemTime, UNIX_EPOCH};
+
+    fn temp_file_path(name_hint: &str) -> std::path::PathBuf {
+        let nanos = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .expect("system time before unix epoch")
+            .as_nanos();
+        std::env::temp_dir().join(format!(
+            "sme_replay_test_{}_{}_{}.json",
+            name_hint,
+            std::process::id(),
+            nanos
+        ))
+    }
+
+    fn sample_grid() -> CollisionGrid {
+        CollisionGrid::from_file(CollisionFile {
+            version: "0.1".to_string(),
+            collision_id: "test".to_string(),
+            cell_size: 32,
+            origin: GridOrigin { x: -320, y: -192 },
+            width: 20,
+            height: 12,
+            solids: (0..20).map(|x| GridCell { x, y: 0 }).collect(),
+            fluids: Vec::new(),
+            surfaces: Vec::new(),
+            time_volumes: Vec::new(),
+        })
+    }
+
+    #[test]
+    fn replay_file_parses_and_expands() {
+        let path = temp_file_path("parse");
+        fs::write(
+            &path,
+            r#"{
+              "fixed_dt": 0.016666667,
+              "frames": [
+                { "move_x": 1.0, "repeat": 3 },
+                { "jump_pressed": true, "repeat": 1 }
+              ]
+            }"#,
+        )
+        .expect("write replay file");
+
+        let replay = load_replay_from_path(&path).expect("replay should load");
+        assert_eq!(replay.seed, DEFAULT_RNG_SEED, "seed defaults when omitted");
+        let expanded = replay.expanded_inputs();
+        assert_eq!(expanded.len(), 4);
+        assert!(expanded[3].jump_pressed);
+
+        let _ = fs::remove_file(path);
+    }
+
+    #[test]
+    fn replay_run_is_deterministic() {
+        let path = temp_file_path("deterministic");
+        fs::write(
+            &path,
+            r#"{
+              "fixed_dt": 0.016666667,
+              "seed": 1234,
+              "frames": [
+                { "move_x": 1.0, "repeat": 60 },
+                { "move_x": 1.0, "jump_pressed": true, "repeat": 1 },
+                { "move_x": 1.0, "repeat": 120 },
+                { "move_x": -1.0, "repeat": 45 }
+              ]
+            }"#,
+        )
+        .expect("write replay file");
+
+        let replay = load_replay_from_path(&path).expect("replay should load");
+        assert_eq!(replay.seed, 1234);
+        let inputs = replay.expanded_inputs();
+        let grid = sample_grid();
+        let start = Aabb {
+            center_x: grid.origin.x as f32 + 64.0,
+            center_y: grid.origin.y as f32 + 96.0,
+            half_w: 10.0,
+            half_h: 14.0,
+        };
+
+        let mut run_a = CharacterController::new(start);
+        let mut run_b = CharacterController::new(start);
+        for input in &inputs {
+            run_a.step(*input, replay.fixed_dt, &grid);
+        }
+        for input in &inputs {
+            run_b.step(*input, replay.fixed_dt, &grid);
+        }
+
+        assert!((run_a.aabb.center_x - run_b.aabb.center_x).abs() < 0.0001);
+        assert!((run_a.aabb.center_y - run_b.aabb.center_y).abs() < 0.0001);
+        assert!((run_a.velocity_x - run_b.velocity_x).abs() < 0.0001);
+        assert!((run_a.velocity_y - run_b.velocity_y).abs() < 0.0001);
+        assert_eq!(run_a.grounded, run_b.grounded);
+
+        let _ = fs::remove_file(path);
+    }
+
+    fn sample_header() -> ReplayHeader {
+        ReplayHeader {
+            engine_version: ENGINE_VERSION.to_string(),
+            scene_path: "assets/scenes/m4_scene.json".to_string(),
+            collision_path: "assets/collision/m3_collision.json".to_string(),
+            controller: ControllerConfig::default(),
+            controller_hash: Some(ControllerTuning::default().tuning_hash()),
+            start: Aabb {
+                center_x: -256.0,
+                center_y: -128.0,
+                half_w: 11.2,
+                half_h: 14.4,
+            },
+        }
+    }
+
+    #[test]
+    fn recording_round_trips_with_header() {
+        let path = temp_file_path("record");
+        let mut recorder = ReplayRecorder::new(sample_header(), 1.0 / 60.0, 77);
+        let right = ControllerInput {
+            move_x: 1.0,
+            jump_pressed: false,
+            crouch: false,
+            dash: false,
+        };
+        for _ in 0..3 {
+            recorder.record(right);
+        }
+        recorder.record(ControllerInput {
+            move_x: 1.0,
+            jump_pressed: true,
+            crouch: false,
+            dash: false,
+        });
+        recorder.record(right);
+        assert_eq!(recorder.sequence().frames.len(), 3, "equal steps merge");
+
+        save_replay_to_path(&path, recorder.sequence()).expect("save replay");
+        let loaded = load_replay_from_path(&path).expect("load replay");
+        assert_eq!(loaded.header, Some(sample_header()));
+        assert_eq!(loaded.seed, 77);
+        assert_eq!(loaded.expanded_inputs().len(), 5);
+        assert_eq!(
+            loaded.check_environment(&sample_header(), 1.0 / 60.0),
+            Ok(Vec::new())
+        );
+
+        let _ = fs::remove_file(path);
+    }
+
+    #[test]
+    fn environment_mismatch_refuses_and_version_only_warns() {
+        let mut recorder = ReplayRecorder::new(sample_header(), 1.0 / 60.0, 1);
+        recorder.record(ControllerInput {
+            move_x: 0.0,
+            jump_pressed: false,
+            crouch: false,
+            dash: false,
+        });
+        let replay = recorder.sequence();
+
+        let newer = ReplayHeader {
+            engine_version: "9.9.9".to_string(),
+            ..sample_header()
+        };
+        let warnings = replay
+            .check_environment(&newer, 1.0 / 60.0)
+            .expect("version change only warns");
+        assert_eq!(warnings.len(), 1);
+
+        let mut moved = sample_header();
+        moved.scene_path = "assets/scenes/other.json".to_string();
+        moved.start.center_x += 32.0;
+        let err = replay
+            .check_environment(&moved, 1.0 / 60.0)
+            .expect_err("different environment");
+        assert!(err.contains("scene"), "{err}");
+        assert!(err.contains("start AABB"), "{err}");
+        assert!(replay
+            .check_environment(&sample_header(), 1.0 / 30.0)
+            .is_err());
+
+        let mut retuned = sample_header();
+        retuned.controller.gravity = -1200.0;
+        let err = replay
+            .check_environment(&retuned, 1.0 / 60.0)
+            .expect_err("different tuning");
+        assert!(err.contains("controller tuning"), "{err}");
+
+        // A preset changed, the player's own config did not.
+        let mut represet = sample_header();
+        represet.controller_hash = Some(0x5eed);
+        let err = replay
+            .check_environment(&represet, 1.0 / 60.0)
+            .expect_err("different tuning file");
+        assert!(err.contains("controller tuning file"), "{err}");
+
+        let mut legacy = replay.clone();
+        legacy.header = None;
+        assert_eq!(
+            legacy
+                .check_environment(&moved, 1.0 / 60.0)
+                .map(|w| w.len()),
+            Ok(1)
+        );
+    }
+}
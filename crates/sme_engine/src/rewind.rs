@@ -0,0 +1,181 @@
+//! Frame-by-frame rewind for debugging.
+//!
+//! Every live fixed step first pushes a `WorldSnapshot` of the state it starts
+//! from -- the world (player body and animations), spawned actors and their id counter, projectiles,
+//! inventories and collected pickups, queued events, camera,
+//! screen shake, sprite tweens and the script RNG -- into a
+//! `RewindBuffer` holding the last `REWIND_CAPACITY` steps. While paused, the
+//! overlay's Back and Forward buttons move through those snapshots one step at
+//! a time. Step or Resume from a rewound point
+//! drops the recorded future and simulates forward again from there, so a
+//! fix to a Lua script, the collision file or controller tuning can be tried
+//! on the exact frames that misbehaved.
+//!
+//! Lua variables are not captured: a script keeps whatever state it had when
+//! the game was paused. Rewind is unavailable while recording or playing a
+//! replay, whose inputs must line up with the steps that actually ran.
+
+use std::collections::{HashSet, VecDeque};
+
+use glam::Vec2;
+use sme_core::rng::DeterministicRng;
+use sme_render::CameraShake;
+
+use crate::actors::{ActorIds, SpawnedActors};
+use crate::events::EventBus;
+use crate::items::Inventories;
+use crate::projectiles::ProjectilePool;
+use crate::tween::Tweens;
+use crate::world::World;
+
+/// Ten seconds of fixed steps at 60 Hz.
+pub const REWIND_CAPACITY: usize = 600;
+
+/// Simulation state at the start of one fixed step.
+#[derive(Debug, Clone)]
+pub struct WorldSnapshot {
+    /// Player body and animations.
+    pub world: World,
+    pub spawned: SpawnedActors,
+    pub actor_ids: ActorIds,
+    pub projectiles: ProjectilePool,
+    pub inventories: Inventories,
+    pub collected: HashSet<String>,
+    pub events: EventBus,
+    pub camera_position: Vec2,
+    pub camera_zoom: f32,
+    pub shake: CameraShake,
+    pub tweens: Tweens,
+    pub rng: DeterministicRng,
+}
+
+/// Overlay request, applied at the next frame boundary.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum RewindStep {
+    Back,
+    Forward,
+}
+
+/// Ring buffer of the last `capacity` step snapshots with a scrub cursor.
+pub struct RewindBuffer<T> {
+    frames: VecDeque<T>,
+    capacity: usize,
+    /// Index into `frames` of the restored snapshot; `None` at the live head.
+    cursor: Option<usize>,
+    /// Live state saved by the first `step_back`, restored by stepping
+    /// forward past the newest snapshot.
+    head: Option<T>,
+}
+
+impl<T: Clone> RewindBuffer<T> {
+    pub fn new(capacity: usize) -> Self {
+        Self {
+            frames: VecDeque::with_capacity(capacity),
+            capacity: capacity.max(1),
+            cursor: None,
+            head: None,
+        }
+    }
+
+    /// Record the state a live step starts from. Called from a rewound
+    /// point, this discards every snapshot after it first.
+    pub fn record(&mut self, state: T) {
+        if let Some(cursor) = self.cursor.take() {
+            self.frames.truncate(cursor);
+            self.head = None;
+        }
+        if self.frames.len() == self.capacity {
+            self.frames.pop_front();
+        }
+        self.frames.push_back(state);
+    }
+
+    /// Move one step back and return the state to restore. `current` is
+    /// only called when leaving the live head.
+    pub fn step_back(&mut self, current: impl FnOnce() -> T) -> Option<T> {
+        let cursor = match self.cursor {
+            Some(0) => return None,
+            Some(cursor) => cursor - 1,
+            None if self.frames.is_empty() => return None,
+            None => {
+                self.head = Some(current());
+                self.frames.len() - 1
+            }
+        };
+        self.cursor = Some(cursor);
+        Some(self.frames[cursor].clone())
+    }
+
+    /// Move one step forward through recorded history and return the state
+    /// to restore; `None` at the live head.
+    pub fn step_forward(&mut self) -> Option<T> {
+        let next = self.cursor? + 1;
+        if next < self.frames.len() {
+            self.cursor = Some(next);
+            Some(self.frames[next].clone())
+        } else {
+            self.cursor = None;
+            self.head.take()
+        }
+    }
+
+    /// Steps that can be rewound from the live head.
+    pub fn available(&self) -> usize {
+        self.frames.len()
+    }
+
+    /// How many steps behind the live head the restored state is.
+    pub fn offset(&self) -> usize {
+        self.cursor.map_or(0, |cursor| self.frames.len() - cursor)
+    }
+
+    pub fn clear(&mut self) {
+        self.frames.clear();
+        self.cursor = None;
+        self.head = None;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn scrubs_back_and_forward_to_the_live_head() {
+        let mut buffer = RewindBuffer::new(3);
+        for step in 0..5 {
+            buffer.record(step);
+        }
+        // Only the newest three survive.
+        assert_eq!(buffer.available(), 3);
+        assert_eq!(buffer.step_back(|| 5), Some(4));
+        assert_eq!(buffer.step_back(|| unreachable!()), Some(3));
+        assert_eq!(buffer.step_back(|| unreachable!()), Some(2));
+        assert_eq!(buffer.step_back(|| unreachable!()), None);
+        assert_eq!(buffer.offset(), 3);
+
+        assert_eq!(buffer.step_forward(), Some(3));
+        assert_eq!(buffer.step_forward(), Some(4));
+        assert_eq!(buffer.step_forward(), Some(5));
+        assert_eq!(buffer.offset(), 0);
+        assert_eq!(buffer.step_forward(), None);
+    }
+
+    #[test]
+    fn recording_from_a_rewound_point_drops_the_future() {
+        let mut buffer = RewindBuffer::new(10);
+        for step in 0..4 {
+            buffer.record(step);
+        }
+        buffer.step_back(|| 4);
+        buffer.step_back(|| 4);
+        assert_eq!(buffer.offset(), 2);
+
+        // Re-simulating from state 2 records it again as the start of a step.
+        buffer.record(2);
+        assert_eq!(buffer.offset(), 0);
+        assert_eq!(buffer.available(), 3);
+        assert_eq!(buffer.step_back(|| 30), Some(2));
+        assert_eq!(buffer.step_forward(), Some(30));
+    }
+}
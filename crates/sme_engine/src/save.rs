@@ -0,0 +1,200 @@
+//! Save games: the progress a player keeps between sessions.
+//!
+//! Separate from rewind snapshots and replays, which capture the whole
+//! simulation for debugging. A save holds only what scripts declare as
+//! persistent (`engine.save.persist(name, default)`), the checkpoints they
+//! unlocked, and the total play time. `engine.save.write(slot)` writes it as
+//! versioned JSON to `<data dir>/saves/slot_<n>.json` (see
+//! `sme_platform::paths::data_dir`), and `engine.save.read(slot)` loads it
+//! back.
+//!
+//! `SaveState` lives on the Lua bridge, so a script reload keeps the values;
+//! declaring a variable again keeps its current value instead of the default.
+//! It is not part of rewind snapshots.
+
+use std::collections::{BTreeMap, BTreeSet};
+use std::path::{Path, PathBuf};
+
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use sme_core::migrate::SchemaMigrations;
+
+/// Save file schema versions and the upgrade path to the current one.
+pub const SAVE_SCHEMA: SchemaMigrations = SchemaMigrations {
+    kind: "Save",
+    current: "0.1",
+    steps: &[],
+};
+
+/// Subdirectory of the data directory that holds save slots.
+pub const SAVE_DIR: &str = "saves";
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct SaveFile {
+    pub version: String,
+    /// Simulated seconds played.
+    pub play_time: f64,
+    #[serde(default)]
+    pub checkpoints: BTreeSet<String>,
+    #[serde(default)]
+    pub vars: BTreeMap<String, Value>,
+}
+
+#[derive(Debug, Default)]
+pub struct SaveState {
+    /// `None` when the platform has no data directory; saving then fails.
+    dir: Option<PathBuf>,
+    vars: BTreeMap<String, Value>,
+    checkpoints: BTreeSet<String>,
+    play_time: f64,
+}
+
+impl SaveState {
+    pub fn set_dir(&mut self, dir: Option<PathBuf>) {
+        self.dir = dir;
+    }
+
+    /// Declare a persistent variable and return its value: `default` the
+    /// first time, the current value after that.
+    pub fn persist(&mut self, name: &str, default: Value) -> &Value {
+        self.vars.entry(name.to_string()).or_insert(default)
+    }
+
+    pub fn get(&self, name: &str) -> Option<&Value> {
+        self.vars.get(name)
+    }
+
+    /// Change a declared variable. Undeclared names are an error, so a typo
+    /// does not silently go unsaved.
+    pub fn set(&mut self, name: &str, value: Value) -> Result<(), String> {
+        let slot = self
+            .vars
+            .get_mut(name)
+            .ok_or_else(|| format!("'{name}' is not declared with engine.save.persist"))?;
+        *slot = value;
+        Ok(())
+    }
+
+    /// Returns true the first time `id` is unlocked.
+    pub fn unlock_checkpoint(&mut self, id: &str) -> bool {
+        self.checkpoints.insert(id.to_string())
+    }
+
+    pub fn checkpoints(&self) -> &BTreeSet<String> {
+        &self.checkpoints
+    }
+
+    /// Add one fixed step's simulated time.
+    pub fn advance(&mut self, dt: f64) {
+        self.play_time += dt;
+    }
+
+    pub fn play_time(&self) -> f64 {
+        self.play_time
+    }
+
+    fn slot_path(&self, slot: u32) -> Result<PathBuf, String> {
+        let dir = self
+            .dir
+            .as_ref()
+            .ok_or_else(|| "no data directory to save in on this platform".to_string())?;
+        Ok(dir.join(format!("slot_{slot}.json")))
+    }
+
+    /// Write the current state to `slot`, replacing what was there.
+    pub fn write(&self, slot: u32) -> Result<PathBuf, String> {
+        let path = self.slot_path(slot)?;
+        let file = SaveFile {
+            version: SAVE_SCHEMA.current.to_string(),
+            play_time: self.play_time,
+            checkpoints: self.checkpoints.clone(),
+            vars: self.vars.clone(),
+        };
+        write_save_to_path(&path, &file)?;
+        Ok(path)
+    }
+
+    /// Replace the current state with `slot`'s. Variables the file does not
+    /// have keep their current values.
+    pub fn read(&mut self, slot: u32) -> Result<(), String> {
+        let file = load_save_from_path(&self.slot_path(slot)?)?;
+        self.vars.extend(file.vars);
+        self.checkpoints = file.checkpoints;
+        self.play_time = file.play_time;
+        Ok(())
+    }
+}
+
+pub fn load_save_from_path(path: &Path) -> Result<SaveFile, String> {
+    let raw = std::fs::read_to_string(path)
+        .map_err(|e| format!("Failed to read save {}: {e}", path.display()))?;
+    let mut value: Value = serde_json::from_str(&raw)
+        .map_err(|e| format!("Failed to parse save {}: {e}", path.display()))?;
+    SAVE_SCHEMA.migrate_with_warning(&mut value, &path.display().to_string())?;
+    serde_json::from_value(value)
+        .map_err(|e| format!("Failed to parse save {}: {e}", path.display()))
+}
+
+/// Write through a temporary file, so a crash mid-write leaves the old save.
+fn write_save_to_path(path: &Path, file: &SaveFile) -> Result<(), String> {
+    if let Some(dir) = path.parent() {
+        std::fs::create_dir_all(dir)
+            .map_err(|e| format!("Failed to create save directory {}: {e}", dir.display()))?;
+    }
+    let json = serde_json::to_string_pretty(file).map_err(|e| e.to_string())?;
+    let temp = path.with_extension("json.tmp");
+    std::fs::write(&temp, json)
+        .map_err(|e| format!("Failed to write save {}: {e}", temp.display()))?;
+    std::fs::rename(&temp, path)
+        .map_err(|e| format!("Failed to write save {}: {e}", path.display()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn temp_save_dir(name: &str) -> PathBuf {
+        std::env::temp_dir().join(format!("sme_save_{name}_{}", std::process::id()))
+    }
+
+    #[test]
+    fn persist_keeps_the_current_value() {
+        let mut save = SaveState::default();
+        assert_eq!(save.persist("coins", json!(0)), &json!(0));
+        save.set("coins", json!(12)).expect("declared");
+        assert_eq!(save.persist("coins", json!(0)), &json!(12));
+        assert!(save.set("coisn", json!(1)).is_err());
+    }
+
+    #[test]
+    fn slots_round_trip_through_disk() {
+        let dir = temp_save_dir("round_trip");
+        let mut save = SaveState::default();
+        save.set_dir(Some(dir.clone()));
+        save.persist("coins", json!(0));
+        save.set("coins", json!(7)).expect("declared");
+        save.unlock_checkpoint("bridge");
+        save.advance(1.5);
+        let path = save.write(2).expect("save written");
+        assert_eq!(path, dir.join("slot_2.json"));
+
+        let mut loaded = SaveState::default();
+        loaded.set_dir(Some(dir.clone()));
+        loaded.persist("lives", json!(3));
+        loaded.read(2).expect("save read");
+        assert_eq!(loaded.get("coins"), Some(&json!(7)));
+        assert_eq!(loaded.get("lives"), Some(&json!(3)));
+        assert!(loaded.checkpoints().contains("bridge"));
+        assert_eq!(loaded.play_time(), 1.5);
+        assert!(loaded.read(3).is_err());
+
+        let _ = std::fs::remove_dir_all(dir);
+    }
+
+    #[test]
+    fn saving_without_a_data_dir_fails() {
+        let save = SaveState::default();
+        assert!(save.write(1).unwrap_err().contains("no data directory"));
+    }
+}
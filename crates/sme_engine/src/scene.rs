@@ -0,0 +1,1842 @@
+//! Scene definition and hot-reload watcher for the sprite-scene-first world model.
+//!
+//! Scenes are the engine's primary content unit: ordered layers of sprites with
+//! parallax, depth sorting, and foreground occlusion. This is NOT a tilemap --
+//! art is authored as layered illustrations positioned in world space.
+//!
+//! Each sprite references its texture via either a raw `asset` path (legacy) or
+//! a stable `sprite_id` resolved through the atlas registry (preferred).
+//!
+//! v0.3 scenes may list `includes`: other scene files whose layers, atlases, and
+//! animations are merged in at load time. Includes are applied in order, and the
+//! including file is applied last, so later content always wins:
+//!  - layers with a matching `id` are replaced in place, new layers are appended
+//!  - atlas/animation paths are unioned, keeping first-seen order
+//!  - `camera`, `background`, `color_lut` and `day_cycle` come from the last file
+//!    that declares one
+//!  - `ambient` layers replace an earlier layer of the same kind
+//!
+//! Include cycles are rejected at load time.
+//!
+//! A file's `units` block converts its own world-space content to pixels,
+//! y-up, as it is read (see `units`), so includes exported from different
+//! tools can each declare theirs. The merged scene keeps the last declared `units`,
+//! which the collision grid loaded with it uses.
+//!
+//! `SceneWatcher` implements hot reload via filesystem mtime polling. This is
+//! deliberately simple (no inotify/ReadDirectoryChanges) for cross-platform
+//! reliability. The watcher is checked once per frame at the top of the
+//! simulation loop, which is a safe reload boundary.
+
+use serde::{Deserialize, Deserializer};
+use sme_core::migrate::{additive_only, Migration, SchemaMigrations};
+use std::collections::{BTreeSet, HashSet};
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::time::SystemTime;
+
+use crate::environment::DayCycle;
+use crate::items::ScenePickup;
+use crate::trail::TrailConfig;
+use crate::tween::{TweenProperty, TweenSpec};
+use crate::units::SceneUnits;
+use crate::weather::AmbientConfig;
+use sme_render::SpriteEffect;
+
+/// Scene schema versions and the upgrade path to the current one.
+pub const SCENE_SCHEMA: SchemaMigrations = SchemaMigrations {
+    kind: "Scene",
+    current: "0.3",
+    steps: &[
+        // 0.2 added optional `atlases` / `animations` and per-sprite animation refs.
+        Migration {
+            from: "0.1",
+            to: "0.2",
+            apply: additive_only,
+        },
+        // 0.3 added optional `includes`.
+        Migration {
+            from: "0.2",
+            to: "0.3",
+            apply: additive_only,
+        },
+    ],
+};
+
+#[derive(Debug, Deserialize, Clone)]
+pub struct SceneFile {
+    pub version: String,
+    /// Required on the root scene; optional on include-only fragments.
+    #[serde(default)]
+    pub scene_id: String,
+    #[serde(default)]
+    pub includes: Vec<String>,
+    pub camera: Option<SceneCamera>,
+    /// Backdrop behind every layer; the tier's clear color when absent.
+    #[serde(default)]
+    pub background: Option<SceneBackground>,
+    #[serde(default)]
+    pub atlases: Vec<String>,
+    #[serde(default)]
+    pub animations: Vec<String>,
+    #[serde(default)]
+    pub layers: Vec<SceneLayer>,
+    /// Strip LUT PNG for Tier 2 color grading (see `sme_render::post_process`).
+    #[serde(default)]
+    pub color_lut: Option<String>,
+    /// Rain, snow and fog layers drawn over the scene (see `weather`).
+    #[serde(default)]
+    pub ambient: Vec<AmbientConfig>,
+    /// Time-of-day ambient tint cycle (see `environment`).
+    #[serde(default)]
+    pub day_cycle: Option<DayCycle>,
+    /// Collision grid loaded along with this scene by `engine.scene.load`
+    /// (see `transition`). The startup grid comes from the engine config.
+    #[serde(default)]
+    pub collision: Option<String>,
+    /// Units this file's world content is authored in; pixels when absent.
+    #[serde(default)]
+    pub units: Option<SceneUnits>,
+    /// Every file pulled in through `includes` (transitively), resolved at load.
+    /// Hot reload watches these alongside the scene file itself.
+    #[serde(skip)]
+    pub included_paths: Vec<PathBuf>,
+}
+
+#[derive(Debug, Deserialize, Clone)]
+pub struct SceneCamera {
+    #[serde(default)]
+    pub start_x: f32,
+    #[serde(default)]
+    pub start_y: f32,
+    #[serde(default = "default_zoom")]
+    pub zoom: f32,
+    #[serde(default)]
+    pub min_zoom: Option<f32>,
+    #[serde(default)]
+    pub max_zoom: Option<f32>,
+    /// World area the camera view may not leave.
+    #[serde(default)]
+    pub bounds: Option<SceneBounds>,
+}
+
+/// What the scene draws behind its layers. Colors are RGB in 0..=1.
+///
+/// `{ "kind": "color", "color": [r, g, b] }` clears to one color,
+/// `{ "kind": "gradient", "top": [r, g, b], "bottom": [r, g, b] }` blends
+/// down the screen, and `{ "kind": "image", "asset": "..." }` stretches a
+/// texture to cover the screen, keeping its aspect. All three stay fixed to
+/// the screen (parallax 0) and take the day/night tint like world sprites.
+///
+/// `{ "kind": "tiled", "tiles": "...json", "origin": [x, y] }` is the
+/// exception: a tileset too large for one texture (see `tiles`), placed in
+/// the world with its bottom-left corner at `origin`, one pixel per unit.
+/// Only the tiles around the view are kept loaded.
+#[derive(Debug, Deserialize, Clone, PartialEq)]
+#[serde(tag = "kind", rename_all = "lowercase")]
+pub enum SceneBackground {
+    Color { color: [f32; 3] },
+    Gradient { top: [f32; 3], bottom: [f32; 3] },
+    Image { asset: String },
+    Tiled { tiles: String, origin: [f32; 2] },
+}
+
+impl SceneBackground {
+    fn validate(&self) -> Result<(), String> {
+        let colors: &[[f32; 3]] = match self {
+            Self::Color { color } => &[*color],
+            Self::Gradient { top, bottom } => &[*top, *bottom],
+            Self::Image { asset } if asset.is_empty() => {
+                return Err("background image asset is empty".to_string())
+            }
+            Self::Image { .. } => &[],
+            Self::Tiled { tiles, .. } if tiles.is_empty() => {
+                return Err("background tileset path is empty".to_string())
+            }
+            Self::Tiled { origin, .. } if !origin.iter().all(|c| c.is_finite()) => {
+                return Err(format!("background origin {origin:?} must be finite"))
+            }
+            Self::Tiled { .. } => &[],
+        };
+        for color in colors {
+            if color.iter().any(|c| !(0.0..=1.0).contains(c)) {
+                return Err(format!(
+                    "background color {color:?} must be within 0.0..=1.0"
+                ));
+            }
+        }
+        Ok(())
+    }
+}
+
+/// World-space rect, y up.
+#[derive(Debug, Deserialize, Clone, Copy)]
+pub struct SceneBounds {
+    pub min_x: f32,
+    pub min_y: f32,
+    pub max_x: f32,
+    pub max_y: f32,
+}
+
+#[derive(Debug, Deserialize, Clone)]
+pub struct SceneLayer {
+    pub id: String,
+    pub parallax: f32,
+    #[serde(default)]
+    pub sort_mode: SortMode,
+    #[serde(default)]
+    pub occlusion: bool,
+    #[serde(default = "default_visible")]
+    pub visible: bool,
+    /// Multiplies the alpha of every sprite in the layer, 0.0..=1.0.
+    #[serde(default = "default_opacity")]
+    pub opacity: f32,
+    /// Coordinate space the layer's sprites are positioned in.
+    #[serde(default)]
+    pub space: LayerSpace,
+    /// Path to a WGSL snippet defining `fn material(in: MaterialInput)`,
+    /// used instead of the default sprite shading for this layer.
+    #[serde(default)]
+    pub material: Option<String>,
+    /// How the layer's textures are sampled when drawn scaled or rotated.
+    #[serde(default)]
+    pub filter: LayerFilter,
+    /// Name shared by layers that are shown and hidden together at runtime
+    /// (see `LayerGroups`), e.g. "interior" or "exterior".
+    #[serde(default)]
+    pub group: Option<String>,
+    pub sprites: Vec<SceneSprite>,
+}
+
+/// `world` layers move with the camera. `screen` layers are positioned in
+/// window pixels (origin bottom-left, y up) and ignore camera and parallax,
+/// for HUD decorations that live in the scene file.
+#[derive(Debug, Deserialize, Clone, Copy, Default, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum LayerSpace {
+    #[default]
+    World,
+    Screen,
+}
+
+/// `nearest` keeps pixel art crisp; `linear` smooths painted art that is
+/// drawn at a scale or rotation.
+#[derive(Debug, Deserialize, Clone, Copy, Default, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum LayerFilter {
+    #[default]
+    Nearest,
+    Linear,
+}
+
+/// Runtime visibility of layer groups. Every group starts shown; a layer is
+/// drawn (and its pickups collected) only while it is `visible` and its group
+/// is shown. Kept apart from `SceneLayer::visible` so group toggles driven by
+/// game state are never saved into the scene file.
+#[derive(Debug, Clone, Default)]
+pub struct LayerGroups {
+    hidden: BTreeSet<String>,
+}
+
+impl LayerGroups {
+    pub fn set_visible(&mut self, group: &str, visible: bool) {
+        if visible {
+            self.hidden.remove(group);
+        } else {
+            self.hidden.insert(group.to_string());
+        }
+    }
+
+    pub fn is_visible(&self, group: &str) -> bool {
+        !self.hidden.contains(group)
+    }
+
+    /// Whether `layer` is drawn: its own flag and its group's.
+    pub fn shows(&self, layer: &SceneLayer) -> bool {
+        layer.visible && layer.group.as_deref().is_none_or(|g| self.is_visible(g))
+    }
+}
+
+impl SceneFile {
+    /// Distinct layer group names, sorted.
+    pub fn layer_groups(&self) -> BTreeSet<&str> {
+        self.layers
+            .iter()
+            .filter_map(|layer| layer.group.as_deref())
+            .collect()
+    }
+}
+
+#[derive(Debug, Deserialize, Clone, Copy, Default)]
+#[serde(rename_all = "lowercase")]
+pub enum SortMode {
+    #[default]
+    None,
+    Y,
+}
+
+#[derive(Debug, Deserialize, Clone)]
+pub struct SceneSprite {
+    pub id: String,
+    #[serde(default)]
+    pub asset: Option<String>,
+    #[serde(default)]
+    pub sprite_id: Option<String>,
+    #[serde(default)]
+    pub animation: Option<String>,
+    #[serde(default)]
+    pub animation_source: Option<String>,
+    pub x: f32,
+    pub y: f32,
+    #[serde(default)]
+    pub z: f32,
+    #[serde(default)]
+    pub rotation_deg: f32,
+    #[serde(default = "default_scale")]
+    pub scale_x: f32,
+    #[serde(default = "default_scale")]
+    pub scale_y: f32,
+    /// Afterimage trail drawn behind the sprite while it moves.
+    #[serde(default)]
+    pub trail: Option<TrailConfig>,
+    /// Shader effect applied to the sprite (and its trail).
+    #[serde(default)]
+    pub effect: Option<SceneEffect>,
+    /// Makes the sprite an item pickup the player collects on touch.
+    #[serde(default)]
+    pub pickup: Option<ScenePickup>,
+    /// Property tweens started when the scene loads (see `tween`).
+    #[serde(default)]
+    pub tweens: Vec<TweenSpec>,
+    /// Controller preset for this sprite's body: the player's, or spawned
+    /// copies' (see `controller`).
+    #[serde(default)]
+    pub controller: Option<String>,
+    /// Overrides the atlas sprite's pivot for this instance: a preset name
+    /// such as `"bottom-center"` or `{ "x": 0.5, "y": 0.0 }`, as fractions of
+    /// the drawn size from its bottom-left corner.
+    #[serde(default, deserialize_with = "deserialize_pivot")]
+    pub pivot: Option<(f32, f32)>,
+}
+
+/// Named pivots, matching the atlas sidecar presets.
+const PIVOT_PRESETS: [(&str, (f32, f32)); 9] = [
+    ("top-left", (0.0, 1.0)),
+    ("top-center", (0.5, 1.0)),
+    ("top-right", (1.0, 1.0)),
+    ("center-left", (0.0, 0.5)),
+    ("center", (0.5, 0.5)),
+    ("center-right", (1.0, 0.5)),
+    ("bottom-left", (0.0, 0.0)),
+    ("bottom-center", (0.5, 0.0)),
+    ("bottom-right", (1.0, 0.0)),
+];
+
+#[derive(Deserialize)]
+#[serde(untagged)]
+enum PivotSpec {
+    Named(String),
+    Point { x: f32, y: f32 },
+}
+
+fn deserialize_pivot<'de, D>(deserializer: D) -> Result<Option<(f32, f32)>, D::Error>
+where
+    D: Deserializer<'de>,
+{
+    match Option::<PivotSpec>::deserialize(deserializer)? {
+        None => Ok(None),
+        Some(PivotSpec::Point { x, y }) => Ok(Some((x, y))),
+        Some(PivotSpec::Named(name)) => PIVOT_PRESETS
+            .iter()
+            .find(|(preset, _)| *preset == name)
+            .map(|&(_, pivot)| Some(pivot))
+            .ok_or_else(|| {
+                let names: Vec<&str> = PIVOT_PRESETS.iter().map(|(preset, _)| *preset).collect();
+                serde::de::Error::custom(format!(
+                    "unknown pivot preset '{name}', expected one of: {}",
+                    names.join(", ")
+                ))
+            }),
+    }
+}
+
+/// `{ "kind": "flash" | "dissolve" | "outline" | "none", "param": 1.0 }`.
+#[derive(Debug, Deserialize, Clone, PartialEq)]
+pub struct SceneEffect {
+    pub kind: String,
+    #[serde(default = "default_effect_param")]
+    pub param: f32,
+}
+
+impl SceneEffect {
+    pub fn to_effect(&self) -> Result<SpriteEffect, String> {
+        SpriteEffect::parse(&self.kind, self.param)
+    }
+}
+
+pub struct SceneWatcher {
+    scene_path: PathBuf,
+    last_seen_modified: Option<SystemTime>,
+}
+
+impl SceneWatcher {
+    pub fn new(scene_path: PathBuf) -> Self {
+        let last_seen_modified = modified_time(&scene_path);
+        Self {
+            scene_path,
+            last_seen_modified,
+        }
+    }
+
+    pub fn should_reload(&mut self) -> bool {
+        let current = modified_time(&self.scene_path);
+        match (self.last_seen_modified, current) {
+            (Some(old), Some(now)) if now > old => {
+                self.last_seen_modified = Some(now);
+                true
+            }
+            (None, Some(now)) => {
+                self.last_seen_modified = Some(now);
+                true
+            }
+            _ => false,
+        }
+    }
+}
+
+pub fn load_scene_from_path(scene_path: &Path) -> Result<SceneFile, String> {
+    let mut include_stack = Vec::new();
+    let scene = load_scene_with_includes(scene_path, &mut include_stack)?;
+    validate_scene(&scene)?;
+    Ok(scene)
+}
+
+fn read_scene_file(scene_path: &Path) -> Result<SceneFile, String> {
+    let raw = sme_core::assets::read_to_string(scene_path)
+        .map_err(|e| format!("Failed to read scene file {}: {e}", scene_path.display()))?;
+    let mut value: serde_json::Value = serde_json::from_str(&raw)
+        .map_err(|e| format!("Failed to parse scene JSON {}: {e}", scene_path.display()))?;
+
+    // `includes` is a 0.3 feature; checked before migration bumps the version.
+    let has_includes = value
+        .get("includes")
+        .and_then(|v| v.as_array())
+        .is_some_and(|includes| !includes.is_empty());
+    let version = value.get("version").and_then(|v| v.as_str()).unwrap_or("");
+    if has_includes && matches!(version, "0.1" | "0.2") {
+        return Err(format!(
+            "Scene validation failed: 'includes' requires version 0.3 (found '{}' in {})",
+            version,
+            scene_path.display()
+        ));
+    }
+
+    SCENE_SCHEMA.migrate_with_warning(&mut value, &scene_path.display().to_string())?;
+    let mut scene: SceneFile = serde_json::from_value(value)
+        .map_err(|e| format!("Failed to parse scene JSON {}: {e}", scene_path.display()))?;
+    if let Some(units) = scene.units {
+        units
+            .validate()
+            .map_err(|e| format!("Scene validation failed: {e} in {}", scene_path.display()))?;
+        convert_to_pixels(&mut scene, units);
+    }
+    Ok(scene)
+}
+
+/// Rewrite the world-space positions of one scene file from `units` to
+/// pixels. Screen layers are already in window pixels.
+fn convert_to_pixels(scene: &mut SceneFile, units: SceneUnits) {
+    if units.is_pixels() {
+        return;
+    }
+    if let Some(camera) = &mut scene.camera {
+        (camera.start_x, camera.start_y) = units.point(camera.start_x, camera.start_y);
+        if let Some(bounds) = &mut camera.bounds {
+            (bounds.min_x, bounds.min_y) = units.point(bounds.min_x, bounds.min_y);
+            (bounds.max_x, bounds.max_y) = units.point(bounds.max_x, bounds.max_y);
+            if units.y_down() {
+                std::mem::swap(&mut bounds.min_y, &mut bounds.max_y);
+            }
+        }
+    }
+    if let Some(SceneBackground::Tiled { origin, .. }) = &mut scene.background {
+        let (x, y) = units.point(origin[0], origin[1]);
+        *origin = [x, y];
+    }
+    let world_layers = scene
+        .layers
+        .iter_mut()
+        .filter(|layer| layer.space == LayerSpace::World);
+    for sprite in world_layers.flat_map(|layer| &mut layer.sprites) {
+        (sprite.x, sprite.y) = units.point(sprite.x, sprite.y);
+        sprite.rotation_deg = units.rotation(sprite.rotation_deg);
+        for tween in &mut sprite.tweens {
+            for (property, value) in &mut tween.to {
+                *value = match property {
+                    TweenProperty::X => units.length(*value),
+                    TweenProperty::Y => units.y(*value),
+                    TweenProperty::Rotation => units.rotation(*value),
+                    _ => *value,
+                };
+            }
+        }
+    }
+}
+
+/// Load a scene file and recursively merge its includes. `include_stack` holds
+/// the canonical paths currently being expanded, which is what detects cycles.
+fn load_scene_with_includes(
+    scene_path: &Path,
+    include_stack: &mut Vec<PathBuf>,
+) -> Result<SceneFile, String> {
+    let canonical = fs::canonicalize(sme_core::assets::resolve(scene_path))
+        .unwrap_or_else(|_| scene_path.to_path_buf());
+    if include_stack.contains(&canonical) {
+        let chain: Vec<String> = include_stack
+            .iter()
+            .chain(std::iter::once(&canonical))
+            .map(|p| p.display().to_string())
+            .collect();
+        return Err(format!(
+            "Scene validation failed: include cycle detected ({})",
+            chain.join(" -> ")
+        ));
+    }
+
+    let scene = read_scene_file(scene_path)?;
+    if scene.includes.is_empty() {
+        return Ok(scene);
+    }
+
+    include_stack.push(canonical);
+    let mut merged = SceneFile {
+        version: scene.version.clone(),
+        scene_id: String::new(),
+        includes: Vec::new(),
+        camera: None,
+        background: None,
+        atlases: Vec::new(),
+        animations: Vec::new(),
+        layers: Vec::new(),
+        color_lut: None,
+        ambient: Vec::new(),
+        day_cycle: None,
+        collision: None,
+        units: None,
+        included_paths: Vec::new(),
+    };
+    for include in &scene.includes {
+        let include_path = PathBuf::from(include);
+        let fragment = load_scene_with_includes(&include_path, include_stack)?;
+        merged.included_paths.push(include_path);
+        merge_scene_fragment(&mut merged, fragment);
+    }
+    include_stack.pop();
+
+    let includes = scene.includes.clone();
+    merge_scene_fragment(&mut merged, scene);
+    merged.includes = includes;
+    Ok(merged)
+}
+
+/// Apply `overlay` on top of `base` using the include override rules.
+fn merge_scene_fragment(base: &mut SceneFile, overlay: SceneFile) {
+    base.version = overlay.version;
+    if !overlay.scene_id.is_empty() {
+        base.scene_id = overlay.scene_id;
+    }
+    if overlay.camera.is_some() {
+        base.camera = overlay.camera;
+    }
+    if overlay.background.is_some() {
+        base.background = overlay.background;
+    }
+    if overlay.color_lut.is_some() {
+        base.color_lut = overlay.color_lut;
+    }
+    if overlay.day_cycle.is_some() {
+        base.day_cycle = overlay.day_cycle;
+    }
+    if overlay.collision.is_some() {
+        base.collision = overlay.collision;
+    }
+    if overlay.units.is_some() {
+        base.units = overlay.units;
+    }
+    for atlas in overlay.atlases {
+        if !base.atlases.contains(&atlas) {
+            base.atlases.push(atlas);
+        }
+    }
+    for animation in overlay.animations {
+        if !base.animations.contains(&animation) {
+            base.animations.push(animation);
+        }
+    }
+    for layer in overlay.layers {
+        match base.layers.iter_mut().find(|l| l.id == layer.id) {
+            Some(existing) => *existing = layer,
+            None => base.layers.push(layer),
+        }
+    }
+    for ambient in overlay.ambient {
+        match base.ambient.iter_mut().find(|a| a.kind == ambient.kind) {
+            Some(existing) => *existing = ambient,
+            None => base.ambient.push(ambient),
+        }
+    }
+    for path in overlay.included_paths {
+        if !base.included_paths.contains(&path) {
+            base.included_paths.push(path);
+        }
+    }
+}
+
+fn validate_scene(scene: &SceneFile) -> Result<(), String> {
+    // Validation is intentionally strict on identifiers so loader/runtime paths
+    // can assume uniqueness without extra defensive branching.
+    if scene.scene_id.is_empty() {
+        return Err("Scene validation failed: scene_id is empty".to_string());
+    }
+    if scene.layers.is_empty() {
+        return Err("Scene validation failed: layers array is empty".to_string());
+    }
+
+    if let Some(camera) = &scene.camera {
+        validate_camera(camera)?;
+    }
+    if let Some(background) = &scene.background {
+        background
+            .validate()
+            .map_err(|e| format!("Scene validation failed: {e}"))?;
+    }
+    for ambient in &scene.ambient {
+        ambient
+            .validate()
+            .map_err(|e| format!("Scene validation failed: {e}"))?;
+    }
+    if let Some(cycle) = &scene.day_cycle {
+        cycle
+            .validate()
+            .map_err(|e| format!("Scene validation failed: {e}"))?;
+    }
+
+    let mut layer_ids = HashSet::new();
+    let mut sprite_ids = HashSet::new();
+
+    for layer in &scene.layers {
+        if !layer_ids.insert(layer.id.clone()) {
+            return Err(format!(
+                "Scene validation failed: duplicate layer id '{}'",
+                layer.id
+            ));
+        }
+        if layer.group.as_deref() == Some("") {
+            return Err(format!(
+                "Scene validation failed: layer '{}' group is empty",
+                layer.id
+            ));
+        }
+        if !(0.0..=1.0).contains(&layer.opacity) {
+            return Err(format!(
+                "Scene validation failed: layer '{}' opacity {} must be within 0.0..=1.0",
+                layer.id, layer.opacity
+            ));
+        }
+        if layer.space == LayerSpace::Screen && layer.parallax != 1.0 {
+            log::warn!(
+                "Scene layer '{}' is screen-space; its parallax {} is ignored.",
+                layer.id,
+                layer.parallax
+            );
+        }
+        if layer.sprites.is_empty() {
+            log::warn!(
+                "Scene layer '{}' has no sprites. This is allowed but often accidental.",
+                layer.id
+            );
+        }
+        for sprite in &layer.sprites {
+            if !sprite_ids.insert(sprite.id.clone()) {
+                return Err(format!(
+                    "Scene validation failed: duplicate sprite id '{}'",
+                    sprite.id
+                ));
+            }
+            if sprite.asset.is_none() && sprite.sprite_id.is_none() {
+                return Err(format!(
+                    "Scene validation failed: sprite '{}' must provide either 'asset' or 'sprite_id'",
+                    sprite.id
+                ));
+            }
+            if let Some((x, y)) = sprite.pivot {
+                if !x.is_finite() || !y.is_finite() {
+                    return Err(format!(
+                        "Scene validation failed: sprite '{}' pivot must be finite",
+                        sprite.id
+                    ));
+                }
+            }
+            if let Some(effect) = &sprite.effect {
+                effect
+                    .to_effect()
+                    .map_err(|e| format!("Scene validation failed: sprite '{}': {e}", sprite.id))?;
+            }
+            if let Some(trail) = &sprite.trail {
+                trail
+                    .validate()
+                    .map_err(|e| format!("Scene validation failed: sprite '{}': {e}", sprite.id))?;
+            }
+            if let Some(pickup) = &sprite.pickup {
+                pickup
+                    .validate()
+                    .map_err(|e| format!("Scene validation failed: sprite '{}': {e}", sprite.id))?;
+            }
+            for tween in &sprite.tweens {
+                tween
+                    .validate()
+                    .map_err(|e| format!("Scene validation failed: sprite '{}': {e}", sprite.id))?;
+            }
+        }
+    }
+
+    Ok(())
+}
+
+/// Write `layer`'s parallax, visibility, and opacity back into the scene file
+/// that defines it: `scene_path` itself, or whichever include wins under the
+/// merge rules. Only those three fields are touched; `visible` and `opacity`
+/// are left out while they hold their defaults, unless the file already sets
+/// them. Returns the file that was written.
+pub fn save_layer_settings(scene_path: &Path, layer: &SceneLayer) -> Result<PathBuf, String> {
+    let target = find_layer_source(scene_path, &layer.id, &mut Vec::new())?.ok_or_else(|| {
+        format!(
+            "Layer '{}' is not defined in {} or its includes",
+            layer.id,
+            scene_path.display()
+        )
+    })?;
+
+    let disk_path = sme_core::assets::resolve(&target);
+    let raw = fs::read_to_string(&disk_path)
+        .map_err(|e| format!("Failed to read scene file {}: {e}", target.display()))?;
+    let mut value: serde_json::Value = serde_json::from_str(&raw)
+        .map_err(|e| format!("Failed to parse scene JSON {}: {e}", target.display()))?;
+    let entry = value
+        .get_mut("layers")
+        .and_then(|layers| layers.as_array_mut())
+        .and_then(|layers| {
+            layers
+                .iter_mut()
+                .find(|l| l.get("id").and_then(|id| id.as_str()) == Some(layer.id.as_str()))
+        })
+        .and_then(|entry| entry.as_object_mut())
+        .ok_or_else(|| format!("Layer '{}' vanished from {}", layer.id, target.display()))?;
+
+    entry.insert("parallax".to_string(), json_number(layer.parallax));
+    if !layer.visible || entry.contains_key("visible") {
+        entry.insert("visible".to_string(), serde_json::json!(layer.visible));
+    }
+    if layer.opacity != default_opacity() || entry.contains_key("opacity") {
+        entry.insert("opacity".to_string(), json_number(layer.opacity));
+    }
+
+    let mut json = serde_json::to_string_pretty(&value)
+        .map_err(|e| format!("Failed to serialize {}: {e}", target.display()))?;
+    json.push('\n');
+    fs::write(&disk_path, json)
+        .map_err(|e| format!("Failed to write {}: {e}", target.display()))?;
+    Ok(target)
+}
+
+/// `0.2f32` as `0.2`, not the `0.20000000298023224` a plain widening gives.
+fn json_number(value: f32) -> serde_json::Value {
+    value
+        .to_string()
+        .parse::<f64>()
+        .map_or(serde_json::Value::Null, |v| serde_json::json!(v))
+}
+
+/// The file whose definition of `layer_id` survives the include merge. A file
+/// is applied after its includes, and later includes after earlier ones, so
+/// search the file first, then its includes from last to first.
+fn find_layer_source(
+    scene_path: &Path,
+    layer_id: &str,
+    include_stack: &mut Vec<PathBuf>,
+) -> Result<Option<PathBuf>, String> {
+    let canonical = fs::canonicalize(sme_core::assets::resolve(scene_path))
+        .unwrap_or_else(|_| scene_path.to_path_buf());
+    if include_stack.contains(&canonical) {
+        return Ok(None);
+    }
+    let raw = fs::read_to_string(sme_core::assets::resolve(scene_path))
+        .map_err(|e| format!("Failed to read scene file {}: {e}", scene_path.display()))?;
+    let value: serde_json::Value = serde_json::from_str(&raw)
+        .map_err(|e| format!("Failed to parse scene JSON {}: {e}", scene_path.display()))?;
+
+    let defines_layer = value
+        .get("layers")
+        .and_then(|layers| layers.as_array())
+        .is_some_and(|layers| {
+            layers
+                .iter()
+                .any(|l| l.get("id").and_then(|id| id.as_str()) == Some(layer_id))
+        });
+    if defines_layer {
+        return Ok(Some(scene_path.to_path_buf()));
+    }
+
+    let includes: Vec<&str> = value
+        .get("includes")
+        .and_then(|includes| includes.as_array())
+        .map(|includes| includes.iter().filter_map(|i| i.as_str()).collect())
+        .unwrap_or_default();
+    include_stack.push(canonical);
+    for include in includes.into_iter().rev() {
+        if let Some(found) = find_layer_source(Path::new(include), layer_id, include_stack)? {
+            return Ok(Some(found));
+        }
+    }
+    include_stack.pop();
+    Ok(None)
+}
+
+fn validate_camera(camera: &SceneCamera) -> Result<(), String> {
+    for (name, zoom) in [("min_zoom", camera.min_zoom), ("max_zoom", camera.max_zoom)] {
+        if zoom.is_some_and(|z| z.is_nan() || z <= 0.0) {
+            return Err(format!(
+                "Scene validation failed: camera {name} must be > 0"
+            ));
+        }
+    }
+    if let (Some(min_zoom), Some(max_zoom)) = (camera.min_zoom, camera.max_zoom) {
+        if min_zoom > max_zoom {
+            return Err(format!(
+                "Scene validation failed: camera min_zoom {min_zoom} exceeds max_zoom {max_zoom}"
+            ));
+        }
+    }
+    if let Some(bounds) = camera.bounds {
+        if !(bounds.min_x < bounds.max_x && bounds.min_y < bounds.max_y) {
+            return Err(
+                "Scene validation failed: camera bounds need min_x < max_x and min_y < max_y"
+                    .to_string(),
+            );
+        }
+    }
+    Ok(())
+}
+
+fn modified_time(path: &Path) -> Option<SystemTime> {
+    sme_core::assets::modified(path)
+}
+
+const fn default_zoom() -> f32 {
+    1.0
+}
+
+const fn default_visible() -> bool {
+    true
+}
+
+const fn default_opacity() -> f32 {
+    1.0
+}
+
+const fn default_scale() -> f32 {
+    1.0
+}
+
+const fn default_effect_param() -> f32 {
+    1.0
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::tween::{Easing, Repeat, TweenProperty};
+    use crate::weather::AmbientKind;
+    use std::time::{SystemTime, UNIX_EPOCH};
+
+    fn temp_file_path(name_hint: &str) -> PathBuf {
+        let nanos = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .expect("system time before unix epoch")
+            .as_nanos();
+        std::env::temp_dir().join(format!(
+            "sme_scene_test_{}_{}_{}.json",
+            name_hint,
+            std::process::id(),
+            nanos
+        ))
+    }
+
+    fn write_scene_file(path: &Path, body: &str) {
+        fs::write(path, body).expect("failed to write temp scene file");
+    }
+
+    #[test]
+    fn load_scene_from_path_parses_valid_scene() {
+        let path = temp_file_path("valid");
+        let json = r#"
+        {
+          "version": "0.1",
+          "scene_id": "test_scene",
+          "layers": [
+            {
+              "id": "background",
+              "parallax": 0.5,
+              "sprites": [
+                { "id": "s1", "asset": "assets/textures/test_sprite.png", "x": 0.0, "y": 0.0 }
+              ]
+            }
+          ]
+        }
+        "#;
+
+        write_scene_file(&path, json);
+        let scene = load_scene_from_path(&path).expect("valid scene should load");
+        assert_eq!(scene.version, SCENE_SCHEMA.current);
+        assert_eq!(scene.scene_id, "test_scene");
+        assert_eq!(scene.layers.len(), 1);
+        assert!(matches!(scene.layers[0].sort_mode, SortMode::None));
+        assert!(scene.layers[0].visible);
+        assert_eq!(scene.layers[0].space, LayerSpace::World);
+        assert_eq!(scene.layers[0].filter, LayerFilter::Nearest);
+        assert_eq!(scene.layers[0].sprites[0].scale_x, 1.0);
+        assert_eq!(scene.layers[0].sprites[0].scale_y, 1.0);
+
+        let _ = fs::remove_file(path);
+    }
+
+    #[test]
+    fn load_scene_from_path_parses_screen_space_layer() {
+        let path = temp_file_path("screen_space");
+        let json = r#"
+        {
+          "version": "0.3",
+          "scene_id": "test_scene",
+          "layers": [
+            {
+              "id": "hud",
+              "parallax": 1.0,
+              "space": "screen",
+              "filter": "linear",
+              "sprites": [
+                { "id": "frame", "asset": "assets/textures/test_sprite.png", "x": 32.0, "y": 32.0 }
+              ]
+            }
+          ]
+        }
+        "#;
+
+        write_scene_file(&path, json);
+        let scene = load_scene_from_path(&path).expect("screen layer should load");
+        assert_eq!(scene.layers[0].space, LayerSpace::Screen);
+        assert_eq!(scene.layers[0].filter, LayerFilter::Linear);
+
+        let _ = fs::remove_file(path);
+    }
+
+    #[test]
+    fn load_scene_from_path_parses_and_validates_sprite_trail() {
+        let path = temp_file_path("trail");
+        let scene_json = |trail: &str| {
+            format!(
+                r#"
+        {{
+          "version": "0.3",
+          "scene_id": "test_scene",
+          "layers": [
+            {{
+              "id": "mid",
+              "parallax": 1.0,
+              "sprites": [
+                {{ "id": "dasher", "asset": "assets/textures/test_sprite.png", "x": 0.0, "y": 0.0, "trail": {trail} }}
+              ]
+            }}
+          ]
+        }}
+        "#
+            )
+        };
+
+        write_scene_file(&path, &scene_json(r#"{ "length": 6 }"#));
+        let scene = load_scene_from_path(&path).expect("trail should load");
+        assert_eq!(scene.layers[0].sprites[0].trail, Some(TrailConfig::new(6)));
+
+        write_scene_file(&path, &scene_json(r#"{ "length": 6, "interval": 0 }"#));
+        let err = load_scene_from_path(&path).expect_err("zero interval should fail");
+        assert!(err.contains("sprite 'dasher'"), "{err}");
+
+        let _ = fs::remove_file(path);
+    }
+
+    #[test]
+    fn layer_groups_hide_whole_groups() {
+        let path = temp_file_path("groups");
+        write_scene_file(
+            &path,
+            r#"
+        {
+          "version": "0.3",
+          "scene_id": "test_scene",
+          "layers": [
+            { "id": "walls", "parallax": 1.0, "group": "interior", "sprites": [] },
+            { "id": "furniture", "parallax": 1.0, "group": "interior", "visible": false, "sprites": [] },
+            { "id": "street", "parallax": 1.0, "group": "exterior", "sprites": [] },
+            { "id": "player", "parallax": 1.0, "sprites": [] }
+          ]
+        }
+        "#,
+        );
+        let scene = load_scene_from_path(&path).expect("grouped scene should load");
+        assert_eq!(
+            scene.layer_groups().into_iter().collect::<Vec<_>>(),
+            ["exterior", "interior"]
+        );
+
+        let mut groups = LayerGroups::default();
+        let shown = |groups: &LayerGroups| -> Vec<&str> {
+            scene
+                .layers
+                .iter()
+                .filter(|layer| groups.shows(layer))
+                .map(|layer| layer.id.as_str())
+                .collect()
+        };
+        assert_eq!(shown(&groups), ["walls", "street", "player"]);
+        groups.set_visible("interior", false);
+        assert!(!groups.is_visible("interior"));
+        assert_eq!(shown(&groups), ["street", "player"]);
+        // Showing the group again keeps the layer's own flag.
+        groups.set_visible("interior", true);
+        assert_eq!(shown(&groups), ["walls", "street", "player"]);
+
+        let _ = fs::remove_file(path);
+    }
+
+    #[test]
+    fn load_scene_from_path_parses_and_validates_background() {
+        let path = temp_file_path("background");
+        let scene_json = |background: &str| {
+            format!(
+                r#"
+        {{
+          "version": "0.3",
+          "scene_id": "test_scene",
+          "background": {background},
+          "layers": [
+            {{
+              "id": "mid",
+              "parallax": 1.0,
+              "sprites": [
+                {{ "id": "hero", "asset": "assets/textures/test_sprite.png", "x": 0.0, "y": 0.0 }}
+              ]
+            }}
+          ]
+        }}
+        "#
+            )
+        };
+
+        write_scene_file(
+            &path,
+            &scene_json(
+                r#"{ "kind": "gradient", "top": [0.1, 0.2, 0.5], "bottom": [0.9, 0.6, 0.4] }"#,
+            ),
+        );
+        let scene = load_scene_from_path(&path).expect("background should load");
+        assert_eq!(
+            scene.background,
+            Some(SceneBackground::Gradient {
+                top: [0.1, 0.2, 0.5],
+                bottom: [0.9, 0.6, 0.4],
+            })
+        );
+
+        write_scene_file(
+            &path,
+            &scene_json(r#"{ "kind": "color", "color": [1.5, 0.0, 0.0] }"#),
+        );
+        let err = load_scene_from_path(&path).expect_err("out-of-range color should fail");
+        assert!(err.contains("background color"), "{err}");
+
+        write_scene_file(&path, &scene_json(r#"{ "kind": "image", "asset": "" }"#));
+        let err = load_scene_from_path(&path).expect_err("empty image asset should fail");
+        assert!(err.contains("background image"), "{err}");
+
+        write_scene_file(
+            &path,
+            &scene_json(
+                r#"{ "kind": "tiled", "tiles": "assets/generated/cliff.json", "origin": [0, -64] }"#,
+            ),
+        );
+        let scene = load_scene_from_path(&path).expect("tiled background should load");
+        assert_eq!(
+            scene.background,
+            Some(SceneBackground::Tiled {
+                tiles: "assets/generated/cliff.json".to_string(),
+                origin: [0.0, -64.0],
+            })
+        );
+
+        let _ = fs::remove_file(path);
+    }
+
+    #[test]
+    fn load_scene_from_path_parses_and_validates_ambient() {
+        let path = temp_file_path("ambient");
+        let scene_json = |ambient: &str| {
+            format!(
+                r#"
+        {{
+          "version": "0.3",
+          "scene_id": "test_scene",
+          "ambient": {ambient},
+          "layers": [
+            {{
+              "id": "mid",
+              "parallax": 1.0,
+              "sprites": [
+                {{ "id": "hero", "asset": "assets/textures/test_sprite.png", "x": 0.0, "y": 0.0 }}
+              ]
+            }}
+          ]
+        }}
+        "#
+            )
+        };
+
+        write_scene_file(&path, &scene_json(r#"[{ "kind": "snow", "wind": -20.0 }]"#));
+        let scene = load_scene_from_path(&path).expect("ambient should load");
+        assert_eq!(scene.ambient.len(), 1);
+        assert_eq!(scene.ambient[0].kind, AmbientKind::Snow);
+        assert_eq!(scene.ambient[0].density, 1.0);
+        assert_eq!(scene.ambient[0].wind, -20.0);
+
+        write_scene_file(
+            &path,
+            &scene_json(r#"[{ "kind": "rain", "density": -1.0 }]"#),
+        );
+        let err = load_scene_from_path(&path).expect_err("negative density should fail");
+        assert!(err.contains("ambient density"), "{err}");
+
+        let _ = fs::remove_file(path);
+    }
+
+    #[test]
+    fn load_scene_from_path_parses_and_validates_sprite_tweens() {
+        let path = temp_file_path("tweens");
+        let scene_json = |tween: &str| {
+            format!(
+                r#"
+        {{
+          "version": "0.3",
+          "scene_id": "test_scene",
+          "layers": [
+            {{
+              "id": "mid",
+              "parallax": 1.0,
+              "sprites": [
+                {{ "id": "lift", "asset": "assets/textures/test_sprite.png", "x": 0.0, "y": 0.0, "tweens": [{tween}] }}
+              ]
+            }}
+          ]
+        }}
+        "#
+            )
+        };
+
+        write_scene_file(
+            &path,
+            &scene_json(
+                r#"{ "to": { "y": 120.0 }, "duration": 2.0, "easing": "ease_in_out", "repeat": "ping_pong" }"#,
+            ),
+        );
+        let scene = load_scene_from_path(&path).expect("tweens should load");
+        let tween = &scene.layers[0].sprites[0].tweens[0];
+        assert_eq!(tween.to.get(&TweenProperty::Y), Some(&120.0));
+        assert_eq!(
+            (tween.easing, tween.repeat),
+            (Easing::EaseInOut, Repeat::PingPong)
+        );
+
+        write_scene_file(
+            &path,
+            &scene_json(r#"{ "to": { "y": 1.0 }, "duration": 0.0 }"#),
+        );
+        let err = load_scene_from_path(&path).expect_err("zero duration should fail");
+        assert!(err.contains("sprite 'lift'"), "{err}");
+
+        let _ = fs::remove_file(path);
+    }
+
+    #[test]
+    fn load_scene_from_path_parses_and_validates_sprite_pickup() {
+        let path = temp_file_path("pickup");
+        let scene_json = |pickup: &str| {
+            format!(
+                r#"
+        {{
+          "version": "0.3",
+          "scene_id": "test_scene",
+          "layers": [
+            {{
+              "id": "mid",
+              "parallax": 1.0,
+              "sprites": [
+                {{ "id": "coin_a", "asset": "assets/textures/test_sprite.png", "x": 0.0, "y": 0.0, "pickup": {pickup} }}
+              ]
+            }}
+          ]
+        }}
+        "#
+            )
+        };
+
+        write_scene_file(&path, &scene_json(r#"{ "item": "soul_coin" }"#));
+        let scene = load_scene_from_path(&path).expect("pickup should load");
+        assert_eq!(
+            scene.layers[0].sprites[0].pickup,
+            Some(ScenePickup {
+                item: "soul_coin".to_string(),
+                count: 1
+            })
+        );
+
+        write_scene_file(&path, &scene_json(r#"{ "item": "soul_coin", "count": 0 }"#));
+        let err = load_scene_from_path(&path).expect_err("zero count should fail");
+        assert!(err.contains("sprite 'coin_a'"), "{err}");
+
+        let _ = fs::remove_file(path);
+    }
+
+    #[test]
+    fn load_scene_from_path_parses_and_validates_sprite_effect() {
+        let path = temp_file_path("effect");
+        let scene_json = |effect: &str| {
+            format!(
+                r#"
+        {{
+          "version": "0.3",
+          "scene_id": "test_scene",
+          "layers": [
+            {{
+              "id": "mid",
+              "parallax": 1.0,
+              "sprites": [
+                {{ "id": "crate", "asset": "assets/textures/test_sprite.png", "x": 0.0, "y": 0.0, "effect": {effect} }}
+              ]
+            }}
+          ]
+        }}
+        "#
+            )
+        };
+
+        write_scene_file(&path, &scene_json(r#"{ "kind": "outline", "param": 2 }"#));
+        let scene = load_scene_from_path(&path).expect("effect should load");
+        let effect = scene.layers[0].sprites[0].effect.as_ref().expect("effect");
+        assert_eq!(effect.to_effect(), Ok(SpriteEffect::Outline(2.0)));
+
+        write_scene_file(&path, &scene_json(r#"{ "kind": "flash" }"#));
+        let scene = load_scene_from_path(&path).expect("param defaults to 1");
+        let effect = scene.layers[0].sprites[0].effect.as_ref().expect("effect");
+        assert_eq!(effect.to_effect(), Ok(SpriteEffect::Flash(1.0)));
+
+        write_scene_file(&path, &scene_json(r#"{ "kind": "sparkle" }"#));
+        let err = load_scene_from_path(&path).expect_err("unknown effect should fail");
+        assert!(err.contains("sprite 'crate'"), "{err}");
+
+        let _ = fs::remove_file(path);
+    }
+
+    #[test]
+    fn load_scene_from_path_resolves_sprite_pivot_overrides() {
+        let path = temp_file_path("pivot");
+        let scene_json = |pivot: &str| {
+            format!(
+                r#"
+        {{
+          "version": "0.3",
+          "scene_id": "test_scene",
+          "layers": [
+            {{
+              "id": "mid",
+              "parallax": 1.0,
+              "sprites": [
+                {{ "id": "hero", "asset": "assets/textures/test_sprite.png", "x": 0.0, "y": 0.0, "pivot": {pivot} }}
+              ]
+            }}
+          ]
+        }}
+        "#
+            )
+        };
+
+        write_scene_file(&path, &scene_json(r#""bottom-center""#));
+        let scene = load_scene_from_path(&path).expect("named pivot should load");
+        assert_eq!(scene.layers[0].sprites[0].pivot, Some((0.5, 0.0)));
+
+        write_scene_file(&path, &scene_json(r#"{ "x": 0.25, "y": 1.0 }"#));
+        let scene = load_scene_from_path(&path).expect("numeric pivot should load");
+        assert_eq!(scene.layers[0].sprites[0].pivot, Some((0.25, 1.0)));
+
+        write_scene_file(&path, &scene_json(r#""feet""#));
+        let err = load_scene_from_path(&path).expect_err("unknown preset should fail");
+        assert!(err.contains("unknown pivot preset 'feet'"), "{err}");
+
+        let _ = fs::remove_file(path);
+    }
+
+    #[test]
+    fn load_scene_from_path_converts_units_to_pixels() {
+        let path = temp_file_path("units");
+        let json = r#"
+        {
+          "version": "0.3",
+          "scene_id": "test_scene",
+          "units": { "pixels_per_unit": 16 },
+          "camera": { "start_x": 2.0, "start_y": 1.0, "bounds": { "min_x": 0.0, "min_y": 0.0, "max_x": 40.0, "max_y": 20.0 } },
+          "layers": [
+            {
+              "id": "mid",
+              "parallax": 1.0,
+              "sprites": [
+                { "id": "crate", "asset": "assets/textures/test_sprite.png", "x": 3.0, "y": 0.5, "scale_x": 2.0,
+                  "tweens": [ { "to": { "y": 2.0, "alpha": 0.5 }, "duration": 1.0 } ] }
+              ]
+            },
+            {
+              "id": "hud",
+              "parallax": 1.0,
+              "space": "screen",
+              "sprites": [
+                { "id": "frame", "asset": "assets/textures/test_sprite.png", "x": 32.0, "y": 32.0 }
+              ]
+            }
+          ]
+        }
+        "#;
+
+        write_scene_file(&path, json);
+        let scene = load_scene_from_path(&path).expect("scene in units should load");
+        let camera = scene.camera.as_ref().expect("camera");
+        assert_eq!((camera.start_x, camera.start_y), (32.0, 16.0));
+        let bounds = camera.bounds.as_ref().expect("bounds");
+        assert_eq!((bounds.max_x, bounds.max_y), (640.0, 320.0));
+        let sprite = &scene.layers[0].sprites[0];
+        assert_eq!((sprite.x, sprite.y, sprite.scale_x), (48.0, 8.0, 2.0));
+        let to = &sprite.tweens[0].to;
+        assert_eq!(to[&TweenProperty::Y], 32.0);
+        assert_eq!(to[&TweenProperty::Alpha], 0.5);
+        let frame = &scene.layers[1].sprites[0];
+        assert_eq!((frame.x, frame.y), (32.0, 32.0));
+
+        write_scene_file(&path, &json.replace("16", "-1"));
+        let err = load_scene_from_path(&path).expect_err("negative scale should fail");
+        assert!(err.contains("pixels_per_unit -1"), "{err}");
+
+        let _ = fs::remove_file(path);
+    }
+
+    #[test]
+    fn load_scene_from_path_mirrors_y_down_content() {
+        let path = temp_file_path("y_down");
+        let json = r#"
+        {
+          "version": "0.3",
+          "scene_id": "test_scene",
+          "units": { "y_axis": "down" },
+          "camera": { "start_x": 10.0, "start_y": 50.0, "bounds": { "min_x": 0.0, "min_y": 0.0, "max_x": 400.0, "max_y": 300.0 } },
+          "layers": [
+            {
+              "id": "mid",
+              "parallax": 1.0,
+              "sprites": [
+                { "id": "crate", "asset": "assets/textures/test_sprite.png", "x": 20.0, "y": 80.0, "rotation_deg": 15.0,
+                  "tweens": [ { "to": { "y": 100.0, "rotation": 90.0 }, "duration": 1.0 } ] }
+              ]
+            }
+          ]
+        }
+        "#;
+
+        write_scene_file(&path, json);
+        let scene = load_scene_from_path(&path).expect("y-down scene should load");
+        let camera = scene.camera.as_ref().expect("camera");
+        assert_eq!((camera.start_x, camera.start_y), (10.0, -50.0));
+        let bounds = camera.bounds.as_ref().expect("bounds");
+        assert_eq!((bounds.min_y, bounds.max_y), (-300.0, 0.0));
+        let sprite = &scene.layers[0].sprites[0];
+        assert_eq!(
+            (sprite.x, sprite.y, sprite.rotation_deg),
+            (20.0, -80.0, -15.0)
+        );
+        let to = &sprite.tweens[0].to;
+        assert_eq!(to[&TweenProperty::Y], -100.0);
+        assert_eq!(to[&TweenProperty::Rotation], -90.0);
+
+        let _ = fs::remove_file(path);
+    }
+
+    #[test]
+    fn load_scene_from_path_rejects_empty_layers() {
+        let path = temp_file_path("empty_layers");
+        let json = r#"
+        {
+          "version": "0.1",
+          "scene_id": "test_scene",
+          "layers": []
+        }
+        "#;
+
+        write_scene_file(&path, json);
+        let err = load_scene_from_path(&path).expect_err("empty layers should fail");
+        assert!(err.contains("layers array is empty"));
+
+        let _ = fs::remove_file(path);
+    }
+
+    #[test]
+    fn load_scene_from_path_rejects_duplicate_layer_ids() {
+        let path = temp_file_path("dup_layer");
+        let json = r#"
+        {
+          "version": "0.1",
+          "scene_id": "test_scene",
+          "layers": [
+            {
+              "id": "layer_a",
+              "parallax": 0.3,
+              "sprites": [
+                { "id": "s1", "asset": "assets/textures/test_sprite.png", "x": 0.0, "y": 0.0 }
+              ]
+            },
+            {
+              "id": "layer_a",
+              "parallax": 0.8,
+              "sprites": [
+                { "id": "s2", "asset": "assets/textures/test_sprite.png", "x": 10.0, "y": 5.0 }
+              ]
+            }
+          ]
+        }
+        "#;
+
+        write_scene_file(&path, json);
+        let err = load_scene_from_path(&path).expect_err("duplicate layer ids should fail");
+        assert!(err.contains("duplicate layer id"));
+
+        let _ = fs::remove_file(path);
+    }
+
+    #[test]
+    fn load_scene_from_path_rejects_duplicate_sprite_ids() {
+        let path = temp_file_path("dup_sprite");
+        let json = r#"
+        {
+          "version": "0.1",
+          "scene_id": "test_scene",
+          "layers": [
+            {
+              "id": "layer_a",
+              "parallax": 0.3,
+              "sprites": [
+                { "id": "same_sprite", "asset": "assets/textures/test_sprite.png", "x": 0.0, "y": 0.0 }
+              ]
+            },
+            {
+              "id": "layer_b",
+              "parallax": 0.9,
+              "sprites": [
+                { "id": "same_sprite", "asset": "assets/textures/test_sprite.png", "x": 5.0, "y": 0.0 }
+              ]
+            }
+          ]
+        }
+        "#;
+
+        write_scene_file(&path, json);
+        let err = load_scene_from_path(&path).expect_err("duplicate sprite ids should fail");
+        assert!(err.contains("duplicate sprite id"));
+
+        let _ = fs::remove_file(path);
+    }
+
+    #[test]
+    fn scene_camera_zoom_defaults_to_one() {
+        let path = temp_file_path("camera_default");
+        let json = r#"
+        {
+          "version": "0.1",
+          "scene_id": "test_scene",
+          "camera": { "start_x": 10.0, "start_y": 20.0 },
+          "layers": [
+            {
+              "id": "layer_a",
+              "parallax": 1.0,
+              "sprites": [
+                { "id": "s1", "asset": "assets/textures/test_sprite.png", "x": 0.0, "y": 0.0 }
+              ]
+            }
+          ]
+        }
+        "#;
+
+        write_scene_file(&path, json);
+        let scene = load_scene_from_path(&path).expect("scene should parse");
+        assert!(scene.camera.is_some());
+        assert_eq!(scene.camera.as_ref().expect("camera exists").zoom, 1.0);
+
+        let _ = fs::remove_file(path);
+    }
+
+    #[test]
+    fn scene_camera_reads_and_checks_constraints() {
+        let path = temp_file_path("camera_constraints");
+        let scene_json = |camera: &str| {
+            format!(
+                r#"{{ "version": "0.3", "scene_id": "cam", "camera": {camera},
+                    "layers": [{{ "id": "l", "parallax": 1.0, "sprites": [{{ "id": "s", "asset": "a.png", "x": 0.0, "y": 0.0 }}] }}] }}"#
+            )
+        };
+
+        write_scene_file(
+            &path,
+            &scene_json(
+                r#"{ "min_zoom": 0.5, "max_zoom": 2.0, "bounds": { "min_x": -100.0, "min_y": 0.0, "max_x": 900.0, "max_y": 400.0 } }"#,
+            ),
+        );
+        let scene = load_scene_from_path(&path).expect("scene should parse");
+        let camera = scene.camera.expect("camera exists");
+        assert_eq!(camera.min_zoom, Some(0.5));
+        assert_eq!(camera.bounds.expect("bounds").max_x, 900.0);
+
+        write_scene_file(
+            &path,
+            &scene_json(r#"{ "min_zoom": 3.0, "max_zoom": 2.0 }"#),
+        );
+        let err = load_scene_from_path(&path).expect_err("inverted zoom range");
+        assert!(err.contains("exceeds max_zoom"), "{err}");
+
+        write_scene_file(
+            &path,
+            &scene_json(
+                r#"{ "bounds": { "min_x": 5.0, "min_y": 0.0, "max_x": 5.0, "max_y": 1.0 } }"#,
+            ),
+        );
+        let err = load_scene_from_path(&path).expect_err("empty bounds");
+        assert!(err.contains("camera bounds"), "{err}");
+
+        let _ = fs::remove_file(path);
+    }
+
+    #[test]
+    fn scene_watcher_detects_newly_created_file() {
+        let path = temp_file_path("watcher_create");
+        let _ = fs::remove_file(&path);
+
+        let mut watcher = SceneWatcher::new(path.clone());
+        assert!(!watcher.should_reload(), "missing file should not reload");
+
+        write_scene_file(
+            &path,
+            r#"{"version":"0.1","scene_id":"watcher","layers":[{"id":"l","parallax":1.0,"sprites":[{"id":"s","asset":"assets/textures/test_sprite.png","x":0.0,"y":0.0}]}]}"#,
+        );
+
+        assert!(
+            watcher.should_reload(),
+            "creating file should trigger reload once"
+        );
+        assert!(
+            !watcher.should_reload(),
+            "without changes, second poll should not reload"
+        );
+
+        let _ = fs::remove_file(path);
+    }
+
+    #[test]
+    fn load_scene_v02_with_atlases_and_animations() {
+        let path = temp_file_path("v02_full");
+        let json = r#"
+        {
+          "version": "0.2",
+          "scene_id": "test_v02",
+          "atlases": [
+            "assets/generated/chars_atlas.json",
+            "assets/generated/env_atlas.json"
+          ],
+          "animations": [
+            "assets/animations/hero_animations.json"
+          ],
+          "layers": [
+            {
+              "id": "gameplay",
+              "parallax": 1.0,
+              "sprites": [
+                {
+                  "id": "player",
+                  "sprite_id": "uuid-aaa",
+                  "animation": "idle",
+                  "animation_source": "hero_animations",
+                  "x": 0.0, "y": 0.0
+                }
+              ]
+            }
+          ]
+        }
+        "#;
+        write_scene_file(&path, json);
+        let scene = load_scene_from_path(&path).expect("v0.2 scene should load");
+        assert_eq!(scene.version, SCENE_SCHEMA.current);
+        assert_eq!(scene.atlases.len(), 2);
+        assert_eq!(scene.animations.len(), 1);
+
+        let sprite = &scene.layers[0].sprites[0];
+        assert_eq!(sprite.animation.as_deref(), Some("idle"));
+        assert_eq!(sprite.animation_source.as_deref(), Some("hero_animations"));
+
+        let _ = fs::remove_file(path);
+    }
+
+    #[test]
+    fn load_scene_v01_still_parses_with_new_optional_fields() {
+        let path = temp_file_path("v01_compat");
+        let json = r#"
+        {
+          "version": "0.1",
+          "scene_id": "legacy",
+          "layers": [
+            {
+              "id": "bg",
+              "parallax": 0.5,
+              "sprites": [
+                { "id": "s1", "asset": "assets/textures/test_sprite.png", "x": 0.0, "y": 0.0 }
+              ]
+            }
+          ]
+        }
+        "#;
+        write_scene_file(&path, json);
+        let scene = load_scene_from_path(&path).expect("v0.1 should still parse");
+        assert_eq!(scene.version, SCENE_SCHEMA.current);
+        assert!(scene.atlases.is_empty());
+        assert!(scene.animations.is_empty());
+        assert!(scene.layers[0].sprites[0].animation.is_none());
+        assert!(scene.layers[0].sprites[0].animation_source.is_none());
+
+        let _ = fs::remove_file(path);
+    }
+
+    #[test]
+    fn load_scene_rejects_unsupported_version() {
+        let path = temp_file_path("bad_version");
+        let json = r#"
+        {
+          "version": "9.9",
+          "scene_id": "future",
+          "layers": [
+            {
+              "id": "bg",
+              "parallax": 1.0,
+              "sprites": [
+                { "id": "s1", "asset": "assets/textures/test_sprite.png", "x": 0.0, "y": 0.0 }
+              ]
+            }
+          ]
+        }
+        "#;
+        write_scene_file(&path, json);
+        let err = load_scene_from_path(&path).expect_err("unsupported version should fail");
+        assert!(err.contains("unsupported version"));
+
+        let _ = fs::remove_file(path);
+    }
+
+    #[test]
+    fn load_scene_rejects_sprite_without_asset_or_sprite_id() {
+        let path = temp_file_path("missing_sprite_ref");
+        let json = r#"
+        {
+          "version": "0.1",
+          "scene_id": "test_scene",
+          "layers": [
+            {
+              "id": "layer_a",
+              "parallax": 1.0,
+              "sprites": [
+                { "id": "s1", "x": 0.0, "y": 0.0 }
+              ]
+            }
+          ]
+        }
+        "#;
+        write_scene_file(&path, json);
+        let err = load_scene_from_path(&path).expect_err("missing sprite refs should fail");
+        assert!(err.contains("must provide either 'asset' or 'sprite_id'"));
+
+        let _ = fs::remove_file(path);
+    }
+
+    #[test]
+    fn load_scene_v03_merges_includes_with_overrides() {
+        let shared = temp_file_path("include_shared");
+        let root = temp_file_path("include_root");
+        write_scene_file(
+            &shared,
+            r#"
+            {
+              "version": "0.3",
+              "atlases": ["assets/generated/shared_atlas.json"],
+              "layers": [
+                {
+                  "id": "sky",
+                  "parallax": 0.1,
+                  "sprites": [{ "id": "sky_1", "asset": "assets/textures/test_sprite.png", "x": 0.0, "y": 0.0 }]
+                },
+                {
+                  "id": "hills",
+                  "parallax": 0.3,
+                  "sprites": [{ "id": "hill_1", "asset": "assets/textures/test_sprite.png", "x": 0.0, "y": 0.0 }]
+                }
+              ]
+            }
+            "#,
+        );
+        let root_json = format!(
+            r#"
+            {{
+              "version": "0.3",
+              "scene_id": "level_1",
+              "includes": ["{}"],
+              "atlases": ["assets/generated/level_atlas.json", "assets/generated/shared_atlas.json"],
+              "layers": [
+                {{
+                  "id": "hills",
+                  "parallax": 0.5,
+                  "sprites": [{{ "id": "hill_override", "asset": "assets/textures/test_sprite.png", "x": 1.0, "y": 0.0 }}]
+                }},
+                {{
+                  "id": "gameplay",
+                  "parallax": 1.0,
+                  "sprites": [{{ "id": "p", "asset": "assets/textures/test_sprite.png", "x": 0.0, "y": 0.0 }}]
+                }}
+              ]
+            }}
+            "#,
+            shared.to_string_lossy().replace('\\', "/")
+        );
+        write_scene_file(&root, &root_json);
+
+        let scene = load_scene_from_path(&root).expect("scene with includes should load");
+        assert_eq!(scene.scene_id, "level_1");
+        let layer_ids: Vec<&str> = scene.layers.iter().map(|l| l.id.as_str()).collect();
+        assert_eq!(layer_ids, vec!["sky", "hills", "gameplay"]);
+        assert_eq!(scene.layers[1].parallax, 0.5);
+        assert_eq!(scene.layers[1].sprites[0].id, "hill_override");
+        assert_eq!(
+            scene.atlases,
+            vec![
+                "assets/generated/shared_atlas.json".to_string(),
+                "assets/generated/level_atlas.json".to_string()
+            ]
+        );
+        assert_eq!(scene.included_paths.len(), 1);
+
+        let _ = fs::remove_file(shared);
+        let _ = fs::remove_file(root);
+    }
+
+    #[test]
+    fn save_layer_settings_writes_the_defining_file() {
+        let shared = temp_file_path("save_shared");
+        let root = temp_file_path("save_root");
+        write_scene_file(
+            &shared,
+            r#"{ "version": "0.3", "layers": [
+                { "id": "sky", "parallax": 0.1, "sprites": [{ "id": "s", "asset": "a.png", "x": 0.0, "y": 0.0 }] },
+                { "id": "hills", "parallax": 0.3, "sprites": [{ "id": "h", "asset": "a.png", "x": 0.0, "y": 0.0 }] }
+            ] }"#,
+        );
+        write_scene_file(
+            &root,
+            &format!(
+                r#"{{ "version": "0.3", "scene_id": "save", "includes": ["{}"], "layers": [
+                    {{ "id": "hills", "parallax": 0.5, "sprites": [{{ "id": "h2", "asset": "a.png", "x": 0.0, "y": 0.0 }}] }}
+                ] }}"#,
+                shared.to_string_lossy().replace('\\', "/")
+            ),
+        );
+
+        let mut scene = load_scene_from_path(&root).expect("scene should load");
+        scene.layers[0].parallax = 0.2;
+        scene.layers[0].opacity = 0.5;
+        scene.layers[1].visible = false;
+        assert_eq!(
+            save_layer_settings(&root, &scene.layers[0]).expect("save sky"),
+            shared
+        );
+        assert_eq!(
+            save_layer_settings(&root, &scene.layers[1]).expect("save hills"),
+            root
+        );
+
+        let reloaded = load_scene_from_path(&root).expect("saved scene should load");
+        assert_eq!(reloaded.layers[0].parallax, 0.2);
+        assert_eq!(reloaded.layers[0].opacity, 0.5);
+        assert!(!reloaded.layers[1].visible);
+        assert_eq!(reloaded.layers[1].parallax, 0.5);
+        // Defaults stay implicit in the file that did not set them.
+        let root_raw = fs::read_to_string(&root).expect("read root");
+        assert!(!root_raw.contains("opacity"), "{root_raw}");
+        let shared_raw = fs::read_to_string(&shared).expect("read shared");
+        assert!(shared_raw.contains("\"parallax\": 0.2,"), "{shared_raw}");
+
+        let _ = fs::remove_file(shared);
+        let _ = fs::remove_file(root);
+    }
+
+    #[test]
+    fn load_scene_rejects_include_cycle() {
+        let a = temp_file_path("cycle_a");
+        let b = temp_file_path("cycle_b");
+        let layer = r#"[{ "id": "l", "parallax": 1.0, "sprites": [] }]"#;
+        write_scene_file(
+            &a,
+            &format!(
+                r#"{{ "version": "0.3", "scene_id": "a", "includes": ["{}"], "layers": {layer} }}"#,
+                b.to_string_lossy().replace('\\', "/")
+            ),
+        );
+        write_scene_file(
+            &b,
+            &format!(
+                r#"{{ "version": "0.3", "includes": ["{}"] }}"#,
+                a.to_string_lossy().replace('\\', "/")
+            ),
+        );
+
+        let err = load_scene_from_path(&a).expect_err("include cycle should fail");
+        assert!(err.contains("include cycle"));
+
+        let _ = fs::remove_file(a);
+        let _ = fs::remove_file(b);
+    }
+
+    #[test]
+    fn load_scene_rejects_includes_before_v03() {
+        let path = temp_file_path("include_old_version");
+        write_scene_file(
+            &path,
+            r#"{ "version": "0.2", "scene_id": "old", "includes": ["other.json"], "layers": [] }"#,
+        );
+        let err = load_scene_from_path(&path).expect_err("includes need v0.3");
+        assert!(err.contains("requires version 0.3"));
+
+        let _ = fs::remove_file(path);
+    }
+}
@@ -0,0 +1,351 @@
+//! Offline scene analysis for content budgets (`sme_scene_stats`).
+//!
+//! `analyze_scene` loads a scene with its atlases and animations the way a
+//! thumbnail does (see `thumbnail`), without a window or GPU, and reports:
+//! sprites per layer, every texture the sprites draw from with its size and
+//! GPU memory, and how many draw calls the sprite batcher makes of the
+//! visible layers. Animated sprites count at their first frame.
+//!
+//! `OverdrawMap` rasterizes the sprite quads of the visible world layers onto
+//! a coarse grid and counts how many cover each cell: a quick estimate of
+//! how often each part of the screen is filled. Quads count whole, however
+//! much of them is transparent, and layers sit as seen with the camera at
+//! the world origin.
+
+use std::collections::{BTreeMap, HashSet};
+use std::path::Path;
+
+use glam::Vec2;
+use sme_core::interned::{Id, InternedMap};
+use sme_core::tier::FidelityTier;
+use sme_render::{SpriteVertex, Texels};
+
+use crate::scene::{LayerSpace, SceneLayer};
+use crate::thumbnail::Content;
+
+/// Tallest heatmap, in cells, whatever the scene's aspect ratio.
+const MAX_HEATMAP_CELLS: u32 = 4096;
+
+/// Heatmap colors by quads covering a cell: none, 1, 2, 3, 4, and 5 or more.
+const OVERDRAW_COLORS: [[u8; 4]; 6] = [
+    [0, 0, 0, 255],
+    [32, 64, 160, 255],
+    [40, 160, 80, 255],
+    [230, 210, 40, 255],
+    [240, 130, 30, 255],
+    [220, 30, 30, 255],
+];
+
+#[derive(Debug)]
+pub struct SceneStats {
+    pub layers: Vec<LayerStats>,
+    /// Sorted by path.
+    pub textures: Vec<TextureStats>,
+    /// Sprite draw calls of the visible world layers, after batching.
+    pub world_draw_calls: usize,
+    /// The same for screen-space layers, drawn under the UI camera.
+    pub screen_draw_calls: usize,
+    /// Sprites that reference a sprite_id or image that does not resolve.
+    pub unresolved_sprites: usize,
+    /// The quads the estimate is made from; see `OverdrawMap::rasterize`.
+    world_quads: Vec<[Vec2; 4]>,
+}
+
+#[derive(Debug)]
+pub struct LayerStats {
+    pub id: String,
+    /// Positioned in window pixels rather than the world.
+    pub screen_space: bool,
+    pub visible: bool,
+    pub sprites: usize,
+    pub animated: usize,
+}
+
+#[derive(Debug)]
+pub struct TextureStats {
+    pub path: String,
+    pub size: (u32, u32),
+    /// Sprites drawn from it.
+    pub sprites: usize,
+    /// Half-resolution copy loaded on Tier 0, if the atlas has one.
+    pub half_res_size: Option<(u32, u32)>,
+}
+
+impl TextureStats {
+    /// GPU memory of the RGBA8 texture at `tier`.
+    pub fn bytes(&self, tier: FidelityTier) -> u64 {
+        let (width, height) = match tier {
+            FidelityTier::Tier0 => self.half_res_size.unwrap_or(self.size),
+            FidelityTier::Tier2 => self.size,
+        };
+        u64::from(width) * u64::from(height) * 4
+    }
+}
+
+impl SceneStats {
+    pub fn sprite_count(&self) -> usize {
+        self.layers.iter().map(|layer| layer.sprites).sum()
+    }
+
+    /// GPU memory of every texture at `tier`.
+    pub fn texture_bytes(&self, tier: FidelityTier) -> u64 {
+        self.textures
+            .iter()
+            .map(|texture| texture.bytes(tier))
+            .sum()
+    }
+
+    /// The overdraw of the visible world layers on a grid `width` cells
+    /// wide; `None` when they draw nothing.
+    pub fn overdraw(&self, width: u32) -> Option<OverdrawMap> {
+        OverdrawMap::rasterize(&self.world_quads, width)
+    }
+}
+
+pub fn analyze_scene(path: &Path) -> Result<SceneStats, String> {
+    let content = Content::scene(path)?;
+
+    let none: InternedMap<(u32, u32)> = InternedMap::new();
+    let mut sprites_per_texture: BTreeMap<&str, usize> = BTreeMap::new();
+    let mut unresolved_sprites = 0;
+    {
+        let (trails, tweens) = Default::default();
+        let source = content.mesh_source(&none, &trails, &tweens);
+        for sprite in content.layers.iter().flat_map(|layer| &layer.sprites) {
+            match source.resolve_sprite_entry(sprite) {
+                Some(entry) => {
+                    *sprites_per_texture
+                        .entry(entry.texture.as_str())
+                        .or_default() += 1
+                }
+                None => unresolved_sprites += 1,
+            }
+        }
+    }
+
+    let mut textures = Vec::new();
+    let mut sizes = InternedMap::new();
+    for (path, sprites) in sprites_per_texture {
+        let key = Id::new(path);
+        let size = image_size(path)?;
+        let half_res = content.multi_atlas.texture_file(key, FidelityTier::Tier0);
+        let half_res_size = if half_res == key {
+            None
+        } else {
+            Some(image_size(half_res.as_str())?)
+        };
+        sizes.insert(key, size);
+        textures.push(TextureStats {
+            path: path.to_string(),
+            size,
+            sprites,
+            half_res_size,
+        });
+    }
+
+    let (trails, tweens) = Default::default();
+    let source = content.mesh_source(&sizes, &trails, &tweens);
+    let build = |space: LayerSpace| {
+        let layers: Vec<(&SceneLayer, (f32, f32))> = content
+            .layers
+            .iter()
+            .filter(|layer| layer.visible && layer.space == space)
+            .map(|layer| (layer, (0.0, 0.0)))
+            .collect();
+        let (mut vertices, mut indices, mut draw_calls) = (Vec::new(), Vec::new(), Vec::new());
+        source.append_layers(
+            &layers,
+            [1.0; 4],
+            &mut vertices,
+            &mut indices,
+            &mut draw_calls,
+        );
+        (vertices, draw_calls.len())
+    };
+    let (world_vertices, world_draw_calls) = build(LayerSpace::World);
+    let (_, screen_draw_calls) = build(LayerSpace::Screen);
+
+    Ok(SceneStats {
+        layers: content
+            .layers
+            .iter()
+            .map(|layer| LayerStats {
+                id: layer.id.clone(),
+                screen_space: layer.space == LayerSpace::Screen,
+                visible: layer.visible,
+                sprites: layer.sprites.len(),
+                animated: layer
+                    .sprites
+                    .iter()
+                    .filter(|sprite| sprite.animation.is_some())
+                    .count(),
+            })
+            .collect(),
+        textures,
+        world_draw_calls,
+        screen_draw_calls,
+        unresolved_sprites,
+        world_quads: quads(&world_vertices),
+    })
+}
+
+/// Pixel size of an image asset.
+fn image_size(path: &str) -> Result<(u32, u32), String> {
+    let bytes = sme_core::assets::read_bytes(Path::new(path))
+        .map_err(|e| format!("Failed to read texture '{path}': {e}"))?;
+    let texels = Texels::decode(&bytes, false).map_err(|e| format!("Texture '{path}': {e}"))?;
+    Ok((texels.width, texels.height))
+}
+
+/// Sprite quads are four vertices each, bottom-left first, counterclockwise.
+fn quads(vertices: &[SpriteVertex]) -> Vec<[Vec2; 4]> {
+    vertices
+        .chunks_exact(4)
+        .map(|quad| std::array::from_fn(|i| Vec2::from(quad[i].position)))
+        .collect()
+}
+
+/// How many sprite quads cover each cell of a grid over the world.
+#[derive(Debug)]
+pub struct OverdrawMap {
+    /// Cells across and down.
+    pub size: (u32, u32),
+    /// Row-major, top row first.
+    pub counts: Vec<u32>,
+    /// World rect the grid covers.
+    pub min: Vec2,
+    pub max: Vec2,
+}
+
+impl OverdrawMap {
+    /// Count quads at each cell center over the bounds of `quads`, on a
+    /// grid `width` cells wide with square cells.
+    pub fn rasterize(quads: &[[Vec2; 4]], width: u32) -> Option<Self> {
+        let mut corners = quads.iter().flatten();
+        let first = *corners.next()?;
+        let (min, max) = corners.fold((first, first), |(min, max), corner| {
+            (min.min(*corner), max.max(*corner))
+        });
+        let width = width.max(1);
+        let cell = ((max.x - min.x) / width as f32).max(f32::EPSILON);
+        let height = (((max.y - min.y) / cell).ceil() as u32).clamp(1, MAX_HEATMAP_CELLS);
+        let mut counts = vec![0; (width * height) as usize];
+        for quad in quads {
+            let quad_min = quad.iter().fold(quad[0], |a, b| a.min(*b));
+            let quad_max = quad.iter().fold(quad[0], |a, b| a.max(*b));
+            let columns = cell_span(quad_min.x - min.x, quad_max.x - min.x, cell, width);
+            // Rows count down from the top.
+            let rows = cell_span(max.y - quad_max.y, max.y - quad_min.y, cell, height);
+            for row in rows {
+                for column in columns.clone() {
+                    let center = Vec2::new(
+                        min.x + (column as f32 + 0.5) * cell,
+                        max.y - (row as f32 + 0.5) * cell,
+                    );
+                    if contains(quad, center) {
+                        counts[(row * width + column) as usize] += 1;
+                    }
+                }
+            }
+        }
+        Some(Self {
+            size: (width, height),
+            counts,
+            min,
+            max,
+        })
+    }
+
+    /// Highest count of any cell.
+    pub fn max_overdraw(&self) -> u32 {
+        self.counts.iter().copied().max().unwrap_or(0)
+    }
+
+    /// Mean count over the cells something covers.
+    pub fn average_overdraw(&self) -> f32 {
+        let covered: Vec<u32> = self.counts.iter().copied().filter(|&n| n > 0).collect();
+        if covered.is_empty() {
+            return 0.0;
+        }
+        covered.iter().sum::<u32>() as f32 / covered.len() as f32
+    }
+
+    /// One RGBA8 pixel per cell, colored by count.
+    pub fn to_rgba(&self) -> Vec<u8> {
+        self.counts
+            .iter()
+            .flat_map(|&count| OVERDRAW_COLORS[(count as usize).min(OVERDRAW_COLORS.len() - 1)])
+            .collect()
+    }
+
+    pub fn save_png(&self, path: &Path) -> Result<(), String> {
+        sme_render::offscreen::save_png(path, &self.to_rgba(), self.size)
+    }
+}
+
+/// Cells, of size `cell` from 0, whose centers may lie in `from..=to`.
+fn cell_span(from: f32, to: f32, cell: f32, cells: u32) -> std::ops::Range<u32> {
+    let first = ((from / cell - 0.5).ceil().max(0.0)) as u32;
+    let end = (((to / cell - 0.5).floor() + 1.0).max(0.0) as u32).min(cells);
+    first.min(end)..end
+}
+
+/// Whether the convex quad `corners` (either winding) contains `point`.
+fn contains(corners: &[Vec2; 4], point: Vec2) -> bool {
+    let sides: HashSet<bool> = (0..4)
+        .map(|i| {
+            let (a, b) = (corners[i], corners[(i + 1) % 4]);
+            (b - a).perp_dot(point - a)
+        })
+        .filter(|cross| *cross != 0.0)
+        .map(|cross| cross > 0.0)
+        .collect();
+    sides.len() <= 1
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn rect(min: (f32, f32), max: (f32, f32)) -> [Vec2; 4] {
+        [
+            Vec2::new(min.0, min.1),
+            Vec2::new(max.0, min.1),
+            Vec2::new(max.0, max.1),
+            Vec2::new(min.0, max.1),
+        ]
+    }
+
+    #[test]
+    fn overdraw_counts_overlapping_quads_per_cell() {
+        // Two 40x40 quads overlapping in a 20x40 strip, on a 60-wide grid of
+        // 10-unit cells.
+        let quads = [
+            rect((0.0, 0.0), (40.0, 40.0)),
+            rect((20.0, 0.0), (60.0, 40.0)),
+        ];
+        let map = OverdrawMap::rasterize(&quads, 6).expect("quads");
+        assert_eq!(map.size, (6, 4));
+        assert_eq!(&map.counts[..6], &[1, 1, 2, 2, 1, 1]);
+        assert_eq!(map.max_overdraw(), 2);
+        assert!((map.average_overdraw() - 4.0 / 3.0).abs() < 1e-5);
+        assert_eq!(&map.to_rgba()[8..12], &OVERDRAW_COLORS[2]);
+
+        assert!(OverdrawMap::rasterize(&[], 8).is_none());
+    }
+
+    #[test]
+    fn rotated_quads_leave_their_corners_uncovered() {
+        let diamond = [
+            Vec2::new(20.0, 0.0),
+            Vec2::new(40.0, 20.0),
+            Vec2::new(20.0, 40.0),
+            Vec2::new(0.0, 20.0),
+        ];
+        let map = OverdrawMap::rasterize(&[diamond], 4).expect("quad");
+        assert_eq!(
+            map.counts,
+            vec![0, 1, 1, 0, 1, 1, 1, 1, 1, 1, 1, 1, 0, 1, 1, 0]
+        );
+    }
+}
@@ -0,0 +1,384 @@
+//! Script unit tests (`sme_script_test`): a gameplay script driven through
+//! `LuaBridge` with scripted input, no window, scene or physics.
+//!
+//! A spec is JSON naming the script and a list of cases:
+//!
+//! ```json
+//! {
+//!   "script": "assets/scripts/controller.lua",
+//!   "cases": [{
+//!     "name": "jump only when pressed",
+//!     "actor": { "grounded": true },
+//!     "steps": [
+//!       { "held": ["right"], "expect": { "move_x": 1.0, "jump": false } },
+//!       { "pressed": ["space"], "expect": { "jump": true } },
+//!       { "repeat": 30, "held": ["a"], "expect": { "move_x": -1.0 } }
+//!     ]
+//!   }]
+//! }
+//! ```
+//!
+//! Every case starts from a freshly loaded script (its own Lua state and RNG,
+//! seeded with `seed`), then calls `on_update` once per step, or `repeat`
+//! times, with that step's input: `held` keys, `pressed` keys (which are held
+//! too), held mouse `buttons`, `clicked` buttons, the `mouse` position in world
+//! units and `wheel` notches. A step's `actor` changes `engine.actor` from then
+//! on. Only the fields an `expect` names are checked, after every call of the
+//! step: `move_x` (to `MOVE_X_TOLERANCE`), `jump`, `crouch`, `dash`,
+//! `play_animation` (a clip name, or `null` for no call), `stop_animation` and
+//! `start_dialog` (likewise). A script error fails the case.
+
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use serde::{Deserialize, Deserializer};
+
+use crate::lua_bridge::{ActorSnapshot, InputSnapshot, LuaBridge, LuaIntent, LuaStatus};
+use crate::SCRIPT_KEYS;
+
+/// Allowed difference between an expected and actual `move_x`.
+pub const MOVE_X_TOLERANCE: f32 = 1e-4;
+const STEP_DT: f32 = 1.0 / 60.0;
+const MOUSE_BUTTONS: [&str; 3] = ["left", "right", "middle"];
+
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct ScriptTestSpec {
+    /// Script under test, relative to the asset root.
+    pub script: PathBuf,
+    /// Seed of `engine.rand` in every case.
+    #[serde(default)]
+    pub seed: Option<u64>,
+    pub cases: Vec<ScriptTestCase>,
+}
+
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct ScriptTestCase {
+    pub name: String,
+    /// `engine.actor` before the first step.
+    #[serde(default)]
+    pub actor: ActorSpec,
+    pub steps: Vec<ScriptTestStep>,
+}
+
+/// `engine.actor` fields to set; the rest keep their values.
+#[derive(Debug, Clone, Default, PartialEq, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct ActorSpec {
+    pub x: Option<f32>,
+    pub y: Option<f32>,
+    pub grounded: Option<bool>,
+    pub swimming: Option<bool>,
+    pub crouching: Option<bool>,
+    pub dashing: Option<bool>,
+    pub invulnerable: Option<bool>,
+    pub velocity_x: Option<f32>,
+    pub velocity_y: Option<f32>,
+    #[serde(default, deserialize_with = "present")]
+    pub current_animation: Option<Option<String>>,
+    pub animation_finished: Option<bool>,
+}
+
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct ScriptTestStep {
+    /// Calls of `on_update` with this input.
+    #[serde(default = "one")]
+    pub repeat: u32,
+    #[serde(default)]
+    pub held: Vec<String>,
+    #[serde(default)]
+    pub pressed: Vec<String>,
+    #[serde(default)]
+    pub buttons: Vec<String>,
+    #[serde(default)]
+    pub clicked: Vec<String>,
+    #[serde(default)]
+    pub mouse: (f32, f32),
+    #[serde(default)]
+    pub wheel: f32,
+    #[serde(default)]
+    pub actor: ActorSpec,
+    #[serde(default)]
+    pub expect: Expectation,
+}
+
+/// Intent fields to check; `None` is not checked.
+#[derive(Debug, Clone, Default, PartialEq, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct Expectation {
+    pub move_x: Option<f32>,
+    pub jump: Option<bool>,
+    pub crouch: Option<bool>,
+    pub dash: Option<bool>,
+    #[serde(default, deserialize_with = "present")]
+    pub play_animation: Option<Option<String>>,
+    pub stop_animation: Option<bool>,
+    #[serde(default, deserialize_with = "present")]
+    pub start_dialog: Option<Option<String>>,
+}
+
+fn one() -> u32 {
+    1
+}
+
+/// A field that is present, even as `null`, is `Some`.
+fn present<'de, D, T>(deserializer: D) -> Result<Option<T>, D::Error>
+where
+    D: Deserializer<'de>,
+    T: Deserialize<'de>,
+{
+    T::deserialize(deserializer).map(Some)
+}
+
+pub fn load_script_test_from_path(path: &Path) -> Result<ScriptTestSpec, String> {
+    let raw =
+        fs::read_to_string(path).map_err(|e| format!("Failed to read {}: {e}", path.display()))?;
+    serde_json::from_str(&raw)
+        .map_err(|e| format!("Failed to parse script test {}: {e}", path.display()))
+}
+
+/// Run every case of `spec`, with its script under `asset_root`; each result
+/// names the case, and on failure the first step that went wrong.
+pub fn run_script_test(
+    spec: &ScriptTestSpec,
+    asset_root: &Path,
+) -> Vec<(String, Result<(), String>)> {
+    let script = asset_root.join(&spec.script);
+    spec.cases
+        .iter()
+        .map(|case| (case.name.clone(), run_case(spec, &script, case)))
+        .collect()
+}
+
+fn run_case(spec: &ScriptTestSpec, script: &Path, case: &ScriptTestCase) -> Result<(), String> {
+    let mut bridge = LuaBridge::new(script.to_path_buf());
+    if bridge.status() != LuaStatus::Loaded {
+        return Err(format!(
+            "{} did not load: {}",
+            script.display(),
+            bridge.last_error().unwrap_or("script not found")
+        ));
+    }
+    if let Some(seed) = spec.seed {
+        bridge.set_rng_seed(seed);
+    }
+    let mut actor = default_actor();
+    case.actor.apply(&mut actor);
+    for (index, step) in case.steps.iter().enumerate() {
+        let at = |call: u32| match step.repeat {
+            1 => format!("step {}", index + 1),
+            repeat => format!("step {} (call {call} of {repeat})", index + 1),
+        };
+        step.actor.apply(&mut actor);
+        let input = step.input().map_err(|err| format!("{}: {err}", at(1)))?;
+        for call in 1..=step.repeat {
+            let intent = bridge.call_update(STEP_DT, &input, &actor).ok_or_else(|| {
+                let message = bridge
+                    .recent_errors()
+                    .first()
+                    .map_or_else(|| "on_update failed".to_string(), |e| e.message.clone());
+                format!("{}: {message}", at(call))
+            })?;
+            let mismatches = step.expect.mismatches(&intent);
+            if !mismatches.is_empty() {
+                return Err(format!("{}: {}", at(call), mismatches.join("; ")));
+            }
+        }
+    }
+    Ok(())
+}
+
+/// Standing at the origin, 16x16, at rest and airborne.
+fn default_actor() -> ActorSnapshot {
+    ActorSnapshot {
+        x: 0.0,
+        y: 0.0,
+        half_w: 8.0,
+        half_h: 8.0,
+        grounded: false,
+        swimming: false,
+        crouching: false,
+        dashing: false,
+        invulnerable: false,
+        time_scale: 1.0,
+        velocity_x: 0.0,
+        velocity_y: 0.0,
+        current_animation: None,
+        animation_finished: false,
+    }
+}
+
+impl ActorSpec {
+    fn apply(&self, actor: &mut ActorSnapshot) {
+        let set = |field: &mut f32, value: Option<f32>| *field = value.unwrap_or(*field);
+        set(&mut actor.x, self.x);
+        set(&mut actor.y, self.y);
+        set(&mut actor.velocity_x, self.velocity_x);
+        set(&mut actor.velocity_y, self.velocity_y);
+        let flag = |field: &mut bool, value: Option<bool>| *field = value.unwrap_or(*field);
+        flag(&mut actor.grounded, self.grounded);
+        flag(&mut actor.swimming, self.swimming);
+        flag(&mut actor.crouching, self.crouching);
+        flag(&mut actor.dashing, self.dashing);
+        flag(&mut actor.invulnerable, self.invulnerable);
+        flag(&mut actor.animation_finished, self.animation_finished);
+        if let Some(animation) = &self.current_animation {
+            actor.current_animation = animation.clone();
+        }
+    }
+}
+
+impl ScriptTestStep {
+    fn input(&self) -> Result<InputSnapshot, String> {
+        let keys = SCRIPT_KEYS.iter().map(|(_, name)| *name);
+        check_names("key", &self.held, keys.clone())?;
+        check_names("key", &self.pressed, keys)?;
+        check_names("mouse button", &self.buttons, MOUSE_BUTTONS)?;
+        check_names("mouse button", &self.clicked, MOUSE_BUTTONS)?;
+        let union = |held: &[String], pressed: &[String]| {
+            let mut names = held.to_vec();
+            names.extend(pressed.iter().filter(|name| !held.contains(name)).cloned());
+            names
+        };
+        Ok(InputSnapshot {
+            held_keys: union(&self.held, &self.pressed),
+            just_pressed_keys: self.pressed.clone(),
+            held_buttons: union(&self.buttons, &self.clicked),
+            just_pressed_buttons: self.clicked.clone(),
+            mouse_x: self.mouse.0,
+            mouse_y: self.mouse.1,
+            wheel: self.wheel,
+        })
+    }
+}
+
+fn check_names<'a>(
+    kind: &str,
+    names: &[String],
+    known: impl IntoIterator<Item = &'a str>,
+) -> Result<(), String> {
+    let known: Vec<&str> = known.into_iter().collect();
+    match names.iter().find(|name| !known.contains(&name.as_str())) {
+        Some(name) => Err(format!(
+            "unknown {kind} '{name}' (expected one of {})",
+            known.join(", ")
+        )),
+        None => Ok(()),
+    }
+}
+
+impl Expectation {
+    fn mismatches(&self, intent: &LuaIntent) -> Vec<String> {
+        let mut mismatches = Vec::new();
+        if let Some(move_x) = self.move_x {
+            if (intent.move_x - move_x).abs() > MOVE_X_TOLERANCE {
+                mismatches.push(format!("move_x: expected {move_x}, got {}", intent.move_x));
+            }
+        }
+        let mut check = |name: &str, expected: Option<bool>, actual: bool| {
+            if expected.is_some_and(|expected| expected != actual) {
+                mismatches.push(format!("{name}: expected {}, got {actual}", !actual));
+            }
+        };
+        check("jump", self.jump, intent.jump_pressed);
+        check("crouch", self.crouch, intent.crouch);
+        check("dash", self.dash, intent.dash);
+        check("stop_animation", self.stop_animation, intent.stop_animation);
+        let mut check_call =
+            |name: &str, expected: &Option<Option<String>>, actual: &Option<String>| {
+                if expected.as_ref().is_some_and(|expected| expected != actual) {
+                    let show = |call: &Option<String>| match call {
+                        Some(value) => format!("'{value}'"),
+                        None => "no call".to_string(),
+                    };
+                    mismatches.push(format!(
+                        "{name}: expected {}, got {}",
+                        show(expected.as_ref().unwrap()),
+                        show(actual)
+                    ));
+                }
+            };
+        check_call(
+            "play_animation",
+            &self.play_animation,
+            &intent.play_animation,
+        );
+        check_call("start_dialog", &self.start_dialog, &intent.start_dialog);
+        mismatches
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::Write;
+
+    fn temp_script(name: &str, source: &str) -> PathBuf {
+        let path = std::env::temp_dir().join(format!(
+            "sme_test_script_test_{name}_{}.lua",
+            std::process::id()
+        ));
+        let mut file = fs::File::create(&path).expect("create script");
+        file.write_all(source.as_bytes()).expect("write script");
+        path
+    }
+
+    fn spec(script: &Path, cases: &str) -> ScriptTestSpec {
+        serde_json::from_str(&format!(
+            r#"{{ "script": {:?}, "cases": {cases} }}"#,
+            script.to_string_lossy()
+        ))
+        .expect("spec")
+    }
+
+    #[test]
+    fn cases_report_the_first_failing_step() {
+        let script = temp_script(
+            "controller",
+            r#"
+function on_update(dt)
+    local move_x = engine.input.is_held("right") and 1 or 0
+    engine.actor.set_intent(move_x, engine.input.is_just_pressed("space") and engine.actor.grounded)
+    if engine.actor.grounded and move_x ~= 0 then engine.actor.play_animation("run") end
+end
+"#,
+        );
+        let spec = spec(
+            &script,
+            r#"[
+                { "name": "runs", "actor": { "grounded": true }, "steps": [
+                    { "repeat": 3, "held": ["right"], "expect": { "move_x": 1, "play_animation": "run" } },
+                    { "expect": { "move_x": 0, "play_animation": null } }
+                ] },
+                { "name": "no air jump", "steps": [
+                    { "pressed": ["space"], "expect": { "jump": false } },
+                    { "actor": { "grounded": true }, "repeat": 2, "pressed": ["space"], "expect": { "jump": false } }
+                ] },
+                { "name": "typo", "steps": [{ "held": ["jump"] }] }
+            ]"#,
+        );
+        let results = run_script_test(&spec, Path::new(""));
+        assert_eq!(results[0], ("runs".to_string(), Ok(())));
+        assert_eq!(
+            results[1].1,
+            Err("step 2 (call 1 of 2): jump: expected false, got true".to_string())
+        );
+        let typo = results[2].1.as_ref().unwrap_err();
+        assert!(typo.starts_with("step 1: unknown key 'jump'"), "{typo}");
+        let _ = fs::remove_file(script);
+    }
+
+    #[test]
+    fn script_errors_fail_the_case() {
+        let script = temp_script("broken", "function on_update(dt) error('boom') end\n");
+        let spec = spec(&script, r#"[{ "name": "boom", "steps": [{}] }]"#);
+        let err = run_script_test(&spec, Path::new(""))[0]
+            .1
+            .clone()
+            .unwrap_err();
+        assert!(err.starts_with("step 1: ") && err.contains("boom"), "{err}");
+        let _ = fs::remove_file(script);
+    }
+}
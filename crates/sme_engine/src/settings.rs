@@ -0,0 +1,420 @@
+//! Player settings: the options a game's menus change at runtime.
+//!
+//! Window size, vsync, master volume, fidelity tier and key bindings are kept
+//! in `<config dir>/settings.toml` (see `sme_platform::paths::config_dir`):
+//!
+//! ```toml
+//! width = 1920
+//! height = 1080
+//! vsync = false
+//! volume = 0.8
+//! tier = 0
+//!
+//! [bindings]
+//! left = "KeyJ"
+//! space = "KeyK"
+//! ```
+//!
+//! The file is read before the window opens, so its size and tier win over
+//! the project manifest's. Scripts read and change settings through
+//! `engine.settings`; the engine applies changes after the frame's fixed steps
+//! and writes the file again. A binding maps an `engine.input` key name to a
+//! physical key, named as winit's `KeyCode` (`"KeyJ"`, `"ArrowUp"`,
+//! `"ShiftLeft"`); the key's default stops triggering it. F5 and the overlay
+//! still change the tier for the session only.
+//!
+//! The engine has no audio output yet; `volume` is stored for the game's own
+//! audio code.
+
+use std::collections::{BTreeMap, HashMap, HashSet};
+use std::path::{Path, PathBuf};
+
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use sme_core::input::Key;
+use sme_core::tier::FidelityTier;
+use winit::keyboard::KeyCode;
+
+use crate::project::tier_from_number;
+use crate::{map_key, EngineConfig, SCRIPT_KEYS};
+
+/// Settings file name inside the config directory.
+pub const SETTINGS_FILE: &str = "settings.toml";
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct Settings {
+    /// Window inner size; `None` keeps the project's.
+    pub width: Option<u32>,
+    pub height: Option<u32>,
+    pub vsync: bool,
+    /// Master volume, 0..=1.
+    pub volume: f32,
+    /// `0` or `2`, as in `FidelityTier`; `None` keeps the project's.
+    pub tier: Option<u8>,
+    /// `engine.input` key name -> physical key name.
+    pub bindings: BTreeMap<String, String>,
+}
+
+impl Default for Settings {
+    fn default() -> Self {
+        Self {
+            width: None,
+            height: None,
+            vsync: true,
+            volume: 1.0,
+            tier: None,
+            bindings: BTreeMap::new(),
+        }
+    }
+}
+
+impl Settings {
+    pub fn tier(&self) -> Option<FidelityTier> {
+        self.tier.and_then(|tier| tier_from_number(tier).ok())
+    }
+
+    /// Override the window size and tier `config` starts with.
+    pub fn apply_to(&self, config: &mut EngineConfig) {
+        if let Some(width) = self.width {
+            config.window.width = width;
+        }
+        if let Some(height) = self.height {
+            config.window.height = height;
+        }
+        if let Some(tier) = self.tier() {
+            config.tier = tier;
+        }
+    }
+
+    /// The value `engine.settings.get(name)` returns.
+    pub fn get(&self, name: &str) -> Result<Value, String> {
+        Ok(match name {
+            "width" => self.width.into(),
+            "height" => self.height.into(),
+            "vsync" => self.vsync.into(),
+            "volume" => self.volume.into(),
+            "tier" => self.tier.into(),
+            _ => return Err(format!("unknown setting '{name}'")),
+        })
+    }
+
+    /// Change one setting; `null` resets the optional ones.
+    pub fn set(&mut self, name: &str, value: &Value) -> Result<(), String> {
+        let size = |value: &Value| match value {
+            Value::Null => Ok(None),
+            _ => value
+                .as_u64()
+                .filter(|&size| size > 0)
+                .and_then(|size| u32::try_from(size).ok())
+                .map(Some)
+                .ok_or_else(|| format!("{name} must be a positive integer, got {value}")),
+        };
+        match name {
+            "width" => self.width = size(value)?,
+            "height" => self.height = size(value)?,
+            "vsync" => {
+                self.vsync = value
+                    .as_bool()
+                    .ok_or_else(|| format!("vsync must be a boolean, got {value}"))?;
+            }
+            "volume" => {
+                self.volume = value
+                    .as_f64()
+                    .filter(|volume| (0.0..=1.0).contains(volume))
+                    .ok_or_else(|| format!("volume must be in 0..1, got {value}"))?
+                    as f32;
+            }
+            "tier" => {
+                self.tier = if value.is_null() {
+                    None
+                } else {
+                    let tier = value
+                        .as_u64()
+                        .and_then(|tier| u8::try_from(tier).ok())
+                        .ok_or_else(|| format!("tier must be 0 or 2, got {value}"))?;
+                    tier_from_number(tier)?;
+                    Some(tier)
+                };
+            }
+            _ => return Err(format!("unknown setting '{name}'")),
+        }
+        Ok(())
+    }
+
+    /// Bind the script key `key` to the physical key `physical`, or back to
+    /// its default when `None`.
+    pub fn bind(&mut self, key: &str, physical: Option<&str>) -> Result<(), String> {
+        if script_key(key).is_none() {
+            return Err(format!("unknown key '{key}'"));
+        }
+        match physical {
+            Some(physical) => {
+                if physical_key(physical).is_none() {
+                    return Err(format!("unknown physical key '{physical}'"));
+                }
+                self.bindings.insert(key.to_string(), physical.to_string());
+            }
+            None => {
+                self.bindings.remove(key);
+            }
+        }
+        Ok(())
+    }
+
+    fn validate(&self) -> Result<(), String> {
+        if self.width == Some(0) || self.height == Some(0) {
+            return Err("width and height must be > 0".to_string());
+        }
+        if !(0.0..=1.0).contains(&self.volume) {
+            return Err(format!("volume {} is outside 0..1", self.volume));
+        }
+        if let Some(tier) = self.tier {
+            tier_from_number(tier)?;
+        }
+        for (key, physical) in &self.bindings {
+            if script_key(key).is_none() {
+                return Err(format!("unknown key '{key}' in bindings"));
+            }
+            if physical_key(physical).is_none() {
+                return Err(format!("unknown physical key '{physical}' in bindings"));
+            }
+        }
+        Ok(())
+    }
+}
+
+pub fn load_settings_from_path(path: &Path) -> Result<Settings, String> {
+    let raw = std::fs::read_to_string(path)
+        .map_err(|e| format!("Failed to read settings {}: {e}", path.display()))?;
+    parse_settings(&raw).map_err(|e| format!("Failed to parse settings {}: {e}", path.display()))
+}
+
+fn parse_settings(raw: &str) -> Result<Settings, String> {
+    let settings: Settings = toml::from_str(raw).map_err(|e| e.to_string())?;
+    settings.validate()?;
+    Ok(settings)
+}
+
+/// Settings shared with the `engine.settings` closures, and where they are
+/// saved.
+#[derive(Debug, Default)]
+pub struct SettingsStore {
+    /// `None` when the platform has no config directory; changes then last
+    /// for the session only.
+    path: Option<PathBuf>,
+    settings: Settings,
+    changed: bool,
+}
+
+impl SettingsStore {
+    /// Settings of the game called `app`, or the defaults if it has none
+    /// saved (or they do not parse).
+    pub fn load(app: &str) -> Self {
+        let path = sme_platform::paths::config_dir(app).map(|dir| dir.join(SETTINGS_FILE));
+        let settings = match &path {
+            Some(path) if path.is_file() => match load_settings_from_path(path) {
+                Ok(settings) => {
+                    log::info!("Settings: {}", path.display());
+                    settings
+                }
+                Err(err) => {
+                    log::warn!("{err}; using default settings");
+                    Settings::default()
+                }
+            },
+            _ => Settings::default(),
+        };
+        Self {
+            path,
+            settings,
+            changed: false,
+        }
+    }
+
+    pub fn settings(&self) -> &Settings {
+        &self.settings
+    }
+
+    /// Change the settings through `change`; they are only kept (and marked
+    /// changed) if it succeeds.
+    pub fn update(
+        &mut self,
+        change: impl FnOnce(&mut Settings) -> Result<(), String>,
+    ) -> Result<(), String> {
+        let mut settings = self.settings.clone();
+        change(&mut settings)?;
+        if settings != self.settings {
+            self.settings = settings;
+            self.changed = true;
+        }
+        Ok(())
+    }
+
+    /// The settings, if they changed since the last call. Writes them to the
+    /// settings file first.
+    pub fn take_changed(&mut self) -> Option<Settings> {
+        if !std::mem::take(&mut self.changed) {
+            return None;
+        }
+        if let Some(path) = &self.path {
+            if let Err(err) = write_settings_to_path(path, &self.settings) {
+                log::warn!("{err}");
+            }
+        }
+        Some(self.settings.clone())
+    }
+}
+
+fn write_settings_to_path(path: &Path, settings: &Settings) -> Result<(), String> {
+    if let Some(dir) = path.parent() {
+        std::fs::create_dir_all(dir)
+            .map_err(|e| format!("Failed to create config directory {}: {e}", dir.display()))?;
+    }
+    let raw = toml::to_string_pretty(settings).map_err(|e| e.to_string())?;
+    std::fs::write(path, raw)
+        .map_err(|e| format!("Failed to write settings {}: {e}", path.display()))
+}
+
+/// Physical keys mapped to engine keys, with the player's bindings applied.
+#[derive(Debug, Default)]
+pub struct KeyBindings {
+    bound: HashMap<KeyCode, Key>,
+    /// Keys whose default physical key no longer triggers them.
+    rebound: HashSet<Key>,
+}
+
+impl KeyBindings {
+    pub fn new(bindings: &BTreeMap<String, String>) -> Self {
+        let mut key_bindings = Self::default();
+        for (key, physical) in bindings {
+            let (Some(key), Some(code)) = (script_key(key), physical_key(physical)) else {
+                continue;
+            };
+            key_bindings.bound.insert(code, key);
+            key_bindings.rebound.insert(key);
+        }
+        key_bindings
+    }
+
+    pub fn map(&self, code: KeyCode) -> Option<Key> {
+        if let Some(&key) = self.bound.get(&code) {
+            return Some(key);
+        }
+        map_key(code).filter(|key| !self.rebound.contains(key))
+    }
+}
+
+fn script_key(name: &str) -> Option<Key> {
+    SCRIPT_KEYS
+        .iter()
+        .find(|(_, key_name)| *key_name == name)
+        .map(|&(key, _)| key)
+}
+
+/// Keys a binding may name.
+pub(crate) const BINDABLE_KEYS: &[KeyCode] = &[
+    KeyCode::KeyA,
+    KeyCode::KeyB,
+    KeyCode::KeyC,
+    KeyCode::KeyD,
+    KeyCode::KeyE,
+    KeyCode::KeyF,
+    KeyCode::KeyG,
+    KeyCode::KeyH,
+    KeyCode::KeyI,
+    KeyCode::KeyJ,
+    KeyCode::KeyK,
+    KeyCode::KeyL,
+    KeyCode::KeyM,
+    KeyCode::KeyN,
+    KeyCode::KeyO,
+    KeyCode::KeyP,
+    KeyCode::KeyQ,
+    KeyCode::KeyR,
+    KeyCode::KeyS,
+    KeyCode::KeyT,
+    KeyCode::KeyU,
+    KeyCode::KeyV,
+    KeyCode::KeyW,
+    KeyCode::KeyX,
+    KeyCode::KeyY,
+    KeyCode::KeyZ,
+    KeyCode::Digit0,
+    KeyCode::Digit1,
+    KeyCode::Digit2,
+    KeyCode::Digit3,
+    KeyCode::Digit4,
+    KeyCode::Digit5,
+    KeyCode::Digit6,
+    KeyCode::Digit7,
+    KeyCode::Digit8,
+    KeyCode::Digit9,
+    KeyCode::ArrowLeft,
+    KeyCode::ArrowRight,
+    KeyCode::ArrowUp,
+    KeyCode::ArrowDown,
+    KeyCode::Space,
+    KeyCode::Enter,
+    KeyCode::Tab,
+    KeyCode::Backspace,
+    KeyCode::ShiftLeft,
+    KeyCode::ShiftRight,
+    KeyCode::ControlLeft,
+    KeyCode::ControlRight,
+    KeyCode::AltLeft,
+    KeyCode::AltRight,
+    KeyCode::Comma,
+    KeyCode::Period,
+    KeyCode::Slash,
+    KeyCode::Semicolon,
+];
+
+fn physical_key(name: &str) -> Option<KeyCode> {
+    BINDABLE_KEYS
+        .iter()
+        .copied()
+        .find(|code| format!("{code:?}") == name)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn settings_file_round_trips() {
+        let mut settings = Settings::default();
+        settings.set("width", &json!(1920)).expect("valid width");
+        settings.set("volume", &json!(0.5)).expect("valid volume");
+        settings.set("tier", &json!(0)).expect("valid tier");
+        settings.bind("left", Some("KeyJ")).expect("valid binding");
+        let raw = toml::to_string_pretty(&settings).expect("serializes");
+        assert_eq!(parse_settings(&raw), Ok(settings));
+        assert_eq!(parse_settings(""), Ok(Settings::default()));
+    }
+
+    #[test]
+    fn invalid_settings_are_rejected() {
+        let mut settings = Settings::default();
+        assert!(settings.set("volume", &json!(2)).is_err());
+        assert!(settings.set("tier", &json!(1)).is_err());
+        assert!(settings.set("width", &json!(0)).is_err());
+        assert!(settings.set("fov", &json!(90)).is_err());
+        assert!(settings.bind("jump", Some("KeyJ")).is_err());
+        assert!(settings.bind("left", Some("KeyJJ")).is_err());
+        assert_eq!(settings, Settings::default());
+        assert!(parse_settings("volume = 3.0").is_err());
+        assert!(parse_settings("[bindings]\nleft = \"Pedal\"").is_err());
+    }
+
+    #[test]
+    fn bindings_replace_the_default_key() {
+        let mut settings = Settings::default();
+        settings.bind("left", Some("KeyJ")).expect("valid binding");
+        let bindings = KeyBindings::new(&settings.bindings);
+        assert_eq!(bindings.map(KeyCode::KeyJ), Some(Key::Left));
+        assert_eq!(bindings.map(KeyCode::ArrowLeft), None);
+        assert_eq!(bindings.map(KeyCode::ArrowRight), Some(Key::Right));
+    }
+}
@@ -0,0 +1,81 @@
+//! Multi-step debug controls: "Step 10", "Until grounded changes" and "Until
+//! trigger fires" in the overlay's simulation controls.
+//!
+//! Starting one resumes the simulation at normal speed; after every fixed
+//! step `Stepping::after_step` checks its `StepUntil` against the player's
+//! grounded flag and the events the step raised, and the engine pauses again
+//! as soon as it holds, before the next step runs. Pause cancels it.
+
+use sme_devtools::StepUntil;
+
+use crate::events::EngineEvent;
+
+/// A `StepUntil` in progress.
+#[derive(Debug, Clone)]
+pub struct Stepping {
+    until: StepUntil,
+    steps: u32,
+    grounded: bool,
+}
+
+impl Stepping {
+    /// Start stepping with the player currently `grounded` or not.
+    pub fn new(until: StepUntil, grounded: bool) -> Self {
+        Self {
+            until,
+            steps: 0,
+            grounded,
+        }
+    }
+
+    /// Note a fixed step that ended with the player `grounded` and raised
+    /// `events`; returns whether stepping is done.
+    pub fn after_step(&mut self, grounded: bool, events: &[EngineEvent]) -> bool {
+        self.steps += 1;
+        match &self.until {
+            StepUntil::Steps(count) => self.steps >= *count,
+            StepUntil::GroundedChanges => grounded != self.grounded,
+            StepUntil::Trigger(name) => events.iter().any(
+                |event| matches!(event, EngineEvent::Trigger { trigger, .. } if trigger == name),
+            ),
+        }
+    }
+
+    /// Progress for the overlay.
+    pub fn label(&self) -> String {
+        match &self.until {
+            StepUntil::Steps(count) => format!("Stepping: {} of {count}", self.steps),
+            StepUntil::GroundedChanges => {
+                format!("Stepping until grounded changes ({} steps)", self.steps)
+            }
+            StepUntil::Trigger(name) => {
+                format!("Stepping until trigger '{name}' ({} steps)", self.steps)
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn predicates_hold_after_the_right_step() {
+        let mut ten = Stepping::new(StepUntil::Steps(10), true);
+        assert!((0..9).all(|_| !ten.after_step(true, &[])));
+        assert!(ten.after_step(true, &[]));
+
+        let mut landing = Stepping::new(StepUntil::GroundedChanges, false);
+        assert!(!landing.after_step(false, &[]));
+        assert!(landing.after_step(true, &[]));
+
+        let mut door = Stepping::new(StepUntil::Trigger("door".into()), true);
+        let trigger = |name: &str| EngineEvent::Trigger {
+            actor: "player".into(),
+            trigger: name.into(),
+        };
+        assert!(!door.after_step(true, &[trigger("coin")]));
+        assert!(door.after_step(true, &[trigger("coin"), trigger("door")]));
+        assert_eq!(door.label(), "Stepping until trigger 'door' (2 steps)");
+    }
+}
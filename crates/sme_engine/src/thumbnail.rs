@@ -0,0 +1,564 @@
+//! Headless rendering of content to images, for editor thumbnails, content
+//! previews and documentation.
+//!
+//! ```no_run
+//! use sme_engine::thumbnail::{render_thumbnail, ThumbnailSubject};
+//!
+//! let subject = ThumbnailSubject::Scene("assets/scenes/m4_scene.json".into());
+//! let thumbnail = render_thumbnail(&subject, (256, 144))?;
+//! thumbnail.save_png("level_thumb.png".as_ref())?;
+//! # Ok::<(), String>(())
+//! ```
+//!
+//! `render_thumbnail` loads the subject the way the engine does, builds its
+//! sprite mesh with the frame's mesh builder, and draws it once with the
+//! sprite pipeline into an offscreen texture (`sme_render::offscreen`). No
+//! window is opened and nothing is simulated: no Lua, physics, tweens,
+//! trails, weather or player, and animations show their first frame.
+//!
+//! A scene thumbnail is framed to fit every world-space sprite of its
+//! visible layers, leaving `FRAME_MARGIN` of the image free around them,
+//! over the scene's color, gradient or image background. Parallax layers sit
+//! where they would with the camera at the center of the frame. Screen-space
+//! layers and tiled backgrounds are left out, and layer materials draw with
+//! the default sprite shading. Sprite and animation frame thumbnails fit the
+//! one sprite on a transparent background.
+//!
+//! Paths are asset keys, resolved against the asset root like the engine's
+//! (see `sme_core::assets`).
+
+use std::collections::{BTreeSet, HashMap};
+use std::path::{Path, PathBuf};
+
+use glam::Vec2;
+use sme_core::interned::{Id, InternedMap};
+use sme_core::tier::FidelityTier;
+use sme_render::{
+    AlphaMode, Camera2D, CameraUniform, HeadlessGpu, OffscreenTarget, SpriteEffect, SpritePipeline,
+    SpriteVertex, Texels, Texture, TextureBinding, OFFSCREEN_FORMAT,
+};
+
+use crate::animation::AnimationRegistry;
+use crate::atlas::{load_atlas_from_path, MultiAtlasRegistry};
+use crate::collision::Aabb;
+use crate::controller::CharacterController;
+use crate::determinism::StateMap;
+use crate::scene::{
+    load_scene_from_path, LayerFilter, LayerSpace, SceneBackground, SceneLayer, SceneSprite,
+    SortMode,
+};
+use crate::tween::Tweens;
+use crate::world::World;
+use crate::{
+    append_view_background, background_clear_color, build_animation_states, build_effects,
+    DrawCall, DrawPipeline, GpuSpriteTexture, SpriteMeshSource, TextureSizes, DEBUG_WHITE_ASSET,
+    LEGACY_ATLAS_PATH,
+};
+
+/// Part of the image, on each side, left free around the framed content.
+pub const FRAME_MARGIN: f32 = 0.05;
+
+/// What to draw.
+#[derive(Debug, Clone)]
+pub enum ThumbnailSubject {
+    /// A scene file with its atlases and animations.
+    Scene(PathBuf),
+    /// One sprite of the given atlases.
+    Sprite {
+        atlases: Vec<PathBuf>,
+        sprite_id: String,
+    },
+    /// Frame `frame` (from 0) of `clip` in an animation file, drawn from the
+    /// given atlases.
+    AnimationFrame {
+        atlases: Vec<PathBuf>,
+        animation: PathBuf,
+        clip: String,
+        frame: usize,
+    },
+}
+
+/// A rendered image: RGBA8 rows, top row first, straight alpha.
+pub struct Thumbnail {
+    pub size: (u32, u32),
+    pub rgba: Vec<u8>,
+}
+
+impl Thumbnail {
+    pub fn save_png(&self, path: &Path) -> Result<(), String> {
+        sme_render::offscreen::save_png(path, &self.rgba, self.size)
+    }
+}
+
+/// Render `subject` into a `size` image. Fails if the content does not load
+/// or the machine has no GPU to draw with.
+pub fn render_thumbnail(subject: &ThumbnailSubject, size: (u32, u32)) -> Result<Thumbnail, String> {
+    let content = Content::load(subject)?;
+    let gpu = HeadlessGpu::new()?;
+    let target = OffscreenTarget::new(&gpu.device, size)?;
+    let mut sprite_pipeline = SpritePipeline::new(&gpu.device, OFFSCREEN_FORMAT);
+    if content.multi_atlas.premultiplied_alpha() == Some(true) {
+        sprite_pipeline.set_alpha_mode(&gpu.device, AlphaMode::Premultiplied)?;
+    }
+    let premultiply_images = sprite_pipeline.alpha_mode() == AlphaMode::Premultiplied;
+    let textures = content.load_textures(&gpu, premultiply_images)?;
+    let frame = content.frame(&textures, size);
+    draw(&gpu, &mut sprite_pipeline, &target, &textures, &frame);
+    Ok(Thumbnail {
+        size,
+        rgba: target.read_rgba(&gpu.device, &gpu.queue)?,
+    })
+}
+
+/// Camera position and zoom that fit `min..max` into a view of `size`.
+fn frame_bounds(min: Vec2, max: Vec2, size: (u32, u32)) -> (Vec2, f32) {
+    let extent = (max - min).max(Vec2::ONE);
+    let view = Vec2::new(size.0 as f32, size.1 as f32) * (1.0 - 2.0 * FRAME_MARGIN);
+    ((min + max) * 0.5, (view / extent).min_element())
+}
+
+/// Content loaded without a window, ready to build meshes from. Shared
+/// with `scene_stats`.
+pub(crate) struct Content {
+    /// Every layer, drawn or not; see `drawn_layers`.
+    pub(crate) layers: Vec<SceneLayer>,
+    background: Option<SceneBackground>,
+    pub(crate) multi_atlas: MultiAtlasRegistry,
+    animations: AnimationRegistry,
+    world: World,
+    effects: HashMap<String, SpriteEffect>,
+    /// Where the camera looks when there is nothing to frame.
+    start: (Vec2, f32),
+    /// Cleared to transparent rather than the background color.
+    transparent: bool,
+}
+
+/// A built mesh and the camera to draw it with.
+struct Frame {
+    camera: CameraUniform,
+    clear_color: wgpu::Color,
+    vertices: Vec<SpriteVertex>,
+    indices: Vec<u32>,
+    draw_calls: Vec<DrawCall>,
+}
+
+impl Content {
+    fn load(subject: &ThumbnailSubject) -> Result<Self, String> {
+        match subject {
+            ThumbnailSubject::Scene(path) => Self::scene(path),
+            ThumbnailSubject::Sprite { atlases, sprite_id } => {
+                Self::sprite(load_atlases(atlases)?, AnimationRegistry::new(), sprite_id)
+            }
+            ThumbnailSubject::AnimationFrame {
+                atlases,
+                animation,
+                clip,
+                frame,
+            } => {
+                let mut animations = AnimationRegistry::new();
+                animations.load_file(animation)?;
+                let sprite_id = animations
+                    .animation_id_of(animation)
+                    .and_then(|id| animations.resolve_clip(Some(id), Id::new(clip)))
+                    .ok_or_else(|| {
+                        format!("Animation {} has no clip '{clip}'", animation.display())
+                    })?
+                    .frames
+                    .get(*frame)
+                    .ok_or_else(|| format!("Clip '{clip}' has no frame {frame}"))?
+                    .sprite_id;
+                Self::sprite(load_atlases(atlases)?, animations, sprite_id.as_str())
+            }
+        }
+    }
+
+    pub(crate) fn scene(path: &Path) -> Result<Self, String> {
+        let scene = load_scene_from_path(path)?;
+        let atlas_paths: Vec<PathBuf> = if scene.atlases.is_empty() {
+            vec![PathBuf::from(LEGACY_ATLAS_PATH)]
+        } else {
+            scene.atlases.iter().map(PathBuf::from).collect()
+        };
+        let multi_atlas = load_atlases(&atlas_paths)?;
+        let mut animations = AnimationRegistry::new();
+        for animation in &scene.animations {
+            animations.load_file(Path::new(animation))?;
+        }
+        let mut world = still_world();
+        world.reset_animations(build_animation_states(&scene, &animations));
+        let start = scene.camera.as_ref().map_or((Vec2::ZERO, 1.0), |camera| {
+            (Vec2::new(camera.start_x, camera.start_y), camera.zoom)
+        });
+        Ok(Self {
+            effects: build_effects(&scene),
+            layers: scene.layers,
+            background: scene.background,
+            multi_atlas,
+            animations,
+            world,
+            start,
+            transparent: false,
+        })
+    }
+
+    fn sprite(
+        multi_atlas: MultiAtlasRegistry,
+        animations: AnimationRegistry,
+        sprite_id: &str,
+    ) -> Result<Self, String> {
+        if multi_atlas.resolve(sprite_id).is_none() {
+            return Err(format!(
+                "sprite_id '{sprite_id}' is not in the given atlases"
+            ));
+        }
+        let sprite = SceneSprite {
+            id: "thumbnail".to_string(),
+            asset: None,
+            sprite_id: Some(sprite_id.to_string()),
+            animation: None,
+            animation_source: None,
+            x: 0.0,
+            y: 0.0,
+            z: 0.0,
+            rotation_deg: 0.0,
+            scale_x: 1.0,
+            scale_y: 1.0,
+            trail: None,
+            effect: None,
+            pickup: None,
+            tweens: Vec::new(),
+            controller: None,
+            pivot: None,
+        };
+        Ok(Self {
+            layers: vec![SceneLayer {
+                id: "thumbnail".to_string(),
+                parallax: 1.0,
+                sort_mode: SortMode::None,
+                occlusion: false,
+                visible: true,
+                opacity: 1.0,
+                space: LayerSpace::World,
+                material: None,
+                filter: LayerFilter::Nearest,
+                group: None,
+                sprites: vec![sprite],
+            }],
+            background: None,
+            multi_atlas,
+            animations,
+            world: still_world(),
+            effects: HashMap::new(),
+            start: (Vec2::ZERO, 1.0),
+            transparent: true,
+        })
+    }
+
+    /// Visible world-space layers, the ones a thumbnail shows.
+    pub(crate) fn drawn_layers(&self) -> impl Iterator<Item = &SceneLayer> {
+        self.layers
+            .iter()
+            .filter(|layer| layer.visible && layer.space == LayerSpace::World)
+    }
+
+    pub(crate) fn mesh_source<'a>(
+        &'a self,
+        textures: &'a dyn TextureSizes,
+        trails: &'a StateMap<String, crate::trail::Trail>,
+        tweens: &'a Tweens,
+    ) -> SpriteMeshSource<'a> {
+        SpriteMeshSource {
+            world: &self.world,
+            animation_registry: &self.animations,
+            multi_atlas: &self.multi_atlas,
+            textures,
+            trails,
+            tweens,
+            effects: &self.effects,
+            tier: FidelityTier::Tier2,
+        }
+    }
+
+    /// Every texture the content draws, full size. Loose images are
+    /// premultiplied when `premultiply_images`; atlases already match.
+    fn load_textures(
+        &self,
+        gpu: &HeadlessGpu,
+        premultiply_images: bool,
+    ) -> Result<InternedMap<GpuSpriteTexture>, String> {
+        let none: InternedMap<(u32, u32)> = InternedMap::new();
+        let (trails, tweens) = (StateMap::default(), Tweens::default());
+        let source = self.mesh_source(&none, &trails, &tweens);
+        // Sorted, so textures load in the same order every run.
+        let mut wanted = BTreeSet::new();
+        for sprite in self.drawn_layers().flat_map(|layer| &layer.sprites) {
+            if let Some(entry) = source.resolve_sprite_entry(sprite) {
+                wanted.insert(entry.texture.as_str());
+            }
+        }
+        if let Some(SceneBackground::Image { asset }) = &self.background {
+            wanted.insert(asset.as_str());
+        }
+
+        let atlas_textures = self.multi_atlas.texture_paths();
+        let mut textures = InternedMap::new();
+        for path in wanted {
+            let key = Id::new(path);
+            let bytes = sme_core::assets::read_bytes(Path::new(path))
+                .map_err(|e| format!("Failed to read texture '{path}': {e}"))?;
+            let premultiply = premultiply_images && !atlas_textures.contains(&key);
+            let texels = Texels::decode(&bytes, premultiply)
+                .map_err(|e| format!("Texture '{path}': {e}"))?;
+            let texture = Texture::from_rgba8(
+                &gpu.device,
+                &gpu.queue,
+                &texels.rgba,
+                texels.width,
+                texels.height,
+                path,
+            );
+            textures.insert(key, gpu_texture(texture, key));
+        }
+        let white = Texture::from_rgba8(
+            &gpu.device,
+            &gpu.queue,
+            &[255, 255, 255, 255],
+            1,
+            1,
+            DEBUG_WHITE_ASSET,
+        );
+        textures.insert(
+            Id::new(DEBUG_WHITE_ASSET),
+            gpu_texture(white, Id::new(DEBUG_WHITE_ASSET)),
+        );
+        Ok(textures)
+    }
+
+    /// The mesh framed into `size`: sprites are laid out once to find their
+    /// bounds, then again with parallax seen from the bounds' center.
+    fn frame(&self, textures: &InternedMap<GpuSpriteTexture>, size: (u32, u32)) -> Frame {
+        let (trails, tweens) = (StateMap::default(), Tweens::default());
+        let source = self.mesh_source(textures, &trails, &tweens);
+        let build = |center: Vec2| {
+            let layers: Vec<(&SceneLayer, (f32, f32))> = self
+                .drawn_layers()
+                .map(|layer| {
+                    let offset = center * (1.0 - layer.parallax);
+                    (layer, (offset.x, offset.y))
+                })
+                .collect();
+            let mut mesh = (Vec::new(), Vec::new(), Vec::new());
+            source.append_layers(&layers, [1.0; 4], &mut mesh.0, &mut mesh.1, &mut mesh.2);
+            mesh
+        };
+
+        let (vertices, ..) = build(Vec2::ZERO);
+        let bounds = vertices.iter().fold(None, |bounds, vertex| {
+            let point = Vec2::from(vertex.position);
+            Some(match bounds {
+                None => (point, point),
+                Some((min, max)) => (point.min(min), point.max(max)),
+            })
+        });
+        let (position, zoom) = match bounds {
+            Some((min, max)) => frame_bounds(min, max, size),
+            None => self.start,
+        };
+        let camera = Camera2D {
+            position,
+            zoom,
+            viewport: size,
+        };
+
+        let mut vertices = Vec::new();
+        let mut indices = Vec::new();
+        let mut draw_calls = Vec::new();
+        let view_a = camera.screen_to_world(Vec2::ZERO);
+        let view_b = camera.screen_to_world(Vec2::new(size.0 as f32, size.1 as f32));
+        append_view_background(
+            &mut vertices,
+            &mut indices,
+            &mut draw_calls,
+            self.background.as_ref(),
+            textures,
+            (view_a.min(view_b), view_a.max(view_b)),
+        );
+        let (layer_vertices, layer_indices, layer_draws) = build(position);
+        let vertex_base = vertices.len() as u32;
+        let index_base = indices.len() as u32;
+        vertices.extend(layer_vertices);
+        indices.extend(layer_indices.iter().map(|index| index + vertex_base));
+        draw_calls.extend(layer_draws.into_iter().map(|draw| DrawCall {
+            index_start: draw.index_start + index_base,
+            ..draw
+        }));
+
+        let clear_color = if self.transparent {
+            wgpu::Color::TRANSPARENT
+        } else {
+            let [r, g, b] = background_clear_color(self.background.as_ref(), FidelityTier::Tier2)
+                .map(f64::from);
+            wgpu::Color { r, g, b, a: 1.0 }
+        };
+        Frame {
+            camera: camera.build_uniform(),
+            clear_color,
+            vertices,
+            indices,
+            draw_calls,
+        }
+    }
+}
+
+/// A world with a player that is never drawn, for animation states only.
+fn still_world() -> World {
+    World::new(CharacterController::new(Aabb {
+        center_x: 0.0,
+        center_y: 0.0,
+        half_w: 0.5,
+        half_h: 0.5,
+    }))
+}
+
+fn load_atlases(paths: &[PathBuf]) -> Result<MultiAtlasRegistry, String> {
+    let mut multi_atlas = MultiAtlasRegistry::new();
+    for path in paths {
+        let registry = load_atlas_from_path(path)?;
+        multi_atlas.add_atlas(&path.to_string_lossy(), registry)?;
+    }
+    Ok(multi_atlas)
+}
+
+fn gpu_texture(texture: Texture, file: Id) -> GpuSpriteTexture {
+    GpuSpriteTexture {
+        size: texture.size,
+        texture,
+        secondary: None,
+        file,
+    }
+}
+
+/// Record `frame` into `target` and submit it.
+fn draw(
+    gpu: &HeadlessGpu,
+    sprite_pipeline: &mut SpritePipeline,
+    target: &OffscreenTarget,
+    textures: &InternedMap<GpuSpriteTexture>,
+    frame: &Frame,
+) {
+    let device = &gpu.device;
+    let camera_buffer = create_filled_buffer(
+        gpu,
+        "Thumbnail Camera Buffer",
+        bytemuck::cast_slice(&[frame.camera]),
+        wgpu::BufferUsages::UNIFORM,
+    );
+    let camera_bind_group = sprite_pipeline.create_camera_bind_group(device, &camera_buffer);
+    let bindings: Vec<Option<TextureBinding>> = frame
+        .draw_calls
+        .iter()
+        .map(|draw| {
+            let texture = &textures.get(draw.texture_key)?.texture;
+            sprite_pipeline.texture_bind_group(device, texture, None, draw.sampler);
+            Some(TextureBinding {
+                texture: texture.id,
+                secondary: None,
+                sampler: draw.sampler,
+            })
+        })
+        .collect();
+
+    let mut encoder = device.create_command_encoder(&wgpu::CommandEncoderDescriptor {
+        label: Some("Thumbnail Encoder"),
+    });
+    {
+        let mut render_pass = encoder.begin_render_pass(&wgpu::RenderPassDescriptor {
+            label: Some("Thumbnail Render Pass"),
+            color_attachments: &[Some(wgpu::RenderPassColorAttachment {
+                view: &target.view,
+                resolve_target: None,
+                ops: wgpu::Operations {
+                    load: wgpu::LoadOp::Clear(frame.clear_color),
+                    store: wgpu::StoreOp::Store,
+                },
+            })],
+            depth_stencil_attachment: None,
+            ..Default::default()
+        });
+        if !frame.indices.is_empty() {
+            let vertex_buffer = create_filled_buffer(
+                gpu,
+                "Thumbnail Vertex Buffer",
+                bytemuck::cast_slice(&frame.vertices),
+                wgpu::BufferUsages::VERTEX,
+            );
+            let index_buffer = create_filled_buffer(
+                gpu,
+                "Thumbnail Index Buffer",
+                bytemuck::cast_slice(&frame.indices),
+                wgpu::BufferUsages::INDEX,
+            );
+            render_pass.set_bind_group(0, &camera_bind_group, &[]);
+            render_pass.set_vertex_buffer(0, vertex_buffer.slice(..));
+            render_pass.set_index_buffer(index_buffer.slice(..), wgpu::IndexFormat::Uint32);
+            for (draw, binding) in frame.draw_calls.iter().zip(&bindings) {
+                let Some(bind_group) = binding
+                    .as_ref()
+                    .and_then(|binding| sprite_pipeline.cached_texture_bind_group(binding))
+                else {
+                    continue;
+                };
+                // Materials need the running engine; they draw plain here.
+                let pipeline = match draw.pipeline {
+                    DrawPipeline::Effects => &sprite_pipeline.effect_pipeline,
+                    DrawPipeline::Plain | DrawPipeline::Material(_) => {
+                        &sprite_pipeline.render_pipeline
+                    }
+                };
+                render_pass.set_pipeline(pipeline);
+                render_pass.set_bind_group(1, bind_group, &[]);
+                render_pass.draw_indexed(
+                    draw.index_start..(draw.index_start + draw.index_count),
+                    0,
+                    0..1,
+                );
+            }
+        }
+    }
+    gpu.queue.submit(std::iter::once(encoder.finish()));
+}
+
+fn create_filled_buffer(
+    gpu: &HeadlessGpu,
+    label: &str,
+    contents: &[u8],
+    usage: wgpu::BufferUsages,
+) -> wgpu::Buffer {
+    let buffer = gpu.device.create_buffer(&wgpu::BufferDescriptor {
+        label: Some(label),
+        size: contents.len() as u64,
+        usage: usage | wgpu::BufferUsages::COPY_DST,
+        mapped_at_creation: false,
+    });
+    gpu.queue.write_buffer(&buffer, 0, contents);
+    buffer
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn framing_fits_content_inside_the_margin() {
+        // 200x50 of content in a 400x400 image: width decides, 360 px of it.
+        let (center, zoom) =
+            frame_bounds(Vec2::new(-100.0, 0.0), Vec2::new(100.0, 50.0), (400, 400));
+        assert_eq!(center, Vec2::new(0.0, 25.0));
+        assert!((zoom - 1.8).abs() < 1e-5, "{zoom}");
+
+        // A single point still gets a finite zoom.
+        let (center, zoom) = frame_bounds(Vec2::splat(3.0), Vec2::splat(3.0), (64, 32));
+        assert_eq!(center, Vec2::splat(3.0));
+        assert!(zoom.is_finite());
+    }
+}
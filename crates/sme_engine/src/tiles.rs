@@ -0,0 +1,268 @@
+//! Streaming of tiled background illustrations.
+//!
+//! A painted background too large for an atlas is split into tiles at import
+//! (`sme_atlas_packer --tiles`), described by a tileset JSON. The scene places
+//! it in the world with a `tiled` background, and `TileStreamer` decides which
+//! tile textures to keep loaded: every tile within half a view of the camera
+//! is loaded, and a tile is evicted once it is more than a whole view away.
+//! The gap between the two margins keeps tiles at the edge from being loaded
+//! and evicted on alternate frames as the camera wobbles.
+//!
+//! This module is GPU-free; the engine loads and drops the textures it names.
+
+use glam::Vec2;
+use serde::Deserialize;
+use sme_core::interned::Id;
+use sme_core::migrate::SchemaMigrations;
+use std::collections::HashSet;
+use std::path::Path;
+
+/// Tileset schema versions and the upgrade path to the current one.
+pub const TILESET_SCHEMA: SchemaMigrations = SchemaMigrations {
+    kind: "Tileset",
+    current: "0.1",
+    steps: &[],
+};
+
+/// Tiles within this many view sizes of the view are loaded.
+const LOAD_MARGIN: f32 = 0.5;
+/// Loaded tiles further than this many view sizes from the view are evicted.
+const EVICT_MARGIN: f32 = 1.0;
+
+#[derive(Debug, Deserialize, Clone)]
+pub struct TileSetFile {
+    #[allow(dead_code)]
+    pub version: String,
+    pub tileset_id: String,
+    pub width: u32,
+    pub height: u32,
+    pub tile_size: u32,
+    pub tiles: Vec<TileFile>,
+}
+
+#[derive(Debug, Deserialize, Clone)]
+pub struct TileFile {
+    pub column: u32,
+    pub row: u32,
+    pub path: String,
+    pub width: u32,
+    pub height: u32,
+}
+
+pub fn load_tileset_from_path(path: &Path) -> Result<TileSetFile, String> {
+    let raw = sme_core::assets::read_to_string(path)
+        .map_err(|e| format!("Failed to read tileset {}: {e}", path.display()))?;
+    let mut value: serde_json::Value = serde_json::from_str(&raw)
+        .map_err(|e| format!("Failed to parse tileset {}: {e}", path.display()))?;
+    TILESET_SCHEMA.migrate_with_warning(&mut value, &path.display().to_string())?;
+    let tileset: TileSetFile = serde_json::from_value(value)
+        .map_err(|e| format!("Failed to parse tileset {}: {e}", path.display()))?;
+    validate_tileset(&tileset)?;
+    Ok(tileset)
+}
+
+fn validate_tileset(tileset: &TileSetFile) -> Result<(), String> {
+    if tileset.width == 0 || tileset.height == 0 || tileset.tile_size == 0 {
+        return Err(format!(
+            "Tileset '{}' validation failed: width, height, and tile_size must be > 0",
+            tileset.tileset_id
+        ));
+    }
+    let mut cells = HashSet::new();
+    for tile in &tileset.tiles {
+        let at = format!("tile ({}, {})", tile.column, tile.row);
+        if !cells.insert((tile.column, tile.row)) {
+            return Err(format!(
+                "Tileset '{}' validation failed: duplicate {at}",
+                tileset.tileset_id
+            ));
+        }
+        if tile.path.is_empty() {
+            return Err(format!(
+                "Tileset '{}' validation failed: {at} has an empty path",
+                tileset.tileset_id
+            ));
+        }
+        // Edge tiles are cut short by the image; every other tile is full.
+        let x = u64::from(tile.column) * u64::from(tileset.tile_size);
+        let y = u64::from(tile.row) * u64::from(tileset.tile_size);
+        let expected_w =
+            u64::from(tileset.tile_size).min(u64::from(tileset.width).saturating_sub(x));
+        let expected_h =
+            u64::from(tileset.tile_size).min(u64::from(tileset.height).saturating_sub(y));
+        if expected_w == 0 || expected_h == 0 {
+            return Err(format!(
+                "Tileset '{}' validation failed: {at} lies outside the {}x{} image",
+                tileset.tileset_id, tileset.width, tileset.height
+            ));
+        }
+        if (u64::from(tile.width), u64::from(tile.height)) != (expected_w, expected_h) {
+            return Err(format!(
+                "Tileset '{}' validation failed: {at} is {}x{}, expected {expected_w}x{expected_h}",
+                tileset.tileset_id, tile.width, tile.height
+            ));
+        }
+    }
+    Ok(())
+}
+
+/// A tile placed in the world.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct PlacedTile {
+    /// Asset path of the tile texture.
+    pub texture: Id,
+    pub min: Vec2,
+    pub max: Vec2,
+}
+
+/// Textures to load and drop after the view moved.
+#[derive(Debug, Default, PartialEq)]
+pub struct TileChanges {
+    pub load: Vec<Id>,
+    pub evict: Vec<Id>,
+}
+
+impl TileChanges {
+    pub fn is_empty(&self) -> bool {
+        self.load.is_empty() && self.evict.is_empty()
+    }
+}
+
+/// Which tiles of one illustration are resident.
+#[derive(Debug, Clone)]
+pub struct TileStreamer {
+    tiles: Vec<PlacedTile>,
+    resident: Vec<bool>,
+}
+
+impl TileStreamer {
+    /// Place `tileset` with the bottom-left corner of the image at `origin`,
+    /// one pixel per world unit. Nothing is resident yet.
+    pub fn new(tileset: &TileSetFile, origin: Vec2) -> Self {
+        let top = origin.y + tileset.height as f32;
+        let tiles: Vec<PlacedTile> = tileset
+            .tiles
+            .iter()
+            .map(|tile| {
+                // Image rows count down from the top; world y is up.
+                let left = origin.x + (tile.column * tileset.tile_size) as f32;
+                let tile_top = top - (tile.row * tileset.tile_size) as f32;
+                PlacedTile {
+                    texture: Id::new(&tile.path),
+                    min: Vec2::new(left, tile_top - tile.height as f32),
+                    max: Vec2::new(left + tile.width as f32, tile_top),
+                }
+            })
+            .collect();
+        let resident = vec![false; tiles.len()];
+        Self { tiles, resident }
+    }
+
+    /// Refit to the view `view_min..view_max`: load tiles near it and evict
+    /// the far ones. The caller loads and drops the textures returned.
+    pub fn update(&mut self, view_min: Vec2, view_max: Vec2) -> TileChanges {
+        let extent = view_max - view_min;
+        let near = |tile: &PlacedTile, margin: f32| {
+            let (min, max) = (view_min - extent * margin, view_max + extent * margin);
+            tile.min.x < max.x && tile.max.x > min.x && tile.min.y < max.y && tile.max.y > min.y
+        };
+        let mut changes = TileChanges::default();
+        for (tile, resident) in self.tiles.iter().zip(&mut self.resident) {
+            if !*resident && near(tile, LOAD_MARGIN) {
+                *resident = true;
+                changes.load.push(tile.texture);
+            } else if *resident && !near(tile, EVICT_MARGIN) {
+                *resident = false;
+                changes.evict.push(tile.texture);
+            }
+        }
+        changes
+    }
+
+    /// Loaded tiles, in tileset order.
+    pub fn resident_tiles(&self) -> impl Iterator<Item = &PlacedTile> {
+        self.tiles
+            .iter()
+            .zip(&self.resident)
+            .filter(|(_, &resident)| resident)
+            .map(|(tile, _)| tile)
+    }
+
+    /// Every tile texture, loaded or not.
+    pub fn textures(&self) -> impl Iterator<Item = Id> + '_ {
+        self.tiles.iter().map(|tile| tile.texture)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// A 4x1 strip of 100px tiles whose last tile is 50px wide.
+    fn strip() -> TileSetFile {
+        TileSetFile {
+            version: "0.1".to_string(),
+            tileset_id: "strip".to_string(),
+            width: 350,
+            height: 100,
+            tile_size: 100,
+            tiles: (0..4)
+                .map(|column| TileFile {
+                    column,
+                    row: 0,
+                    path: format!("tiles_test/strip_{column}_0.png"),
+                    width: if column == 3 { 50 } else { 100 },
+                    height: 100,
+                })
+                .collect(),
+        }
+    }
+
+    #[test]
+    fn tiles_load_near_the_view_and_evict_past_a_wider_margin() {
+        let tileset = strip();
+        validate_tileset(&tileset).expect("valid");
+        let mut streamer = TileStreamer::new(&tileset, Vec2::new(0.0, -50.0));
+        let id = |column: u32| Id::new(&format!("tiles_test/strip_{column}_0.png"));
+
+        // A 40-wide view over tile 0 loads tiles within 20 units of it.
+        let changes = streamer.update(Vec2::new(10.0, 0.0), Vec2::new(50.0, 10.0));
+        assert_eq!(changes.load, vec![id(0)]);
+        let tile = streamer.resident_tiles().next().expect("tile 0");
+        assert_eq!(
+            (tile.min, tile.max),
+            (Vec2::new(0.0, -50.0), Vec2::new(100.0, 50.0))
+        );
+
+        // Near the seam both tiles are wanted.
+        let changes = streamer.update(Vec2::new(70.0, 0.0), Vec2::new(110.0, 10.0));
+        assert_eq!(changes.load, vec![id(1)]);
+        assert!(changes.evict.is_empty());
+
+        // Tile 0 ends 10 units left of this view: kept within one view.
+        let changes = streamer.update(Vec2::new(110.0, 0.0), Vec2::new(150.0, 10.0));
+        assert!(changes.is_empty(), "{changes:?}");
+        let changes = streamer.update(Vec2::new(320.0, 0.0), Vec2::new(360.0, 10.0));
+        assert_eq!(changes.load, vec![id(3)]);
+        assert_eq!(changes.evict, vec![id(0), id(1)]);
+        assert_eq!(streamer.resident_tiles().count(), 1);
+    }
+
+    #[test]
+    fn tilesets_with_misplaced_or_missized_tiles_are_rejected() {
+        let mut tileset = strip();
+        tileset.tiles[3].width = 100;
+        let err = validate_tileset(&tileset).expect_err("edge tile too wide");
+        assert!(err.contains("expected 50x100"), "{err}");
+
+        let mut tileset = strip();
+        tileset.tiles[3].column = 4;
+        let err = validate_tileset(&tileset).expect_err("outside the image");
+        assert!(err.contains("outside the 350x100 image"), "{err}");
+
+        let mut tileset = strip();
+        tileset.tiles[1].column = 0;
+        let err = validate_tileset(&tileset).expect_err("duplicate");
+        assert!(err.contains("duplicate tile (0, 0)"), "{err}");
+    }
+}
@@ -0,0 +1,188 @@
+//! Afterimage trails: faded copies of a sprite at its recent positions.
+//!
+//! A `Trail` records the sprite's transform every `interval` fixed steps and
+//! keeps the last `length` samples. The mesh builder draws those samples
+//! behind the sprite, oldest first, with alpha falling off linearly from
+//! `alpha` for the newest copy. Samples are taken in simulation, not per
+//! rendered frame, so a dash leaves the same trail at any frame rate.
+//!
+//! Trails come from a sprite's `trail` block in the scene file or from
+//! `engine.sprite.set_trail` in Lua. The player's debug quad uses the id
+//! `"player"`.
+
+use serde::Deserialize;
+use std::collections::VecDeque;
+
+/// Upper bound on `TrailConfig::length`; each copy is another quad.
+pub const MAX_TRAIL_LENGTH: u32 = 32;
+
+#[derive(Debug, Deserialize, Clone, Copy, PartialEq)]
+pub struct TrailConfig {
+    /// Faded copies drawn behind the sprite.
+    pub length: u32,
+    /// Fixed steps between recorded samples.
+    #[serde(default = "default_interval")]
+    pub interval: u32,
+    /// Alpha multiplier of the newest copy.
+    #[serde(default = "default_alpha")]
+    pub alpha: f32,
+}
+
+impl TrailConfig {
+    pub fn new(length: u32) -> Self {
+        Self {
+            length,
+            interval: default_interval(),
+            alpha: default_alpha(),
+        }
+    }
+
+    pub fn validate(&self) -> Result<(), String> {
+        if self.length == 0 || self.length > MAX_TRAIL_LENGTH {
+            return Err(format!(
+                "trail length {} must be within 1..={MAX_TRAIL_LENGTH}",
+                self.length
+            ));
+        }
+        if self.interval == 0 {
+            return Err("trail interval must be >= 1".to_string());
+        }
+        if !(0.0..=1.0).contains(&self.alpha) {
+            return Err(format!(
+                "trail alpha {} must be within 0.0..=1.0",
+                self.alpha
+            ));
+        }
+        Ok(())
+    }
+}
+
+/// Where a sprite was drawn on one fixed step.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct TrailSample {
+    pub x: f32,
+    pub y: f32,
+    pub rotation_deg: f32,
+    pub scale_x: f32,
+    pub scale_y: f32,
+}
+
+impl TrailSample {
+    pub fn at(x: f32, y: f32) -> Self {
+        Self {
+            x,
+            y,
+            rotation_deg: 0.0,
+            scale_x: 1.0,
+            scale_y: 1.0,
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct Trail {
+    pub config: TrailConfig,
+    /// Newest first.
+    samples: VecDeque<TrailSample>,
+    steps_since_sample: u32,
+}
+
+impl Trail {
+    pub fn new(config: TrailConfig) -> Self {
+        Self {
+            config,
+            samples: VecDeque::with_capacity(config.length as usize),
+            steps_since_sample: 0,
+        }
+    }
+
+    /// Change the configuration, keeping whatever history still fits.
+    pub fn set_config(&mut self, config: TrailConfig) {
+        self.config = config;
+        self.samples.truncate(config.length as usize);
+    }
+
+    /// Call once per fixed step with the sprite's current transform.
+    pub fn record(&mut self, sample: TrailSample) {
+        self.steps_since_sample += 1;
+        if self.steps_since_sample < self.config.interval && !self.samples.is_empty() {
+            return;
+        }
+        self.steps_since_sample = 0;
+        self.samples.push_front(sample);
+        self.samples.truncate(self.config.length as usize);
+    }
+
+    /// Copies to draw behind a sprite now at `current`, oldest first, with
+    /// their alpha multipliers. Samples at the current transform are skipped
+    /// so a resting sprite shows no trail.
+    pub fn copies(&self, current: &TrailSample) -> Vec<(TrailSample, f32)> {
+        let length = self.config.length as f32;
+        self.samples
+            .iter()
+            .enumerate()
+            .rev()
+            .filter(|(_, sample)| *sample != current)
+            .map(|(age, sample)| (*sample, self.config.alpha * (length - age as f32) / length))
+            .collect()
+    }
+}
+
+const fn default_interval() -> u32 {
+    2
+}
+
+const fn default_alpha() -> f32 {
+    0.5
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn records_every_interval_and_fades_oldest_copies() {
+        let mut trail = Trail::new(TrailConfig {
+            length: 3,
+            interval: 2,
+            alpha: 0.6,
+        });
+        for step in 0..9 {
+            trail.record(TrailSample::at(step as f32, 0.0));
+        }
+        // Samples at steps 0, 2, 4, 6, 8; only the last three are kept.
+        let copies = trail.copies(&TrailSample::at(9.0, 0.0));
+        let xs: Vec<f32> = copies.iter().map(|(s, _)| s.x).collect();
+        assert_eq!(xs, [4.0, 6.0, 8.0]);
+        let alphas: Vec<f32> = copies.iter().map(|(_, a)| *a).collect();
+        assert!((alphas[0] - 0.2).abs() < 1e-6);
+        assert!((alphas[2] - 0.6).abs() < 1e-6);
+    }
+
+    #[test]
+    fn resting_sprite_has_no_visible_copies() {
+        let mut trail = Trail::new(TrailConfig::new(4));
+        let rest = TrailSample::at(10.0, 20.0);
+        for _ in 0..10 {
+            trail.record(rest);
+        }
+        assert!(trail.copies(&rest).is_empty());
+
+        trail.set_config(TrailConfig::new(1));
+        trail.record(TrailSample::at(11.0, 20.0));
+        trail.record(TrailSample::at(12.0, 20.0));
+        assert_eq!(trail.copies(&TrailSample::at(13.0, 20.0)).len(), 1);
+    }
+
+    #[test]
+    fn validate_rejects_out_of_range_settings() {
+        assert!(TrailConfig::new(4).validate().is_ok());
+        assert!(TrailConfig::new(0).validate().is_err());
+        assert!(TrailConfig::new(MAX_TRAIL_LENGTH + 1).validate().is_err());
+        let too_opaque = TrailConfig {
+            alpha: 1.5,
+            ..TrailConfig::new(4)
+        };
+        assert!(too_opaque.validate().is_err());
+    }
+}
@@ -0,0 +1,157 @@
+//! Scene transitions: `engine.scene.load(path, opts)` and
+//! `SystemContext::load_scene` move to another scene.
+//!
+//! A request fades the screen to black over `fade` simulated seconds, then
+//! the scene, its atlases and animations, and optionally the collision grid
+//! are swapped between two fixed steps, like a hot reload. The new scene
+//! fades back in over the same time. Without a fade the swap happens before
+//! the next step. The fade advances with fixed steps, so a replay swaps at
+//! the same step as its recording.
+
+use std::path::PathBuf;
+
+/// Where to go, and how.
+#[derive(Debug, Clone, PartialEq)]
+pub struct SceneLoad {
+    pub scene: PathBuf,
+    /// Collision grid to load with the scene. `None` uses the scene's own
+    /// `collision`, or keeps the current grid if it names none.
+    pub collision: Option<PathBuf>,
+    /// Seconds to fade out, and again to fade in; 0 cuts straight over.
+    pub fade: f32,
+}
+
+#[derive(Debug, Default)]
+pub struct SceneTransition {
+    active: Option<ActiveTransition>,
+}
+
+#[derive(Debug)]
+struct ActiveTransition {
+    /// Taken when the fade-out completes; `None` while fading in.
+    request: Option<SceneLoad>,
+    fade: f32,
+    elapsed: f32,
+}
+
+impl SceneTransition {
+    /// Begin moving to `request`. A request made while fading out replaces
+    /// the pending one without restarting the fade; one made while fading in
+    /// fades out from the current darkness.
+    pub fn start(&mut self, request: SceneLoad) {
+        let fade = request.fade.max(0.0);
+        let elapsed = match &self.active {
+            Some(active) if active.request.is_some() => active.elapsed.min(fade),
+            Some(active) if active.fade > 0.0 => {
+                (1.0 - active.elapsed / active.fade).clamp(0.0, 1.0) * fade
+            }
+            _ => 0.0,
+        };
+        self.active = Some(ActiveTransition {
+            request: Some(request),
+            fade,
+            elapsed,
+        });
+    }
+
+    pub fn step(&mut self, dt: f32) {
+        let Some(active) = &mut self.active else {
+            return;
+        };
+        active.elapsed = (active.elapsed + dt).min(active.fade);
+        if active.request.is_none() && active.elapsed >= active.fade {
+            self.active = None;
+        }
+    }
+
+    /// The request to swap in now, once its fade-out is complete. The fade-in
+    /// starts from here.
+    pub fn take_ready(&mut self) -> Option<SceneLoad> {
+        let active = self.active.as_mut()?;
+        if active.request.is_none() || active.elapsed < active.fade {
+            return None;
+        }
+        let request = active.request.take();
+        if active.fade > 0.0 {
+            active.elapsed = 0.0;
+        } else {
+            self.active = None;
+        }
+        request
+    }
+
+    /// Opacity of the black overlay, 0 when no transition is running.
+    pub fn alpha(&self) -> f32 {
+        let Some(active) = &self.active else {
+            return 0.0;
+        };
+        if active.fade <= 0.0 {
+            return if active.request.is_some() { 1.0 } else { 0.0 };
+        }
+        let progress = (active.elapsed / active.fade).clamp(0.0, 1.0);
+        if active.request.is_some() {
+            progress
+        } else {
+            1.0 - progress
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn load(scene: &str, fade: f32) -> SceneLoad {
+        SceneLoad {
+            scene: scene.into(),
+            collision: None,
+            fade,
+        }
+    }
+
+    #[test]
+    fn cut_is_ready_before_the_next_step() {
+        let mut transition = SceneTransition::default();
+        transition.start(load("b.json", 0.0));
+        assert_eq!(transition.take_ready(), Some(load("b.json", 0.0)));
+        assert_eq!(transition.alpha(), 0.0);
+        assert_eq!(transition.take_ready(), None);
+    }
+
+    #[test]
+    fn fade_swaps_at_black_then_fades_back_in() {
+        let mut transition = SceneTransition::default();
+        transition.start(load("b.json", 0.5));
+        transition.step(0.25);
+        assert_eq!(transition.alpha(), 0.5);
+        assert_eq!(transition.take_ready(), None);
+
+        transition.step(0.25);
+        assert_eq!(transition.alpha(), 1.0);
+        assert_eq!(transition.take_ready(), Some(load("b.json", 0.5)));
+        assert_eq!(transition.alpha(), 1.0);
+
+        transition.step(0.25);
+        assert_eq!(transition.alpha(), 0.5);
+        assert_eq!(transition.take_ready(), None);
+        transition.step(0.25);
+        assert_eq!(transition.alpha(), 0.0);
+        assert!(transition.active.is_none());
+    }
+
+    #[test]
+    fn new_request_while_fading_out_keeps_the_progress() {
+        let mut transition = SceneTransition::default();
+        transition.start(load("b.json", 1.0));
+        transition.step(0.75);
+        transition.start(load("c.json", 1.0));
+        assert_eq!(transition.alpha(), 0.75);
+        transition.step(0.25);
+        assert_eq!(transition.take_ready(), Some(load("c.json", 1.0)));
+
+        // Interrupting the fade-in fades out from the current darkness.
+        transition.step(0.25);
+        transition.start(load("d.json", 1.0));
+        assert_eq!(transition.alpha(), 0.75);
+    }
+}
@@ -0,0 +1,351 @@
+//! Property tweens: sprites that move, scale, rotate or fade over time.
+//!
+//! A tween eases some of a sprite's drawn properties (`x`, `y`, `scale_x`,
+//! `scale_y`, `rotation`, `alpha`) from their current values to targets over
+//! `duration` simulated seconds. Tweens come from a sprite's `tweens` list in
+//! the scene file, which start when the scene loads, or from
+//! `engine.tween.to(id, { x = ..., alpha = ... }, duration, easing, repeat)`
+//! in Lua. Starting a tween replaces any running one on the same property.
+//!
+//! Tweens step in the fixed step (see `world::FixedSystem::Tweens`), so they
+//! replay and rewind with the rest of the simulation. They never touch the
+//! scene itself: the mesh builder and trails read a sprite's properties
+//! through `Tweens::apply`, and a finished tween holds its final values until
+//! the scene reloads.
+
+use std::collections::BTreeMap;
+
+use serde::Deserialize;
+
+use crate::determinism::{self, StateMap};
+
+#[derive(Debug, Deserialize, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+#[serde(rename_all = "snake_case")]
+pub enum TweenProperty {
+    X,
+    Y,
+    ScaleX,
+    ScaleY,
+    /// Degrees, like `rotation_deg`.
+    Rotation,
+    /// Multiplies the sprite's alpha; 1 when untweened.
+    Alpha,
+}
+
+impl TweenProperty {
+    pub fn parse(name: &str) -> Result<Self, String> {
+        match name {
+            "x" => Ok(Self::X),
+            "y" => Ok(Self::Y),
+            "scale_x" => Ok(Self::ScaleX),
+            "scale_y" => Ok(Self::ScaleY),
+            "rotation" => Ok(Self::Rotation),
+            "alpha" => Ok(Self::Alpha),
+            other => Err(format!(
+                "unknown tween property '{other}' (expected x, y, scale_x, scale_y, rotation or alpha)"
+            )),
+        }
+    }
+
+    fn get(self, props: &SpriteProps) -> f32 {
+        match self {
+            Self::X => props.x,
+            Self::Y => props.y,
+            Self::ScaleX => props.scale_x,
+            Self::ScaleY => props.scale_y,
+            Self::Rotation => props.rotation_deg,
+            Self::Alpha => props.alpha,
+        }
+    }
+
+    fn set(self, props: &mut SpriteProps, value: f32) {
+        let field = match self {
+            Self::X => &mut props.x,
+            Self::Y => &mut props.y,
+            Self::ScaleX => &mut props.scale_x,
+            Self::ScaleY => &mut props.scale_y,
+            Self::Rotation => &mut props.rotation_deg,
+            Self::Alpha => &mut props.alpha,
+        };
+        *field = value;
+    }
+}
+
+#[derive(Debug, Deserialize, Clone, Copy, Default, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum Easing {
+    #[default]
+    Linear,
+    EaseIn,
+    EaseOut,
+    EaseInOut,
+}
+
+impl Easing {
+    pub fn parse(name: &str) -> Result<Self, String> {
+        match name {
+            "linear" => Ok(Self::Linear),
+            "ease_in" => Ok(Self::EaseIn),
+            "ease_out" => Ok(Self::EaseOut),
+            "ease_in_out" => Ok(Self::EaseInOut),
+            other => Err(format!(
+                "unknown easing '{other}' (expected linear, ease_in, ease_out or ease_in_out)"
+            )),
+        }
+    }
+
+    /// Eased progress for `t` in 0..=1 (quadratic curves).
+    pub fn apply(self, t: f32) -> f32 {
+        match self {
+            Self::Linear => t,
+            Self::EaseIn => t * t,
+            Self::EaseOut => 1.0 - (1.0 - t) * (1.0 - t),
+            Self::EaseInOut if t < 0.5 => 2.0 * t * t,
+            Self::EaseInOut => 1.0 - (2.0 - 2.0 * t).powi(2) * 0.5,
+        }
+    }
+}
+
+/// What a tween does when it reaches its targets.
+#[derive(Debug, Deserialize, Clone, Copy, Default, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum Repeat {
+    /// Stop and hold the targets.
+    #[default]
+    Once,
+    /// Jump back to the start values and play again.
+    Loop,
+    /// Play back toward the start values, then forward again.
+    PingPong,
+}
+
+impl Repeat {
+    pub fn parse(name: &str) -> Result<Self, String> {
+        match name {
+            "once" => Ok(Self::Once),
+            "loop" => Ok(Self::Loop),
+            "ping_pong" => Ok(Self::PingPong),
+            other => Err(format!(
+                "unknown tween repeat '{other}' (expected once, loop or ping_pong)"
+            )),
+        }
+    }
+}
+
+/// `{ "to": { "y": 120.0, "alpha": 0.5 }, "duration": 2.0, "easing": "ease_in_out", "repeat": "ping_pong" }`.
+#[derive(Debug, Deserialize, Clone, PartialEq)]
+pub struct TweenSpec {
+    pub to: BTreeMap<TweenProperty, f32>,
+    /// Simulated seconds.
+    pub duration: f32,
+    #[serde(default)]
+    pub easing: Easing,
+    #[serde(default)]
+    pub repeat: Repeat,
+}
+
+impl TweenSpec {
+    pub fn validate(&self) -> Result<(), String> {
+        if !(self.duration > 0.0 && self.duration.is_finite()) {
+            return Err(format!("tween duration {} must be positive", self.duration));
+        }
+        if self.to.is_empty() {
+            return Err("tween needs at least one target in 'to'".to_string());
+        }
+        if let Some((property, value)) = self.to.iter().find(|(_, v)| !v.is_finite()) {
+            return Err(format!(
+                "tween target {property:?} = {value} must be finite"
+            ));
+        }
+        Ok(())
+    }
+}
+
+/// The tweenable properties of a sprite as drawn.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct SpriteProps {
+    pub x: f32,
+    pub y: f32,
+    pub scale_x: f32,
+    pub scale_y: f32,
+    pub rotation_deg: f32,
+    pub alpha: f32,
+}
+
+#[derive(Debug, Clone)]
+struct Track {
+    property: TweenProperty,
+    from: f32,
+    to: f32,
+    duration: f32,
+    elapsed: f32,
+    easing: Easing,
+    repeat: Repeat,
+}
+
+#[derive(Debug, Clone, Default)]
+struct SpriteTweens {
+    /// Current value of every property a tween has touched.
+    values: BTreeMap<TweenProperty, f32>,
+    tracks: Vec<Track>,
+}
+
+/// Running tweens and tweened values, by sprite id.
+#[derive(Debug, Clone, Default)]
+pub struct Tweens {
+    sprites: StateMap<String, SpriteTweens>,
+}
+
+impl Tweens {
+    /// Start `spec` on `sprite_id` from its current values; `base` is the
+    /// sprite's untweened properties.
+    pub fn start(&mut self, sprite_id: &str, base: SpriteProps, spec: &TweenSpec) {
+        let sprite = self.sprites.entry(sprite_id.to_string()).or_default();
+        for (&property, &to) in &spec.to {
+            let from = sprite
+                .values
+                .get(&property)
+                .copied()
+                .unwrap_or_else(|| property.get(&base));
+            sprite.values.insert(property, from);
+            sprite.tracks.retain(|track| track.property != property);
+            sprite.tracks.push(Track {
+                property,
+                from,
+                to,
+                duration: spec.duration,
+                elapsed: 0.0,
+                easing: spec.easing,
+                repeat: spec.repeat,
+            });
+        }
+    }
+
+    /// Advance every running tween by `dt` simulated seconds.
+    pub fn step(&mut self, dt: f32) {
+        determinism::assert_stable_order("tweens", self.sprites.keys());
+        for sprite in self.sprites.values_mut() {
+            sprite.tracks.retain_mut(|track| {
+                track.elapsed += dt;
+                let mut running = true;
+                if track.elapsed >= track.duration {
+                    match track.repeat {
+                        Repeat::Once => {
+                            track.elapsed = track.duration;
+                            running = false;
+                        }
+                        Repeat::Loop => track.elapsed %= track.duration,
+                        Repeat::PingPong => {
+                            track.elapsed %= track.duration;
+                            std::mem::swap(&mut track.from, &mut track.to);
+                        }
+                    }
+                }
+                let t = track.easing.apply(track.elapsed / track.duration);
+                let value = track.from + (track.to - track.from) * t;
+                sprite.values.insert(track.property, value);
+                running
+            });
+        }
+    }
+
+    /// `base` with the sprite's tweened values applied.
+    pub fn apply(&self, sprite_id: &str, base: SpriteProps) -> SpriteProps {
+        let mut props = base;
+        if let Some(sprite) = self.sprites.get(sprite_id) {
+            for (&property, &value) in &sprite.values {
+                property.set(&mut props, value);
+            }
+        }
+        props
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const DT: f32 = 0.25;
+
+    fn base() -> SpriteProps {
+        SpriteProps {
+            x: 0.0,
+            y: 10.0,
+            scale_x: 1.0,
+            scale_y: 1.0,
+            rotation_deg: 0.0,
+            alpha: 1.0,
+        }
+    }
+
+    fn spec(to: &[(TweenProperty, f32)], repeat: Repeat) -> TweenSpec {
+        TweenSpec {
+            to: to.iter().copied().collect(),
+            duration: 1.0,
+            easing: Easing::Linear,
+            repeat,
+        }
+    }
+
+    #[test]
+    fn tween_eases_to_targets_and_holds() {
+        let mut tweens = Tweens::default();
+        let targets = [(TweenProperty::X, 100.0), (TweenProperty::Alpha, 0.0)];
+        tweens.start("door", base(), &spec(&targets, Repeat::Once));
+        assert_eq!(tweens.apply("door", base()), base());
+
+        tweens.step(DT);
+        let props = tweens.apply("door", base());
+        assert_eq!((props.x, props.alpha, props.y), (25.0, 0.75, 10.0));
+
+        for _ in 0..8 {
+            tweens.step(DT);
+        }
+        let props = tweens.apply("door", base());
+        assert_eq!((props.x, props.alpha), (100.0, 0.0));
+        assert_eq!(tweens.apply("other", base()), base());
+
+        // A new tween starts from where the last one left off.
+        tweens.start(
+            "door",
+            base(),
+            &spec(&[(TweenProperty::X, 0.0)], Repeat::Once),
+        );
+        tweens.step(DT);
+        assert_eq!(tweens.apply("door", base()).x, 75.0);
+    }
+
+    #[test]
+    fn ping_pong_reverses_at_each_end() {
+        let mut tweens = Tweens::default();
+        tweens.start(
+            "platform",
+            base(),
+            &spec(&[(TweenProperty::Y, 50.0)], Repeat::PingPong),
+        );
+        let ys: Vec<f32> = (0..8)
+            .map(|_| {
+                tweens.step(DT);
+                tweens.apply("platform", base()).y
+            })
+            .collect();
+        assert_eq!(ys, [20.0, 30.0, 40.0, 50.0, 40.0, 30.0, 20.0, 10.0]);
+    }
+
+    #[test]
+    fn easing_curves_hit_their_ends() {
+        for easing in [
+            Easing::Linear,
+            Easing::EaseIn,
+            Easing::EaseOut,
+            Easing::EaseInOut,
+        ] {
+            assert_eq!(easing.apply(0.0), 0.0);
+            assert_eq!(easing.apply(1.0), 1.0);
+        }
+        assert!(Easing::EaseIn.apply(0.5) < 0.5);
+        assert!(Easing::EaseOut.apply(0.5) > 0.5);
+        assert_eq!(Easing::EaseInOut.apply(0.5), 0.5);
+        assert!(Easing::parse("bounce").is_err());
+    }
+}
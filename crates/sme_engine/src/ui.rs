@@ -0,0 +1,555 @@
+//! In-game UI widgets (panels, buttons, bars, icons) for shipping builds.
+//!
+//! egui stays debug-only. Game UI is authored as JSON in a fixed **virtual
+//! canvas** (e.g. 640x360) and scaled uniformly to the window with letterbox
+//! offsets, so layouts look the same at any resolution. Each widget is
+//! anchored to one of nine canvas points; its `x`/`y` offset is measured
+//! inward from that anchor (y grows downward, like screen space).
+//!
+//! This module is GPU-free: `layout()` turns the document into screen-space
+//! quads that the engine appends to its sprite mesh under a screen-space
+//! camera. Icons and button art reference atlas `sprite_id`s; everything else
+//! is a tinted solid quad. Widget `text` (and `label` widgets, which are only
+//! text) is drawn with the built-in pixel font (`ui_font`) as solid quads over
+//! the widget, `text_size` canvas units tall.
+//!
+//! Lua drives widgets indirectly: scripts queue `UiCommand`s through
+//! `engine.ui.*`, and button clicks call the Lua function named in `on_click`.
+
+use serde::Deserialize;
+use sme_core::migrate::SchemaMigrations;
+use std::collections::HashSet;
+use std::path::Path;
+
+use crate::ui_font;
+
+/// UI schema versions and the upgrade path to the current one.
+pub const UI_SCHEMA: SchemaMigrations = SchemaMigrations {
+    kind: "UI",
+    current: "0.1",
+    steps: &[],
+};
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct UiDocument {
+    #[allow(dead_code)]
+    pub version: String,
+    pub ui_id: String,
+    pub canvas: UiCanvas,
+    pub widgets: Vec<UiWidget>,
+}
+
+#[derive(Debug, Clone, Copy, Deserialize)]
+pub struct UiCanvas {
+    pub width: f32,
+    pub height: f32,
+}
+
+#[derive(Debug, Clone, Copy, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum WidgetKind {
+    Panel,
+    Button,
+    Bar,
+    Icon,
+    /// Text only.
+    Label,
+}
+
+#[derive(Debug, Clone, Copy, Deserialize, PartialEq, Eq, Default)]
+#[serde(rename_all = "snake_case")]
+pub enum TextAlign {
+    Left,
+    #[default]
+    Center,
+    Right,
+}
+
+#[derive(Debug, Clone, Copy, Deserialize, PartialEq, Eq, Default)]
+#[serde(rename_all = "snake_case")]
+pub enum Anchor {
+    #[default]
+    TopLeft,
+    Top,
+    TopRight,
+    Left,
+    Center,
+    Right,
+    BottomLeft,
+    Bottom,
+    BottomRight,
+}
+
+impl Anchor {
+    /// Normalized (0..1) position of the anchor on a rect, y down.
+    fn factors(self) -> (f32, f32) {
+        match self {
+            Self::TopLeft => (0.0, 0.0),
+            Self::Top => (0.5, 0.0),
+            Self::TopRight => (1.0, 0.0),
+            Self::Left => (0.0, 0.5),
+            Self::Center => (0.5, 0.5),
+            Self::Right => (1.0, 0.5),
+            Self::BottomLeft => (0.0, 1.0),
+            Self::Bottom => (0.5, 1.0),
+            Self::BottomRight => (1.0, 1.0),
+        }
+    }
+}
+
+fn default_true() -> bool {
+    true
+}
+
+fn default_white() -> [f32; 4] {
+    [1.0, 1.0, 1.0, 1.0]
+}
+
+fn default_text_size() -> f32 {
+    ui_font::GLYPH_HEIGHT as f32
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct UiWidget {
+    pub id: String,
+    pub kind: WidgetKind,
+    #[serde(default)]
+    pub anchor: Anchor,
+    /// Offset from the anchor in canvas units, pointing into the canvas.
+    #[serde(default)]
+    pub x: f32,
+    #[serde(default)]
+    pub y: f32,
+    pub width: f32,
+    pub height: f32,
+    #[serde(default = "default_true")]
+    pub visible: bool,
+    #[serde(default = "default_white")]
+    pub color: [f32; 4],
+    /// Button tint while the cursor is over it.
+    #[serde(default)]
+    pub hover_color: Option<[f32; 4]>,
+    /// Bar background; the fill uses `color`.
+    #[serde(default)]
+    pub back_color: Option<[f32; 4]>,
+    /// Bar fill fraction, clamped to 0..1.
+    #[serde(default)]
+    pub value: f32,
+    /// Atlas sprite for icons and button art.
+    #[serde(default)]
+    pub sprite_id: Option<String>,
+    /// Global Lua function called as `fn(widget_id)` when a button is clicked.
+    #[serde(default)]
+    pub on_click: Option<String>,
+    /// Text drawn over the widget, vertically centered.
+    #[serde(default)]
+    pub text: Option<String>,
+    #[serde(default = "default_white")]
+    pub text_color: [f32; 4],
+    /// Glyph height in canvas units; font pixels round to whole screen pixels.
+    #[serde(default = "default_text_size")]
+    pub text_size: f32,
+    #[serde(default)]
+    pub text_align: TextAlign,
+}
+
+pub fn load_ui_from_path(path: &Path) -> Result<UiDocument, String> {
+    let raw = sme_core::assets::read_to_string(path)
+        .map_err(|e| format!("Failed to read UI file {}: {e}", path.display()))?;
+    let mut value: serde_json::Value = serde_json::from_str(&raw)
+        .map_err(|e| format!("Failed to parse UI file {}: {e}", path.display()))?;
+    UI_SCHEMA.migrate_with_warning(&mut value, &path.display().to_string())?;
+    let document: UiDocument = serde_json::from_value(value)
+        .map_err(|e| format!("Failed to parse UI file {}: {e}", path.display()))?;
+    validate_ui(&document)?;
+    Ok(document)
+}
+
+fn validate_ui(document: &UiDocument) -> Result<(), String> {
+    if document.canvas.width <= 0.0 || document.canvas.height <= 0.0 {
+        return Err("UI validation failed: canvas width/height must be > 0".to_string());
+    }
+    let mut ids = HashSet::new();
+    for widget in &document.widgets {
+        if widget.id.is_empty() {
+            return Err("UI validation failed: widget id is empty".to_string());
+        }
+        if !ids.insert(widget.id.as_str()) {
+            return Err(format!(
+                "UI validation failed: duplicate widget id '{}'",
+                widget.id
+            ));
+        }
+        if widget.width <= 0.0 || widget.height <= 0.0 {
+            return Err(format!(
+                "UI validation failed: widget '{}' must have positive size",
+                widget.id
+            ));
+        }
+        if widget.text_size <= 0.0 {
+            return Err(format!(
+                "UI validation failed: widget '{}' must have a positive text_size",
+                widget.id
+            ));
+        }
+        if widget.kind == WidgetKind::Icon && widget.sprite_id.is_none() {
+            return Err(format!(
+                "UI validation failed: icon '{}' has no sprite_id",
+                widget.id
+            ));
+        }
+    }
+    Ok(())
+}
+
+/// Runtime change requested from Lua; variants mirror `engine.ui.set_*`.
+#[derive(Debug, Clone, PartialEq)]
+#[allow(clippy::enum_variant_names)]
+pub enum UiCommand {
+    SetVisible { id: String, visible: bool },
+    SetValue { id: String, value: f32 },
+    SetText { id: String, text: String },
+}
+
+/// Screen-space rectangle in physical pixels, origin top-left, y down.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct UiRect {
+    pub x: f32,
+    pub y: f32,
+    pub width: f32,
+    pub height: f32,
+}
+
+impl UiRect {
+    pub fn contains(&self, px: f32, py: f32) -> bool {
+        px >= self.x && px < self.x + self.width && py >= self.y && py < self.y + self.height
+    }
+}
+
+/// One quad to draw; `sprite_id: None` means a solid tinted quad.
+#[derive(Debug, Clone, PartialEq)]
+pub struct UiQuad {
+    pub rect: UiRect,
+    pub color: [f32; 4],
+    pub sprite_id: Option<String>,
+}
+
+/// A loaded UI document plus hover state.
+#[derive(Debug, Clone)]
+pub struct UiLayer {
+    pub document: UiDocument,
+    hovered: Option<usize>,
+}
+
+impl UiLayer {
+    pub fn new(document: UiDocument) -> Self {
+        Self {
+            document,
+            hovered: None,
+        }
+    }
+
+    /// Uniform canvas->screen scale and letterbox offset for a screen size.
+    fn canvas_transform(&self, screen: (u32, u32)) -> (f32, f32, f32) {
+        let canvas = self.document.canvas;
+        let scale = (screen.0 as f32 / canvas.width).min(screen.1 as f32 / canvas.height);
+        let offset_x = (screen.0 as f32 - canvas.width * scale) * 0.5;
+        let offset_y = (screen.1 as f32 - canvas.height * scale) * 0.5;
+        (scale, offset_x, offset_y)
+    }
+
+    pub fn widget_rect(&self, widget: &UiWidget, screen: (u32, u32)) -> UiRect {
+        let canvas = self.document.canvas;
+        let (fx, fy) = widget.anchor.factors();
+        // Offsets point inward, so right/bottom anchors subtract them.
+        let dir_x = if fx == 1.0 { -1.0 } else { 1.0 };
+        let dir_y = if fy == 1.0 { -1.0 } else { 1.0 };
+        let canvas_x = canvas.width * fx + widget.x * dir_x - widget.width * fx;
+        let canvas_y = canvas.height * fy + widget.y * dir_y - widget.height * fy;
+        let (scale, offset_x, offset_y) = self.canvas_transform(screen);
+        UiRect {
+            x: offset_x + canvas_x * scale,
+            y: offset_y + canvas_y * scale,
+            width: widget.width * scale,
+            height: widget.height * scale,
+        }
+    }
+
+    /// Update hover state from the cursor. Returns true when it changed.
+    pub fn update_hover(&mut self, cursor: (f32, f32), screen: (u32, u32)) -> bool {
+        let hovered = self.button_at(cursor, screen);
+        let changed = hovered != self.hovered;
+        self.hovered = hovered;
+        changed
+    }
+
+    /// Topmost visible button under `cursor` (later widgets draw on top).
+    fn button_at(&self, cursor: (f32, f32), screen: (u32, u32)) -> Option<usize> {
+        self.document
+            .widgets
+            .iter()
+            .enumerate()
+            .rev()
+            .find(|(_, w)| {
+                w.visible
+                    && w.kind == WidgetKind::Button
+                    && self.widget_rect(w, screen).contains(cursor.0, cursor.1)
+            })
+            .map(|(i, _)| i)
+    }
+
+    /// The clicked button's `(widget_id, on_click)` if the cursor is over one.
+    pub fn click(
+        &self,
+        cursor: (f32, f32),
+        screen: (u32, u32),
+    ) -> Option<(String, Option<String>)> {
+        let widget = &self.document.widgets[self.button_at(cursor, screen)?];
+        Some((widget.id.clone(), widget.on_click.clone()))
+    }
+
+    pub fn apply(&mut self, command: &UiCommand) {
+        let (id, apply): (&str, &dyn Fn(&mut UiWidget)) = match command {
+            UiCommand::SetVisible { id, visible } => (id, &|w| w.visible = *visible),
+            UiCommand::SetValue { id, value } => (id, &|w| w.value = value.clamp(0.0, 1.0)),
+            UiCommand::SetText { id, text } => (id, &|w| w.text = Some(text.clone())),
+        };
+        match self.document.widgets.iter_mut().find(|w| w.id == id) {
+            Some(widget) => apply(widget),
+            None => log::warn!("UI command for unknown widget '{}'", id),
+        }
+    }
+
+    /// Screen-space quads in draw order.
+    pub fn layout(&self, screen: (u32, u32)) -> Vec<UiQuad> {
+        let mut quads = Vec::new();
+        for (i, widget) in self.document.widgets.iter().enumerate() {
+            if !widget.visible {
+                continue;
+            }
+            let rect = self.widget_rect(widget, screen);
+            match widget.kind {
+                WidgetKind::Panel | WidgetKind::Icon => quads.push(UiQuad {
+                    rect,
+                    color: widget.color,
+                    sprite_id: widget.sprite_id.clone(),
+                }),
+                WidgetKind::Button => {
+                    let color = match widget.hover_color {
+                        Some(hover) if self.hovered == Some(i) => hover,
+                        _ => widget.color,
+                    };
+                    quads.push(UiQuad {
+                        rect,
+                        color,
+                        sprite_id: widget.sprite_id.clone(),
+                    });
+                }
+                WidgetKind::Label => {}
+                WidgetKind::Bar => {
+                    if let Some(back) = widget.back_color {
+                        quads.push(UiQuad {
+                            rect,
+                            color: back,
+                            sprite_id: None,
+                        });
+                    }
+                    let fill = widget.value.clamp(0.0, 1.0);
+                    if fill > 0.0 {
+                        quads.push(UiQuad {
+                            rect: UiRect {
+                                width: rect.width * fill,
+                                ..rect
+                            },
+                            color: widget.color,
+                            sprite_id: None,
+                        });
+                    }
+                }
+            }
+            if let Some(text) = &widget.text {
+                let scale = self.canvas_transform(screen).0;
+                push_text(&mut quads, text, widget, rect, scale);
+            }
+        }
+        quads
+    }
+
+    /// Atlas sprites the layout can reference, for texture preloading.
+    pub fn sprite_ids(&self) -> impl Iterator<Item = &str> {
+        self.document
+            .widgets
+            .iter()
+            .filter_map(|w| w.sprite_id.as_deref())
+    }
+}
+
+/// Quads of `widget`'s `text` inside `rect`, at `scale` screen pixels per
+/// canvas unit. Font pixels snap to whole screen pixels.
+fn push_text(quads: &mut Vec<UiQuad>, text: &str, widget: &UiWidget, rect: UiRect, scale: f32) {
+    let pixel = (widget.text_size * scale / ui_font::GLYPH_HEIGHT as f32)
+        .round()
+        .max(1.0);
+    let width = ui_font::text_width(text) as f32 * pixel;
+    let height = ui_font::GLYPH_HEIGHT as f32 * pixel;
+    let x = match widget.text_align {
+        TextAlign::Left => rect.x,
+        TextAlign::Center => rect.x + (rect.width - width) * 0.5,
+        TextAlign::Right => rect.x + rect.width - width,
+    }
+    .round();
+    let y = (rect.y + (rect.height - height) * 0.5).round();
+    for (run_x, run_y, run_width) in ui_font::text_runs(text) {
+        quads.push(UiQuad {
+            rect: UiRect {
+                x: x + run_x as f32 * pixel,
+                y: y + run_y as f32 * pixel,
+                width: run_width as f32 * pixel,
+                height: pixel,
+            },
+            color: widget.text_color,
+            sprite_id: None,
+        });
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::fs;
+    use std::time::{SystemTime, UNIX_EPOCH};
+
+    fn temp_file_path(name_hint: &str) -> std::path::PathBuf {
+        let nanos = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .expect("system time before unix epoch")
+            .as_nanos();
+        std::env::temp_dir().join(format!(
+            "sme_ui_test_{}_{}_{}.json",
+            name_hint,
+            std::process::id(),
+            nanos
+        ))
+    }
+
+    const SAMPLE_UI: &str = r#"
+    {
+      "version": "0.1",
+      "ui_id": "hud",
+      "canvas": { "width": 640, "height": 360 },
+      "widgets": [
+        { "id": "speed", "kind": "bar", "x": 16, "y": 16, "width": 100, "height": 10,
+          "value": 0.5, "color": [0, 1, 0, 1], "back_color": [0, 0, 0, 0.5] },
+        { "id": "menu", "kind": "button", "anchor": "bottom_right", "x": 10, "y": 10,
+          "width": 40, "height": 20, "hover_color": [1, 1, 0, 1], "on_click": "on_menu" }
+      ]
+    }
+    "#;
+
+    fn load_sample() -> UiLayer {
+        let path = temp_file_path("sample");
+        fs::write(&path, SAMPLE_UI).expect("write temp file");
+        let document = load_ui_from_path(&path).expect("ui should load");
+        let _ = fs::remove_file(path);
+        UiLayer::new(document)
+    }
+
+    #[test]
+    fn anchors_scale_with_letterboxing() {
+        let ui = load_sample();
+        // 2x scale with 40px horizontal bars: 1360x720 vs a 640x360 canvas.
+        let screen = (1360, 720);
+        let bar = ui.widget_rect(&ui.document.widgets[0], screen);
+        assert_eq!(
+            bar,
+            UiRect {
+                x: 40.0 + 32.0,
+                y: 32.0,
+                width: 200.0,
+                height: 20.0
+            }
+        );
+        let button = ui.widget_rect(&ui.document.widgets[1], screen);
+        assert_eq!(button.x, 40.0 + (640.0 - 10.0 - 40.0) * 2.0);
+        assert_eq!(button.y, (360.0 - 10.0 - 20.0) * 2.0);
+    }
+
+    #[test]
+    fn bar_fill_and_commands() {
+        let mut ui = load_sample();
+        let screen = (640, 360);
+        let quads = ui.layout(screen);
+        assert_eq!(quads.len(), 3, "bar back + bar fill + button");
+        assert_eq!(quads[1].rect.width, 50.0);
+
+        ui.apply(&UiCommand::SetValue {
+            id: "speed".to_string(),
+            value: 2.0,
+        });
+        assert_eq!(ui.layout(screen)[1].rect.width, 100.0, "value clamps to 1");
+
+        ui.apply(&UiCommand::SetVisible {
+            id: "speed".to_string(),
+            visible: false,
+        });
+        assert_eq!(ui.layout(screen).len(), 1);
+    }
+
+    #[test]
+    fn hover_and_click_hit_buttons_only() {
+        let mut ui = load_sample();
+        let screen = (640, 360);
+        let inside = (600.0, 340.0);
+        assert!(ui.update_hover(inside, screen));
+        assert_eq!(ui.layout(screen)[2].color, [1.0, 1.0, 0.0, 1.0]);
+        assert_eq!(
+            ui.click(inside, screen),
+            Some(("menu".to_string(), Some("on_menu".to_string())))
+        );
+        assert_eq!(
+            ui.click((20.0, 20.0), screen),
+            None,
+            "bars are not clickable"
+        );
+    }
+
+    #[test]
+    fn text_draws_as_pixel_quads_over_its_widget() {
+        let mut ui = load_sample();
+        ui.apply(&UiCommand::SetText {
+            id: "menu".to_string(),
+            text: "-".to_string(),
+        });
+        // 2x canvas scale: 14px glyphs, 2px font pixels, centered in the button.
+        let quads = ui.layout((1280, 720));
+        let button = quads[2].rect;
+        assert_eq!(quads.len(), 4, "bar back + fill + button + one run of '-'");
+        assert_eq!(
+            quads[3].rect,
+            UiRect {
+                x: button.x + (80.0 - 10.0) * 0.5,
+                y: button.y + (40.0 - 14.0) * 0.5 + 3.0 * 2.0,
+                width: 10.0,
+                height: 2.0
+            }
+        );
+        assert_eq!(quads[3].color, [1.0, 1.0, 1.0, 1.0]);
+    }
+
+    #[test]
+    fn rejects_duplicate_ids() {
+        let path = temp_file_path("dup");
+        fs::write(
+            &path,
+            r#"{ "version": "0.1", "ui_id": "x", "canvas": { "width": 10, "height": 10 },
+                 "widgets": [
+                   { "id": "a", "kind": "panel", "width": 1, "height": 1 },
+                   { "id": "a", "kind": "panel", "width": 1, "height": 1 }
+                 ] }"#,
+        )
+        .expect("write temp file");
+        let err = load_ui_from_path(&path).expect_err("duplicate ids should fail");
+        assert!(err.contains("duplicate widget id 'a'"));
+        let _ = fs::remove_file(path);
+    }
+}
@@ -0,0 +1,156 @@
+//! Unit conventions of imported content.
+//!
+//! The engine's world space is one unit per pixel of sprite art. Content
+//! exported from tools that measure in tiles or meters says so with a scene
+//! `units` block, `{ "pixels_per_unit": 16 }`, and is converted to pixels
+//! while it loads: sprite positions, tween targets, the camera start and
+//! bounds, and the tiled background origin in the scene, and the cell size and
+//! origin of the collision grid loaded with it. The camera, collision and
+//! mesh builder then only ever see pixels.
+//!
+//! Sprite scale, rotation and camera zoom are unitless and left alone; sprites
+//! are always drawn at their pixel size.
+//!
+//! World space is also y-up. Content from tools that count y downward adds
+//! `"y_axis": "down"`: its points are mirrored across the x axis (scene
+//! `y = 100` lands at world `y = -100`), rotations turn clockwise, and the
+//! collision grid's rows count down from its top-left corner. Sprites still
+//! stand upright, and their pivots are still measured from the bottom-left
+//! of the drawn image.
+
+use serde::Deserialize;
+
+#[derive(Debug, Deserialize, Clone, Copy, PartialEq)]
+#[serde(deny_unknown_fields)]
+pub struct SceneUnits {
+    #[serde(default = "default_pixels_per_unit")]
+    pub pixels_per_unit: f32,
+    #[serde(default)]
+    pub y_axis: YAxis,
+}
+
+/// Which way content y grows.
+#[derive(Debug, Deserialize, Clone, Copy, Default, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum YAxis {
+    #[default]
+    Up,
+    Down,
+}
+
+impl Default for SceneUnits {
+    fn default() -> Self {
+        Self {
+            pixels_per_unit: default_pixels_per_unit(),
+            y_axis: YAxis::Up,
+        }
+    }
+}
+
+impl SceneUnits {
+    pub fn validate(&self) -> Result<(), String> {
+        if !(self.pixels_per_unit > 0.0 && self.pixels_per_unit.is_finite()) {
+            return Err(format!(
+                "units pixels_per_unit {} must be positive",
+                self.pixels_per_unit
+            ));
+        }
+        Ok(())
+    }
+
+    pub fn is_pixels(&self) -> bool {
+        *self == Self::default()
+    }
+
+    /// A distance in content units, in pixels.
+    pub fn length(&self, value: f32) -> f32 {
+        value * self.pixels_per_unit
+    }
+
+    pub fn y_down(&self) -> bool {
+        self.y_axis == YAxis::Down
+    }
+
+    /// A y coordinate in content units, in world space.
+    pub fn y(&self, value: f32) -> f32 {
+        let y = self.length(value);
+        if self.y_down() {
+            -y
+        } else {
+            y
+        }
+    }
+
+    /// A point in content units, in world space.
+    pub fn point(&self, x: f32, y: f32) -> (f32, f32) {
+        (self.length(x), self.y(y))
+    }
+
+    /// Degrees counterclockwise in world space.
+    pub fn rotation(&self, degrees: f32) -> f32 {
+        if self.y_down() {
+            -degrees
+        } else {
+            degrees
+        }
+    }
+
+    /// A grid distance in content units, in whole pixels; `what` names it
+    /// when it doesn't come out whole.
+    pub fn grid_length(&self, value: i32, what: &str) -> Result<i32, String> {
+        let pixels = self.length(value as f32);
+        if pixels.fract() != 0.0 || pixels.abs() > i32::MAX as f32 {
+            return Err(format!(
+                "{what} {value} at {} pixels per unit is not a whole number of pixels",
+                self.pixels_per_unit
+            ));
+        }
+        Ok(pixels as i32)
+    }
+}
+
+const fn default_pixels_per_unit() -> f32 {
+    1.0
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn units_scale_points_and_grid_lengths_to_pixels() {
+        let units = SceneUnits {
+            pixels_per_unit: 16.0,
+            y_axis: YAxis::Up,
+        };
+        units.validate().expect("valid");
+        assert!(!units.is_pixels());
+        assert_eq!(units.point(2.5, -1.0), (40.0, -16.0));
+        assert_eq!(units.grid_length(3, "cell_size"), Ok(48));
+
+        let units = SceneUnits {
+            pixels_per_unit: 2.5,
+            y_axis: YAxis::Up,
+        };
+        let err = units.grid_length(3, "cell_size").expect_err("7.5 pixels");
+        assert!(err.contains("cell_size 3"), "{err}");
+
+        let units = SceneUnits {
+            pixels_per_unit: 0.0,
+            y_axis: YAxis::Up,
+        };
+        assert!(units.validate().is_err());
+    }
+
+    #[test]
+    fn y_down_units_mirror_points_and_rotations() {
+        let units = SceneUnits {
+            pixels_per_unit: 2.0,
+            y_axis: YAxis::Down,
+        };
+        assert!(!units.is_pixels());
+        assert_eq!(units.point(3.0, 10.0), (6.0, -20.0));
+        assert_eq!(units.rotation(30.0), -30.0);
+        assert_eq!(units.length(10.0), 20.0);
+    }
+}
@@ -0,0 +1,228 @@
+//! Ambient weather layers: rain, snow and fog declared by the scene.
+//!
+//! Each entry of a scene's `ambient` list becomes an `AmbientEmitter` over a
+//! `ParticleSystem`. Emitters keep their particles around the camera view
+//! rather than at fixed world points: every step wraps particles that left
+//! the view (plus a margin) back in on the opposite side and tops the
+//! population up anywhere inside it, so the effect covers the screen however
+//! the camera moves. The population is the kind's base count times
+//! `density`, scaled by the tier's `ambient_density`.
+//!
+//! Weather is visual only. It advances by the simulated time of each frame
+//! but outside the fixed step, from its own random stream, so it never
+//! touches gameplay state or replays.
+
+use glam::Vec2;
+use serde::Deserialize;
+use sme_core::rng::DeterministicRng;
+use sme_core::tier::FidelityTier;
+
+use crate::debug_draw::segment_quad;
+use crate::particles::{Particle, ParticleSystem};
+
+/// Upper bound on `AmbientConfig::density`.
+pub const MAX_AMBIENT_DENSITY: f32 = 4.0;
+
+/// World units kept around the view so particles wrap off-screen.
+const VIEW_MARGIN: f32 = 64.0;
+
+#[derive(Debug, Deserialize, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum AmbientKind {
+    Rain,
+    Snow,
+    Fog,
+}
+
+impl AmbientKind {
+    /// Particles at density 1 on Tier 2.
+    fn base_count(self) -> usize {
+        match self {
+            Self::Rain => 240,
+            Self::Snow => 160,
+            Self::Fog => 10,
+        }
+    }
+}
+
+/// `{ "kind": "rain" | "snow" | "fog", "density": 1.0, "wind": 0.0 }`.
+#[derive(Debug, Deserialize, Clone, Copy, PartialEq)]
+pub struct AmbientConfig {
+    pub kind: AmbientKind,
+    /// Multiplier on the kind's base particle count.
+    #[serde(default = "default_density")]
+    pub density: f32,
+    /// Horizontal drift in world units per second; positive blows right.
+    #[serde(default)]
+    pub wind: f32,
+}
+
+impl AmbientConfig {
+    pub fn validate(&self) -> Result<(), String> {
+        if !(0.0..=MAX_AMBIENT_DENSITY).contains(&self.density) {
+            return Err(format!(
+                "ambient density {} must be within 0.0..={MAX_AMBIENT_DENSITY}",
+                self.density
+            ));
+        }
+        if !self.wind.is_finite() {
+            return Err(format!("ambient wind {} must be finite", self.wind));
+        }
+        Ok(())
+    }
+}
+
+const fn default_density() -> f32 {
+    1.0
+}
+
+pub struct AmbientEmitter {
+    config: AmbientConfig,
+    particles: ParticleSystem,
+    rng: DeterministicRng,
+}
+
+impl AmbientEmitter {
+    pub fn new(config: AmbientConfig, seed: u64) -> Self {
+        let capacity = (config.kind.base_count() as f32 * config.density).round() as usize;
+        Self {
+            config,
+            particles: ParticleSystem::new(capacity),
+            rng: DeterministicRng::new(seed),
+        }
+    }
+
+    /// Particles kept alive on `tier`.
+    pub fn target_count(&self, tier: FidelityTier) -> usize {
+        (self.particles.capacity() as f32 * tier.ambient_density()).round() as usize
+    }
+
+    /// Advance by `dt` seconds and refit the particles to the view rect
+    /// `view_min..view_max` (world units).
+    pub fn step(&mut self, dt: f32, view_min: Vec2, view_max: Vec2, tier: FidelityTier) {
+        if self.config.kind == AmbientKind::Snow {
+            for particle in self.particles.iter_mut() {
+                let sway = ((particle.age + particle.seed * 10.0) * 1.7).sin() * 18.0;
+                particle.velocity.x = self.config.wind * 0.6 + sway;
+            }
+        }
+        self.particles.step(dt);
+
+        let target = self.target_count(tier);
+        self.particles.truncate(target);
+        let min = view_min - Vec2::splat(VIEW_MARGIN);
+        let span = (view_max - view_min + Vec2::splat(VIEW_MARGIN * 2.0)).max(Vec2::ONE);
+        for particle in self.particles.iter_mut() {
+            particle.position = min + (particle.position - min).rem_euclid(span);
+        }
+        while self.particles.len() < target {
+            let position = min + span * Vec2::new(self.random(), self.random());
+            let particle = self.make_particle(position);
+            self.particles.spawn(particle);
+        }
+    }
+
+    /// Corners and color of each particle's quad. Rain is a streak along
+    /// its velocity.
+    pub fn quads(&self) -> impl Iterator<Item = ([Vec2; 4], [f32; 4])> + '_ {
+        self.particles.iter().map(|p| {
+            let corners = match self.config.kind {
+                AmbientKind::Rain => {
+                    let tail = p.position - p.velocity.normalize_or_zero() * p.size.y;
+                    segment_quad(p.position, tail, p.size.x)
+                }
+                AmbientKind::Snow | AmbientKind::Fog => {
+                    let half = p.size * 0.5;
+                    [
+                        p.position - half,
+                        p.position + Vec2::new(half.x, -half.y),
+                        p.position + half,
+                        p.position + Vec2::new(-half.x, half.y),
+                    ]
+                }
+            };
+            (corners, p.color)
+        })
+    }
+
+    fn random(&mut self) -> f32 {
+        self.rng.next_f64() as f32
+    }
+
+    fn make_particle(&mut self, position: Vec2) -> Particle {
+        let wind = self.config.wind;
+        let seed = self.random();
+        let (velocity, size, color) = match self.config.kind {
+            AmbientKind::Rain => (
+                Vec2::new(wind + (seed - 0.5) * 20.0, -(520.0 + seed * 160.0)),
+                Vec2::new(1.5, 14.0),
+                [0.72, 0.78, 0.92, 0.45],
+            ),
+            AmbientKind::Snow => (
+                Vec2::new(wind * 0.6, -(40.0 + seed * 40.0)),
+                Vec2::splat(2.0 + seed * 2.0),
+                [1.0, 1.0, 1.0, 0.85],
+            ),
+            AmbientKind::Fog => (
+                Vec2::new(wind * 0.3 + (seed - 0.5) * 16.0, 0.0),
+                Vec2::new(220.0 + seed * 120.0, 70.0 + seed * 40.0),
+                [0.86, 0.89, 0.93, 0.07],
+            ),
+        };
+        Particle {
+            position,
+            velocity,
+            age: 0.0,
+            lifetime: f32::INFINITY,
+            size,
+            color,
+            seed,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn rain(density: f32) -> AmbientEmitter {
+        AmbientEmitter::new(
+            AmbientConfig {
+                kind: AmbientKind::Rain,
+                density,
+                wind: 30.0,
+            },
+            7,
+        )
+    }
+
+    #[test]
+    fn emitter_fills_the_view_and_follows_the_camera() {
+        let mut emitter = rain(0.5);
+        let half = Vec2::new(320.0, 180.0);
+        emitter.step(0.0, -half, half, FidelityTier::Tier2);
+        assert_eq!(emitter.quads().count(), 120);
+        emitter.step(0.0, -half, half, FidelityTier::Tier0);
+        assert_eq!(
+            emitter.quads().count(),
+            emitter.target_count(FidelityTier::Tier0)
+        );
+        assert!(emitter.target_count(FidelityTier::Tier0) < 120);
+
+        // A camera jump leaves no particle behind.
+        let far = Vec2::new(5000.0, -2000.0);
+        emitter.step(1.0 / 60.0, far - half, far + half, FidelityTier::Tier0);
+        let margin = Vec2::splat(VIEW_MARGIN + 20.0);
+        for (corners, _) in emitter.quads() {
+            let p = corners[0];
+            assert!(p.cmpge(far - half - margin).all() && p.cmple(far + half + margin).all());
+        }
+    }
+
+    #[test]
+    fn config_checks_density() {
+        assert!(rain(1.0).config.validate().is_ok());
+        let err = rain(5.0).config.validate().unwrap_err();
+        assert!(err.contains("density 5"), "{err}");
+    }
+}
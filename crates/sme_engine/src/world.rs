@@ -0,0 +1,462 @@
+//! Entity/component store for the simulation, and the order its systems run
+//! in each fixed step.
+//!
+//! An `Entity` is a slot index plus a generation, so a stale handle to a
+//! despawned entity never aliases whatever reuses the slot. Each component
+//! type lives in its own `ComponentStore`, a sparse set: components are packed
+//! in a dense `Vec` in insertion order, which is what systems iterate, and a
+//! sparse table maps entity slots to dense indices for O(1) lookup. Iteration
+//! order only depends on the sequence of inserts and removals, so replays and
+//! rewinds visit entities in the same order (unlike the `HashMap`s this
+//! replaces).
+//!
+//! Entities carry a unique name -- the scene sprite id, `"player"`, or a
+//! spawned actor's id -- because scripts and data files address them by name.
+//! The player is created with the world and always has a body; animated scene
+//! sprites are entities with only an animation. Spawned actors still keep
+//! their sprite and body in `actors::SpawnedActors`; only their animations
+//! live here.
+//!
+//! Animations of sprites on screen-space layers carry a `ui_clock` marker:
+//! they advance every frame with the UI clock (`tick_ui_animations`) instead
+//! of in fixed steps, so menus keep animating while the simulation is paused.
+//! They raise no `animation_finished` events and stay out of the
+//! determinism hash, since wall time drives them.
+//!
+//! `FIXED_STEP_ORDER` lists the systems of one fixed step; `App::fixed_step`
+//! runs them in exactly that order.
+
+use std::collections::HashMap;
+
+use sme_core::animation::AnimationState;
+
+use crate::animation::AnimationRegistry;
+use crate::controller::CharacterController;
+#[cfg(any(test, feature = "determinism-audit"))]
+use crate::determinism::StateHasher;
+
+/// Name of the entity every world starts with.
+pub const PLAYER_ENTITY: &str = "player";
+
+/// One simulation step's systems.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum FixedSystem {
+    /// Publish actors and input to Lua, run `on_update` (or the Rust fallback
+    /// controller), apply its intents, then let replay input override it.
+    Script,
+    /// Move the player's body with the controller input.
+    Player,
+    /// Move spawned actors' bodies.
+    Bodies,
+    /// Report the contact edges bodies gained as collision events.
+    Contacts,
+    Projectiles,
+    Pickups,
+    /// Hard landings add screen shake; shake decays.
+    Shake,
+    /// Advance sprite property tweens.
+    Tweens,
+    /// Advance the scene transition's fade (see `transition`).
+    Transition,
+    Trails,
+    Animations,
+    /// Follow the player unless the free camera is active.
+    Camera,
+    /// Game systems added with `Engine::add_system`, in the order added.
+    Custom,
+    /// Hand the step's events to the script's handlers.
+    Events,
+}
+
+pub const FIXED_STEP_ORDER: [FixedSystem; 14] = [
+    FixedSystem::Script,
+    FixedSystem::Player,
+    FixedSystem::Bodies,
+    FixedSystem::Contacts,
+    FixedSystem::Projectiles,
+    FixedSystem::Pickups,
+    FixedSystem::Shake,
+    FixedSystem::Tweens,
+    FixedSystem::Transition,
+    FixedSystem::Trails,
+    FixedSystem::Animations,
+    FixedSystem::Camera,
+    FixedSystem::Custom,
+    FixedSystem::Events,
+];
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct Entity {
+    index: u32,
+    generation: u32,
+}
+
+/// Sparse set of `T` components.
+#[derive(Debug, Clone)]
+pub struct ComponentStore<T> {
+    dense: Vec<T>,
+    owners: Vec<Entity>,
+    /// Dense index per entity slot.
+    sparse: Vec<Option<usize>>,
+}
+
+impl<T> Default for ComponentStore<T> {
+    fn default() -> Self {
+        Self {
+            dense: Vec::new(),
+            owners: Vec::new(),
+            sparse: Vec::new(),
+        }
+    }
+}
+
+impl<T> ComponentStore<T> {
+    fn slot(&self, entity: Entity) -> Option<usize> {
+        let index = (*self.sparse.get(entity.index as usize)?)?;
+        (self.owners[index] == entity).then_some(index)
+    }
+
+    /// Add or replace `entity`'s component; returns the one it replaced.
+    pub fn insert(&mut self, entity: Entity, value: T) -> Option<T> {
+        if let Some(index) = self.slot(entity) {
+            return Some(std::mem::replace(&mut self.dense[index], value));
+        }
+        let slot = entity.index as usize;
+        if self.sparse.len() <= slot {
+            self.sparse.resize(slot + 1, None);
+        }
+        self.sparse[slot] = Some(self.dense.len());
+        self.dense.push(value);
+        self.owners.push(entity);
+        None
+    }
+
+    /// Remove `entity`'s component. The last component moves into its place.
+    pub fn remove(&mut self, entity: Entity) -> Option<T> {
+        let index = self.slot(entity)?;
+        self.sparse[entity.index as usize] = None;
+        self.owners.swap_remove(index);
+        let value = self.dense.swap_remove(index);
+        if let Some(moved) = self.owners.get(index) {
+            self.sparse[moved.index as usize] = Some(index);
+        }
+        Some(value)
+    }
+
+    pub fn get(&self, entity: Entity) -> Option<&T> {
+        self.slot(entity).map(|index| &self.dense[index])
+    }
+
+    pub fn get_mut(&mut self, entity: Entity) -> Option<&mut T> {
+        self.slot(entity).map(|index| &mut self.dense[index])
+    }
+
+    pub fn contains(&self, entity: Entity) -> bool {
+        self.slot(entity).is_some()
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = (Entity, &T)> {
+        self.owners.iter().copied().zip(&self.dense)
+    }
+
+    pub fn iter_mut(&mut self) -> impl Iterator<Item = (Entity, &mut T)> {
+        self.owners.iter().copied().zip(&mut self.dense)
+    }
+
+    pub fn len(&self) -> usize {
+        self.dense.len()
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct World {
+    generations: Vec<u32>,
+    free: Vec<u32>,
+    names: ComponentStore<String>,
+    by_name: HashMap<String, Entity>,
+    player: Entity,
+    pub bodies: ComponentStore<CharacterController>,
+    pub animations: ComponentStore<AnimationState>,
+    /// Entities whose animation runs on the UI clock.
+    pub ui_clock: ComponentStore<()>,
+}
+
+impl World {
+    pub fn new(player_body: CharacterController) -> Self {
+        let mut world = Self {
+            generations: Vec::new(),
+            free: Vec::new(),
+            names: ComponentStore::default(),
+            by_name: HashMap::new(),
+            player: Entity {
+                index: 0,
+                generation: 0,
+            },
+            bodies: ComponentStore::default(),
+            animations: ComponentStore::default(),
+            ui_clock: ComponentStore::default(),
+        };
+        world.player = world.spawn(PLAYER_ENTITY).expect("empty world");
+        world.bodies.insert(world.player, player_body);
+        world
+    }
+
+    /// Create an entity named `name`; names are unique.
+    pub fn spawn(&mut self, name: &str) -> Result<Entity, String> {
+        if self.by_name.contains_key(name) {
+            return Err(format!("entity '{name}' already exists"));
+        }
+        let entity = match self.free.pop() {
+            Some(index) => Entity {
+                index,
+                generation: self.generations[index as usize],
+            },
+            None => {
+                self.generations.push(0);
+                Entity {
+                    index: self.generations.len() as u32 - 1,
+                    generation: 0,
+                }
+            }
+        };
+        self.names.insert(entity, name.to_string());
+        self.by_name.insert(name.to_string(), entity);
+        Ok(entity)
+    }
+
+    /// Remove an entity and all its components. The player cannot be
+    /// despawned.
+    pub fn despawn(&mut self, entity: Entity) -> bool {
+        if entity == self.player {
+            return false;
+        }
+        let Some(name) = self.names.remove(entity) else {
+            return false;
+        };
+        self.by_name.remove(&name);
+        self.bodies.remove(entity);
+        self.animations.remove(entity);
+        self.ui_clock.remove(entity);
+        self.generations[entity.index as usize] += 1;
+        self.free.push(entity.index);
+        true
+    }
+
+    pub fn find(&self, name: &str) -> Option<Entity> {
+        self.by_name.get(name).copied()
+    }
+
+    pub fn name(&self, entity: Entity) -> Option<&str> {
+        self.names.get(entity).map(String::as_str)
+    }
+
+    pub fn player_body(&self) -> &CharacterController {
+        self.bodies.get(self.player).expect("player has a body")
+    }
+
+    pub fn player_body_mut(&mut self) -> &mut CharacterController {
+        self.bodies.get_mut(self.player).expect("player has a body")
+    }
+
+    /// Animation of the entity named `name`.
+    pub fn animation(&self, name: &str) -> Option<&AnimationState> {
+        self.animations.get(self.find(name)?)
+    }
+
+    /// Give `name` an animation, creating the entity if needed.
+    pub fn set_animation(&mut self, name: &str, state: AnimationState) {
+        let entity = match self.find(name) {
+            Some(entity) => entity,
+            None => self.spawn(name).expect("name is free"),
+        };
+        self.animations.insert(entity, state);
+    }
+
+    /// Run `name`'s animation on the UI clock rather than in fixed steps.
+    pub fn set_ui_clock(&mut self, name: &str) {
+        if let Some(entity) = self.find(name) {
+            self.ui_clock.insert(entity, ());
+        }
+    }
+
+    /// Stop `name`'s animation; an entity left with no components goes too.
+    pub fn remove_animation(&mut self, name: &str) -> Option<AnimationState> {
+        let entity = self.find(name)?;
+        let state = self.animations.remove(entity);
+        self.ui_clock.remove(entity);
+        if !self.bodies.contains(entity) {
+            self.despawn(entity);
+        }
+        state
+    }
+
+    /// Replace every animation, e.g. after a scene reload.
+    pub fn reset_animations(&mut self, states: impl IntoIterator<Item = (String, AnimationState)>) {
+        let animated: Vec<Entity> = self.animations.iter().map(|(entity, _)| entity).collect();
+        for entity in animated {
+            if let Some(name) = self.name(entity).map(str::to_string) {
+                self.remove_animation(&name);
+            }
+        }
+        for (name, state) in states {
+            self.set_animation(&name, state);
+        }
+    }
+
+    /// Hash of every body and animation, in store order, for the
+    /// determinism audit (see `determinism`).
+    #[cfg(any(test, feature = "determinism-audit"))]
+    pub fn state_hash(&self) -> u64 {
+        use std::hash::{Hash, Hasher};
+
+        let mut hasher = StateHasher::new();
+        for (entity, body) in self.bodies.iter() {
+            self.names.get(entity).hash(&mut hasher);
+            hasher.write_body(body);
+        }
+        for (entity, state) in self.animations.iter() {
+            if self.ui_clock.contains(entity) {
+                continue;
+            }
+            self.names.get(entity).hash(&mut hasher);
+            state.source_id.as_str().hash(&mut hasher);
+            state.clip_name.as_str().hash(&mut hasher);
+            hasher.write_usize(state.frame_index);
+            hasher.write_u64(state.elapsed_us);
+            hasher.write_u8(state.finished as u8);
+        }
+        hasher.finish()
+    }
+
+    /// Advance every fixed-step animation by `dt_us` and return
+    /// `(name, clip)` for each that finished this step, in store order.
+    pub fn tick_animations(
+        &mut self,
+        registry: &AnimationRegistry,
+        dt_us: u64,
+    ) -> Vec<(String, String)> {
+        let mut finished = Vec::new();
+        for (entity, state) in self.animations.iter_mut() {
+            if self.ui_clock.contains(entity) {
+                continue;
+            }
+            let name = self.names.get(entity).map_or("", String::as_str);
+            let Some(clip) = registry.resolve_clip(Some(state.source_id), state.clip_name) else {
+                log::warn!(
+                    "Sprite '{}' references unknown animation clip '{}'",
+                    name,
+                    state.clip_name
+                );
+                continue;
+            };
+            let was_finished = state.finished;
+            state.tick(dt_us, clip);
+            if state.finished && !was_finished {
+                finished.push((name.to_string(), state.clip_name.to_string()));
+            }
+        }
+        finished
+    }
+
+    /// Advance every UI-clock animation by `dt_us`; returns whether any of
+    /// them changed frame.
+    pub fn tick_ui_animations(&mut self, registry: &AnimationRegistry, dt_us: u64) -> bool {
+        let mut changed = false;
+        for (entity, state) in self.animations.iter_mut() {
+            if !self.ui_clock.contains(entity) {
+                continue;
+            }
+            let Some(clip) = registry.resolve_clip(Some(state.source_id), state.clip_name) else {
+                continue;
+            };
+            let frame = state.frame_index;
+            state.tick(dt_us, clip);
+            changed |= state.frame_index != frame;
+        }
+        changed
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::collision::Aabb;
+
+    fn body() -> CharacterController {
+        CharacterController::new(Aabb {
+            center_x: 0.0,
+            center_y: 0.0,
+            half_w: 8.0,
+            half_h: 8.0,
+        })
+    }
+
+    #[test]
+    fn stale_handles_do_not_alias_reused_slots() {
+        let mut world = World::new(body());
+        let bat = world.spawn("bat").expect("bat");
+        world.bodies.insert(bat, body());
+        assert!(world.spawn("bat").is_err());
+        assert!(world.despawn(bat));
+        assert!(!world.despawn(world.player), "player stays");
+
+        let owl = world.spawn("owl").expect("owl");
+        assert_eq!(world.name(owl), Some("owl"));
+        assert!(world.bodies.get(bat).is_none());
+        assert!(world.name(bat).is_none());
+        assert!(world.find("bat").is_none());
+    }
+
+    #[test]
+    fn removal_keeps_the_store_packed() {
+        let mut world = World::new(body());
+        let names = ["a", "b", "c"];
+        let entities: Vec<Entity> = names.iter().map(|n| world.spawn(n).unwrap()).collect();
+        let mut store = ComponentStore::default();
+        for (entity, value) in entities.iter().zip([1, 2, 3]) {
+            store.insert(*entity, value);
+        }
+        assert_eq!(store.remove(entities[0]), Some(1));
+        let order: Vec<i32> = store.iter().map(|(_, v)| *v).collect();
+        assert_eq!(order, [3, 2]);
+        assert_eq!(store.get(entities[2]), Some(&3));
+        assert_eq!(store.insert(entities[1], 20), Some(2));
+        assert_eq!(store.len(), 2);
+    }
+
+    #[test]
+    fn animation_only_entities_go_with_their_animation() {
+        let mut world = World::new(body());
+        world.set_animation("torch", AnimationState::new("fire", "flicker"));
+        world.set_animation(PLAYER_ENTITY, AnimationState::new("hero", "run"));
+        assert_eq!(world.animations.len(), 2);
+
+        let before = world.state_hash();
+        world.remove_animation("torch");
+        assert_ne!(world.state_hash(), before);
+        assert!(world.find("torch").is_none());
+        world.remove_animation(PLAYER_ENTITY);
+        assert_eq!(world.find(PLAYER_ENTITY), Some(world.player));
+
+        world.reset_animations([("torch".to_string(), AnimationState::new("fire", "out"))]);
+        assert_eq!(
+            world.animation("torch").map(|s| s.clip_name.as_str()),
+            Some("out")
+        );
+    }
+
+    #[test]
+    fn ui_clock_animations_stay_out_of_the_simulation() {
+        let mut world = World::new(body());
+        world.set_animation("menu_arrow", AnimationState::new("ui", "bob"));
+        world.set_ui_clock("menu_arrow");
+        let before = world.state_hash();
+        world.set_animation("menu_arrow", AnimationState::new("ui", "blink"));
+        assert_eq!(world.state_hash(), before);
+
+        world.remove_animation("menu_arrow");
+        world.set_animation("menu_arrow", AnimationState::new("ui", "bob"));
+        assert_eq!(world.ui_clock.len(), 0, "marker removed");
+        assert_ne!(world.state_hash(), before);
+    }
+}
@@ -0,0 +1,125 @@
+use sme_core::pack::{normalize_pack_path, PackArchive, PackWriter, PACK_EXTENSION};
+use std::fs;
+use std::path::{Path, PathBuf};
+
+fn usage() -> String {
+    format!(
+        "Usage: cargo run -p sme_pack -- <output.{PACK_EXTENSION}> <input_file_or_dir>...\nExample: cargo run -p sme_pack -- build/content.{PACK_EXTENSION} assets"
+    )
+}
+
+fn main() -> Result<(), String> {
+    let args: Vec<String> = std::env::args().collect();
+    if args.len() < 3 {
+        return Err(usage());
+    }
+
+    let output = PathBuf::from(&args[1]);
+    let inputs: Vec<PathBuf> = args[2..].iter().map(PathBuf::from).collect();
+    let files = collect_input_files(&inputs)?;
+    if files.is_empty() {
+        return Err("No input files found".to_string());
+    }
+
+    let bytes = build_pack(&files)?;
+    // Re-open the result so a broken pack never ships.
+    let pack = PackArchive::from_bytes(bytes.clone())?;
+    if let Some(parent) = output.parent().filter(|p| !p.as_os_str().is_empty()) {
+        fs::create_dir_all(parent)
+            .map_err(|e| format!("Failed to create '{}': {e}", parent.display()))?;
+    }
+    fs::write(&output, &bytes)
+        .map_err(|e| format!("Failed to write '{}': {e}", output.display()))?;
+
+    println!(
+        "Packed {} entries into {} ({} bytes)",
+        pack.len(),
+        output.display(),
+        bytes.len()
+    );
+    Ok(())
+}
+
+/// Expand directories recursively. Output is sorted so packs are reproducible.
+fn collect_input_files(inputs: &[PathBuf]) -> Result<Vec<PathBuf>, String> {
+    let mut files = Vec::new();
+    for input in inputs {
+        collect_into(input, &mut files)?;
+    }
+    files.sort();
+    files.dedup();
+    Ok(files)
+}
+
+fn collect_into(path: &Path, files: &mut Vec<PathBuf>) -> Result<(), String> {
+    let metadata = fs::metadata(path)
+        .map_err(|e| format!("Failed to read input '{}': {e}", path.display()))?;
+    if metadata.is_file() {
+        files.push(path.to_path_buf());
+        return Ok(());
+    }
+    let entries = fs::read_dir(path)
+        .map_err(|e| format!("Failed to read input dir '{}': {e}", path.display()))?;
+    for entry in entries {
+        let entry = entry.map_err(|e| format!("Failed to read '{}': {e}", path.display()))?;
+        collect_into(&entry.path(), files)?;
+    }
+    Ok(())
+}
+
+/// Entries are keyed by their normalized path, which is exactly what the
+/// engine's loaders ask for when run from the same working directory.
+fn build_pack(files: &[PathBuf]) -> Result<Vec<u8>, String> {
+    let mut writer = PackWriter::new();
+    for file in files {
+        let contents =
+            fs::read(file).map_err(|e| format!("Failed to read '{}': {e}", file.display()))?;
+        writer.add(&normalize_pack_path(file), contents)?;
+    }
+    Ok(writer.finish())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn test_temp_dir(hint: &str) -> PathBuf {
+        use std::time::{SystemTime, UNIX_EPOCH};
+        let nanos = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .expect("time")
+            .as_nanos();
+        std::env::temp_dir().join(format!("sme_pack_test_{}_{}", hint, nanos))
+    }
+
+    #[test]
+    fn test_collect_input_files_recurses_and_sorts() {
+        let root = test_temp_dir("collect");
+        fs::create_dir_all(root.join("nested")).expect("create dirs");
+        fs::write(root.join("b.json"), "{}").expect("write b");
+        fs::write(root.join("nested/a.png"), [1u8, 2, 3]).expect("write a");
+
+        let files = collect_input_files(&[root.clone(), root.join("b.json")]).expect("collect");
+        assert_eq!(files, vec![root.join("b.json"), root.join("nested/a.png")]);
+
+        let _ = fs::remove_dir_all(root);
+    }
+
+    #[test]
+    fn test_build_pack_round_trips_file_contents() {
+        let root = test_temp_dir("build");
+        fs::create_dir_all(&root).expect("create dir");
+        let file = root.join("scene.json");
+        fs::write(&file, r#"{"version":"0.3"}"#).expect("write scene");
+
+        let pack = PackArchive::from_bytes(build_pack(std::slice::from_ref(&file)).expect("build"))
+            .expect("open pack");
+        let key = normalize_pack_path(&file);
+        assert_eq!(
+            pack.read(&key).expect("read entry"),
+            br#"{"version":"0.3"}"#
+        );
+
+        let _ = fs::remove_dir_all(root);
+    }
+}
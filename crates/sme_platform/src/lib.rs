@@ -1,3 +1,5 @@
+pub mod mobile;
+pub mod paths;
 pub mod window;
 
 pub use winit;
@@ -0,0 +1,41 @@
+//! Mobile bootstrap helpers.
+//!
+//! Content paths are relative (`assets/...`) everywhere. On desktop they
+//! resolve against the working directory. iOS starts apps in `/`, so
+//! [`enter_bundle_dir`] moves into the app bundle, where `assets/` is copied as
+//! a resource. An APK has no filesystem view at all; [`read_apk_asset`] reads
+//! a file from its `assets/` folder so the game can mount a content pack
+//! shipped there.
+
+#[cfg(target_os = "android")]
+pub use winit::platform::android::activity::AndroidApp;
+
+/// Make the app bundle the working directory.
+#[cfg(target_os = "ios")]
+pub fn enter_bundle_dir() -> Result<(), String> {
+    let exe =
+        std::env::current_exe().map_err(|e| format!("Failed to locate app executable: {e}"))?;
+    let bundle = exe
+        .parent()
+        .ok_or_else(|| format!("App executable '{}' has no parent", exe.display()))?;
+    std::env::set_current_dir(bundle)
+        .map_err(|e| format!("Failed to enter app bundle '{}': {e}", bundle.display()))
+}
+
+/// Read `name` (relative to the APK's `assets/` folder) into memory.
+#[cfg(target_os = "android")]
+pub fn read_apk_asset(app: &AndroidApp, name: &str) -> Result<Vec<u8>, String> {
+    use std::io::Read;
+
+    let c_name =
+        std::ffi::CString::new(name).map_err(|_| format!("Invalid APK asset name '{name}'"))?;
+    let mut asset = app
+        .asset_manager()
+        .open(&c_name)
+        .ok_or_else(|| format!("APK asset '{name}' not found"))?;
+    let mut bytes = Vec::new();
+    asset
+        .read_to_end(&mut bytes)
+        .map_err(|e| format!("Failed to read APK asset '{name}': {e}"))?;
+    Ok(bytes)
+}
@@ -0,0 +1,55 @@
+//! Per-user directories.
+//!
+//! [`data_dir`] is where a game keeps files the player owns, such as save
+//! games: `~/.local/share/<app>` on Linux, `%APPDATA%\<app>\data` on Windows,
+//! `~/Library/Application Support/<app>` on macOS. `SME_DATA_DIR` overrides
+//! it, for portable installs and tests. [`config_dir`] holds player settings
+//! (`~/.config/<app>` on Linux, `%APPDATA%\<app>\config` on Windows); it is
+//! overridden by `SME_CONFIG_DIR`. The web build has no filesystem, so there
+//! are no directories there unless an override is set.
+
+use std::path::PathBuf;
+
+/// Environment variable that replaces the platform data directory.
+pub const DATA_DIR_ENV: &str = "SME_DATA_DIR";
+
+/// Environment variable that replaces the platform config directory.
+pub const CONFIG_DIR_ENV: &str = "SME_CONFIG_DIR";
+
+/// Data directory for the game called `app` (usually its window title).
+pub fn data_dir(app: &str) -> Option<PathBuf> {
+    user_dir(DATA_DIR_ENV, app, Dir::Data)
+}
+
+/// Config directory for the game called `app`.
+pub fn config_dir(app: &str) -> Option<PathBuf> {
+    user_dir(CONFIG_DIR_ENV, app, Dir::Config)
+}
+
+#[derive(Clone, Copy)]
+enum Dir {
+    Data,
+    Config,
+}
+
+fn user_dir(env: &str, app: &str, dir: Dir) -> Option<PathBuf> {
+    if let Some(path) = std::env::var_os(env).filter(|path| !path.is_empty()) {
+        return Some(PathBuf::from(path));
+    }
+    platform_dir(app, dir)
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+fn platform_dir(app: &str, dir: Dir) -> Option<PathBuf> {
+    let dirs = directories::ProjectDirs::from("", "", app)?;
+    let path = match dir {
+        Dir::Data => dirs.data_dir(),
+        Dir::Config => dirs.config_dir(),
+    };
+    Some(path.to_path_buf())
+}
+
+#[cfg(target_arch = "wasm32")]
+fn platform_dir(_app: &str, _dir: Dir) -> Option<PathBuf> {
+    None
+}
@@ -8,6 +8,7 @@ use std::sync::Arc;
 use winit::event_loop::ActiveEventLoop;
 use winit::window::{Window, WindowAttributes};
 
+#[derive(Debug, Clone, PartialEq)]
 pub struct PlatformConfig {
     pub title: String,
     pub width: u32,
@@ -24,6 +25,38 @@ impl Default for PlatformConfig {
     }
 }
 
+/// What the game does while its window is unfocused.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct BackgroundPolicy {
+    /// Stop stepping the simulation; rendering and hot reload continue.
+    pub pause_simulation: bool,
+    /// Silence audio output.
+    pub mute_audio: bool,
+    /// Redraw at most this many times per second; `None` keeps full rate.
+    pub redraw_hz: Option<u32>,
+}
+
+impl BackgroundPolicy {
+    /// Behave exactly as when focused.
+    pub fn keep_running() -> Self {
+        Self {
+            pause_simulation: false,
+            mute_audio: false,
+            redraw_hz: None,
+        }
+    }
+}
+
+impl Default for BackgroundPolicy {
+    fn default() -> Self {
+        Self {
+            pause_simulation: true,
+            mute_audio: true,
+            redraw_hz: Some(10),
+        }
+    }
+}
+
 pub fn create_window(event_loop: &ActiveEventLoop, config: &PlatformConfig) -> Arc<Window> {
     let attrs = WindowAttributes::default()
         .with_title(&config.title)
@@ -3,6 +3,9 @@
 //! The camera defines the visible world-space region as a centered rectangle
 //! around `position`, scaled by `zoom`. The resulting `CameraUniform` is uploaded
 //! to a GPU uniform buffer each frame and consumed by the sprite vertex shader.
+//!
+//! `CameraConstraints` keep the camera inside authored limits: a zoom range
+//! and a world-space rect the visible area may not leave.
 
 use glam::{Mat4, Vec2};
 
@@ -10,6 +13,33 @@ use glam::{Mat4, Vec2};
 #[derive(Copy, Clone, Debug, bytemuck::Pod, bytemuck::Zeroable)]
 pub struct CameraUniform {
     pub view_proj: [[f32; 4]; 4],
+    /// RGB multiplier on every vertex color drawn through this camera (alpha
+    /// unused). The world camera carries the day/night ambient; cameras built
+    /// here start white, which leaves colors unchanged.
+    pub ambient: [f32; 4],
+}
+
+/// Zoom range and world bounds the camera must respect. `None` is unlimited.
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct CameraConstraints {
+    pub min_zoom: Option<f32>,
+    pub max_zoom: Option<f32>,
+    /// Bottom-left and top-right corners of the world area the view stays in.
+    pub bounds: Option<(Vec2, Vec2)>,
+}
+
+/// Which parts of the camera `Camera2D::apply_constraints` had to move.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct CameraClamp {
+    pub zoom: bool,
+    pub x: bool,
+    pub y: bool,
+}
+
+impl CameraClamp {
+    pub fn any(&self) -> bool {
+        self.zoom || self.x || self.y
+    }
 }
 
 pub struct Camera2D {
@@ -27,21 +57,87 @@ impl Camera2D {
         }
     }
 
+    /// World position under a window pixel (origin top-left, y down).
+    pub fn screen_to_world(&self, screen_px: Vec2) -> Vec2 {
+        let center = Vec2::new(self.viewport.0 as f32, self.viewport.1 as f32) * 0.5;
+        let offset = screen_px - center;
+        self.position + Vec2::new(offset.x, -offset.y) / self.zoom
+    }
+
+    /// Window pixel showing `world`; the inverse of `screen_to_world`.
+    pub fn world_to_screen(&self, world: Vec2) -> Vec2 {
+        let center = Vec2::new(self.viewport.0 as f32, self.viewport.1 as f32) * 0.5;
+        let offset = (world - self.position) * self.zoom;
+        center + Vec2::new(offset.x, -offset.y)
+    }
+
+    /// Multiply zoom by `factor` while keeping the world point under
+    /// `screen_px` fixed on screen.
+    pub fn zoom_about(&mut self, screen_px: Vec2, factor: f32) {
+        let anchor = self.screen_to_world(screen_px);
+        self.zoom *= factor;
+        self.position += anchor - self.screen_to_world(screen_px);
+    }
+
+    /// Clamp zoom into range, then move the camera so the visible area stays
+    /// inside the bounds. On an axis where the bounds are smaller than the
+    /// view, the view is centered on them instead.
+    pub fn apply_constraints(&mut self, constraints: &CameraConstraints) -> CameraClamp {
+        let mut clamp = CameraClamp::default();
+        let mut zoom = self.zoom;
+        if let Some(min_zoom) = constraints.min_zoom {
+            zoom = zoom.max(min_zoom);
+        }
+        if let Some(max_zoom) = constraints.max_zoom {
+            zoom = zoom.min(max_zoom);
+        }
+        if zoom != self.zoom {
+            self.zoom = zoom;
+            clamp.zoom = true;
+        }
+
+        if let Some((min, max)) = constraints.bounds {
+            let half_w = (self.viewport.0 as f32) / (2.0 * self.zoom);
+            let half_h = (self.viewport.1 as f32) / (2.0 * self.zoom);
+            let clamp_axis = |value: f32, lo: f32, hi: f32, half: f32| {
+                if hi - lo <= half * 2.0 {
+                    (lo + hi) * 0.5
+                } else {
+                    value.clamp(lo + half, hi - half)
+                }
+            };
+            let x = clamp_axis(self.position.x, min.x, max.x, half_w);
+            let y = clamp_axis(self.position.y, min.y, max.y, half_h);
+            clamp.x = x != self.position.x;
+            clamp.y = y != self.position.y;
+            self.position = Vec2::new(x, y);
+        }
+        clamp
+    }
+
     pub fn build_uniform(&self) -> CameraUniform {
+        self.build_uniform_offset(Vec2::ZERO)
+    }
+
+    /// Uniform for a view displaced by `offset` world units, leaving
+    /// `position` itself alone (used for screen shake).
+    pub fn build_uniform_offset(&self, offset: Vec2) -> CameraUniform {
+        let center = self.position + offset;
         let half_w = (self.viewport.0 as f32) / (2.0 * self.zoom);
         let half_h = (self.viewport.1 as f32) / (2.0 * self.zoom);
 
         let proj = Mat4::orthographic_rh(
-            self.position.x - half_w,
-            self.position.x + half_w,
-            self.position.y - half_h,
-            self.position.y + half_h,
+            center.x - half_w,
+            center.x + half_w,
+            center.y - half_h,
+            center.y + half_h,
             -1.0,
             1.0,
         );
 
         CameraUniform {
             view_proj: proj.to_cols_array_2d(),
+            ambient: [1.0; 4],
         }
     }
 }
@@ -115,6 +211,68 @@ mod tests {
         assert_approx(bl.y, -1.0, "offset bottom-left y");
     }
 
+    #[test]
+    fn test_apply_constraints_clamps_zoom_then_position() {
+        let mut cam = Camera2D::new(800, 600);
+        cam.zoom = 0.5;
+        cam.position = Vec2::new(950.0, -10.0);
+        let constraints = CameraConstraints {
+            min_zoom: Some(1.0),
+            max_zoom: Some(3.0),
+            bounds: Some((Vec2::new(0.0, 0.0), Vec2::new(1000.0, 500.0))),
+        };
+        let clamp = cam.apply_constraints(&constraints);
+        assert_eq!(
+            clamp,
+            CameraClamp {
+                zoom: true,
+                x: true,
+                y: true
+            }
+        );
+        assert_approx(cam.zoom, 1.0, "zoom");
+        // View is 800 wide inside 1000: x stays within [400, 600].
+        assert_approx(cam.position.x, 600.0, "x");
+        // View is 600 tall but bounds only 500: centered.
+        assert_approx(cam.position.y, 250.0, "y");
+
+        // Already inside: nothing moves.
+        let clamp = cam.apply_constraints(&constraints);
+        assert!(!clamp.any());
+    }
+
+    #[test]
+    fn test_zoom_about_keeps_the_cursor_point_fixed() {
+        let mut cam = Camera2D::new(800, 600);
+        cam.position = Vec2::new(100.0, 50.0);
+        // Top-left pixel is 400 left and 300 up from the center.
+        let corner = cam.screen_to_world(Vec2::ZERO);
+        assert_approx(corner.x, -300.0, "corner x");
+        assert_approx(corner.y, 350.0, "corner y");
+
+        let cursor = Vec2::new(600.0, 100.0);
+        let before = cam.screen_to_world(cursor);
+        cam.zoom_about(cursor, 2.0);
+        let after = cam.screen_to_world(cursor);
+        assert_approx(cam.zoom, 2.0, "zoom");
+        assert_approx(after.x, before.x, "anchor x");
+        assert_approx(after.y, before.y, "anchor y");
+    }
+
+    #[test]
+    fn test_world_to_screen_inverts_screen_to_world() {
+        let mut cam = Camera2D::new(800, 600);
+        cam.position = Vec2::new(-40.0, 25.0);
+        cam.zoom = 1.5;
+        let pixel = Vec2::new(123.0, 456.0);
+        let back = cam.world_to_screen(cam.screen_to_world(pixel));
+        assert_approx(back.x, pixel.x, "x");
+        assert_approx(back.y, pixel.y, "y");
+        let center = cam.world_to_screen(cam.position);
+        assert_approx(center.x, 400.0, "center x");
+        assert_approx(center.y, 300.0, "center y");
+    }
+
     #[test]
     fn test_zoom_in() {
         let mut cam = Camera2D::new(800, 600);
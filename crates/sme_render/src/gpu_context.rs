@@ -1,15 +1,41 @@
-use std::sync::Arc;
+use std::sync::{Arc, Mutex, MutexGuard, PoisonError};
 use winit::window::Window;
 
 pub struct GpuContext {
-    pub surface: wgpu::Surface<'static>,
+    instance: wgpu::Instance,
+    adapter: wgpu::Adapter,
+    surface: SharedSurface,
+    /// Mirrors whether `surface` is gone, so checking it never waits on a
+    /// frame being presented.
+    suspended: bool,
     pub device: wgpu::Device,
     pub queue: wgpu::Queue,
-    pub config: wgpu::SurfaceConfiguration,
     pub surface_format: wgpu::TextureFormat,
     pub size: (u32, u32),
 }
 
+struct SurfaceSlot {
+    /// `None` while suspended: mobile platforms destroy the native window
+    /// when the app is backgrounded, and the surface must go with it.
+    surface: Option<wgpu::Surface<'static>>,
+    config: wgpu::SurfaceConfiguration,
+}
+
+/// The main window's surface, shared with the thread that presents to it.
+/// A [`SurfaceFrame`] holds the surface from acquire to present, so resizing
+/// or dropping the surface waits for a frame in flight instead of racing it.
+#[derive(Clone)]
+pub struct SharedSurface {
+    slot: Arc<Mutex<SurfaceSlot>>,
+}
+
+/// A surface texture to draw into; [`SurfaceFrame::present`] shows it.
+pub struct SurfaceFrame<'a> {
+    output: wgpu::SurfaceTexture,
+    pub view: wgpu::TextureView,
+    _slot: MutexGuard<'a, SurfaceSlot>,
+}
+
 impl GpuContext {
     /// Synchronous constructor for native targets, where blocking on GPU
     /// init inside `resumed` is fine. On wasm32 the browser forbids
@@ -22,8 +48,10 @@ impl GpuContext {
     pub async fn new_async(window: Arc<Window>) -> Self {
         let size = window.inner_size();
 
-        #[cfg(not(target_arch = "wasm32"))]
+        #[cfg(not(any(target_arch = "wasm32", target_vendor = "apple")))]
         let backends = wgpu::Backends::DX12 | wgpu::Backends::VULKAN;
+        #[cfg(target_vendor = "apple")]
+        let backends = wgpu::Backends::METAL;
         // GL (WebGL2) only: mixing in BROWSER_WEBGPU is unreliable — if the
         // browser exposes navigator.gpu but adapter request fails, the canvas
         // is already bound to a webgpu context and can no longer fall back to
@@ -94,10 +122,17 @@ impl GpuContext {
         surface.configure(&device, &config);
 
         Self {
-            surface,
+            instance,
+            adapter,
+            surface: SharedSurface {
+                slot: Arc::new(Mutex::new(SurfaceSlot {
+                    surface: Some(surface),
+                    config,
+                })),
+            },
+            suspended: false,
             device,
             queue,
-            config,
             surface_format,
             size: (size.width, size.height),
         }
@@ -108,16 +143,116 @@ impl GpuContext {
             return;
         }
         self.size = (width, height);
-        self.config.width = width;
-        self.config.height = height;
-        self.surface.configure(&self.device, &self.config);
+        let mut slot = self.surface.lock();
+        slot.config.width = width;
+        slot.config.height = height;
+        if let Some(surface) = &slot.surface {
+            surface.configure(&self.device, &slot.config);
+        }
+    }
+
+    /// Wait for vertical blank before presenting (`Fifo`), or present as soon
+    /// as a frame is ready where the platform allows it.
+    pub fn set_vsync(&mut self, vsync: bool) {
+        let mut slot = self.surface.lock();
+        slot.config.present_mode = if vsync {
+            wgpu::PresentMode::Fifo
+        } else {
+            wgpu::PresentMode::AutoNoVsync
+        };
+        if let Some(surface) = &slot.surface {
+            surface.configure(&self.device, &slot.config);
+        }
+    }
+
+    /// Drop the surface when the app is suspended. The device, queue and every
+    /// GPU resource survive; frames are skipped until [`GpuContext::resume`].
+    pub fn suspend(&mut self) {
+        self.surface.lock().surface = None;
+        self.suspended = true;
+    }
+
+    /// Recreate the surface for `window` after a suspend. A no-op if the
+    /// surface still exists (desktop sends `resumed` once at startup only).
+    pub fn resume(&mut self, window: Arc<Window>) {
+        if !self.suspended {
+            return;
+        }
+        let size = window.inner_size();
+        let surface = self
+            .instance
+            .create_surface(window)
+            .expect("Failed to recreate surface");
+        self.size = (size.width, size.height);
+        let mut slot = self.surface.lock();
+        slot.config.width = size.width.max(1);
+        slot.config.height = size.height.max(1);
+        surface.configure(&self.device, &slot.config);
+        slot.surface = Some(surface);
+        self.suspended = false;
+    }
+
+    pub fn is_suspended(&self) -> bool {
+        self.suspended
+    }
+
+    /// The main surface, for presenting from another thread.
+    pub fn shared_surface(&self) -> SharedSurface {
+        self.surface.clone()
     }
 
     pub fn begin_frame(&self) -> Option<(wgpu::SurfaceTexture, wgpu::TextureView)> {
-        let output = match self.surface.get_current_texture() {
+        let slot = self.surface.lock();
+        slot.next_texture(&self.device)
+    }
+
+    /// Surface for an additional window (a tool window) that renders with this
+    /// context's device and queue.
+    pub fn create_window_surface(&self, window: Arc<Window>) -> WindowSurface {
+        let size = window.inner_size();
+        let surface = self
+            .instance
+            .create_surface(window.clone())
+            .expect("Failed to create tool window surface");
+        let caps = surface.get_capabilities(&self.adapter);
+        let format = if caps.formats.contains(&self.surface_format) {
+            self.surface_format
+        } else {
+            caps.formats[0]
+        };
+        let config = wgpu::SurfaceConfiguration {
+            format,
+            width: size.width.max(1),
+            height: size.height.max(1),
+            alpha_mode: caps.alpha_modes[0],
+            ..self.surface.lock().config.clone()
+        };
+        surface.configure(&self.device, &config);
+        WindowSurface {
+            window,
+            surface,
+            config,
+            size: (size.width, size.height),
+        }
+    }
+}
+
+impl SurfaceSlot {
+    fn next_texture(
+        &self,
+        device: &wgpu::Device,
+    ) -> Option<(wgpu::SurfaceTexture, wgpu::TextureView)> {
+        let surface = self.surface.as_ref()?;
+        let output = match surface.get_current_texture() {
             Ok(tex) => tex,
-            Err(wgpu::SurfaceError::Lost) => {
-                self.surface.configure(&self.device, &self.config);
+            // Lost after sleep/wake or a driver reset, Outdated after the
+            // window changed under us: reconfigure and retry next frame.
+            Err(wgpu::SurfaceError::Lost | wgpu::SurfaceError::Outdated) => {
+                surface.configure(device, &self.config);
+                return None;
+            }
+            Err(wgpu::SurfaceError::Timeout) => {
+                log::debug!("Surface timeout; skipping frame");
                 return None;
             }
             Err(wgpu::SurfaceError::OutOfMemory) => {
@@ -135,3 +270,86 @@ impl GpuContext {
         Some((output, view))
     }
 }
+
+impl SharedSurface {
+    fn lock(&self) -> MutexGuard<'_, SurfaceSlot> {
+        // A thread that panicked mid-frame leaves the surface itself intact,
+        // and the crash path still has to release it.
+        self.slot.lock().unwrap_or_else(PoisonError::into_inner)
+    }
+
+    /// The next texture to draw a frame of `size` into. `None` while
+    /// suspended, when acquiring fails, or when the surface has since been
+    /// resized away from `size`.
+    pub fn acquire(&self, device: &wgpu::Device, size: (u32, u32)) -> Option<SurfaceFrame<'_>> {
+        let slot = self.lock();
+        if (slot.config.width, slot.config.height) != size {
+            return None;
+        }
+        let (output, view) = slot.next_texture(device)?;
+        Some(SurfaceFrame {
+            output,
+            view,
+            _slot: slot,
+        })
+    }
+}
+
+impl SurfaceFrame<'_> {
+    pub fn present(self) {
+        self.output.present();
+    }
+}
+
+/// A window other than the main one, drawing with the main [`GpuContext`]'s
+/// device. Tool windows are desktop-only and are simply closed on suspend.
+pub struct WindowSurface {
+    window: Arc<Window>,
+    surface: wgpu::Surface<'static>,
+    config: wgpu::SurfaceConfiguration,
+    pub size: (u32, u32),
+}
+
+impl WindowSurface {
+    pub fn window(&self) -> &Arc<Window> {
+        &self.window
+    }
+
+    pub fn format(&self) -> wgpu::TextureFormat {
+        self.config.format
+    }
+
+    pub fn resize(&mut self, device: &wgpu::Device, width: u32, height: u32) {
+        if width == 0 || height == 0 {
+            return;
+        }
+        self.size = (width, height);
+        self.config.width = width;
+        self.config.height = height;
+        self.surface.configure(device, &self.config);
+    }
+
+    pub fn begin_frame(
+        &self,
+        device: &wgpu::Device,
+    ) -> Option<(wgpu::SurfaceTexture, wgpu::TextureView)> {
+        if self.size.0 == 0 || self.size.1 == 0 {
+            return None;
+        }
+        let output = match self.surface.get_current_texture() {
+            Ok(tex) => tex,
+            Err(wgpu::SurfaceError::Lost | wgpu::SurfaceError::Outdated) => {
+                self.surface.configure(device, &self.config);
+                return None;
+            }
+            Err(e) => {
+                log::warn!("Tool window surface error: {:?}", e);
+                return None;
+            }
+        };
+        let view = output
+            .texture
+            .create_view(&wgpu::TextureViewDescriptor::default());
+        Some((output, view))
+    }
+}
@@ -1,11 +1,19 @@
 pub mod camera;
 pub mod gpu_context;
+pub mod material;
+pub mod offscreen;
+pub mod post_process;
+pub mod shake;
 pub mod sprite_pipeline;
 pub mod texture;
 pub mod vertex;
 
-pub use camera::{Camera2D, CameraUniform};
-pub use gpu_context::GpuContext;
-pub use sprite_pipeline::SpritePipeline;
-pub use texture::Texture;
-pub use vertex::SpriteVertex;
+pub use camera::{Camera2D, CameraClamp, CameraConstraints, CameraUniform};
+pub use gpu_context::{GpuContext, SharedSurface, SurfaceFrame, WindowSurface};
+pub use material::MaterialUniform;
+pub use offscreen::{HeadlessGpu, OffscreenTarget, OFFSCREEN_FORMAT};
+pub use post_process::{ColorLut, GradePass, PostProcess};
+pub use shake::CameraShake;
+pub use sprite_pipeline::{AlphaMode, SpritePipeline, TextureBinding};
+pub use texture::{SamplerAddress, SamplerDesc, SamplerFilter, Texels, Texture, TextureId};
+pub use vertex::{SpriteEffect, SpriteVertex};
@@ -0,0 +1,191 @@
+//! Rendering without a window: a device with no surface, and a texture to
+//! draw into and read back.
+//!
+//! Thumbnails and previews are drawn with the same sprite pipeline as the
+//! game, into an `OffscreenTarget` of `OFFSCREEN_FORMAT` (sRGB like the
+//! window surfaces, so colors come out the same) instead of a swapchain.
+
+use crate::texture::unpremultiply_srgb;
+
+/// Color format of offscreen targets.
+pub const OFFSCREEN_FORMAT: wgpu::TextureFormat = wgpu::TextureFormat::Rgba8UnormSrgb;
+
+/// A device and queue with no surface.
+pub struct HeadlessGpu {
+    pub device: wgpu::Device,
+    pub queue: wgpu::Queue,
+}
+
+impl HeadlessGpu {
+    /// Blocks on adapter and device creation. Fails, rather than panicking,
+    /// on machines without a usable GPU, e.g. CI runners.
+    #[cfg(not(target_arch = "wasm32"))]
+    pub fn new() -> Result<Self, String> {
+        pollster::block_on(Self::new_async())
+    }
+
+    pub async fn new_async() -> Result<Self, String> {
+        let instance = wgpu::Instance::new(&wgpu::InstanceDescriptor::default());
+        let adapter = instance
+            .request_adapter(&wgpu::RequestAdapterOptions {
+                power_preference: wgpu::PowerPreference::HighPerformance,
+                compatible_surface: None,
+                force_fallback_adapter: false,
+            })
+            .await
+            .ok_or_else(|| "No GPU adapter available for headless rendering".to_string())?;
+        log::info!("Headless GPU adapter: {:?}", adapter.get_info().name);
+        let (device, queue) = adapter
+            .request_device(
+                &wgpu::DeviceDescriptor {
+                    label: Some("SME Headless Device"),
+                    required_features: wgpu::Features::empty(),
+                    required_limits: wgpu::Limits::downlevel_defaults()
+                        .using_resolution(adapter.limits()),
+                    ..Default::default()
+                },
+                None,
+            )
+            .await
+            .map_err(|e| format!("Failed to create headless device: {e}"))?;
+        Ok(Self { device, queue })
+    }
+}
+
+/// A color texture to render into and copy back to memory.
+pub struct OffscreenTarget {
+    texture: wgpu::Texture,
+    pub view: wgpu::TextureView,
+    pub size: (u32, u32),
+}
+
+impl OffscreenTarget {
+    pub fn new(device: &wgpu::Device, size: (u32, u32)) -> Result<Self, String> {
+        let max = device.limits().max_texture_dimension_2d;
+        if size.0 == 0 || size.1 == 0 || size.0 > max || size.1 > max {
+            return Err(format!(
+                "Offscreen size {}x{} must be between 1 and {max} on each side",
+                size.0, size.1
+            ));
+        }
+        let texture = device.create_texture(&wgpu::TextureDescriptor {
+            label: Some("Offscreen Target"),
+            size: wgpu::Extent3d {
+                width: size.0,
+                height: size.1,
+                depth_or_array_layers: 1,
+            },
+            mip_level_count: 1,
+            sample_count: 1,
+            dimension: wgpu::TextureDimension::D2,
+            format: OFFSCREEN_FORMAT,
+            usage: wgpu::TextureUsages::RENDER_ATTACHMENT | wgpu::TextureUsages::COPY_SRC,
+            view_formats: &[],
+        });
+        let view = texture.create_view(&wgpu::TextureViewDescriptor::default());
+        Ok(Self {
+            texture,
+            view,
+            size,
+        })
+    }
+
+    /// Copy the target back as tightly packed RGBA8 rows, top row first,
+    /// waiting for the GPU to finish. Sprites blended onto a transparent
+    /// clear leave color multiplied by alpha; that is undone, so the pixels
+    /// are straight alpha like an image file's.
+    pub fn read_rgba(&self, device: &wgpu::Device, queue: &wgpu::Queue) -> Result<Vec<u8>, String> {
+        let (width, height) = self.size;
+        let padded_row = padded_bytes_per_row(width);
+        let buffer = device.create_buffer(&wgpu::BufferDescriptor {
+            label: Some("Offscreen Readback Buffer"),
+            size: u64::from(padded_row) * u64::from(height),
+            usage: wgpu::BufferUsages::COPY_DST | wgpu::BufferUsages::MAP_READ,
+            mapped_at_creation: false,
+        });
+        let mut encoder = device.create_command_encoder(&wgpu::CommandEncoderDescriptor {
+            label: Some("Offscreen Readback Encoder"),
+        });
+        encoder.copy_texture_to_buffer(
+            wgpu::TexelCopyTextureInfo {
+                texture: &self.texture,
+                mip_level: 0,
+                origin: wgpu::Origin3d::ZERO,
+                aspect: wgpu::TextureAspect::All,
+            },
+            wgpu::TexelCopyBufferInfo {
+                buffer: &buffer,
+                layout: wgpu::TexelCopyBufferLayout {
+                    offset: 0,
+                    bytes_per_row: Some(padded_row),
+                    rows_per_image: Some(height),
+                },
+            },
+            wgpu::Extent3d {
+                width,
+                height,
+                depth_or_array_layers: 1,
+            },
+        );
+        queue.submit(std::iter::once(encoder.finish()));
+
+        let slice = buffer.slice(..);
+        let (sender, receiver) = std::sync::mpsc::channel();
+        slice.map_async(wgpu::MapMode::Read, move |result| {
+            let _ = sender.send(result);
+        });
+        device.poll(wgpu::Maintain::Wait).panic_on_timeout();
+        receiver
+            .recv()
+            .map_err(|e| format!("Offscreen readback was dropped: {e}"))?
+            .map_err(|e| format!("Failed to map offscreen readback: {e}"))?;
+        let mut rgba = unpad_rows(&slice.get_mapped_range(), width, height);
+        buffer.unmap();
+        unpremultiply_srgb(&mut rgba);
+        Ok(rgba)
+    }
+}
+
+/// Row pitch of a `width`-texel RGBA8 copy; wgpu wants it aligned.
+fn padded_bytes_per_row(width: u32) -> u32 {
+    let row = width * 4;
+    let align = wgpu::COPY_BYTES_PER_ROW_ALIGNMENT;
+    row.div_ceil(align) * align
+}
+
+/// `height` rows of `width` RGBA8 texels, with each row's padding dropped.
+fn unpad_rows(padded: &[u8], width: u32, height: u32) -> Vec<u8> {
+    let row = width as usize * 4;
+    padded
+        .chunks(padded_bytes_per_row(width) as usize)
+        .take(height as usize)
+        .flat_map(|padded_row| &padded_row[..row])
+        .copied()
+        .collect()
+}
+
+/// Write tightly packed RGBA8 pixels to a PNG file.
+pub fn save_png(path: &std::path::Path, rgba: &[u8], size: (u32, u32)) -> Result<(), String> {
+    image::save_buffer(path, rgba, size.0, size.1, image::ColorType::Rgba8)
+        .map_err(|e| format!("Failed to write {}: {e}", path.display()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn readback_rows_lose_their_padding() {
+        assert_eq!(padded_bytes_per_row(64), 256);
+        assert_eq!(padded_bytes_per_row(65), 512);
+
+        // Three texels wide: 12 bytes of pixels per 256-byte row.
+        let mut padded = vec![0xee; 256 * 2];
+        padded[..12].copy_from_slice(&[1; 12]);
+        padded[256..268].copy_from_slice(&[2; 12]);
+        let rows = unpad_rows(&padded, 3, 2);
+        assert_eq!(rows.len(), 24);
+        assert!(rows[..12].iter().all(|&b| b == 1));
+        assert!(rows[12..].iter().all(|&b| b == 2));
+    }
+}
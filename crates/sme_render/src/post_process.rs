@@ -0,0 +1,366 @@
+//! Tier 2 post-process pass: color grading through a per-scene LUT.
+//!
+//! While a LUT is set, `grade_pass` hands out a `GradePass`: the scene is
+//! drawn into its `scene_view` instead of the surface, and its `draw` copies
+//! it to the surface through the LUT. A `GradePass` only holds GPU handles,
+//! so it can be recorded on another thread. LUTs use the
+//! common strip layout: `n` slices of `n`x`n` texels side by side (256x16 for
+//! `n` = 16), red increasing along x within a slice, green down y, and blue
+//! selecting the slice. Lookups happen on sRGB-encoded values, so a LUT made
+//! by grading a screenshot of `ColorLut::identity` in an image editor applies
+//! the same grade in game.
+
+pub const POST_PROCESS_SHADER_SRC: &str = include_str!("../../../assets/shaders/post_process.wgsl");
+
+/// A decoded strip LUT.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ColorLut {
+    pub rgba: Vec<u8>,
+    /// Texels per color axis; the image is `size * size` by `size`.
+    pub size: u32,
+}
+
+impl ColorLut {
+    pub fn from_rgba8(rgba: Vec<u8>, width: u32, height: u32) -> Result<Self, String> {
+        if height < 2 || width != height * height {
+            return Err(format!(
+                "color LUT is {width}x{height}; expected a strip of n slices of n x n \
+                 (e.g. 256x16)"
+            ));
+        }
+        Ok(Self { rgba, size: height })
+    }
+
+    pub fn from_png_bytes(bytes: &[u8]) -> Result<Self, String> {
+        let image = image::load_from_memory(bytes)
+            .map_err(|e| format!("failed to decode color LUT: {e}"))?
+            .to_rgba8();
+        let (width, height) = image.dimensions();
+        Self::from_rgba8(image.into_raw(), width, height)
+    }
+
+    /// The LUT that leaves colors unchanged.
+    pub fn identity(size: u32) -> Self {
+        let max = (size - 1) as f32;
+        let channel = |i: u32| (i as f32 / max * 255.0).round() as u8;
+        let mut rgba = Vec::with_capacity((size * size * size * 4) as usize);
+        for green in 0..size {
+            for blue in 0..size {
+                for red in 0..size {
+                    rgba.extend_from_slice(&[channel(red), channel(green), channel(blue), 255]);
+                }
+            }
+        }
+        Self { rgba, size }
+    }
+}
+
+/// Matches `GradeUniform` in the post-process shader.
+#[repr(C)]
+#[derive(Copy, Clone, Debug, Default, bytemuck::Pod, bytemuck::Zeroable)]
+struct GradeUniform {
+    lut_size: f32,
+    srgb_surface: f32,
+    _padding: [f32; 2],
+}
+
+pub struct PostProcess {
+    pipeline: wgpu::RenderPipeline,
+    bind_group_layout: wgpu::BindGroupLayout,
+    scene_sampler: wgpu::Sampler,
+    lut_sampler: wgpu::Sampler,
+    uniform_buffer: wgpu::Buffer,
+    format: wgpu::TextureFormat,
+    lut: Option<wgpu::TextureView>,
+    /// Offscreen scene texture and its size; made on first use and remade
+    /// when the surface size changes.
+    scene: Option<(wgpu::TextureView, (u32, u32))>,
+    bind_group: Option<wgpu::BindGroup>,
+}
+
+impl PostProcess {
+    pub fn new(device: &wgpu::Device, surface_format: wgpu::TextureFormat) -> Self {
+        let texture_entry = |binding| wgpu::BindGroupLayoutEntry {
+            binding,
+            visibility: wgpu::ShaderStages::FRAGMENT,
+            ty: wgpu::BindingType::Texture {
+                sample_type: wgpu::TextureSampleType::Float { filterable: true },
+                view_dimension: wgpu::TextureViewDimension::D2,
+                multisampled: false,
+            },
+            count: None,
+        };
+        let sampler_entry = |binding| wgpu::BindGroupLayoutEntry {
+            binding,
+            visibility: wgpu::ShaderStages::FRAGMENT,
+            ty: wgpu::BindingType::Sampler(wgpu::SamplerBindingType::Filtering),
+            count: None,
+        };
+        let bind_group_layout = device.create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
+            label: Some("Post Process Bind Group Layout"),
+            entries: &[
+                texture_entry(0),
+                sampler_entry(1),
+                texture_entry(2),
+                sampler_entry(3),
+                wgpu::BindGroupLayoutEntry {
+                    binding: 4,
+                    visibility: wgpu::ShaderStages::FRAGMENT,
+                    ty: wgpu::BindingType::Buffer {
+                        ty: wgpu::BufferBindingType::Uniform,
+                        has_dynamic_offset: false,
+                        min_binding_size: None,
+                    },
+                    count: None,
+                },
+            ],
+        });
+        let layout = device.create_pipeline_layout(&wgpu::PipelineLayoutDescriptor {
+            label: Some("Post Process Pipeline Layout"),
+            bind_group_layouts: &[&bind_group_layout],
+            push_constant_ranges: &[],
+        });
+        let shader = device.create_shader_module(wgpu::ShaderModuleDescriptor {
+            label: Some("Post Process Shader"),
+            source: wgpu::ShaderSource::Wgsl(POST_PROCESS_SHADER_SRC.into()),
+        });
+        let pipeline = device.create_render_pipeline(&wgpu::RenderPipelineDescriptor {
+            label: Some("Post Process Pipeline"),
+            layout: Some(&layout),
+            vertex: wgpu::VertexState {
+                module: &shader,
+                entry_point: Some("vs_main"),
+                buffers: &[],
+                compilation_options: Default::default(),
+            },
+            fragment: Some(wgpu::FragmentState {
+                module: &shader,
+                entry_point: Some("fs_main"),
+                targets: &[Some(wgpu::ColorTargetState {
+                    format: surface_format,
+                    blend: None,
+                    write_mask: wgpu::ColorWrites::ALL,
+                })],
+                compilation_options: Default::default(),
+            }),
+            primitive: wgpu::PrimitiveState::default(),
+            depth_stencil: None,
+            multisample: wgpu::MultisampleState::default(),
+            multiview: None,
+            cache: None,
+        });
+
+        let scene_sampler = device.create_sampler(&wgpu::SamplerDescriptor {
+            label: Some("Post Process Scene Sampler"),
+            ..Default::default()
+        });
+        let lut_sampler = device.create_sampler(&wgpu::SamplerDescriptor {
+            label: Some("Color LUT Sampler"),
+            mag_filter: wgpu::FilterMode::Linear,
+            min_filter: wgpu::FilterMode::Linear,
+            ..Default::default()
+        });
+        let uniform_buffer = device.create_buffer(&wgpu::BufferDescriptor {
+            label: Some("Post Process Uniform Buffer"),
+            size: std::mem::size_of::<GradeUniform>() as u64,
+            usage: wgpu::BufferUsages::UNIFORM | wgpu::BufferUsages::COPY_DST,
+            mapped_at_creation: false,
+        });
+
+        Self {
+            pipeline,
+            bind_group_layout,
+            scene_sampler,
+            lut_sampler,
+            uniform_buffer,
+            format: surface_format,
+            lut: None,
+            scene: None,
+            bind_group: None,
+        }
+    }
+
+    pub fn has_lut(&self) -> bool {
+        self.lut.is_some()
+    }
+
+    /// Upload `lut`, or drop the current one with `None`.
+    pub fn set_lut(&mut self, device: &wgpu::Device, queue: &wgpu::Queue, lut: Option<&ColorLut>) {
+        self.bind_group = None;
+        let Some(lut) = lut else {
+            self.lut = None;
+            return;
+        };
+        let size = wgpu::Extent3d {
+            width: lut.size * lut.size,
+            height: lut.size,
+            depth_or_array_layers: 1,
+        };
+        let texture = device.create_texture(&wgpu::TextureDescriptor {
+            label: Some("Color LUT"),
+            size,
+            mip_level_count: 1,
+            sample_count: 1,
+            dimension: wgpu::TextureDimension::D2,
+            // The LUT maps encoded values to encoded values.
+            format: wgpu::TextureFormat::Rgba8Unorm,
+            usage: wgpu::TextureUsages::TEXTURE_BINDING | wgpu::TextureUsages::COPY_DST,
+            view_formats: &[],
+        });
+        queue.write_texture(
+            wgpu::TexelCopyTextureInfo {
+                texture: &texture,
+                mip_level: 0,
+                origin: wgpu::Origin3d::ZERO,
+                aspect: wgpu::TextureAspect::All,
+            },
+            &lut.rgba,
+            wgpu::TexelCopyBufferLayout {
+                offset: 0,
+                bytes_per_row: Some(4 * size.width),
+                rows_per_image: Some(size.height),
+            },
+            size,
+        );
+        queue.write_buffer(
+            &self.uniform_buffer,
+            0,
+            bytemuck::cast_slice(&[GradeUniform {
+                lut_size: lut.size as f32,
+                srgb_surface: if self.format.is_srgb() { 1.0 } else { 0.0 },
+                _padding: [0.0; 2],
+            }]),
+        );
+        self.lut = Some(texture.create_view(&wgpu::TextureViewDescriptor::default()));
+    }
+
+    /// This frame's grading, with a scene texture sized to the surface.
+    /// `None` without a LUT; draw straight to the surface then.
+    pub fn grade_pass(&mut self, device: &wgpu::Device, size: (u32, u32)) -> Option<GradePass> {
+        let lut = self.lut.as_ref()?;
+        if self
+            .scene
+            .as_ref()
+            .is_none_or(|(_, scene_size)| *scene_size != size)
+        {
+            let texture = device.create_texture(&wgpu::TextureDescriptor {
+                label: Some("Post Process Scene Texture"),
+                size: wgpu::Extent3d {
+                    width: size.0.max(1),
+                    height: size.1.max(1),
+                    depth_or_array_layers: 1,
+                },
+                mip_level_count: 1,
+                sample_count: 1,
+                dimension: wgpu::TextureDimension::D2,
+                format: self.format,
+                usage: wgpu::TextureUsages::RENDER_ATTACHMENT
+                    | wgpu::TextureUsages::TEXTURE_BINDING,
+                view_formats: &[],
+            });
+            self.scene = Some((
+                texture.create_view(&wgpu::TextureViewDescriptor::default()),
+                size,
+            ));
+            self.bind_group = None;
+        }
+        let (scene, _) = self.scene.as_ref()?;
+        if self.bind_group.is_none() {
+            self.bind_group = Some(device.create_bind_group(&wgpu::BindGroupDescriptor {
+                label: Some("Post Process Bind Group"),
+                layout: &self.bind_group_layout,
+                entries: &[
+                    wgpu::BindGroupEntry {
+                        binding: 0,
+                        resource: wgpu::BindingResource::TextureView(scene),
+                    },
+                    wgpu::BindGroupEntry {
+                        binding: 1,
+                        resource: wgpu::BindingResource::Sampler(&self.scene_sampler),
+                    },
+                    wgpu::BindGroupEntry {
+                        binding: 2,
+                        resource: wgpu::BindingResource::TextureView(lut),
+                    },
+                    wgpu::BindGroupEntry {
+                        binding: 3,
+                        resource: wgpu::BindingResource::Sampler(&self.lut_sampler),
+                    },
+                    wgpu::BindGroupEntry {
+                        binding: 4,
+                        resource: self.uniform_buffer.as_entire_binding(),
+                    },
+                ],
+            }));
+        }
+        Some(GradePass {
+            scene_view: scene.clone(),
+            pipeline: self.pipeline.clone(),
+            bind_group: self.bind_group.clone()?,
+        })
+    }
+}
+
+/// One frame of color grading; see [`PostProcess::grade_pass`].
+#[derive(Clone)]
+pub struct GradePass {
+    /// Draw the scene into this instead of the surface.
+    pub scene_view: wgpu::TextureView,
+    pipeline: wgpu::RenderPipeline,
+    bind_group: wgpu::BindGroup,
+}
+
+impl GradePass {
+    /// Grade the scene texture onto `target`. Call after the scene passes
+    /// that drew into `scene_view`.
+    pub fn draw(&self, encoder: &mut wgpu::CommandEncoder, target: &wgpu::TextureView) {
+        let mut pass = encoder.begin_render_pass(&wgpu::RenderPassDescriptor {
+            label: Some("Post Process Pass"),
+            color_attachments: &[Some(wgpu::RenderPassColorAttachment {
+                view: target,
+                resolve_target: None,
+                ops: wgpu::Operations {
+                    load: wgpu::LoadOp::Clear(wgpu::Color::BLACK),
+                    store: wgpu::StoreOp::Store,
+                },
+            })],
+            depth_stencil_attachment: None,
+            ..Default::default()
+        });
+        pass.set_pipeline(&self.pipeline);
+        pass.set_bind_group(0, &self.bind_group, &[]);
+        pass.draw(0..3, 0..1);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::sprite_pipeline::validate_wgsl;
+    use wgpu::naga;
+
+    #[test]
+    fn shader_validates() {
+        validate_wgsl(
+            POST_PROCESS_SHADER_SRC,
+            &[
+                ("vs_main", naga::ShaderStage::Vertex),
+                ("fs_main", naga::ShaderStage::Fragment),
+            ],
+        )
+        .expect("post-process shader");
+    }
+
+    #[test]
+    fn lut_must_be_a_strip_of_square_slices() {
+        let identity = ColorLut::identity(16);
+        assert_eq!(identity.rgba.len(), 256 * 16 * 4);
+        // Row 0, slice 1, column 0: red 0, green 0, blue 1/15.
+        assert_eq!(&identity.rgba[16 * 4..16 * 4 + 4], &[0, 0, 17, 255]);
+        let lut = ColorLut::from_rgba8(identity.rgba.clone(), 256, 16).expect("valid strip");
+        assert_eq!(lut.size, 16);
+
+        let err = ColorLut::from_rgba8(vec![0; 64 * 64 * 4], 64, 64).expect_err("square image");
+        assert!(err.contains("64x64"), "{err}");
+    }
+}
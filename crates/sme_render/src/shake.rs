@@ -0,0 +1,146 @@
+//! Trauma-based screen shake for the render camera.
+//!
+//! Impacts add "trauma" in `0..=1`, which decays linearly per fixed step. The
+//! shake offset scales with trauma squared, so small hits barely register and
+//! big ones stack into a strong jolt. Offsets come from 1D Perlin noise sampled
+//! along simulated time, with decorrelated channels for x and y; that reads as
+//! a rattle rather than the flicker of per-frame random offsets.
+//!
+//! Shake is presentation only: the offset is applied when building the camera
+//! uniform and never moves `Camera2D::position`, so collision, Lua and replays
+//! see the same camera with or without it.
+
+use glam::Vec2;
+
+#[derive(Debug, Clone)]
+pub struct CameraShake {
+    trauma: f32,
+    /// Simulated seconds since the current shake started.
+    time: f32,
+    /// Trauma lost per simulated second.
+    pub decay: f32,
+    /// Offset at full trauma, in screen pixels.
+    pub max_offset_px: f32,
+    /// Noise samples per simulated second; higher is more jittery.
+    pub frequency: f32,
+}
+
+impl Default for CameraShake {
+    fn default() -> Self {
+        Self {
+            trauma: 0.0,
+            time: 0.0,
+            decay: 1.2,
+            max_offset_px: 12.0,
+            frequency: 25.0,
+        }
+    }
+}
+
+impl CameraShake {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Add trauma; the total saturates at 1.
+    pub fn add_trauma(&mut self, amount: f32) {
+        self.trauma = (self.trauma + amount.max(0.0)).min(1.0);
+    }
+
+    pub fn trauma(&self) -> f32 {
+        self.trauma
+    }
+
+    pub fn reset(&mut self) {
+        self.trauma = 0.0;
+        self.time = 0.0;
+    }
+
+    /// Advance one fixed step.
+    pub fn step(&mut self, dt: f32) {
+        if self.trauma <= 0.0 {
+            self.time = 0.0;
+            return;
+        }
+        self.time += dt;
+        self.trauma = (self.trauma - self.decay * dt).max(0.0);
+    }
+
+    /// Current offset in screen pixels. `limit` caps the intensity (trauma
+    /// squared) so a fidelity tier can keep shake gentle.
+    pub fn offset_px(&self, limit: f32) -> Vec2 {
+        let intensity = (self.trauma * self.trauma).min(limit.clamp(0.0, 1.0));
+        if intensity <= 0.0 {
+            return Vec2::ZERO;
+        }
+        let t = self.time * self.frequency;
+        Vec2::new(perlin_1d(0x5eed_0001, t), perlin_1d(0x5eed_0002, t))
+            * self.max_offset_px
+            * intensity
+    }
+}
+
+/// Gradient noise in roughly `-1..=1`, zero at integer `t`.
+fn perlin_1d(seed: u32, t: f32) -> f32 {
+    let cell = t.floor();
+    let f = t - cell;
+    let gradient = |i: i32| {
+        let mut h = (i as u32).wrapping_mul(0x9e37_79b9) ^ seed;
+        h ^= h >> 16;
+        h = h.wrapping_mul(0x85eb_ca6b);
+        h ^= h >> 13;
+        (h & 0xffff) as f32 / 32767.5 - 1.0
+    };
+    let a = gradient(cell as i32) * f;
+    let b = gradient(cell as i32 + 1) * (f - 1.0);
+    let u = f * f * (3.0 - 2.0 * f);
+    (a + (b - a) * u) * 2.0
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const DT: f32 = 1.0 / 60.0;
+
+    #[test]
+    fn trauma_saturates_and_decays_to_rest() {
+        let mut shake = CameraShake::new();
+        assert_eq!(shake.offset_px(1.0), Vec2::ZERO);
+        shake.add_trauma(0.7);
+        shake.add_trauma(0.7);
+        assert_eq!(shake.trauma(), 1.0);
+
+        let mut moved = false;
+        for _ in 0..120 {
+            shake.step(DT);
+            let offset = shake.offset_px(1.0);
+            assert!(offset.abs().max_element() <= shake.max_offset_px);
+            moved |= offset != Vec2::ZERO;
+        }
+        assert!(moved);
+        assert_eq!(shake.trauma(), 0.0);
+        assert_eq!(shake.offset_px(1.0), Vec2::ZERO);
+    }
+
+    #[test]
+    fn limit_caps_intensity_deterministically() {
+        let run = |limit: f32| {
+            let mut shake = CameraShake::new();
+            shake.add_trauma(1.0);
+            (0..30)
+                .map(|_| {
+                    shake.step(DT);
+                    shake.offset_px(limit)
+                })
+                .collect::<Vec<_>>()
+        };
+        let full = run(1.0);
+        assert_eq!(full, run(1.0));
+        let gentle = run(0.25);
+        for offset in &gentle {
+            assert!(offset.abs().max_element() <= 12.0 * 0.25 + 1e-4);
+        }
+        assert!(gentle[0].length() < full[0].length());
+    }
+}
@@ -1,56 +1,95 @@
-use crate::texture::Texture;
+use std::collections::HashMap;
+
+use wgpu::naga;
+
+use crate::material::{material_shader_source, validate_material};
+use crate::texture::{SamplerDesc, Texture, TextureId};
 use crate::vertex::SpriteVertex;
 
-const SHADER_SRC: &str = r#"
-struct CameraUniform {
-    view_proj: mat4x4<f32>,
-};
-@group(0) @binding(0) var<uniform> camera: CameraUniform;
-
-@group(1) @binding(0) var t_diffuse: texture_2d<f32>;
-@group(1) @binding(1) var s_diffuse: sampler;
-
-struct VertexInput {
-    @location(0) position: vec2<f32>,
-    @location(1) tex_coords: vec2<f32>,
-    @location(2) color: vec4<f32>,
-};
-
-struct VertexOutput {
-    @builtin(position) clip_position: vec4<f32>,
-    @location(0) tex_coords: vec2<f32>,
-    @location(1) color: vec4<f32>,
-};
-
-@vertex
-fn vs_main(in: VertexInput) -> VertexOutput {
-    var out: VertexOutput;
-    out.clip_position = camera.view_proj * vec4<f32>(in.position, 0.0, 1.0);
-    out.tex_coords = in.tex_coords;
-    out.color = in.color;
-    return out;
+/// Sprite shader files. `SpritePipeline::reload_shaders` swaps in edited
+/// copies at runtime; the built-in copies below are used until then.
+pub const SPRITE_SHADER_PATH: &str = "assets/shaders/sprite.wgsl";
+pub const SPRITE_EFFECT_SHADER_PATH: &str = "assets/shaders/sprite_effects.wgsl";
+
+/// Bindings, vertex stage and the fragment stage of the plain pipeline.
+pub const BUILTIN_SHADER_SRC: &str = include_str!("../../../assets/shaders/sprite.wgsl");
+
+/// Extra fragment entry point for `SpriteEffect`s, appended to the sprite
+/// shader. Kept out of the plain pipeline so ordinary sprites don't pay for
+/// the branches and outline taps.
+pub const BUILTIN_EFFECT_SHADER_SRC: &str =
+    include_str!("../../../assets/shaders/sprite_effects.wgsl");
+
+/// How sprite textures store color. All sprite pipelines share one mode, and
+/// every atlas they draw must have been packed for it.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum AlphaMode {
+    /// Color independent of alpha, blended as `src * a + dst * (1 - a)`.
+    #[default]
+    Straight,
+    /// Color already multiplied by alpha (`sme_atlas_packer --premultiply`),
+    /// blended as `src + dst * (1 - a)`. Filtering then never pulls the color
+    /// of transparent texels into sprite edges, which removes dark fringes.
+    Premultiplied,
 }
 
-@fragment
-fn fs_main(in: VertexOutput) -> @location(0) vec4<f32> {
-    let tex_color = textureSample(t_diffuse, s_diffuse, in.tex_coords);
-    return tex_color * in.color;
+impl AlphaMode {
+    fn blend_state(self) -> wgpu::BlendState {
+        match self {
+            Self::Straight => wgpu::BlendState::ALPHA_BLENDING,
+            Self::Premultiplied => wgpu::BlendState::PREMULTIPLIED_ALPHA_BLENDING,
+        }
+    }
+
+    /// Values for the sprite shader's `premultiplied_alpha` override. Left
+    /// out in straight mode, so shaders predating it still build.
+    fn shader_constants(self) -> HashMap<String, f64> {
+        match self {
+            Self::Straight => HashMap::new(),
+            Self::Premultiplied => HashMap::from([(PREMULTIPLIED_OVERRIDE.to_string(), 1.0)]),
+        }
+    }
 }
-"#;
+
+/// Pipeline-overridable constant in `sprite.wgsl` that `AlphaMode` sets.
+const PREMULTIPLIED_OVERRIDE: &str = "premultiplied_alpha";
 
 pub struct SpritePipeline {
     pub render_pipeline: wgpu::RenderPipeline,
+    /// Same layout and vertex format, with `SpriteEffect`s in the fragment stage.
+    pub effect_pipeline: wgpu::RenderPipeline,
     pub camera_bind_group_layout: wgpu::BindGroupLayout,
+    /// Group 1: the sprite texture, its sampler and the secondary texture.
     pub texture_bind_group_layout: wgpu::BindGroupLayout,
+    /// Group 2 of material pipelines: the `MaterialUniform` buffer.
+    pub material_bind_group_layout: wgpu::BindGroupLayout,
+    pipeline_layout: wgpu::PipelineLayout,
+    material_pipeline_layout: wgpu::PipelineLayout,
+    surface_format: wgpu::TextureFormat,
+    /// 1x1 transparent black, bound where a sprite texture has no secondary.
+    neutral_secondary: wgpu::TextureView,
+    /// Sprite shader the current pipelines were built from; materials are
+    /// composed on top of it.
+    shader_src: String,
+    effect_src: String,
+    alpha_mode: AlphaMode,
+    /// One sampler per `SamplerDesc` in use.
+    samplers: HashMap<SamplerDesc, wgpu::Sampler>,
+    /// Texture bind groups by what they bind; see `texture_bind_group`.
+    bind_groups: HashMap<TextureBinding, wgpu::BindGroup>,
+}
+
+/// What a group 1 bind group binds: a sprite texture, its secondary texture
+/// if any, and the sampler for both.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct TextureBinding {
+    pub texture: TextureId,
+    pub secondary: Option<TextureId>,
+    pub sampler: SamplerDesc,
 }
 
 impl SpritePipeline {
     pub fn new(device: &wgpu::Device, surface_format: wgpu::TextureFormat) -> Self {
-        let shader = device.create_shader_module(wgpu::ShaderModuleDescriptor {
-            label: Some("Sprite Shader"),
-            source: wgpu::ShaderSource::Wgsl(SHADER_SRC.into()),
-        });
-
         let camera_bind_group_layout =
             device.create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
                 label: Some("Camera Bind Group Layout"),
@@ -86,54 +125,198 @@ impl SpritePipeline {
                         ty: wgpu::BindingType::Sampler(wgpu::SamplerBindingType::Filtering),
                         count: None,
                     },
+                    wgpu::BindGroupLayoutEntry {
+                        binding: 2,
+                        visibility: wgpu::ShaderStages::FRAGMENT,
+                        ty: wgpu::BindingType::Texture {
+                            sample_type: wgpu::TextureSampleType::Float { filterable: true },
+                            view_dimension: wgpu::TextureViewDimension::D2,
+                            multisampled: false,
+                        },
+                        count: None,
+                    },
                 ],
             });
 
+        // Never written: wgpu zero-initializes textures.
+        let neutral_secondary = device
+            .create_texture(&wgpu::TextureDescriptor {
+                label: Some("Neutral Secondary Texture"),
+                size: wgpu::Extent3d {
+                    width: 1,
+                    height: 1,
+                    depth_or_array_layers: 1,
+                },
+                mip_level_count: 1,
+                sample_count: 1,
+                dimension: wgpu::TextureDimension::D2,
+                format: wgpu::TextureFormat::Rgba8Unorm,
+                usage: wgpu::TextureUsages::TEXTURE_BINDING,
+                view_formats: &[],
+            })
+            .create_view(&wgpu::TextureViewDescriptor::default());
+
         let pipeline_layout = device.create_pipeline_layout(&wgpu::PipelineLayoutDescriptor {
             label: Some("Sprite Pipeline Layout"),
             bind_group_layouts: &[&camera_bind_group_layout, &texture_bind_group_layout],
             push_constant_ranges: &[],
         });
 
-        let render_pipeline = device.create_render_pipeline(&wgpu::RenderPipelineDescriptor {
-            label: Some("Sprite Render Pipeline"),
-            layout: Some(&pipeline_layout),
-            vertex: wgpu::VertexState {
-                module: &shader,
-                entry_point: Some("vs_main"),
-                buffers: &[SpriteVertex::layout()],
-                compilation_options: Default::default(),
-            },
-            fragment: Some(wgpu::FragmentState {
-                module: &shader,
-                entry_point: Some("fs_main"),
-                targets: &[Some(wgpu::ColorTargetState {
-                    format: surface_format,
-                    blend: Some(wgpu::BlendState::ALPHA_BLENDING),
-                    write_mask: wgpu::ColorWrites::ALL,
-                })],
-                compilation_options: Default::default(),
-            }),
-            primitive: wgpu::PrimitiveState {
-                topology: wgpu::PrimitiveTopology::TriangleList,
-                strip_index_format: None,
-                front_face: wgpu::FrontFace::Ccw,
-                cull_mode: None,
-                polygon_mode: wgpu::PolygonMode::Fill,
-                unclipped_depth: false,
-                conservative: false,
-            },
-            depth_stencil: None,
-            multisample: wgpu::MultisampleState::default(),
-            multiview: None,
-            cache: None,
-        });
+        let material_bind_group_layout =
+            device.create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
+                label: Some("Material Bind Group Layout"),
+                entries: &[wgpu::BindGroupLayoutEntry {
+                    binding: 0,
+                    visibility: wgpu::ShaderStages::FRAGMENT,
+                    ty: wgpu::BindingType::Buffer {
+                        ty: wgpu::BufferBindingType::Uniform,
+                        has_dynamic_offset: false,
+                        min_binding_size: None,
+                    },
+                    count: None,
+                }],
+            });
+        let material_pipeline_layout =
+            device.create_pipeline_layout(&wgpu::PipelineLayoutDescriptor {
+                label: Some("Sprite Material Pipeline Layout"),
+                bind_group_layouts: &[
+                    &camera_bind_group_layout,
+                    &texture_bind_group_layout,
+                    &material_bind_group_layout,
+                ],
+                push_constant_ranges: &[],
+            });
+
+        let (render_pipeline, effect_pipeline) = create_sprite_pipelines(
+            device,
+            &pipeline_layout,
+            BUILTIN_SHADER_SRC,
+            BUILTIN_EFFECT_SHADER_SRC,
+            surface_format,
+            AlphaMode::Straight,
+        );
 
         Self {
             render_pipeline,
+            effect_pipeline,
             camera_bind_group_layout,
             texture_bind_group_layout,
+            material_bind_group_layout,
+            pipeline_layout,
+            material_pipeline_layout,
+            surface_format,
+            neutral_secondary,
+            shader_src: BUILTIN_SHADER_SRC.to_string(),
+            effect_src: BUILTIN_EFFECT_SHADER_SRC.to_string(),
+            alpha_mode: AlphaMode::Straight,
+            samplers: HashMap::new(),
+            bind_groups: HashMap::new(),
+        }
+    }
+
+    pub fn alpha_mode(&self) -> AlphaMode {
+        self.alpha_mode
+    }
+
+    /// Rebuild the plain and effect pipelines for textures stored in `mode`.
+    /// Material pipelines keep the blend state they were built with, so
+    /// callers rebuild those afterwards.
+    pub fn set_alpha_mode(&mut self, device: &wgpu::Device, mode: AlphaMode) -> Result<(), String> {
+        if mode == self.alpha_mode {
+            return Ok(());
         }
+        check_alpha_mode_support(&self.shader_src, mode)?;
+        let (render_pipeline, effect_pipeline) = create_sprite_pipelines(
+            device,
+            &self.pipeline_layout,
+            &self.shader_src,
+            &self.effect_src,
+            self.surface_format,
+            mode,
+        );
+        self.render_pipeline = render_pipeline;
+        self.effect_pipeline = effect_pipeline;
+        self.alpha_mode = mode;
+        Ok(())
+    }
+
+    /// Rebuild the plain and effect pipelines from edited shader sources.
+    /// Both are validated first; on error the current pipelines stay in use.
+    /// Material pipelines embed the old sprite shader, so callers rebuild
+    /// those afterwards.
+    pub fn reload_shaders(
+        &mut self,
+        device: &wgpu::Device,
+        shader_src: &str,
+        effect_src: &str,
+    ) -> Result<(), String> {
+        validate_wgsl(
+            shader_src,
+            &[
+                ("vs_main", naga::ShaderStage::Vertex),
+                ("fs_main", naga::ShaderStage::Fragment),
+            ],
+        )?;
+        validate_wgsl(
+            &format!("{shader_src}{effect_src}"),
+            &[("fs_effect", naga::ShaderStage::Fragment)],
+        )
+        .map_err(|e| format!("{SPRITE_EFFECT_SHADER_PATH}: {e}"))?;
+        check_alpha_mode_support(shader_src, self.alpha_mode)?;
+        let (render_pipeline, effect_pipeline) = create_sprite_pipelines(
+            device,
+            &self.pipeline_layout,
+            shader_src,
+            effect_src,
+            self.surface_format,
+            self.alpha_mode,
+        );
+        self.render_pipeline = render_pipeline;
+        self.effect_pipeline = effect_pipeline;
+        self.shader_src = shader_src.to_string();
+        self.effect_src = effect_src.to_string();
+        Ok(())
+    }
+
+    /// Build a pipeline for a layer material snippet (see `material`). The
+    /// snippet is validated first; errors come back as readable strings.
+    pub fn create_material_pipeline(
+        &self,
+        device: &wgpu::Device,
+        label: &str,
+        snippet: &str,
+    ) -> Result<wgpu::RenderPipeline, String> {
+        validate_material(&self.shader_src, snippet)?;
+        let shader = device.create_shader_module(wgpu::ShaderModuleDescriptor {
+            label: Some(label),
+            source: wgpu::ShaderSource::Wgsl(
+                material_shader_source(&self.shader_src, snippet).into(),
+            ),
+        });
+        Ok(create_sprite_pipeline(
+            device,
+            &self.material_pipeline_layout,
+            &shader,
+            "fs_material",
+            label,
+            self.surface_format,
+            self.alpha_mode,
+        ))
+    }
+
+    pub fn create_material_bind_group(
+        &self,
+        device: &wgpu::Device,
+        buffer: &wgpu::Buffer,
+    ) -> wgpu::BindGroup {
+        device.create_bind_group(&wgpu::BindGroupDescriptor {
+            label: Some("Material Bind Group"),
+            layout: &self.material_bind_group_layout,
+            entries: &[wgpu::BindGroupEntry {
+                binding: 0,
+                resource: buffer.as_entire_binding(),
+            }],
+        })
     }
 
     pub fn create_camera_bind_group(
@@ -151,10 +334,65 @@ impl SpritePipeline {
         })
     }
 
+    /// Bind `texture` with an optional secondary texture (normal map or
+    /// emissive mask) sampled at `SpriteVertex::tex_coords2`.
     pub fn create_texture_bind_group(
         &self,
         device: &wgpu::Device,
         texture: &Texture,
+        secondary: Option<&Texture>,
+    ) -> wgpu::BindGroup {
+        self.build_texture_bind_group(device, texture, secondary, &texture.sampler)
+    }
+
+    /// The bind group for `texture` and `secondary` sampled with `sampler`,
+    /// made on first use and cached after. Cached groups keep their textures
+    /// alive: call `invalidate_texture` when dropping one.
+    pub fn texture_bind_group(
+        &mut self,
+        device: &wgpu::Device,
+        texture: &Texture,
+        secondary: Option<&Texture>,
+        sampler: SamplerDesc,
+    ) -> &wgpu::BindGroup {
+        let binding = TextureBinding {
+            texture: texture.id,
+            secondary: secondary.map(|t| t.id),
+            sampler,
+        };
+        if !self.bind_groups.contains_key(&binding) {
+            self.samplers
+                .entry(sampler)
+                .or_insert_with(|| sampler.create(device));
+            let group =
+                self.build_texture_bind_group(device, texture, secondary, &self.samplers[&sampler]);
+            self.bind_groups.insert(binding, group);
+        }
+        &self.bind_groups[&binding]
+    }
+
+    /// A bind group `texture_bind_group` already made.
+    pub fn cached_texture_bind_group(&self, binding: &TextureBinding) -> Option<&wgpu::BindGroup> {
+        self.bind_groups.get(binding)
+    }
+
+    /// Forget every cached bind group using `texture`, as its sprite texture
+    /// or its secondary.
+    pub fn invalidate_texture(&mut self, texture: TextureId) {
+        self.bind_groups
+            .retain(|binding, _| binding.texture != texture && binding.secondary != Some(texture));
+    }
+
+    pub fn cached_bind_group_count(&self) -> usize {
+        self.bind_groups.len()
+    }
+
+    fn build_texture_bind_group(
+        &self,
+        device: &wgpu::Device,
+        texture: &Texture,
+        secondary: Option<&Texture>,
+        sampler: &wgpu::Sampler,
     ) -> wgpu::BindGroup {
         device.create_bind_group(&wgpu::BindGroupDescriptor {
             label: Some("Texture Bind Group"),
@@ -166,9 +404,193 @@ impl SpritePipeline {
                 },
                 wgpu::BindGroupEntry {
                     binding: 1,
-                    resource: wgpu::BindingResource::Sampler(&texture.sampler),
+                    resource: wgpu::BindingResource::Sampler(sampler),
+                },
+                wgpu::BindGroupEntry {
+                    binding: 2,
+                    resource: wgpu::BindingResource::TextureView(
+                        secondary.map_or(&self.neutral_secondary, |t| &t.view),
+                    ),
                 },
             ],
         })
     }
 }
+
+/// Parse and validate WGSL with naga and check that the entry points the
+/// pipelines use exist, so bad shader edits come back as readable errors
+/// instead of device panics.
+pub(crate) fn validate_wgsl(
+    source: &str,
+    entry_points: &[(&str, naga::ShaderStage)],
+) -> Result<(), String> {
+    let module = naga::front::wgsl::parse_str(source)
+        .map_err(|e| format!("WGSL parse error: {}", e.emit_to_string(source)))?;
+    naga::valid::Validator::new(
+        naga::valid::ValidationFlags::all(),
+        naga::valid::Capabilities::empty(),
+    )
+    .validate(&module)
+    .map_err(|e| format!("WGSL validation error: {}", e.emit_to_string(source)))?;
+    for (name, stage) in entry_points {
+        if !module
+            .entry_points
+            .iter()
+            .any(|ep| ep.name == *name && ep.stage == *stage)
+        {
+            return Err(format!("missing {stage:?} entry point '{name}'"));
+        }
+    }
+    Ok(())
+}
+
+/// Premultiplied textures need the sprite shader's `premultiplied_alpha`
+/// override; an edited shader without it can only draw straight alpha.
+fn check_alpha_mode_support(shader_src: &str, mode: AlphaMode) -> Result<(), String> {
+    if mode == AlphaMode::Straight {
+        return Ok(());
+    }
+    let module = naga::front::wgsl::parse_str(shader_src)
+        .map_err(|e| format!("WGSL parse error: {}", e.emit_to_string(shader_src)))?;
+    if module
+        .overrides
+        .iter()
+        .any(|(_, o)| o.name.as_deref() == Some(PREMULTIPLIED_OVERRIDE))
+    {
+        Ok(())
+    } else {
+        Err(format!(
+            "{SPRITE_SHADER_PATH}: premultiplied-alpha atlases need the '{PREMULTIPLIED_OVERRIDE}' override"
+        ))
+    }
+}
+
+/// The plain and effect pipelines for one version of the sprite shader.
+fn create_sprite_pipelines(
+    device: &wgpu::Device,
+    layout: &wgpu::PipelineLayout,
+    shader_src: &str,
+    effect_src: &str,
+    surface_format: wgpu::TextureFormat,
+    alpha_mode: AlphaMode,
+) -> (wgpu::RenderPipeline, wgpu::RenderPipeline) {
+    let shader = device.create_shader_module(wgpu::ShaderModuleDescriptor {
+        label: Some("Sprite Shader"),
+        source: wgpu::ShaderSource::Wgsl(shader_src.into()),
+    });
+    let effect_shader = device.create_shader_module(wgpu::ShaderModuleDescriptor {
+        label: Some("Sprite Effect Shader"),
+        source: wgpu::ShaderSource::Wgsl(format!("{shader_src}{effect_src}").into()),
+    });
+    let render_pipeline = create_sprite_pipeline(
+        device,
+        layout,
+        &shader,
+        "fs_main",
+        "Sprite Render Pipeline",
+        surface_format,
+        alpha_mode,
+    );
+    let effect_pipeline = create_sprite_pipeline(
+        device,
+        layout,
+        &effect_shader,
+        "fs_effect",
+        "Sprite Effect Pipeline",
+        surface_format,
+        alpha_mode,
+    );
+    (render_pipeline, effect_pipeline)
+}
+
+fn create_sprite_pipeline(
+    device: &wgpu::Device,
+    layout: &wgpu::PipelineLayout,
+    shader: &wgpu::ShaderModule,
+    fragment_entry: &str,
+    label: &str,
+    surface_format: wgpu::TextureFormat,
+    alpha_mode: AlphaMode,
+) -> wgpu::RenderPipeline {
+    let constants = alpha_mode.shader_constants();
+    device.create_render_pipeline(&wgpu::RenderPipelineDescriptor {
+        label: Some(label),
+        layout: Some(layout),
+        vertex: wgpu::VertexState {
+            module: shader,
+            entry_point: Some("vs_main"),
+            buffers: &[SpriteVertex::layout()],
+            compilation_options: Default::default(),
+        },
+        fragment: Some(wgpu::FragmentState {
+            module: shader,
+            entry_point: Some(fragment_entry),
+            targets: &[Some(wgpu::ColorTargetState {
+                format: surface_format,
+                blend: Some(alpha_mode.blend_state()),
+                write_mask: wgpu::ColorWrites::ALL,
+            })],
+            compilation_options: wgpu::PipelineCompilationOptions {
+                constants: &constants,
+                ..Default::default()
+            },
+        }),
+        primitive: wgpu::PrimitiveState {
+            topology: wgpu::PrimitiveTopology::TriangleList,
+            strip_index_format: None,
+            front_face: wgpu::FrontFace::Ccw,
+            cull_mode: None,
+            polygon_mode: wgpu::PolygonMode::Fill,
+            unclipped_depth: false,
+            conservative: false,
+        },
+        depth_stencil: None,
+        multisample: wgpu::MultisampleState::default(),
+        multiview: None,
+        cache: None,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn builtin_shaders_validate() {
+        validate_wgsl(
+            BUILTIN_SHADER_SRC,
+            &[
+                ("vs_main", naga::ShaderStage::Vertex),
+                ("fs_main", naga::ShaderStage::Fragment),
+            ],
+        )
+        .expect("sprite shader");
+        validate_wgsl(
+            &format!("{BUILTIN_SHADER_SRC}{BUILTIN_EFFECT_SHADER_SRC}"),
+            &[("fs_effect", naga::ShaderStage::Fragment)],
+        )
+        .expect("effect shader");
+    }
+
+    #[test]
+    fn premultiplied_alpha_needs_the_shader_override() {
+        check_alpha_mode_support(BUILTIN_SHADER_SRC, AlphaMode::Premultiplied)
+            .expect("builtin shader has the override");
+        let old = BUILTIN_SHADER_SRC.replace(
+            "override premultiplied_alpha: bool = false;",
+            "const premultiplied_alpha: bool = false;",
+        );
+        check_alpha_mode_support(&old, AlphaMode::Straight).expect("straight needs nothing");
+        let err = check_alpha_mode_support(&old, AlphaMode::Premultiplied)
+            .expect_err("no override to set");
+        assert!(err.contains(PREMULTIPLIED_OVERRIDE), "{err}");
+    }
+
+    #[test]
+    fn missing_entry_point_is_an_error() {
+        let renamed = BUILTIN_SHADER_SRC.replace("fn fs_main", "fn fs_plain");
+        let err = validate_wgsl(&renamed, &[("fs_main", naga::ShaderStage::Fragment)])
+            .expect_err("fs_main was renamed");
+        assert!(err.contains("fs_main"), "{err}");
+    }
+}
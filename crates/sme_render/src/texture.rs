@@ -1,8 +1,67 @@
+use std::sync::atomic::{AtomicU64, Ordering};
+
 use wgpu;
 
+/// Identifies one GPU texture allocation for the life of the process, for
+/// caches of bind groups that reference it. `Texture::rewrite` keeps the id;
+/// a texture created again gets a new one.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct TextureId(u64);
+
+impl TextureId {
+    fn next() -> Self {
+        static NEXT: AtomicU64 = AtomicU64::new(0);
+        Self(NEXT.fetch_add(1, Ordering::Relaxed))
+    }
+}
+
+/// How a sprite texture is sampled. The default, nearest and clamped, keeps
+/// pixel art crisp.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
+pub struct SamplerDesc {
+    pub filter: SamplerFilter,
+    pub address: SamplerAddress,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
+pub enum SamplerFilter {
+    #[default]
+    Nearest,
+    Linear,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
+pub enum SamplerAddress {
+    #[default]
+    Clamp,
+    Repeat,
+}
+
+impl SamplerDesc {
+    pub fn create(self, device: &wgpu::Device) -> wgpu::Sampler {
+        let filter = match self.filter {
+            SamplerFilter::Nearest => wgpu::FilterMode::Nearest,
+            SamplerFilter::Linear => wgpu::FilterMode::Linear,
+        };
+        let address = match self.address {
+            SamplerAddress::Clamp => wgpu::AddressMode::ClampToEdge,
+            SamplerAddress::Repeat => wgpu::AddressMode::Repeat,
+        };
+        device.create_sampler(&wgpu::SamplerDescriptor {
+            address_mode_u: address,
+            address_mode_v: address,
+            mag_filter: filter,
+            min_filter: filter,
+            ..Default::default()
+        })
+    }
+}
+
 pub struct Texture {
+    pub id: TextureId,
     pub texture: wgpu::Texture,
     pub view: wgpu::TextureView,
+    /// `SamplerDesc::default()`, for bind groups made without the cache.
     pub sampler: wgpu::Sampler,
     pub size: (u32, u32),
 }
@@ -14,60 +73,62 @@ impl Texture {
         bytes: &[u8],
         label: &str,
     ) -> Self {
-        let img = image::load_from_memory(bytes)
-            .expect("Failed to decode image")
-            .to_rgba8();
-        let dimensions = img.dimensions();
-
-        let tex_size = wgpu::Extent3d {
-            width: dimensions.0,
-            height: dimensions.1,
-            depth_or_array_layers: 1,
-        };
+        Self::decode(
+            device,
+            queue,
+            bytes,
+            label,
+            wgpu::TextureFormat::Rgba8UnormSrgb,
+        )
+    }
 
-        let texture = device.create_texture(&wgpu::TextureDescriptor {
-            label: Some(label),
-            size: tex_size,
-            mip_level_count: 1,
-            sample_count: 1,
-            dimension: wgpu::TextureDimension::D2,
-            format: wgpu::TextureFormat::Rgba8UnormSrgb,
-            usage: wgpu::TextureUsages::TEXTURE_BINDING | wgpu::TextureUsages::COPY_DST,
-            view_formats: &[],
-        });
+    /// Like `from_bytes`, but the texels are data rather than color (normal
+    /// maps), so they are sampled without sRGB decoding.
+    pub fn from_bytes_linear(
+        device: &wgpu::Device,
+        queue: &wgpu::Queue,
+        bytes: &[u8],
+        label: &str,
+    ) -> Self {
+        Self::decode(device, queue, bytes, label, wgpu::TextureFormat::Rgba8Unorm)
+    }
 
-        queue.write_texture(
-            wgpu::TexelCopyTextureInfo {
-                texture: &texture,
-                mip_level: 0,
-                origin: wgpu::Origin3d::ZERO,
-                aspect: wgpu::TextureAspect::All,
-            },
+    /// Like `from_bytes`, but multiplies color by alpha (in linear light)
+    /// for loose images drawn by a premultiplied-alpha pipeline.
+    pub fn from_bytes_premultiplied(
+        device: &wgpu::Device,
+        queue: &wgpu::Queue,
+        bytes: &[u8],
+        label: &str,
+    ) -> Self {
+        let mut img = image::load_from_memory(bytes)
+            .expect("Failed to decode image")
+            .to_rgba8();
+        premultiply_srgb(&mut img);
+        let (width, height) = img.dimensions();
+        Self::upload(
+            device,
+            queue,
             &img,
-            wgpu::TexelCopyBufferLayout {
-                offset: 0,
-                bytes_per_row: Some(4 * dimensions.0),
-                rows_per_image: Some(dimensions.1),
-            },
-            tex_size,
-        );
-
-        let view = texture.create_view(&wgpu::TextureViewDescriptor::default());
-
-        let sampler = device.create_sampler(&wgpu::SamplerDescriptor {
-            address_mode_u: wgpu::AddressMode::ClampToEdge,
-            address_mode_v: wgpu::AddressMode::ClampToEdge,
-            mag_filter: wgpu::FilterMode::Nearest,
-            min_filter: wgpu::FilterMode::Nearest,
-            ..Default::default()
-        });
+            width,
+            height,
+            label,
+            wgpu::TextureFormat::Rgba8UnormSrgb,
+        )
+    }
 
-        Self {
-            texture,
-            view,
-            sampler,
-            size: dimensions,
-        }
+    fn decode(
+        device: &wgpu::Device,
+        queue: &wgpu::Queue,
+        bytes: &[u8],
+        label: &str,
+        format: wgpu::TextureFormat,
+    ) -> Self {
+        let img = image::load_from_memory(bytes)
+            .expect("Failed to decode image")
+            .to_rgba8();
+        let (width, height) = img.dimensions();
+        Self::upload(device, queue, &img, width, height, label, format)
     }
 
     pub fn from_rgba8(
@@ -87,7 +148,26 @@ impl Texture {
             expected_len,
             "from_rgba8 expects width*height*4 bytes"
         );
+        Self::upload(
+            device,
+            queue,
+            rgba,
+            width,
+            height,
+            label,
+            wgpu::TextureFormat::Rgba8UnormSrgb,
+        )
+    }
 
+    fn upload(
+        device: &wgpu::Device,
+        queue: &wgpu::Queue,
+        rgba: &[u8],
+        width: u32,
+        height: u32,
+        label: &str,
+        format: wgpu::TextureFormat,
+    ) -> Self {
         let tex_size = wgpu::Extent3d {
             width,
             height,
@@ -100,41 +180,176 @@ impl Texture {
             mip_level_count: 1,
             sample_count: 1,
             dimension: wgpu::TextureDimension::D2,
-            format: wgpu::TextureFormat::Rgba8UnormSrgb,
+            format,
             usage: wgpu::TextureUsages::TEXTURE_BINDING | wgpu::TextureUsages::COPY_DST,
             view_formats: &[],
         });
 
-        queue.write_texture(
-            wgpu::TexelCopyTextureInfo {
-                texture: &texture,
-                mip_level: 0,
-                origin: wgpu::Origin3d::ZERO,
-                aspect: wgpu::TextureAspect::All,
-            },
-            rgba,
-            wgpu::TexelCopyBufferLayout {
-                offset: 0,
-                bytes_per_row: Some(4 * width),
-                rows_per_image: Some(height),
-            },
-            tex_size,
-        );
+        write_texels(queue, &texture, rgba, width, height);
 
         let view = texture.create_view(&wgpu::TextureViewDescriptor::default());
-        let sampler = device.create_sampler(&wgpu::SamplerDescriptor {
-            address_mode_u: wgpu::AddressMode::ClampToEdge,
-            address_mode_v: wgpu::AddressMode::ClampToEdge,
-            mag_filter: wgpu::FilterMode::Nearest,
-            min_filter: wgpu::FilterMode::Nearest,
-            ..Default::default()
-        });
+        let sampler = SamplerDesc::default().create(device);
 
         Self {
+            id: TextureId::next(),
             texture,
             view,
             sampler,
             size: (width, height),
         }
     }
+
+    /// Replace the texels in place, so the view and every bind group using
+    /// this texture stay valid. Returns false, changing nothing, if `texels`
+    /// is a different size; the caller then needs a new texture.
+    pub fn rewrite(&self, queue: &wgpu::Queue, texels: &Texels) -> bool {
+        if (texels.width, texels.height) != self.size {
+            return false;
+        }
+        write_texels(
+            queue,
+            &self.texture,
+            &texels.rgba,
+            texels.width,
+            texels.height,
+        );
+        true
+    }
+}
+
+/// Decoded RGBA8 pixels of an image file, for `Texture::rewrite`.
+pub struct Texels {
+    pub rgba: Vec<u8>,
+    pub width: u32,
+    pub height: u32,
+}
+
+impl Texels {
+    /// Decode an image file, multiplying color by alpha when `premultiply`
+    /// (as `Texture::from_bytes_premultiplied` does). Unlike the `from_bytes`
+    /// constructors this fails instead of panicking, so a file caught
+    /// half-written by a hot reload is only an error.
+    pub fn decode(bytes: &[u8], premultiply: bool) -> Result<Self, String> {
+        let mut img = image::load_from_memory(bytes)
+            .map_err(|e| format!("Failed to decode image: {e}"))?
+            .to_rgba8();
+        if premultiply {
+            premultiply_srgb(&mut img);
+        }
+        let (width, height) = img.dimensions();
+        Ok(Self {
+            rgba: img.into_raw(),
+            width,
+            height,
+        })
+    }
+}
+
+fn write_texels(
+    queue: &wgpu::Queue,
+    texture: &wgpu::Texture,
+    rgba: &[u8],
+    width: u32,
+    height: u32,
+) {
+    queue.write_texture(
+        wgpu::TexelCopyTextureInfo {
+            texture,
+            mip_level: 0,
+            origin: wgpu::Origin3d::ZERO,
+            aspect: wgpu::TextureAspect::All,
+        },
+        rgba,
+        wgpu::TexelCopyBufferLayout {
+            offset: 0,
+            bytes_per_row: Some(4 * width),
+            rows_per_image: Some(height),
+        },
+        wgpu::Extent3d {
+            width,
+            height,
+            depth_or_array_layers: 1,
+        },
+    );
+}
+
+/// Multiply sRGB-encoded color by alpha. The product is taken in linear
+/// light, matching what the GPU sees after sRGB decoding.
+pub fn premultiply_srgb(rgba: &mut [u8]) {
+    for pixel in rgba.chunks_exact_mut(4) {
+        let alpha = f32::from(pixel[3]) / 255.0;
+        if alpha == 1.0 {
+            continue;
+        }
+        for channel in &mut pixel[..3] {
+            let linear = srgb_to_linear(f32::from(*channel) / 255.0) * alpha;
+            *channel = (linear_to_srgb(linear) * 255.0).round() as u8;
+        }
+    }
+}
+
+/// Divide sRGB-encoded color by alpha, undoing `premultiply_srgb`. Fully
+/// transparent pixels are left black.
+pub fn unpremultiply_srgb(rgba: &mut [u8]) {
+    for pixel in rgba.chunks_exact_mut(4) {
+        let alpha = f32::from(pixel[3]) / 255.0;
+        if alpha == 1.0 || alpha == 0.0 {
+            continue;
+        }
+        for channel in &mut pixel[..3] {
+            let linear = (srgb_to_linear(f32::from(*channel) / 255.0) / alpha).min(1.0);
+            *channel = (linear_to_srgb(linear) * 255.0).round() as u8;
+        }
+    }
+}
+
+fn srgb_to_linear(c: f32) -> f32 {
+    if c <= 0.04045 {
+        c / 12.92
+    } else {
+        ((c + 0.055) / 1.055).powf(2.4)
+    }
+}
+
+fn linear_to_srgb(c: f32) -> f32 {
+    if c <= 0.003_130_8 {
+        c * 12.92
+    } else {
+        1.055 * c.powf(1.0 / 2.4) - 0.055
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn premultiply_scales_linear_color_by_alpha_and_back() {
+        // sRGB 255 at half alpha is linear 0.5, which encodes to 188.
+        let mut rgba = [255, 0, 128, 128, 10, 20, 30, 255, 200, 200, 200, 0];
+        premultiply_srgb(&mut rgba);
+        assert_eq!(rgba, [188, 0, 93, 128, 10, 20, 30, 255, 0, 0, 0, 0]);
+        // Back within one step: 93 was rounded once already.
+        unpremultiply_srgb(&mut rgba);
+        assert_eq!(rgba, [255, 0, 129, 128, 10, 20, 30, 255, 0, 0, 0, 0]);
+    }
+
+    #[test]
+    fn texels_decode_images_and_reject_truncated_files() {
+        let image = image::RgbaImage::from_raw(2, 1, vec![255, 0, 128, 128, 10, 20, 30, 255])
+            .expect("2x1 image");
+        let mut png = Vec::new();
+        image
+            .write_to(&mut std::io::Cursor::new(&mut png), image::ImageFormat::Png)
+            .expect("encode png");
+
+        let texels = Texels::decode(&png, false).expect("decode");
+        assert_eq!((texels.width, texels.height), (2, 1));
+        assert_eq!(texels.rgba, [255, 0, 128, 128, 10, 20, 30, 255]);
+        let texels = Texels::decode(&png, true).expect("decode premultiplied");
+        assert_eq!(&texels.rgba[..4], [188, 0, 93, 128]);
+
+        let err = Texels::decode(&png[..png.len() / 2], false).err();
+        assert!(err.is_some_and(|e| e.contains("Failed to decode")));
+    }
 }
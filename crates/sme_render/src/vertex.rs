@@ -4,6 +4,12 @@ pub struct SpriteVertex {
     pub position: [f32; 2],
     pub tex_coords: [f32; 2],
     pub color: [f32; 4],
+    /// `SpriteEffect::vertex_data`: effect id and parameter. Only read by
+    /// `SpritePipeline::effect_pipeline`.
+    pub effect: [f32; 2],
+    /// UVs into the secondary texture (normal map or emissive mask), from
+    /// the sprite's parallel atlas. Zero when the sprite has none.
+    pub tex_coords2: [f32; 2],
 }
 
 impl SpriteVertex {
@@ -30,7 +36,97 @@ impl SpriteVertex {
                     shader_location: 2,
                     format: wgpu::VertexFormat::Float32x4,
                 },
+                // effect
+                wgpu::VertexAttribute {
+                    offset: std::mem::offset_of!(SpriteVertex, effect) as wgpu::BufferAddress,
+                    shader_location: 3,
+                    format: wgpu::VertexFormat::Float32x2,
+                },
+                // tex_coords2
+                wgpu::VertexAttribute {
+                    offset: std::mem::offset_of!(SpriteVertex, tex_coords2) as wgpu::BufferAddress,
+                    shader_location: 4,
+                    format: wgpu::VertexFormat::Float32x2,
+                },
             ],
         }
     }
 }
+
+/// Per-sprite shader effect. Sprites with an effect are drawn with
+/// `SpritePipeline::effect_pipeline`; everything else stays on the plain one.
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub enum SpriteEffect {
+    #[default]
+    None,
+    /// Blend toward white by the parameter (0..=1), for hit flashes.
+    Flash(f32),
+    /// Drop a noise-selected fraction (0..=1) of texels, for spawn/despawn.
+    Dissolve(f32),
+    /// Outline in the sprite's tint, the parameter wide in texels. The sprite
+    /// needs that much transparent padding for the outline to show.
+    Outline(f32),
+}
+
+impl SpriteEffect {
+    /// Parse the `kind` names used by scene files and Lua.
+    pub fn parse(kind: &str, param: f32) -> Result<Self, String> {
+        let unit = |name: &str| {
+            if (0.0..=1.0).contains(&param) {
+                Ok(param)
+            } else {
+                Err(format!(
+                    "{name} effect parameter {param} must be within 0.0..=1.0"
+                ))
+            }
+        };
+        match kind {
+            "none" => Ok(Self::None),
+            "flash" => unit("flash").map(Self::Flash),
+            "dissolve" => unit("dissolve").map(Self::Dissolve),
+            "outline" if param > 0.0 => Ok(Self::Outline(param)),
+            "outline" => Err(format!("outline effect width {param} must be > 0")),
+            other => Err(format!(
+                "unknown sprite effect '{other}' (expected none, flash, dissolve or outline)"
+            )),
+        }
+    }
+
+    pub fn is_none(&self) -> bool {
+        *self == Self::None
+    }
+
+    /// Value for `SpriteVertex::effect`; ids match the effect shader.
+    pub const fn vertex_data(self) -> [f32; 2] {
+        match self {
+            Self::None => [0.0, 0.0],
+            Self::Flash(amount) => [1.0, amount],
+            Self::Dissolve(amount) => [2.0, amount],
+            Self::Outline(width) => [3.0, width],
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parse_accepts_known_kinds_and_checks_ranges() {
+        assert_eq!(
+            SpriteEffect::parse("flash", 1.0),
+            Ok(SpriteEffect::Flash(1.0))
+        );
+        assert_eq!(
+            SpriteEffect::parse("outline", 2.0),
+            Ok(SpriteEffect::Outline(2.0))
+        );
+        assert!(SpriteEffect::parse("none", 0.0).unwrap().is_none());
+        assert!(SpriteEffect::parse("dissolve", 1.5).is_err());
+        assert!(SpriteEffect::parse("outline", 0.0).is_err());
+        assert!(SpriteEffect::parse("glow", 0.5)
+            .unwrap_err()
+            .contains("unknown sprite effect 'glow'"));
+        assert_eq!(SpriteEffect::Dissolve(0.25).vertex_data(), [2.0, 0.25]);
+    }
+}
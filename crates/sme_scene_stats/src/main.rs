@@ -0,0 +1,139 @@
+//! Scene statistics: loads a scene with its atlases and animations, without
+//! a window, and prints what it costs to draw (sprites per layer, textures
+//! and their GPU memory, batched draw calls, an overdraw estimate), so
+//! content can be checked against mobile budgets before it reaches a device.
+//!
+//! `--heatmap` also writes the overdraw estimate as a PNG, one pixel per
+//! cell: black where nothing draws, then blue, green, yellow, orange and red
+//! for one to five or more overlapping sprites.
+
+use std::path::PathBuf;
+
+use sme_core::tier::FidelityTier;
+use sme_engine::scene_stats::{analyze_scene, SceneStats};
+
+/// Heatmap width in cells unless `--heatmap-width` says otherwise.
+const DEFAULT_HEATMAP_WIDTH: u32 = 256;
+
+fn usage() -> String {
+    "Usage: cargo run -p sme_scene_stats -- [--heatmap <out.png>] [--heatmap-width <cells>] <scene.json>\nExample: cargo run -p sme_scene_stats -- --heatmap overdraw.png assets/scenes/m4_scene.json".to_string()
+}
+
+fn main() -> Result<(), String> {
+    let mut heatmap = None;
+    let mut heatmap_width = DEFAULT_HEATMAP_WIDTH;
+    let mut scene = None;
+    let mut args = std::env::args().skip(1);
+    while let Some(arg) = args.next() {
+        if !arg.starts_with("--") {
+            if scene.replace(PathBuf::from(&arg)).is_some() {
+                return Err(format!("Unexpected argument '{arg}'\n{}", usage()));
+            }
+            continue;
+        }
+        let value = args
+            .next()
+            .ok_or_else(|| format!("{arg} needs a value\n{}", usage()))?;
+        match arg.as_str() {
+            "--heatmap" => heatmap = Some(PathBuf::from(value)),
+            "--heatmap-width" => heatmap_width = parse_count(&arg, &value)?,
+            _ => return Err(format!("Unknown flag '{arg}'\n{}", usage())),
+        }
+    }
+    let scene = scene.ok_or_else(usage)?;
+
+    let stats = analyze_scene(&scene)?;
+    print_stats(&stats);
+
+    match stats.overdraw(heatmap_width) {
+        Some(map) => {
+            println!(
+                "Overdraw (world layers): average {:.2}, max {} on a {}x{} grid",
+                map.average_overdraw(),
+                map.max_overdraw(),
+                map.size.0,
+                map.size.1
+            );
+            if let Some(path) = heatmap {
+                map.save_png(&path)?;
+                println!("Wrote overdraw heatmap to {}", path.display());
+            }
+        }
+        None => {
+            println!("Overdraw (world layers): nothing drawn");
+            if heatmap.is_some() {
+                return Err("No visible world sprites to draw a heatmap of".to_string());
+            }
+        }
+    }
+    Ok(())
+}
+
+fn parse_count(flag: &str, value: &str) -> Result<u32, String> {
+    match value.parse::<u32>() {
+        Ok(0) => Err(format!("{flag} must be at least 1")),
+        Ok(count) => Ok(count),
+        Err(e) => Err(format!("Invalid {flag} '{value}': {e}")),
+    }
+}
+
+fn print_stats(stats: &SceneStats) {
+    println!("Layers:");
+    for layer in &stats.layers {
+        let mut notes = Vec::new();
+        if layer.screen_space {
+            notes.push("screen");
+        }
+        if !layer.visible {
+            notes.push("hidden");
+        }
+        let notes = if notes.is_empty() {
+            String::new()
+        } else {
+            format!(" ({})", notes.join(", "))
+        };
+        println!(
+            "  {:<24} {:>7} sprites, {:>6} animated{notes}",
+            layer.id, layer.sprites, layer.animated
+        );
+    }
+    println!("  {:<24} {:>7} sprites", "total", stats.sprite_count());
+
+    println!("Textures:");
+    for texture in &stats.textures {
+        let half_res = texture
+            .half_res_size
+            .map(|(width, height)| format!(", half-res {width}x{height}"))
+            .unwrap_or_default();
+        println!(
+            "  {} {}x{}{half_res}: {} sprites, {} (Tier 2), {} (Tier 0)",
+            texture.path,
+            texture.size.0,
+            texture.size.1,
+            texture.sprites,
+            mebibytes(texture.bytes(FidelityTier::Tier2)),
+            mebibytes(texture.bytes(FidelityTier::Tier0))
+        );
+    }
+    println!(
+        "  {} unique, {} (Tier 2), {} (Tier 0)",
+        stats.textures.len(),
+        mebibytes(stats.texture_bytes(FidelityTier::Tier2)),
+        mebibytes(stats.texture_bytes(FidelityTier::Tier0))
+    );
+
+    println!(
+        "Draw calls (visible layers, batched): {} world, {} screen",
+        stats.world_draw_calls, stats.screen_draw_calls
+    );
+    if stats.unresolved_sprites > 0 {
+        println!(
+            "Unresolved sprites (not counted in textures or draw calls): {}",
+            stats.unresolved_sprites
+        );
+    }
+}
+
+fn mebibytes(bytes: u64) -> String {
+    format!("{:.2} MiB", bytes as f64 / (1024.0 * 1024.0))
+}
@@ -0,0 +1,117 @@
+//! Script unit tests: runs gameplay scripts against JSON specs of input
+//! steps and expected intents, headless (no window, scene or physics), and
+//! prints PASS or FAIL per case. See `sme_engine::script_test` for the spec
+//! format.
+//!
+//! Arguments are spec files or directories of them; with none, every spec in
+//! `assets/tests/scripts` runs. Scripts resolve against `--root` (default:
+//! the working directory). Exits with an error if any case fails.
+
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use sme_engine::script_test::{load_script_test_from_path, run_script_test};
+
+/// Specs run when no paths are given, relative to the asset root.
+const SCRIPT_TEST_DIR: &str = "assets/tests/scripts";
+
+fn usage() -> String {
+    "Usage: cargo run -p sme_script_test -- [--root <dir>] [<spec.json | dir>...]\nExample: cargo run -p sme_script_test -- assets/tests/scripts/controller.json".to_string()
+}
+
+fn main() -> Result<(), String> {
+    let mut root = PathBuf::from(".");
+    let mut paths = Vec::new();
+    let mut args = std::env::args().skip(1);
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--root" => {
+                root = args
+                    .next()
+                    .map(PathBuf::from)
+                    .ok_or_else(|| format!("--root needs a value\n{}", usage()))?;
+            }
+            "--help" | "-h" => {
+                println!("{}", usage());
+                return Ok(());
+            }
+            _ if arg.starts_with("--") => {
+                return Err(format!("Unknown flag '{arg}'\n{}", usage()));
+            }
+            _ => paths.push(PathBuf::from(arg)),
+        }
+    }
+    if paths.is_empty() {
+        paths.push(root.join(SCRIPT_TEST_DIR));
+    }
+
+    let failed = run_specs(&collect_specs(&paths)?, &root)?;
+    if failed > 0 {
+        return Err(format!("{failed} script test case(s) failed"));
+    }
+    Ok(())
+}
+
+/// `.json` files among `paths`, and in the directories among them, sorted.
+fn collect_specs(paths: &[PathBuf]) -> Result<Vec<PathBuf>, String> {
+    let mut specs = Vec::new();
+    for path in paths {
+        if !path.is_dir() {
+            specs.push(path.clone());
+            continue;
+        }
+        let entries =
+            fs::read_dir(path).map_err(|e| format!("Failed to read {}: {e}", path.display()))?;
+        for entry in entries {
+            let entry = entry.map_err(|e| format!("Failed to read {}: {e}", path.display()))?;
+            let file = entry.path();
+            if file.extension().is_some_and(|ext| ext == "json") {
+                specs.push(file);
+            }
+        }
+    }
+    specs.sort();
+    Ok(specs)
+}
+
+/// Run and report every case of `specs`; returns how many failed.
+fn run_specs(specs: &[PathBuf], root: &Path) -> Result<usize, String> {
+    let mut passed = 0;
+    let mut failed = 0;
+    for path in specs {
+        let spec = load_script_test_from_path(path)?;
+        println!("{} ({})", path.display(), spec.script.display());
+        for (name, result) in run_script_test(&spec, root) {
+            match result {
+                Ok(()) => {
+                    passed += 1;
+                    println!("  PASS {name}");
+                }
+                Err(err) => {
+                    failed += 1;
+                    println!("  FAIL {name}: {err}");
+                }
+            }
+        }
+    }
+    println!("{passed} passed, {failed} failed");
+    Ok(failed)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn shipped_script_tests_pass() {
+        let root = Path::new(env!("CARGO_MANIFEST_DIR")).join("../..");
+        let specs = collect_specs(&[root.join(SCRIPT_TEST_DIR)]).expect("spec dir");
+        assert!(!specs.is_empty(), "no specs in {SCRIPT_TEST_DIR}");
+        for path in &specs {
+            let spec = load_script_test_from_path(path).expect("spec");
+            for (name, result) in run_script_test(&spec, &root) {
+                assert!(result.is_ok(), "{}: {name}: {result:?}", path.display());
+            }
+        }
+    }
+}
@@ -0,0 +1,300 @@
+//! Simulation regression harness: recorded replays run headless against
+//! expected per-step traces.
+//!
+//! A replay directory holds `<name>.json` replays, recorded with
+//! `--record-replay` so they carry a `ReplayHeader`, next to
+//! `<name>.trace.json` files listing the player's position, velocity and
+//! grounded flag after every fixed step. `check_replay_dir` re-simulates each
+//! replay through the real controller and collision code (no window, GPU or
+//! Lua) and reports the first step that leaves the trace, with the inputs and
+//! states around it, so a change to physics feel cannot slip in unnoticed.
+//!
+//! After an intentional physics change, rerun with `SME_BLESS_TRACES=1` to
+//! rewrite the traces from the current code, and review the diff.
+//!
+//! With the `determinism-audit` feature, every replay is also run twice
+//! in-process and the per-step state hashes compared (`check_determinism`).
+
+use std::fmt;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use serde::{Deserialize, Serialize};
+use sme_engine::collision::{load_collision_from_path, CollisionGrid};
+use sme_engine::controller::{CharacterController, ControllerInput};
+use sme_engine::replay::{load_replay_from_path, ReplaySequence};
+
+/// Replays checked by this crate's tests, relative to the workspace root.
+pub const REPLAY_DIR: &str = "assets/tests/replays";
+pub const TRACE_SUFFIX: &str = ".trace.json";
+/// Set to `1` to write traces instead of comparing against them.
+pub const BLESS_ENV: &str = "SME_BLESS_TRACES";
+/// Allowed drift in position (world units) and velocity (units per second).
+pub const TRACE_TOLERANCE: f32 = 1e-3;
+/// Steps shown before the divergence in a report.
+const CONTEXT_STEPS: usize = 3;
+
+/// Player state after one fixed step.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct TraceStep {
+    pub x: f32,
+    pub y: f32,
+    pub vx: f32,
+    pub vy: f32,
+    pub grounded: bool,
+}
+
+impl TraceStep {
+    pub fn of(controller: &CharacterController) -> Self {
+        Self {
+            x: controller.aabb.center_x,
+            y: controller.aabb.center_y,
+            vx: controller.velocity_x,
+            vy: controller.velocity_y,
+            grounded: controller.grounded,
+        }
+    }
+
+    pub fn matches(&self, other: &TraceStep, tolerance: f32) -> bool {
+        self.grounded == other.grounded
+            && [
+                (self.x, other.x),
+                (self.y, other.y),
+                (self.vx, other.vx),
+                (self.vy, other.vy),
+            ]
+            .iter()
+            .all(|(a, b)| (a - b).abs() <= tolerance)
+    }
+}
+
+impl fmt::Display for TraceStep {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "pos ({:.3}, {:.3}) vel ({:.3}, {:.3}){}",
+            self.x,
+            self.y,
+            self.vx,
+            self.vy,
+            if self.grounded { " grounded" } else { "" }
+        )
+    }
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct Trace {
+    pub steps: Vec<TraceStep>,
+}
+
+/// File name of a checked replay and its outcome.
+pub type ReplayOutcome = (String, Result<(), String>);
+
+/// A replay run to completion.
+#[derive(Debug, Clone)]
+pub struct Simulation {
+    pub inputs: Vec<ControllerInput>,
+    pub steps: Vec<TraceStep>,
+}
+
+/// Run `replay` from its header's start state. Headerless replays are
+/// rejected: without a start AABB and controller config there is nothing to
+/// compare against.
+pub fn simulate(replay: &ReplaySequence, grid: &CollisionGrid) -> Result<Simulation, String> {
+    let header = replay
+        .header
+        .as_ref()
+        .ok_or("replay has no header; record it with --record-replay")?;
+    let mut controller = CharacterController::new(header.start);
+    controller.config = header.controller;
+    let inputs = replay.expanded_inputs();
+    let steps = inputs
+        .iter()
+        .map(|input| {
+            controller.step(*input, replay.fixed_dt, grid);
+            TraceStep::of(&controller)
+        })
+        .collect();
+    Ok(Simulation { inputs, steps })
+}
+
+/// Run `replay` twice and compare the full controller state after every
+/// step, bit for bit.
+#[cfg(feature = "determinism-audit")]
+pub fn check_determinism(replay: &ReplaySequence, grid: &CollisionGrid) -> Result<(), String> {
+    use std::hash::Hasher;
+
+    let header = replay
+        .header
+        .as_ref()
+        .ok_or("replay has no header; record it with --record-replay")?;
+    let inputs = replay.expanded_inputs();
+    sme_engine::determinism::audit_runs(|| {
+        let mut controller = CharacterController::new(header.start);
+        controller.config = header.controller;
+        inputs
+            .iter()
+            .map(|input| {
+                controller.step(*input, replay.fixed_dt, grid);
+                let mut hasher = sme_engine::determinism::StateHasher::new();
+                hasher.write_body(&controller);
+                hasher.finish()
+            })
+            .collect()
+    })
+}
+
+/// Compare a run with its expected trace; the error is a readable report of
+/// the first divergence.
+pub fn compare_trace(expected: &[TraceStep], run: &Simulation) -> Result<(), String> {
+    let first_bad = expected
+        .iter()
+        .zip(&run.steps)
+        .position(|(want, got)| !want.matches(got, TRACE_TOLERANCE));
+    let Some(step) = first_bad else {
+        if expected.len() != run.steps.len() {
+            return Err(format!(
+                "trace has {} steps but the replay ran {}",
+                expected.len(),
+                run.steps.len()
+            ));
+        }
+        return Ok(());
+    };
+
+    let mut report = format!("diverged at step {step} of {}:\n", run.steps.len());
+    let first = step.saturating_sub(CONTEXT_STEPS);
+    let window = run.inputs[first..=step]
+        .iter()
+        .zip(&expected[first..=step])
+        .zip(&run.steps[first..=step]);
+    for (i, ((input, want), got)) in (first..).zip(window) {
+        report.push_str(&format!(
+            "{} step {i:>5}  input move_x {:+.2}{}{}{}\n",
+            if i == step { ">" } else { " " },
+            input.move_x,
+            if input.jump_pressed { " jump" } else { "" },
+            if input.crouch { " crouch" } else { "" },
+            if input.dash { " dash" } else { "" }
+        ));
+        report.push_str(&format!("      expected {want}\n"));
+        report.push_str(&format!("      actual   {got}\n"));
+    }
+    Err(report)
+}
+
+/// Trace file belonging to a replay file.
+pub fn trace_path(replay_path: &Path) -> PathBuf {
+    let stem = replay_path
+        .file_stem()
+        .map(|s| s.to_string_lossy().to_string())
+        .unwrap_or_default();
+    replay_path.with_file_name(format!("{stem}{TRACE_SUFFIX}"))
+}
+
+pub fn load_trace(path: &Path) -> Result<Trace, String> {
+    let raw =
+        fs::read_to_string(path).map_err(|e| format!("Failed to read {}: {e}", path.display()))?;
+    serde_json::from_str(&raw).map_err(|e| format!("Failed to parse trace {}: {e}", path.display()))
+}
+
+pub fn save_trace(path: &Path, trace: &Trace) -> Result<(), String> {
+    let json = serde_json::to_string_pretty(trace)
+        .map_err(|e| format!("Failed to serialize trace: {e}"))?;
+    fs::write(path, json).map_err(|e| format!("Failed to write {}: {e}", path.display()))
+}
+
+/// Run one replay and compare it with its trace (or write the trace when
+/// `bless` is set). The header's collision path is resolved against
+/// `asset_root`.
+pub fn check_replay(replay_path: &Path, asset_root: &Path, bless: bool) -> Result<(), String> {
+    let replay = load_replay_from_path(replay_path)?;
+    let collision_path = replay
+        .header
+        .as_ref()
+        .map(|header| asset_root.join(&header.collision_path))
+        .ok_or("replay has no header; record it with --record-replay")?;
+    let grid = load_collision_from_path(&collision_path)?;
+    let run = simulate(&replay, &grid)?;
+    #[cfg(feature = "determinism-audit")]
+    check_determinism(&replay, &grid)?;
+
+    let trace_path = trace_path(replay_path);
+    if bless {
+        return save_trace(&trace_path, &Trace { steps: run.steps });
+    }
+    let expected = load_trace(&trace_path)?;
+    compare_trace(&expected.steps, &run)
+}
+
+/// Check every replay in `dir`, by file name. `bless` comes from `BLESS_ENV`.
+pub fn check_replay_dir(dir: &Path, asset_root: &Path) -> Result<Vec<ReplayOutcome>, String> {
+    let bless = std::env::var(BLESS_ENV).is_ok_and(|v| v == "1");
+    let mut replays: Vec<PathBuf> = fs::read_dir(dir)
+        .map_err(|e| format!("Failed to read {}: {e}", dir.display()))?
+        .filter_map(|entry| entry.ok().map(|e| e.path()))
+        .filter(|path| {
+            let name = path.to_string_lossy();
+            name.ends_with(".json") && !name.ends_with(TRACE_SUFFIX)
+        })
+        .collect();
+    replays.sort();
+    Ok(replays
+        .iter()
+        .map(|path| {
+            let name = path
+                .file_name()
+                .map(|n| n.to_string_lossy().to_string())
+                .unwrap_or_default();
+            (name, check_replay(path, asset_root, bless))
+        })
+        .collect())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn workspace_root() -> PathBuf {
+        Path::new(env!("CARGO_MANIFEST_DIR")).join("../..")
+    }
+
+    #[test]
+    fn recorded_replays_match_their_traces() {
+        let root = workspace_root();
+        let results = check_replay_dir(&root.join(REPLAY_DIR), &root).expect("replay dir");
+        assert!(!results.is_empty(), "no replays in {REPLAY_DIR}");
+        let failures: Vec<String> = results
+            .into_iter()
+            .filter_map(|(name, result)| result.err().map(|err| format!("{name}: {err}")))
+            .collect();
+        assert!(
+            failures.is_empty(),
+            "{}\nIf the change is intended, rerun with {BLESS_ENV}=1.",
+            failures.join("\n")
+        );
+    }
+
+    #[test]
+    fn report_points_at_the_first_divergent_step() {
+        let root = workspace_root();
+        let replay_path = root.join(REPLAY_DIR).join("run_jump_turn.json");
+        let replay = load_replay_from_path(&replay_path).expect("replay");
+        let header = replay.header.as_ref().expect("header");
+        let grid = load_collision_from_path(&root.join(&header.collision_path)).expect("grid");
+        let run = simulate(&replay, &grid).expect("simulate");
+
+        let mut expected = run.steps.clone();
+        assert_eq!(compare_trace(&expected, &run), Ok(()));
+        expected[40].x += 0.5;
+        expected[41].x += 0.5;
+        let report = compare_trace(&expected, &run).expect_err("nudged step");
+        assert!(report.starts_with("diverged at step 40 "), "{report}");
+        assert!(report.contains("> step    40"), "{report}");
+        assert!(report.contains("  step    37"), "{report}");
+
+        expected.truncate(10);
+        let err = compare_trace(&expected, &run).expect_err("short trace");
+        assert!(err.contains("10 steps"), "{err}");
+    }
+}
@@ -0,0 +1,503 @@
+//! Builds the generated content in memory; `main` writes it out.
+//!
+//! Sprites are spread evenly over the layers and placed at random inside a
+//! world area sized so every layer keeps the same density whatever the
+//! sprite count. Each sprite picks an atlas (evenly, or skewed toward the
+//! first atlases to mimic one busy atlas and a long tail) and a random sprite
+//! in it. Animated sprites play a looping clip over their own atlas, so
+//! animation never changes which textures a layer touches. The collision grid
+//! covers the same area: walls, a floor and random platforms, with the
+//! player's spawn corner left clear.
+
+use std::path::Path;
+
+use image::{Rgba, RgbaImage};
+use serde_json::{json, Value};
+use sme_atlas::metadata::{
+    AtlasMetadata, AtlasPivot, AtlasRectPx, AtlasSprite, AtlasTexture, AtlasUvRect,
+    ATLAS_FORMAT_VERSION,
+};
+use sme_atlas::output::normalize_path_for_json;
+use sme_core::rng::DeterministicRng;
+
+/// Atlas cell edge in pixels; every generated sprite fills one cell.
+const CELL_PX: u32 = 32;
+/// World area per sprite on one layer, as the edge of a square.
+const SPACING: f32 = 48.0;
+/// Smallest world area edge, so tiny scenes still have room to move.
+const MIN_WORLD_EXTENT: f32 = 640.0;
+const COLLISION_CELL: i32 = 32;
+/// Rows between platform rows in the collision grid.
+const PLATFORM_EVERY: i32 = 4;
+/// Cells at the spawn corner kept free of platforms.
+const SPAWN_CLEARANCE: i32 = 6;
+const FRAMES_PER_CLIP: usize = 4;
+const FRAME_MS: u64 = 120;
+const ANIMATION_ID: &str = "stress";
+/// Scene format version written by the generator.
+const SCENE_VERSION: &str = "0.3";
+/// Base colors of the atlases, cycled when there are more atlases.
+const PALETTE: [[u8; 3]; 8] = [
+    [220, 80, 70],
+    [70, 160, 220],
+    [90, 190, 90],
+    [230, 180, 60],
+    [170, 100, 210],
+    [60, 200, 190],
+    [230, 120, 170],
+    [150, 150, 150],
+];
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum AtlasDistribution {
+    /// Every atlas is equally likely.
+    Even,
+    /// Atlas `k` is picked with weight `1 / (k + 1)`.
+    Skewed,
+}
+
+impl AtlasDistribution {
+    pub fn parse(name: &str) -> Result<Self, String> {
+        match name {
+            "even" => Ok(Self::Even),
+            "skewed" => Ok(Self::Skewed),
+            other => Err(format!(
+                "unknown distribution '{other}' (expected even or skewed)"
+            )),
+        }
+    }
+
+    fn weights(self, atlases: usize) -> Vec<f64> {
+        (0..atlases)
+            .map(|k| match self {
+                Self::Even => 1.0,
+                Self::Skewed => 1.0 / (k + 1) as f64,
+            })
+            .collect()
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct StressConfig {
+    pub sprites: usize,
+    pub layers: usize,
+    pub atlases: usize,
+    pub sprites_per_atlas: usize,
+    pub distribution: AtlasDistribution,
+    /// Fraction of sprites, 0..=1, that play an animation.
+    pub animated: f32,
+    pub seed: u64,
+}
+
+impl Default for StressConfig {
+    fn default() -> Self {
+        Self {
+            sprites: 10_000,
+            layers: 4,
+            atlases: 4,
+            sprites_per_atlas: 64,
+            distribution: AtlasDistribution::Even,
+            animated: 0.25,
+            seed: 1,
+        }
+    }
+}
+
+pub struct GeneratedAtlas {
+    pub png_path: String,
+    pub json_path: String,
+    pub metadata: AtlasMetadata,
+    pub image: RgbaImage,
+}
+
+pub struct GeneratedContent {
+    pub atlases: Vec<GeneratedAtlas>,
+    pub animation_path: String,
+    pub animation: Value,
+    pub collision_path: String,
+    pub collision: Value,
+    pub scene_path: String,
+    pub scene: Value,
+    pub sprite_count: usize,
+    pub animated_count: usize,
+}
+
+/// Paths in the generated files are `output` joined with the file name, so
+/// run the engine from the directory the generator ran in.
+pub fn generate(config: &StressConfig, output: &Path) -> Result<GeneratedContent, String> {
+    if config.sprites == 0 || config.layers == 0 || config.atlases == 0 {
+        return Err("sprites, layers and atlases must be at least 1".to_string());
+    }
+    if config.sprites_per_atlas < FRAMES_PER_CLIP {
+        return Err(format!(
+            "sprites per atlas must be at least {FRAMES_PER_CLIP}, one per animation frame"
+        ));
+    }
+    if !(0.0..=1.0).contains(&config.animated) {
+        return Err(format!(
+            "animated fraction {} must be within 0..=1",
+            config.animated
+        ));
+    }
+    let path = |name: &str| normalize_path_for_json(&output.join(name));
+    let mut rng = DeterministicRng::new(config.seed);
+
+    let atlases: Vec<GeneratedAtlas> = (0..config.atlases)
+        .map(|index| {
+            let png_path = path(&format!("stress_atlas_{index}.png"));
+            GeneratedAtlas {
+                json_path: path(&format!("stress_atlas_{index}.json")),
+                metadata: atlas_metadata(index, config.sprites_per_atlas, &png_path),
+                image: atlas_image(index, config.sprites_per_atlas),
+                png_path,
+            }
+        })
+        .collect();
+
+    let per_layer = config.sprites.div_ceil(config.layers);
+    let extent = (per_layer as f32).sqrt().ceil() * SPACING;
+    let extent = extent.max(MIN_WORLD_EXTENT);
+    let grid_cells = (extent / COLLISION_CELL as f32).ceil() as i32;
+    let origin = -(grid_cells * COLLISION_CELL) / 2;
+    let world_min = origin as f32;
+    let world_size = (grid_cells * COLLISION_CELL) as f32;
+
+    let weights = config.distribution.weights(config.atlases);
+    let total_weight: f64 = weights.iter().sum();
+    let mut animated_count = 0;
+    let mut layers = Vec::with_capacity(config.layers);
+    for layer in 0..config.layers {
+        let first = layer * per_layer;
+        let count = per_layer.min(config.sprites.saturating_sub(first));
+        let mut sprites = Vec::with_capacity(count);
+        for n in 0..count {
+            let atlas = pick_weighted(&mut rng, &weights, total_weight);
+            let slot = (rng.next_u32() as usize) % config.sprites_per_atlas;
+            let mut sprite = json!({
+                "id": format!("stress_{}", first + n),
+                "sprite_id": atlases[atlas].metadata.sprites[slot].sprite_id,
+                "x": round(world_min + rng.next_f64() as f32 * world_size),
+                "y": round(world_min + rng.next_f64() as f32 * world_size),
+            });
+            let scale = round(0.75 + rng.next_f64() as f32 * 0.75);
+            sprite["scale_x"] = json!(scale);
+            sprite["scale_y"] = json!(scale);
+            if (rng.next_f64() as f32) < config.animated {
+                sprite["animation"] = json!(clip_name(atlas));
+                sprite["animation_source"] = json!(ANIMATION_ID);
+                animated_count += 1;
+            }
+            sprites.push(sprite);
+        }
+        // Back layers drift slower; the front one scrolls with the world
+        // and is y-sorted like gameplay layers.
+        let front = layer + 1 == config.layers;
+        let parallax = 0.5 + 0.5 * (layer + 1) as f32 / config.layers as f32;
+        layers.push(json!({
+            "id": format!("stress_layer_{layer}"),
+            "parallax": round(parallax),
+            "sort_mode": if front { "y" } else { "none" },
+            "sprites": sprites,
+        }));
+    }
+
+    let animation_path = path("stress_anim.json");
+    let collision_path = path("stress_collision.json");
+    let scene_path = path("stress_scene.json");
+    // The engine spawns the player two cells in from the grid's origin.
+    let spawn = (origin + 2 * COLLISION_CELL) as f32;
+    let scene = json!({
+        "version": SCENE_VERSION,
+        "scene_id": "stress",
+        "camera": { "start_x": spawn, "start_y": spawn, "zoom": 1.0 },
+        "atlases": atlases.iter().map(|atlas| atlas.json_path.clone()).collect::<Vec<_>>(),
+        "animations": [animation_path],
+        "layers": layers,
+    });
+
+    Ok(GeneratedContent {
+        animation: animation_file(&atlases),
+        collision: collision_file(&mut rng, grid_cells, origin),
+        atlases,
+        animation_path,
+        collision_path,
+        scene_path,
+        scene,
+        sprite_count: config.sprites,
+        animated_count,
+    })
+}
+
+fn pick_weighted(rng: &mut DeterministicRng, weights: &[f64], total: f64) -> usize {
+    let mut roll = rng.next_f64() * total;
+    for (index, weight) in weights.iter().enumerate() {
+        if roll < *weight {
+            return index;
+        }
+        roll -= weight;
+    }
+    weights.len() - 1
+}
+
+/// Two decimals keep the scene file readable and small.
+fn round(value: f32) -> f32 {
+    (value * 100.0).round() / 100.0
+}
+
+fn clip_name(atlas: usize) -> String {
+    format!("atlas_{atlas}")
+}
+
+/// Square-ish grid of cells with room for `sprites`.
+fn atlas_grid(sprites: usize) -> (u32, u32) {
+    let columns = (sprites as f64).sqrt().ceil() as u32;
+    (columns, (sprites as u32).div_ceil(columns))
+}
+
+fn atlas_metadata(index: usize, sprites: usize, png_path: &str) -> AtlasMetadata {
+    let (columns, rows) = atlas_grid(sprites);
+    let (width, height) = (columns * CELL_PX, rows * CELL_PX);
+    let sprites = (0..sprites as u32)
+        .map(|slot| {
+            let name = format!("stress_{index}_{slot}");
+            let (x, y) = ((slot % columns) * CELL_PX, (slot / columns) * CELL_PX);
+            AtlasSprite {
+                // Stable across runs, like ids from the atlas packer.
+                sprite_id: uuid::Uuid::new_v5(&uuid::Uuid::NAMESPACE_OID, name.as_bytes())
+                    .to_string(),
+                source_path: format!("generated/{name}.png"),
+                name,
+                rect_px: AtlasRectPx {
+                    x,
+                    y,
+                    w: CELL_PX,
+                    h: CELL_PX,
+                },
+                uv: AtlasUvRect {
+                    u0: x as f32 / width as f32,
+                    v0: y as f32 / height as f32,
+                    u1: (x + CELL_PX) as f32 / width as f32,
+                    v1: (y + CELL_PX) as f32 / height as f32,
+                },
+                pivot: AtlasPivot { x: 0.5, y: 0.5 },
+                nine_slice: None,
+                trim: None,
+                rotated: false,
+            }
+        })
+        .collect();
+    AtlasMetadata {
+        version: ATLAS_FORMAT_VERSION.to_string(),
+        atlas_id: format!("stress_atlas_{index}"),
+        texture: AtlasTexture {
+            path: png_path.to_string(),
+            width,
+            height,
+            half_res: None,
+            premultiplied_alpha: false,
+        },
+        sprites,
+    }
+}
+
+/// Cells in the atlas's palette color, shaded per sprite, with a dark border
+/// so individual quads stay visible when they overlap.
+fn atlas_image(index: usize, sprites: usize) -> RgbaImage {
+    let (columns, rows) = atlas_grid(sprites);
+    let base = PALETTE[index % PALETTE.len()];
+    RgbaImage::from_fn(columns * CELL_PX, rows * CELL_PX, |x, y| {
+        let slot = (y / CELL_PX) * columns + x / CELL_PX;
+        let (cx, cy) = (x % CELL_PX, y % CELL_PX);
+        if slot as usize >= sprites {
+            return Rgba([0, 0, 0, 0]);
+        }
+        if cx == 0 || cy == 0 || cx == CELL_PX - 1 || cy == CELL_PX - 1 {
+            return Rgba([20, 20, 30, 255]);
+        }
+        let shade = 0.6 + 0.4 * (slot % 8) as f32 / 7.0;
+        let [r, g, b] = base.map(|c| (c as f32 * shade) as u8);
+        Rgba([r, g, b, 255])
+    })
+}
+
+/// One looping clip per atlas over its first sprites.
+fn animation_file(atlases: &[GeneratedAtlas]) -> Value {
+    let animations: serde_json::Map<String, Value> = atlases
+        .iter()
+        .enumerate()
+        .map(|(index, atlas)| {
+            let frames: Vec<Value> = atlas.metadata.sprites[..FRAMES_PER_CLIP]
+                .iter()
+                .map(|sprite| json!({ "sprite_id": sprite.sprite_id, "duration_ms": FRAME_MS }))
+                .collect();
+            (
+                clip_name(index),
+                json!({ "frames": frames, "looping": true }),
+            )
+        })
+        .collect();
+    json!({
+        "version": "0.1",
+        "animation_id": ANIMATION_ID,
+        "animations": animations,
+    })
+}
+
+/// Walls, a floor and runs of platform every few rows.
+fn collision_file(rng: &mut DeterministicRng, cells: i32, origin: i32) -> Value {
+    let mut solids = Vec::new();
+    for x in 0..cells {
+        solids.push(json!({ "x": x, "y": 0 }));
+    }
+    for y in 1..cells {
+        solids.push(json!({ "x": 0, "y": y }));
+        solids.push(json!({ "x": cells - 1, "y": y }));
+    }
+    for y in (PLATFORM_EVERY..cells).step_by(PLATFORM_EVERY as usize) {
+        let mut x = 2;
+        while x < cells - 2 {
+            let run = 3 + (rng.next_u32() % 6) as i32;
+            let gap = 2 + (rng.next_u32() % 6) as i32;
+            for px in x..(x + run).min(cells - 2) {
+                if !(px < SPAWN_CLEARANCE && y < SPAWN_CLEARANCE) {
+                    solids.push(json!({ "x": px, "y": y }));
+                }
+            }
+            x += run + gap;
+        }
+    }
+    json!({
+        "version": "0.1",
+        "collision_id": "stress_collision",
+        "cell_size": COLLISION_CELL,
+        "origin": { "x": origin, "y": origin },
+        "width": cells,
+        "height": cells,
+        "solids": solids,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn config() -> StressConfig {
+        StressConfig {
+            sprites: 1_000,
+            layers: 3,
+            atlases: 3,
+            sprites_per_atlas: 16,
+            distribution: AtlasDistribution::Skewed,
+            animated: 0.5,
+            seed: 7,
+        }
+    }
+
+    fn layer_sprites(content: &GeneratedContent) -> Vec<&Value> {
+        content.scene["layers"]
+            .as_array()
+            .expect("layers")
+            .iter()
+            .flat_map(|layer| layer["sprites"].as_array().expect("sprites"))
+            .collect()
+    }
+
+    #[test]
+    fn generates_requested_counts_with_resolvable_references() {
+        let content = generate(&config(), Path::new("out")).expect("generate");
+        let sprites = layer_sprites(&content);
+        assert_eq!(sprites.len(), 1_000);
+        assert_eq!(content.scene["layers"].as_array().unwrap().len(), 3);
+        assert_eq!(content.atlases.len(), 3);
+        assert_eq!(content.scene_path, "out/stress_scene.json");
+
+        let sprite_ids: std::collections::HashSet<&str> = content
+            .atlases
+            .iter()
+            .flat_map(|atlas| &atlas.metadata.sprites)
+            .map(|sprite| sprite.sprite_id.as_str())
+            .collect();
+        assert_eq!(sprite_ids.len(), 48, "sprite ids are unique");
+        assert!(sprites
+            .iter()
+            .all(|sprite| sprite_ids.contains(sprite["sprite_id"].as_str().unwrap())));
+
+        let animated = sprites
+            .iter()
+            .filter(|sprite| sprite.get("animation").is_some())
+            .count();
+        assert_eq!(animated, content.animated_count);
+        assert!((400..600).contains(&animated), "about half: {animated}");
+        let clips = content.animation["animations"].as_object().unwrap();
+        assert!(sprites
+            .iter()
+            .filter_map(|sprite| sprite.get("animation"))
+            .all(|clip| clips.contains_key(clip.as_str().unwrap())));
+
+        let image = &content.atlases[0].image;
+        let texture = &content.atlases[0].metadata.texture;
+        assert_eq!(
+            (image.width(), image.height()),
+            (texture.width, texture.height)
+        );
+    }
+
+    #[test]
+    fn skewed_distribution_favors_first_atlas_and_output_is_seeded() {
+        let content = generate(&config(), Path::new("out")).expect("generate");
+        let first_atlas: std::collections::HashSet<&str> = content.atlases[0]
+            .metadata
+            .sprites
+            .iter()
+            .map(|sprite| sprite.sprite_id.as_str())
+            .collect();
+        let in_first = layer_sprites(&content)
+            .iter()
+            .filter(|sprite| first_atlas.contains(sprite["sprite_id"].as_str().unwrap()))
+            .count();
+        // Weights 1, 1/2, 1/3: the first atlas gets about 55%.
+        assert!((450..650).contains(&in_first), "{in_first}");
+
+        let again = generate(&config(), Path::new("out")).expect("generate");
+        assert_eq!(content.scene, again.scene);
+        assert_eq!(content.collision, again.collision);
+    }
+
+    #[test]
+    fn collision_covers_the_world_and_keeps_spawn_clear() {
+        let content = generate(&config(), Path::new("out")).expect("generate");
+        let collision = &content.collision;
+        let cells = collision["width"].as_i64().unwrap();
+        let origin = collision["origin"]["x"].as_i64().unwrap();
+        assert_eq!(origin, -(cells * COLLISION_CELL as i64) / 2);
+        let solid = |x: i64, y: i64| {
+            collision["solids"]
+                .as_array()
+                .unwrap()
+                .iter()
+                .any(|cell| cell["x"] == x && cell["y"] == y)
+        };
+        assert!(solid(cells / 2, 0), "floor");
+        for x in 1..SPAWN_CLEARANCE as i64 {
+            for y in 1..SPAWN_CLEARANCE as i64 {
+                assert!(!solid(x, y), "spawn corner cell ({x}, {y})");
+            }
+        }
+    }
+
+    #[test]
+    fn rejects_bad_config() {
+        let out = Path::new("out");
+        let zero_layers = StressConfig {
+            layers: 0,
+            ..config()
+        };
+        assert!(generate(&zero_layers, out).is_err());
+        let too_animated = StressConfig {
+            animated: 1.5,
+            ..config()
+        };
+        assert!(generate(&too_animated, out).is_err());
+        assert!(AtlasDistribution::parse("zipf").is_err());
+    }
+}
@@ -0,0 +1,98 @@
+//! Stress-test scene generator: writes a scene with a chosen number of
+//! sprites, layers and atlases, animation coverage and a matching collision
+//! grid, for profiling the engine and for reproducing performance reports.
+//!
+//! The same options and seed always produce the same files, so a report only
+//! needs the command line.
+
+mod generate;
+
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use generate::{generate, AtlasDistribution, GeneratedContent, StressConfig};
+
+fn usage() -> String {
+    "Usage: cargo run -p sme_stress -- [--sprites <n>] [--layers <n>] [--atlases <n>] [--sprites-per-atlas <n>] [--distribution even|skewed] [--animated <0..1>] [--seed <u64>] <output_dir>\nExample: cargo run -p sme_stress -- --sprites 50000 --layers 6 --atlases 8 --distribution skewed --animated 0.5 assets/stress\nThen: cargo run -p sme_game -- --scene assets/stress/stress_scene.json --collision assets/stress/stress_collision.json".to_string()
+}
+
+fn main() -> Result<(), String> {
+    let mut config = StressConfig::default();
+    let mut output = None;
+    let mut args = std::env::args().skip(1);
+    while let Some(arg) = args.next() {
+        if !arg.starts_with("--") {
+            if output.replace(PathBuf::from(&arg)).is_some() {
+                return Err(format!("Unexpected argument '{arg}'\n{}", usage()));
+            }
+            continue;
+        }
+        let value = args
+            .next()
+            .ok_or_else(|| format!("{arg} needs a value\n{}", usage()))?;
+        match arg.as_str() {
+            "--sprites" => config.sprites = parse_count(&arg, &value)?,
+            "--layers" => config.layers = parse_count(&arg, &value)?,
+            "--atlases" => config.atlases = parse_count(&arg, &value)?,
+            "--sprites-per-atlas" => config.sprites_per_atlas = parse_count(&arg, &value)?,
+            "--distribution" => config.distribution = AtlasDistribution::parse(&value)?,
+            "--animated" => {
+                config.animated = value
+                    .parse()
+                    .map_err(|e| format!("Invalid {arg} '{value}': {e}"))?
+            }
+            "--seed" => {
+                config.seed = value
+                    .parse()
+                    .map_err(|e| format!("Invalid {arg} '{value}': {e}"))?
+            }
+            _ => return Err(format!("Unknown flag '{arg}'\n{}", usage())),
+        }
+    }
+    let output = output.ok_or_else(usage)?;
+
+    let content = generate(&config, &output)?;
+    write_content(&content, &output)?;
+    println!(
+        "Wrote {} sprites in {} layers over {} atlases ({} animated) to {}",
+        content.sprite_count,
+        config.layers,
+        content.atlases.len(),
+        content.animated_count,
+        output.display()
+    );
+    println!(
+        "Run it with: cargo run -p sme_game -- --scene {} --collision {}",
+        content.scene_path, content.collision_path
+    );
+    Ok(())
+}
+
+fn parse_count(flag: &str, value: &str) -> Result<usize, String> {
+    match value.parse::<usize>() {
+        Ok(0) => Err(format!("{flag} must be at least 1")),
+        Ok(count) => Ok(count),
+        Err(e) => Err(format!("Invalid {flag} '{value}': {e}")),
+    }
+}
+
+fn write_content(content: &GeneratedContent, output: &Path) -> Result<(), String> {
+    fs::create_dir_all(output)
+        .map_err(|e| format!("Failed to create '{}': {e}", output.display()))?;
+    for atlas in &content.atlases {
+        atlas
+            .image
+            .save(&atlas.png_path)
+            .map_err(|e| format!("Failed to write '{}': {e}", atlas.png_path))?;
+        write_json(&atlas.json_path, &atlas.metadata)?;
+    }
+    write_json(&content.animation_path, &content.animation)?;
+    write_json(&content.collision_path, &content.collision)?;
+    write_json(&content.scene_path, &content.scene)
+}
+
+fn write_json(path: &str, value: &impl serde::Serialize) -> Result<(), String> {
+    let json = serde_json::to_string_pretty(value)
+        .map_err(|e| format!("Failed to serialize '{path}': {e}"))?;
+    fs::write(path, json).map_err(|e| format!("Failed to write '{path}': {e}"))
+}
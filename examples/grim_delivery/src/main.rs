@@ -25,7 +25,7 @@ use hud::GameHud;
 use sme_core::input::{InputState, Key};
 use sme_core::time::TimeState;
 use sme_platform::window::PlatformConfig;
-use sme_render::{Camera2D, GpuContext, SpritePipeline, SpriteVertex, Texture};
+use sme_render::{Camera2D, GpuContext, SpriteEffect, SpritePipeline, SpriteVertex, Texture};
 
 const CAMERA_ZOOM: f32 = 1.4;
 /// Camera x bias: keeps the street center-right so the house row stays on screen.
@@ -75,7 +75,7 @@ impl GameApp {
             1,
             "white",
         );
-        let white_bind_group = sprite_pipeline.create_texture_bind_group(&gpu.device, &white);
+        let white_bind_group = sprite_pipeline.create_texture_bind_group(&gpu.device, &white, None);
 
         let mut camera = Camera2D::new(gpu.size.0, gpu.size.1);
         camera.zoom = CAMERA_ZOOM;
@@ -151,6 +151,8 @@ impl GameApp {
                     position: *pos,
                     tex_coords: *uv,
                     color: quad.color,
+                    effect: SpriteEffect::None.vertex_data(),
+                    tex_coords2: [0.0, 0.0],
                 });
             }
             indices.extend_from_slice(&[base, base + 1, base + 2, base, base + 2, base + 3]);
@@ -427,7 +429,7 @@ fn create_index_buffer(device: &wgpu::Device, index_capacity: usize) -> wgpu::Bu
 
 fn main() {
     #[cfg(not(target_arch = "wasm32"))]
-    env_logger::Builder::from_env(env_logger::Env::default().default_filter_or("info")).init();
+    sme_core::logging::init_from_file(std::path::Path::new(sme_core::logging::LOG_CONFIG_PATH));
     #[cfg(target_arch = "wasm32")]
     {
         std::panic::set_hook(Box::new(console_error_panic_hook::hook));